@@ -21,6 +21,7 @@
 //
 
 use empires::resource::ResourceType;
+use empires::unit::Unit;
 use error::Result;
 
 use identifier::{UnitClassId, AgeId, UnitId, ResearchId};
@@ -128,6 +129,26 @@ pub enum ResearchEffect {
     },
 }
 
+impl ResearchEffect {
+    /// Whether the effect changes the unit: its attributes, whether it's available, or what it
+    /// upgrades to (or from)
+    pub fn affects(&self, unit: &Unit) -> bool {
+        match *self {
+            ResearchEffect::UnitAttribute { target_unit_id, target_unit_class_id, .. } => {
+                target_unit_id == Some(unit.id) ||
+                target_unit_class_id.map_or(false, |class_id| {
+                    unit.class_id() >= 0 && *class_id == unit.class_id() as u32
+                })
+            }
+            ResearchEffect::SetUnitEnabled { target_unit_id, .. } => target_unit_id == Some(unit.id),
+            ResearchEffect::UpgradeUnit { source_unit_id, target_unit_id } => {
+                source_unit_id == unit.id || target_unit_id == unit.id
+            }
+            _ => false,
+        }
+    }
+}
+
 impl Default for ResearchEffect {
     fn default() -> ResearchEffect {
         ResearchEffect::Unknown {
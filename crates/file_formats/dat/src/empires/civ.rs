@@ -47,7 +47,7 @@ pub struct CivilizationStartingValues {
     /// Multiplier for gold mined; increases with research
     gold_mine_productivity: f32,
 
-    /// Used to initialize unit attributes for the civ
+    /// Used to initialize unit attributes for the civ, and to disable what it can't research
     age_id: Option<AgeId>,
 
     /// If not starting in the default age, grant the given tech based on what the starting age is
@@ -73,11 +73,62 @@ pub struct Civilization {
 }
 
 impl Civilization {
+    pub fn id(&self) -> CivilizationId {
+        self.id
+    }
+
+    pub fn name<'a>(&'a self) -> &'a str {
+        &self.name
+    }
+
+    /// The effects that every player of this civilization starts with, which is what makes its
+    /// tech tree different from the others
+    pub fn tech_tree_id(&self) -> Option<AgeId> {
+        self.starting_values.age_id
+    }
+
+    /// Every unit this civilization has, ordered by ID
+    pub fn units<'a>(&'a self) -> Vec<&'a Unit> {
+        let mut units: Vec<&Unit> = self.units.values().collect();
+        units.sort_by_key(|unit| *unit.id);
+        units
+    }
+
+    /// Every enabled unit that can be trained at the given building, ordered by ID
+    pub fn units_trained_at<'a>(&'a self, building_id: UnitId) -> Vec<&'a Unit> {
+        self.units()
+            .into_iter()
+            .filter(|unit| unit.enabled && unit.train_location_id() == Some(building_id))
+            .collect()
+    }
+
+    /// The unit that gathers from the target for the given unit: the unit itself if it can, or
+    /// whatever it turns into from its task swap group (a villager becomes a fisherman for fish)
+    pub fn gatherer_for<'a>(&'a self, unit: &'a Unit, target: &Unit) -> Option<&'a Unit> {
+        if unit.gather_command(target).is_some() {
+            return Some(unit);
+        }
+        if unit.task_swap_id() <= 0 {
+            return None;
+        }
+        self.units()
+            .into_iter()
+            .find(|other| {
+                other.task_swap_id() == unit.task_swap_id() && other.gather_command(target).is_some()
+            })
+    }
+
     /// Retrieve unit data by ID
     #[inline]
     pub fn unit<'a>(&'a self, unit_id: UnitId) -> &'a Unit {
         return &self.units[&unit_id];
     }
+
+    /// Retrieve unit data by ID if this civilization has such a unit
+    #[inline]
+    pub fn find_unit<'a>(&'a self, unit_id: UnitId) -> Option<&'a Unit> {
+        self.units.get(&unit_id)
+    }
 }
 
 pub fn read_civs<R: Read + Seek>(stream: &mut R) -> Result<Vec<Civilization>> {
@@ -35,22 +35,27 @@ mod terrain_restrictions;
 mod unit;
 
 
-use empires::age::{ResearchEffectGroup, read_ages};
+pub use empires::age::{ResearchEffect, ResearchEffectGroup, ResearchEffectValue, UnitAttributeId};
+use empires::age::read_ages;
 pub use empires::civ::Civilization;
 use empires::civ::read_civs;
 pub use empires::graphic::Graphic;
 use empires::graphic::read_graphics;
 use empires::player_color::{PlayerColor, read_player_colors};
 use empires::random_map::{RandomMap, read_random_maps};
-use empires::research::{Research, read_research};
-use empires::sound::{SoundEffectGroup, read_sound_effect_groups};
+use empires::research::read_research;
+use empires::sound::read_sound_effect_groups;
 pub use empires::terrain_block::Terrain;
+pub use empires::terrain_block::TerrainAnimation;
 
 pub use empires::terrain_block::TerrainBlock;
 pub use empires::terrain_block::TerrainBorder;
 use empires::terrain_block::read_terrain_block;
 use empires::terrain_restrictions::{TerrainRestriction, read_terrain_restrictions};
-pub use empires::unit::{InteractionMode, Unit};
+pub use empires::research::Research;
+pub use empires::resource::ResourceType;
+pub use empires::sound::{SoundEffect, SoundEffectGroup};
+pub use empires::unit::{BattleParams, InteractionMode, Unit, UnitCommand};
 use error::{Result, ErrorKind};
 
 use identifier::{UnitTerrainRestrictionId, TerrainId, CivilizationId, ResearchId, TerrainBorderId, PlayerColorId, UnitId, GraphicId, SoundGroupId, AgeId};
@@ -77,6 +82,9 @@ pub struct EmpiresDb {
     ages: Vec<ResearchEffectGroup>,
     civilizations: Vec<Civilization>,
     research: Vec<Research>,
+
+    /// Sections that were cut off the end of the file (the demo's empires.dat is incomplete)
+    missing_sections: Vec<&'static str>,
 }
 
 pub type EmpiresDbRef = Arc<EmpiresDb>;
@@ -116,12 +124,48 @@ impl EmpiresDb {
         self.civilization(civilization_id).unit(unit_id)
     }
 
+    /// Unit information if the data has the given civilization and unit; scenarios made for the full
+    /// game can use units that the demo's data doesn't have
+    pub fn find_unit<'a>(&'a self, civilization_id: CivilizationId, unit_id: UnitId) -> Option<&'a Unit> {
+        if *civilization_id == 0 {
+            return None;
+        }
+        self.civilizations.get((*civilization_id - 1) as usize).and_then(|civ| civ.find_unit(unit_id))
+    }
+
+    /// Every unit the civilization has, ordered by ID
+    pub fn units<'a>(&'a self, civilization_id: CivilizationId) -> Vec<&'a Unit> {
+        self.civilization(civilization_id).units()
+    }
+
+    /// Every enabled unit the civilization can train at the building, ordered by ID
+    pub fn units_trained_at<'a>(&'a self,
+                                civilization_id: CivilizationId,
+                                building_id: UnitId)
+                                -> Vec<&'a Unit> {
+        self.civilization(civilization_id).units_trained_at(building_id)
+    }
+
+    /// False if the file ended early and some sections are missing (see `missing_sections`)
+    pub fn is_complete(&self) -> bool {
+        self.missing_sections.is_empty()
+    }
+
+    pub fn missing_sections(&self) -> &[&'static str] {
+        &self.missing_sections
+    }
+
     /// Retrieve the terrain information
     #[inline]
     pub fn terrain_block<'a>(&'a self) -> &'a TerrainBlock {
         &self.terrain_block
     }
 
+    #[inline]
+    pub fn terrains<'a>(&'a self) -> &'a [Terrain] {
+        self.terrain_block().terrains()
+    }
+
     /// Convenience that returns terrain by ID
     #[inline]
     pub fn terrain<'a>(&'a self, terrain_id: TerrainId) -> &'a Terrain {
@@ -154,6 +198,80 @@ impl EmpiresDb {
         &self.research[*research_id as usize]
     }
 
+    #[inline]
+    pub fn civilizations<'a>(&'a self) -> &'a [Civilization] {
+        &self.civilizations
+    }
+
+    #[inline]
+    pub fn graphics<'a>(&'a self) -> &'a [Graphic] {
+        &self.graphics
+    }
+
+    #[inline]
+    pub fn researches<'a>(&'a self) -> &'a [Research] {
+        &self.research
+    }
+
+    #[inline]
+    pub fn sound_effect_groups<'a>(&'a self) -> &'a [SoundEffectGroup] {
+        &self.sound_effect_groups
+    }
+
+    /// Research information if the data has it (the demo's empires.dat has no research)
+    pub fn find_research<'a>(&'a self, research_id: ResearchId) -> Option<&'a Research> {
+        self.research.get(*research_id as usize)
+    }
+
+    /// The effects that researching something applies, if it has any
+    pub fn research_effects<'a>(&'a self, research_id: ResearchId) -> Option<&'a ResearchEffectGroup> {
+        self.find_research(research_id)
+            .and_then(|research| research.age_id)
+            .and_then(|effects_id| self.ages.get(*effects_id as usize))
+    }
+
+    /// False if the civilization's tech tree disables the research. Research that requires a
+    /// disabled research can't be done either, but it isn't disabled itself.
+    pub fn is_research_available(&self, civilization_id: CivilizationId, research_id: ResearchId) -> bool {
+        self.civilization(civilization_id)
+            .tech_tree_id()
+            .and_then(|tech_tree_id| self.ages.get(*tech_tree_id as usize))
+            .map(|tech_tree| {
+                !tech_tree.effects.iter().any(|effect| match *effect {
+                    ResearchEffect::DisableResearch { research_id: disabled_id } => {
+                        disabled_id == research_id
+                    }
+                    _ => false,
+                })
+            })
+            .unwrap_or(true)
+    }
+
+    /// Every research done at the building, ordered by ID
+    pub fn researched_at<'a>(&'a self, building_id: UnitId) -> Vec<&'a Research> {
+        self.research.iter().filter(|research| research.location == Some(building_id)).collect()
+    }
+
+    /// Every research with an effect on the unit (see `ResearchEffect::affects`), ordered by ID
+    pub fn researches_affecting<'a>(&'a self, unit: &Unit) -> Vec<&'a Research> {
+        self.research
+            .iter()
+            .filter(|research| {
+                self.research_effects(research.id)
+                    .map(|effects| effects.effects.iter().any(|effect| effect.affects(unit)))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Every research that has this one as a requirement, ordered by ID
+    pub fn researches_requiring<'a>(&'a self, research_id: ResearchId) -> Vec<&'a Research> {
+        self.research
+            .iter()
+            .filter(|research| research.required_research_ids().contains(&research_id))
+            .collect()
+    }
+
     /// Retrieve a sound effect group by ID
     #[inline]
     pub fn sound_effect_group<'a>(&'a self, sound_group_id: SoundGroupId) -> &'a SoundEffectGroup {
@@ -162,8 +280,13 @@ impl EmpiresDb {
 
     /// Read all of the game data from the empires.dat file specified
     pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> Result<EmpiresDb> {
-        let file = try!(File::open(file_name.as_ref()));
-        let mut stream = io::Cursor::new(try!(file.read_and_decompress()));
+        EmpiresDb::read_from_stream(try!(File::open(file_name.as_ref())))
+    }
+
+    /// Read all of the game data from the contents of an empires.dat file, for when it didn't come
+    /// from the file system (such as a file picked in a browser)
+    pub fn read_from_stream<S: Read>(mut stream: S) -> Result<EmpiresDb> {
+        let mut stream = io::Cursor::new(try!(stream.read_and_decompress()));
 
         try!(read_header(&mut stream));
         let terrain_restriction_count = try!(stream.read_u16()) as usize;
@@ -180,12 +303,35 @@ impl EmpiresDb {
         db.random_maps = try!(read_random_maps(&mut stream));
         db.ages = try!(read_ages(&mut stream));
         db.civilizations = try!(read_civs(&mut stream));
-        db.research = try!(read_research(&mut stream));
+
+        // Everything after the civilizations is optional, since the game is playable without it
+        db.research = try!(optional_section(read_research(&mut stream),
+                                            "research",
+                                            &mut db.missing_sections));
 
         Ok(db)
     }
 }
 
+/// Treats running out of file as the section (and any after it) being absent, rather than an error
+fn optional_section<T>(result: Result<Vec<T>>,
+                       name: &'static str,
+                       missing_sections: &mut Vec<&'static str>)
+                       -> Result<Vec<T>> {
+    match result {
+        Err(err) => {
+            match *err.kind() {
+                ErrorKind::IoError(ref io_err) if io_err.kind() == io::ErrorKind::UnexpectedEof => {
+                    missing_sections.push(name);
+                    Ok(Vec::new())
+                }
+                _ => Err(err),
+            }
+        }
+        result => result,
+    }
+}
+
 fn read_header<R: Read + Seek>(stream: &mut R) -> Result<()> {
     let mut version = [0u8; 8];
     try!(stream.read_exact(&mut version));
@@ -19,11 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use empires::resource::{ResourceCost, ReadResourceCost};
+use empires::resource::{ResourceCost, ResourceType, ReadResourceCost};
 
 use error::{Result, ErrorKind};
 
 use identifier::{LocalizationId, AgeId, UnitId, ResearchId};
+use language::Language;
 use chariot_io_tools::{ReadExt, ReadArrayExt};
 
 use std::io::prelude::{Seek, Read};
@@ -61,6 +62,31 @@ pub struct Research {
     pub name: String,
 }
 
+impl Research {
+    /// The research that has to be done before this one
+    pub fn required_research_ids(&self) -> Vec<ResearchId> {
+        self.required_techs
+            .iter()
+            .filter(|&&research_id| research_id >= 0)
+            .map(|&research_id| (research_id as usize).into())
+            .collect()
+    }
+
+    /// What the research costs to do, leaving out the unused cost slots
+    pub fn costs(&self) -> Vec<(ResourceType, i16)> {
+        self.resource_costs
+            .iter()
+            .filter(|cost| cost.enabled && cost.amount > 0)
+            .map(|cost| (cost.resource_type, cost.amount))
+            .collect()
+    }
+
+    /// The name the player sees, or the internal name if language.dll doesn't have one
+    pub fn display_name<'a>(&'a self, language: &'a Language) -> &'a str {
+        language.string(*self.name_id as usize).unwrap_or(&self.name)
+    }
+}
+
 pub fn read_research<R: Read + Seek>(stream: &mut R) -> Result<Vec<Research>> {
     let research_count = try!(stream.read_u16()) as usize;
     let mut research = try!(stream.read_array(research_count, |c| read_single_research(c)));
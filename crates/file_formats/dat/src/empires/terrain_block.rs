@@ -38,6 +38,40 @@ pub struct TerrainFrameData {
     pub frame_id: SlpFrameId,
 }
 
+/// How a terrain or border cycles through its frames, such as water rippling
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TerrainAnimation {
+    pub frame_count: u16,
+
+    /// Frames to hold the last frame for before the loop starts over
+    pub pause_frames: u16,
+
+    /// Seconds between frames
+    pub frame_interval: f32,
+
+    /// Seconds to hold the last frame for (after the pause frames) before the loop starts over
+    pub pause_between_loops: f32,
+}
+
+impl TerrainAnimation {
+    fn from_fields(animated: bool,
+                   animation_frames: i16,
+                   pause_frames: i16,
+                   frame_interval: f32,
+                   pause_between_loops: f32)
+                   -> Option<TerrainAnimation> {
+        if !animated || animation_frames <= 1 || frame_interval <= 0f32 {
+            return None;
+        }
+        Some(TerrainAnimation {
+            frame_count: animation_frames as u16,
+            pause_frames: if pause_frames > 0 { pause_frames as u16 } else { 0 },
+            frame_interval: frame_interval,
+            pause_between_loops: if pause_between_loops > 0f32 { pause_between_loops } else { 0f32 },
+        })
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct TerrainBorder {
     pub id: TerrainBorderId,
@@ -64,6 +98,17 @@ pub struct TerrainBorder {
     pub borders: Vec<Vec<TerrainFrameData>>,
 }
 
+impl TerrainBorder {
+    /// How the border animates (shorelines lapping at the land, for example), if it does
+    pub fn animation(&self) -> Option<TerrainAnimation> {
+        TerrainAnimation::from_fields(self.animated,
+                                      self.animation_frames,
+                                      self.pause_frames,
+                                      self.frame_interval,
+                                      self.pause_between_loops)
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct TerrainUnit {
     unit_id: UnitId,
@@ -114,6 +159,34 @@ pub struct Terrain {
 }
 
 impl Terrain {
+    pub fn name<'a>(&'a self) -> &'a str {
+        &self.name
+    }
+
+    /// Whether the terrain is used at all; unused slots in the terrain table are disabled
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Ambient sound that loops while the terrain fills the screen (waves for water, for example)
+    pub fn sound_group_id(&self) -> Option<SoundGroupId> {
+        self.sound_group_id
+    }
+
+    /// How the terrain animates (water rippling, for example), if it does
+    pub fn animation(&self) -> Option<TerrainAnimation> {
+        TerrainAnimation::from_fields(self.animated,
+                                      self.animation_frames,
+                                      self.pause_frames,
+                                      self.frame_interval,
+                                      self.pause_between_loops)
+    }
+
+    /// Palette index the minimap draws the terrain in
+    pub fn minimap_color(&self) -> u8 {
+        self.colors[0]
+    }
+
     /// Returns the terrain border ID for a given terrain ID
     #[inline]
     pub fn terrain_border<'a>(&'a self, terrain_id: TerrainId) -> TerrainBorderId {
@@ -174,6 +247,11 @@ impl TerrainBlock {
     pub fn terrain_border<'a>(&'a self, terrain_border_id: TerrainBorderId) -> &'a TerrainBorder {
         &self.terrain_borders[*terrain_border_id as usize]
     }
+
+    #[inline]
+    pub fn terrains<'a>(&'a self) -> &'a [Terrain] {
+        &self.terrains
+    }
 }
 
 pub fn read_terrain_block<R: Read + Seek>(stream: &mut R) -> Result<TerrainBlock> {
@@ -20,17 +20,24 @@
 // SOFTWARE.
 //
 
-use empires::resource::{ResourceCost, ReadResourceCost};
+use empires::resource::{ResourceCost, ReadResourceCost, ResourceType};
 use error::{Result, ErrorKind};
 
 use identifier::{LocalizationId, UnitCommandId, SoundGroupId, GraphicId, TerrainId, UnitId, ResearchId, UnitTerrainRestrictionId};
 use chariot_io_tools::{ReadExt, ReadArrayExt};
+use language::Language;
 
 use std::io::prelude::{Seek, Read};
 
 type UnitResourceStorage = ResourceCost<f32, u8>;
 type UnitResourceCost = ResourceCost<i16, i16>;
 
+/// Command type of the commands that villagers and fishing boats gather resources with
+const GATHER_COMMAND_TYPE: i16 = 5;
+
+/// Command type of the command that villagers put up buildings with
+const BUILD_COMMAND_TYPE: i16 = 101;
+
 #[derive(Default, Debug)]
 pub struct DamageGraphic {
     graphic_id: GraphicId,
@@ -182,6 +189,40 @@ pub struct UnitCommand {
     resource_deposit_sound_id: Option<SoundGroupId>,
 }
 
+impl UnitCommand {
+    pub fn is_gather(&self) -> bool {
+        self.enabled && self.type_id == GATHER_COMMAND_TYPE
+    }
+
+    pub fn is_build(&self) -> bool {
+        self.enabled && self.type_id == BUILD_COMMAND_TYPE
+    }
+
+    /// Whether the command can be used on the given unit; commands name either a specific unit
+    /// or a whole class of them
+    pub fn targets(&self, unit: &Unit) -> bool {
+        match self.unit_id {
+            Some(unit_id) => unit_id == unit.id,
+            None => self.class_id >= 0 && self.class_id == unit.class_id,
+        }
+    }
+
+    /// The resource the command takes from its target
+    pub fn resource_type(&self) -> ResourceType {
+        ResourceType::from_i16(self.resource_in)
+    }
+
+    /// Graphic for while the command is being carried out (chopping, mining, casting a net)
+    pub fn action_graphic_id(&self) -> Option<GraphicId> {
+        self.action_graphic_id
+    }
+
+    /// Graphic for walking around with what the command got
+    pub fn carrying_graphic_id(&self) -> Option<GraphicId> {
+        self.carrying_graphic_id
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct MotionParams {
     pub speed: f32,
@@ -199,28 +240,28 @@ pub struct CommandableParams {
     work_rate: f32,
     drop_sites: [i16; 2],
     task_swap_id: i8,
-    attack_sound: i16,
-    move_sound: i16,
+    attack_sound_id: Option<SoundGroupId>,
+    move_sound_id: Option<SoundGroupId>,
     animal_mode: i8,
     commands: Vec<UnitCommand>,
 }
 
 #[derive(Default, Debug)]
 pub struct BattleParams {
-    default_armor: u8,
-    attacks: Vec<(i16, i16)>, // class, amount
-    armors: Vec<(i16, i16)>, // class, amount
+    pub default_armor: u8,
+    pub attacks: Vec<(i16, i16)>, // class, amount
+    pub armors: Vec<(i16, i16)>, // class, amount
     terrain_restriction_for_damage_multiplier: i16,
-    max_range: f32,
-    blast_width: f32,
-    reload_time: f32,
-    projectile_unit_id: Option<UnitId>,
+    pub max_range: f32,
+    pub blast_width: f32,
+    pub reload_time: f32,
+    pub projectile_unit_id: Option<UnitId>,
     accuracy_percent: i16,
     tower_mode: i8,
     frame_delay: i16,
     graphic_displacements: [f32; 3],
-    blast_attack_level: i8,
-    min_range: f32,
+    pub blast_attack_level: i8,
+    pub min_range: f32,
     attack_graphic_id: Option<GraphicId>,
     displayed_melee_armour: i16,
     displayed_attack: i16,
@@ -279,8 +320,8 @@ pub struct Unit {
     /// Always zero; use unknown
     death_mode: i8,
 
-    hit_points: i16,
-    line_of_sight: f32,
+    pub hit_points: i16,
+    pub line_of_sight: f32,
     garrison_capability: i8,
     pub collision_size_x: f32,
     pub collision_size_y: f32,
@@ -290,7 +331,7 @@ pub struct Unit {
     train_sound_id: Option<SoundGroupId>,
 
     /// Replacement unit id for when the unit is dead and dying animation is completed
-    dead_unit_id: Option<UnitId>,
+    pub dead_unit_id: Option<UnitId>,
 
     /// 0 = unit can be placed on other units in the map editor, 5 = it can't
     placement_mode: i8,
@@ -327,7 +368,7 @@ pub struct Unit {
     resource_decay: f32,
 
     /// Unit would only be affected by a blast attack with the same or lower level
-    blast_defense_level: i8,
+    pub blast_defense_level: i8,
 
     sub_type: i8,
     pub interaction_mode: InteractionMode,
@@ -351,20 +392,166 @@ pub struct Unit {
     resource_storage: Vec<UnitResourceStorage>,
     damage_graphics: Vec<DamageGraphic>,
 
-    selection_sound: i16,
+    selection_sound_id: Option<SoundGroupId>,
     dying_sound: i16,
     attack_mode: i8,
 
+    /// Whether the unit changes over to the first player who finds it while it belongs to gaia
+    /// (abandoned buildings in scenarios, for example)
+    converts_on_discovery: bool,
+
     id2: i16,
 
     pub motion_params: Option<MotionParams>,
     commandable_params: Option<CommandableParams>,
-    battle_params: Option<BattleParams>,
+    pub battle_params: Option<BattleParams>,
     projectile_params: Option<ProjectileParams>,
     trainable_params: Option<TrainableParams>,
     building_params: Option<BuildingParams>,
 }
 
+impl Unit {
+    /// Internal name (the localized name comes from language.dll through `name_id`)
+    pub fn name<'a>(&'a self) -> &'a str {
+        &self.name
+    }
+
+    pub fn name_id(&self) -> Option<LocalizationId> {
+        self.name_id
+    }
+
+    /// The name the player sees, or the internal name if language.dll doesn't have one
+    pub fn display_name<'a>(&'a self, language: &'a Language) -> &'a str {
+        self.name_id
+            .and_then(|name_id| language.string(*name_id as usize))
+            .unwrap_or(&self.name)
+    }
+
+    pub fn is_building(&self) -> bool {
+        self.building_params.is_some()
+    }
+
+    pub fn class_id(&self) -> i16 {
+        self.class_id
+    }
+
+    /// How many units fit inside (0 for most units; the original game doesn't use it)
+    pub fn garrison_capability(&self) -> i8 {
+        self.garrison_capability
+    }
+
+    pub fn converts_on_discovery(&self) -> bool {
+        self.converts_on_discovery
+    }
+
+    /// The building the unit is trained at, if it can be trained
+    pub fn train_location_id(&self) -> Option<UnitId> {
+        self.trainable_params.as_ref().and_then(|params| params.train_location_id)
+    }
+
+    /// Seconds it takes to train the unit, if it can be trained
+    pub fn train_time(&self) -> Option<i16> {
+        self.trainable_params.as_ref().map(|params| params.train_time)
+    }
+
+    /// What it takes to train the unit (or put up the building), leaving out the unused cost slots
+    pub fn costs(&self) -> Vec<(ResourceType, i16)> {
+        match self.trainable_params {
+            Some(ref params) => {
+                params.resource_costs
+                    .iter()
+                    .filter(|cost| cost.enabled && cost.amount > 0)
+                    .map(|cost| (cost.resource_type, cost.amount))
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// What the unit says when it's selected. Each civilization has its own copy of every unit,
+    /// so this is the voice of the unit's civilization.
+    pub fn selection_sound_id(&self) -> Option<SoundGroupId> {
+        self.selection_sound_id
+    }
+
+    /// What the unit says when it's ordered to move (or to do anything other than attack)
+    pub fn move_sound_id(&self) -> Option<SoundGroupId> {
+        self.commandable_params.as_ref().and_then(|params| params.move_sound_id)
+    }
+
+    /// What the unit says when it's ordered to attack
+    pub fn attack_sound_id(&self) -> Option<SoundGroupId> {
+        self.commandable_params.as_ref().and_then(|params| params.attack_sound_id)
+    }
+
+    pub fn commands(&self) -> &[UnitCommand] {
+        match self.commandable_params {
+            Some(ref params) => &params.commands,
+            None => &[],
+        }
+    }
+
+    /// The command the unit gathers from the target with, if it can
+    pub fn gather_command<'a>(&'a self, target: &Unit) -> Option<&'a UnitCommand> {
+        self.commands().iter().find(|command| command.is_gather() && command.targets(target))
+    }
+
+    /// The command the unit puts up buildings with, if it can
+    pub fn build_command<'a>(&'a self) -> Option<&'a UnitCommand> {
+        self.commands().iter().find(|command| command.is_build())
+    }
+
+    /// What a building looks like while it's still being built
+    pub fn construction_graphic_id(&self) -> Option<GraphicId> {
+        self.building_params.as_ref().map(|params| params.construction_graphic_id)
+    }
+
+    /// Units with the same (positive) task swap group turn into each other depending on what
+    /// they're doing; villagers become fishermen, foragers, lumberjacks, and so on
+    pub fn task_swap_id(&self) -> i8 {
+        self.commandable_params.as_ref().map(|params| params.task_swap_id).unwrap_or(0)
+    }
+
+    /// The buildings the unit can drop off what it gathered at
+    pub fn drop_site_ids(&self) -> Vec<UnitId> {
+        match self.commandable_params {
+            Some(ref params) => {
+                params.drop_sites.iter().filter(|&&id| id >= 0).map(|&id| (id as usize).into()).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Resources gathered per second
+    pub fn work_rate(&self) -> f32 {
+        self.commandable_params.as_ref().map(|params| params.work_rate).unwrap_or(0f32)
+    }
+
+    /// How much the unit can carry before it has to go to a drop site
+    pub fn resource_capacity(&self) -> i16 {
+        self.resource_capacity
+    }
+
+    /// How much of what it holds a resource loses every second; carcasses rot, the rest keep
+    pub fn resource_decay(&self) -> f32 {
+        self.resource_decay
+    }
+
+    /// What a resource (tree, mine, bush, or fish) holds when it's placed
+    pub fn stored_resource(&self) -> Option<(ResourceType, f32)> {
+        self.resource_storage
+            .iter()
+            .find(|storage| {
+                storage.amount > 0f32 &&
+                match storage.resource_type {
+                    ResourceType::Unknown(_) => false,
+                    _ => true,
+                }
+            })
+            .map(|storage| (storage.resource_type, storage.amount))
+    }
+}
+
 pub fn read_unit<R: Read + Seek>(stream: &mut R) -> Result<Unit> {
     let mut unit: Unit = Default::default();
 
@@ -431,10 +618,10 @@ pub fn read_unit<R: Read + Seek>(stream: &mut R) -> Result<Unit> {
     let damage_graphic_count = try!(stream.read_u8()) as usize;
     unit.damage_graphics = try!(stream.read_array(damage_graphic_count, |c| read_damage_graphic(c)));
 
-    unit.selection_sound = try!(stream.read_i16());
+    unit.selection_sound_id = optional_id!(try!(stream.read_i16()));
     unit.dying_sound = try!(stream.read_i16());
     unit.attack_mode = try!(stream.read_i8());
-    try!(stream.read_u8()); // Unknown
+    unit.converts_on_discovery = try!(stream.read_u8()) != 0;
 
     unit.name = try!(stream.read_sized_str(name_length));
     unit.id2 = try!(stream.read_i16());
@@ -505,8 +692,8 @@ fn read_commandable_params<R: Read>(stream: &mut R) -> Result<CommandableParams>
     params.drop_sites[0] = try!(stream.read_i16());
     params.drop_sites[1] = try!(stream.read_i16());
     params.task_swap_id = try!(stream.read_i8());
-    params.attack_sound = try!(stream.read_i16());
-    params.move_sound = try!(stream.read_i16());
+    params.attack_sound_id = optional_id!(try!(stream.read_i16()));
+    params.move_sound_id = optional_id!(try!(stream.read_i16()));
     params.animal_mode = try!(stream.read_i8());
 
     let command_count = try!(stream.read_u16()) as usize;
@@ -27,16 +27,22 @@ extern crate error_chain;
 extern crate chariot_identifier as identifier;
 
 extern crate chariot_io_tools;
+extern crate chariot_language as language;
 
 mod empires;
 mod tileedge;
 mod error;
 
 pub use empires::{EmpiresDb, EmpiresDbRef};
-pub use empires::{InteractionMode, Unit};
+pub use empires::{BattleParams, InteractionMode, Unit, UnitCommand};
 pub use empires::Civilization;
 pub use empires::Graphic;
+pub use empires::Research;
+pub use empires::ResourceType;
+pub use empires::{ResearchEffect, ResearchEffectGroup, ResearchEffectValue, UnitAttributeId};
+pub use empires::{SoundEffect, SoundEffectGroup};
 pub use empires::Terrain;
+pub use empires::TerrainAnimation;
 pub use empires::TerrainBlock;
 pub use empires::TerrainBorder;
 
@@ -49,11 +49,14 @@ impl TileEdgeDb {
     }
 
     pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> Result<TileEdgeDb> {
-        let mut file = try!(File::open(file_name.as_ref()));
-        try!(file.seek(SeekFrom::Start(444)));
+        TileEdgeDb::read_from_stream(try!(File::open(file_name.as_ref())))
+    }
+
+    pub fn read_from_stream<S: Read + Seek>(mut stream: S) -> Result<TileEdgeDb> {
+        try!(stream.seek(SeekFrom::Start(444)));
 
         let mut bytes = Vec::new();
-        try!(file.read_to_end(&mut bytes));
+        try!(stream.read_to_end(&mut bytes));
 
         let mut db = TileEdgeDb::new();
 
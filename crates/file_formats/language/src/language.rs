@@ -39,6 +39,11 @@ impl Language {
         Language { strings: BTreeMap::new() }
     }
 
+    /// The string with the given ID, if there is one
+    pub fn string<'a>(&'a self, string_id: usize) -> Option<&'a str> {
+        self.strings.get(&string_id).map(|string| string.as_str())
+    }
+
     pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> Result<Language> {
         let file_name = file_name.as_ref();
         let mut file = try!(File::open(file_name));
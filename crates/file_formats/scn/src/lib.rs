@@ -29,6 +29,7 @@ extern crate error_chain;
 extern crate chariot_identifier as identifier;
 
 extern crate chariot_io_tools;
+extern crate flate2;
 
 mod error;
 mod scn;
@@ -36,11 +37,14 @@ mod player_data;
 mod player_resources;
 mod player_unit;
 mod map;
+mod write_ext;
 
 pub use error::ChainErr;
 pub use error::Error;
 pub use error::ErrorKind;
 pub use error::Result;
 pub use map::{Map, MapTile};
+pub use player_resources::PlayerResources;
+pub use player_unit::PlayerUnit;
 
 pub use scn::Scenario;
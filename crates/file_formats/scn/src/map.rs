@@ -24,23 +24,34 @@ use error::Result;
 
 use identifier::TerrainId;
 use chariot_io_tools::{ReadArrayExt, ReadExt};
+use write_ext::WriteExt;
 
-use std::io::Read;
+use std::io::{Read, Write};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Map {
     pub width: u32,
     pub height: u32,
     pub tiles: Vec<MapTile>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct MapTile {
     pub terrain_id: TerrainId,
     pub elevation: u8,
     unused: u8,
 }
 
+impl MapTile {
+    pub fn new(terrain_id: TerrainId, elevation: u8) -> MapTile {
+        MapTile {
+            terrain_id: terrain_id,
+            elevation: elevation,
+            unused: 0,
+        }
+    }
+}
+
 impl Map {
     pub fn read_from_stream<S: Read>(stream: &mut S) -> Result<Map> {
         let mut map = Map {
@@ -51,6 +62,12 @@ impl Map {
         map.tiles = try!(stream.read_array((map.width * map.height) as usize, |s| read_map_tile(s)));
         Ok(map)
     }
+
+    pub fn write_to_stream<S: Write>(&self, stream: &mut S) -> Result<()> {
+        try!(stream.write_u32(self.width));
+        try!(stream.write_u32(self.height));
+        stream.write_array(&self.tiles, |s, tile| write_map_tile(s, tile))
+    }
 }
 
 fn read_map_tile<S: Read>(stream: &mut S) -> Result<MapTile> {
@@ -60,3 +77,10 @@ fn read_map_tile<S: Read>(stream: &mut S) -> Result<MapTile> {
         unused: try!(stream.read_u8()),
     })
 }
+
+fn write_map_tile<S: Write>(stream: &mut S, tile: &MapTile) -> Result<()> {
+    try!(stream.write_u8(*tile.terrain_id));
+    try!(stream.write_u8(tile.elevation));
+    try!(stream.write_u8(tile.unused));
+    Ok(())
+}
@@ -23,10 +23,11 @@ use error::Result;
 use identifier::CivilizationId;
 
 use chariot_io_tools::{ReadArrayExt, ReadExt};
+use write_ext::{WriteExt, terminated_bytes};
 
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PlayerData {
     version: f32,
     pub player_names: Vec<String>,
@@ -68,7 +69,7 @@ pub struct PlayerData {
     pub starting_ages: Vec<u32>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PlayerCivilization {
     state: u32, // enabled flag?
     type_id: u32, // human/ai?
@@ -76,7 +77,7 @@ pub struct PlayerCivilization {
     unknown1: u32,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PlayerStartingResources {
     gold: u32,
     wood: u32,
@@ -84,7 +85,7 @@ pub struct PlayerStartingResources {
     stone: u32,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PreviewThumbnail {
     included: bool,
     width: u32,
@@ -95,14 +96,14 @@ pub struct PreviewThumbnail {
     pixel_data: Vec<u8>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct AiScriptConfig {
     ai_file_name: String,
     city_file_name: String,
     personality_file_name: String,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct VictoryConditions {
     conquest_required: bool,
     unused1: u32,
@@ -116,19 +117,38 @@ pub struct VictoryConditions {
     timed_game_time: u32,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Diplomacy {
     stances: Vec<Vec<u32>>,
     individual_victory: Vec<Vec<u32>>,
 }
 
+/// The stance a player takes toward someone they're allied with
+pub const STANCE_ALLIED: u32 = 0;
+/// The stance a player takes toward someone they're at war with
+pub const STANCE_ENEMY: u32 = 3;
+
+impl Diplomacy {
+    /// The player's stance toward the other player (both by player index), if the scenario has one
+    pub fn stance(&self, player_index: usize, other_index: usize) -> Option<u32> {
+        self.stances.get(player_index).and_then(|stances| stances.get(other_index)).cloned()
+    }
+
+    /// Changes the player's stance toward the other player; the other player's stance is left alone
+    pub fn set_stance(&mut self, player_index: usize, other_index: usize, stance: u32) {
+        if let Some(stances) = self.stances.get_mut(player_index) {
+            if let Some(existing) = stances.get_mut(other_index) {
+                *existing = stance;
+            }
+        }
+    }
+}
+
 const PLAYER_DATA_UNKNOWN_1_LENGTH: usize = 8;
 const THUMBNAIL_UNKNOWN_1_LENGTH: usize = 22;
 const THUMBNAIL_UNKNOWN_2_LENGTH: usize = 16;
 
 impl PlayerData {
-    // TODO: Implement writing
-
     pub fn read_from_stream<S: Read + Seek>(stream: &mut S) -> Result<PlayerData> {
         let mut data: PlayerData = Default::default();
         data.version = try!(stream.read_f32());
@@ -178,6 +198,54 @@ impl PlayerData {
 
         Ok(data)
     }
+
+    pub fn write_to_stream<S: Write>(&self, stream: &mut S) -> Result<()> {
+        try!(stream.write_f32(self.version));
+        try!(stream.write_array(&self.player_names, |s, name| Ok(try!(s.write_sized_str(name, 256)))));
+        try!(stream.write_array(&self.player_civs, |s, civ| write_civilization(s, civ)));
+        try!(stream.write_u8(self.conquest_victory as u8));
+        try!(stream.write_all(&self.unknown1));
+
+        for text in &[&self.original_file_name,
+                      &self.instructions,
+                      &self.hints,
+                      &self.victory,
+                      &self.loss,
+                      &self.history,
+                      &self.pre_game_cinematic_file_name,
+                      &self.victory_cinematic_file_name,
+                      &self.loss_cinematic_file_name,
+                      &self.background_file_name] {
+            try!(write_pascal_string(stream, text));
+        }
+
+        try!(write_preview_thumbnail(stream, &self.preview_thumbnail));
+
+        try!(stream.write_array(&self.ai_names, |s, name| write_pascal_string(s, name)));
+        try!(stream.write_array(&self.city_names, |s, name| write_pascal_string(s, name)));
+        try!(stream.write_array(&self.personality_names, |s, name| write_pascal_string(s, name)));
+        try!(stream.write_array(&self.ai_script_configs, |s, config| write_ai_script_config(s, config)));
+        try!(stream.write_all(&self.ai_types));
+
+        try!(stream.write_array(&self.player_starting_resources,
+                                |s, resources| write_player_starting_resources(s, resources)));
+        try!(stream.write_i32(-1)); // separator
+
+        try!(write_victory_conditions(stream, &self.victory_conditions));
+        try!(write_diplomacy(stream, &self.diplomacy));
+        try!(stream.write_i32(-1)); // separator
+
+        try!(stream.write_array(&self.allied_victory, |s, &value| Ok(try!(s.write_u32(value)))));
+        try!(stream.write_array(&self.disabled_research_ids, |s, ids| write_u32s(s, ids)));
+
+        try!(stream.write_u32(self.unused1));
+        try!(stream.write_u32(self.unused2));
+
+        try!(stream.write_u32(self.all_techs as u32));
+        try!(write_u32s(stream, &self.starting_ages));
+        try!(stream.write_i32(-1)); // separator
+        Ok(())
+    }
 }
 
 fn read_civilization<S: Read + Seek>(stream: &mut S) -> Result<PlayerCivilization> {
@@ -261,3 +329,154 @@ fn read_pascal_string<S: Read>(stream: &mut S) -> Result<String> {
     let length = try!(stream.read_u16()) as usize;
     Ok(try!(stream.read_sized_str(length)))
 }
+
+fn write_civilization<S: Write>(stream: &mut S, civ: &PlayerCivilization) -> Result<()> {
+    try!(stream.write_u32(civ.state));
+    try!(stream.write_u32(civ.type_id));
+    try!(stream.write_i32(*civ.civilization_id as i32));
+    try!(stream.write_u32(civ.unknown1));
+    Ok(())
+}
+
+fn write_preview_thumbnail<S: Write>(stream: &mut S, thumb: &PreviewThumbnail) -> Result<()> {
+    try!(stream.write_u32(thumb.included as u32));
+    try!(stream.write_u32(thumb.width));
+    try!(stream.write_u32(thumb.height));
+    try!(stream.write_all(&thumb.unknown1));
+    if thumb.included {
+        try!(stream.write_u32(thumb.pixel_data_length + 40));
+        try!(stream.write_all(&thumb.unknown2));
+        try!(stream.write_all(&thumb.pixel_data));
+    }
+    Ok(())
+}
+
+fn write_ai_script_config<S: Write>(stream: &mut S, config: &AiScriptConfig) -> Result<()> {
+    let file_names = [terminated_bytes(&config.ai_file_name),
+                      terminated_bytes(&config.city_file_name),
+                      terminated_bytes(&config.personality_file_name)];
+    for file_name in &file_names {
+        try!(stream.write_u32(file_name.len() as u32));
+    }
+    for file_name in &file_names {
+        try!(stream.write_all(file_name));
+    }
+    Ok(())
+}
+
+fn write_player_starting_resources<S: Write>(stream: &mut S,
+                                             resources: &PlayerStartingResources)
+                                             -> Result<()> {
+    try!(stream.write_u32(resources.gold));
+    try!(stream.write_u32(resources.wood));
+    try!(stream.write_u32(resources.food));
+    try!(stream.write_u32(resources.stone));
+    Ok(())
+}
+
+fn write_victory_conditions<S: Write>(stream: &mut S, conditions: &VictoryConditions) -> Result<()> {
+    try!(stream.write_u32(conditions.conquest_required as u32));
+    try!(stream.write_u32(conditions.unused1));
+    try!(stream.write_u32(conditions.required_relic_count));
+    try!(stream.write_u32(conditions.unused2));
+    try!(stream.write_u32(conditions.required_exploration_percent));
+    try!(stream.write_u32(conditions.unused3));
+    try!(stream.write_u32(conditions.all_conditions_required as u32));
+    try!(stream.write_u32(conditions.victory_mode));
+    try!(stream.write_u32(conditions.score_required));
+    try!(stream.write_u32(conditions.timed_game_time));
+    Ok(())
+}
+
+fn write_diplomacy<S: Write>(stream: &mut S, diplomacy: &Diplomacy) -> Result<()> {
+    try!(stream.write_array(&diplomacy.stances, |s, stances| write_u32s(s, stances)));
+    stream.write_array(&diplomacy.individual_victory, |s, victory| write_u32s(s, victory))
+}
+
+fn write_u32s<S: Write>(stream: &mut S, values: &[u32]) -> Result<()> {
+    stream.write_array(values, |s, &value| Ok(try!(s.write_u32(value))))
+}
+
+fn write_pascal_string<S: Write>(stream: &mut S, value: &str) -> Result<()> {
+    let bytes = terminated_bytes(value);
+    try!(stream.write_u16(bytes.len() as u16));
+    try!(stream.write_all(&bytes));
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io;
+    use super::{AiScriptConfig, PLAYER_DATA_UNKNOWN_1_LENGTH, PlayerCivilization, PlayerData,
+                PlayerStartingResources, VictoryConditions};
+
+    /// Player data with every list as long as it is in a scenario file, and something in each of
+    /// the different kinds of fields
+    pub fn player_data() -> PlayerData {
+        let mut data: PlayerData = Default::default();
+        data.version = 1.15;
+        data.player_names = (0..16).map(|index| format!("Player {}", index)).collect();
+        data.player_civs = (0usize..16)
+            .map(|index| {
+                PlayerCivilization {
+                    state: 1,
+                    type_id: 4,
+                    civilization_id: (index % 12 + 1).into(),
+                    unknown1: 4,
+                }
+            })
+            .collect();
+        data.conquest_victory = true;
+        data.unknown1 = vec![7u8; PLAYER_DATA_UNKNOWN_1_LENGTH];
+        data.original_file_name = "MUF7E5_1.SCN".to_string();
+        data.instructions = "Build a wonder".to_string();
+        data.preview_thumbnail.unknown1 = vec![0u8; 2];
+        data.ai_names = vec![String::new(); 16];
+        data.city_names = vec![String::new(); 16];
+        data.personality_names = vec![String::new(); 16];
+        data.ai_script_configs = (0..16)
+            .map(|_| {
+                AiScriptConfig {
+                    ai_file_name: "Default".to_string(),
+                    city_file_name: String::new(),
+                    personality_file_name: String::new(),
+                }
+            })
+            .collect();
+        data.ai_types = vec![0u8; 4];
+        data.player_starting_resources = (0..16)
+            .map(|_| {
+                PlayerStartingResources {
+                    gold: 100,
+                    wood: 200,
+                    food: 200,
+                    stone: 150,
+                }
+            })
+            .collect();
+        data.victory_conditions = VictoryConditions {
+            conquest_required: true,
+            required_relic_count: 5,
+            timed_game_time: 600,
+            ..Default::default()
+        };
+        data.diplomacy.stances = vec![vec![3; 16]; 16];
+        data.diplomacy.individual_victory = vec![vec![0; 180]; 16];
+        data.allied_victory = vec![0; 16];
+        data.disabled_research_ids = vec![vec![0; 20]; 16];
+        data.all_techs = true;
+        data.starting_ages = vec![1; 16];
+        data
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = player_data();
+        let mut bytes = Vec::new();
+        data.write_to_stream(&mut bytes).unwrap();
+
+        let mut stream = io::Cursor::new(bytes);
+        assert_eq!(data, PlayerData::read_from_stream(&mut stream).unwrap());
+        assert_eq!(stream.get_ref().len() as u64, stream.position());
+    }
+}
@@ -23,10 +23,11 @@ use error::Result;
 use identifier::PlayerId;
 
 use chariot_io_tools::{ReadArrayExt, ReadExt};
+use write_ext::WriteExt;
 
-use std::io::Read;
+use std::io::{Read, Write};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PlayerResources {
     pub player_id: PlayerId,
     pub food: f32,
@@ -36,8 +37,6 @@ pub struct PlayerResources {
 }
 
 impl PlayerResources {
-    // TODO: Implement writing
-
     pub fn read_from_stream<S: Read>(stream: &mut S) -> Result<Vec<PlayerResources>> {
         let mut resources = try!(stream.read_array(8, |s| read_single_from_stream(s)));
         for (index, mut resource) in resources.iter_mut().enumerate() {
@@ -45,6 +44,11 @@ impl PlayerResources {
         }
         Ok(resources)
     }
+
+    /// Writes every player's resources, in player order (the reverse of `read_from_stream`)
+    pub fn write_to_stream<S: Write>(resources: &[PlayerResources], stream: &mut S) -> Result<()> {
+        stream.write_array(resources, |s, resource| write_single_to_stream(s, resource))
+    }
 }
 
 fn read_single_from_stream<S: Read>(stream: &mut S) -> Result<PlayerResources> {
@@ -55,3 +59,11 @@ fn read_single_from_stream<S: Read>(stream: &mut S) -> Result<PlayerResources> {
     data.stone = try!(stream.read_f32());
     Ok(data)
 }
+
+fn write_single_to_stream<S: Write>(stream: &mut S, data: &PlayerResources) -> Result<()> {
+    try!(stream.write_f32(data.food));
+    try!(stream.write_f32(data.wood));
+    try!(stream.write_f32(data.gold));
+    try!(stream.write_f32(data.stone));
+    Ok(())
+}
@@ -23,10 +23,11 @@ use error::Result;
 use identifier::{SpawnId, UnitId};
 
 use chariot_io_tools::ReadExt;
+use write_ext::WriteExt;
 
-use std::io::Read;
+use std::io::{Read, Write};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PlayerUnit {
     pub position_x: f32,
     pub position_y: f32,
@@ -38,8 +39,6 @@ pub struct PlayerUnit {
 }
 
 impl PlayerUnit {
-    // TODO: Implement writing
-
     pub fn read_from_stream<S: Read>(stream: &mut S) -> Result<PlayerUnit> {
         let mut data: PlayerUnit = Default::default();
         data.position_x = try!(stream.read_f32());
@@ -51,4 +50,15 @@ impl PlayerUnit {
         data.rotation = try!(stream.read_f32());
         Ok(data)
     }
+
+    pub fn write_to_stream<S: Write>(&self, stream: &mut S) -> Result<()> {
+        try!(stream.write_f32(self.position_x));
+        try!(stream.write_f32(self.position_y));
+        try!(stream.write_f32(self.position_z));
+        try!(stream.write_i32(self.spawn_id.map(|id| *id as i32).unwrap_or(-1)));
+        try!(stream.write_i16(*self.unit_id as i16));
+        try!(stream.write_u8(self.state));
+        try!(stream.write_f32(self.rotation));
+        Ok(())
+    }
 }
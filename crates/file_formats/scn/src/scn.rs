@@ -24,22 +24,27 @@ use error::{ErrorKind, Result};
 use identifier::{CivilizationId, PlayerId};
 use chariot_io_tools::{ReadExt, ReadArrayExt};
 use map::Map;
-use player_data::PlayerData;
+use player_data::{PlayerData, STANCE_ALLIED, STANCE_ENEMY};
 use player_resources::PlayerResources;
 use player_unit::PlayerUnit;
+use std::cmp;
 use std::fs::File;
+use write_ext::{self, WriteExt, terminated_bytes};
 
 use std::io;
-use std::io::prelude::{Read, Seek};
+use std::io::prelude::{Read, Seek, Write};
 use std::path::Path;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Scenario {
     header: ScenarioHeader,
+    next_unit_id: u32,
     pub player_data: PlayerData,
     player_resources: Vec<PlayerResources>,
     player_units: Vec<Vec<PlayerUnit>>,
     pub map: Map,
+    /// Everything after the units, which isn't read yet; it's written back as it was
+    unread: Vec<u8>,
 }
 
 impl Scenario {
@@ -49,6 +54,58 @@ impl Scenario {
         &self.player_resources[*player_id as usize]
     }
 
+    /// Retrieves player resources by player ID, if the scenario has any for the player
+    #[inline]
+    pub fn find_player_resources<'a>(&'a self, player_id: PlayerId) -> Option<&'a PlayerResources> {
+        self.player_resources.get(*player_id as usize)
+    }
+
+    /// Replaces the player's starting resources (by the player ID they hold), if the scenario
+    /// has room for that player
+    pub fn set_player_resources(&mut self, resources: PlayerResources) {
+        if let Some(existing) = self.player_resources.get_mut(*resources.player_id as usize) {
+            *existing = resources;
+        }
+    }
+
+    /// Every player's team, indexed by player ID. Players who are allied with each other both
+    /// ways are on the same team, which is numbered after its lowest player ID; everyone else is
+    /// on a team of their own. Gaia is never on anyone's team.
+    pub fn player_teams(&self) -> Vec<u8> {
+        let player_count = self.player_units.len();
+        let mut teams: Vec<u8> = (0..player_count).map(|index| index as u8).collect();
+        let diplomacy = &self.player_data.diplomacy;
+        for player in 1..player_count {
+            for other in (player + 1)..player_count {
+                let allied = diplomacy.stance(player, other) == Some(STANCE_ALLIED) &&
+                             diplomacy.stance(other, player) == Some(STANCE_ALLIED);
+                if allied {
+                    let from = cmp::max(teams[player], teams[other]);
+                    let to = cmp::min(teams[player], teams[other]);
+                    for team in teams.iter_mut().filter(|team| **team == from) {
+                        *team = to;
+                    }
+                }
+            }
+        }
+        teams
+    }
+
+    /// Makes players on the same team (indexed by player ID, like `player_teams`) allies and
+    /// everyone else enemies; gaia's stances are left alone
+    pub fn set_player_teams(&mut self, teams: &[u8]) {
+        for player in 1..teams.len() {
+            for other in (1..teams.len()).filter(|other| *other != player) {
+                let stance = if teams[player] == teams[other] {
+                    STANCE_ALLIED
+                } else {
+                    STANCE_ENEMY
+                };
+                self.player_data.diplomacy.set_stance(player, other, stance);
+            }
+        }
+    }
+
     /// Retrieves a list of units by player ID
     #[inline]
     pub fn player_units<'a>(&'a self, player_id: PlayerId) -> &'a Vec<PlayerUnit> {
@@ -66,7 +123,16 @@ impl Scenario {
         (0..self.player_units.len()).map(|i| i.into()).collect()
     }
 
-    // TODO: Implement writing
+    /// Replaces every player's units (indexed by player ID), numbering their spawn IDs in order
+    pub fn set_player_units(&mut self, player_units: Vec<Vec<PlayerUnit>>) {
+        let mut next_unit_id = 0;
+        self.player_units = player_units;
+        for unit in self.player_units.iter_mut().flat_map(|units| units.iter_mut()) {
+            unit.spawn_id = Some((next_unit_id as usize).into());
+            next_unit_id += 1;
+        }
+        self.next_unit_id = next_unit_id;
+    }
 
     pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> Result<Scenario> {
         Scenario::read_from_stream(try!(File::open(file_name.as_ref())))
@@ -78,7 +144,7 @@ impl Scenario {
 
         let mut stream = io::Cursor::new(try!(stream.read_and_decompress()));
 
-        let _next_unit_id = try!(stream.read_u32()); // not sure what this is for yet
+        scenario.next_unit_id = try!(stream.read_u32());
         scenario.player_data = try!(PlayerData::read_from_stream(&mut stream));
         scenario.map = try!(Map::read_from_stream(&mut stream));
 
@@ -93,17 +159,42 @@ impl Scenario {
 
         // TODO: Read other player data
         // TODO: Read triggers
+        try!(stream.read_to_end(&mut scenario.unread));
 
         Ok(scenario)
     }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_name: P) -> Result<()> {
+        self.write_to_stream(&mut try!(File::create(file_name.as_ref())))
+    }
+
+    pub fn write_to_stream<S: Write>(&self, stream: &mut S) -> Result<()> {
+        try!(self.header.write_to_stream(stream));
+
+        let mut body = Vec::new();
+        try!(body.write_u32(self.next_unit_id));
+        try!(self.player_data.write_to_stream(&mut body));
+        try!(self.map.write_to_stream(&mut body));
+
+        try!(body.write_u32(self.player_units.len() as u32));
+        try!(PlayerResources::write_to_stream(&self.player_resources, &mut body));
+
+        for units in &self.player_units {
+            try!(body.write_u32(units.len() as u32));
+            try!(body.write_array(units, |s, unit| unit.write_to_stream(s)));
+        }
+        try!(body.write_all(&self.unread));
+
+        try!(stream.write_all(&try!(write_ext::compress(&body))));
+        Ok(())
+    }
 }
 
 const REASONABLE_INSTRUCTION_LIMIT: usize = 512 * 1024; // 0.5 mibibytes
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 struct ScenarioHeader {
     version: String,
-    length: u32,
     save_type: i32,
     last_save_time: u32,
     instructions: String,
@@ -112,8 +203,6 @@ struct ScenarioHeader {
 }
 
 impl ScenarioHeader {
-    // TODO: Implement writing
-
     fn read_from_stream<S: Read + Seek>(stream: &mut S) -> Result<ScenarioHeader> {
         let mut header: ScenarioHeader = Default::default();
         header.version = try!(stream.read_sized_str(4));
@@ -121,7 +210,7 @@ impl ScenarioHeader {
             return Err(ErrorKind::UnrecognizedScenarioVersion.into());
         }
 
-        header.length = try!(stream.read_u32());
+        try!(stream.read_u32()); // length of the rest of the header, worked out again when writing
         header.save_type = try!(stream.read_i32());
         header.last_save_time = try!(stream.read_u32());
         header.instructions = {
@@ -136,4 +225,98 @@ impl ScenarioHeader {
         header.player_count = try!(stream.read_u32());
         Ok(header)
     }
+
+    /// Writes the header with its length worked out again, since the instructions may have changed
+    fn write_to_stream<S: Write>(&self, stream: &mut S) -> Result<()> {
+        let instructions = terminated_bytes(&self.instructions);
+        try!(stream.write_sized_str(&self.version, 4));
+        // Everything after the length: the save type, save time, instructions and their length,
+        // victory type, and player count
+        try!(stream.write_u32(20 + instructions.len() as u32));
+        try!(stream.write_i32(self.save_type));
+        try!(stream.write_u32(self.last_save_time));
+        try!(stream.write_u32(instructions.len() as u32));
+        try!(stream.write_all(&instructions));
+        try!(stream.write_u32(self.victory_type));
+        try!(stream.write_u32(self.player_count));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use map::{Map, MapTile};
+    use player_data::tests::player_data;
+    use player_resources::PlayerResources;
+    use player_unit::PlayerUnit;
+    use std::io;
+    use super::{Scenario, ScenarioHeader};
+
+    #[test]
+    fn test_round_trip() {
+        let mut scenario = Scenario {
+            header: ScenarioHeader {
+                version: "1.11".to_string(),
+                save_type: 2,
+                last_save_time: 847_000_000,
+                instructions: "Build a wonder".to_string(),
+                victory_type: 0,
+                player_count: 2,
+            },
+            player_data: player_data(),
+            player_resources: (0usize..8)
+                .map(|index| {
+                    PlayerResources {
+                        player_id: index.into(),
+                        food: 200.0,
+                        wood: 200.0,
+                        gold: 100.0,
+                        stone: 150.0,
+                    }
+                })
+                .collect(),
+            map: Map {
+                width: 2,
+                height: 3,
+                tiles: (0usize..6)
+                    .map(|index| MapTile::new(index.into(), (index % 3) as u8))
+                    .collect(),
+            },
+            unread: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+        let town_center = PlayerUnit {
+            position_x: 1.5,
+            position_y: 2.5,
+            position_z: 0.0,
+            spawn_id: None,
+            unit_id: 109.into(),
+            state: 2,
+            rotation: 0.0,
+        };
+        let player_units = vec![Vec::new(), vec![town_center.clone(), town_center.clone()], vec![town_center]];
+        scenario.set_player_units(player_units);
+        assert_eq!(3, scenario.next_unit_id);
+        assert_eq!(Some(2.into()), scenario.player_units(2.into())[0].spawn_id);
+
+        // The player data starts everyone off as enemies
+        assert_eq!(vec![0, 1, 2], scenario.player_teams());
+        scenario.set_player_teams(&[0, 1, 1]);
+        scenario.set_player_resources(PlayerResources {
+            player_id: 2.into(),
+            food: 500.0,
+            wood: 400.0,
+            gold: 300.0,
+            stone: 250.0,
+        });
+
+        let mut bytes = Vec::new();
+        scenario.write_to_stream(&mut bytes).unwrap();
+        let read = Scenario::read_from_stream(io::Cursor::new(bytes)).unwrap();
+        assert_eq!(scenario, read);
+        assert_eq!(vec![0, 1, 1], read.player_teams());
+        assert_eq!(500.0, read.player_resources(2.into()).food);
+        assert_eq!(250.0, read.player_resources(2.into()).stone);
+        assert_eq!(200.0, read.player_resources(1.into()).food);
+    }
 }
@@ -0,0 +1,92 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use error::Result;
+
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
+
+use std::cmp;
+use std::io::{self, Write};
+use std::mem;
+
+/// The writing counterparts to `chariot_io_tools::ReadExt`, little endian like the reads
+pub trait WriteExt: Write {
+    fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write_all(&[value])
+    }
+
+    fn write_u16(&mut self, value: u16) -> io::Result<()> {
+        self.write_all(&[value as u8, (value >> 8) as u8])
+    }
+
+    fn write_i16(&mut self, value: i16) -> io::Result<()> {
+        self.write_u16(value as u16)
+    }
+
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_all(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8])
+    }
+
+    fn write_i32(&mut self, value: i32) -> io::Result<()> {
+        self.write_u32(value as u32)
+    }
+
+    fn write_f32(&mut self, value: f32) -> io::Result<()> {
+        self.write_u32(unsafe { mem::transmute::<f32, u32>(value) })
+    }
+
+    /// Writes the string into a field of exactly `length` bytes, padded with nulls (and cut short if
+    /// it doesn't fit)
+    fn write_sized_str(&mut self, value: &str, length: usize) -> io::Result<()> {
+        let mut bytes = vec![0u8; length];
+        let copied = cmp::min(value.len(), length);
+        bytes[..copied].copy_from_slice(&value.as_bytes()[..copied]);
+        self.write_all(&bytes)
+    }
+
+    fn write_array<T, F>(&mut self, values: &[T], mut write_value: F) -> Result<()>
+        where F: FnMut(&mut Self, &T) -> Result<()>
+    {
+        for value in values {
+            try!(write_value(self, value));
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> WriteExt for W {}
+
+/// The bytes of the string as it's stored after a length: with a null at the end, unless it's empty
+pub fn terminated_bytes(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    if !bytes.is_empty() {
+        bytes.push(0);
+    }
+    bytes
+}
+
+/// Compresses the bytes the way `chariot_io_tools::ReadExt::read_and_decompress` expects them
+pub fn compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::Default);
+    try!(encoder.write_all(bytes));
+    encoder.finish()
+}
@@ -19,30 +19,42 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use action::Action;
-use specs::Index;
-use std::collections::HashMap;
-use std::mem;
-
-pub struct ActionBatcher {
-    actions: HashMap<Index, Vec<Action>>,
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum DrsKey {
+    Border,
+    Graphics,
+    Interfac,
+    Sounds,
+    Terrain,
 }
 
-impl ActionBatcher {
-    pub fn new() -> ActionBatcher {
-        ActionBatcher { actions: HashMap::new() }
+impl DrsKey {
+    /// Whether the game can run without this archive; the demo version doesn't ship all of them,
+    /// and any shapes or sounds that would have come from a missing archive are left as placeholders
+    pub fn is_optional(&self) -> bool {
+        *self != DrsKey::Interfac
     }
 
-    pub fn queue_for_entity(&mut self, entity_id: Index, action: Action) {
-        if !self.actions.contains_key(&entity_id) {
-            self.actions.insert(entity_id, Vec::new());
+    pub fn path(&self) -> &'static str {
+        use self::DrsKey::*;
+        match *self {
+            Border => "data/border.drs",
+            Graphics => "data/graphics.drs",
+            Interfac => "data/interfac.drs",
+            Sounds => "data/sounds.drs",
+            Terrain => "data/terrain.drs",
         }
-        self.actions.get_mut(&entity_id).unwrap().push(action);
     }
 
-    pub fn consume_actions(&mut self) -> HashMap<Index, Vec<Action>> {
-        let mut consumed = HashMap::new();
-        mem::swap(&mut consumed, &mut self.actions);
-        consumed
+    /// Name of the directory that mods put loose replacements for this archive's files in
+    pub fn name(&self) -> &'static str {
+        use self::DrsKey::*;
+        match *self {
+            Border => "border",
+            Graphics => "graphics",
+            Interfac => "interfac",
+            Sounds => "sounds",
+            Terrain => "terrain",
+        }
     }
 }
@@ -19,6 +19,8 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+mod drs_key;
 mod id;
 
+pub use drs_key::DrsKey;
 pub use id::*;
@@ -0,0 +1,363 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use key::{Key, MouseButton};
+
+use nalgebra::Vector2;
+use std::collections::HashSet;
+use std::f32::consts::PI;
+
+/// How far a stick has to be pushed before it counts, out of 1
+const STICK_DEAD_ZONE: f32 = 0.25;
+/// How far the trigger has to be pulled to open the command menu, out of 1
+const TRIGGER_THRESHOLD: f32 = 0.5;
+/// How many pixels the cursor moves in an update with the right stick pushed all the way
+const CURSOR_SPEED: f32 = 12.0;
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ControllerButton {
+    A,
+    B,
+    X,
+    Y,
+    Back,
+    LeftShoulder,
+    RightShoulder,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Buttons that stand in for a key or mouse button. The sticks, the left trigger, and the rest of
+/// the buttons are handled by the controller itself.
+const BUTTON_KEYS: [(ControllerButton, Key); 9] = [(ControllerButton::X, Key::C),
+                                                   (ControllerButton::Y, Key::Tab),
+                                                   (ControllerButton::Back, Key::Delete),
+                                                   (ControllerButton::LeftShoulder, Key::ShiftLeft),
+                                                   (ControllerButton::RightShoulder, Key::CtrlLeft),
+                                                   (ControllerButton::DPadUp, Key::Up),
+                                                   (ControllerButton::DPadDown, Key::Down),
+                                                   (ControllerButton::DPadLeft, Key::Left),
+                                                   (ControllerButton::DPadRight, Key::Right)];
+const BUTTON_MOUSE_BUTTONS: [(ControllerButton, MouseButton); 2] =
+    [(ControllerButton::A, MouseButton::Left), (ControllerButton::B, MouseButton::Right)];
+
+/// Where the sticks (from -1 to 1, with positive y pointing down) and the left trigger (from 0 to 1)
+/// are, and which buttons are held down
+#[derive(Clone, Debug)]
+pub struct ControllerInput {
+    pub left_stick: (f32, f32),
+    pub right_stick: (f32, f32),
+    pub left_trigger: f32,
+    pub buttons: HashSet<ControllerButton>,
+}
+
+impl ControllerInput {
+    pub fn new() -> ControllerInput {
+        ControllerInput {
+            left_stick: (0.0, 0.0),
+            right_stick: (0.0, 0.0),
+            left_trigger: 0.0,
+            buttons: HashSet::new(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RadialAction {
+    /// Taps the key
+    Press(Key),
+    /// Holds the key down until the next command (B) is given
+    HoldForNextCommand(Key),
+}
+
+#[derive(Clone)]
+pub struct RadialMenuItem {
+    pub label: &'static str,
+    pub action: RadialAction,
+}
+
+/// Commands laid out in a circle, clockwise from the top, that the right stick picks from while the
+/// left trigger is held
+#[derive(Clone)]
+pub struct RadialMenu {
+    items: Vec<RadialMenuItem>,
+    highlighted: Option<usize>,
+}
+
+impl RadialMenu {
+    pub fn new() -> RadialMenu {
+        use self::RadialAction::*;
+        let item = |label, action| {
+            RadialMenuItem {
+                label: label,
+                action: action,
+            }
+        };
+        RadialMenu {
+            items: vec![item("Train", Press(Key::C)),
+                        item("Attack ground", HoldForNextCommand(Key::AltLeft)),
+                        item("Queue command", HoldForNextCommand(Key::CtrlLeft)),
+                        item("Next unit type", Press(Key::Tab)),
+                        item("Delete", Press(Key::Delete))],
+            highlighted: None,
+        }
+    }
+
+    pub fn items(&self) -> &[RadialMenuItem] {
+        &self.items
+    }
+
+    pub fn highlighted(&self) -> Option<usize> {
+        self.highlighted
+    }
+
+    fn highlight(&mut self, stick: (f32, f32)) {
+        self.highlighted = if outside_dead_zone(stick) {
+            // Zero at the top, growing clockwise
+            let angle = stick.0.atan2(-stick.1);
+            let slice = 2.0 * PI / self.items.len() as f32;
+            let index = (angle / slice).round() as i32;
+            let len = self.items.len() as i32;
+            Some((((index % len) + len) % len) as usize)
+        } else {
+            None
+        };
+    }
+}
+
+// TODO: Snap the cursor to the nearest unit, which needs the units' screen positions from the game
+/// Turns a game controller into the keys and mouse buttons that it stands in for, so that nothing
+/// past the media layer needs to know which one is being used. The left stick and the d-pad pan the
+/// camera, the right stick moves the cursor, A selects, B commands, and the left trigger opens the
+/// radial command menu.
+#[derive(Clone)]
+pub struct Controller {
+    menu: RadialMenu,
+    menu_open: bool,
+    pending_press: Option<Key>,
+    held_for_command: Option<Key>,
+    commanding: bool,
+    cursor_moved: bool,
+}
+
+impl Controller {
+    pub fn new() -> Controller {
+        Controller {
+            menu: RadialMenu::new(),
+            menu_open: false,
+            pending_press: None,
+            held_for_command: None,
+            commanding: false,
+            cursor_moved: false,
+        }
+    }
+
+    /// The radial menu, while it's open
+    pub fn menu(&self) -> Option<&RadialMenu> {
+        if self.menu_open { Some(&self.menu) } else { None }
+    }
+
+    /// Whether the right stick moved the cursor since the mouse last did
+    pub fn cursor_moved(&self) -> bool {
+        self.cursor_moved
+    }
+
+    /// Tells the controller the mouse moved, which takes the cursor back from the right stick
+    pub fn mouse_moved(&mut self) {
+        self.cursor_moved = false;
+    }
+
+    /// Adds the keys and mouse buttons that the controller is holding down, and moves the cursor
+    /// with the right stick, keeping it inside the given viewport
+    pub fn update(&mut self,
+                  input: &ControllerInput,
+                  keys: &mut HashSet<Key>,
+                  mouse_buttons: &mut HashSet<MouseButton>,
+                  cursor: &mut Vector2<i32>,
+                  viewport_size: Vector2<u32>) {
+        for &(button, key) in &BUTTON_KEYS {
+            if input.buttons.contains(&button) {
+                keys.insert(key);
+            }
+        }
+        for &(button, mouse_button) in &BUTTON_MOUSE_BUTTONS {
+            if input.buttons.contains(&button) {
+                mouse_buttons.insert(mouse_button);
+            }
+        }
+
+        let (pan_x, pan_y) = input.left_stick;
+        if pan_x < -STICK_DEAD_ZONE {
+            keys.insert(Key::Left);
+        } else if pan_x > STICK_DEAD_ZONE {
+            keys.insert(Key::Right);
+        }
+        if pan_y < -STICK_DEAD_ZONE {
+            keys.insert(Key::Up);
+        } else if pan_y > STICK_DEAD_ZONE {
+            keys.insert(Key::Down);
+        }
+
+        // A tap from the menu is held for one update so that it transitions down and then up
+        if let Some(key) = self.pending_press.take() {
+            keys.insert(key);
+        }
+
+        // Held keys stay down until the command button is let go, since that's when commands are given
+        let commanding = input.buttons.contains(&ControllerButton::B);
+        if let Some(key) = self.held_for_command {
+            keys.insert(key);
+            if self.commanding && !commanding {
+                self.held_for_command = None;
+            }
+        }
+        self.commanding = commanding;
+
+        if input.left_trigger >= TRIGGER_THRESHOLD {
+            self.menu_open = true;
+            self.menu.highlight(input.right_stick);
+            return;
+        }
+        if self.menu_open {
+            self.menu_open = false;
+            if let Some(index) = self.menu.highlighted {
+                match self.menu.items[index].action {
+                    RadialAction::Press(key) => self.pending_press = Some(key),
+                    RadialAction::HoldForNextCommand(key) => self.held_for_command = Some(key),
+                }
+            }
+            self.menu.highlighted = None;
+        }
+
+        if outside_dead_zone(input.right_stick) {
+            let (x, y) = input.right_stick;
+            cursor.x = clamp(cursor.x + (x * CURSOR_SPEED) as i32, viewport_size.x as i32);
+            cursor.y = clamp(cursor.y + (y * CURSOR_SPEED) as i32, viewport_size.y as i32);
+            self.cursor_moved = true;
+        }
+    }
+}
+
+fn outside_dead_zone(stick: (f32, f32)) -> bool {
+    stick.0 * stick.0 + stick.1 * stick.1 > STICK_DEAD_ZONE * STICK_DEAD_ZONE
+}
+
+fn clamp(value: i32, size: i32) -> i32 {
+    if value < 0 {
+        0
+    } else if value >= size {
+        size - 1
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use key::{Key, MouseButton};
+    use nalgebra::Vector2;
+    use std::collections::HashSet;
+    use super::{Controller, ControllerButton, ControllerInput};
+
+    struct Update {
+        keys: HashSet<Key>,
+        mouse_buttons: HashSet<MouseButton>,
+        cursor: Vector2<i32>,
+    }
+
+    fn update(controller: &mut Controller, input: &ControllerInput) -> Update {
+        let mut result = Update {
+            keys: HashSet::new(),
+            mouse_buttons: HashSet::new(),
+            cursor: Vector2::new(100, 100),
+        };
+        controller.update(input,
+                          &mut result.keys,
+                          &mut result.mouse_buttons,
+                          &mut result.cursor,
+                          Vector2::new(200, 200));
+        result
+    }
+
+    #[test]
+    fn test_sticks_and_buttons() {
+        let mut controller = Controller::new();
+        let mut input = ControllerInput::new();
+        input.left_stick = (-0.9, 0.1);
+        input.right_stick = (1.0, -1.0);
+        input.buttons.insert(ControllerButton::A);
+        input.buttons.insert(ControllerButton::X);
+
+        let result = update(&mut controller, &input);
+        assert_eq!(vec![Key::Left, Key::C].into_iter().collect::<HashSet<_>>(), result.keys);
+        assert!(result.mouse_buttons.contains(&MouseButton::Left));
+        assert_eq!(Vector2::new(112, 88), result.cursor);
+        assert!(controller.cursor_moved());
+
+        controller.mouse_moved();
+        let result = update(&mut controller, &ControllerInput::new());
+        assert!(result.keys.is_empty());
+        assert_eq!(Vector2::new(100, 100), result.cursor);
+        assert!(!controller.cursor_moved());
+    }
+
+    #[test]
+    fn test_radial_menu_press() {
+        let mut controller = Controller::new();
+        let mut input = ControllerInput::new();
+        input.left_trigger = 1.0;
+        input.right_stick = (0.0, -1.0);
+        let result = update(&mut controller, &input);
+        assert_eq!(Some(0), controller.menu().unwrap().highlighted());
+        assert_eq!(Vector2::new(100, 100), result.cursor);
+
+        // Letting go of the trigger taps the highlighted item's key on the next update
+        input.left_trigger = 0.0;
+        input.right_stick = (0.0, 0.0);
+        assert!(update(&mut controller, &input).keys.is_empty());
+        assert!(controller.menu().is_none());
+        assert!(update(&mut controller, &input).keys.contains(&Key::C));
+        assert!(update(&mut controller, &input).keys.is_empty());
+    }
+
+    #[test]
+    fn test_radial_menu_hold_for_next_command() {
+        let mut controller = Controller::new();
+        let mut input = ControllerInput::new();
+        input.left_trigger = 1.0;
+        input.right_stick = (0.95, -0.3);
+        update(&mut controller, &input);
+        assert_eq!(Some(1), controller.menu().unwrap().highlighted());
+
+        input = ControllerInput::new();
+        update(&mut controller, &input);
+        assert!(update(&mut controller, &input).keys.contains(&Key::AltLeft));
+        input.buttons.insert(ControllerButton::B);
+        assert!(update(&mut controller, &input).keys.contains(&Key::AltLeft));
+
+        // Still held on the update that the command button is let go, since that's when it's given
+        input.buttons.clear();
+        assert!(update(&mut controller, &input).keys.contains(&Key::AltLeft));
+        assert!(update(&mut controller, &input).keys.is_empty());
+    }
+}
@@ -40,5 +40,14 @@ error_chain! {
     }
 
     errors {
+        ReadPixelsFailed(message: String) {
+            description("failed to read pixels")
+            display("Failed to read pixels: {}", message)
+        }
+
+        MissingPixels(expected: usize, actual: usize) {
+            description("not enough pixels for the shape")
+            display("Expected {} pixels for the shape, but there were only {}", expected, actual)
+        }
     }
 }
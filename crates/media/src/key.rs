@@ -32,12 +32,82 @@ pub enum Key {
     Left,
     Right,
     Space,
+    Tab,
+    Escape,
+    Return,
     ShiftLeft,
     CtrlLeft,
+    AltLeft,
+    Delete,
+    Backspace,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Num1,
+    Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Minus,
+    Equals,
+    PageUp,
+    PageDown,
+    Home,
+    F1,
+    F2,
+    F3,
+    F4,
+    F8,
+    F9,
+    F10,
+    F11,
+    Backquote,
+    Period,
+    Slash,
     // Add keys as necessary
 }
 
 impl Key {
+    /// The key for a letter (in either case), for hotkeys that come from names
+    pub fn from_letter(letter: char) -> Option<Key> {
+        static LETTERS: [Key; 26] = [Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+                                     Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+                                     Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z];
+        if letter >= 'A' && letter <= 'Z' {
+            Some(LETTERS[letter as usize - 'A' as usize])
+        } else if letter >= 'a' && letter <= 'z' {
+            Some(LETTERS[letter as usize - 'a' as usize])
+        } else {
+            None
+        }
+    }
+
     pub fn from_sdl(scancode: sdl2::keyboard::Scancode) -> Option<Key> {
         sdl2::keyboard::Keycode::from_scancode(scancode).and_then(|keycode| {
             use sdl2::keyboard::Keycode as K;
@@ -47,8 +117,64 @@ impl Key {
                 K::Left => Key::Left,
                 K::Right => Key::Right,
                 K::Space => Key::Space,
+                K::Tab => Key::Tab,
+                K::Escape => Key::Escape,
+                K::Return => Key::Return,
                 K::LShift => Key::ShiftLeft,
                 K::LCtrl => Key::CtrlLeft,
+                K::LAlt => Key::AltLeft,
+                K::Delete => Key::Delete,
+                K::Backspace => Key::Backspace,
+                K::A => Key::A,
+                K::B => Key::B,
+                K::C => Key::C,
+                K::D => Key::D,
+                K::E => Key::E,
+                K::F => Key::F,
+                K::G => Key::G,
+                K::H => Key::H,
+                K::I => Key::I,
+                K::J => Key::J,
+                K::K => Key::K,
+                K::L => Key::L,
+                K::M => Key::M,
+                K::N => Key::N,
+                K::O => Key::O,
+                K::P => Key::P,
+                K::Q => Key::Q,
+                K::R => Key::R,
+                K::S => Key::S,
+                K::T => Key::T,
+                K::U => Key::U,
+                K::V => Key::V,
+                K::W => Key::W,
+                K::X => Key::X,
+                K::Y => Key::Y,
+                K::Z => Key::Z,
+                K::Num1 => Key::Num1,
+                K::Num2 => Key::Num2,
+                K::Num3 => Key::Num3,
+                K::Num4 => Key::Num4,
+                K::Num5 => Key::Num5,
+                K::Num6 => Key::Num6,
+                K::Num7 => Key::Num7,
+                K::Num8 => Key::Num8,
+                K::Minus => Key::Minus,
+                K::Equals => Key::Equals,
+                K::PageUp => Key::PageUp,
+                K::PageDown => Key::PageDown,
+                K::Home => Key::Home,
+                K::F1 => Key::F1,
+                K::F2 => Key::F2,
+                K::F3 => Key::F3,
+                K::F4 => Key::F4,
+                K::F8 => Key::F8,
+                K::F9 => Key::F9,
+                K::F10 => Key::F10,
+                K::F11 => Key::F11,
+                K::Backquote => Key::Backquote,
+                K::Period => Key::Period,
+                K::Slash => Key::Slash,
                 _ => return None,
             })
         })
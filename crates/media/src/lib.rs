@@ -24,15 +24,20 @@
 #[macro_use]
 extern crate error_chain;
 
+// TODO: A WebGL and Web Audio backend implementing Media and Renderer is what stands between the
+// game and a wasm32-unknown-unknown build, along with calling Game::frame from requestAnimationFrame
 extern crate sdl2;
 extern crate nalgebra;
 
 extern crate chariot_types as types;
 
+mod controller;
 mod error;
+pub mod font;
 mod key;
 mod media;
 mod renderer;
+mod sdl_types;
 mod texture;
 mod texture_builder;
 
@@ -41,12 +46,13 @@ pub use error::Error;
 pub use error::ErrorKind;
 pub use error::Result;
 
+pub use controller::{Controller, RadialAction, RadialMenu, RadialMenuItem};
 pub use key::{Key, KeyState, KeyStates, MouseButton};
 pub use media::{Media, MediaRef};
 
 pub use media::create_media;
 
-pub use renderer::Renderer;
+pub use renderer::{CapturedFrame, Renderer};
 
 pub use texture::Texture;
 pub use texture_builder::TextureBuilder;
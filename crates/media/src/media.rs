@@ -19,11 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use controller::{Controller, ControllerButton, ControllerInput};
 use error::Result;
 use key::{Key, KeyState, KeyStates, MouseButton};
 
 use nalgebra::Vector2;
-use renderer::Renderer;
+use renderer::{Renderer, SdlRenderer};
 
 use sdl2;
 use std::cell::RefCell;
@@ -41,6 +42,9 @@ pub trait Media {
     fn mouse_position(&self) -> Vector2<i32>;
     fn mouse_button_states<'a>(&'a self) -> &'a KeyStates<MouseButton>;
 
+    /// The game controller's state, while one is connected
+    fn controller(&self) -> Option<&Controller>;
+
     fn renderer<'a>(&'a mut self) -> &'a mut Renderer;
     fn viewport_size(&self) -> Vector2<u32>;
 }
@@ -53,12 +57,16 @@ pub fn create_media(width: u32, height: u32, title: &str) -> Result<MediaRef> {
 
 struct SdlMedia {
     context: sdl2::Sdl,
-    renderer: Renderer,
+    renderer: SdlRenderer,
     open: bool,
     keys_pressed: HashSet<Key>,
     key_states: KeyStates<Key>,
     mouse_position: Vector2<i32>,
     mouse_button_states: KeyStates<MouseButton>,
+    last_physical_mouse_position: Vector2<i32>,
+    controller_subsystem: Option<sdl2::GameControllerSubsystem>,
+    game_controller: Option<sdl2::controller::GameController>,
+    controller: Controller,
     initial_width: u32,
     initial_height: u32,
     scale_x: f32,
@@ -68,7 +76,16 @@ struct SdlMedia {
 impl SdlMedia {
     fn new(width: u32, height: u32, title: &str) -> Result<SdlMedia> {
         let mut context = try!(sdl2::init());
-        let renderer = try!(Renderer::new(&mut context, width, height, title));
+        let renderer = try!(SdlRenderer::new(&mut context, width, height, title));
+
+        // Controllers are optional, so the game still runs if SDL can't look for them
+        let controller_subsystem = match context.game_controller() {
+            Ok(subsystem) => Some(subsystem),
+            Err(err) => {
+                println!("Game controllers are unavailable: {}", err);
+                None
+            }
+        };
 
         Ok(SdlMedia {
             context: context,
@@ -78,6 +95,10 @@ impl SdlMedia {
             key_states: KeyStates::new(HashMap::new()),
             mouse_position: Vector2::new(0, 0),
             mouse_button_states: KeyStates::new(HashMap::new()),
+            last_physical_mouse_position: Vector2::new(0, 0),
+            controller_subsystem: controller_subsystem,
+            game_controller: None,
+            controller: Controller::new(),
             initial_width: width,
             initial_height: height,
             scale_x: 1f32,
@@ -120,13 +141,30 @@ impl Media for SdlMedia {
         self.keys_pressed =
             event_pump.keyboard_state().pressed_scancodes().filter_map(Key::from_sdl).collect();
 
+        let mouse_state = event_pump.mouse_state();
+        let physical_mouse_position = Vector2::new(mouse_state.x(), mouse_state.y());
+        if physical_mouse_position != self.last_physical_mouse_position {
+            self.last_physical_mouse_position = physical_mouse_position;
+            self.mouse_position = physical_mouse_position;
+            self.controller.mouse_moved();
+        }
+        let mut mouse_buttons_pressed = determine_pressed_mouse_buttons(&mouse_state);
+
+        self.update_game_controller();
+        if let Some(input) = self.game_controller.as_ref().map(read_controller_input) {
+            let window_size = Vector2::new((self.initial_width as f32 * self.scale_x) as u32,
+                                           (self.initial_height as f32 * self.scale_y) as u32);
+            self.controller.update(&input,
+                                   &mut self.keys_pressed,
+                                   &mut mouse_buttons_pressed,
+                                   &mut self.mouse_position,
+                                   window_size);
+        }
+
         let new_key_states = update_key_states(&self.key_states, &self.keys_pressed);
         self.key_states = new_key_states;
 
-        let mouse_state = event_pump.mouse_state();
-        self.mouse_position = Vector2::new(mouse_state.x(), mouse_state.y());
-
-        let new_mouse_states = update_key_states(&self.mouse_button_states, &determine_pressed_mouse_buttons(&mouse_state));
+        let new_mouse_states = update_key_states(&self.mouse_button_states, &mouse_buttons_pressed);
         self.mouse_button_states = new_mouse_states;
     }
 
@@ -143,6 +181,10 @@ impl Media for SdlMedia {
         &self.mouse_button_states
     }
 
+    fn controller(&self) -> Option<&Controller> {
+        self.game_controller.as_ref().map(|_| &self.controller)
+    }
+
     fn renderer<'a>(&'a mut self) -> &'a mut Renderer {
         &mut self.renderer
     }
@@ -152,6 +194,59 @@ impl Media for SdlMedia {
     }
 }
 
+impl SdlMedia {
+    /// Drops the game controller if it was unplugged, and opens the first one there is if none is open
+    fn update_game_controller(&mut self) {
+        let attached = self.game_controller.as_ref().map(|controller| controller.attached()).unwrap_or(false);
+        if attached {
+            return;
+        }
+
+        let opened = match self.controller_subsystem {
+            Some(ref subsystem) => {
+                let joystick_count = subsystem.num_joysticks().unwrap_or(0);
+                (0..joystick_count)
+                    .filter(|&id| subsystem.is_game_controller(id))
+                    .filter_map(|id| subsystem.open(id).ok())
+                    .next()
+            }
+            None => None,
+        };
+        if let Some(ref controller) = opened {
+            println!("Using game controller \"{}\"", controller.name());
+        }
+        self.game_controller = opened;
+    }
+}
+
+fn read_controller_input(controller: &sdl2::controller::GameController) -> ControllerInput {
+    use sdl2::controller::{Axis, Button};
+
+    let axis = |axis| controller.axis(axis) as f32 / i16::max_value() as f32;
+    let mut input = ControllerInput::new();
+    input.left_stick = (axis(Axis::LeftX), axis(Axis::LeftY));
+    input.right_stick = (axis(Axis::RightX), axis(Axis::RightY));
+    input.left_trigger = axis(Axis::TriggerLeft);
+
+    let buttons = [(Button::A, ControllerButton::A),
+                   (Button::B, ControllerButton::B),
+                   (Button::X, ControllerButton::X),
+                   (Button::Y, ControllerButton::Y),
+                   (Button::Back, ControllerButton::Back),
+                   (Button::LeftShoulder, ControllerButton::LeftShoulder),
+                   (Button::RightShoulder, ControllerButton::RightShoulder),
+                   (Button::DPadUp, ControllerButton::DPadUp),
+                   (Button::DPadDown, ControllerButton::DPadDown),
+                   (Button::DPadLeft, ControllerButton::DPadLeft),
+                   (Button::DPadRight, ControllerButton::DPadRight)];
+    for &(sdl_button, button) in &buttons {
+        if controller.button(sdl_button) {
+            input.buttons.insert(button);
+        }
+    }
+    input
+}
+
 fn update_key_states<K: Eq + Hash + Copy>(key_states: &KeyStates<K>,
                                           pressed_keys: &HashSet<K>)
                                           -> KeyStates<K> {
@@ -19,27 +19,101 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use error::Result;
+use error::{ErrorKind, Result};
+use font;
 
 use nalgebra::Vector2;
 
 use sdl2;
-use texture::{self, SdlTexture, Texture};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use sdl_types::{sdl_color, sdl_rect};
+use texture::{self, Texture};
 use types::{Color, Rect};
 
-// Separate so that it's not exported with the crate
-pub trait SdlRenderer {
-    fn create_texture_from_surface(&mut self, surface: sdl2::surface::Surface) -> Result<Texture>;
+/// A copy of what was rendered, as rows of RGBA pixels starting at the top left
+#[derive(Clone)]
+pub struct CapturedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
 }
 
-pub struct Renderer {
+/// Everything the game draws goes through this, so that it doesn't matter what's drawing it.
+/// Anything that isn't on the screen or text is drawn relative to the camera position.
+pub trait Renderer {
+    /// Shows everything rendered since the last call, then clears it for the next frame
+    fn present(&mut self);
+
+    /// Reads back everything rendered since the last `present`, so it must be called before it
+    fn read_pixels(&self) -> Result<CapturedFrame>;
+
+    fn viewport_size(&self) -> Vector2<u32>;
+
+    /// Uploads an image for drawing with `render_texture`. The pixels are packed RGBA8888 values
+    /// in native byte order, row by row from the top left (see `TextureBuilder`).
+    fn create_texture(&mut self, width: u32, height: u32, pixels: Vec<u8>) -> Result<Texture>;
+
+    fn camera_position(&self) -> Vector2<i32>;
+    fn set_camera_position(&mut self, position: &Vector2<i32>);
+
+    fn render_texture(&mut self,
+                      texture: &Texture,
+                      src_rect: Option<Rect>,
+                      dst_rect: Rect,
+                      flip_horizontal: bool,
+                      flip_vertical: bool);
+
+    /// Draws a whole texture on the screen, scaled to fit the rectangle; the camera position
+    /// doesn't affect it
+    fn render_screen_texture(&mut self, texture: &Texture, dst_rect: Rect);
+
+    fn set_render_color(&mut self, color: Color);
+
+    fn render_rect(&mut self, rect: Rect);
+
+    /// Fills a rectangle on the screen; the camera position doesn't affect it
+    fn fill_screen_rect(&mut self, rect: Rect);
+
+    /// Draws a line on the screen; the camera position doesn't affect it
+    fn render_screen_line(&mut self, first: Vector2<i32>, second: Vector2<i32>);
+
+    fn render_line(&mut self, first: Vector2<i32>, second: Vector2<i32>) {
+        let camera_position = self.camera_position();
+        self.render_screen_line(first - camera_position, second - camera_position);
+    }
+
+    /// Draws text on the screen (ignoring the camera position) with the built-in font, where each
+    /// font pixel is `scale` pixels wide
+    fn render_text(&mut self, text: &str, position: &Vector2<i32>, scale: u32, color: Color) {
+        self.set_render_color(color);
+        let mut x = position.x;
+        for c in text.chars() {
+            for (col, row) in font::glyph_pixels(c) {
+                self.fill_screen_rect(Rect::of(x + (col * scale) as i32,
+                                               position.y + (row * scale) as i32,
+                                               scale as i32,
+                                               scale as i32));
+            }
+            x += font::advance(scale) as i32;
+        }
+    }
+
+    /// Draws text on the map with the built-in font; unlike `render_text`, it moves with the camera
+    fn render_world_text(&mut self, text: &str, position: &Vector2<i32>, scale: u32, color: Color) {
+        let position = *position - self.camera_position();
+        self.render_text(text, &position, scale, color);
+    }
+}
+
+pub struct SdlRenderer {
     camera_pos: Vector2<i32>,
     _video: sdl2::VideoSubsystem,
     renderer: sdl2::render::Renderer<'static>,
 }
 
-impl Renderer {
-    pub fn new(sdl_context: &mut sdl2::Sdl, width: u32, height: u32, title: &str) -> Result<Renderer> {
+impl SdlRenderer {
+    pub fn new(sdl_context: &mut sdl2::Sdl, width: u32, height: u32, title: &str) -> Result<SdlRenderer> {
         let video = try!(sdl_context.video());
         let mut window = try!(video.window(title, width, height)
             .position_centered()
@@ -51,44 +125,86 @@ impl Renderer {
         let renderer = try!(window.renderer().present_vsync().build());
         println!("Renderer initialized with {:#?}", renderer.info());
 
-        Ok(Renderer {
+        Ok(SdlRenderer {
             camera_pos: Vector2::new(0, 0),
             _video: video,
             renderer: renderer,
         })
     }
 
-    pub fn present(&mut self) {
+    pub fn set_scale(&mut self, scale_x: f32, scale_y: f32) {
+        self.renderer.set_scale(scale_x, scale_y).expect("set render scale");
+    }
+}
+
+impl Renderer for SdlRenderer {
+    fn present(&mut self) {
         self.set_render_color(Color::rgba(0, 0, 0, 0));
         self.renderer.present();
         self.renderer.clear();
     }
 
-    pub fn viewport_size(&self) -> Vector2<u32> {
+    fn read_pixels(&self) -> Result<CapturedFrame> {
+        let (width, height) = try!(self.renderer
+            .output_size()
+            .map_err(|err| ErrorKind::ReadPixelsFailed(err)));
+
+        // SDL's packed formats are in native byte order, so the one that has R, G, B, A bytes in
+        // memory order depends on the platform
+        let format = if cfg!(target_endian = "little") {
+            PixelFormatEnum::ABGR8888
+        } else {
+            PixelFormatEnum::RGBA8888
+        };
+        let pixels = try!(self.renderer
+            .read_pixels(None, format)
+            .map_err(|err| ErrorKind::ReadPixelsFailed(err)));
+        Ok(CapturedFrame {
+            width: width,
+            height: height,
+            pixels: pixels,
+        })
+    }
+
+    fn viewport_size(&self) -> Vector2<u32> {
         let size = self.renderer.window().unwrap().size();
         Vector2::new(size.0, size.1)
     }
 
-    pub fn set_scale(&mut self, scale_x: f32, scale_y: f32) {
-        self.renderer.set_scale(scale_x, scale_y).expect("set render scale");
+    fn create_texture(&mut self, width: u32, height: u32, mut pixels: Vec<u8>) -> Result<Texture> {
+        let surface = try!(Surface::from_data(&mut pixels,
+                                              width,
+                                              height,
+                                              4 * width,
+                                              PixelFormatEnum::RGBA8888));
+        let sdl_texture = try!(self.renderer.create_texture_from_surface(surface));
+        Ok(texture::create_texture(sdl_texture, width, height))
+    }
+
+    fn camera_position(&self) -> Vector2<i32> {
+        self.camera_pos
     }
 
-    pub fn set_camera_position(&mut self, position: &Vector2<i32>) {
+    fn set_camera_position(&mut self, position: &Vector2<i32>) {
         self.camera_pos = *position;
     }
 
-    pub fn render_texture(&mut self,
-                          texture: &Texture,
-                          src_rect: Option<Rect>,
-                          mut dst_rect: Rect,
-                          flip_horizontal: bool,
-                          flip_vertical: bool) {
+    fn render_texture(&mut self,
+                      texture: &Texture,
+                      src_rect: Option<Rect>,
+                      mut dst_rect: Rect,
+                      flip_horizontal: bool,
+                      flip_vertical: bool) {
+        let sdl_texture = match sdl_texture(texture) {
+            Some(sdl_texture) => sdl_texture,
+            None => return,
+        };
         dst_rect.x -= self.camera_pos.x;
         dst_rect.y -= self.camera_pos.y;
         self.renderer
-            .copy_ex(texture.sdl_texture(),
-                     src_rect.map(|r| r.into()),
-                     Some(dst_rect.into()),
+            .copy_ex(sdl_texture,
+                     src_rect.map(sdl_rect),
+                     Some(sdl_rect(dst_rect)),
                      0.0,
                      None,
                      flip_horizontal,
@@ -98,21 +214,39 @@ impl Renderer {
             });
     }
 
-    pub fn set_render_color(&mut self, color: Color) {
-        self.renderer.set_draw_color(color.into());
+    fn render_screen_texture(&mut self, texture: &Texture, dst_rect: Rect) {
+        let sdl_texture = match sdl_texture(texture) {
+            Some(sdl_texture) => sdl_texture,
+            None => return,
+        };
+        self.renderer
+            .copy_ex(sdl_texture,
+                     None,
+                     Some(sdl_rect(dst_rect)),
+                     0.0,
+                     None,
+                     false,
+                     false)
+            .unwrap_or_else(|err| {
+                println!("Failed to render texture: {}", err);
+            });
     }
 
-    pub fn render_rect(&mut self, mut rect: Rect) {
+    fn set_render_color(&mut self, color: Color) {
+        self.renderer.set_draw_color(sdl_color(color));
+    }
+
+    fn render_rect(&mut self, mut rect: Rect) {
         rect.x -= self.camera_pos.x;
         rect.y -= self.camera_pos.y;
-        self.renderer.draw_rect(rect.into()).expect("Failed to draw rect");
+        self.renderer.draw_rect(sdl_rect(rect)).expect("Failed to draw rect");
+    }
+
+    fn fill_screen_rect(&mut self, rect: Rect) {
+        self.renderer.fill_rect(sdl_rect(rect)).expect("Failed to fill rect");
     }
 
-    pub fn render_line(&mut self, mut first: Vector2<i32>, mut second: Vector2<i32>) {
-        first.x -= self.camera_pos.x;
-        first.y -= self.camera_pos.y;
-        second.x -= self.camera_pos.x;
-        second.y -= self.camera_pos.y;
+    fn render_screen_line(&mut self, first: Vector2<i32>, second: Vector2<i32>) {
         self.renderer
             .draw_line(sdl2::rect::Point::new(first.x, first.y),
                        sdl2::rect::Point::new(second.x, second.y))
@@ -120,10 +254,11 @@ impl Renderer {
     }
 }
 
-impl SdlRenderer for Renderer {
-    fn create_texture_from_surface(&mut self, surface: sdl2::surface::Surface) -> Result<Texture> {
-        let (width, height) = (surface.width(), surface.height());
-        let sdl_texture = try!(self.renderer.create_texture_from_surface(surface));
-        Ok(texture::create_texture(sdl_texture, width, height))
+/// The SDL texture that one of `SdlRenderer`'s textures was uploaded to
+fn sdl_texture(texture: &Texture) -> Option<&sdl2::render::Texture> {
+    let sdl_texture = texture::backend_data::<sdl2::render::Texture>(texture);
+    if sdl_texture.is_none() {
+        println!("Failed to render texture: it wasn't created by SDL");
     }
+    sdl_texture
 }
@@ -19,31 +19,26 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use sdl2;
+use std::any::Any;
 
+/// An image uploaded by a `Renderer`, which only that renderer knows how to draw
 pub struct Texture {
     pub width: u32,
     pub height: u32,
-    texture: sdl2::render::Texture,
+    data: Box<Any>,
 }
 
 // TODO: Haven't quite figured out how to make a new method on Texture that is only exposed
 // to other members of the crate (but not outside of the crate)
-pub fn create_texture(sdl_texture: sdl2::render::Texture, width: u32, height: u32) -> Texture {
+pub fn create_texture<T: Any>(data: T, width: u32, height: u32) -> Texture {
     Texture {
         width: width,
         height: height,
-        texture: sdl_texture,
+        data: Box::new(data),
     }
 }
 
-// Separate so that it's not exported with the crate
-pub trait SdlTexture {
-    fn sdl_texture<'a>(&'a self) -> &'a sdl2::render::Texture;
-}
-
-impl SdlTexture for Texture {
-    fn sdl_texture<'a>(&'a self) -> &'a sdl2::render::Texture {
-        &self.texture
-    }
+/// What the renderer uploaded the texture to, or `None` if it was a different kind of renderer
+pub fn backend_data<T: Any>(texture: &Texture) -> Option<&T> {
+    texture.data.downcast_ref::<T>()
 }
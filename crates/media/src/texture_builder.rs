@@ -19,80 +19,65 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use error::{Error, Result};
-use renderer::{Renderer, SdlRenderer};
-use sdl2::pixels::PixelFormatEnum;
+use error::{Error, ErrorKind, Result};
+use renderer::Renderer;
 
-use sdl2::surface::Surface;
-
-use std::io::{self, Write};
+use std::cmp;
 use std::mem;
 use texture::Texture;
 use types::Rect;
 
+/// Composites palette-indexed shapes into an RGBA image, then uploads it with a `Renderer`.
+/// Color index 0 is transparent, so it leaves whatever was already there.
 pub struct TextureBuilder<'a> {
-    surface: Surface<'static>,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
     palette: &'a [u32],
     error: Option<Error>,
 }
 
-fn to_rgba(palette: &[u32], src_pixels: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut dst_pixels = io::Cursor::new(vec![0u8; width * height * 4]);
-
-    for y in 0..height {
-        for x in 0..width {
-            let src_index = y * width + x;
-            let color_index = src_pixels[src_index] as usize;
-            if color_index > 0 {
-                let color = palette[color_index];
-                let color_bytes = unsafe { mem::transmute::<u32, [u8; 4]>(color) };
-                try!(dst_pixels.write(&color_bytes));
-            } else {
-                try!(dst_pixels.write(&[0, 0, 0, 0u8]));
-            }
-        }
-    }
-
-    Ok(dst_pixels.into_inner())
-}
-
 impl<'a> TextureBuilder<'a> {
     pub fn new(width: u32, height: u32, palette: &'a [u32]) -> Result<TextureBuilder<'a>> {
         Ok(TextureBuilder {
-            surface: try!(Surface::new(width, height, PixelFormatEnum::RGBA8888)),
+            width: width,
+            height: height,
+            pixels: vec![0u8; (width * height * 4) as usize],
             palette: palette,
             error: None,
         })
     }
 
+    /// Copies the part of the shape from `src_rect`'s position up to its size, which is the size
+    /// of the whole shape, to `dst_rect`'s position; anything outside of the texture is left out
     pub fn blit_shape(mut self, pixel_buffer: &[u8], src_rect: Rect, dst_rect: Rect) -> Self {
-        let mut pixels = match to_rgba(&self.palette,
-                                       pixel_buffer,
-                                       src_rect.w as usize,
-                                       src_rect.h as usize) {
-            Ok(pixels) => pixels,
-            Err(err) => {
-                self.error = Some(err);
-                return self;
-            }
-        };
-
-        let surf_result = Surface::from_data(&mut pixels,
-                                             src_rect.w as u32,
-                                             src_rect.h as u32,
-                                             4 * (src_rect.w as u32),
-                                             PixelFormatEnum::RGBA8888);
-        if surf_result.is_err() {
-            self.error = Some(surf_result.err().unwrap().into());
+        let expected = (cmp::max(src_rect.w, 0) * cmp::max(src_rect.h, 0)) as usize;
+        if pixel_buffer.len() < expected {
+            self.error = Some(ErrorKind::MissingPixels(expected, pixel_buffer.len()).into());
             return self;
         }
 
-        let src_surface = surf_result.ok().unwrap();
-        let blit_result = src_surface.blit(Some(src_rect.into()),
-                                           &mut self.surface,
-                                           Some(dst_rect.into()));
-        if blit_result.is_err() {
-            self.error = Some(blit_result.err().unwrap().into());
+        let src_x = cmp::max(src_rect.x, 0);
+        let src_y = cmp::max(src_rect.y, 0);
+        for y in src_y..src_rect.h {
+            let dst_y = dst_rect.y + y - src_y;
+            if dst_y < 0 || dst_y >= self.height as i32 {
+                continue;
+            }
+            for x in src_x..src_rect.w {
+                let dst_x = dst_rect.x + x - src_x;
+                if dst_x < 0 || dst_x >= self.width as i32 {
+                    continue;
+                }
+
+                let color_index = pixel_buffer[(y * src_rect.w + x) as usize] as usize;
+                if color_index > 0 {
+                    let color = self.palette[color_index];
+                    let color_bytes = unsafe { mem::transmute::<u32, [u8; 4]>(color) };
+                    let offset = ((dst_y as u32 * self.width + dst_x as u32) * 4) as usize;
+                    self.pixels[offset..offset + 4].copy_from_slice(&color_bytes);
+                }
+            }
         }
 
         self
@@ -103,6 +88,45 @@ impl<'a> TextureBuilder<'a> {
             return Err(self.error.unwrap());
         }
 
-        renderer.create_texture_from_surface(self.surface)
+        renderer.create_texture(self.width, self.height, self.pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextureBuilder;
+    use std::mem;
+    use types::Rect;
+
+    fn pixel(builder: &TextureBuilder, x: u32, y: u32) -> u32 {
+        let offset = ((y * builder.width + x) * 4) as usize;
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&builder.pixels[offset..offset + 4]);
+        unsafe { mem::transmute::<[u8; 4], u32>(bytes) }
+    }
+
+    #[test]
+    fn test_blit_shape() {
+        let palette = [0, 0x11111111, 0x22222222];
+        // A 2x2 shape with a transparent corner, drawn over another one that's partly off the texture
+        let builder = TextureBuilder::new(3, 2, &palette)
+            .unwrap()
+            .blit_shape(&[2, 2, 2, 2], Rect::of(0, 0, 2, 2), Rect::of(2, 0, 2, 2))
+            .blit_shape(&[1, 0, 1, 1], Rect::of(0, 0, 2, 2), Rect::of(1, 0, 2, 2));
+        assert!(builder.error.is_none());
+        assert_eq!(0, pixel(&builder, 0, 0));
+        assert_eq!(0x11111111, pixel(&builder, 1, 0));
+        assert_eq!(0x22222222, pixel(&builder, 2, 0));
+        assert_eq!(0x11111111, pixel(&builder, 1, 1));
+        assert_eq!(0x11111111, pixel(&builder, 2, 1));
+    }
+
+    #[test]
+    fn test_blit_shape_missing_pixels() {
+        let palette = [0, 0x11111111];
+        let builder = TextureBuilder::new(2, 2, &palette)
+            .unwrap()
+            .blit_shape(&[1, 1, 1], Rect::of(0, 0, 2, 2), Rect::of(0, 0, 2, 2));
+        assert!(builder.error.is_some());
     }
 }
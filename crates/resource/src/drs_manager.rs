@@ -21,53 +21,71 @@
 //
 
 
-use drs::DrsFile;
-use error::Result;
+use drs::{DrsFile, DrsFileType};
+use error::{ChainErr, Result};
 use game_dir::GameDir;
+use identifier::DrsKey;
+use mod_overrides::ModOverrides;
+use std::borrow::Cow;
 use std::cell::RefCell;
 
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 use std::rc::Rc;
 
-#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
-pub enum DrsKey {
-    Border,
-    Graphics,
-    Interfac,
-    Sounds,
-    Terrain,
-}
-
-impl DrsKey {
-    pub fn path(&self) -> &'static str {
-        use self::DrsKey::*;
-        match *self {
-            Border => "data/border.drs",
-            Graphics => "data/graphics.drs",
-            Interfac => "data/interfac.drs",
-            Sounds => "data/sounds.drs",
-            Terrain => "data/terrain.drs",
-        }
-    }
-}
-
 pub struct DrsManager {
     game_dir: GameDir,
+    overrides: ModOverrides,
     resources: HashMap<DrsKey, DrsFile>,
 }
 
 pub type DrsManagerRef = Rc<RefCell<DrsManager>>;
 
 impl DrsManager {
-    pub fn new(game_dir: &GameDir) -> DrsManagerRef {
+    pub fn new(game_dir: &GameDir, overrides: &ModOverrides) -> DrsManagerRef {
         Rc::new(RefCell::new(DrsManager {
             game_dir: game_dir.clone(),
+            overrides: overrides.clone(),
             resources: HashMap::new(),
         }))
     }
 
-    pub fn get<'a>(&'a self, drs_key: DrsKey) -> &'a DrsFile {
-        self.resources.get(&drs_key).unwrap()
+    /// The loaded archive, or None if it's an optional archive that isn't in the game data directory
+    pub fn get<'a>(&'a self, drs_key: DrsKey) -> Option<&'a DrsFile> {
+        self.resources.get(&drs_key)
+    }
+
+    pub fn overrides<'a>(&'a self) -> &'a ModOverrides {
+        &self.overrides
+    }
+
+    /// Contents of a file in one of the archives, or a mod's replacement for it if there is one
+    pub fn find_file_contents<'a>(&'a self,
+                                  drs_key: DrsKey,
+                                  file_type: DrsFileType,
+                                  file_id: u32)
+                                  -> Result<Option<Cow<'a, [u8]>>> {
+        if let Some(file_name) = self.overrides.find_drs_file(drs_key, file_type, file_id) {
+            return read_loose_file(&file_name).map(|contents| Some(Cow::Owned(contents)));
+        }
+        Ok(self.get(drs_key)
+            .and_then(|drs| drs.find_table(file_type))
+            .and_then(|table| table.find_file_contents(file_id))
+            .map(|contents| Cow::Borrowed(&contents[..])))
+    }
+
+    /// IDs of every file of the given type in one of the archives, including ones that only mods add
+    pub fn file_ids(&self, drs_key: DrsKey, file_type: DrsFileType) -> Vec<u32> {
+        let mut file_ids: Vec<u32> = match self.get(drs_key).and_then(|drs| drs.find_table(file_type)) {
+            Some(table) => table.entries.iter().map(|entry| entry.file_id as u32).collect(),
+            None => Vec::new(),
+        };
+        file_ids.extend(self.overrides.drs_file_ids(drs_key, file_type));
+        file_ids.sort();
+        file_ids.dedup();
+        file_ids
     }
 
     pub fn preload(&mut self) -> Result<()> {
@@ -80,10 +98,25 @@ impl DrsManager {
     }
 
     fn preload_drs(&mut self, drs_key: DrsKey) -> Result<()> {
-        let file_name = try!(self.game_dir.find_file(drs_key.path()));
+        let file_name = match self.game_dir.find_file(drs_key.path()) {
+            Ok(file_name) => file_name,
+            Err(_) if drs_key.is_optional() => {
+                println!("{:?} not found; its shapes and sounds will be placeholders", drs_key.path());
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
         println!("Loading {:?}...", file_name);
         let drs = try!(DrsFile::read_from_file(file_name));
         self.resources.insert(drs_key, drs);
         Ok(())
     }
 }
+
+fn read_loose_file(file_name: &Path) -> Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    try!(try!(File::open(file_name).chain_err(|| format!("Failed to open {:?}", file_name)))
+        .read_to_end(&mut contents)
+        .chain_err(|| format!("Failed to read {:?}", file_name)));
+    Ok(contents)
+}
@@ -45,6 +45,10 @@ error_chain! {
     }
 
     errors {
+        DrsMissing(drs_key: DrsKey) {
+            description("DRS file missing")
+            display("\"{}\" wasn't found in the game data directory", drs_key.path())
+        }
         InterfacBinaryTableMissing {
             description("interfac.drs is missing its binary table")
             display("interfac.drs is missing its binary table")
@@ -69,5 +73,9 @@ error_chain! {
             description("Game data file not found")
             display("Game data file not found: {:?}", file_name)
         }
+        ModNotFound(name: String, dir: PathBuf) {
+            description("Enabled mod not found")
+            display("Mod \"{}\" is enabled but {:?} doesn't exist", name, dir)
+        }
     }
 }
@@ -19,12 +19,24 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use drs_manager::DrsKey;
 use error::{ErrorKind, Result, Error, ChainErr};
 
 use std::ascii::AsciiExt;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
+/// Files that even the demo version has, and that the game can't run without
+const REQUIRED_FILES: &'static [&'static str] = &["language.dll",
+                                                   "data/empires.dat",
+                                                   "data/interfac.drs",
+                                                   "data/tileedge.dat"];
+
+const OPTIONAL_DRS_FILES: &'static [DrsKey] = &[DrsKey::Border,
+                                                DrsKey::Graphics,
+                                                DrsKey::Sounds,
+                                                DrsKey::Terrain];
+
 #[derive(Clone)]
 pub struct GameDir {
     dir: PathBuf,
@@ -44,51 +56,63 @@ impl GameDir {
         }
 
         let game_dir = GameDir { dir: dir.to_path_buf() };
-        for file_name in &["language.dll",
-                           "data/border.drs",
-                           "data/empires.dat",
-                           "data/graphics.drs",
-                           "data/interfac.drs",
-                           "data/sounds.drs",
-                           "data/terrain.drs",
-                           "data/tileedge.dat"] {
+        for file_name in REQUIRED_FILES {
             if let Ok(actual_file_name) = game_dir.find_file(file_name) {
                 println!("Found {:?} at {:?}", file_name, actual_file_name);
             } else {
                 return Err(error(dir, &format!("Failed to find {}", file_name)));
             }
         }
+        for drs_key in OPTIONAL_DRS_FILES {
+            if let Ok(actual_file_name) = game_dir.find_file(drs_key.path()) {
+                println!("Found {:?} at {:?}", drs_key.path(), actual_file_name);
+            } else {
+                println!("Didn't find {:?} (this is expected for the demo version)", drs_key.path());
+            }
+        }
         Ok(game_dir)
     }
 
+    /// Whether the directory has all of the files that the game can't run without
+    pub fn is_game_dir<P: AsRef<Path>>(dir: P) -> bool {
+        let dir = dir.as_ref();
+        dir.is_dir() && REQUIRED_FILES.iter().all(|file_name| find_file_ignoring_case(dir, file_name).is_ok())
+    }
+
     /// Find a file in the game data directory even if the requested case doesn't match
     /// the case of the file name on the file system (for case-sensitive file systems)
     pub fn find_file<P: AsRef<Path>>(&self, file_name: P) -> Result<PathBuf> {
-        let mut full_path = self.dir.clone();
-        for component in file_name.as_ref().components() {
-            if let Component::Normal(component_name) = component {
-                let component_name = component_name.to_string_lossy();
-                let mut found = false;
-                for dir_entry in try!(fs::read_dir(&full_path)
-                    .chain_err(|| "Failed to traverse game data directory")) {
-                    let dir_entry =
-                        try!(dir_entry.chain_err(|| "Failed to read directory entry in game data directory"));
-                    if component_name.eq_ignore_ascii_case(&dir_entry.path()
-                        .file_name()
-                        .unwrap()
-                        .to_string_lossy()) {
-                        full_path = full_path.join(&*dir_entry.path().file_name().unwrap().to_string_lossy());
-                        found = true;
-                        break;
-                    }
-                }
-                if !found {
-                    return Err(ErrorKind::GameDataFileNotFound(file_name.as_ref().to_path_buf()).into());
+        find_file_ignoring_case(&self.dir, file_name)
+    }
+}
+
+/// Find a file relative to `dir` even if the requested case doesn't match the case of the
+/// file name on the file system (game data copied off of a Windows machine rarely matches)
+pub fn find_file_ignoring_case<P: AsRef<Path>>(dir: &Path, file_name: P) -> Result<PathBuf> {
+    let mut full_path = dir.to_path_buf();
+    for component in file_name.as_ref().components() {
+        if let Component::Normal(component_name) = component {
+            let component_name = component_name.to_string_lossy();
+            let mut found = false;
+            for dir_entry in try!(fs::read_dir(&full_path)
+                .chain_err(|| "Failed to traverse game data directory")) {
+                let dir_entry =
+                    try!(dir_entry.chain_err(|| "Failed to read directory entry in game data directory"));
+                if component_name.eq_ignore_ascii_case(&dir_entry.path()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()) {
+                    full_path = full_path.join(&*dir_entry.path().file_name().unwrap().to_string_lossy());
+                    found = true;
+                    break;
                 }
             }
+            if !found {
+                return Err(ErrorKind::GameDataFileNotFound(file_name.as_ref().to_path_buf()).into());
+            }
         }
-        Ok(full_path)
     }
+    Ok(full_path)
 }
 
 fn error(dir: &Path, msg: &str) -> Error {
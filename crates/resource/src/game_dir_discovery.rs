@@ -0,0 +1,234 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use error::{ChainErr, Result};
+use game_dir::{GameDir, find_file_ignoring_case};
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the file in the config directory that remembers where the game data is
+pub const SAVED_GAME_DIR_FILE_NAME: &'static str = "game_dir.txt";
+
+/// Directories that the game gets installed to, relative to an install root such as Program Files
+const INSTALL_DIR_NAMES: &'static [&'static str] = &["Microsoft Games/Age of Empires",
+                                                      "Age of Empires",
+                                                      "GOG Games/Age of Empires",
+                                                      "GOG Galaxy/Games/Age of Empires",
+                                                      "Steam/steamapps/common/Age of Empires"];
+
+/// A place the game data could be: `relative` is resolved against `root` ignoring case,
+/// since installs copied off of Windows rarely match the expected case
+#[derive(Clone, Debug, PartialEq)]
+struct Candidate {
+    root: PathBuf,
+    relative: PathBuf,
+}
+
+impl Candidate {
+    fn new<P: Into<PathBuf>, R: Into<PathBuf>>(root: P, relative: R) -> Candidate {
+        Candidate {
+            root: root.into(),
+            relative: relative.into(),
+        }
+    }
+
+    fn resolve(&self) -> Option<PathBuf> {
+        if !self.root.is_dir() {
+            return None;
+        }
+        find_file_ignoring_case(&self.root, &self.relative).ok()
+    }
+}
+
+/// Looks for an existing Age of Empires install in the usual places for this platform: install
+/// directories on Windows, GOG and Steam libraries, Wine prefixes, and mounted game CDs.
+/// Returns the first directory that has all of the required game data in it.
+pub fn detect_game_dir() -> Option<PathBuf> {
+    for candidate in candidates(&|name| env::var_os(name).map(PathBuf::from)) {
+        if let Some(dir) = candidate.resolve() {
+            // Installs have the game data at the top level, but the CD has it in a "game" directory
+            if GameDir::is_game_dir(&dir) {
+                return Some(dir);
+            }
+            if let Ok(game_dir) = find_file_ignoring_case(&dir, "game") {
+                if GameDir::is_game_dir(&game_dir) {
+                    return Some(game_dir);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The game data directory that was saved by `save_game_dir`, if there is one
+pub fn saved_game_dir() -> Option<PathBuf> {
+    let file_name = match config_dir() {
+        Some(config_dir) => config_dir.join(SAVED_GAME_DIR_FILE_NAME),
+        None => return None,
+    };
+    let mut contents = String::new();
+    if let Ok(mut file) = File::open(&file_name) {
+        if file.read_to_string(&mut contents).is_ok() {
+            let dir = contents.trim();
+            if !dir.is_empty() {
+                return Some(PathBuf::from(dir));
+            }
+        }
+    }
+    None
+}
+
+/// Remembers the game data directory for the next time the game runs, and returns the
+/// name of the file it was saved in
+pub fn save_game_dir(dir: &Path) -> Result<PathBuf> {
+    let config_dir = try!(config_dir().ok_or("Failed to find a directory to save settings in"));
+    try!(fs::create_dir_all(&config_dir).chain_err(|| format!("Failed to create {:?}", config_dir)));
+
+    let file_name = config_dir.join(SAVED_GAME_DIR_FILE_NAME);
+    let mut file = try!(File::create(&file_name).chain_err(|| format!("Failed to create {:?}", file_name)));
+    try!(writeln!(file, "{}", dir.to_string_lossy())
+        .chain_err(|| format!("Failed to write {:?}", file_name)));
+    Ok(file_name)
+}
+
+/// Where Chariot keeps its settings on this platform
+fn config_dir() -> Option<PathBuf> {
+    let var = |name: &str| env::var_os(name).map(PathBuf::from);
+    if cfg!(windows) {
+        var("APPDATA").map(|dir| dir.join("Chariot"))
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|dir| dir.join("Library/Application Support/Chariot"))
+    } else {
+        var("XDG_CONFIG_HOME")
+            .or_else(|| var("HOME").map(|dir| dir.join(".config")))
+            .map(|dir| dir.join("chariot"))
+    }
+}
+
+/// Every place worth looking for the game data, most likely first. `var` looks up environment
+/// variables (it's a parameter so that tests don't depend on the machine they run on).
+fn candidates(var: &Fn(&str) -> Option<PathBuf>) -> Vec<Candidate> {
+    let mut install_roots = Vec::new();
+    let mut cd_dirs = Vec::new();
+    if cfg!(windows) {
+        for name in &["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = var(name) {
+                install_roots.push(dir);
+            }
+        }
+        install_roots.push(PathBuf::from("C:\\Program Files (x86)"));
+        install_roots.push(PathBuf::from("C:\\Program Files"));
+        install_roots.push(PathBuf::from("C:\\"));
+        for letter in "DEFGH".chars() {
+            cd_dirs.push(Candidate::new(format!("{}:\\", letter), "game"));
+        }
+    } else {
+        let home = var("HOME");
+
+        // Wine prefixes, starting with whichever one is active
+        let mut wine_prefixes = Vec::new();
+        if let Some(prefix) = var("WINEPREFIX") {
+            wine_prefixes.push(prefix);
+        }
+        if let Some(ref home) = home {
+            wine_prefixes.push(home.join(".wine"));
+        }
+        for prefix in &wine_prefixes {
+            install_roots.push(prefix.join("drive_c/Program Files (x86)"));
+            install_roots.push(prefix.join("drive_c/Program Files"));
+            install_roots.push(prefix.join("drive_c"));
+        }
+
+        if let Some(ref home) = home {
+            install_roots.push(home.join(".steam/steam/steamapps/common"));
+            install_roots.push(home.join(".local/share/Steam/steamapps/common"));
+            install_roots.push(home.join("Library/Application Support/Steam/steamapps/common"));
+            install_roots.push(home.join("GOG Games"));
+        }
+
+        if let Some(user) = var("USER") {
+            cd_dirs.push(Candidate::new(Path::new("/media").join(&user), "AOE/game"));
+            cd_dirs.push(Candidate::new(Path::new("/run/media").join(&user), "AOE/game"));
+        }
+        cd_dirs.push(Candidate::new("/media", "AOE/game"));
+        cd_dirs.push(Candidate::new("/media", "cdrom/game"));
+        cd_dirs.push(Candidate::new("/mnt", "cdrom/game"));
+        cd_dirs.push(Candidate::new("/Volumes", "AOE/game"));
+    }
+
+    let mut candidates = Vec::new();
+    for root in &install_roots {
+        for install_dir_name in INSTALL_DIR_NAMES {
+            candidates.push(Candidate::new(root.clone(), *install_dir_name));
+        }
+    }
+    candidates.extend(cd_dirs);
+    candidates.dedup();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Candidate, candidates};
+    use game_dir::GameDir;
+
+    use std::path::PathBuf;
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_candidates_include_wine_prefix_and_steam() {
+        let var = |name: &str| match name {
+            "HOME" => Some(PathBuf::from("/home/someone")),
+            "WINEPREFIX" => Some(PathBuf::from("/opt/prefix")),
+            _ => None,
+        };
+        let candidates = candidates(&var);
+        assert_eq!(Candidate::new("/opt/prefix/drive_c/Program Files (x86)",
+                                  "Microsoft Games/Age of Empires"),
+                   candidates[0]);
+        assert!(candidates.contains(&Candidate::new("/home/someone/.wine/drive_c/Program Files",
+                                                    "Age of Empires")));
+        assert!(candidates.contains(&Candidate::new("/home/someone/.steam/steam/steamapps/common",
+                                                    "Age of Empires")));
+        assert_eq!(&Candidate::new("/Volumes", "AOE/game"), candidates.last().unwrap());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_candidates_include_program_files() {
+        let var = |name: &str| match name {
+            "ProgramFiles" => Some(PathBuf::from("E:\\Programs")),
+            _ => None,
+        };
+        let candidates = candidates(&var);
+        assert_eq!(Candidate::new("E:\\Programs", "Microsoft Games/Age of Empires"), candidates[0]);
+        assert!(candidates.contains(&Candidate::new("D:\\", "game")));
+    }
+
+    #[test]
+    fn test_missing_root_does_not_resolve() {
+        assert_eq!(None, Candidate::new("this/directory/does/not/exist", "game").resolve());
+        assert!(!GameDir::is_game_dir("this/directory/does/not/exist"));
+    }
+}
@@ -37,13 +37,20 @@ extern crate chariot_types as types;
 
 mod error;
 mod game_dir;
+mod game_dir_discovery;
 mod drs_manager;
+mod mod_overrides;
+mod player_color_scheme;
 mod shape_manager;
 mod shape_metadata;
 mod render_command;
 
-pub use drs_manager::{DrsKey, DrsManager, DrsManagerRef};
+pub use drs_manager::{DrsManager, DrsManagerRef};
 pub use game_dir::GameDir;
+pub use identifier::DrsKey;
+pub use game_dir_discovery::{SAVED_GAME_DIR_FILE_NAME, detect_game_dir, save_game_dir, saved_game_dir};
+pub use mod_overrides::{MOD_MANIFEST_FILE_NAME, ModManifest, ModManifestEntry, ModOverrides};
+pub use player_color_scheme::{PlayerColorScheme, minimap_palette_index};
 pub use render_command::*;
-pub use shape_manager::{Shape, ShapeKey, ShapeManager, ShapeManagerRef};
+pub use shape_manager::{CompositeKey, Shape, ShapeKey, ShapeManager, ShapeManagerRef};
 pub use shape_metadata::{ShapeMetadata, ShapeMetadataKey, ShapeMetadataStore, ShapeMetadataStoreRef};
@@ -0,0 +1,257 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use drs::DrsFileType;
+use drs_manager::DrsKey;
+use error::{ChainErr, ErrorKind, Result};
+use game_dir::find_file_ignoring_case;
+
+use std::ascii::AsciiExt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Name of the manifest in the mods directory
+pub const MOD_MANIFEST_FILE_NAME: &'static str = "mods.txt";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModManifestEntry {
+    /// Name of the mod's directory inside the mods directory
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Lists the installed mods in priority order; when more than one mod overrides the same file,
+/// the one listed first wins.
+///
+/// One mod per line. A leading `-` disables a mod without having to remove it, and lines starting
+/// with `#` are comments:
+///
+/// ```text
+/// # Balance patch first so that its empires.dat wins
+/// balance-patch
+/// hd-units
+/// -old-experiment
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModManifest {
+    entries: Vec<ModManifestEntry>,
+}
+
+impl ModManifest {
+    pub fn new() -> ModManifest {
+        ModManifest { entries: Vec::new() }
+    }
+
+    pub fn parse(text: &str) -> ModManifest {
+        let mut manifest = ModManifest::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, enabled) = if line.starts_with('-') {
+                (line[1..].trim(), false)
+            } else {
+                (line, true)
+            };
+            manifest.entries.push(ModManifestEntry {
+                name: name.to_string(),
+                enabled: enabled,
+            });
+        }
+        manifest
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            if !entry.enabled {
+                text.push('-');
+            }
+            text.push_str(&entry.name);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn entries(&self) -> &[ModManifestEntry] {
+        &self.entries
+    }
+
+    /// Names of the enabled mods, highest priority first
+    pub fn enabled_mods(&self) -> Vec<&str> {
+        self.entries.iter().filter(|e| e.enabled).map(|e| &e.name[..]).collect()
+    }
+
+    /// Returns false if there's no such mod in the manifest
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(entry) => {
+                entry.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The enabled mods' directories, checked in order before falling back to the original game data.
+///
+/// A mod can replace any game data file by putting it at the same relative path (such as
+/// `data/empires.dat` for a balance patch), and can replace individual files inside of a DRS
+/// archive by putting loose files in a directory named after the archive, named after the file ID
+/// (such as `graphics/418.slp` or `sounds/5036.wav`).
+#[derive(Clone, Debug, Default)]
+pub struct ModOverrides {
+    sources: Vec<PathBuf>,
+}
+
+impl ModOverrides {
+    /// No overrides; everything comes from the original game data
+    pub fn none() -> ModOverrides {
+        ModOverrides { sources: Vec::new() }
+    }
+
+    /// Loads the manifest from the given mods directory. Not having a mods directory (or a manifest)
+    /// just means there are no mods, but enabling a mod that isn't there is an error.
+    pub fn load<P: AsRef<Path>>(mods_dir: P) -> Result<ModOverrides> {
+        let mods_dir = mods_dir.as_ref();
+        let manifest_file_name = mods_dir.join(MOD_MANIFEST_FILE_NAME);
+        if !manifest_file_name.exists() {
+            return Ok(ModOverrides::none());
+        }
+
+        let mut text = String::new();
+        try!(try!(File::open(&manifest_file_name).chain_err(|| "Failed to open the mod manifest"))
+            .read_to_string(&mut text)
+            .chain_err(|| "Failed to read the mod manifest"));
+        let manifest = ModManifest::parse(&text);
+
+        let mut sources = Vec::new();
+        for name in manifest.enabled_mods() {
+            let mod_dir = mods_dir.join(name);
+            if !mod_dir.is_dir() {
+                return Err(ErrorKind::ModNotFound(name.to_string(), mod_dir).into());
+            }
+            println!("Using mod \"{}\"", name);
+            sources.push(mod_dir);
+        }
+        Ok(ModOverrides { sources: sources })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// The highest priority mod's replacement for a game data file, if any mod has one
+    pub fn find_data_file<P: AsRef<Path>>(&self, file_name: P) -> Option<PathBuf> {
+        self.sources
+            .iter()
+            .filter_map(|source| find_file_ignoring_case(source, file_name.as_ref()).ok())
+            .next()
+    }
+
+    /// The highest priority mod's replacement for a file inside of a DRS archive, if any mod has one
+    pub fn find_drs_file(&self, drs_key: DrsKey, file_type: DrsFileType, file_id: u32) -> Option<PathBuf> {
+        extension(file_type).and_then(|extension| {
+            self.find_data_file(Path::new(drs_key.name()).join(format!("{}.{}", file_id, extension)))
+        })
+    }
+
+    /// IDs of every file of the given type that some mod puts in the given DRS archive,
+    /// including ones that aren't in the original archive at all
+    pub fn drs_file_ids(&self, drs_key: DrsKey, file_type: DrsFileType) -> Vec<u32> {
+        let extension = match extension(file_type) {
+            Some(extension) => extension,
+            None => return Vec::new(),
+        };
+        let mut file_ids = Vec::new();
+        for source in &self.sources {
+            let drs_dir = match find_file_ignoring_case(source, drs_key.name()) {
+                Ok(drs_dir) => drs_dir,
+                Err(_) => continue,
+            };
+            let dir_entries = match fs::read_dir(drs_dir) {
+                Ok(dir_entries) => dir_entries,
+                Err(_) => continue,
+            };
+            for dir_entry in dir_entries.filter_map(|e| e.ok()) {
+                let path = dir_entry.path();
+                let matches_extension = path.extension()
+                    .map(|e| e.to_string_lossy().eq_ignore_ascii_case(extension))
+                    .unwrap_or(false);
+                if !matches_extension {
+                    continue;
+                }
+                if let Some(file_id) = file_id(&path) {
+                    file_ids.push(file_id);
+                }
+            }
+        }
+        file_ids.sort();
+        file_ids.dedup();
+        file_ids
+    }
+}
+
+fn extension(file_type: DrsFileType) -> Option<&'static str> {
+    match file_type {
+        DrsFileType::Slp => Some("slp"),
+        DrsFileType::Wav => Some("wav"),
+        _ => None,
+    }
+}
+
+fn file_id(path: &Path) -> Option<u32> {
+    path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = ModManifest::parse("# comment\n\nbalance-patch\n  hd-units \n-old-experiment\n");
+        assert_eq!(vec!["balance-patch", "hd-units"], manifest.enabled_mods());
+        assert_eq!(3, manifest.entries().len());
+        assert!(!manifest.entries()[2].enabled);
+        assert_eq!("old-experiment", manifest.entries()[2].name);
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let mut manifest = ModManifest::parse("first\n-second\nthird\n");
+        assert!(manifest.set_enabled("second", true));
+        assert!(manifest.set_enabled("third", false));
+        assert!(!manifest.set_enabled("fourth", true));
+        assert_eq!("first\nsecond\n-third\n", manifest.to_text());
+        assert_eq!(manifest, ModManifest::parse(&manifest.to_text()));
+    }
+
+    #[test]
+    fn test_no_mods_dir() {
+        let overrides = ModOverrides::load("this/directory/does/not/exist").unwrap();
+        assert!(overrides.is_empty());
+        assert_eq!(None, overrides.find_data_file("data/empires.dat"));
+    }
+}
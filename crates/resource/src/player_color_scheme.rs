@@ -0,0 +1,197 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::ops::Range;
+
+/// How many player colors the palette has ramps for
+pub const PLAYER_COLOR_COUNT: usize = 8;
+
+/// Player color pixels in an SLP for player color N are drawn from palette entries 16 * N onwards
+const PLAYER_COLOR_RAMP_STRIDE: usize = 16;
+const PLAYER_COLOR_RAMP_LENGTH: usize = 8;
+
+/// Close enough to the palette's player colors for the HUD, in player color ID order
+const ORIGINAL_COLORS: [(u8, u8, u8); PLAYER_COLOR_COUNT] = [(0, 0, 196),
+                                                             (200, 0, 0),
+                                                             (230, 230, 0),
+                                                             (140, 80, 20),
+                                                             (240, 130, 0),
+                                                             (0, 160, 0),
+                                                             (150, 150, 150),
+                                                             (0, 200, 200)];
+
+/// Saturated colors that are as far apart from each other (and the terrain) as possible
+const HIGH_CONTRAST_COLORS: [(u8, u8, u8); PLAYER_COLOR_COUNT] = [(0, 64, 255),
+                                                                  (255, 0, 0),
+                                                                  (255, 255, 0),
+                                                                  (255, 0, 255),
+                                                                  (255, 255, 255),
+                                                                  (0, 255, 0),
+                                                                  (32, 32, 32),
+                                                                  (0, 255, 255)];
+
+/// The Okabe-Ito palette, which stays distinguishable with the common kinds of color blindness
+const COLORBLIND_SAFE_COLORS: [(u8, u8, u8); PLAYER_COLOR_COUNT] = [(0, 114, 178),
+                                                                    (213, 94, 0),
+                                                                    (240, 228, 66),
+                                                                    (0, 158, 115),
+                                                                    (230, 159, 0),
+                                                                    (86, 180, 233),
+                                                                    (204, 121, 167),
+                                                                    (153, 153, 153)];
+
+/// Which colors the players are drawn in. Anything other than the original scheme replaces the
+/// player color ramps in the palette, so the sprites and the HUD pick it up alike.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlayerColorScheme {
+    Original,
+    HighContrast,
+    ColorblindSafe,
+}
+
+impl PlayerColorScheme {
+    pub fn all() -> &'static [PlayerColorScheme] {
+        use self::PlayerColorScheme::*;
+        static ALL: [PlayerColorScheme; 3] = [Original, HighContrast, ColorblindSafe];
+        &ALL
+    }
+
+    pub fn name(&self) -> &'static str {
+        use self::PlayerColorScheme::*;
+        match *self {
+            Original => "original",
+            HighContrast => "high-contrast",
+            ColorblindSafe => "colorblind-safe",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PlayerColorScheme> {
+        PlayerColorScheme::all().iter().cloned().find(|scheme| scheme.name() == name)
+    }
+
+    /// The color that stands for the given player color ID, such as in graphs and text
+    pub fn color(&self, player_color: usize) -> (u8, u8, u8) {
+        self.colors()[player_color % PLAYER_COLOR_COUNT]
+    }
+
+    /// Replaces the player color ramps in the given palette. Each shade keeps its brightness, so the
+    /// sprites stay shaded the way they were drawn.
+    pub fn remap_palette(&self, palette: &mut [(u8, u8, u8)]) {
+        if *self == PlayerColorScheme::Original {
+            return;
+        }
+        for player_color in 0..PLAYER_COLOR_COUNT {
+            let base = self.color(player_color);
+            for index in ramp(player_color) {
+                if index < palette.len() {
+                    palette[index] = shade(base, palette[index]);
+                }
+            }
+        }
+    }
+
+    fn colors(&self) -> &'static [(u8, u8, u8); PLAYER_COLOR_COUNT] {
+        use self::PlayerColorScheme::*;
+        match *self {
+            Original => &ORIGINAL_COLORS,
+            HighContrast => &HIGH_CONTRAST_COLORS,
+            ColorblindSafe => &COLORBLIND_SAFE_COLORS,
+        }
+    }
+}
+
+/// The palette entry the minimap draws the given player color ID's units in, from the middle of its
+/// ramp. The ramps are remapped along with everything else, so the minimap follows the scheme too.
+pub fn minimap_palette_index(player_color: usize) -> u8 {
+    (ramp(player_color % PLAYER_COLOR_COUNT).start + PLAYER_COLOR_RAMP_LENGTH / 2) as u8
+}
+
+/// Palette entries used for the given player color ID. SLP player color N is player color ID N - 1,
+/// since player color 0 is for gaia and isn't remapped.
+fn ramp(player_color: usize) -> Range<usize> {
+    let start = (player_color + 1) * PLAYER_COLOR_RAMP_STRIDE;
+    start..(start + PLAYER_COLOR_RAMP_LENGTH)
+}
+
+/// Darkens the base color towards black for dark originals, and lightens it towards white for
+/// bright ones
+fn shade(base: (u8, u8, u8), original: (u8, u8, u8)) -> (u8, u8, u8) {
+    let (r, g, b) = original;
+    // Rec. 601 luma, out of 255000
+    let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+    let half = 255 * 500;
+    let channel = |base: u8| -> u8 {
+        let base = base as u32;
+        if luma < half {
+            (base * luma / half) as u8
+        } else {
+            (base + (255 - base) * (luma - half) / half) as u8
+        }
+    };
+    (channel(base.0), channel(base.1), channel(base.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayerColorScheme, minimap_palette_index, ramp, shade};
+
+    #[test]
+    fn test_names() {
+        for scheme in PlayerColorScheme::all() {
+            assert_eq!(Some(*scheme), PlayerColorScheme::from_name(scheme.name()));
+        }
+        assert_eq!(None, PlayerColorScheme::from_name("plaid"));
+    }
+
+    #[test]
+    fn test_shade() {
+        let base = (0, 114, 178);
+        assert_eq!((0, 0, 0), shade(base, (0, 0, 0)));
+        assert_eq!((255, 255, 255), shade(base, (255, 255, 255)));
+        assert_eq!((0, 57, 89), shade(base, (64, 64, 64)));
+        assert_eq!((129, 185, 216), shade(base, (192, 192, 192)));
+    }
+
+    #[test]
+    fn test_minimap_palette_index() {
+        for player_color in 0..8 {
+            let index = minimap_palette_index(player_color) as usize;
+            assert!(ramp(player_color).start <= index && index < ramp(player_color).end);
+        }
+    }
+
+    #[test]
+    fn test_remap_palette() {
+        let original: Vec<(u8, u8, u8)> = (0..256).map(|i| (i as u8, i as u8, i as u8)).collect();
+
+        let mut palette = original.clone();
+        PlayerColorScheme::Original.remap_palette(&mut palette);
+        assert_eq!(original, palette);
+
+        PlayerColorScheme::ColorblindSafe.remap_palette(&mut palette);
+        assert_eq!(original[..16], palette[..16]);
+        assert_eq!(original[24..32], palette[24..32]);
+        for index in ramp(1) {
+            let shaded = shade(PlayerColorScheme::ColorblindSafe.color(1), original[index]);
+            assert_eq!(shaded, palette[index]);
+        }
+    }
+}
@@ -22,14 +22,16 @@
 use media::Renderer;
 use nalgebra::Vector2;
 use std::cmp::{Ordering, PartialOrd};
-use super::{ShapeKey, ShapeManager};
+use super::{CompositeKey, ShapeKey, ShapeManager};
 use types::{Color, Rect};
 
 #[derive(Copy, Clone, Debug)]
 pub enum RenderCommand {
     RenderShape(RenderOrder, RenderShapeParams),
+    RenderComposite(RenderOrder, RenderCompositeParams),
     RenderRect(RenderOrder, RenderRectParams),
     RenderLine(RenderOrder, RenderLineParams),
+    RenderNumber(RenderOrder, RenderNumberParams),
 }
 
 impl RenderCommand {
@@ -49,6 +51,9 @@ impl RenderCommand {
                                       params.flip_horizontal,
                                       params.flip_vertical);
                 }
+                RenderComposite(_, params) => {
+                    shape_manager.render_composite(renderer, &params.key);
+                }
                 RenderRect(_, params) => {
                     renderer.render_rect(params.rect);
                 }
@@ -56,6 +61,12 @@ impl RenderCommand {
                     renderer.set_render_color(params.color);
                     renderer.render_line(params.points[0], params.points[1]);
                 }
+                RenderNumber(_, params) => {
+                    renderer.render_world_text(&params.number.to_string(),
+                                               &params.position,
+                                               params.scale,
+                                               params.color);
+                }
             }
         }
     }
@@ -77,6 +88,13 @@ impl RenderCommand {
         RenderCommand::RenderShape(order, params)
     }
 
+    /// Draws the shapes that were built into a composite under the key (see
+    /// `ShapeManager::build_composite`), or nothing if there isn't one
+    pub fn new_composite(layer: u16, depth: i32, key: CompositeKey) -> RenderCommand {
+        let order = RenderOrder::new(layer, depth, false);
+        RenderCommand::RenderComposite(order, RenderCompositeParams::new(key))
+    }
+
     pub fn new_line(layer: u16,
                     depth: i32,
                     color: Color,
@@ -88,6 +106,18 @@ impl RenderCommand {
         RenderCommand::RenderLine(order, params)
     }
 
+    pub fn new_number(layer: u16,
+                      depth: i32,
+                      number: u16,
+                      position: Vector2<i32>,
+                      scale: u32,
+                      color: Color)
+                      -> RenderCommand {
+        let order = RenderOrder::new(layer, depth, false);
+        let params = RenderNumberParams::new(number, position, scale, color);
+        RenderCommand::RenderNumber(order, params)
+    }
+
     pub fn new_debug_rect(layer: u16, depth: i32, rect: Rect) -> RenderCommand {
         let order = RenderOrder::new(layer, depth, true);
         let params = RenderRectParams::new(rect);
@@ -109,8 +139,10 @@ impl RenderCommand {
         use RenderCommand::*;
         match *self {
             RenderShape(ref order, _) => order,
+            RenderComposite(ref order, _) => order,
             RenderRect(ref order, _) => order,
             RenderLine(ref order, _) => order,
+            RenderNumber(ref order, _) => order,
         }
     }
 }
@@ -173,6 +205,17 @@ impl RenderShapeParams {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct RenderCompositeParams {
+    pub key: CompositeKey,
+}
+
+impl RenderCompositeParams {
+    pub fn new(key: CompositeKey) -> RenderCompositeParams {
+        RenderCompositeParams { key: key }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct RenderRectParams {
     pub rect: Rect,
@@ -198,3 +241,23 @@ impl RenderLineParams {
         }
     }
 }
+
+/// A number drawn on the map with the built-in font, such as a waypoint's place in a route
+#[derive(Copy, Clone, Debug)]
+pub struct RenderNumberParams {
+    pub number: u16,
+    pub position: Vector2<i32>,
+    pub scale: u32,
+    pub color: Color,
+}
+
+impl RenderNumberParams {
+    pub fn new(number: u16, position: Vector2<i32>, scale: u32, color: Color) -> RenderNumberParams {
+        RenderNumberParams {
+            number: number,
+            position: position,
+            scale: scale,
+            color: color,
+        }
+    }
+}
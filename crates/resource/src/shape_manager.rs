@@ -28,8 +28,12 @@ use media::{Renderer, Texture, TextureBuilder};
 
 use nalgebra::Vector2;
 use palette::{self, PaletteColor};
+use player_color_scheme::PlayerColorScheme;
+use render_command::RenderShapeParams;
 use slp::SlpFile;
 
+use std::cmp;
+
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io;
@@ -44,6 +48,8 @@ const CENTER_CUTOFF: i32 = 100000;
 pub struct ShapeKey {
     pub drs_key: DrsKey,
     pub slp_id: SlpFileId,
+    /// The player index that the SLP's player color pixels are remapped with, from 1 to 8, or 0 to
+    /// leave them alone. Each one is decoded and cached as a shape of its own.
     pub player_color: PlayerColorId,
 }
 
@@ -57,6 +63,31 @@ impl ShapeKey {
     }
 }
 
+/// Names a composite (see `ShapeManager::build_composite`). Whoever builds one picks its key: the
+/// terrain uses its layer and the chunk's row and column.
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct CompositeKey {
+    pub layer: u16,
+    pub row: i32,
+    pub col: i32,
+}
+
+impl CompositeKey {
+    pub fn new(layer: u16, row: i32, col: i32) -> CompositeKey {
+        CompositeKey {
+            layer: layer,
+            row: row,
+            col: col,
+        }
+    }
+}
+
+/// Shapes drawn into a texture of their own, and where its top left corner goes on the map
+struct Composite {
+    texture: Texture,
+    position: Vector2<i32>,
+}
+
 pub struct Shape {
     texture: Texture,
     frames: Vec<Rect>,
@@ -142,33 +173,60 @@ enum ShapeCache {
 pub struct ShapeManager {
     drs_manager: DrsManagerRef,
     shapes: HashMap<ShapeKey, ShapeCache>,
+
+    /// The decoded pixels of the shapes that composites are drawn from (`None` if one failed to
+    /// load), and the composites themselves
+    slps: HashMap<ShapeKey, Option<SlpFile>>,
+    composites: HashMap<CompositeKey, Composite>,
+
     palette: Vec<u32>,
+    player_color_scheme: PlayerColorScheme,
 }
 
 pub type ShapeManagerRef = Rc<RefCell<ShapeManager>>;
 
 impl ShapeManager {
-    pub fn new(drs_manager: DrsManagerRef) -> Result<ShapeManagerRef> {
-        let palette = {
-                let borrowed_drs = drs_manager.borrow();
-                let interfac = borrowed_drs.get(DrsKey::Interfac);
-                let bin_table = try!(interfac.find_table(DrsFileType::Binary)
-                    .ok_or(ErrorKind::InterfacBinaryTableMissing));
-                let palette_contents = &try!(bin_table.find_file_contents(PALETTE_FILE_ID)
-                    .ok_or(ErrorKind::InterfacMissingPalette));
-                try!(palette::read_from(&mut io::Cursor::new(palette_contents)))
-            }
-            .iter()
-            .map(|c: &PaletteColor| -> u32 { (*c).into() })
-            .collect();
+    pub fn new(drs_manager: DrsManagerRef,
+               player_color_scheme: PlayerColorScheme)
+               -> Result<ShapeManagerRef> {
+        let mut palette = {
+            let borrowed_drs = drs_manager.borrow();
+            let interfac = try!(borrowed_drs.get(DrsKey::Interfac)
+                .ok_or(ErrorKind::DrsMissing(DrsKey::Interfac)));
+            let bin_table = try!(interfac.find_table(DrsFileType::Binary)
+                .ok_or(ErrorKind::InterfacBinaryTableMissing));
+            let palette_contents = &try!(bin_table.find_file_contents(PALETTE_FILE_ID)
+                .ok_or(ErrorKind::InterfacMissingPalette));
+            try!(palette::read_from(&mut io::Cursor::new(palette_contents)))
+        };
+
+        let mut colors: Vec<(u8, u8, u8)> = palette.iter().map(|c| (c.r, c.g, c.b)).collect();
+        player_color_scheme.remap_palette(&mut colors);
+        for (color, &(r, g, b)) in palette.iter_mut().zip(colors.iter()) {
+            color.r = r;
+            color.g = g;
+            color.b = b;
+        }
 
         Ok(Rc::new(RefCell::new(ShapeManager {
             drs_manager: drs_manager,
             shapes: HashMap::new(),
-            palette: palette,
+            slps: HashMap::new(),
+            composites: HashMap::new(),
+            palette: palette.iter().map(|c: &PaletteColor| -> u32 { (*c).into() }).collect(),
+            player_color_scheme: player_color_scheme,
         })))
     }
 
+    pub fn player_color_scheme(&self) -> PlayerColorScheme {
+        self.player_color_scheme
+    }
+
+    /// The game's palette (with the player colors remapped), as textures are built with it
+    pub fn palette(&self) -> &[u32] {
+        &self.palette
+    }
+
     pub fn get<'a>(&'a mut self, shape_key: &ShapeKey, renderer: &mut Renderer) -> Option<&'a Shape> {
         use self::ShapeCache::*;
 
@@ -192,23 +250,149 @@ impl ShapeManager {
         }
     }
 
+    /// Draws the shapes, in the order they're given, into a single texture that's drawn with
+    /// `RenderCommand::RenderComposite` under the key, so that drawing them all again is one copy.
+    /// Shapes are drawn at their positions on the map and never flipped. Any that fail to load are
+    /// left out, and the composite is replaced if there already was one.
+    pub fn build_composite(&mut self,
+                           key: CompositeKey,
+                           shapes: &[RenderShapeParams],
+                           renderer: &mut Renderer) {
+        self.composites.remove(&key);
+        match self.composite(shapes, renderer) {
+            Ok(Some(composite)) => {
+                self.composites.insert(key, composite);
+            }
+            Ok(None) => (),
+            Err(err) => println!("Failed to build composite {:?}: {}", key, err),
+        }
+    }
+
+    pub fn forget_composite(&mut self, key: &CompositeKey) {
+        self.composites.remove(key);
+    }
+
+    pub fn render_composite(&self, renderer: &mut Renderer, key: &CompositeKey) {
+        if let Some(composite) = self.composites.get(key) {
+            let texture = &composite.texture;
+            renderer.render_texture(texture,
+                                    None,
+                                    Rect::of(composite.position.x,
+                                             composite.position.y,
+                                             texture.width as i32,
+                                             texture.height as i32),
+                                    false,
+                                    false);
+        }
+    }
+
+    /// `None` if none of the shapes have anything to draw
+    fn composite(&mut self,
+                 shapes: &[RenderShapeParams],
+                 renderer: &mut Renderer)
+                 -> Result<Option<Composite>> {
+        for params in shapes {
+            if !self.slps.contains_key(&params.shape_key) {
+                let slp = match self.read_slp(&params.shape_key) {
+                    Ok(slp) => Some(slp),
+                    Err(err) => {
+                        println!("Failed to load shape {:?}: {}", params.shape_key, err);
+                        None
+                    }
+                };
+                self.slps.insert(params.shape_key, slp);
+            }
+        }
+
+        // Where each frame goes on the map, the way `Shape::render_frame` would draw it
+        let mut frames = Vec::new();
+        for params in shapes {
+            let shape = match self.slps[&params.shape_key] {
+                Some(ref slp) => slp.shapes.get(params.frame_num as usize),
+                None => None,
+            };
+            if let Some(shape) = shape {
+                let center = Vector2::new(shape.header.center_x, shape.header.center_y);
+                if center.x.abs() > CENTER_CUTOFF || center.y.abs() > CENTER_CUTOFF {
+                    continue;
+                }
+                frames.push((shape,
+                             Rect::of(params.position.x - center.x,
+                                      params.position.y - center.y,
+                                      shape.header.width as i32,
+                                      shape.header.height as i32)));
+            }
+        }
+        let bounds = match bounding_rect(frames.iter().map(|&(_, rect)| rect)) {
+            Some(bounds) if bounds.w > 0 && bounds.h > 0 => bounds,
+            _ => return Ok(None),
+        };
+
+        let mut texture_builder =
+            try!(TextureBuilder::new(bounds.w as u32, bounds.h as u32, &self.palette));
+        for &(shape, rect) in &frames {
+            texture_builder = texture_builder.blit_shape(&shape.pixels,
+                                                         Rect::of(0, 0, rect.w, rect.h),
+                                                         Rect::of(rect.x - bounds.x,
+                                                                  rect.y - bounds.y,
+                                                                  rect.w,
+                                                                  rect.h));
+        }
+        Ok(Some(Composite {
+            texture: try!(texture_builder.build(renderer)),
+            position: Vector2::new(bounds.x, bounds.y),
+        }))
+    }
+
     fn load_shape(&self, shape_key: &ShapeKey, renderer: &mut Renderer) -> Result<Shape> {
-        let borrowed_drs = self.drs_manager.borrow();
-        let drs_file = borrowed_drs.get(shape_key.drs_key);
+        let slp = try!(self.read_slp(shape_key));
+        Shape::load_from(&slp, &self.palette, renderer)
+    }
 
-        let slp_table = try!(drs_file.find_table(DrsFileType::Slp)
-            .ok_or(ErrorKind::NoSlpTableInDrs(shape_key.drs_key)));
-        
-        let slp = match slp_table.find_file_contents(*shape_key.slp_id) {
+    fn read_slp(&self, shape_key: &ShapeKey) -> Result<SlpFile> {
+        let borrowed_drs = self.drs_manager.borrow();
+        let slp_contents =
+            try!(borrowed_drs.find_file_contents(shape_key.drs_key, DrsFileType::Slp, *shape_key.slp_id));
+        let slp = match slp_contents {
             Some(slp_contents) => {
-                try!(SlpFile::read_from(&mut io::Cursor::new(slp_contents), *shape_key.player_color))
+                try!(SlpFile::read_from(&mut io::Cursor::new(&slp_contents[..]), *shape_key.player_color))
             },
             None => {
                 // Load the "missing" SLP file if we can't find the requested SLP in the DRS archive
                 try!(SlpFile::read_from_file("data/nope-64x64.slp", *shape_key.player_color))
             }
         };
+        Ok(slp)
+    }
+}
 
-        Shape::load_from(&slp, &self.palette, renderer)
+/// The smallest rectangle that covers all of them, if there are any
+fn bounding_rect<I: Iterator<Item = Rect>>(rects: I) -> Option<Rect> {
+    let mut bounds: Option<(i32, i32, i32, i32)> = None;
+    for rect in rects {
+        let (right, bottom) = (rect.x + rect.w, rect.y + rect.h);
+        bounds = Some(match bounds {
+            Some((left, top, old_right, old_bottom)) => {
+                (cmp::min(left, rect.x),
+                 cmp::min(top, rect.y),
+                 cmp::max(old_right, right),
+                 cmp::max(old_bottom, bottom))
+            }
+            None => (rect.x, rect.y, right, bottom),
+        });
+    }
+    bounds.map(|(left, top, right, bottom)| Rect::of(left, top, right - left, bottom - top))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bounding_rect;
+    use types::Rect;
+
+    #[test]
+    fn test_bounding_rect() {
+        assert_eq!(None, bounding_rect(Vec::new().into_iter()));
+        let rects = vec![Rect::of(10, 5, 64, 32), Rect::of(-22, 21, 64, 40), Rect::of(42, -3, 8, 8)];
+        assert_eq!(Some(Rect::of(-22, -3, 96, 64)), bounding_rect(rects.into_iter()));
     }
 }
@@ -67,19 +67,19 @@ impl ShapeMetadataStore {
     fn load_drs(drs_manager: &DrsManager,
                 drs_key: DrsKey,
                 metadata: &mut HashMap<ShapeMetadataKey, ShapeMetadata>) {
-        let drs = drs_manager.get(drs_key);
-        if let Some(table) = drs.find_table(DrsFileType::Slp) {
-            let keys: Vec<ShapeMetadataKey> = table.entries
-                .iter()
-                .map(|entry| ShapeMetadataKey::new(drs_key, (entry.file_id as usize).into()))
-                .collect();
+        // Mods can replace SLPs (or add new ones), so go through the DRS manager rather than the archive
+        let keys: Vec<ShapeMetadataKey> = drs_manager.file_ids(drs_key, DrsFileType::Slp)
+            .into_iter()
+            .map(|file_id| ShapeMetadataKey::new(drs_key, (file_id as usize).into()))
+            .collect();
 
-            for key in keys {
-                // TODO: Should probably return a result instead of unwrapping
-                let contents = table.find_file_contents(*key.slp_id).unwrap();
-                let slp_header = SlpHeader::read_from(&mut io::Cursor::new(contents)).unwrap();
-                metadata.insert(key, ShapeMetadata { shape_count: slp_header.shape_count });
-            }
+        for key in keys {
+            // TODO: Should probably return a result instead of unwrapping
+            let contents = drs_manager.find_file_contents(drs_key, DrsFileType::Slp, *key.slp_id)
+                .unwrap()
+                .unwrap();
+            let slp_header = SlpHeader::read_from(&mut io::Cursor::new(&contents[..])).unwrap();
+            metadata.insert(key, ShapeMetadata { shape_count: slp_header.shape_count });
         }
     }
 
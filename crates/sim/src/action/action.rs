@@ -0,0 +1,76 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::{ResearchId, UnitId};
+use super::{AttackGroundParams, AttackMoveParams, AttackUnitParams, BuildParams, GarrisonParams,
+            GatherParams, MoveToPositionParams};
+
+/// Enum of possible actions a unit can undertake
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Clears a unit's action queue
+    ClearQueue,
+
+    /// Instructs a unit to move to a given position on the map
+    MoveToPosition(MoveToPositionParams),
+
+    /// Instructs a siege unit to keep firing at a spot on the ground, hitting anything there
+    /// (including its own player's units)
+    AttackGround(AttackGroundParams),
+
+    /// Instructs a unit to follow a path, but to stop and fight any enemies it sees along the
+    /// way before carrying on
+    AttackMove(AttackMoveParams),
+
+    /// Instructs a unit to attack another unit until one of them dies
+    AttackUnit(AttackUnitParams),
+
+    /// Instructs a villager to put up a building. The first villager given the order lays the
+    /// foundation (paying for it), and every villager sent along helps build it.
+    Build(BuildParams),
+
+    /// Instructs a unit next to one of its player's buildings to go inside for protection
+    Garrison(GarrisonParams),
+
+    /// Instructs a villager or fishing boat to gather from a resource, taking what it gathers to
+    /// the nearest drop site whenever it can't carry any more
+    Gather(GatherParams),
+
+    /// Lets a garrisoned unit back out, or turns back a unit the town bell sent to garrison,
+    /// and has it carry on with what it was doing before `RememberQueue`
+    Ungarrison,
+
+    /// Saves a unit's current and queued actions for `Ungarrison` to pick back up, and clears
+    /// the queue
+    RememberQueue,
+
+    /// Kills a unit right away (the player deleted it), skipping anything else in its queue
+    Kill,
+
+    /// Gives up the game for the unit's owner; any of the player's units can carry it
+    Resign,
+
+    /// Researches a technology at a building for the building's owner
+    Research(ResearchId),
+
+    /// Adds a unit to the end of a building's training queue
+    Train(UnitId),
+}
@@ -19,8 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod action;
-mod move_to_position;
+use super::Path;
 
-pub use self::action::Action;
-pub use self::move_to_position::MoveToPositionParams;
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackMoveParams {
+    pub path: Path,
+}
+
+impl AttackMoveParams {
+    pub fn new(path: Path) -> AttackMoveParams {
+        AttackMoveParams { path: path }
+    }
+}
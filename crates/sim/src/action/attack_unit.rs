@@ -19,6 +19,16 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod move_to_position;
 
-pub use self::move_to_position::MoveToPositionActionComponent;
+use specs::Index;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackUnitParams {
+    pub target_id: Index,
+}
+
+impl AttackUnitParams {
+    pub fn new(target_id: Index) -> AttackUnitParams {
+        AttackUnitParams { target_id: target_id }
+    }
+}
@@ -0,0 +1,458 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use specs::Index;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use super::{Action, AttackGroundParams, AttackMoveParams, AttackUnitParams, BuildParams, GarrisonParams,
+            GatherParams, MoveToPositionParams, PathNode};
+use super::wire_format;
+use types::{Fixed, Vector3};
+
+/// Command logs written to files with this extension use the binary format
+pub const BINARY_EXTENSION: &'static str = "cmds";
+
+/// A single action as it was consumed by the simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandLogEntry {
+    /// Simulation tick on which the action was handed to the entity
+    pub tick: u64,
+    pub entity_id: Index,
+    pub action: Action,
+}
+
+/// An ordered log of every action the simulation consumed. Feeding a log back into
+/// the simulation (starting from the same scenario) reproduces the same game.
+///
+/// The text format is an optional `seed <n>` line (the GameRng seed the game was set up with) and
+/// an optional `settings <settings>` line (the game's starting conditions, in the text form the
+/// game gives them), followed by one entry per line: `<tick> <entity id> <action> [args...]`.
+/// Lines starting with `#` are comments. Fixed point values are written as their raw
+/// scaled integer so that they round trip exactly. Files ending in `.cmds` use the much smaller
+/// binary format in `wire_format` instead.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandLog {
+    seed: Option<u64>,
+    settings: Option<String>,
+    entries: VecDeque<CommandLogEntry>,
+}
+
+impl CommandLog {
+    pub fn new() -> CommandLog {
+        CommandLog {
+            seed: None,
+            settings: None,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Reads either format, whatever the file is named
+    pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> io::Result<CommandLog> {
+        let mut bytes = Vec::new();
+        try!(try!(File::open(file_name)).read_to_end(&mut bytes));
+        if wire_format::is_binary(&bytes) {
+            return CommandLog::from_bytes(&bytes);
+        }
+        let text = try!(String::from_utf8(bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "command log: invalid UTF-8")));
+        CommandLog::parse(&text)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_name: P) -> io::Result<()> {
+        let binary = file_name.as_ref()
+            .extension()
+            .map(|extension| extension == BINARY_EXTENSION)
+            .unwrap_or(false);
+        let mut file = try!(File::create(file_name));
+        if binary {
+            file.write_all(&self.to_bytes())
+        } else {
+            file.write_all(self.to_text().as_bytes())
+        }
+    }
+
+    pub fn parse(text: &str) -> io::Result<CommandLog> {
+        let mut log = CommandLog::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with("seed ") {
+                log.seed = Some(try!(line[5..].trim().parse::<u64>().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData,
+                                   format!("command log line {}: invalid seed", line_number + 1))
+                })));
+                continue;
+            }
+            if line.starts_with("settings ") {
+                log.settings = Some(line[9..].trim().to_string());
+                continue;
+            }
+            let entry = try!(parse_entry(line).map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               format!("command log line {}: {}", line_number + 1, err))
+            }));
+            if log.last_tick().map(|tick| tick > entry.tick).unwrap_or(false) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          format!("command log line {}: ticks must not decrease",
+                                                  line_number + 1)));
+            }
+            log.entries.push_back(entry);
+        }
+        Ok(log)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<CommandLog> {
+        let (seed, settings, entries) = try!(wire_format::decode(bytes));
+        Ok(CommandLog {
+            seed: seed,
+            settings: settings,
+            entries: entries.into_iter().collect(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        wire_format::encode(self.seed,
+                            self.settings.as_ref().map(|settings| &settings[..]),
+                            self.entries.iter())
+    }
+
+    /// The log in an older binary format, for peers that can't read the newest one (see
+    /// `wire_format::encode_as`)
+    pub fn to_bytes_in(&self, version: u8) -> io::Result<Vec<u8>> {
+        wire_format::encode_as(version,
+                               self.seed,
+                               self.settings.as_ref().map(|settings| &settings[..]),
+                               self.entries.iter())
+            .map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("binary command log: {}", err))
+            })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(seed) = self.seed {
+            text.push_str(&format!("seed {}\n", seed));
+        }
+        if let Some(ref settings) = self.settings {
+            text.push_str(&format!("settings {}\n", settings));
+        }
+        for entry in &self.entries {
+            text.push_str(&format!("{} {} {}\n", entry.tick, entry.entity_id, format_action(&entry.action)));
+        }
+        text
+    }
+
+    /// Appends a batch of actions that were consumed on the given tick
+    pub fn record(&mut self, tick: u64, actions: &BTreeMap<Index, Vec<Action>>) {
+        for (entity_id, entity_actions) in actions {
+            for action in entity_actions {
+                self.entries.push_back(CommandLogEntry {
+                    tick: tick,
+                    entity_id: *entity_id,
+                    action: action.clone(),
+                });
+            }
+        }
+    }
+
+    /// Appends a single entry, which must not be from before the last one
+    pub fn push(&mut self, entry: CommandLogEntry) {
+        self.entries.push_back(entry);
+    }
+
+    /// Removes and returns all entries up to and including the given tick
+    pub fn take_until(&mut self, tick: u64) -> Vec<CommandLogEntry> {
+        let mut taken = Vec::new();
+        while self.entries.front().map(|entry| entry.tick <= tick).unwrap_or(false) {
+            taken.push(self.entries.pop_front().unwrap());
+        }
+        taken
+    }
+
+    /// The GameRng seed the recorded game was set up with, if known
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// The starting conditions the recorded game was set up with, if known
+    pub fn settings(&self) -> Option<&str> {
+        self.settings.as_ref().map(|settings| &settings[..])
+    }
+
+    /// The settings have to fit on one line
+    pub fn set_settings(&mut self, settings: &str) {
+        self.settings = Some(settings.replace(|c: char| c == '\n' || c == '\r', " "));
+    }
+
+    pub fn last_tick(&self) -> Option<u64> {
+        self.entries.back().map(|entry| entry.tick)
+    }
+
+    pub fn entries<'a>(&'a self) -> &'a VecDeque<CommandLogEntry> {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn format_action(action: &Action) -> String {
+    match *action {
+        Action::ClearQueue => "clear".to_string(),
+        Action::Kill => "kill".to_string(),
+        Action::Resign => "resign".to_string(),
+        Action::Research(research_id) => format!("research {}", *research_id),
+        Action::Train(unit_id) => format!("train {}", *unit_id),
+        Action::AttackUnit(ref params) => format!("attack {}", params.target_id),
+        Action::Garrison(ref params) => {
+            if params.town_bell {
+                format!("garrison {} bell", params.building_id)
+            } else {
+                format!("garrison {}", params.building_id)
+            }
+        }
+        Action::Gather(ref params) => format!("gather {}", params.target_id),
+        Action::Build(ref params) => {
+            let position = &params.position;
+            format!("build {} {},{},{}",
+                    *params.building_id,
+                    position.x.scaled,
+                    position.y.scaled,
+                    position.z.scaled)
+        }
+        Action::Ungarrison => "ungarrison".to_string(),
+        Action::RememberQueue => "remember".to_string(),
+        Action::AttackGround(ref params) => {
+            let target = &params.target;
+            format!("attack-ground {},{},{}", target.x.scaled, target.y.scaled, target.z.scaled)
+        }
+        Action::MoveToPosition(ref params) => format_path("move", &params.path),
+        Action::AttackMove(ref params) => format_path("attack-move", &params.path),
+    }
+}
+
+fn format_path(name: &str, path: &[PathNode]) -> String {
+    let mut text = name.to_string();
+    for node in path {
+        text.push_str(&format!(" {},{},{}", node.x.scaled, node.y.scaled, node.z.scaled));
+    }
+    text
+}
+
+fn parse_entry(line: &str) -> Result<CommandLogEntry, String> {
+    let mut fields = line.split_whitespace();
+    let tick = try!(parse_field::<u64>(fields.next(), "tick"));
+    let entity_id = try!(parse_field::<Index>(fields.next(), "entity id"));
+    let action = match fields.next() {
+        Some("clear") => Action::ClearQueue,
+        Some("kill") => Action::Kill,
+        Some("resign") => Action::Resign,
+        Some("research") => {
+            let research_id = try!(parse_field::<u32>(fields.next(), "research id"));
+            Action::Research((research_id as usize).into())
+        }
+        Some("train") => {
+            let unit_id = try!(parse_field::<u32>(fields.next(), "unit id"));
+            Action::Train((unit_id as usize).into())
+        }
+        Some("attack") => {
+            let target_id = try!(parse_field::<Index>(fields.next(), "target id"));
+            Action::AttackUnit(AttackUnitParams::new(target_id))
+        }
+        Some("garrison") => {
+            let building_id = try!(parse_field::<Index>(fields.next(), "building id"));
+            let town_bell = match fields.next() {
+                Some("bell") => true,
+                Some(extra) => return Err(format!("unexpected \"{}\"", extra)),
+                None => false,
+            };
+            Action::Garrison(GarrisonParams::new(building_id, town_bell))
+        }
+        Some("gather") => {
+            let target_id = try!(parse_field::<Index>(fields.next(), "target id"));
+            Action::Gather(GatherParams::new(target_id))
+        }
+        Some("build") => {
+            let building_id = try!(parse_field::<u32>(fields.next(), "building id"));
+            let position = try!(fields.next().ok_or_else(|| "missing position".to_string()));
+            Action::Build(BuildParams::new((building_id as usize).into(), try!(parse_path_node(position))))
+        }
+        Some("ungarrison") => Action::Ungarrison,
+        Some("remember") => Action::RememberQueue,
+        Some("attack-ground") => {
+            let target = try!(fields.next().ok_or_else(|| "missing target".to_string()));
+            Action::AttackGround(AttackGroundParams::new(try!(parse_path_node(target))))
+        }
+        Some("move") => Action::MoveToPosition(MoveToPositionParams::new(try!(parse_path(&mut fields)))),
+        Some("attack-move") => Action::AttackMove(AttackMoveParams::new(try!(parse_path(&mut fields)))),
+        Some(unknown) => return Err(format!("unknown action \"{}\"", unknown)),
+        None => return Err("missing action".to_string()),
+    };
+    if let Some(extra) = fields.next() {
+        return Err(format!("unexpected \"{}\"", extra));
+    }
+    Ok(CommandLogEntry {
+        tick: tick,
+        entity_id: entity_id,
+        action: action,
+    })
+}
+
+fn parse_field<T: ::std::str::FromStr>(field: Option<&str>, name: &str) -> Result<T, String> {
+    let field = try!(field.ok_or_else(|| format!("missing {}", name)));
+    field.parse().map_err(|_| format!("invalid {} \"{}\"", name, field))
+}
+
+fn parse_path<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Result<Vec<PathNode>, String> {
+    let mut path = Vec::new();
+    for node in fields {
+        path.push(try!(parse_path_node(node)));
+    }
+    Ok(path)
+}
+
+fn parse_path_node(text: &str) -> Result<PathNode, String> {
+    let components: Vec<&str> = text.split(',').collect();
+    if components.len() != 3 {
+        return Err(format!("invalid path node \"{}\"", text));
+    }
+    let mut values = [Fixed::from(0); 3];
+    for (value, component) in values.iter_mut().zip(components) {
+        *value = Fixed { scaled: try!(parse_field::<i64>(Some(component), "path node component")) };
+    }
+    Ok(Vector3::new(values[0], values[1], values[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use action::{Action, AttackGroundParams, AttackMoveParams, AttackUnitParams, BuildParams,
+                 GarrisonParams, GatherParams, MoveToPositionParams};
+    use std::collections::BTreeMap;
+    use super::CommandLog;
+    use types::{Fixed, Vector3};
+
+    fn sample_log() -> CommandLog {
+        let mut batch = BTreeMap::new();
+        batch.insert(3,
+                     vec![Action::ClearQueue,
+                          Action::MoveToPosition(MoveToPositionParams::new(vec![
+                              Vector3::new(fixed_const!(1.5), fixed_const!(-2.25), 0.into()),
+                              Vector3::new(Fixed { scaled: 1 }, 10.into(), 0.into()),
+                          ]))]);
+        batch.insert(7,
+                     vec![Action::ClearQueue,
+                          Action::Kill,
+                          Action::Resign,
+                          Action::Research(46.into()),
+                          Action::Train(83.into())]);
+        batch.insert(9,
+                     vec![Action::AttackGround(AttackGroundParams::new(Vector3::new(fixed_const!(12.5),
+                                                                                     4.into(),
+                                                                                     0.into()))),
+                          Action::AttackUnit(AttackUnitParams::new(17))]);
+        batch.insert(11,
+                     vec![Action::RememberQueue,
+                          Action::Garrison(GarrisonParams::new(2, true)),
+                          Action::Ungarrison,
+                          Action::Garrison(GarrisonParams::new(2, false))]);
+        batch.insert(12,
+                     vec![Action::Gather(GatherParams::new(40)),
+                          Action::Build(BuildParams::new(70.into(),
+                                                         Vector3::new(fixed_const!(20.5),
+                                                                      fixed_const!(8.5),
+                                                                      2.into())))]);
+        batch.insert(13,
+                     vec![Action::AttackMove(AttackMoveParams::new(vec![
+                              Vector3::new(fixed_const!(3.5), fixed_const!(4.5), 1.into()),
+                          ]))]);
+
+        let mut log = CommandLog::new();
+        log.set_seed(1234);
+        log.set_settings("resources=high,victory=conquest");
+        log.record(6, &batch);
+        log
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let log = sample_log();
+        let text = log.to_text();
+        assert_eq!(log, CommandLog::parse(&text).unwrap());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let log = sample_log();
+        let bytes = log.to_bytes();
+        assert!(bytes.len() * 2 < log.to_text().len());
+        assert_eq!(log, CommandLog::from_bytes(&bytes).unwrap());
+        assert_eq!(CommandLog::new(), CommandLog::from_bytes(&CommandLog::new().to_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_parse_comments_and_blank_lines() {
+        let log = CommandLog::parse("# comment\n\n  12 4 clear\n").unwrap();
+        assert_eq!(1, log.entries().len());
+        assert_eq!(12, log.entries()[0].tick);
+        assert_eq!(4, log.entries()[0].entity_id);
+        assert_eq!(Some(12), log.last_tick());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(CommandLog::parse("12").is_err());
+        assert!(CommandLog::parse("12 4").is_err());
+        assert!(CommandLog::parse("12 4 dance").is_err());
+        assert!(CommandLog::parse("12 4 clear extra").is_err());
+        assert!(CommandLog::parse("12 4 move 1,2").is_err());
+        assert!(CommandLog::parse("12 4 attack-move 1,2,x").is_err());
+        assert!(CommandLog::parse("12 4 research").is_err());
+        assert!(CommandLog::parse("12 4 train").is_err());
+        assert!(CommandLog::parse("12 4 attack-ground").is_err());
+        assert!(CommandLog::parse("12 4 attack").is_err());
+        assert!(CommandLog::parse("12 4 garrison").is_err());
+        assert!(CommandLog::parse("12 4 garrison 2 now").is_err());
+        assert!(CommandLog::parse("12 4 gather").is_err());
+        assert!(CommandLog::parse("12 4 build 70").is_err());
+        assert!(CommandLog::parse("12 4 build 70 1,2").is_err());
+        assert!(CommandLog::parse("12 4 clear\n10 4 clear").is_err());
+        assert!(CommandLog::parse("seed -1").is_err());
+    }
+
+    #[test]
+    fn test_take_until() {
+        let mut log = CommandLog::parse("1 1 clear\n5 1 clear\n5 2 clear\n9 1 clear").unwrap();
+        assert!(log.take_until(0).is_empty());
+        assert_eq!(1, log.take_until(4).len());
+        assert_eq!(2, log.take_until(5).len());
+        assert_eq!(1, log.take_until(100).len());
+        assert!(log.is_empty());
+    }
+}
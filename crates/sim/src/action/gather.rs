@@ -19,17 +19,16 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::collections::HashSet;
+use specs::Index;
 
-/// (row, col) tile position
-pub type OccupiedTile = (i32, i32);
-
-pub struct OccupiedTiles {
-    pub tiles: HashSet<OccupiedTile>,
+#[derive(Clone, Debug, PartialEq)]
+pub struct GatherParams {
+    /// The tree, mine, bush, or fish to gather from
+    pub target_id: Index,
 }
 
-impl OccupiedTiles {
-    pub fn new() -> OccupiedTiles {
-        OccupiedTiles { tiles: HashSet::new() }
+impl GatherParams {
+    pub fn new(target_id: Index) -> GatherParams {
+        GatherParams { target_id: target_id }
     }
 }
@@ -0,0 +1,42 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod action;
+mod attack_ground;
+mod attack_move;
+mod attack_unit;
+mod build;
+mod command_log;
+mod garrison;
+mod gather;
+mod move_to_position;
+mod wire_format;
+
+pub use self::action::Action;
+pub use self::attack_ground::AttackGroundParams;
+pub use self::attack_move::AttackMoveParams;
+pub use self::attack_unit::AttackUnitParams;
+pub use self::build::BuildParams;
+pub use self::command_log::{BINARY_EXTENSION, CommandLog, CommandLogEntry};
+pub use self::garrison::GarrisonParams;
+pub use self::gather::GatherParams;
+pub use self::move_to_position::{MoveToPositionParams, Path, PathNode};
+pub use self::wire_format::{FORMAT_VERSIONS, Reader, Writer, negotiate_format, shared_formats};
@@ -19,9 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use ecs::resource::path_finder::Path;
+use types::Vector3;
 
-#[derive(Clone, Debug)]
+pub type PathNode = Vector3;
+pub type Path = Vec<PathNode>;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct MoveToPositionParams {
     pub path: Path,
 }
@@ -0,0 +1,624 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A compact binary encoding of command logs. It's what auto-saves and `.cmds` recordings are
+//! written in, and what each tick's commands go over the network in during LAN games; the text
+//! format stays around for logs that people write and read by hand.
+//!
+//! Version 1 starts with `CHCL`, the version byte, and the seed (a flag byte followed by the seed
+//! if there is one), then the entry count and every entry: the tick as the number of ticks since
+//! the previous entry, the entity id, an action tag byte, and the action's arguments. Integers are
+//! LEB128 varints (zigzagged when signed), so small ids and tick gaps take a byte or two. Paths
+//! are stored as their first node followed by the difference from each node to the next, which
+//! stays small because a path's nodes are close together.
+//!
+//! Version 2 adds the game's settings right after the seed: a flag byte followed by the settings'
+//! length and UTF-8 text if there are any.
+//!
+//! Version 3 is laid out the same as version 2, but logs can hold build actions, which older
+//! builds don't know.
+//!
+//! Version 4 stores each coordinate of a node's offset in half tiles when it's a whole number of
+//! them, which it is between the tile centers that paths go through: the half tiles are zigzagged
+//! and shifted up a bit. Any other offset is written as a 1 followed by the scaled offset. A path
+//! node then usually takes three bytes instead of around eight.
+
+use identifier::{ResearchId, UnitId};
+use specs::Index;
+use std::io;
+use super::{Action, AttackGroundParams, AttackMoveParams, AttackUnitParams, BuildParams, CommandLogEntry,
+            GarrisonParams, GatherParams, MoveToPositionParams, Path, PathNode};
+use types::{Fixed, SCALE_BITS, Vector3};
+
+const MAGIC: &'static [u8] = b"CHCL";
+
+/// Every version this build can read, oldest first; the last one is what gets written
+pub const FORMAT_VERSIONS: &'static [u8] = &[1, 2, 3, 4];
+
+/// The first version with the game's settings
+const SETTINGS_VERSION: u8 = 2;
+/// The first version with build actions
+const BUILD_VERSION: u8 = 3;
+/// The first version with nodes in half tiles
+const HALF_TILE_VERSION: u8 = 4;
+
+const HALF_TILE_BITS: i64 = SCALE_BITS - 1;
+/// What a coordinate's offset is written as when it isn't a whole number of half tiles
+const SCALED_OFFSET: u64 = 1;
+
+const TAG_CLEAR_QUEUE: u8 = 0;
+const TAG_MOVE_TO_POSITION: u8 = 1;
+const TAG_ATTACK_GROUND: u8 = 2;
+const TAG_ATTACK_UNIT: u8 = 3;
+const TAG_GARRISON: u8 = 4;
+const TAG_UNGARRISON: u8 = 5;
+const TAG_REMEMBER_QUEUE: u8 = 6;
+const TAG_KILL: u8 = 7;
+const TAG_RESEARCH: u8 = 8;
+const TAG_TRAIN: u8 = 9;
+const TAG_GATHER: u8 = 10;
+const TAG_ATTACK_MOVE: u8 = 11;
+const TAG_RESIGN: u8 = 12;
+const TAG_BUILD: u8 = 13;
+
+/// Whether the bytes are a binary command log (rather than a text one)
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// The versions in both lists, oldest first (so the last one is the newest they share)
+pub fn shared_formats(ours: &[u8], theirs: &[u8]) -> Vec<u8> {
+    ours.iter().cloned().filter(|version| theirs.contains(version)).collect()
+}
+
+/// The newest version both this build and a peer that supports `theirs` can read
+pub fn negotiate_format(theirs: &[u8]) -> Option<u8> {
+    shared_formats(FORMAT_VERSIONS, theirs).last().cloned()
+}
+
+/// Writes the log in the newest version. Entries go out in tick order, whatever order they come in.
+pub fn encode<'a, I>(seed: Option<u64>, settings: Option<&str>, entries: I) -> Vec<u8>
+    where I: Iterator<Item = &'a CommandLogEntry>
+{
+    encode_as(*FORMAT_VERSIONS.last().unwrap(), seed, settings, entries)
+        .expect("the newest version can hold any log")
+}
+
+/// Writes the log in an older version, for peers that can't read the newest one. Fails if the
+/// version isn't one of `FORMAT_VERSIONS`, or the log holds something it didn't have yet.
+pub fn encode_as<'a, I>(version: u8,
+                        seed: Option<u64>,
+                        settings: Option<&str>,
+                        entries: I)
+                        -> Result<Vec<u8>, String>
+    where I: Iterator<Item = &'a CommandLogEntry>
+{
+    if !FORMAT_VERSIONS.contains(&version) {
+        return Err(format!("version {} isn't one this build knows", version));
+    }
+    let mut writer = Writer::for_format(version);
+    writer.bytes.extend(MAGIC);
+    writer.bytes.push(version);
+    match seed {
+        Some(seed) => {
+            writer.bytes.push(1);
+            writer.unsigned(seed);
+        }
+        None => writer.bytes.push(0),
+    }
+    match settings {
+        _ if version < SETTINGS_VERSION && settings.is_some() => {
+            return Err(format!("version {} can't hold settings", version));
+        }
+        _ if version < SETTINGS_VERSION => {}
+        Some(settings) => {
+            writer.bytes.push(1);
+            writer.text(settings);
+        }
+        None => writer.bytes.push(0),
+    }
+
+    // Ticks are stored as the gap since the previous entry, which can't go backwards
+    let mut entries: Vec<&CommandLogEntry> = entries.collect();
+    entries.sort_by_key(|entry| entry.tick);
+    writer.unsigned(entries.len() as u64);
+    let mut last_tick = 0;
+    for entry in entries {
+        if let Action::Build(_) = entry.action {
+            if version < BUILD_VERSION {
+                return Err(format!("version {} can't hold build actions", version));
+            }
+        }
+        writer.unsigned(entry.tick - last_tick);
+        writer.unsigned(entry.entity_id as u64);
+        writer.action(&entry.action);
+        last_tick = entry.tick;
+    }
+    Ok(writer.bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> io::Result<(Option<u64>, Option<String>, Vec<CommandLogEntry>)> {
+    let mut reader = Reader::new(bytes);
+    decode_log(&mut reader).map_err(|err| {
+        io::Error::new(io::ErrorKind::InvalidData,
+                       format!("binary command log byte {}: {}", reader.position, err))
+    })
+}
+
+fn decode_log(reader: &mut Reader) -> Result<(Option<u64>, Option<String>, Vec<CommandLogEntry>), String> {
+    for &byte in MAGIC {
+        if try!(reader.byte()) != byte {
+            return Err("not a binary command log".to_string());
+        }
+    }
+    let version = try!(reader.byte());
+    if !FORMAT_VERSIONS.contains(&version) {
+        return Err(format!("version {} is newer than this build understands", version));
+    }
+    reader.format = version;
+    let seed = match try!(reader.byte()) {
+        0 => None,
+        1 => Some(try!(reader.unsigned())),
+        flag => return Err(format!("invalid seed flag {}", flag)),
+    };
+    let settings = match version {
+        _ if version < SETTINGS_VERSION => None,
+        _ => {
+            match try!(reader.byte()) {
+                0 => None,
+                1 => Some(try!(reader.text())),
+                flag => return Err(format!("invalid settings flag {}", flag)),
+            }
+        }
+    };
+    let entry_count = try!(reader.unsigned());
+    let mut entries = Vec::new();
+    let mut tick = 0u64;
+    for _ in 0..entry_count {
+        tick = try!(tick.checked_add(try!(reader.unsigned())).ok_or_else(|| "tick overflow".to_string()));
+        let entity_id = try!(reader.index());
+        entries.push(CommandLogEntry {
+            tick: tick,
+            entity_id: entity_id,
+            action: try!(reader.action()),
+        });
+    }
+    if !reader.is_at_end() {
+        return Err("unexpected bytes after the last entry".to_string());
+    }
+    Ok((seed, settings, entries))
+}
+
+fn origin() -> PathNode {
+    Vector3::new(0.into(), 0.into(), 0.into())
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Writes the integers, text, and actions that binary command logs are made of, for other binary
+/// formats (such as saved games) to build on
+pub struct Writer {
+    bytes: Vec<u8>,
+    /// The command log version that nodes are written in
+    format: u8,
+}
+
+impl Writer {
+    /// Writes nodes the way the newest version does
+    pub fn new() -> Writer {
+        Writer::for_format(*FORMAT_VERSIONS.last().unwrap())
+    }
+
+    fn for_format(format: u8) -> Writer {
+        Writer {
+            bytes: Vec::new(),
+            format: format,
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn byte(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    /// Writes the bytes as they are, without their length
+    pub fn raw(&mut self, bytes: &[u8]) {
+        self.bytes.extend(bytes);
+    }
+
+    pub fn unsigned(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    pub fn signed(&mut self, value: i64) {
+        self.unsigned(zigzag(value));
+    }
+
+    /// Writes the bytes after their length
+    pub fn blob(&mut self, bytes: &[u8]) {
+        self.unsigned(bytes.len() as u64);
+        self.bytes.extend(bytes);
+    }
+
+    pub fn text(&mut self, text: &str) {
+        self.blob(text.as_bytes());
+    }
+
+    /// Writes the node as its offset from `previous` (wrapping, so that any node round trips)
+    pub fn node(&mut self, node: &PathNode, previous: &PathNode) {
+        self.offset(node.x.scaled.wrapping_sub(previous.x.scaled));
+        self.offset(node.y.scaled.wrapping_sub(previous.y.scaled));
+        self.offset(node.z.scaled.wrapping_sub(previous.z.scaled));
+    }
+
+    fn offset(&mut self, offset: i64) {
+        if self.format < HALF_TILE_VERSION {
+            self.signed(offset);
+        } else if offset & ((1 << HALF_TILE_BITS) - 1) == 0 {
+            self.unsigned(zigzag(offset >> HALF_TILE_BITS) << 1);
+        } else {
+            self.unsigned(SCALED_OFFSET);
+            self.signed(offset);
+        }
+    }
+
+    pub fn path(&mut self, path: &Path) {
+        self.unsigned(path.len() as u64);
+        let mut previous = origin();
+        for node in path {
+            self.node(node, &previous);
+            previous = *node;
+        }
+    }
+
+    pub fn action(&mut self, action: &Action) {
+        match *action {
+            Action::ClearQueue => self.bytes.push(TAG_CLEAR_QUEUE),
+            Action::MoveToPosition(ref params) => {
+                self.bytes.push(TAG_MOVE_TO_POSITION);
+                self.path(&params.path);
+            }
+            Action::AttackMove(ref params) => {
+                self.bytes.push(TAG_ATTACK_MOVE);
+                self.path(&params.path);
+            }
+            Action::AttackGround(ref params) => {
+                self.bytes.push(TAG_ATTACK_GROUND);
+                self.node(&params.target, &origin());
+            }
+            Action::AttackUnit(ref params) => {
+                self.bytes.push(TAG_ATTACK_UNIT);
+                self.unsigned(params.target_id as u64);
+            }
+            Action::Garrison(ref params) => {
+                self.bytes.push(TAG_GARRISON);
+                self.unsigned(params.building_id as u64);
+                self.bytes.push(params.town_bell as u8);
+            }
+            Action::Gather(ref params) => {
+                self.bytes.push(TAG_GATHER);
+                self.unsigned(params.target_id as u64);
+            }
+            Action::Build(ref params) => {
+                self.bytes.push(TAG_BUILD);
+                self.unsigned(*params.building_id as u64);
+                self.node(&params.position, &origin());
+            }
+            Action::Ungarrison => self.bytes.push(TAG_UNGARRISON),
+            Action::RememberQueue => self.bytes.push(TAG_REMEMBER_QUEUE),
+            Action::Kill => self.bytes.push(TAG_KILL),
+            Action::Resign => self.bytes.push(TAG_RESIGN),
+            Action::Research(research_id) => {
+                self.bytes.push(TAG_RESEARCH);
+                self.unsigned(*research_id as u64);
+            }
+            Action::Train(unit_id) => {
+                self.bytes.push(TAG_TRAIN);
+                self.unsigned(*unit_id as u64);
+            }
+        }
+    }
+}
+
+/// Reads back what a `Writer` wrote. Errors say what was wrong but not where; `position` is
+/// how far the reader got.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// The command log version that nodes are read in
+    format: u8,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads nodes the way the newest version writes them
+    pub fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader {
+            bytes: bytes,
+            position: 0,
+            format: *FORMAT_VERSIONS.last().unwrap(),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    pub fn byte(&mut self) -> Result<u8, String> {
+        let byte = try!(self.bytes.get(self.position).cloned().ok_or_else(|| "unexpected end".to_string()));
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub fn unsigned(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = try!(self.byte());
+            if shift > 63 || (shift == 63 && byte & 0x7e != 0) {
+                return Err("varint too long".to_string());
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Reads the given number of bytes as they are
+    pub fn raw(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if length > self.bytes.len() - self.position {
+            return Err("unexpected end".to_string());
+        }
+        let bytes = self.bytes;
+        let raw = &bytes[self.position..self.position + length];
+        self.position += length;
+        Ok(raw)
+    }
+
+    pub fn blob(&mut self) -> Result<&'a [u8], String> {
+        let length = try!(self.unsigned());
+        if length > (self.bytes.len() - self.position) as u64 {
+            return Err("unexpected end".to_string());
+        }
+        self.raw(length as usize)
+    }
+
+    pub fn text(&mut self) -> Result<String, String> {
+        let blob = try!(self.blob());
+        String::from_utf8(blob.to_vec()).map_err(|_| "invalid UTF-8".to_string())
+    }
+
+    pub fn signed(&mut self) -> Result<i64, String> {
+        Ok(unzigzag(try!(self.unsigned())))
+    }
+
+    pub fn index(&mut self) -> Result<Index, String> {
+        let value = try!(self.unsigned());
+        if value > Index::max_value() as u64 {
+            return Err(format!("id {} out of range", value));
+        }
+        Ok(value as Index)
+    }
+
+    pub fn id(&mut self) -> Result<u32, String> {
+        let value = try!(self.unsigned());
+        if value > u32::max_value() as u64 {
+            return Err(format!("id {} out of range", value));
+        }
+        Ok(value as u32)
+    }
+
+    pub fn node(&mut self, previous: &PathNode) -> Result<PathNode, String> {
+        let x = Fixed { scaled: previous.x.scaled.wrapping_add(try!(self.offset())) };
+        let y = Fixed { scaled: previous.y.scaled.wrapping_add(try!(self.offset())) };
+        let z = Fixed { scaled: previous.z.scaled.wrapping_add(try!(self.offset())) };
+        Ok(Vector3::new(x, y, z))
+    }
+
+    fn offset(&mut self) -> Result<i64, String> {
+        if self.format < HALF_TILE_VERSION {
+            return self.signed();
+        }
+        match try!(self.unsigned()) {
+            SCALED_OFFSET => self.signed(),
+            value if value & 1 == 0 => {
+                unzigzag(value >> 1)
+                    .checked_mul(1 << HALF_TILE_BITS)
+                    .ok_or_else(|| "node offset out of range".to_string())
+            }
+            value => Err(format!("invalid node offset {}", value)),
+        }
+    }
+
+    pub fn path(&mut self) -> Result<Path, String> {
+        let node_count = try!(self.unsigned());
+        let mut path = Vec::new();
+        let mut previous = origin();
+        for _ in 0..node_count {
+            let node = try!(self.node(&previous));
+            path.push(node);
+            previous = node;
+        }
+        Ok(path)
+    }
+
+    pub fn action(&mut self) -> Result<Action, String> {
+        let action = match try!(self.byte()) {
+            TAG_CLEAR_QUEUE => Action::ClearQueue,
+            TAG_MOVE_TO_POSITION => Action::MoveToPosition(MoveToPositionParams::new(try!(self.path()))),
+            TAG_ATTACK_MOVE => Action::AttackMove(AttackMoveParams::new(try!(self.path()))),
+            TAG_ATTACK_GROUND => Action::AttackGround(AttackGroundParams::new(try!(self.node(&origin())))),
+            TAG_ATTACK_UNIT => Action::AttackUnit(AttackUnitParams::new(try!(self.index()))),
+            TAG_GARRISON => {
+                let building_id = try!(self.index());
+                let town_bell = match try!(self.byte()) {
+                    0 => false,
+                    1 => true,
+                    flag => return Err(format!("invalid town bell flag {}", flag)),
+                };
+                Action::Garrison(GarrisonParams::new(building_id, town_bell))
+            }
+            TAG_GATHER => Action::Gather(GatherParams::new(try!(self.index()))),
+            TAG_BUILD => {
+                let building_id = UnitId::from(try!(self.id()) as usize);
+                Action::Build(BuildParams::new(building_id, try!(self.node(&origin()))))
+            }
+            TAG_UNGARRISON => Action::Ungarrison,
+            TAG_REMEMBER_QUEUE => Action::RememberQueue,
+            TAG_KILL => Action::Kill,
+            TAG_RESIGN => Action::Resign,
+            TAG_RESEARCH => Action::Research(ResearchId::from(try!(self.id()) as usize)),
+            TAG_TRAIN => Action::Train(UnitId::from(try!(self.id()) as usize)),
+            tag => return Err(format!("unknown action {}", tag)),
+        };
+        Ok(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use action::{Action, BuildParams, CommandLogEntry, MoveToPositionParams};
+    use super::{decode, encode, encode_as, negotiate_format};
+    use types::{Fixed, Vector3};
+
+    #[test]
+    fn test_path_deltas_stay_small() {
+        let path = (0..100).map(|x| Vector3::new(Fixed::from(x), Fixed::from(x / 2), 0.into())).collect();
+        let entries = vec![CommandLogEntry {
+                               tick: 5_000_000,
+                               entity_id: 70_000,
+                               action: Action::MoveToPosition(MoveToPositionParams::new(path)),
+                           }];
+        let bytes = encode(Some(u64::max_value()), None, entries.iter());
+        // Each step is a whole tile on x and every other one on y, so every coordinate takes a
+        // byte; the header and the entry's tick and id take less than 32 more
+        assert!(bytes.len() < 100 * 3 + 32);
+        assert_eq!((Some(u64::max_value()), None, entries), decode(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_odd_offsets_round_trip() {
+        let half = Fixed::from(1) / Fixed::from(2);
+        let (max, min) = (Fixed { scaled: i64::max_value() }, Fixed { scaled: i64::min_value() });
+        let path = vec![Vector3::new(half, Fixed::from(3) / Fixed::from(7), Fixed::from(-2)),
+                        Vector3::new(max, min, half),
+                        Vector3::new(0.into(), Fixed { scaled: 1 }, Fixed { scaled: -(1 << 23) })];
+        let entries = vec![CommandLogEntry {
+                               tick: 1,
+                               entity_id: 2,
+                               action: Action::MoveToPosition(MoveToPositionParams::new(path)),
+                           }];
+        assert_eq!((None, None, entries.clone()), decode(&encode(None, None, entries.iter())).unwrap());
+    }
+
+    #[test]
+    fn test_older_versions_round_trip() {
+        let path = vec![Vector3::new(Fixed::from(5) / Fixed::from(2), 1.into(), 0.into())];
+        let entries = vec![CommandLogEntry {
+                               tick: 4,
+                               entity_id: 9,
+                               action: Action::MoveToPosition(MoveToPositionParams::new(path)),
+                           }];
+        for &version in &[2, 3] {
+            let bytes = encode_as(version, Some(7), Some("victory=conquest"), entries.iter()).unwrap();
+            assert_eq!(version, bytes[4]);
+            assert_eq!((Some(7), Some("victory=conquest".to_string()), entries.clone()),
+                       decode(&bytes).unwrap());
+        }
+        let bytes = encode_as(1, Some(7), None, entries.iter()).unwrap();
+        assert_eq!((Some(7), None, entries.clone()), decode(&bytes).unwrap());
+
+        assert!(encode_as(1, None, Some("victory=conquest"), entries.iter()).is_err());
+        assert!(encode_as(0, None, None, entries.iter()).is_err());
+        let build = vec![CommandLogEntry {
+                             tick: 4,
+                             entity_id: 9,
+                             action: Action::Build(BuildParams::new(109.into(), Vector3::new(1.into(),
+                                                                                              1.into(),
+                                                                                              0.into()))),
+                         }];
+        assert!(encode_as(2, None, None, build.iter()).is_err());
+        assert!(encode_as(3, None, None, build.iter()).is_ok());
+    }
+
+    #[test]
+    fn test_entries_are_written_in_tick_order() {
+        let entry = |tick| {
+            CommandLogEntry {
+                tick: tick,
+                entity_id: 1,
+                action: Action::Kill,
+            }
+        };
+        let entries = vec![entry(5), entry(2), entry(5), entry(3)];
+        let (_, _, decoded) = decode(&encode(None, None, entries.iter())).unwrap();
+        assert_eq!(vec![2, 3, 5, 5], decoded.iter().map(|entry| entry.tick).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let entries = vec![CommandLogEntry {
+                               tick: 3,
+                               entity_id: 1,
+                               action: Action::Kill,
+                           }];
+        let bytes = encode(None, Some("victory=conquest"), entries.iter());
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode(&bytes[..12]).is_err());
+        assert!(decode(&[b'C', b'H', b'C', b'L', 200]).is_err());
+        assert!(decode(b"12 4 kill").is_err());
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(decode(&extra).is_err());
+        let mut unknown_action = bytes;
+        *unknown_action.last_mut().unwrap() = 99;
+        assert!(decode(&unknown_action).is_err());
+    }
+
+    #[test]
+    fn test_negotiate_format() {
+        assert_eq!(Some(4), negotiate_format(&[1, 2, 3, 4, 5]));
+        assert_eq!(Some(3), negotiate_format(&[3, 1]));
+        assert_eq!(Some(2), negotiate_format(&[1, 2]));
+        assert_eq!(Some(1), negotiate_format(&[1]));
+        assert_eq!(None, negotiate_format(&[5]));
+        assert_eq!(None, negotiate_format(&[]));
+    }
+}
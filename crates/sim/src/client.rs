@@ -0,0 +1,327 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, CommandLog};
+use dat::{EmpiresDbRef, ResourceType};
+use ecs::{self, GameSettings, GameSetup, HealthComponent, LineOfSightComponent, MapVisibility,
+          TransformComponent, UnitComponent, WorldPlanner};
+use ecs::resource::{ActionBatcher, Exploration, GameRng, PlayerTechs, Players, SimulationTick, Stockpiles,
+                    Terrain, Tile};
+use identifier::{PlayerId, UnitId};
+use scn;
+use specs::{self, Index, Join};
+use types::{Fixed, ToPrimitive, Vector3};
+
+/// A unit as a bot sees it
+#[derive(Clone, Debug)]
+pub struct UnitObservation {
+    /// What to give commands to (see `GameClient::command`)
+    pub entity_id: Index,
+    pub player_id: PlayerId,
+    pub unit_id: UnitId,
+    pub position: Vector3,
+    pub hit_points: Fixed,
+    pub max_hit_points: Fixed,
+}
+
+/// What the bot's player can see after a tick: their own units, everyone else's that are in the
+/// sight of units they share vision with, and gaia's (resources and animals) on tiles they've
+/// explored, the way the fog of war shows them to a player
+#[derive(Clone, Debug)]
+pub struct Observation {
+    pub tick: u64,
+    pub units: Vec<UnitObservation>,
+    /// Food, wood, stone, and gold
+    pub resources: [Fixed; 4],
+}
+
+/// The map's tiles, row by row, with `None` for the ones the player hasn't explored
+#[derive(Clone, Debug)]
+pub struct MapObservation {
+    pub width: i32,
+    pub height: i32,
+    pub tiles: Vec<Option<Tile>>,
+}
+
+/// Lets a program play a headless game as one of its players: it sees what the player sees after
+/// each tick (and nothing that the fog of war would hide from them), and gives commands through
+/// the ActionBatcher like the player's input does, so games played by bots are recorded and
+/// replay the same as any other.
+pub struct GameClient {
+    planner: WorldPlanner,
+    player_id: PlayerId,
+}
+
+impl GameClient {
+    /// Starts the game with the bot playing as the setup's local player. Only the simulation
+    /// runs; there's nothing to draw, so none of the view's systems are scheduled.
+    pub fn new(empires: EmpiresDbRef, scenario: &scn::Scenario, setup: &GameSetup) -> GameClient {
+        let mut planner = ecs::create_simulation_planner(&empires, scenario, setup);
+        let systems = ecs::simulation_systems(&empires);
+        ecs::schedule_systems(&mut planner, systems);
+        GameClient {
+            planner: planner,
+            player_id: setup.local_player_id,
+        }
+    }
+
+    pub fn player_id(&self) -> PlayerId {
+        self.player_id
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        let player_id = self.player_id;
+        let world = self.planner.mut_world();
+        let sights = sights(world, player_id);
+        let entities = world.entities();
+        let units = world.read::<UnitComponent>();
+        let transforms = world.read::<TransformComponent>();
+        let healths = world.read::<HealthComponent>();
+        let stockpiles = world.read_resource::<Stockpiles>();
+        let exploration = world.read_resource::<Exploration>();
+        let revealed = world.read_resource::<GameSettings>().map_visibility == MapVisibility::Revealed;
+
+        let resource_types =
+            [ResourceType::Food, ResourceType::Wood, ResourceType::Stone, ResourceType::Gold];
+        let mut resources = [Fixed::from(0); 4];
+        for (amount, &resource_type) in resources.iter_mut().zip(resource_types.iter()) {
+            *amount = stockpiles.amount(player_id, resource_type);
+        }
+
+        Observation {
+            tick: world.read_resource::<SimulationTick>().tick,
+            units: (&entities, &units, &transforms, &healths)
+                .iter()
+                .filter(|&(_, unit, transform, _)| {
+                    let (row, col) = tile_of(transform);
+                    unit.player_id == player_id || revealed || in_sight(&sights, row, col) ||
+                    (*unit.player_id == 0 && exploration.is_explored(player_id, row, col))
+                })
+                .map(|(entity, unit, transform, health)| {
+                    UnitObservation {
+                        entity_id: entity.get_id(),
+                        player_id: unit.player_id,
+                        unit_id: unit.unit_id,
+                        position: *transform.position(),
+                        hit_points: health.hit_points,
+                        max_hit_points: health.max_hit_points,
+                    }
+                })
+                .collect(),
+            resources: resources,
+        }
+    }
+
+    /// The map doesn't change during a game, but more of it is explored as the player's units
+    /// get around, so it's worth looking at again now and then
+    pub fn observe_map(&mut self) -> MapObservation {
+        let player_id = self.player_id;
+        let world = self.planner.mut_world();
+        let sights = sights(world, player_id);
+        let terrain = world.read_resource::<Terrain>();
+        let exploration = world.read_resource::<Exploration>();
+        let mut tiles = Vec::new();
+        for row in 0..terrain.height() {
+            for col in 0..terrain.width() {
+                if exploration.is_explored(player_id, row, col) || in_sight(&sights, row, col) {
+                    tiles.push(Some(*terrain.tile_at_row_col(row, col)));
+                } else {
+                    tiles.push(None);
+                }
+            }
+        }
+        MapObservation {
+            width: terrain.width(),
+            height: terrain.height(),
+            tiles: tiles,
+        }
+    }
+
+    /// Gives the action to one of the player's units on the next tick. Returns false (and does
+    /// nothing) if the entity isn't a unit the player owns.
+    pub fn command(&mut self, entity_id: Index, action: Action) -> bool {
+        let player_id = self.player_id;
+        let world = self.planner.mut_world();
+        let owned = {
+            let entities = world.entities();
+            let units = world.read::<UnitComponent>();
+            let players = world.read_resource::<Players>();
+            !players.is_observing() &&
+            (&entities, &units)
+                .iter()
+                .any(|(entity, unit)| entity.get_id() == entity_id && unit.player_id == player_id)
+        };
+        if owned {
+            world.write_resource::<ActionBatcher>().queue_for_entity(entity_id, action);
+        }
+        owned
+    }
+
+    /// Runs a single tick and returns which one it was
+    pub fn step(&mut self) -> u64 {
+        ecs::update_world(&mut self.planner, ecs::time_step_seconds());
+        self.planner.mut_world().read_resource::<SimulationTick>().tick
+    }
+
+    /// Every command given so far, with the seed and settings, to replay the game with
+    pub fn command_log(&mut self) -> CommandLog {
+        let world = self.planner.mut_world();
+        let mut commands = world.read_resource::<ActionBatcher>().history().clone();
+        commands.set_seed(world.read_resource::<GameRng>().seed());
+        commands.set_settings(&world.read_resource::<GameSettings>().to_field());
+        commands
+    }
+}
+
+/// The tile each unit is on, and how far it sees, for every unit whose vision the player shares
+fn sights(world: &specs::World, player_id: PlayerId) -> Vec<((i32, i32), i32)> {
+    let units = world.read::<UnitComponent>();
+    let transforms = world.read::<TransformComponent>();
+    let line_of_sights = world.read::<LineOfSightComponent>();
+    let players = world.read_resource::<Players>();
+    let vision_player_ids = players.vision_player_ids(&*world.read_resource::<PlayerTechs>(), player_id);
+    (&units, &transforms, &line_of_sights)
+        .iter()
+        .filter(|&(unit, _, _)| vision_player_ids.contains(&unit.player_id))
+        .map(|(_, transform, line_of_sight)| (tile_of(transform), line_of_sight.range.to_i32().unwrap()))
+        .collect()
+}
+
+/// Whether the tile is within any of the sights, the way the fog of war works it out
+fn in_sight(sights: &[((i32, i32), i32)], row: i32, col: i32) -> bool {
+    sights.iter().any(|&((center_row, center_col), radius)| {
+        let (row_offset, col_offset) = (row - center_row, col - center_col);
+        row_offset * row_offset + col_offset * col_offset <= radius * radius
+    })
+}
+
+fn tile_of(transform: &TransformComponent) -> (i32, i32) {
+    let position = transform.position();
+    (position.y.to_i32().unwrap(), position.x.to_i32().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use action::Action;
+    use dat::{EmpiresDb, EmpiresDbRef};
+    use ecs::{GameSetup, HealthComponent, LineOfSightComponent, TransformComponent, UnitComponent};
+    use ecs::resource::Exploration;
+    use identifier::PlayerId;
+    use scn::{Map, MapTile, Scenario};
+    use specs::Index;
+    use super::GameClient;
+    use types::{Fixed, Vector3};
+
+    /// A small map with gaia and one player on it, and no units
+    fn scenario() -> Scenario {
+        let mut scenario = Scenario::default();
+        scenario.player_data.player_names = vec!["Gaia".to_string(), "Bot".to_string()];
+        scenario.player_data.player_civs = vec![Default::default(); 2];
+        scenario.map = Map {
+            width: 4,
+            height: 3,
+            tiles: vec![MapTile::new(0.into(), 0); 12],
+        };
+        scenario.set_player_units(vec![Vec::new(), Vec::new()]);
+        scenario
+    }
+
+    fn client(seed: u64) -> GameClient {
+        GameClient::new(EmpiresDbRef::new(EmpiresDb::new()), &scenario(), &GameSetup::new(seed))
+    }
+
+    /// A unit that sees `sight` tiles around it. The test data doesn't have any units, so these
+    /// can't be ticked, only observed.
+    fn add_unit(client: &mut GameClient, player_id: usize, row: i32, col: i32, sight: i32) -> Index {
+        client.planner
+            .mut_world()
+            .create_now()
+            .with(UnitComponent::new(player_id.into(), 1.into(), 83.into()))
+            .with(TransformComponent::new(Vector3::new(col.into(), row.into(), 0.into()), 0.into()))
+            .with(HealthComponent::new(25.into()))
+            .with(LineOfSightComponent::new(sight.into()))
+            .build()
+            .get_id()
+    }
+
+    #[test]
+    fn test_headless_game() {
+        let mut client = client(7);
+        let player_id: PlayerId = 1.into();
+        assert_eq!(player_id, client.player_id());
+
+        let map = client.observe_map();
+        assert_eq!((4, 3, 12), (map.width, map.height, map.tiles.len()));
+
+        let observation = client.observe();
+        assert_eq!(0, observation.tick);
+        assert!(observation.units.is_empty());
+        assert_eq!([Fixed::from(0); 4], observation.resources);
+
+        // The camera is the first entity, but it isn't a unit that the player can command
+        assert!(!client.command(0, Action::ClearQueue));
+
+        assert_eq!(1, client.step());
+        assert_eq!(2, client.step());
+        assert_eq!(2, client.observe().tick);
+
+        let commands = client.command_log();
+        assert_eq!(Some(7), commands.seed());
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn test_fog_of_war() {
+        let mut scenario = scenario();
+        scenario.player_data.player_names.push("Enemy".to_string());
+        scenario.player_data.player_civs.push(Default::default());
+        scenario.map = Map {
+            width: 16,
+            height: 16,
+            tiles: vec![MapTile::new(0.into(), 0); 256],
+        };
+        scenario.set_player_units(vec![Vec::new(), Vec::new(), Vec::new()]);
+        let mut client = GameClient::new(EmpiresDbRef::new(EmpiresDb::new()), &scenario, &GameSetup::new(7));
+
+        let scout = add_unit(&mut client, 1, 2, 2, 3);
+        let seen = add_unit(&mut client, 2, 4, 3, 3);
+        let hidden = add_unit(&mut client, 2, 12, 12, 3);
+        let sheep = add_unit(&mut client, 0, 14, 2, 0);
+
+        // The enemy's own sight doesn't show their units to the bot
+        let entity_ids: Vec<Index> = client.observe().units.iter().map(|unit| unit.entity_id).collect();
+        assert!(entity_ids.contains(&scout));
+        assert!(entity_ids.contains(&seen));
+        assert!(!entity_ids.contains(&hidden));
+        assert!(!entity_ids.contains(&sheep));
+
+        let map = client.observe_map();
+        assert!(map.tiles[2 * 16 + 5].is_some());
+        assert!(map.tiles[2 * 16 + 6].is_none());
+        assert!(map.tiles[12 * 16 + 12].is_none());
+
+        // Gaia's units show on explored tiles even when nothing sees them anymore
+        client.planner.mut_world().write_resource::<Exploration>().explore(1.into(), 14, 2, 1);
+        let entity_ids: Vec<Index> = client.observe().units.iter().map(|unit| unit.entity_id).collect();
+        assert!(entity_ids.contains(&sheep));
+        assert!(client.observe_map().tiles[14 * 16 + 2].is_some());
+    }
+}
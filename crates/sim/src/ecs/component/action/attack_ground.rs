@@ -0,0 +1,47 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use specs;
+use types::{Fixed, Vector3};
+
+#[derive(Clone, Debug)]
+pub struct AttackGroundActionComponent {
+    pub target: Vector3,
+
+    /// Seconds until the unit can fire again
+    pub reload_time_left: Fixed,
+}
+
+impl specs::Component for AttackGroundActionComponent {
+    type Storage = specs::HashMapStorage<AttackGroundActionComponent>;
+}
+
+persist_struct!(AttackGroundActionComponent { target, reload_time_left });
+
+impl AttackGroundActionComponent {
+    pub fn new(target: Vector3) -> AttackGroundActionComponent {
+        AttackGroundActionComponent {
+            target: target,
+            reload_time_left: 0.into(),
+        }
+    }
+}
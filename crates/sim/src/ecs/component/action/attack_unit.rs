@@ -19,27 +19,29 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use specs;
-use super::super::world::SystemGroup;
+
+use specs::{self, Index};
 use types::Fixed;
 
-pub trait System: Send {
-    fn update(&mut self, arg: specs::RunArg, time_step: Fixed);
-}
+#[derive(Clone, Debug)]
+pub struct AttackUnitActionComponent {
+    pub target_id: Index,
 
-pub struct SystemWrapper(Box<System>);
+    /// Seconds until the unit can attack again
+    pub reload_time_left: Fixed,
+}
 
-impl SystemWrapper {
-    pub fn new(system: Box<System>) -> SystemWrapper {
-        SystemWrapper(system)
-    }
+impl specs::Component for AttackUnitActionComponent {
+    type Storage = specs::HashMapStorage<AttackUnitActionComponent>;
 }
 
-impl specs::System<(SystemGroup, Fixed)> for SystemWrapper {
-    fn run(&mut self, arg: specs::RunArg, params: (SystemGroup, Fixed)) {
-        match params.0 {
-            SystemGroup::Normal => self.0.update(arg, params.1),
-            _ => arg.fetch(|_| {}),
+persist_struct!(AttackUnitActionComponent { target_id, reload_time_left });
+
+impl AttackUnitActionComponent {
+    pub fn new(target_id: Index) -> AttackUnitActionComponent {
+        AttackUnitActionComponent {
+            target_id: target_id,
+            reload_time_left: 0.into(),
         }
     }
 }
@@ -0,0 +1,51 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::ResourceType;
+use specs::{self, Index};
+
+#[derive(Clone, Debug)]
+pub struct GatherActionComponent {
+    pub target_id: Index,
+
+    /// What the target holds, for finding another one like it once it runs out
+    pub resource_type: Option<ResourceType>,
+
+    /// Whether the target rots (a carcass), so once it's gone the gatherer takes what it has home
+    /// before it goes looking for more
+    pub perishable: bool,
+}
+
+impl specs::Component for GatherActionComponent {
+    type Storage = specs::HashMapStorage<GatherActionComponent>;
+}
+
+persist_struct!(GatherActionComponent { target_id, resource_type, perishable });
+
+impl GatherActionComponent {
+    pub fn new(target_id: Index) -> GatherActionComponent {
+        GatherActionComponent {
+            target_id: target_id,
+            resource_type: None,
+            perishable: false,
+        }
+    }
+}
@@ -31,6 +31,8 @@ impl specs::Component for MoveToPositionActionComponent {
     type Storage = specs::HashMapStorage<MoveToPositionActionComponent>;
 }
 
+persist_struct!(MoveToPositionActionComponent { path });
+
 impl MoveToPositionActionComponent {
     pub fn new(path: Path) -> MoveToPositionActionComponent {
         MoveToPositionActionComponent { path: path }
@@ -22,6 +22,8 @@
 use action::Action;
 
 use specs;
+use std::mem;
+use types::Vector3;
 
 #[derive(Clone, Debug)]
 pub struct ActionQueueComponent {
@@ -31,18 +33,24 @@ pub struct ActionQueueComponent {
     actions: Vec<Action>,
     current_action: Option<Action>,
     current_action_done: bool,
+
+    /// What the unit was doing before the town bell rang
+    remembered: Vec<Action>,
 }
 
 impl specs::Component for ActionQueueComponent {
     type Storage = specs::VecStorage<ActionQueueComponent>;
 }
 
+persist_struct!(ActionQueueComponent { actions, current_action, current_action_done, remembered });
+
 impl ActionQueueComponent {
     pub fn new() -> ActionQueueComponent {
         ActionQueueComponent {
             actions: Vec::new(),
             current_action: None,
             current_action_done: true,
+            remembered: Vec::new(),
         }
     }
 
@@ -66,6 +74,34 @@ impl ActionQueueComponent {
         self.current_action_done = true;
     }
 
+    /// Saves the current and queued actions, then clears the queue.
+    /// This should only ever be called by UnitActionSystem
+    pub fn remember(&mut self) {
+        self.remembered.clear();
+        if !self.current_action_done {
+            if let Some(ref action) = self.current_action {
+                self.remembered.push(action.clone());
+            }
+        }
+        self.remembered.extend(self.actions.iter().cloned());
+        self.clear();
+    }
+
+    /// Queues up whatever was saved by `remember` again
+    pub fn resume(&mut self) {
+        let remembered = mem::replace(&mut self.remembered, Vec::new());
+        self.actions.extend(remembered);
+    }
+
+    /// Ends the current action and puts the given actions ahead of everything else in the queue.
+    /// This is for action systems that have to take a detour (a villager walking to a drop site
+    /// and back), which is fine without the ActionBatcher since every peer simulates it the same.
+    pub fn interrupt(&mut self, actions: Vec<Action>) {
+        let queued = mem::replace(&mut self.actions, actions);
+        self.actions.extend(queued);
+        self.current_action_done = true;
+    }
+
     /// This should only ever be called by UnitActionSystem
     pub fn current_action_done(&self) -> bool {
         self.current_action_done
@@ -76,6 +112,31 @@ impl ActionQueueComponent {
         &self.current_action
     }
 
+    /// Number of actions waiting behind the current one
+    pub fn pending_len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Where the unit is headed next, for the current action (if it isn't done) and then each of
+    /// the queued ones in order. Actions that don't go anywhere in particular are skipped.
+    pub fn waypoints(&self) -> Vec<Vector3> {
+        let current = if self.current_action_done {
+            None
+        } else {
+            self.current_action.as_ref()
+        };
+        current.into_iter()
+            .chain(self.actions.iter())
+            .filter_map(|action| match *action {
+                Action::MoveToPosition(ref params) => params.path.last().cloned(),
+                Action::AttackMove(ref params) => params.path.last().cloned(),
+                Action::AttackGround(ref params) => Some(params.target),
+                Action::Build(ref params) => Some(params.position),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// This should only ever be called by UnitActionSystem
     pub fn next_action(&mut self) {
         if !self.actions.is_empty() {
@@ -27,3 +27,5 @@ pub struct CameraComponent;
 impl specs::Component for CameraComponent {
     type Storage = specs::NullStorage<CameraComponent>;
 }
+
+persist_struct!(CameraComponent);
@@ -0,0 +1,52 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::ResourceType;
+use identifier::GraphicId;
+use specs;
+use types::Fixed;
+
+/// What a gatherer has on it that hasn't been dropped off yet. It sticks around (empty) after
+/// dropping off, so the gatherer remembers what kind of resource it was working on.
+#[derive(Clone, Debug)]
+pub struct CarryComponent {
+    pub resource_type: ResourceType,
+    pub amount: Fixed,
+
+    /// Walking graphic for while the unit is carrying it (a villager with a basket of fish)
+    pub graphic_id: Option<GraphicId>,
+}
+
+impl specs::Component for CarryComponent {
+    type Storage = specs::HashMapStorage<CarryComponent>;
+}
+
+persist_struct!(CarryComponent { resource_type, amount, graphic_id });
+
+impl CarryComponent {
+    pub fn new(resource_type: ResourceType, graphic_id: Option<GraphicId>) -> CarryComponent {
+        CarryComponent {
+            resource_type: resource_type,
+            amount: 0.into(),
+            graphic_id: graphic_id,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use specs::{self, Index};
+use types::Vector3;
+
+/// Marks a unit that's inside a building. Garrisoned units are taken out of the grid partition,
+/// so they aren't drawn, selected, or hit by blasts, and lose their velocity until they leave.
+#[derive(Clone, Debug)]
+pub struct GarrisonedComponent {
+    pub building_id: Index,
+
+    /// Where the unit went in, which is where it comes back out
+    pub entrance: Vector3,
+
+    /// Whether the town bell sent the unit in
+    pub town_bell: bool,
+
+    /// Set by `Action::Ungarrison`; the unit leaves on the next update
+    pub leaving: bool,
+}
+
+impl specs::Component for GarrisonedComponent {
+    type Storage = specs::HashMapStorage<GarrisonedComponent>;
+}
+
+persist_struct!(GarrisonedComponent { building_id, entrance, town_bell, leaving });
+
+impl GarrisonedComponent {
+    pub fn new(building_id: Index, entrance: Vector3, town_bell: bool) -> GarrisonedComponent {
+        GarrisonedComponent {
+            building_id: building_id,
+            entrance: entrance,
+            town_bell: town_bell,
+            leaving: false,
+        }
+    }
+}
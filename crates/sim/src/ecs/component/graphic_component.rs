@@ -19,13 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use identifier::{GraphicId, PlayerColorId};
+use identifier::{GraphicId, PlayerId};
 use specs;
 use types::Fixed;
 
 #[derive(Clone, Debug)]
 pub struct GraphicComponent {
-    pub player_color_id: PlayerColorId,
+    /// Whose colors the graphic is drawn in. The color itself is looked up when rendering
+    /// (see `Players::sprite_color`), so it's always the one the player picked.
+    pub player_id: PlayerId,
     pub graphic_id: Option<GraphicId>,
     pub frame: u16,
     pub frame_time: Fixed,
@@ -37,10 +39,19 @@ impl specs::Component for GraphicComponent {
     type Storage = specs::VecStorage<GraphicComponent>;
 }
 
+persist_struct!(GraphicComponent {
+    player_id,
+    graphic_id,
+    frame,
+    frame_time,
+    flip_horizontal,
+    flip_vertical
+});
+
 impl GraphicComponent {
     pub fn new() -> GraphicComponent {
         GraphicComponent {
-            player_color_id: 0.into(),
+            player_id: 0.into(),
             graphic_id: None,
             frame: 0u16,
             frame_time: 0.into(),
@@ -0,0 +1,57 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::PlayerId;
+use specs::{self, Index};
+use types::Fixed;
+
+#[derive(Clone, Debug)]
+pub struct HealthComponent {
+    pub hit_points: Fixed,
+    pub max_hit_points: Fixed,
+
+    /// The player whose unit last damaged this one; they get the credit if it dies
+    pub last_attacker: Option<PlayerId>,
+
+    /// The unit that last damaged this one, until something reacts to it (a boar charging back)
+    pub last_attacker_id: Option<Index>,
+}
+
+impl specs::Component for HealthComponent {
+    type Storage = specs::VecStorage<HealthComponent>;
+}
+
+persist_struct!(HealthComponent { hit_points, max_hit_points, last_attacker, last_attacker_id });
+
+impl HealthComponent {
+    pub fn new(max_hit_points: Fixed) -> HealthComponent {
+        HealthComponent {
+            hit_points: max_hit_points,
+            max_hit_points: max_hit_points,
+            last_attacker: None,
+            last_attacker_id: None,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hit_points <= 0.into()
+    }
+}
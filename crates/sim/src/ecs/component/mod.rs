@@ -21,22 +21,40 @@
 
 mod action;
 mod action_queue_component;
+mod artifact_component;
 mod camera_component;
-mod decal_component;
+mod carry_component;
+mod construction_component;
+mod decay_component;
+mod garrisoned_component;
 mod graphic_component;
+mod health_component;
+mod line_of_sight_component;
 mod on_screen_component;
+mod projectile_component;
+mod resource_component;
 mod selected_unit_component;
+mod training_queue_component;
 mod transform_component;
 mod unit_component;
 mod velocity_component;
 
 pub use self::action::*;
 pub use self::action_queue_component::ActionQueueComponent;
+pub use self::artifact_component::ArtifactComponent;
 pub use self::camera_component::CameraComponent;
-pub use self::decal_component::DecalComponent;
+pub use self::carry_component::CarryComponent;
+pub use self::construction_component::ConstructionComponent;
+pub use self::decay_component::DecayComponent;
+pub use self::garrisoned_component::GarrisonedComponent;
 pub use self::graphic_component::GraphicComponent;
+pub use self::health_component::HealthComponent;
+pub use self::line_of_sight_component::LineOfSightComponent;
 pub use self::on_screen_component::OnScreenComponent;
+pub use self::projectile_component::ProjectileComponent;
+pub use self::resource_component::ResourceComponent;
 pub use self::selected_unit_component::SelectedUnitComponent;
+pub use self::training_queue_component::{MAX_TRAINING_QUEUE_LENGTH, Production, TrainingQueueComponent};
 pub use self::transform_component::TransformComponent;
 pub use self::unit_component::UnitComponent;
 pub use self::velocity_component::VelocityComponent;
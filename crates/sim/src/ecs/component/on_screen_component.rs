@@ -28,3 +28,5 @@ pub struct OnScreenComponent;
 impl specs::Component for OnScreenComponent {
     type Storage = specs::NullStorage<OnScreenComponent>;
 }
+
+persist_struct!(OnScreenComponent);
@@ -0,0 +1,75 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use identifier::PlayerId;
+use specs;
+use types::{Fixed, Vector3};
+
+/// Something fired at a spot, which damages whatever is there when it lands
+#[derive(Clone, Debug)]
+pub struct ProjectileComponent {
+    /// Player whose unit fired it; they get the credit for anything it kills
+    pub owner_id: PlayerId,
+    pub target: Vector3,
+
+    /// Attack classes and amounts, as in empires.dat
+    pub attacks: Vec<(i16, i16)>,
+
+    /// Radius, in tiles, of the area damaged when it lands (zero only hits the target spot)
+    pub blast_radius: Fixed,
+    pub blast_attack_level: i8,
+
+    /// Seconds until it lands
+    pub time_left: Fixed,
+}
+
+impl specs::Component for ProjectileComponent {
+    type Storage = specs::HashMapStorage<ProjectileComponent>;
+}
+
+persist_struct!(ProjectileComponent {
+    owner_id,
+    target,
+    attacks,
+    blast_radius,
+    blast_attack_level,
+    time_left
+});
+
+impl ProjectileComponent {
+    pub fn new(owner_id: PlayerId,
+               target: Vector3,
+               attacks: Vec<(i16, i16)>,
+               blast_radius: Fixed,
+               blast_attack_level: i8,
+               flight_time: Fixed)
+               -> ProjectileComponent {
+        ProjectileComponent {
+            owner_id: owner_id,
+            target: target,
+            attacks: attacks,
+            blast_radius: blast_radius,
+            blast_attack_level: blast_attack_level,
+            time_left: flight_time,
+        }
+    }
+}
@@ -19,32 +19,32 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use identifier::{PlayerColorId, SlpFileId};
-use resource::DrsKey;
+use dat::ResourceType;
 use specs;
 use types::Fixed;
 
+/// What's left in a tree, mine, bush, carcass, or fish
 #[derive(Clone, Debug)]
-pub struct DecalComponent {
-    pub player_color_id: PlayerColorId,
-    pub drs_key: DrsKey,
-    pub slp_file_id: SlpFileId,
-    pub frame: u16,
-    pub frame_time: Fixed,
+pub struct ResourceComponent {
+    pub resource_type: ResourceType,
+    pub amount: Fixed,
+
+    /// How much is lost every second whether or not anyone gathers it (meat rotting on a carcass)
+    pub rot_rate: Fixed,
 }
 
-impl specs::Component for DecalComponent {
-    type Storage = specs::HashMapStorage<DecalComponent>;
+impl specs::Component for ResourceComponent {
+    type Storage = specs::HashMapStorage<ResourceComponent>;
 }
 
-impl DecalComponent {
-    pub fn new(player_color_id: PlayerColorId, drs_key: DrsKey, slp_file_id: SlpFileId) -> DecalComponent {
-        DecalComponent {
-            player_color_id: player_color_id,
-            drs_key: drs_key,
-            slp_file_id: slp_file_id,
-            frame: 0u16,
-            frame_time: 0.into(),
+persist_struct!(ResourceComponent { resource_type, amount, rot_rate });
+
+impl ResourceComponent {
+    pub fn new(resource_type: ResourceType, amount: Fixed) -> ResourceComponent {
+        ResourceComponent {
+            resource_type: resource_type,
+            amount: amount,
+            rot_rate: 0.into(),
         }
     }
 }
@@ -28,3 +28,5 @@ pub struct SelectedUnitComponent;
 impl specs::Component for SelectedUnitComponent {
     type Storage = specs::NullStorage<SelectedUnitComponent>;
 }
+
+persist_struct!(SelectedUnitComponent);
@@ -0,0 +1,168 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use action::{Reader, Writer};
+use dat::ResourceType;
+use ecs::Persist;
+use identifier::{ResearchId, UnitId};
+use specs;
+use types::Fixed;
+
+/// Most units a building can have waiting to be trained (including the one in training)
+pub const MAX_TRAINING_QUEUE_LENGTH: usize = 5;
+
+/// Something a building makes: a unit it trains or a technology it researches
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Production {
+    Unit(UnitId),
+    Research(ResearchId),
+}
+
+impl Persist for Production {
+    fn write(&self, writer: &mut Writer) {
+        match *self {
+            Production::Unit(unit_id) => {
+                writer.byte(0);
+                unit_id.write(writer);
+            }
+            Production::Research(research_id) => {
+                writer.byte(1);
+                research_id.write(writer);
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Production, String> {
+        match try!(reader.byte()) {
+            0 => Ok(Production::Unit(try!(UnitId::read(reader)))),
+            1 => Ok(Production::Research(try!(ResearchId::read(reader)))),
+            tag => Err(format!("unknown production {}", tag)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct QueuedProduction {
+    production: Production,
+
+    /// What the player paid for it, which they get back if it's cancelled
+    paid: Vec<(ResourceType, i16)>,
+}
+
+persist_struct!(QueuedProduction { production, paid });
+
+/// Units and research waiting at a building, first in first out. They're paid for when they're
+/// queued.
+#[derive(Clone, Debug)]
+pub struct TrainingQueueComponent {
+    queue: Vec<QueuedProduction>,
+
+    /// Seconds spent on the production at the front of the queue
+    pub progress: Fixed,
+}
+
+impl specs::Component for TrainingQueueComponent {
+    type Storage = specs::HashMapStorage<TrainingQueueComponent>;
+}
+
+persist_struct!(TrainingQueueComponent { queue, progress });
+
+impl TrainingQueueComponent {
+    pub fn new() -> TrainingQueueComponent {
+        TrainingQueueComponent {
+            queue: Vec::new(),
+            progress: 0.into(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.len() >= MAX_TRAINING_QUEUE_LENGTH
+    }
+
+    pub fn contains(&self, production: Production) -> bool {
+        self.queue.iter().any(|queued| queued.production == production)
+    }
+
+    /// Adds the production to the end of the queue, unless the queue is full
+    pub fn push(&mut self, production: Production, paid: Vec<(ResourceType, i16)>) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.queue.push(QueuedProduction {
+            production: production,
+            paid: paid,
+        });
+        true
+    }
+
+    /// The production being worked on right now
+    pub fn current(&self) -> Option<Production> {
+        self.queue.first().map(|queued| queued.production)
+    }
+
+    /// Takes the production being worked on off the front of the queue and starts on the next one
+    pub fn finish_current(&mut self) -> Option<Production> {
+        self.progress = 0.into();
+        if self.queue.is_empty() {
+            None
+        } else {
+            Some(self.queue.remove(0).production)
+        }
+    }
+
+    /// Empties the queue, and returns everything that was paid for what was in it
+    pub fn cancel_all(&mut self) -> Vec<(ResourceType, i16)> {
+        self.progress = 0.into();
+        self.queue.drain(..).flat_map(|queued| queued.paid.into_iter()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dat::ResourceType;
+    use super::{MAX_TRAINING_QUEUE_LENGTH, Production, TrainingQueueComponent};
+
+    #[test]
+    fn test_cancel_all_returns_what_was_paid() {
+        let mut queue = TrainingQueueComponent::new();
+        let villager = Production::Unit(83.into());
+        assert!(queue.push(villager, vec![(ResourceType::Food, 50)]));
+        assert!(queue.push(Production::Research(46.into()),
+                           vec![(ResourceType::Food, 100), (ResourceType::Gold, 40)]));
+        for _ in 2..MAX_TRAINING_QUEUE_LENGTH {
+            assert!(queue.push(villager, Vec::new()));
+        }
+        assert!(!queue.push(villager, vec![(ResourceType::Food, 50)]));
+        assert!(queue.contains(Production::Research(46.into())));
+
+        queue.progress = 3.into();
+        assert_eq!(vec![(ResourceType::Food, 50), (ResourceType::Food, 100), (ResourceType::Gold, 40)],
+                   queue.cancel_all());
+        assert_eq!(0, queue.len());
+        assert_eq!(None, queue.current());
+        assert!(queue.cancel_all().is_empty());
+    }
+}
@@ -33,6 +33,8 @@ impl specs::Component for TransformComponent {
     type Storage = specs::VecStorage<TransformComponent>;
 }
 
+persist_struct!(TransformComponent { current_position, last_position, rotation });
+
 impl TransformComponent {
     pub fn new(position: Vector3, rotation: Fixed) -> TransformComponent {
         TransformComponent {
@@ -51,7 +53,24 @@ impl TransformComponent {
         self.current_position = position;
     }
 
+    /// Turns to face along the given ground direction. A zero direction keeps the current
+    /// facing so that units that stop moving don't snap back to facing east.
+    pub fn face_direction(&mut self, x: Fixed, y: Fixed) {
+        if x != 0.into() || y != 0.into() {
+            self.rotation = Fixed::atan2(y, x);
+        }
+    }
+
+    /// Turns to face the given point on the ground
+    pub fn face_towards(&mut self, target: &Vector3) {
+        let (x, y) = (target.x - self.current_position.x, target.y - self.current_position.y);
+        self.face_direction(x, y);
+    }
+
+    /// Position to render at, `lerp` of the way from the previous tick's position to the current one.
+    /// Rendering up to a tick behind (rather than guessing ahead) means that units that stop or turn
+    /// never overshoot and then snap back.
     pub fn lerped_position(&self, lerp: Fixed) -> Vector3 {
-        self.current_position + (self.current_position - self.last_position) * lerp
+        self.last_position + (self.current_position - self.last_position) * lerp
     }
 }
@@ -34,6 +34,8 @@ impl specs::Component for UnitComponent {
     type Storage = specs::VecStorage<UnitComponent>;
 }
 
+persist_struct!(UnitComponent { player_id, civilization_id, unit_id });
+
 impl UnitComponent {
     pub fn new(player_id: PlayerId, civilization_id: CivilizationId, unit_id: UnitId) -> UnitComponent {
         UnitComponent {
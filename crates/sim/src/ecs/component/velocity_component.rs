@@ -31,6 +31,8 @@ impl specs::Component for VelocityComponent {
     type Storage = specs::VecStorage<VelocityComponent>;
 }
 
+persist_struct!(VelocityComponent { velocity });
+
 impl VelocityComponent {
     pub fn new() -> VelocityComponent {
         VelocityComponent { velocity: Vector3::new(0.into(), 0.into(), 0.into()) }
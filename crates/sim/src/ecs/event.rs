@@ -0,0 +1,113 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::{PlayerId, ResearchId, UnitId};
+use specs::Index;
+use types::{Fixed, Vector3};
+
+/// A unit was killed or deleted; its entity is removed at the end of the tick
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitDied {
+    pub entity_id: Index,
+    pub player_id: PlayerId,
+    pub unit_id: UnitId,
+}
+
+persist_struct!(UnitDied { entity_id, player_id, unit_id });
+
+/// A unit was handed over to another player (a captured artifact, or a discovered building)
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitConverted {
+    pub entity_id: Index,
+    pub from_player_id: PlayerId,
+    pub to_player_id: PlayerId,
+}
+
+persist_struct!(UnitConverted { entity_id, from_player_id, to_player_id });
+
+/// A building finished construction
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildingCompleted {
+    pub entity_id: Index,
+    pub player_id: PlayerId,
+    pub unit_id: UnitId,
+}
+
+persist_struct!(BuildingCompleted { entity_id, player_id, unit_id });
+
+/// A player finished researching a technology at a building
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResearchCompleted {
+    pub entity_id: Index,
+    pub player_id: PlayerId,
+    pub research_id: ResearchId,
+}
+
+persist_struct!(ResearchCompleted { entity_id, player_id, research_id });
+
+/// Something hit hard enough to hurt everything around it: a catapult stone landing, or an
+/// elephant trampling the units next to the one it attacked
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blast {
+    pub center: Vector3,
+
+    /// In tiles
+    pub radius: Fixed,
+
+    /// Attack classes and amounts, as in empires.dat; units take less the further out they are
+    pub attacks: Vec<(i16, i16)>,
+
+    /// Blast attack level from empires.dat; see `util::combat::blast_hits`
+    pub attack_level: i8,
+    pub attacker_id: PlayerId,
+
+    /// A unit that was already hit directly by the attack, so the blast leaves it alone
+    pub primary_target_id: Option<Index>,
+}
+
+persist_struct!(Blast { center, radius, attacks, attack_level, attacker_id, primary_target_id });
+
+/// A resource (tree, mine, bush, carcass, or fish) ran out
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceDepleted {
+    pub entity_id: Index,
+    pub unit_id: UnitId,
+}
+
+persist_struct!(ResourceDepleted { entity_id, unit_id });
+
+/// A player gave up; the VictorySystem takes them out of the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerResigned {
+    pub player_id: PlayerId,
+}
+
+persist_struct!(PlayerResigned { player_id });
+
+/// A player is out of the game, having resigned or lost every unit and every building that
+/// could make more
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerDefeated {
+    pub player_id: PlayerId,
+    pub resigned: bool,
+}
+
+persist_struct!(PlayerDefeated { player_id, resigned });
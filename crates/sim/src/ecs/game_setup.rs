@@ -0,0 +1,380 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::resource::{GameRng, Script};
+use identifier::{CivilizationId, PlayerColorId, PlayerId};
+use std::collections::BTreeMap;
+
+/// What players start the game with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StartingResources {
+    /// Whatever the scenario gives each player
+    Scenario,
+    Low,
+    Medium,
+    High,
+}
+
+impl StartingResources {
+    /// Food, wood, stone, and gold that every player starts with, if the preset overrides the scenario
+    pub fn amounts(&self) -> Option<[u32; 4]> {
+        match *self {
+            StartingResources::Scenario => None,
+            StartingResources::Low => Some([200, 200, 150, 0]),
+            StartingResources::Medium => Some([500, 500, 250, 250]),
+            StartingResources::High => Some([1000, 1000, 750, 750]),
+        }
+    }
+}
+
+/// How much of the map players can see from the start
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MapVisibility {
+    /// Only what the players' units see is explored
+    Normal,
+    /// The terrain is explored, but units outside of line of sight are hidden
+    Explored,
+    /// Everything is visible all the time
+    Revealed,
+}
+
+/// Whether lobby players play from the scenario's player slots in order or are shuffled
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StartingPositions {
+    Fixed,
+    Random,
+}
+
+/// How the game can be won
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VictoryCondition {
+    /// By conquest, holding the artifacts and ruins, or building a wonder
+    Standard,
+    /// Only by conquest; artifact, ruin, and wonder countdowns never start
+    Conquest,
+}
+
+/// How well a computer player plays
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easiest,
+    Easy,
+    Moderate,
+    Hard,
+    Hardest,
+}
+
+impl Difficulty {
+    pub fn all() -> &'static [Difficulty] {
+        use self::Difficulty::*;
+        static ALL: [Difficulty; 5] = [Easiest, Easy, Moderate, Hard, Hardest];
+        &ALL
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easiest => "easiest",
+            Difficulty::Easy => "easy",
+            Difficulty::Moderate => "moderate",
+            Difficulty::Hard => "hard",
+            Difficulty::Hardest => "hardest",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::all().iter().cloned().find(|difficulty| difficulty.name() == name)
+    }
+
+    /// Food, wood, stone, and gold that the computer gets on top of everyone else. Only the
+    /// hardest computers cheat, like in the original.
+    pub fn bonus_resources(&self) -> [u32; 4] {
+        match *self {
+            Difficulty::Hardest => [500, 500, 250, 250],
+            _ => [0, 0, 0, 0],
+        }
+    }
+}
+
+/// The starting conditions picked before the game. Everyone in a game (and anyone replaying it)
+/// has to play with the same settings, so they're sent with the lobby's settings and recorded
+/// with the commands.
+///
+/// The text form is a single comma separated field, such as
+/// `resources=high,map=explored,positions=random,victory=conquest,difficulty.2=hard,handicap.1=75`.
+/// Settings that are left out keep their defaults.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSettings {
+    pub starting_resources: StartingResources,
+    pub map_visibility: MapVisibility,
+    pub starting_positions: StartingPositions,
+    pub victory_condition: VictoryCondition,
+    // TODO: There's no AI to play computer players yet. It should react slower and aim for a smaller
+    // economy the easier the difficulty is.
+    /// The computer players and how well they play; everyone else is played by a person
+    pub difficulties: BTreeMap<PlayerId, Difficulty>,
+    /// Percent of the usual starting resources that each player gets, where it isn't 100
+    pub handicaps: BTreeMap<PlayerId, u32>,
+}
+
+impl GameSettings {
+    pub fn new() -> GameSettings {
+        GameSettings {
+            starting_resources: StartingResources::Scenario,
+            map_visibility: MapVisibility::Normal,
+            starting_positions: StartingPositions::Fixed,
+            victory_condition: VictoryCondition::Standard,
+            difficulties: BTreeMap::new(),
+            handicaps: BTreeMap::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<GameSettings, String> {
+        let mut settings = GameSettings::new();
+        for setting in text.split(',').filter(|setting| !setting.is_empty()) {
+            let (key, value) = match setting.find('=') {
+                Some(index) => (&setting[..index], &setting[index + 1..]),
+                None => return Err(format!("setting \"{}\" has no value", setting)),
+            };
+            if key.starts_with("difficulty.") || key.starts_with("handicap.") {
+                let invalid = || format!("invalid setting \"{}\"", setting);
+                let (name, player) = key.split_at(key.find('.').unwrap());
+                let player_id: PlayerId = match player[1..].parse::<u8>() {
+                    Ok(player) if player > 0 => (player as usize).into(),
+                    _ => return Err(invalid()),
+                };
+                if name == "difficulty" {
+                    let difficulty = try!(Difficulty::from_name(value).ok_or_else(|| invalid()));
+                    settings.difficulties.insert(player_id, difficulty);
+                } else {
+                    let percent = try!(value.parse::<u32>().map_err(|_| invalid()));
+                    settings.handicaps.insert(player_id, percent);
+                }
+                continue;
+            }
+            match (key, value) {
+                ("resources", "scenario") => settings.starting_resources = StartingResources::Scenario,
+                ("resources", "low") => settings.starting_resources = StartingResources::Low,
+                ("resources", "medium") => settings.starting_resources = StartingResources::Medium,
+                ("resources", "high") => settings.starting_resources = StartingResources::High,
+                ("map", "normal") => settings.map_visibility = MapVisibility::Normal,
+                ("map", "explored") => settings.map_visibility = MapVisibility::Explored,
+                ("map", "revealed") => settings.map_visibility = MapVisibility::Revealed,
+                ("positions", "fixed") => settings.starting_positions = StartingPositions::Fixed,
+                ("positions", "random") => settings.starting_positions = StartingPositions::Random,
+                ("victory", "standard") => settings.victory_condition = VictoryCondition::Standard,
+                ("victory", "conquest") => settings.victory_condition = VictoryCondition::Conquest,
+                _ => return Err(format!("invalid setting \"{}\"", setting)),
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn to_field(&self) -> String {
+        let resources = match self.starting_resources {
+            StartingResources::Scenario => "scenario",
+            StartingResources::Low => "low",
+            StartingResources::Medium => "medium",
+            StartingResources::High => "high",
+        };
+        let map = match self.map_visibility {
+            MapVisibility::Normal => "normal",
+            MapVisibility::Explored => "explored",
+            MapVisibility::Revealed => "revealed",
+        };
+        let positions = match self.starting_positions {
+            StartingPositions::Fixed => "fixed",
+            StartingPositions::Random => "random",
+        };
+        let victory = match self.victory_condition {
+            VictoryCondition::Standard => "standard",
+            VictoryCondition::Conquest => "conquest",
+        };
+        let mut field = format!("resources={},map={},positions={},victory={}",
+                                resources,
+                                map,
+                                positions,
+                                victory);
+        for (player_id, difficulty) in &self.difficulties {
+            field.push_str(&format!(",difficulty.{}={}", **player_id, difficulty.name()));
+        }
+        for (player_id, percent) in &self.handicaps {
+            field.push_str(&format!(",handicap.{}={}", **player_id, percent));
+        }
+        field
+    }
+
+    /// Percent of the usual starting resources that the player gets
+    pub fn handicap_percent(&self, player_id: PlayerId) -> u32 {
+        self.handicaps.get(&player_id).cloned().unwrap_or(100)
+    }
+
+    /// Food, wood, stone, and gold that the player gets on top of their starting resources
+    pub fn bonus_resources(&self, player_id: PlayerId) -> [u32; 4] {
+        match self.difficulties.get(&player_id) {
+            Some(difficulty) => difficulty.bonus_resources(),
+            None => [0, 0, 0, 0],
+        }
+    }
+}
+
+/// A player's choices from the lobby; these take precedence over what the scenario says
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerSetup {
+    pub player_id: PlayerId,
+    pub name: String,
+    pub civilization_id: CivilizationId,
+    pub player_color_id: PlayerColorId,
+    pub team: u8,
+    pub allied_vision: bool,
+}
+
+/// Everything about a game that's decided before the simulation starts
+/// and isn't stored in the scenario itself
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSetup {
+    pub seed: u64,
+    /// The player whose perspective is shown; when observing, this is only where the view starts
+    pub local_player_id: PlayerId,
+    /// Observers run the same simulation as everyone else but don't own a player,
+    /// so they can't issue commands
+    pub observer: bool,
+    /// The scenario editor only runs the systems that keep the view up to date;
+    /// nothing moves, fights, or trains
+    pub editor: bool,
+    pub players: Vec<PlayerSetup>,
+    pub settings: GameSettings,
+    // TODO: Send scripts to everyone in LAN games; for now every peer has to load the same ones
+    /// Rules added to the game by the scenario's author or by mods
+    pub scripts: Vec<Script>,
+}
+
+impl GameSetup {
+    /// Single player setup that just uses the scenario's players
+    pub fn new(seed: u64) -> GameSetup {
+        GameSetup {
+            seed: seed,
+            // TODO: Local player currently hardcoded to 1 outside of the lobby; should be determined in menu
+            local_player_id: 1.into(),
+            observer: false,
+            editor: false,
+            players: Vec::new(),
+            settings: GameSettings::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    pub fn player(&self, player_id: PlayerId) -> Option<&PlayerSetup> {
+        self.players.iter().find(|p| p.player_id == player_id)
+    }
+
+    /// Shuffles which of the scenario's players each lobby player plays as, if the settings ask
+    /// for random positions. The shuffle only depends on the seed, so every peer agrees on it.
+    pub fn assign_positions(&mut self) {
+        if self.settings.starting_positions != StartingPositions::Random {
+            return;
+        }
+        let mut player_ids: Vec<PlayerId> = self.players.iter().map(|player| player.player_id).collect();
+        let mut rng = GameRng::new(self.seed);
+        for index in (1..player_ids.len()).rev() {
+            let other = rng.gen_range(0, index as u32 + 1) as usize;
+            player_ids.swap(index, other);
+        }
+
+        let mut local_player_id = self.local_player_id;
+        for (player, player_id) in self.players.iter_mut().zip(player_ids) {
+            if player.player_id == self.local_player_id {
+                local_player_id = player_id;
+            }
+            player.player_id = player_id;
+        }
+        self.local_player_id = local_player_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::PlayerId;
+    use super::*;
+
+    fn player(player_id: usize) -> PlayerSetup {
+        PlayerSetup {
+            player_id: player_id.into(),
+            name: format!("Player {}", player_id),
+            civilization_id: 1.into(),
+            player_color_id: player_id.into(),
+            team: player_id as u8,
+            allied_vision: false,
+        }
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let (one, two, three): (PlayerId, PlayerId, PlayerId) = (1.into(), 2.into(), 3.into());
+        let settings = GameSettings {
+            starting_resources: StartingResources::High,
+            map_visibility: MapVisibility::Explored,
+            starting_positions: StartingPositions::Random,
+            victory_condition: VictoryCondition::Conquest,
+            difficulties: vec![(two, Difficulty::Hardest), (three, Difficulty::Easy)].into_iter().collect(),
+            handicaps: vec![(one, 75)].into_iter().collect(),
+        };
+        assert_eq!(Ok(settings.clone()), GameSettings::parse(&settings.to_field()));
+        assert_eq!(Ok(GameSettings::new()), GameSettings::parse(""));
+        assert_eq!(StartingResources::Low,
+                   GameSettings::parse("resources=low").unwrap().starting_resources);
+        assert!(GameSettings::parse("resources=lots").is_err());
+        assert!(GameSettings::parse("victory").is_err());
+        assert!(GameSettings::parse("difficulty.0=hard").is_err());
+        assert!(GameSettings::parse("difficulty.2=impossible").is_err());
+        assert!(GameSettings::parse("handicap.2=lots").is_err());
+
+        let handicapped = GameSettings::parse("handicap.2=50,difficulty.3=hardest").unwrap();
+        assert_eq!(50, handicapped.handicap_percent(two));
+        assert_eq!(100, handicapped.handicap_percent(three));
+        assert_eq!([0, 0, 0, 0], handicapped.bonus_resources(two));
+        assert_eq!(Difficulty::Hardest.bonus_resources(), handicapped.bonus_resources(three));
+    }
+
+    #[test]
+    fn test_random_positions_follow_the_local_player() {
+        let mut setup = GameSetup::new(7);
+        setup.local_player_id = 2.into();
+        setup.players = (1..5).map(player).collect();
+        setup.assign_positions();
+        assert_eq!((1..5).map(player).collect::<Vec<_>>(), setup.players);
+
+        setup.settings.starting_positions = StartingPositions::Random;
+        let mut shuffled = setup.clone();
+        shuffled.assign_positions();
+        let local = shuffled.players.iter().find(|player| player.name == "Player 2").unwrap();
+        assert_eq!(local.player_id, shuffled.local_player_id);
+
+        let mut player_ids: Vec<PlayerId> = shuffled.players.iter().map(|player| player.player_id).collect();
+        player_ids.sort();
+        let expected: Vec<PlayerId> = (1..5).map(|player_id: usize| player_id.into()).collect();
+        assert_eq!(expected, player_ids);
+
+        // Every peer shuffles the same way
+        let mut again = setup.clone();
+        again.assign_positions();
+        assert_eq!(shuffled, again);
+    }
+}
@@ -0,0 +1,41 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+pub mod component;
+pub mod event;
+mod game_setup;
+mod persist;
+pub mod resource;
+mod snapshot;
+mod state_hash;
+pub mod system;
+mod unit_factory;
+mod world;
+
+pub use self::component::*;
+pub use self::game_setup::{GameSettings, GameSetup, MapVisibility, PlayerSetup, StartingPositions,
+                           StartingResources, VictoryCondition};
+pub use self::persist::Persist;
+pub use self::snapshot::WorldSnapshot;
+pub use self::state_hash::state_hash;
+pub use self::unit_factory::{UnitFactory, UnitMorph, UnitSpawn};
+pub use self::world::{SystemGroup, UPDATES_PER_SECOND, WorldPlanner, create_simulation_planner,
+                      schedule_systems, simulation_systems, time_step_seconds, update_world};
@@ -0,0 +1,406 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Binary encoding of the simulation state, for snapshots that get written to disk (repro bundles
+//! and saved games). It's built out of the same varints and actions as binary command logs.
+//!
+//! Everything is written field by field in the order it's declared, with nothing to mark where
+//! one field ends and the next starts, so changing a type that's persisted changes the format.
+//! Maps are written in key order so the same state always comes out as the same bytes.
+
+use action::{Action, CommandLog, Reader, Writer};
+use dat::ResourceType;
+use identifier::{CivilizationId, GraphicId, PlayerId, ResearchId, UnitId};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+use types::{Fixed, Rect, Vector3};
+
+pub trait Persist: Sized {
+    fn write(&self, writer: &mut Writer);
+    fn read(reader: &mut Reader) -> Result<Self, String>;
+}
+
+macro_rules! persist_unsigned {
+    ($($typ:ty),*) => {
+        $(impl Persist for $typ {
+            fn write(&self, writer: &mut Writer) {
+                writer.unsigned(*self as u64);
+            }
+
+            fn read(reader: &mut Reader) -> Result<$typ, String> {
+                let value = try!(reader.unsigned());
+                if value > <$typ>::max_value() as u64 {
+                    return Err(format!("{} out of range", value));
+                }
+                Ok(value as $typ)
+            }
+        })*
+    }
+}
+
+macro_rules! persist_signed {
+    ($($typ:ty),*) => {
+        $(impl Persist for $typ {
+            fn write(&self, writer: &mut Writer) {
+                writer.signed(*self as i64);
+            }
+
+            fn read(reader: &mut Reader) -> Result<$typ, String> {
+                let value = try!(reader.signed());
+                if value < <$typ>::min_value() as i64 || value > <$typ>::max_value() as i64 {
+                    return Err(format!("{} out of range", value));
+                }
+                Ok(value as $typ)
+            }
+        })*
+    }
+}
+
+persist_unsigned!(u16, u32, usize);
+persist_signed!(i8, i16, i32);
+
+impl Persist for u64 {
+    fn write(&self, writer: &mut Writer) {
+        writer.unsigned(*self);
+    }
+
+    fn read(reader: &mut Reader) -> Result<u64, String> {
+        reader.unsigned()
+    }
+}
+
+impl Persist for i64 {
+    fn write(&self, writer: &mut Writer) {
+        writer.signed(*self);
+    }
+
+    fn read(reader: &mut Reader) -> Result<i64, String> {
+        reader.signed()
+    }
+}
+
+impl Persist for u8 {
+    fn write(&self, writer: &mut Writer) {
+        writer.byte(*self);
+    }
+
+    fn read(reader: &mut Reader) -> Result<u8, String> {
+        reader.byte()
+    }
+}
+
+impl Persist for bool {
+    fn write(&self, writer: &mut Writer) {
+        writer.byte(*self as u8);
+    }
+
+    fn read(reader: &mut Reader) -> Result<bool, String> {
+        match try!(reader.byte()) {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(format!("invalid flag {}", flag)),
+        }
+    }
+}
+
+impl Persist for String {
+    fn write(&self, writer: &mut Writer) {
+        writer.text(self);
+    }
+
+    fn read(reader: &mut Reader) -> Result<String, String> {
+        reader.text()
+    }
+}
+
+impl Persist for Fixed {
+    fn write(&self, writer: &mut Writer) {
+        writer.signed(self.scaled);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Fixed, String> {
+        Ok(Fixed { scaled: try!(reader.signed()) })
+    }
+}
+
+impl Persist for Vector3 {
+    fn write(&self, writer: &mut Writer) {
+        self.x.write(writer);
+        self.y.write(writer);
+        self.z.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Vector3, String> {
+        let x = try!(Fixed::read(reader));
+        let y = try!(Fixed::read(reader));
+        let z = try!(Fixed::read(reader));
+        Ok(Vector3::new(x, y, z))
+    }
+}
+
+impl Persist for Rect {
+    fn write(&self, writer: &mut Writer) {
+        self.x.write(writer);
+        self.y.write(writer);
+        self.w.write(writer);
+        self.h.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Rect, String> {
+        let x = try!(i32::read(reader));
+        let y = try!(i32::read(reader));
+        let w = try!(i32::read(reader));
+        let h = try!(i32::read(reader));
+        Ok(Rect::of(x, y, w, h))
+    }
+}
+
+macro_rules! persist_id {
+    ($($name:ident: $underlying_type:ty),*) => {
+        $(impl Persist for $name {
+            fn write(&self, writer: &mut Writer) {
+                (**self).write(writer);
+            }
+
+            fn read(reader: &mut Reader) -> Result<$name, String> {
+                Ok((try!(<$underlying_type>::read(reader)) as usize).into())
+            }
+        })*
+    }
+}
+
+persist_id!(PlayerId: u8,
+            CivilizationId: u8,
+            UnitId: u32,
+            ResearchId: u32,
+            GraphicId: u32);
+
+impl Persist for ResourceType {
+    fn write(&self, writer: &mut Writer) {
+        let value = match *self {
+            ResourceType::Food => 0,
+            ResourceType::Wood => 1,
+            ResourceType::Stone => 2,
+            ResourceType::Gold => 3,
+            ResourceType::Unknown(value) => value,
+        };
+        value.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<ResourceType, String> {
+        Ok(ResourceType::from_i16(try!(i16::read(reader))))
+    }
+}
+
+impl Persist for Action {
+    fn write(&self, writer: &mut Writer) {
+        writer.action(self);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Action, String> {
+        reader.action()
+    }
+}
+
+/// Command logs are written in their own binary format, seed and settings included
+impl Persist for CommandLog {
+    fn write(&self, writer: &mut Writer) {
+        writer.blob(&self.to_bytes());
+    }
+
+    fn read(reader: &mut Reader) -> Result<CommandLog, String> {
+        let bytes = try!(reader.blob());
+        CommandLog::from_bytes(bytes).map_err(|err| err.to_string())
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn write(&self, writer: &mut Writer) {
+        match *self {
+            Some(ref value) => {
+                writer.byte(1);
+                value.write(writer);
+            }
+            None => writer.byte(0),
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Option<T>, String> {
+        match try!(reader.byte()) {
+            0 => Ok(None),
+            1 => Ok(Some(try!(T::read(reader)))),
+            flag => Err(format!("invalid option flag {}", flag)),
+        }
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn write(&self, writer: &mut Writer) {
+        self.0.write(writer);
+        self.1.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<(A, B), String> {
+        let a = try!(A::read(reader));
+        let b = try!(B::read(reader));
+        Ok((a, b))
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn write(&self, writer: &mut Writer) {
+        self.len().write(writer);
+        for value in self {
+            value.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Vec<T>, String> {
+        let count = try!(usize::read(reader));
+        let mut values = Vec::new();
+        for _ in 0..count {
+            values.push(try!(T::read(reader)));
+        }
+        Ok(values)
+    }
+}
+
+impl<T: Persist + Ord> Persist for BTreeSet<T> {
+    fn write(&self, writer: &mut Writer) {
+        self.len().write(writer);
+        for value in self {
+            value.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<BTreeSet<T>, String> {
+        let count = try!(usize::read(reader));
+        let mut values = BTreeSet::new();
+        for _ in 0..count {
+            values.insert(try!(T::read(reader)));
+        }
+        Ok(values)
+    }
+}
+
+impl<K: Persist + Ord, V: Persist> Persist for BTreeMap<K, V> {
+    fn write(&self, writer: &mut Writer) {
+        self.len().write(writer);
+        for (key, value) in self {
+            key.write(writer);
+            value.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<BTreeMap<K, V>, String> {
+        let count = try!(usize::read(reader));
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            let key = try!(K::read(reader));
+            map.insert(key, try!(V::read(reader)));
+        }
+        Ok(map)
+    }
+}
+
+impl<K: Persist + Ord + Hash, V: Persist> Persist for HashMap<K, V> {
+    fn write(&self, writer: &mut Writer) {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.len().write(writer);
+        for (key, value) in entries {
+            key.write(writer);
+            value.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<HashMap<K, V>, String> {
+        let count = try!(usize::read(reader));
+        let mut map = HashMap::new();
+        for _ in 0..count {
+            let key = try!(K::read(reader));
+            map.insert(key, try!(V::read(reader)));
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use action::{Reader, Writer};
+    use dat::ResourceType;
+    use identifier::PlayerId;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::Debug;
+    use super::Persist;
+    use types::{Fixed, Vector3};
+
+    fn round_trip<T: Persist + Debug + PartialEq>(value: T) {
+        let mut writer = Writer::new();
+        value.write(&mut writer);
+        let bytes = writer.into_bytes();
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(value, T::read(&mut reader).unwrap());
+        assert!(reader.is_at_end());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(-129i16);
+        round_trip(u64::max_value());
+        round_trip(Some("villager".to_string()));
+        round_trip(Vector3::new(Fixed::from(-3), Fixed::from(12.5), 0.into()));
+        let player_id: PlayerId = 2usize.into();
+        round_trip(vec![(player_id, ResourceType::Gold), (player_id, ResourceType::Unknown(7))]);
+
+        let mut hash_map = HashMap::new();
+        hash_map.insert((4, -2), vec![1u32, 2]);
+        hash_map.insert((-1, 0), Vec::new());
+        round_trip(hash_map);
+    }
+
+    #[test]
+    fn test_maps_are_written_in_key_order() {
+        let mut hash_map = HashMap::new();
+        let mut tree_map = BTreeMap::new();
+        for key in 0..50u32 {
+            hash_map.insert(key * 7 % 50, key);
+            tree_map.insert(key * 7 % 50, key);
+        }
+        let (mut hash_writer, mut tree_writer) = (Writer::new(), Writer::new());
+        hash_map.write(&mut hash_writer);
+        tree_map.write(&mut tree_writer);
+        assert_eq!(tree_writer.into_bytes(), hash_writer.into_bytes());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let mut writer = Writer::new();
+        300u32.write(&mut writer);
+        2u8.write(&mut writer);
+        let bytes = writer.into_bytes();
+        assert!(u8::read(&mut Reader::new(&bytes[..2])).is_ok());
+        assert!(i8::read(&mut Reader::new(&bytes[..2])).is_err());
+        assert!(bool::read(&mut Reader::new(&bytes[2..])).is_err());
+        assert!(Option::<u8>::read(&mut Reader::new(&bytes[2..])).is_err());
+        assert!(Vec::<u8>::read(&mut Reader::new(&bytes)).is_err());
+    }
+}
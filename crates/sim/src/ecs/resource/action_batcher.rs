@@ -0,0 +1,107 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, CommandLog, CommandLogEntry};
+use specs::Index;
+use std::collections::BTreeMap;
+use std::mem;
+
+#[derive(Clone)]
+pub struct ActionBatcher {
+    // Ordered so that batches are always applied in the same order; the simulation must be deterministic
+    actions: BTreeMap<Index, Vec<Action>>,
+    playback: CommandLog,
+    history: CommandLog,
+    /// In LAN games, what the local player queues is held back this many ticks so that it can
+    /// reach everyone else first (see `delay_actions`)
+    delay_ticks: u64,
+    /// Held back actions that haven't been taken to be sent yet
+    outgoing: CommandLog,
+}
+
+persist_struct!(ActionBatcher { actions, playback, history, delay_ticks, outgoing });
+
+impl ActionBatcher {
+    pub fn new() -> ActionBatcher {
+        ActionBatcher {
+            actions: BTreeMap::new(),
+            playback: CommandLog::new(),
+            history: CommandLog::new(),
+            delay_ticks: 0,
+            outgoing: CommandLog::new(),
+        }
+    }
+
+    /// From now on, actions queued before a tick aren't consumed on it, but are tagged with the
+    /// tick `ticks` later and kept in `outgoing` until the lockstep session takes them. They're
+    /// only consumed once they come back through `schedule`, along with everyone else's.
+    pub fn delay_actions(&mut self, ticks: u64) {
+        self.delay_ticks = ticks;
+    }
+
+    /// Schedules commands to be consumed on the ticks they're tagged with, which must not be
+    /// before any that are already scheduled
+    pub fn schedule(&mut self, entries: Vec<CommandLogEntry>) {
+        for entry in entries {
+            self.playback.push(entry);
+        }
+    }
+
+    /// Every held back action since this was last called (see `delay_actions`)
+    pub fn take_outgoing(&mut self) -> CommandLog {
+        mem::replace(&mut self.outgoing, CommandLog::new())
+    }
+
+    /// Schedules previously recorded commands to be fed back into the
+    /// simulation on the same ticks that they were originally consumed on
+    pub fn play_back(&mut self, commands: CommandLog) {
+        self.playback = commands;
+    }
+
+    pub fn queue_for_entity(&mut self, entity_id: Index, action: Action) {
+        self.actions.entry(entity_id).or_insert_with(Vec::new).push(action);
+    }
+
+    /// Drops whatever is waiting for the entity, which is gone (or changed hands) before it got
+    /// to its actions. Entity indices get reused, so they'd go to some other entity otherwise.
+    pub fn forget(&mut self, entity_id: Index) {
+        self.actions.remove(&entity_id);
+    }
+
+    pub fn consume_actions(&mut self, tick: u64) -> BTreeMap<Index, Vec<Action>> {
+        if self.delay_ticks > 0 {
+            let queued = mem::replace(&mut self.actions, BTreeMap::new());
+            self.outgoing.record(tick + self.delay_ticks, &queued);
+        }
+        for entry in self.playback.take_until(tick) {
+            self.queue_for_entity(entry.entity_id, entry.action);
+        }
+
+        let consumed = mem::replace(&mut self.actions, BTreeMap::new());
+        self.history.record(tick, &consumed);
+        consumed
+    }
+
+    /// Every action consumed so far, tagged with the tick it was consumed on
+    pub fn history<'a>(&'a self) -> &'a CommandLog {
+        &self.history
+    }
+}
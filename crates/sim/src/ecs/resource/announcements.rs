@@ -0,0 +1,99 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use types::Fixed;
+
+/// How long an announcement stays on screen unless it says otherwise
+pub const ANNOUNCEMENT_SECONDS: Fixed = fixed_const!(8.0);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Announcement {
+    pub text: String,
+
+    /// Seconds until it goes away
+    pub time_left: Fixed,
+}
+
+persist_struct!(Announcement { text, time_left });
+
+/// Messages shown to every player in the middle of the screen, such as victory countdowns
+#[derive(Clone, Debug)]
+pub struct Announcements {
+    announcements: Vec<Announcement>,
+}
+
+persist_struct!(Announcements { announcements });
+
+impl Announcements {
+    pub fn new() -> Announcements {
+        Announcements { announcements: Vec::new() }
+    }
+
+    pub fn announce(&mut self, text: String) {
+        self.announce_for(text, ANNOUNCEMENT_SECONDS);
+    }
+
+    pub fn announce_for(&mut self, text: String, seconds: Fixed) {
+        self.announcements.push(Announcement {
+            text: text,
+            time_left: seconds,
+        });
+    }
+
+    /// Oldest first
+    pub fn active<'a>(&'a self) -> &'a [Announcement] {
+        &self.announcements
+    }
+
+    /// Counts down and drops the announcements that have been up long enough. Called once per tick.
+    pub fn update(&mut self, time_step: Fixed) {
+        for announcement in &mut self.announcements {
+            announcement.time_left -= time_step;
+        }
+        let expired: Vec<usize> = self.announcements
+            .iter()
+            .enumerate()
+            .filter(|&(_, announcement)| announcement.time_left <= 0.into())
+            .map(|(index, _)| index)
+            .collect();
+        for index in expired.into_iter().rev() {
+            self.announcements.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Announcements;
+
+    #[test]
+    fn test_announcements_expire() {
+        let mut announcements = Announcements::new();
+        announcements.announce_for("first".to_string(), 1.into());
+        announcements.announce_for("second".to_string(), 3.into());
+        announcements.update(2.into());
+        assert_eq!(1, announcements.active().len());
+        assert_eq!("second", announcements.active()[0].text);
+        announcements.update(1.into());
+        assert!(announcements.active().is_empty());
+    }
+}
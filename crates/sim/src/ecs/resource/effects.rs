@@ -0,0 +1,177 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::{DrsKey, PlayerId, SlpFileId};
+use types::{Fixed, Vector3};
+
+// Hardcoded framerate for now
+const SECONDS_PER_FRAME: Fixed = fixed_const!(0.1);
+
+/// Which graphic an effect plays, and in whose colors
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EffectShape {
+    pub drs_key: DrsKey,
+    pub slp_file_id: SlpFileId,
+    pub player_id: PlayerId,
+}
+
+impl EffectShape {
+    pub fn new(player_id: PlayerId, drs_key: DrsKey, slp_file_id: SlpFileId) -> EffectShape {
+        EffectShape {
+            drs_key: drs_key,
+            slp_file_id: slp_file_id,
+            player_id: player_id,
+        }
+    }
+
+    /// The flag that shows where units were ordered to
+    pub fn move_flag() -> EffectShape {
+        EffectShape::new(0.into(), DrsKey::Interfac, 50405.into())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Effect {
+    pub shape: EffectShape,
+    pub position: Vector3,
+    pub frame: u16,
+    frame_time: Fixed,
+    alive: bool,
+}
+
+impl Effect {
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+}
+
+// TODO: Sparks and blood splats, once it's known which graphics the original game uses for them
+/// Short lived animated sprites that play once and go away, such as move flags and collapsing
+/// buildings. Anything can spawn one without knowing how it's drawn.
+///
+/// Effects aren't entities: the ones spawned by local input (move flags) would give every peer's
+/// entities different indices. So they live in a pool here instead, and finished effects leave
+/// their slot for the next one rather than being freed.
+pub struct EffectSpawner {
+    effects: Vec<Effect>,
+
+    /// Slots of finished effects, which new ones reuse
+    free_slots: Vec<usize>,
+}
+
+impl EffectSpawner {
+    pub fn new() -> EffectSpawner {
+        EffectSpawner {
+            effects: Vec::new(),
+            free_slots: Vec::new(),
+        }
+    }
+
+    pub fn spawn(&mut self, shape: EffectShape, position: Vector3) {
+        let effect = Effect {
+            shape: shape,
+            position: position,
+            frame: 0,
+            frame_time: 0.into(),
+            alive: true,
+        };
+        match self.free_slots.pop() {
+            Some(slot) => self.effects[slot] = effect,
+            None => self.effects.push(effect),
+        }
+    }
+
+    /// Advances every effect's animation. `frame_count` tells how many frames a shape has; effects
+    /// expire after their last frame, or right away if their shape can't be found.
+    pub fn update<F>(&mut self, time_step: Fixed, frame_count: F)
+        where F: Fn(&EffectShape) -> Option<u16>
+    {
+        for (slot, effect) in self.effects.iter_mut().enumerate() {
+            if !effect.alive {
+                continue;
+            }
+            let frames = frame_count(&effect.shape).unwrap_or(0);
+            effect.frame_time += time_step;
+            if effect.frame_time >= SECONDS_PER_FRAME {
+                effect.frame_time -= SECONDS_PER_FRAME;
+                effect.frame += 1;
+            }
+            if effect.frame >= frames {
+                effect.alive = false;
+                self.free_slots.push(slot);
+            }
+        }
+    }
+
+    /// Every slot in the pool, including those of finished effects (see `Effect::is_alive`)
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    /// How many effects are playing
+    pub fn len(&self) -> usize {
+        self.effects.len() - self.free_slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Vector3;
+
+    fn position(x: i32) -> Vector3 {
+        Vector3::new(x.into(), 0.into(), 0.into())
+    }
+
+    #[test]
+    fn test_effects_expire_and_their_slots_are_reused() {
+        let mut spawner = EffectSpawner::new();
+        spawner.spawn(EffectShape::move_flag(), position(1));
+        spawner.spawn(EffectShape::move_flag(), position(2));
+        assert_eq!(2, spawner.len());
+
+        // Two frames at a tenth of a second each
+        let frame_count = |_: &EffectShape| Some(2);
+        spawner.update(fixed_const!(0.1), &frame_count);
+        assert_eq!(2, spawner.len());
+        assert_eq!(1, spawner.effects()[0].frame);
+        spawner.update(fixed_const!(0.1), &frame_count);
+        assert_eq!(0, spawner.len());
+
+        spawner.spawn(EffectShape::move_flag(), position(3));
+        assert_eq!(1, spawner.len());
+        assert_eq!(2, spawner.effects().len());
+        let alive: Vec<Vector3> = spawner.effects()
+            .iter()
+            .filter(|effect| effect.is_alive())
+            .map(|effect| effect.position)
+            .collect();
+        assert_eq!(vec![position(3)], alive);
+    }
+
+    #[test]
+    fn test_effects_without_a_shape_expire_right_away() {
+        let mut spawner = EffectSpawner::new();
+        spawner.spawn(EffectShape::move_flag(), position(1));
+        spawner.update(fixed_const!(0.05), |_| None);
+        assert_eq!(0, spawner.len());
+    }
+}
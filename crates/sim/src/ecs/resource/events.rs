@@ -0,0 +1,153 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Reader, Writer};
+use ecs::Persist;
+
+/// A channel of events of a single type, stored as a resource so that systems can
+/// react to things that happened elsewhere without knowing about each other.
+///
+/// Producers `emit` events, and each consumer keeps its own `EventReader` cursor (usually
+/// a field on the system) so that any number of consumers can see every event exactly once.
+/// Events stay around for two ticks (see `update`), so a consumer that runs before the
+/// producer within a tick still gets them on the next tick. Consumers that don't read for
+/// longer than that miss events.
+#[derive(Clone)]
+pub struct Events<T> {
+    events: Vec<T>,
+    /// Sequence number of `events[0]`
+    start: u64,
+    /// Events before this index were emitted before the previous update
+    previous_tick_start: usize,
+}
+
+impl<T: Persist> Persist for Events<T> {
+    fn write(&self, writer: &mut Writer) {
+        self.events.write(writer);
+        self.start.write(writer);
+        self.previous_tick_start.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Events<T>, String> {
+        Ok(Events {
+            events: try!(Persist::read(reader)),
+            start: try!(Persist::read(reader)),
+            previous_tick_start: try!(Persist::read(reader)),
+        })
+    }
+}
+
+/// A consumer's position in an `Events` channel
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EventReader {
+    next: u64,
+}
+
+impl EventReader {
+    /// Creates a reader that sees every event still held by the channel
+    pub fn new() -> EventReader {
+        EventReader { next: 0 }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Events<T> {
+        Events {
+            events: Vec::new(),
+            start: 0,
+            previous_tick_start: 0,
+        }
+    }
+
+    pub fn emit(&mut self, event: T) {
+        self.events.push(event);
+    }
+
+    /// Returns every event the reader hasn't seen yet, and advances the reader past them
+    pub fn read<'a>(&'a self, reader: &mut EventReader) -> &'a [T] {
+        let first = if reader.next > self.start { (reader.next - self.start) as usize } else { 0 };
+        let first = if first > self.events.len() { self.events.len() } else { first };
+        reader.next = self.start + self.events.len() as u64;
+        &self.events[first..]
+    }
+
+    /// Drops events emitted before the previous update. Called once per tick for every channel.
+    pub fn update(&mut self) {
+        let expired = self.previous_tick_start;
+        self.events.drain(..expired);
+        self.start += expired as u64;
+        self.previous_tick_start = self.events.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventReader, Events};
+
+    #[test]
+    fn test_each_reader_sees_every_event_once() {
+        let mut events = Events::new();
+        let mut reader_a = EventReader::new();
+        let mut reader_b = EventReader::new();
+
+        events.emit(1);
+        events.emit(2);
+        assert_eq!(&[1, 2], events.read(&mut reader_a));
+        assert!(events.read(&mut reader_a).is_empty());
+
+        events.emit(3);
+        assert_eq!(&[3], events.read(&mut reader_a));
+        assert_eq!(&[1, 2, 3], events.read(&mut reader_b));
+    }
+
+    #[test]
+    fn test_events_live_for_two_updates() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+
+        events.emit(1);
+        events.update();
+        events.emit(2);
+        events.update();
+        // Event 1 has expired, but event 2 is still around for one more update
+        assert_eq!(&[2], events.read(&mut reader));
+
+        events.emit(3);
+        events.update();
+        assert_eq!(&[3], events.read(&mut reader));
+        events.update();
+        events.update();
+        assert!(events.read(&mut reader).is_empty());
+    }
+
+    #[test]
+    fn test_slow_reader_skips_expired_events() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+        events.emit(1);
+        assert_eq!(&[1], events.read(&mut reader));
+        for i in 2..6 {
+            events.emit(i);
+            events.update();
+        }
+        assert_eq!(&[5], events.read(&mut reader));
+    }
+}
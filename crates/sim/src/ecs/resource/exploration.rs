@@ -0,0 +1,195 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use ecs::MapVisibility;
+use identifier::PlayerId;
+use std::cmp;
+use std::collections::BTreeMap;
+
+/// The tiles one player has seen, and a revision that's bumped whenever more of them are
+#[derive(Clone, Debug)]
+struct Explored {
+    tiles: Vec<bool>,
+    revision: u32,
+}
+
+persist_struct!(Explored { tiles, revision });
+
+/// Which tiles each player has seen so far. Unlike the fog of war, which only cares about the
+/// local player, this is part of the simulation, since a defeated player's map goes over to their
+/// allies and has to be the same on every machine.
+#[derive(Clone, Debug)]
+pub struct Exploration {
+    width: i32,
+    height: i32,
+
+    /// The whole map starts out explored unless the map visibility is `MapVisibility::Normal`
+    explored_from_start: bool,
+
+    /// Players that haven't seen anything yet aren't in here
+    players: BTreeMap<PlayerId, Explored>,
+}
+
+persist_struct!(Exploration { width, height, explored_from_start, players });
+
+impl Exploration {
+    pub fn new(width: i32, height: i32, visibility: MapVisibility) -> Exploration {
+        Exploration {
+            width: width,
+            height: height,
+            explored_from_start: visibility != MapVisibility::Normal,
+            players: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_explored(&self, player_id: PlayerId, row: i32, col: i32) -> bool {
+        match self.tile_index(row, col) {
+            Some(index) => {
+                self.players
+                    .get(&player_id)
+                    .map(|explored| explored.tiles[index])
+                    .unwrap_or(self.explored_from_start)
+            }
+            None => false,
+        }
+    }
+
+    /// Every tile the player has explored, a row at a time; `None` if they haven't seen anything
+    pub fn tiles<'a>(&'a self, player_id: PlayerId) -> Option<&'a [bool]> {
+        self.players.get(&player_id).map(|explored| &explored.tiles[..])
+    }
+
+    /// Changes whenever the player explores more of the map
+    pub fn revision(&self, player_id: PlayerId) -> u32 {
+        self.players.get(&player_id).map(|explored| explored.revision).unwrap_or(0)
+    }
+
+    /// Marks every tile within the radius (in tiles) of the center as seen by the player
+    pub fn explore(&mut self, player_id: PlayerId, center_row: i32, center_col: i32, radius: i32) {
+        let (width, height) = (self.width, self.height);
+        let explored = self.explored(player_id);
+        let mut changed = false;
+        for row in cmp::max(center_row - radius, 0)..cmp::min(center_row + radius + 1, height) {
+            for col in cmp::max(center_col - radius, 0)..cmp::min(center_col + radius + 1, width) {
+                let (row_offset, col_offset) = (row - center_row, col - center_col);
+                if row_offset * row_offset + col_offset * col_offset > radius * radius {
+                    continue;
+                }
+                let index = (row * width + col) as usize;
+                if !explored.tiles[index] {
+                    explored.tiles[index] = true;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            explored.revision = explored.revision.wrapping_add(1);
+        }
+    }
+
+    /// Gives the other player everything the player has explored (when the player is
+    /// defeated, their allies get their map)
+    pub fn share(&mut self, player_id: PlayerId, other_id: PlayerId) {
+        let tiles = match self.players.get(&player_id) {
+            Some(explored) => explored.tiles.clone(),
+            None => return,
+        };
+        let other = self.explored(other_id);
+        let mut changed = false;
+        for (other_tile, &tile) in other.tiles.iter_mut().zip(tiles.iter()) {
+            if tile && !*other_tile {
+                *other_tile = true;
+                changed = true;
+            }
+        }
+        if changed {
+            other.revision = other.revision.wrapping_add(1);
+        }
+    }
+
+    fn explored<'a>(&'a mut self, player_id: PlayerId) -> &'a mut Explored {
+        let tile_count = (self.width * self.height) as usize;
+        let explored_from_start = self.explored_from_start;
+        self.players.entry(player_id).or_insert_with(|| {
+            Explored {
+                tiles: vec![explored_from_start; tile_count],
+                revision: 0,
+            }
+        })
+    }
+
+    fn tile_index(&self, row: i32, col: i32) -> Option<usize> {
+        if row < 0 || col < 0 || row >= self.height || col >= self.width {
+            return None;
+        }
+        Some((row * self.width + col) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::MapVisibility;
+    use super::Exploration;
+
+    #[test]
+    fn test_explore() {
+        let mut exploration = Exploration::new(10, 10, MapVisibility::Normal);
+        exploration.explore(1.into(), 5, 5, 2);
+        assert!(exploration.is_explored(1.into(), 5, 7));
+        assert!(exploration.is_explored(1.into(), 4, 4));
+        assert!(!exploration.is_explored(1.into(), 7, 7));
+        assert!(!exploration.is_explored(2.into(), 5, 5));
+        let revision = exploration.revision(1.into());
+        assert!(revision != 0);
+
+        // Seeing the same tiles again doesn't change anything, and the map's edges cut sight off
+        exploration.explore(1.into(), 5, 6, 1);
+        assert_eq!(revision, exploration.revision(1.into()));
+        exploration.explore(1.into(), 0, 9, 3);
+        assert!(exploration.is_explored(1.into(), 0, 9));
+        assert!(!exploration.is_explored(1.into(), -1, 9));
+    }
+
+    #[test]
+    fn test_share() {
+        let mut exploration = Exploration::new(10, 10, MapVisibility::Normal);
+        exploration.explore(1.into(), 2, 2, 1);
+        exploration.explore(2.into(), 8, 8, 1);
+        exploration.share(1.into(), 2.into());
+        assert!(exploration.is_explored(2.into(), 2, 2));
+        assert!(exploration.is_explored(2.into(), 8, 8));
+        assert!(!exploration.is_explored(1.into(), 8, 8));
+
+        // Nothing changes if the other player already saw it all
+        let revision = exploration.revision(2.into());
+        exploration.share(1.into(), 2.into());
+        assert_eq!(revision, exploration.revision(2.into()));
+    }
+
+    #[test]
+    fn test_explored_from_start() {
+        let mut exploration = Exploration::new(10, 10, MapVisibility::Explored);
+        assert!(exploration.is_explored(1.into(), 9, 9));
+        exploration.explore(1.into(), 2, 2, 1);
+        assert!(exploration.is_explored(1.into(), 9, 9));
+    }
+}
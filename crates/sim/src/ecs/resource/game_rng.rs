@@ -0,0 +1,205 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Reader, Writer};
+use ecs::Persist;
+use types::Fixed;
+
+/// Deterministic random numbers for the simulation.
+///
+/// Every peer in a lockstep game, and every replay of a recorded game, must draw exactly the same
+/// random numbers in exactly the same order. That only holds if:
+///
+/// * Simulation systems (`SystemGroup::Normal`) get all of their randomness from this resource,
+///   never from the OS, the clock, or a thread local generator.
+/// * Render systems, and anything else that depends on the frame rate, the camera, or the local
+///   player, never touch it. Use a separate generator for purely cosmetic randomness.
+/// * Systems always fetch it as a mutable resource, even just to peek, so that two systems never
+///   draw from it concurrently.
+///
+/// The generator is xoshiro256** seeded through splitmix64. It's defined entirely in terms of
+/// wrapping integer arithmetic, so it produces the same sequence on every platform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRng {
+    seed: u64,
+    state: [u64; 4],
+}
+
+impl Persist for GameRng {
+    fn write(&self, writer: &mut Writer) {
+        self.seed.write(writer);
+        for word in &self.state {
+            word.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<GameRng, String> {
+        let seed = try!(u64::read(reader));
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            *word = try!(u64::read(reader));
+        }
+        Ok(GameRng::from_state(seed, state))
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        let mut splitmix_state = seed;
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            *word = splitmix64(&mut splitmix_state);
+        }
+        GameRng {
+            seed: seed,
+            state: state,
+        }
+    }
+
+    /// Restores a generator from a previously saved `state()`
+    pub fn from_state(seed: u64, state: [u64; 4]) -> GameRng {
+        GameRng {
+            seed: seed,
+            state: state,
+        }
+    }
+
+    /// The seed the game was set up with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The current generator state (for saves and state hashes)
+    pub fn state(&self) -> [u64; 4] {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+
+        result
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a uniformly distributed value in [low, high)
+    pub fn gen_range(&mut self, low: u32, high: u32) -> u32 {
+        assert!(low < high, "empty range: [{}, {})", low, high);
+        let range = (high - low) as u64;
+        // Reject values from the incomplete last "bucket" so there's no modulo bias
+        let zone = u64::max_value() - (u64::max_value() % range + 1) % range;
+        loop {
+            let value = self.next_u64();
+            if value <= zone {
+                return low + (value % range) as u32;
+            }
+        }
+    }
+
+    /// Returns a value in [0, 1)
+    pub fn gen_fixed(&mut self) -> Fixed {
+        // Fixed has 24 fractional bits; take the top 24 bits of the output
+        Fixed { scaled: (self.next_u64() >> 40) as i64 }
+    }
+
+    /// Returns true with the given probability (0 is never, 1 or more is always)
+    pub fn chance(&mut self, probability: Fixed) -> bool {
+        self.gen_fixed() < probability
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameRng, splitmix64};
+    use types::Fixed;
+
+    #[test]
+    fn test_splitmix64_reference_values() {
+        let mut state = 0;
+        assert_eq!(0xe220a8397b1dcdaf, splitmix64(&mut state));
+        assert_eq!(0x6e789e6aa1b965f4, splitmix64(&mut state));
+    }
+
+    #[test]
+    fn test_reference_sequence() {
+        // Changing these values breaks every recorded replay
+        let mut rng = GameRng::new(0);
+        assert_eq!(0x99ec5f36cb75f2b4, rng.next_u64());
+        assert_eq!(0xbf6e1f784956452a, rng.next_u64());
+        assert_eq!(0x1a5f849d4933e6e0, rng.next_u64());
+
+        let mut rng = GameRng::new(12345);
+        assert_eq!(0xbe6a36374160d49b, rng.next_u64());
+        assert_eq!(0x214aaa0637a688c6, rng.next_u64());
+        assert_eq!(0xf69d16de9954d388, rng.next_u64());
+    }
+
+    #[test]
+    fn test_restore_state() {
+        let mut rng = GameRng::new(42);
+        rng.next_u64();
+        let mut restored = GameRng::from_state(rng.seed(), rng.state());
+        assert_eq!(rng.next_u64(), restored.next_u64());
+        assert_eq!(42, restored.seed());
+    }
+
+    #[test]
+    fn test_gen_range() {
+        let mut rng = GameRng::new(7);
+        let mut seen = [false; 5];
+        for _ in 0..1000 {
+            let value = rng.gen_range(10, 15);
+            assert!(value >= 10 && value < 15);
+            seen[(value - 10) as usize] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert_eq!(3, rng.gen_range(3, 4));
+    }
+
+    #[test]
+    fn test_gen_fixed_and_chance() {
+        let mut rng = GameRng::new(7);
+        for _ in 0..1000 {
+            let value = rng.gen_fixed();
+            assert!(value >= Fixed::from(0) && value < Fixed::from(1));
+            assert!(!rng.chance(0.into()));
+            assert!(rng.chance(1.into()));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use scn::Scenario;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedMessage {
+    /// Seconds of game time when it was shown
+    pub seconds: u64,
+    pub text: String,
+}
+
+persist_struct!(LoggedMessage { seconds, text });
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Objective {
+    pub text: String,
+    pub complete: bool,
+}
+
+persist_struct!(Objective { text, complete });
+
+/// Everything the scenario has told the player: its instructions, hints, and history, the
+/// objectives its scripts have set, and every message the scripts have shown so far
+#[derive(Clone, Debug)]
+pub struct Messages {
+    instructions: String,
+    hints: String,
+    history: String,
+    objectives: Vec<Objective>,
+    log: Vec<LoggedMessage>,
+
+    /// Set when an objective is added or completed, until the player opens the panel
+    unread: bool,
+}
+
+persist_struct!(Messages { instructions, hints, history, objectives, log, unread });
+
+impl Messages {
+    pub fn new() -> Messages {
+        Messages {
+            instructions: String::new(),
+            hints: String::new(),
+            history: String::new(),
+            objectives: Vec::new(),
+            log: Vec::new(),
+            unread: false,
+        }
+    }
+
+    pub fn from_scenario(scenario: &Scenario) -> Messages {
+        let mut messages = Messages::new();
+        messages.instructions = scenario.player_data.instructions.trim().to_string();
+        messages.hints = scenario.player_data.hints.trim().to_string();
+        messages.history = scenario.player_data.history.trim().to_string();
+        // So that the player knows there's something to read before the game gets going
+        messages.unread = !messages.instructions.is_empty();
+        messages
+    }
+
+    pub fn instructions(&self) -> &str {
+        &self.instructions
+    }
+
+    pub fn hints(&self) -> &str {
+        &self.hints
+    }
+
+    pub fn history(&self) -> &str {
+        &self.history
+    }
+
+    pub fn objectives(&self) -> &[Objective] {
+        &self.objectives
+    }
+
+    /// Oldest first
+    pub fn log(&self) -> &[LoggedMessage] {
+        &self.log
+    }
+
+    pub fn has_unread(&self) -> bool {
+        self.unread
+    }
+
+    pub fn mark_read(&mut self) {
+        self.unread = false;
+    }
+
+    pub fn add_message(&mut self, seconds: u64, text: String) {
+        self.log.push(LoggedMessage {
+            seconds: seconds,
+            text: text,
+        });
+    }
+
+    /// Adds an objective; returns false if there already is one with the same text
+    pub fn add_objective(&mut self, seconds: u64, text: String) -> bool {
+        if self.objectives.iter().any(|objective| objective.text == text) {
+            return false;
+        }
+        self.add_message(seconds, format!("New objective: {}", text));
+        self.objectives.push(Objective {
+            text: text,
+            complete: false,
+        });
+        self.unread = true;
+        true
+    }
+
+    /// Marks the objective with the given text complete; returns false if there isn't one, or it
+    /// was already complete
+    pub fn complete_objective(&mut self, seconds: u64, text: &str) -> bool {
+        match self.objectives.iter_mut().find(|objective| objective.text == text && !objective.complete) {
+            Some(objective) => objective.complete = true,
+            None => return false,
+        }
+        self.add_message(seconds, format!("Objective complete: {}", text));
+        self.unread = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Messages;
+
+    #[test]
+    fn test_objectives() {
+        let mut messages = Messages::new();
+        assert!(messages.add_objective(10, "Find the ruins".to_string()));
+        assert!(!messages.add_objective(20, "Find the ruins".to_string()));
+        assert_eq!(1, messages.objectives().len());
+        assert!(messages.has_unread());
+
+        messages.mark_read();
+        assert!(!messages.complete_objective(30, "Build a wonder"));
+        assert!(!messages.has_unread());
+        assert!(messages.complete_objective(40, "Find the ruins"));
+        assert!(!messages.complete_objective(50, "Find the ruins"));
+        assert!(messages.objectives()[0].complete);
+        assert!(messages.has_unread());
+
+        let log: Vec<(u64, &str)> = messages.log()
+            .iter()
+            .map(|message| (message.seconds, &message.text[..]))
+            .collect();
+        assert_eq!(vec![(10, "New objective: Find the ruins"), (40, "Objective complete: Find the ruins")],
+                   log);
+    }
+}
@@ -0,0 +1,62 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod action_batcher;
+mod announcements;
+mod effects;
+mod events;
+mod exploration;
+mod game_rng;
+mod messages;
+pub mod path_finder;
+mod occupied_tiles;
+mod player_stats;
+mod player_techs;
+mod players;
+mod scores;
+mod scripts;
+mod simulation_tick;
+mod spawn_queue;
+mod stockpiles;
+pub mod terrain;
+mod timeline;
+mod victory;
+
+pub use self::action_batcher::ActionBatcher;
+pub use self::announcements::{Announcement, Announcements};
+pub use self::effects::{Effect, EffectShape, EffectSpawner};
+pub use self::events::{EventReader, Events};
+pub use self::exploration::Exploration;
+pub use self::game_rng::GameRng;
+pub use self::messages::{LoggedMessage, Messages, Objective};
+pub use self::occupied_tiles::{OccupiedTile, OccupiedTiles};
+pub use self::path_finder::{HostileTiles, PathFinder, ReachableTiles};
+pub use self::player_stats::{PlayerStatistics, PlayerStats};
+pub use self::player_techs::PlayerTechs;
+pub use self::players::{Player, Players};
+pub use self::scores::{Score, ScoreInputs, Scores};
+pub use self::scripts::{SCRIPT_INTERVAL_TICKS, Script, ScriptApi, Scripts};
+pub use self::simulation_tick::SimulationTick;
+pub use self::spawn_queue::SpawnQueue;
+pub use self::stockpiles::Stockpiles;
+pub use self::terrain::{Terrain, Tile};
+pub use self::timeline::{SAMPLE_SECONDS, Timeline, TimelineSample};
+pub use self::victory::{Countdown, CountdownKind, CountdownUpdate, Victory};
@@ -0,0 +1,161 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use specs::Index;
+use std::collections::HashMap;
+
+/// (row, col) tile position
+pub type OccupiedTile = (i32, i32);
+
+/// Tracks which tiles are blocked and by whom. Entities that block tiles occupy their footprint
+/// when they're created, update it if they move, and release it when they die or are deleted.
+/// All changes must go through these methods so that the footprints and the tile lookup agree.
+///
+/// Footprints are allowed to overlap (scenarios place objects on top of each other), so a tile
+/// keeps every entity on it and stays occupied until the last of them releases it.
+#[derive(Clone)]
+pub struct OccupiedTiles {
+    /// Everyone on each tile, in the order they got there
+    occupants: HashMap<OccupiedTile, Vec<Index>>,
+    footprints: HashMap<Index, Vec<OccupiedTile>>,
+}
+
+persist_struct!(OccupiedTiles { occupants, footprints });
+
+impl OccupiedTiles {
+    pub fn new() -> OccupiedTiles {
+        OccupiedTiles {
+            occupants: HashMap::new(),
+            footprints: HashMap::new(),
+        }
+    }
+
+    pub fn is_occupied(&self, tile: &OccupiedTile) -> bool {
+        self.occupants.contains_key(tile)
+    }
+
+    /// The first entity to occupy the tile, out of everyone that's still on it
+    pub fn occupant(&self, tile: &OccupiedTile) -> Option<Index> {
+        self.occupants.get(tile).and_then(|entity_ids| entity_ids.first().cloned())
+    }
+
+    /// Marks the tiles as occupied by the entity, replacing whatever footprint it had before
+    pub fn occupy(&mut self, entity_id: Index, tiles: Vec<OccupiedTile>) {
+        self.release(entity_id);
+        for tile in &tiles {
+            self.occupants.entry(*tile).or_insert_with(Vec::new).push(entity_id);
+        }
+        self.footprints.insert(entity_id, tiles);
+    }
+
+    /// Moves the entity's footprint; returns false if it didn't change
+    pub fn update(&mut self, entity_id: Index, tiles: Vec<OccupiedTile>) -> bool {
+        if self.footprints.get(&entity_id).map(|current| *current == tiles).unwrap_or(false) {
+            return false;
+        }
+        self.occupy(entity_id, tiles);
+        true
+    }
+
+    /// Takes the entity off every tile it occupies (if any). Tiles that other entities are
+    /// also on stay occupied by them.
+    pub fn release(&mut self, entity_id: Index) {
+        if let Some(tiles) = self.footprints.remove(&entity_id) {
+            for tile in &tiles {
+                let now_empty = match self.occupants.get_mut(tile) {
+                    Some(entity_ids) => {
+                        entity_ids.retain(|&id| id != entity_id);
+                        entity_ids.is_empty()
+                    }
+                    None => false,
+                };
+                if now_empty {
+                    self.occupants.remove(tile);
+                }
+            }
+        }
+    }
+
+    pub fn footprint<'a>(&'a self, entity_id: Index) -> Option<&'a Vec<OccupiedTile>> {
+        self.footprints.get(&entity_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OccupiedTiles;
+
+    #[test]
+    fn test_occupy_and_release() {
+        let mut tiles = OccupiedTiles::new();
+        tiles.occupy(1, vec![(0, 0), (0, 1)]);
+        tiles.occupy(2, vec![(5, 5)]);
+        assert!(tiles.is_occupied(&(0, 1)));
+        assert_eq!(Some(2), tiles.occupant(&(5, 5)));
+        assert_eq!(None, tiles.occupant(&(1, 1)));
+
+        tiles.release(1);
+        assert!(!tiles.is_occupied(&(0, 0)));
+        assert!(!tiles.is_occupied(&(0, 1)));
+        assert!(tiles.is_occupied(&(5, 5)));
+
+        // Releasing an entity that doesn't occupy anything is fine
+        tiles.release(1);
+        tiles.release(3);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut tiles = OccupiedTiles::new();
+        tiles.occupy(1, vec![(0, 0), (0, 1)]);
+        assert!(!tiles.update(1, vec![(0, 0), (0, 1)]));
+        assert!(tiles.update(1, vec![(0, 1), (0, 2)]));
+        assert!(!tiles.is_occupied(&(0, 0)));
+        assert_eq!(Some(1), tiles.occupant(&(0, 2)));
+        assert_eq!(Some(&vec![(0, 1), (0, 2)]), tiles.footprint(1));
+    }
+
+    #[test]
+    fn test_overlapping_footprints() {
+        let mut tiles = OccupiedTiles::new();
+        tiles.occupy(1, vec![(0, 0), (0, 1)]);
+        tiles.occupy(2, vec![(0, 1), (0, 2)]);
+        assert_eq!(Some(1), tiles.occupant(&(0, 1)));
+
+        // Only the entity's own hold on the shared tile goes away
+        tiles.release(1);
+        assert!(!tiles.is_occupied(&(0, 0)));
+        assert_eq!(Some(2), tiles.occupant(&(0, 1)));
+
+        tiles.release(2);
+        assert!(!tiles.is_occupied(&(0, 1)));
+        assert!(!tiles.is_occupied(&(0, 2)));
+    }
+
+    #[test]
+    fn test_occupying_again_replaces_the_footprint() {
+        let mut tiles = OccupiedTiles::new();
+        tiles.occupy(1, vec![(0, 0)]);
+        tiles.occupy(1, vec![(3, 3)]);
+        assert!(!tiles.is_occupied(&(0, 0)));
+        assert_eq!(Some(&vec![(3, 3)]), tiles.footprint(1));
+    }
+}
@@ -0,0 +1,658 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+pub use action::{Path, PathNode};
+use dat;
+use ecs::resource::{OccupiedTiles, Terrain};
+use identifier::{TerrainId, UnitTerrainRestrictionId};
+use std::cmp;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::collections::HashSet;
+use types::{Fixed, ToFixed, Vector3};
+use util::movement::{self, STEP_COST};
+
+const PASSABILITY_THRESHOLD: f32 = 0.999;
+
+/// How many tiles away from an impassable destination to look for a passable one
+pub const MAX_DESTINATION_SEARCH_RADIUS: i32 = 8;
+
+/// What walking onto a hostile tile costs on top of the terrain's own cost, so routes around
+/// hostile territory win unless they're more than five times as long
+const HOSTILE_STEP_PENALTY: i32 = STEP_COST * 4;
+
+type TileNode = (i32, i32);
+type TilePath = Vec<TileNode>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TilePathCandidate {
+    path: TilePath,
+
+    /// What it costs to walk the path so far, in `STEP_COST`s per tile at full speed
+    cost: i32,
+    heuristic: i32,
+    dist_from_target: i32,
+    direction: (i32, i32),
+}
+
+impl TilePathCandidate {
+    fn new(path: TilePath,
+           cost: i32,
+           heuristic: i32,
+           dist_from_target: i32,
+           direction: (i32, i32))
+           -> TilePathCandidate {
+        TilePathCandidate {
+            path: path,
+            cost: cost,
+            heuristic: heuristic,
+            dist_from_target: dist_from_target,
+            direction: direction,
+        }
+    }
+}
+
+impl Ord for TilePathCandidate {
+    fn cmp(&self, other: &TilePathCandidate) -> Ordering {
+        other.heuristic.cmp(&self.heuristic)
+    }
+}
+
+impl PartialOrd for TilePathCandidate {
+    fn partial_cmp(&self, other: &TilePathCandidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Trait for faking out the Empires DB for tests
+trait PassabilityProvider: Send + Sync {
+    fn passable(&self, restriction_id: UnitTerrainRestrictionId, terrain_id: TerrainId) -> bool;
+
+    /// What it costs to walk onto a tile of the terrain, `STEP_COST` being full speed
+    fn step_cost(&self, terrain_id: TerrainId) -> i32;
+}
+
+struct EmpiresPassabilityProvider {
+    empires: dat::EmpiresDbRef,
+}
+
+impl EmpiresPassabilityProvider {
+    pub fn new(empires: dat::EmpiresDbRef) -> EmpiresPassabilityProvider {
+        EmpiresPassabilityProvider { empires: empires }
+    }
+}
+
+impl PassabilityProvider for EmpiresPassabilityProvider {
+    fn passable(&self, restriction_id: UnitTerrainRestrictionId, terrain_id: TerrainId) -> bool {
+        let restrictions = self.empires.terrain_restrictions(restriction_id);
+        restrictions.passability(terrain_id) >= PASSABILITY_THRESHOLD
+    }
+
+    fn step_cost(&self, terrain_id: TerrainId) -> i32 {
+        movement::terrain_step_cost(terrain_id)
+    }
+}
+
+pub struct PathFinder {
+    passability_provider: Box<PassabilityProvider>,
+}
+
+/// The tiles a unit can walk to from where it stands, ignoring other units
+pub struct ReachableTiles {
+    restriction_id: UnitTerrainRestrictionId,
+    width: i32,
+    height: i32,
+    reachable: Vec<bool>,
+}
+
+/// Tiles that paths keep out of when there's a way around, such as those near an enemy's buildings
+#[derive(Clone, Debug, Default)]
+pub struct HostileTiles {
+    tiles: HashSet<TileNode>,
+}
+
+impl HostileTiles {
+    pub fn new() -> HostileTiles {
+        HostileTiles { tiles: HashSet::new() }
+    }
+
+    /// Marks every tile within `radius` tiles of the position
+    pub fn add_around(&mut self, position: &Vector3, radius: i32) {
+        let center: TileNode = (position.y.into(), position.x.into());
+        for row in (center.0 - radius)..(center.0 + radius + 1) {
+            for col in (center.1 - radius)..(center.1 + radius + 1) {
+                self.tiles.insert((row, col));
+            }
+        }
+    }
+
+    fn step_penalty(&self, tile: &TileNode) -> i32 {
+        if self.tiles.contains(tile) { HOSTILE_STEP_PENALTY } else { 0 }
+    }
+}
+
+impl ReachableTiles {
+    pub fn restriction_id(&self) -> UnitTerrainRestrictionId {
+        self.restriction_id
+    }
+
+    pub fn contains(&self, position: &Vector3) -> bool {
+        self.contains_tile((position.y.into(), position.x.into()))
+    }
+
+    fn contains_tile(&self, tile: TileNode) -> bool {
+        if tile.0 < 0 || tile.1 < 0 || tile.0 >= self.height || tile.1 >= self.width {
+            return false;
+        }
+        self.reachable[(tile.0 * self.width + tile.1) as usize]
+    }
+}
+
+impl PathFinder {
+    pub fn new(empires: dat::EmpiresDbRef) -> PathFinder {
+        PathFinder { passability_provider: Box::new(EmpiresPassabilityProvider::new(empires)) }
+    }
+
+    #[cfg(test)]
+    fn new_with(passability_provider: Box<PassabilityProvider>) -> PathFinder {
+        PathFinder { passability_provider: passability_provider }
+    }
+
+    pub fn find_path(&self,
+                     terrain: &Terrain,
+                     occupied_tiles: &OccupiedTiles,
+                     from: &Vector3,
+                     to: &Vector3,
+                     restriction_id: UnitTerrainRestrictionId)
+                     -> Path {
+        self.find_path_avoiding(terrain,
+                                occupied_tiles,
+                                from,
+                                to,
+                                restriction_id,
+                                &HostileTiles::new())
+    }
+
+    /// Like `find_path`, but goes around the hostile tiles unless the detour is much longer
+    pub fn find_path_avoiding(&self,
+                              terrain: &Terrain,
+                              occupied_tiles: &OccupiedTiles,
+                              from: &Vector3,
+                              to: &Vector3,
+                              restriction_id: UnitTerrainRestrictionId,
+                              hostile_tiles: &HostileTiles)
+                              -> Path {
+        let from_tile: (i32, i32) = (from.y.into(), from.x.into());
+        let to_tile: (i32, i32) = (to.y.into(), to.x.into());
+        let tile_path = self.find_tile_path(terrain,
+                                            occupied_tiles,
+                                            from_tile,
+                                            to_tile,
+                                            restriction_id,
+                                            hostile_tiles);
+
+        let mut position_path: Vec<PathNode> = Vec::new();
+        for tile_node in tile_path.iter().skip(1) {
+            let tile = terrain.tile_at_row_col(tile_node.0, tile_node.1);
+            let position = Vector3::new(tile_node.1.to_fixed() + fixed_const!(0.5),
+                                        tile_node.0.to_fixed() + fixed_const!(0.5),
+                                        tile.elevation.to_fixed());
+            position_path.push(position);
+        }
+        if *tile_path.last().unwrap() == to_tile {
+            position_path.pop(); // Remove the tile center for the last tile
+            position_path.push(*to);
+        }
+
+        position_path
+    }
+
+    /// Number of tiles a unit has to walk to get within `reach` tiles of `to`, or None if it can't
+    /// get there. Use a reach of at least half the size of a building to find how far away it is,
+    /// since the tiles under the building itself are occupied. The walk goes around the hostile
+    /// tiles when it can, and each hostile tile it can't avoid counts as five.
+    pub fn path_distance(&self,
+                         terrain: &Terrain,
+                         occupied_tiles: &OccupiedTiles,
+                         from: &Vector3,
+                         to: &Vector3,
+                         reach: i32,
+                         restriction_id: UnitTerrainRestrictionId,
+                         hostile_tiles: &HostileTiles)
+                         -> Option<i32> {
+        let from_tile: TileNode = (from.y.into(), from.x.into());
+        let to_tile: TileNode = (to.y.into(), to.x.into());
+        let to_tile = clamp(to_tile, terrain.width(), terrain.height());
+        let tile_path = self.search(terrain,
+                                    occupied_tiles,
+                                    from_tile,
+                                    to_tile,
+                                    reach,
+                                    restriction_id,
+                                    hostile_tiles);
+        if dist(tile_path.last().unwrap(), &to_tile) > reach {
+            return None;
+        }
+        let penalty: i32 = tile_path.iter()
+            .skip(1)
+            .map(|tile| hostile_tiles.step_penalty(tile) / STEP_COST)
+            .sum();
+        Some(tile_path.len() as i32 - 1 + penalty)
+    }
+
+    /// Flood fills outward from `from` to find every tile reachable with the given restriction.
+    /// This is much cheaper than a search that can't succeed, so commands check it first.
+    pub fn reachable_tiles(&self,
+                           terrain: &Terrain,
+                           from: &Vector3,
+                           restriction_id: UnitTerrainRestrictionId)
+                           -> ReachableTiles {
+        let (width, height) = (terrain.width(), terrain.height());
+        let mut reachable = vec![false; (width * height) as usize];
+        let from_tile: TileNode = (from.y.into(), from.x.into());
+        let from_tile = (cmp::min(height - 1, cmp::max(0, from_tile.0)),
+                         cmp::min(width - 1, cmp::max(0, from_tile.1)));
+
+        // The unit's own tile counts even if it's impassable (a boat at a dock, for instance)
+        let mut pending = vec![from_tile];
+        reachable[(from_tile.0 * width + from_tile.1) as usize] = true;
+        while let Some(node) = pending.pop() {
+            for neighbor in neighbors(&node, width - 1, height - 1).into_iter() {
+                let index = (neighbor.0 * width + neighbor.1) as usize;
+                if !reachable[index] &&
+                   self.passability_provider
+                    .passable(restriction_id, terrain.tile_at_row_col(neighbor.0, neighbor.1).terrain_id) {
+                    reachable[index] = true;
+                    pending.push(*neighbor);
+                }
+            }
+        }
+
+        ReachableTiles {
+            restriction_id: restriction_id,
+            width: width,
+            height: height,
+            reachable: reachable,
+        }
+    }
+
+    /// Where a unit should go when told to move to `to`: `to` itself if it can get there,
+    /// otherwise the center of the closest reachable tile within `MAX_DESTINATION_SEARCH_RADIUS`
+    /// tiles (so right-clicking the sea with a land unit walks it to the shore), or None.
+    pub fn passable_destination(&self,
+                                terrain: &Terrain,
+                                reachable: &ReachableTiles,
+                                to: &Vector3)
+                                -> Option<Vector3> {
+        if reachable.contains(to) {
+            return Some(*to);
+        }
+
+        let to_tile: TileNode = (to.y.into(), to.x.into());
+        for radius in 1..(MAX_DESTINATION_SEARCH_RADIUS + 1) {
+            let mut closest: Option<(i32, TileNode)> = None;
+            for row in (to_tile.0 - radius)..(to_tile.0 + radius + 1) {
+                for col in (to_tile.1 - radius)..(to_tile.1 + radius + 1) {
+                    let tile = (row, col);
+                    if dist(&tile, &to_tile) != radius || !reachable.contains_tile(tile) {
+                        continue;
+                    }
+                    // Prefer straight lines over the corners of the ring
+                    let (drow, dcol) = (row - to_tile.0, col - to_tile.1);
+                    let distance = drow * drow + dcol * dcol;
+                    if closest.map(|(best, _)| distance < best).unwrap_or(true) {
+                        closest = Some((distance, tile));
+                    }
+                }
+            }
+            if let Some((_, tile)) = closest {
+                let elevation = terrain.tile_at_row_col(tile.0, tile.1).elevation;
+                return Some(Vector3::new(tile.1.to_fixed() + fixed_const!(0.5),
+                                         tile.0.to_fixed() + fixed_const!(0.5),
+                                         elevation.to_fixed()));
+            }
+        }
+        None
+    }
+
+    fn find_tile_path(&self,
+                      terrain: &Terrain,
+                      occupied_tiles: &OccupiedTiles,
+                      from: TileNode,
+                      to: TileNode,
+                      restriction_id: UnitTerrainRestrictionId,
+                      hostile_tiles: &HostileTiles)
+                      -> TilePath {
+        self.search(terrain, occupied_tiles, from, to, 0, restriction_id, hostile_tiles)
+    }
+
+    /// Finds a path that ends within `reach` tiles of `to`,
+    /// or the path that gets closest if there isn't one
+    fn search(&self,
+              terrain: &Terrain,
+              occupied_tiles: &OccupiedTiles,
+              from: TileNode,
+              to: TileNode,
+              reach: i32,
+              restriction_id: UnitTerrainRestrictionId,
+              hostile_tiles: &HostileTiles)
+              -> TilePath {
+        let (width, height) = (terrain.width(), terrain.height());
+        let (from, to) = (clamp(from, width, height), clamp(to, width, height));
+        if dist(&from, &to) <= reach {
+            return vec![from];
+        }
+
+        // For tracking the path that comes closest to the target in case it's not possible to reach the target
+        let mut closest = {
+            let distance = dist(&from, &to);
+            TilePathCandidate::new(vec![from],
+                                   STEP_COST,
+                                   heuristic(STEP_COST, distance, false),
+                                   distance,
+                                   (0, 0))
+        };
+
+        // For tracking nodes whose neighbors we've already pushed onto the queue
+        let mut visited: HashSet<TileNode> = HashSet::new();
+        visited.insert(from);
+
+        let mut path_queue: BinaryHeap<TilePathCandidate> = BinaryHeap::new();
+        path_queue.push(closest.clone());
+
+        // Breadth-first search with priority queue and heuristic; also known as A*. Slow terrain
+        // costs more to cross, so the search goes around shallows when that's quicker.
+        while !path_queue.is_empty() {
+            let next = path_queue.pop().unwrap();
+            let last_node = *next.path.last().unwrap();
+            if next.dist_from_target <= reach {
+                return next.path;
+            } else {
+                // Setup future exploration of neighbors
+                for neighbor in neighbors(&last_node, width, height).into_iter() {
+                    let tile = terrain.tile_at_row_col(neighbor.0, neighbor.1);
+                    if !visited.contains(neighbor) && !occupied_tiles.is_occupied(neighbor) &&
+                       self.passability_provider.passable(restriction_id, tile.terrain_id) {
+                        let mut neighbor_path = next.path.clone();
+                        neighbor_path.push(*neighbor);
+
+                        let neighbor_direction = (neighbor.0 - last_node.0, neighbor.1 - last_node.1);
+                        let neighbor_dist = dist(neighbor, &to);
+                        let neighbor_cost = next.cost + self.passability_provider.step_cost(tile.terrain_id) +
+                                            hostile_tiles.step_penalty(neighbor);
+                        let neighbor_heuristic = heuristic(neighbor_cost,
+                                                           neighbor_dist,
+                                                           next.direction != neighbor_direction);
+                        let neighbor_candidate = TilePathCandidate::new(neighbor_path,
+                                                                        neighbor_cost,
+                                                                        neighbor_heuristic,
+                                                                        neighbor_dist,
+                                                                        neighbor_direction);
+                        path_queue.push(neighbor_candidate);
+                    }
+                    visited.insert(*neighbor);
+                }
+
+                // Keep track of the closest path
+                if closest.dist_from_target > next.dist_from_target {
+                    closest = next;
+                }
+            }
+        }
+
+        closest.path
+    }
+}
+
+fn neighbors(node: &TileNode, width: i32, height: i32) -> [TileNode; 8] {
+    [clamp((node.0 - 1, node.1), width, height),
+     clamp((node.0 + 1, node.1), width, height),
+     clamp((node.0, node.1 - 1), width, height),
+     clamp((node.0, node.1 + 1), width, height),
+     clamp((node.0 - 1, node.1 - 1), width, height),
+     clamp((node.0 - 1, node.1 + 1), width, height),
+     clamp((node.0 + 1, node.1 - 1), width, height),
+     clamp((node.0 + 1, node.1 + 1), width, height)]
+}
+
+/// Every tile left to go costs at least a step at full speed, and turning costs one more step
+/// so that paths keep going straight when they can
+fn heuristic(cost: i32, dist_to_goal: i32, direction_change: bool) -> i32 {
+    cost + (dist_to_goal + direction_change as i32) * STEP_COST
+}
+
+fn dist(from: &TileNode, to: &TileNode) -> i32 {
+    // Chebyshev distance (from: http://theory.stanford.edu/~amitp/GameProgramming/Heuristics.html)
+    let rdist = (from.0 - to.0).abs();
+    let cdist = (from.1 - to.1).abs();
+    cmp::max(rdist, cdist)
+}
+
+fn clamp(node: TileNode, width: i32, height: i32) -> TileNode {
+    (cmp::min(height, cmp::max(0, node.0)), cmp::min(width, cmp::max(0, node.1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use dat::{EmpiresDb, EmpiresDbRef};
+    use ecs::resource::{OccupiedTiles, Terrain, Tile};
+    use identifier::{TerrainId, UnitTerrainRestrictionId};
+    use super::*;
+    use super::PassabilityProvider;
+
+    struct FakePassabilityProvider {
+        map: Vec<i32>,
+        width: i32,
+    }
+
+    impl PassabilityProvider for FakePassabilityProvider {
+        fn passable(&self, restriction_id: UnitTerrainRestrictionId, terrain_id: TerrainId) -> bool {
+            0 != *terrain_id
+        }
+
+        fn step_cost(&self, terrain_id: TerrainId) -> i32 {
+            // 2 is slow going (like shallows) for the purposes of these tests
+            if *terrain_id == 2 { STEP_COST * 2 } else { STEP_COST }
+        }
+    }
+
+    fn make_terrain_and_path_finder(passability: Vec<i32>, width: i32) -> (Terrain, PathFinder) {
+        let empires = EmpiresDbRef::new(EmpiresDb::new());
+        let tiles = passability.iter()
+            .cloned()
+            .map(|v| Tile::new((v as usize).into(), 0))
+            .collect();
+
+        let terrain = Terrain::new(width as i32,
+                                   passability.len() as i32 / width,
+                                   tiles,
+                                   empires);
+        let path_finder = PathFinder::new_with(Box::new(FakePassabilityProvider {
+            map: passability,
+            width: width,
+        }));
+        (terrain, path_finder)
+    }
+
+    #[test]
+    fn test_find_tile_path() {
+        let width = 7;
+        let map = vec![
+            1, 1, 1, 0, 1, 1, 1, // 1 = passable
+            1, 1, 1, 0, 1, 1, 1, // 0 = impassible
+            1, 1, 1, 0, 1, 1, 1, // for the purposes of this test
+            0, 0, 0, 0, 0, 1, 0,
+            1, 1, 0, 1, 1, 1, 1,
+            1, 1, 0, 1, 1, 0, 1,
+            1, 1, 1, 1, 1, 1, 1,
+        ];
+
+        let (terrain, path_finder) = make_terrain_and_path_finder(map, width);
+        let occupied_tiles = OccupiedTiles::new();
+
+        let test = &mut |from, to, exp| {
+            let path = path_finder.find_tile_path(&terrain,
+                                                  &occupied_tiles,
+                                                  from,
+                                                  to,
+                                                  UnitTerrainRestrictionId::Flying,
+                                                  &HostileTiles::new());
+            assert_eq!(exp, path);
+        };
+
+        // Simple cases
+        test((0, 0), (0, 0), vec![(0, 0)]);
+        test((0, 0), (0, 1), vec![(0, 0), (0, 1)]);
+        test((0, 0), (0, 2), vec![(0, 0), (0, 1), (0, 2)]);
+        test((0, 0), (2, 2), vec![(0, 0), (1, 1), (2, 2)]);
+
+        // Test impossible path just returns path to closest tile
+        test((0, 0), (2, 4), vec![(0, 0), (0, 1), (0, 2)]);
+        test((6, 0), (0, 0), vec![(6, 0), (5, 0), (4, 0)]);
+
+        // Test one corner of map to the other
+        test((2, 4),
+             (6, 0),
+             vec![(2, 4), (3, 5), (4, 4), (5, 3), (6, 2), (7, 1), (6, 0)]);
+    }
+
+    #[test]
+    fn test_find_tile_path_around_slow_terrain() {
+        let width = 5;
+        let map = vec![
+            1, 1, 1, 1, 1, // 2 = passable, but twice as slow
+            1, 2, 2, 2, 1,
+            0, 0, 0, 0, 0,
+        ];
+
+        let (terrain, path_finder) = make_terrain_and_path_finder(map, width);
+        let occupied_tiles = OccupiedTiles::new();
+        let path = path_finder.find_tile_path(&terrain,
+                                              &occupied_tiles,
+                                              (1, 0),
+                                              (1, 4),
+                                              UnitTerrainRestrictionId::Flying,
+                                              &HostileTiles::new());
+        assert_eq!(vec![(1, 0), (0, 1), (0, 2), (0, 3), (1, 4)], path);
+    }
+
+    #[test]
+    fn test_path_distance() {
+        let width = 5;
+        let map = vec![
+            1, 1, 0, 1, 1,
+            1, 1, 0, 1, 1,
+            1, 1, 0, 1, 1,
+            1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1,
+        ];
+
+        let (terrain, path_finder) = make_terrain_and_path_finder(map, width);
+        let occupied_tiles = OccupiedTiles::new();
+        let position = |row: i32, col: i32| Vector3::new(col.into(), row.into(), 0.into());
+        let distance = |from, to, reach| {
+            path_finder.path_distance(&terrain,
+                                      &occupied_tiles,
+                                      &from,
+                                      &to,
+                                      reach,
+                                      UnitTerrainRestrictionId::Flying,
+                                      &HostileTiles::new())
+        };
+
+        // The wall makes the path a lot longer than the straight line
+        assert_eq!(Some(6), distance(position(0, 0), position(0, 4), 0));
+        assert_eq!(Some(5), distance(position(0, 0), position(0, 4), 1));
+        assert_eq!(Some(0), distance(position(0, 0), position(1, 1), 1));
+
+        // The wall itself can't be reached, but tiles next to it can
+        assert_eq!(None, distance(position(0, 0), position(0, 2), 0));
+        assert_eq!(Some(1), distance(position(0, 0), position(0, 2), 1));
+    }
+
+    #[test]
+    fn test_path_distance_around_hostile_tiles() {
+        let width = 5;
+        let map = vec![1; 25];
+
+        let (terrain, path_finder) = make_terrain_and_path_finder(map, width);
+        let occupied_tiles = OccupiedTiles::new();
+        let position = |row: i32, col: i32| Vector3::new(col.into(), row.into(), 0.into());
+        let distance = |hostile_tiles: &HostileTiles| {
+            path_finder.path_distance(&terrain,
+                                      &occupied_tiles,
+                                      &position(0, 0),
+                                      &position(0, 4),
+                                      0,
+                                      UnitTerrainRestrictionId::Flying,
+                                      hostile_tiles)
+        };
+        assert_eq!(Some(4), distance(&HostileTiles::new()));
+
+        // Going around the hostile tiles in the middle of the top row takes two more steps
+        let mut hostile_tiles = HostileTiles::new();
+        hostile_tiles.add_around(&position(0, 2), 1);
+        assert_eq!(Some(6), distance(&hostile_tiles));
+
+        // There's no way around a column of them, so the one crossed counts as five
+        let mut hostile_tiles = HostileTiles::new();
+        for row in 0..5 {
+            hostile_tiles.add_around(&position(row, 2), 0);
+        }
+        assert_eq!(Some(8), distance(&hostile_tiles));
+    }
+
+    #[test]
+    fn test_passable_destination() {
+        let width = 6;
+        let map = vec![
+            1, 1, 0, 0, 0, 1,
+            1, 1, 0, 0, 0, 0,
+            1, 1, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ];
+
+        let (terrain, path_finder) = make_terrain_and_path_finder(map, width);
+        let position = |row: i32, col: i32| Vector3::new(col.into(), row.into(), 0.into());
+        let center = |row: i32, col: i32| {
+            Vector3::new(col.to_fixed() + fixed_const!(0.5),
+                         row.to_fixed() + fixed_const!(0.5),
+                         0.into())
+        };
+        let reachable =
+            path_finder.reachable_tiles(&terrain, &position(0, 0), UnitTerrainRestrictionId::Flying);
+
+        // The island in the top right corner is passable, but can't be reached
+        assert!(reachable.contains(&position(2, 1)));
+        assert!(!reachable.contains(&position(0, 5)));
+
+        assert_eq!(Some(position(1, 1)),
+                   path_finder.passable_destination(&terrain, &reachable, &position(1, 1)));
+        assert_eq!(Some(center(1, 1)),
+                   path_finder.passable_destination(&terrain, &reachable, &position(1, 3)));
+        assert_eq!(Some(center(2, 1)),
+                   path_finder.passable_destination(&terrain, &reachable, &position(5, 1)));
+        assert_eq!(Some(center(0, 1)),
+                   path_finder.passable_destination(&terrain, &reachable, &position(0, 5)));
+    }
+}
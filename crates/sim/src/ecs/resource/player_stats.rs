@@ -0,0 +1,134 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use identifier::PlayerId;
+use std::collections::BTreeMap;
+
+/// Running totals for one player, shown in the post-game statistics
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerStatistics {
+    pub units_killed: u32,
+    pub units_lost: u32,
+    pub buildings_razed: u32,
+    pub buildings_lost: u32,
+    pub resources_gathered: u32,
+
+    /// Whether the player is out of the game, and whether they gave up rather than lost
+    pub defeated: bool,
+    pub resigned: bool,
+}
+
+persist_struct!(PlayerStatistics {
+    units_killed,
+    units_lost,
+    buildings_razed,
+    buildings_lost,
+    resources_gathered,
+    defeated,
+    resigned
+});
+
+/// Every player's statistics
+#[derive(Clone, Debug, Default)]
+pub struct PlayerStats {
+    players: BTreeMap<PlayerId, PlayerStatistics>,
+}
+
+persist_struct!(PlayerStats { players });
+
+impl PlayerStats {
+    pub fn new() -> PlayerStats {
+        PlayerStats { players: BTreeMap::new() }
+    }
+
+    /// Players that haven't done anything yet have all zeroes
+    pub fn player(&self, player_id: PlayerId) -> PlayerStatistics {
+        self.players.get(&player_id).cloned().unwrap_or_else(Default::default)
+    }
+
+    /// Counts resources that were dropped off at one of the player's buildings
+    pub fn record_gathered(&mut self, player_id: PlayerId, amount: u32) {
+        self.players.entry(player_id).or_insert_with(Default::default).resources_gathered += amount;
+    }
+
+    pub fn record_defeat(&mut self, player_id: PlayerId, resigned: bool) {
+        let player = self.players.entry(player_id).or_insert_with(Default::default);
+        player.defeated = true;
+        player.resigned = resigned;
+    }
+
+    /// Counts a unit or building that died. The killer only gets credit for killing someone
+    /// else's; deleting your own or hitting it with friendly fire only counts as a loss.
+    pub fn record_death(&mut self, owner_id: PlayerId, killer_id: Option<PlayerId>, building: bool) {
+        {
+            let owner = self.players.entry(owner_id).or_insert_with(Default::default);
+            if building {
+                owner.buildings_lost += 1;
+            } else {
+                owner.units_lost += 1;
+            }
+        }
+
+        if let Some(killer_id) = killer_id {
+            if killer_id != owner_id {
+                let killer = self.players.entry(killer_id).or_insert_with(Default::default);
+                if building {
+                    killer.buildings_razed += 1;
+                } else {
+                    killer.units_killed += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::PlayerId;
+    use super::{PlayerStatistics, PlayerStats};
+
+    #[test]
+    fn test_razing_credits_the_attacker() {
+        let (owner, attacker): (PlayerId, PlayerId) = (1.into(), 2.into());
+        let mut stats = PlayerStats::new();
+        stats.record_death(owner, Some(attacker), true);
+        stats.record_death(owner, Some(attacker), false);
+
+        assert_eq!(1, stats.player(owner).buildings_lost);
+        assert_eq!(1, stats.player(owner).units_lost);
+        assert_eq!(1, stats.player(attacker).buildings_razed);
+        assert_eq!(1, stats.player(attacker).units_killed);
+        assert_eq!(0, stats.player(owner).buildings_razed);
+    }
+
+    #[test]
+    fn test_own_losses_give_no_credit() {
+        let owner: PlayerId = 1.into();
+        let mut stats = PlayerStats::new();
+        stats.record_death(owner, Some(owner), true);
+        stats.record_death(owner, None, true);
+
+        assert_eq!(2, stats.player(owner).buildings_lost);
+        assert_eq!(0, stats.player(owner).buildings_razed);
+        assert_eq!(PlayerStatistics::default(), stats.player(3.into()));
+    }
+}
@@ -0,0 +1,142 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::{PlayerId, ResearchId, UnitId};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Debug, Default)]
+struct TechState {
+    researched: BTreeSet<ResearchId>,
+
+    /// Base unit to the unit it's been upgraded to (always the end of the upgrade line)
+    unit_upgrades: BTreeMap<UnitId, UnitId>,
+
+    /// Whether the player sees what their allies see (from Writing)
+    shares_exploration: bool,
+}
+
+persist_struct!(TechState { researched, unit_upgrades, shares_exploration });
+
+/// What each player has researched, and what that's done to the units they have and train
+#[derive(Clone, Debug, Default)]
+pub struct PlayerTechs {
+    players: BTreeMap<PlayerId, TechState>,
+}
+
+persist_struct!(PlayerTechs { players });
+
+impl PlayerTechs {
+    pub fn new() -> PlayerTechs {
+        PlayerTechs { players: BTreeMap::new() }
+    }
+
+    pub fn has_researched(&self, player_id: PlayerId, research_id: ResearchId) -> bool {
+        self.players.get(&player_id).map(|state| state.researched.contains(&research_id)).unwrap_or(false)
+    }
+
+    /// Marks the research as done; returns false if the player already had it
+    pub fn complete_research(&mut self, player_id: PlayerId, research_id: ResearchId) -> bool {
+        self.players.entry(player_id).or_insert_with(Default::default).researched.insert(research_id)
+    }
+
+    /// Makes the player's `from` units (and any units that were already upgraded to `from`)
+    /// become `to` units
+    pub fn upgrade_unit(&mut self, player_id: PlayerId, from: UnitId, to: UnitId) {
+        let state = self.players.entry(player_id).or_insert_with(Default::default);
+        for target in state.unit_upgrades.values_mut() {
+            if *target == from {
+                *target = to;
+            }
+        }
+        if from != to {
+            state.unit_upgrades.insert(from, to);
+        }
+        // An upgrade back to a unit in the line would make a cycle; the later upgrade wins
+        state.unit_upgrades.remove(&to);
+    }
+
+    /// The unit that the player gets when they train (or are given) a `unit_id` unit
+    pub fn upgraded_unit(&self, player_id: PlayerId, unit_id: UnitId) -> UnitId {
+        self.players
+            .get(&player_id)
+            .and_then(|state| state.unit_upgrades.get(&unit_id))
+            .cloned()
+            .unwrap_or(unit_id)
+    }
+
+    /// Lets the player see everything their allies' units see
+    pub fn share_exploration(&mut self, player_id: PlayerId) {
+        self.players.entry(player_id).or_insert_with(Default::default).shares_exploration = true;
+    }
+
+    pub fn shares_exploration(&self, player_id: PlayerId) -> bool {
+        self.players.get(&player_id).map(|state| state.shares_exploration).unwrap_or(false)
+    }
+
+    /// Every completed research, ordered by player and then research
+    pub fn researched(&self) -> Vec<(PlayerId, ResearchId)> {
+        let mut researched = Vec::new();
+        for (player_id, state) in &self.players {
+            for research_id in &state.researched {
+                researched.push((*player_id, *research_id));
+            }
+        }
+        researched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::{PlayerId, UnitId};
+    use super::PlayerTechs;
+
+    fn ids(player: usize, unit: usize) -> (PlayerId, UnitId) {
+        (player.into(), unit.into())
+    }
+
+    #[test]
+    fn test_complete_research_once() {
+        let mut techs = PlayerTechs::new();
+        let player: PlayerId = 1.into();
+        assert!(techs.complete_research(player, 46.into()));
+        assert!(!techs.complete_research(player, 46.into()));
+        assert!(techs.has_researched(player, 46.into()));
+        assert!(!techs.has_researched(2.into(), 46.into()));
+    }
+
+    #[test]
+    fn test_upgrade_lines_follow_through() {
+        let mut techs = PlayerTechs::new();
+        let (player, clubman) = ids(1, 73);
+        let axeman: UnitId = 74.into();
+        let short_swordsman: UnitId = 75.into();
+
+        techs.upgrade_unit(player, clubman, axeman);
+        assert_eq!(axeman, techs.upgraded_unit(player, clubman));
+        assert_eq!(clubman, techs.upgraded_unit(2.into(), clubman));
+
+        // Units trained as the base type skip straight to the end of the line
+        techs.upgrade_unit(player, axeman, short_swordsman);
+        assert_eq!(short_swordsman, techs.upgraded_unit(player, clubman));
+        assert_eq!(short_swordsman, techs.upgraded_unit(player, axeman));
+        assert_eq!(short_swordsman, techs.upgraded_unit(player, short_swordsman));
+    }
+}
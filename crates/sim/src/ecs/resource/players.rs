@@ -0,0 +1,271 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::GameSetup;
+use ecs::resource::PlayerTechs;
+use identifier::{CivilizationId, PlayerColorId, PlayerId};
+use scn::Scenario;
+use std::cmp;
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub struct Player {
+    pub name: String,
+    pub player_id: PlayerId,
+    pub player_color_id: PlayerColorId,
+    pub civ_id: CivilizationId,
+    pub team: u8,
+    /// Shows allies what this player's units see, if the allies have it turned on too
+    pub allied_vision: bool,
+}
+
+impl Player {
+    pub fn new(name: String,
+               player_id: PlayerId,
+               player_color_id: PlayerColorId,
+               civ_id: CivilizationId,
+               team: u8)
+               -> Player {
+        Player {
+            name: name,
+            player_id: player_id,
+            player_color_id: player_color_id,
+            civ_id: civ_id,
+            team: team,
+            allied_vision: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Players {
+    local_player_id: PlayerId,
+    observing: bool,
+    players: HashMap<PlayerId, Player>,
+}
+
+impl Players {
+    pub fn new() -> Players {
+        Players {
+            local_player_id: 0.into(),
+            observing: false,
+            players: HashMap::new(),
+        }
+    }
+
+    pub fn from_scenario(scenario: &Scenario, local_player_id: PlayerId) -> Players {
+        let mut players = Players::new();
+        let teams = scenario.player_teams();
+        for player_id in scenario.player_ids() {
+            let name = scenario.player_data.player_names[*player_id as usize].clone();
+            let civ_id = scenario.player_data.player_civs[*player_id as usize].civilization_id;
+            // Player colors count from 0 (blue) for player 1; gaia's color never shows
+            let color_id = (cmp::max(*player_id as usize, 1) - 1).into();
+            let local = player_id == local_player_id;
+            // The scenario's alliances pick the teams unless the lobby says otherwise
+            let team = teams[*player_id as usize];
+            players.add_player(Player::new(name, player_id, color_id, civ_id, team), local);
+        }
+        players
+    }
+
+    /// Scenario players with any choices made in the lobby applied on top
+    pub fn from_setup(scenario: &Scenario, setup: &GameSetup) -> Players {
+        let mut players = Players::from_scenario(scenario, setup.local_player_id);
+        players.observing = setup.observer;
+        for player_setup in &setup.players {
+            if let Some(player) = players.players.get_mut(&player_setup.player_id) {
+                player.name = player_setup.name.clone();
+                player.player_color_id = player_setup.player_color_id;
+                player.civ_id = player_setup.civilization_id;
+                player.team = player_setup.team;
+                player.allied_vision = player_setup.allied_vision;
+            }
+        }
+        players
+    }
+
+    pub fn add_player(&mut self, player: Player, local: bool) {
+        if local {
+            self.local_player_id = player.player_id;
+        }
+        self.players.insert(player.player_id, player);
+    }
+
+    pub fn player<'a>(&'a self, player_id: PlayerId) -> Option<&'a Player> {
+        self.players.get(&player_id)
+    }
+
+    /// The player's name, or a generic one for players that aren't in the game (such as gaia)
+    pub fn name(&self, player_id: PlayerId) -> String {
+        match self.players.get(&player_id) {
+            Some(player) => player.name.clone(),
+            None => format!("Player {}", *player_id),
+        }
+    }
+
+    /// Which player colors the player's units are drawn in, as the player index that SLPs remap their
+    /// player color pixels with. SLPs count player colors from 1, and 0 leaves the pixels as they
+    /// are, which is what gaia (and anyone that isn't in the game) gets.
+    pub fn sprite_color(&self, player_id: PlayerId) -> PlayerColorId {
+        match self.players.get(&player_id) {
+            Some(player) if *player_id != 0 => (*player.player_color_id as usize + 1).into(),
+            _ => 0.into(),
+        }
+    }
+
+    /// Moves the player onto another team, as the scenario editor does
+    pub fn set_team(&mut self, player_id: PlayerId, team: u8) {
+        if let Some(player) = self.players.get_mut(&player_id) {
+            player.team = team;
+        }
+    }
+
+    /// Everyone in the game (including gaia), in player order
+    pub fn player_ids(&self) -> Vec<PlayerId> {
+        let mut player_ids: Vec<PlayerId> = self.players.keys().cloned().collect();
+        player_ids.sort();
+        player_ids
+    }
+
+    pub fn local_player_id(&self) -> PlayerId {
+        self.local_player_id
+    }
+
+    pub fn local_player<'a>(&'a self) -> &'a Player {
+        let local_player_id = self.local_player_id;
+        &self.players[&local_player_id]
+    }
+
+    /// Whether the two players' units fight each other. Nobody fights gaia or their own team.
+    pub fn are_enemies(&self, player_id: PlayerId, other_id: PlayerId) -> bool {
+        let team = |player_id| self.players.get(&player_id).map(|player| player.team);
+        player_id != other_id && *player_id != 0 && *other_id != 0 &&
+        (team(player_id).is_none() || team(player_id) != team(other_id))
+    }
+
+    /// Whether the viewer gets to see what the other player's units see: their own, and their
+    /// allies' when both have allied vision turned on or the viewer has researched a tech that
+    /// shares exploration (Writing)
+    pub fn shares_vision(&self, techs: &PlayerTechs, viewer_id: PlayerId, other_id: PlayerId) -> bool {
+        if viewer_id == other_id {
+            return true;
+        }
+        if *viewer_id == 0 || *other_id == 0 || self.are_enemies(viewer_id, other_id) {
+            return false;
+        }
+        let allied_vision = |player_id| {
+            self.players.get(&player_id).map(|player| player.allied_vision).unwrap_or(false)
+        };
+        (allied_vision(viewer_id) && allied_vision(other_id)) || techs.shares_exploration(viewer_id)
+    }
+
+    /// Every player whose units' vision is merged into what the viewer sees, in player order
+    pub fn vision_player_ids(&self, techs: &PlayerTechs, viewer_id: PlayerId) -> Vec<PlayerId> {
+        self.player_ids()
+            .into_iter()
+            .filter(|&player_id| self.shares_vision(techs, viewer_id, player_id))
+            .collect()
+    }
+
+    /// Observers watch a game without owning a player
+    pub fn is_observing(&self) -> bool {
+        self.observing
+    }
+
+    /// Whether the local player is allowed to issue commands to the given player's units
+    pub fn can_command(&self, player_id: PlayerId) -> bool {
+        !self.observing && player_id == self.local_player_id
+    }
+
+    /// Switches an observer's perspective to another player; players can't do this
+    pub fn observe_player(&mut self, player_id: PlayerId) {
+        if self.observing && self.players.contains_key(&player_id) {
+            self.local_player_id = player_id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::resource::PlayerTechs;
+    use identifier::{PlayerColorId, PlayerId};
+    use super::{Player, Players};
+
+    fn add_player(players: &mut Players, player_id: usize, team: u8, allied_vision: bool) {
+        let mut player = Player::new(format!("Player {}", player_id),
+                                     player_id.into(),
+                                     player_id.into(),
+                                     1.into(),
+                                     team);
+        player.allied_vision = allied_vision;
+        players.add_player(player, player_id == 1);
+    }
+
+    #[test]
+    fn test_allied_vision_needs_both_allies() {
+        let mut players = Players::new();
+        add_player(&mut players, 1, 1, true);
+        add_player(&mut players, 2, 1, false);
+        add_player(&mut players, 3, 2, true);
+        let mut techs = PlayerTechs::new();
+        let (one, two, three): (PlayerId, PlayerId, PlayerId) = (1.into(), 2.into(), 3.into());
+
+        assert_eq!(vec![one], players.vision_player_ids(&techs, one));
+        players.players.get_mut(&two).unwrap().allied_vision = true;
+        assert_eq!(vec![one, two], players.vision_player_ids(&techs, one));
+
+        // Enemies never share, even with allied vision on and the tech researched
+        techs.share_exploration(three);
+        assert_eq!(vec![three], players.vision_player_ids(&techs, three));
+    }
+
+    #[test]
+    fn test_shared_exploration_sees_allies() {
+        let mut players = Players::new();
+        add_player(&mut players, 1, 1, false);
+        add_player(&mut players, 2, 1, false);
+        let mut techs = PlayerTechs::new();
+        let (one, two): (PlayerId, PlayerId) = (1.into(), 2.into());
+
+        techs.share_exploration(one);
+        assert!(players.shares_vision(&techs, one, two));
+        assert!(!players.shares_vision(&techs, two, one));
+    }
+
+    #[test]
+    fn test_sprite_color_follows_the_picked_color() {
+        let mut players = Players::new();
+        add_player(&mut players, 0, 0, false);
+        add_player(&mut players, 1, 1, false);
+        add_player(&mut players, 2, 2, false);
+        let (gaia, one, two, missing): (PlayerId, PlayerId, PlayerId, PlayerId) =
+            (0.into(), 1.into(), 2.into(), 5.into());
+        players.players.get_mut(&one).unwrap().player_color_id = 4.into();
+
+        let expected: Vec<PlayerColorId> = vec![0.into(), 5.into(), 3.into(), 0.into()];
+        assert_eq!(expected,
+                   vec![players.sprite_color(gaia),
+                        players.sprite_color(one),
+                        players.sprite_color(two),
+                        players.sprite_color(missing)]);
+    }
+}
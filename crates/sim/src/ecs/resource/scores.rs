@@ -0,0 +1,200 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::PlayerId;
+use std::collections::BTreeMap;
+
+// Roughly the original game's scoring; each category is a plain sum of points
+const POINTS_PER_UNIT_KILLED: u32 = 1;
+const POINTS_PER_BUILDING_RAZED: u32 = 2;
+const POINTS_PER_UNIT_ALIVE: u32 = 1;
+const POINTS_PER_ARTIFACT: u32 = 10;
+const POINTS_PER_RESEARCH: u32 = 2;
+const POINTS_PER_WONDER: u32 = 100;
+
+/// Bonus for being the one player ahead of everyone else in kills, population, or research
+const LEADER_BONUS: u32 = 25;
+
+/// What goes into a player's score
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScoreInputs {
+    pub units_killed: u32,
+    pub buildings_razed: u32,
+    pub population: u32,
+    pub artifacts: u32,
+    pub researched: u32,
+    pub wonders: u32,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Score {
+    pub military: u32,
+    pub economy: u32,
+    pub religion: u32,
+    pub technology: u32,
+}
+
+impl Score {
+    pub fn total(&self) -> u32 {
+        self.military + self.economy + self.religion + self.technology
+    }
+}
+
+/// Every player's current score
+#[derive(Clone, Debug, Default)]
+pub struct Scores {
+    players: BTreeMap<PlayerId, Score>,
+}
+
+impl Scores {
+    pub fn new() -> Scores {
+        Scores { players: BTreeMap::new() }
+    }
+
+    /// Works out everyone's score from scratch. Players missing from `inputs` drop out of the scores.
+    pub fn update(&mut self, inputs: &BTreeMap<PlayerId, ScoreInputs>) {
+        let most_kills = leader(inputs, |input| input.units_killed);
+        let most_population = leader(inputs, |input| input.population);
+        let most_research = leader(inputs, |input| input.researched);
+        let bonus = |leader: Option<PlayerId>, player_id: PlayerId| {
+            if leader == Some(player_id) { LEADER_BONUS } else { 0 }
+        };
+
+        self.players.clear();
+        for (&player_id, input) in inputs {
+            let score = Score {
+                military: input.units_killed * POINTS_PER_UNIT_KILLED +
+                          input.buildings_razed * POINTS_PER_BUILDING_RAZED +
+                          bonus(most_kills, player_id),
+                economy: input.population * POINTS_PER_UNIT_ALIVE + bonus(most_population, player_id),
+                religion: input.artifacts * POINTS_PER_ARTIFACT,
+                technology: input.researched * POINTS_PER_RESEARCH + input.wonders * POINTS_PER_WONDER +
+                            bonus(most_research, player_id),
+            };
+            self.players.insert(player_id, score);
+        }
+    }
+
+    /// Players that aren't being scored have all zeroes
+    pub fn score(&self, player_id: PlayerId) -> Score {
+        self.players.get(&player_id).cloned().unwrap_or_else(Default::default)
+    }
+
+    /// Players and their total scores, highest first; ties go to the lower player ID
+    pub fn ranking(&self) -> Vec<(PlayerId, u32)> {
+        let mut ranking: Vec<(PlayerId, u32)> =
+            self.players.iter().map(|(&player_id, score)| (player_id, score.total())).collect();
+        // The sort is stable and the map is in player order, so ties stay in player order
+        ranking.sort_by(|a, b| b.1.cmp(&a.1));
+        ranking
+    }
+
+    /// The player's place in the ranking, starting from 1. Players with the same score share a
+    /// place, and the places after them are skipped, as in 1, 2, 2, 4.
+    pub fn rank(&self, player_id: PlayerId) -> Option<usize> {
+        self.players.get(&player_id).map(|score| {
+            let total = score.total();
+            1 + self.players.values().filter(|other| other.total() > total).count()
+        })
+    }
+}
+
+/// The player with strictly more of something than anyone else, if there is one
+fn leader<F>(inputs: &BTreeMap<PlayerId, ScoreInputs>, value: F) -> Option<PlayerId>
+    where F: Fn(&ScoreInputs) -> u32
+{
+    let mut best: Option<(PlayerId, u32)> = None;
+    let mut tied = false;
+    for (&player_id, input) in inputs {
+        let value = value(input);
+        match best {
+            Some((_, best_value)) if value < best_value => {}
+            Some((_, best_value)) if value == best_value => tied = true,
+            _ => {
+                best = Some((player_id, value));
+                tied = false;
+            }
+        }
+    }
+    match best {
+        Some((player_id, value)) if !tied && value > 0 => Some(player_id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::PlayerId;
+    use std::collections::BTreeMap;
+    use super::{Score, ScoreInputs, Scores};
+
+    fn inputs(players: Vec<(usize, ScoreInputs)>) -> BTreeMap<PlayerId, ScoreInputs> {
+        players.into_iter().map(|(player_id, input)| (player_id.into(), input)).collect()
+    }
+
+    #[test]
+    fn test_categories() {
+        let mut scores = Scores::new();
+        scores.update(&inputs(vec![(1,
+                                    ScoreInputs {
+                                        units_killed: 3,
+                                        buildings_razed: 2,
+                                        population: 10,
+                                        artifacts: 1,
+                                        researched: 4,
+                                        wonders: 1,
+                                    }),
+                                   (2, ScoreInputs::default())]));
+
+        // Player 1 leads in kills, population, and research
+        assert_eq!(Score {
+                       military: 3 + 4 + 25,
+                       economy: 10 + 25,
+                       religion: 10,
+                       technology: 8 + 100 + 25,
+                   },
+                   scores.score(1.into()));
+        assert_eq!(Score::default(), scores.score(2.into()));
+        assert_eq!(Score::default(), scores.score(3.into()));
+    }
+
+    #[test]
+    fn test_ties_get_no_bonus() {
+        let mut scores = Scores::new();
+        let input = ScoreInputs { population: 5, ..Default::default() };
+        scores.update(&inputs(vec![(1, input.clone()), (2, input)]));
+        assert_eq!(5, scores.score(1.into()).economy);
+        assert_eq!(5, scores.score(2.into()).economy);
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut scores = Scores::new();
+        scores.update(&inputs(vec![(1, ScoreInputs { artifacts: 1, ..Default::default() }),
+                                   (2, ScoreInputs { artifacts: 2, ..Default::default() }),
+                                   (3, ScoreInputs { artifacts: 1, ..Default::default() })]));
+        assert_eq!(vec![(2.into(), 20), (1.into(), 10), (3.into(), 10)], scores.ranking());
+        assert_eq!(Some(1), scores.rank(2.into()));
+        assert_eq!(Some(2), scores.rank(1.into()));
+        assert_eq!(Some(2), scores.rank(3.into()));
+        assert_eq!(None, scores.rank(4.into()));
+    }
+}
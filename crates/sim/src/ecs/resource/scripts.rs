@@ -0,0 +1,718 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Reader, Writer};
+use dat::ResourceType;
+use ecs::Persist;
+use identifier::{PlayerId, UnitId};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use types::Rect;
+
+/// Scripts are checked once a second of game time, on ticks that are a multiple of this
+pub const SCRIPT_INTERVAL_TICKS: u64 = 60;
+
+// TODO: Change diplomacy once players have diplomatic stances
+/// Everything a script can see and do. The script system implements this on top of the world;
+/// keeping scripts behind it means they can't reach anything that would make the game play out
+/// differently on different machines.
+pub trait ScriptApi {
+    fn tick(&self) -> u64;
+
+    /// How many of the player's units are alive, optionally only of one kind or inside an area
+    /// (in tiles)
+    fn unit_count(&self, player_id: PlayerId, unit_id: Option<UnitId>, area: Option<Rect>) -> u32;
+
+    /// How much of the resource the player has stockpiled (rounded down)
+    fn resource_amount(&self, player_id: PlayerId, resource_type: ResourceType) -> u32;
+
+    /// Rolled with the game's random number generator
+    fn chance(&mut self, percent: u32) -> bool;
+
+    /// Shows the text to every player, and keeps it in the message log
+    fn announce(&mut self, text: &str);
+
+    /// Adds an objective to the objectives panel (and tells the players about it)
+    fn add_objective(&mut self, text: &str);
+
+    /// Marks the objective with the given text complete
+    fn complete_objective(&mut self, text: &str);
+
+    /// Creates the unit in the middle of the given tile
+    fn spawn(&mut self, player_id: PlayerId, unit_id: UnitId, x: i32, y: i32);
+
+    /// Ends the game
+    fn win(&mut self, player_id: PlayerId);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn parse(text: &str) -> Result<Comparison, String> {
+        Ok(match text {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            "=" | "==" => Comparison::Equal,
+            ">=" => Comparison::GreaterOrEqual,
+            ">" => Comparison::Greater,
+            _ => return Err(format!("unknown comparison \"{}\"", text)),
+        })
+    }
+
+    fn compare(&self, value: u64, other: u64) -> bool {
+        match *self {
+            Comparison::Less => value < other,
+            Comparison::LessOrEqual => value <= other,
+            Comparison::Equal => value == other,
+            Comparison::GreaterOrEqual => value >= other,
+            Comparison::Greater => value > other,
+        }
+    }
+}
+
+impl Persist for Comparison {
+    fn write(&self, writer: &mut Writer) {
+        writer.byte(match *self {
+            Comparison::Less => 0,
+            Comparison::LessOrEqual => 1,
+            Comparison::Equal => 2,
+            Comparison::GreaterOrEqual => 3,
+            Comparison::Greater => 4,
+        });
+    }
+
+    fn read(reader: &mut Reader) -> Result<Comparison, String> {
+        Ok(match try!(reader.byte()) {
+            0 => Comparison::Less,
+            1 => Comparison::LessOrEqual,
+            2 => Comparison::Equal,
+            3 => Comparison::GreaterOrEqual,
+            4 => Comparison::Greater,
+            tag => return Err(format!("unknown comparison {}", tag)),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Tick(Comparison, u64),
+    Units {
+        player_id: PlayerId,
+        unit_id: Option<UnitId>,
+        area: Option<Rect>,
+        comparison: Comparison,
+        count: u32,
+    },
+    Resources {
+        player_id: PlayerId,
+        resource_type: ResourceType,
+        comparison: Comparison,
+        amount: u32,
+    },
+    Chance(u32),
+}
+
+impl Condition {
+    fn check(&self, api: &mut ScriptApi) -> bool {
+        match *self {
+            Condition::Tick(comparison, tick) => comparison.compare(api.tick(), tick),
+            Condition::Units { player_id, unit_id, area, comparison, count } => {
+                comparison.compare(api.unit_count(player_id, unit_id, area) as u64, count as u64)
+            }
+            Condition::Resources { player_id, resource_type, comparison, amount } => {
+                comparison.compare(api.resource_amount(player_id, resource_type) as u64, amount as u64)
+            }
+            Condition::Chance(percent) => api.chance(percent),
+        }
+    }
+}
+
+impl Persist for Condition {
+    fn write(&self, writer: &mut Writer) {
+        match *self {
+            Condition::Tick(comparison, tick) => {
+                writer.byte(0);
+                comparison.write(writer);
+                tick.write(writer);
+            }
+            Condition::Units { player_id, unit_id, area, comparison, count } => {
+                writer.byte(1);
+                player_id.write(writer);
+                unit_id.write(writer);
+                area.write(writer);
+                comparison.write(writer);
+                count.write(writer);
+            }
+            Condition::Resources { player_id, resource_type, comparison, amount } => {
+                writer.byte(2);
+                player_id.write(writer);
+                resource_type.write(writer);
+                comparison.write(writer);
+                amount.write(writer);
+            }
+            Condition::Chance(percent) => {
+                writer.byte(3);
+                percent.write(writer);
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Condition, String> {
+        Ok(match try!(reader.byte()) {
+            0 => Condition::Tick(try!(Persist::read(reader)), try!(Persist::read(reader))),
+            1 => {
+                Condition::Units {
+                    player_id: try!(Persist::read(reader)),
+                    unit_id: try!(Persist::read(reader)),
+                    area: try!(Persist::read(reader)),
+                    comparison: try!(Persist::read(reader)),
+                    count: try!(Persist::read(reader)),
+                }
+            }
+            2 => {
+                Condition::Resources {
+                    player_id: try!(Persist::read(reader)),
+                    resource_type: try!(Persist::read(reader)),
+                    comparison: try!(Persist::read(reader)),
+                    amount: try!(Persist::read(reader)),
+                }
+            }
+            3 => Condition::Chance(try!(Persist::read(reader))),
+            tag => return Err(format!("unknown condition {}", tag)),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    Announce(String),
+    Objective(String),
+    CompleteObjective(String),
+    Spawn {
+        player_id: PlayerId,
+        unit_id: UnitId,
+        x: i32,
+        y: i32,
+    },
+    Win(PlayerId),
+}
+
+impl Effect {
+    fn apply(&self, api: &mut ScriptApi) {
+        match *self {
+            Effect::Announce(ref text) => api.announce(text),
+            Effect::Objective(ref text) => api.add_objective(text),
+            Effect::CompleteObjective(ref text) => api.complete_objective(text),
+            Effect::Spawn { player_id, unit_id, x, y } => api.spawn(player_id, unit_id, x, y),
+            Effect::Win(player_id) => api.win(player_id),
+        }
+    }
+}
+
+impl Persist for Effect {
+    fn write(&self, writer: &mut Writer) {
+        match *self {
+            Effect::Announce(ref text) => {
+                writer.byte(0);
+                text.write(writer);
+            }
+            Effect::Objective(ref text) => {
+                writer.byte(1);
+                text.write(writer);
+            }
+            Effect::CompleteObjective(ref text) => {
+                writer.byte(2);
+                text.write(writer);
+            }
+            Effect::Spawn { player_id, unit_id, x, y } => {
+                writer.byte(3);
+                player_id.write(writer);
+                unit_id.write(writer);
+                x.write(writer);
+                y.write(writer);
+            }
+            Effect::Win(player_id) => {
+                writer.byte(4);
+                player_id.write(writer);
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Effect, String> {
+        Ok(match try!(reader.byte()) {
+            0 => Effect::Announce(try!(Persist::read(reader))),
+            1 => Effect::Objective(try!(Persist::read(reader))),
+            2 => Effect::CompleteObjective(try!(Persist::read(reader))),
+            3 => {
+                Effect::Spawn {
+                    player_id: try!(Persist::read(reader)),
+                    unit_id: try!(Persist::read(reader)),
+                    x: try!(Persist::read(reader)),
+                    y: try!(Persist::read(reader)),
+                }
+            }
+            4 => Effect::Win(try!(Persist::read(reader))),
+            tag => return Err(format!("unknown effect {}", tag)),
+        })
+    }
+}
+
+/// When every condition holds, the effects happen (in order). Rules only fire once unless
+/// they repeat, in which case they fire on every check that their conditions hold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub conditions: Vec<Condition>,
+    pub effects: Vec<Effect>,
+    pub repeat: bool,
+}
+
+persist_struct!(Rule { name, conditions, effects, repeat });
+
+/// Rules that scenarios and mods can add to a game.
+///
+/// The text format is a list of rules. Each one starts with `rule <name>` and ends with `end`,
+/// and in between has one condition, effect, or `repeat` per line. Lines starting with `#` are
+/// comments.
+///
+/// Conditions (all of them have to hold):
+///
+/// * `when tick <comparison> <tick>`
+/// * `when units <player> [unit <unit id>] [in <x> <y> <width> <height>] <comparison> <count>`
+/// * `when resources <player> <food|wood|stone|gold> <comparison> <amount>`
+/// * `when chance <percent>`
+///
+/// Comparisons are `<`, `<=`, `=`, `>=`, or `>`. Effects:
+///
+/// * `announce <text>`
+/// * `spawn <player> <unit id> <x> <y>`
+/// * `win <player>`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    rules: Vec<Rule>,
+}
+
+impl Script {
+    pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> io::Result<Script> {
+        let mut text = String::new();
+        try!(try!(File::open(file_name)).read_to_string(&mut text));
+        Script::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Script> {
+        let mut script = Script::default();
+        let mut rule: Option<Rule> = None;
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |err: String| {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               format!("script line {}: {}", line_number + 1, err))
+            };
+
+            if line.starts_with("rule ") || line == "rule" {
+                if rule.is_some() {
+                    return Err(error("rules can't be nested".to_string()));
+                }
+                rule = Some(Rule {
+                    name: line[4..].trim().to_string(),
+                    conditions: Vec::new(),
+                    effects: Vec::new(),
+                    repeat: false,
+                });
+                continue;
+            }
+
+            let finished = match rule {
+                Some(ref mut rule) => {
+                    if line == "end" {
+                        true
+                    } else {
+                        try!(parse_rule_line(rule, line).map_err(&error));
+                        false
+                    }
+                }
+                None => return Err(error("expected \"rule <name>\"".to_string())),
+            };
+            if finished {
+                script.rules.push(rule.take().unwrap());
+            }
+        }
+        if rule.is_some() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "script ends in the middle of a rule"));
+        }
+        Ok(script)
+    }
+
+    pub fn rules<'a>(&'a self) -> &'a [Rule] {
+        &self.rules
+    }
+}
+
+fn parse_rule_line(rule: &mut Rule, line: &str) -> Result<(), String> {
+    let mut words = line.split_whitespace();
+    let keyword = words.next().unwrap_or("");
+    let args: Vec<&str> = words.collect();
+    match keyword {
+        "when" => rule.conditions.push(try!(parse_condition(&args))),
+        "announce" => rule.effects.push(Effect::Announce(try!(parse_text(line, keyword)))),
+        "objective" => rule.effects.push(Effect::Objective(try!(parse_text(line, keyword)))),
+        "complete" => rule.effects.push(Effect::CompleteObjective(try!(parse_text(line, keyword)))),
+        "spawn" => {
+            if args.len() != 4 {
+                return Err("expected \"spawn <player> <unit id> <x> <y>\"".to_string());
+            }
+            rule.effects.push(Effect::Spawn {
+                player_id: try!(parse_player(args[0])),
+                unit_id: (try!(parse_number::<u32>(args[1], "unit id")) as usize).into(),
+                x: try!(parse_number(args[2], "x")),
+                y: try!(parse_number(args[3], "y")),
+            });
+        }
+        "win" => {
+            if args.len() != 1 {
+                return Err("expected \"win <player>\"".to_string());
+            }
+            rule.effects.push(Effect::Win(try!(parse_player(args[0]))));
+        }
+        "repeat" => rule.repeat = true,
+        _ => return Err(format!("unknown keyword \"{}\"", keyword)),
+    }
+    Ok(())
+}
+
+/// The rest of the line after the keyword, which can't be empty
+fn parse_text(line: &str, keyword: &str) -> Result<String, String> {
+    let text = line[keyword.len()..].trim();
+    if text.is_empty() {
+        return Err(format!("expected \"{} <text>\"", keyword));
+    }
+    Ok(text.to_string())
+}
+
+fn parse_condition(args: &[&str]) -> Result<Condition, String> {
+    match args.first() {
+        Some(&"tick") if args.len() == 3 => {
+            Ok(Condition::Tick(try!(Comparison::parse(args[1])), try!(parse_number(args[2], "tick"))))
+        }
+        Some(&"units") if args.len() >= 4 => {
+            let player_id = try!(parse_player(args[1]));
+            let mut rest = &args[2..];
+            let mut unit_id = None;
+            if rest.len() >= 2 && rest[0] == "unit" {
+                unit_id = Some((try!(parse_number::<u32>(rest[1], "unit id")) as usize).into());
+                rest = &rest[2..];
+            }
+            let mut area = None;
+            if rest.len() >= 5 && rest[0] == "in" {
+                area = Some(Rect::of(try!(parse_number(rest[1], "x")),
+                                     try!(parse_number(rest[2], "y")),
+                                     try!(parse_number(rest[3], "width")),
+                                     try!(parse_number(rest[4], "height"))));
+                rest = &rest[5..];
+            }
+            if rest.len() != 2 {
+                return Err("expected \"units <player> [unit <unit id>] [in <x> <y> <width> <height>] \
+                            <comparison> <count>\""
+                    .to_string());
+            }
+            Ok(Condition::Units {
+                player_id: player_id,
+                unit_id: unit_id,
+                area: area,
+                comparison: try!(Comparison::parse(rest[0])),
+                count: try!(parse_number(rest[1], "count")),
+            })
+        }
+        Some(&"resources") if args.len() == 5 => {
+            let resource_type = match args[2] {
+                "food" => ResourceType::Food,
+                "wood" => ResourceType::Wood,
+                "stone" => ResourceType::Stone,
+                "gold" => ResourceType::Gold,
+                other => return Err(format!("unknown resource \"{}\"", other)),
+            };
+            Ok(Condition::Resources {
+                player_id: try!(parse_player(args[1])),
+                resource_type: resource_type,
+                comparison: try!(Comparison::parse(args[3])),
+                amount: try!(parse_number(args[4], "amount")),
+            })
+        }
+        Some(&"chance") if args.len() == 2 => {
+            let percent = try!(parse_number(args[1], "percent"));
+            if percent > 100 {
+                return Err("chance is a percentage".to_string());
+            }
+            Ok(Condition::Chance(percent))
+        }
+        _ => Err(format!("unknown condition \"{}\"", args.join(" "))),
+    }
+}
+
+fn parse_player(text: &str) -> Result<PlayerId, String> {
+    Ok((try!(parse_number::<u8>(text, "player")) as usize).into())
+}
+
+fn parse_number<T: ::std::str::FromStr>(text: &str, what: &str) -> Result<T, String> {
+    text.parse::<T>().map_err(|_| format!("invalid {} \"{}\"", what, text))
+}
+
+/// The rules of every script in the game, and which ones have already fired
+#[derive(Clone, Debug)]
+pub struct Scripts {
+    rules: Vec<Rule>,
+    fired: Vec<bool>,
+}
+
+persist_struct!(Scripts { rules, fired });
+
+impl Scripts {
+    pub fn new(scripts: &[Script]) -> Scripts {
+        let rules: Vec<Rule> = scripts.iter().flat_map(|script| script.rules.iter().cloned()).collect();
+        Scripts {
+            fired: vec![false; rules.len()],
+            rules: rules,
+        }
+    }
+
+    /// Swaps in new versions of the scripts (when they're reloaded during development).
+    /// Rules that already fired stay fired as long as they keep their names.
+    pub fn reload(&mut self, scripts: &[Script]) {
+        let mut reloaded = Scripts::new(scripts);
+        for (rule, fired) in reloaded.rules.iter().zip(reloaded.fired.iter_mut()) {
+            *fired = self.rules
+                .iter()
+                .zip(&self.fired)
+                .any(|(old_rule, &old_fired)| old_fired && old_rule.name == rule.name);
+        }
+        *self = reloaded;
+    }
+
+    /// Checks every rule in order, and fires the ones whose conditions hold. Conditions are checked
+    /// in order and stop at the first one that doesn't hold, so a `chance` is only rolled when the
+    /// conditions before it hold.
+    pub fn update(&mut self, api: &mut ScriptApi) {
+        for (rule, fired) in self.rules.iter().zip(self.fired.iter_mut()) {
+            if *fired && !rule.repeat {
+                continue;
+            }
+            if rule.conditions.iter().all(|condition| condition.check(api)) {
+                *fired = true;
+                for effect in &rule.effects {
+                    effect.apply(api);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dat::ResourceType;
+    use identifier::{PlayerId, UnitId};
+    use super::{Comparison, Condition, Effect, Script, ScriptApi, Scripts};
+    use types::Rect;
+
+    #[derive(Default)]
+    struct FakeApi {
+        tick: u64,
+        units: u32,
+        gold: u32,
+        rolls: Vec<bool>,
+        log: Vec<String>,
+    }
+
+    impl ScriptApi for FakeApi {
+        fn tick(&self) -> u64 {
+            self.tick
+        }
+
+        fn unit_count(&self, _player_id: PlayerId, _unit_id: Option<UnitId>, _area: Option<Rect>) -> u32 {
+            self.units
+        }
+
+        fn resource_amount(&self, _player_id: PlayerId, resource_type: ResourceType) -> u32 {
+            if resource_type == ResourceType::Gold { self.gold } else { 0 }
+        }
+
+        fn chance(&mut self, percent: u32) -> bool {
+            self.log.push(format!("chance {}", percent));
+            self.rolls.pop().unwrap_or(false)
+        }
+
+        fn announce(&mut self, text: &str) {
+            self.log.push(format!("announce {}", text));
+        }
+
+        fn add_objective(&mut self, text: &str) {
+            self.log.push(format!("objective {}", text));
+        }
+
+        fn complete_objective(&mut self, text: &str) {
+            self.log.push(format!("complete {}", text));
+        }
+
+        fn spawn(&mut self, player_id: PlayerId, unit_id: UnitId, x: i32, y: i32) {
+            self.log.push(format!("spawn {} {} {} {}", *player_id, *unit_id, x, y));
+        }
+
+        fn win(&mut self, player_id: PlayerId) {
+            self.log.push(format!("win {}", *player_id));
+        }
+    }
+
+    const SCRIPT: &'static str = "
+        # Reinforcements
+        rule reinforcements
+        when tick >= 600
+        when units 1 in 10 10 5 5 < 3
+        announce Help is on the way
+        spawn 1 83 12 14
+        end
+
+        rule ambush
+        when chance 25
+        spawn 0 48 30 30
+        repeat
+        end
+    ";
+
+    #[test]
+    fn test_parse() {
+        let script = Script::parse(SCRIPT).unwrap();
+        let rules = script.rules();
+        assert_eq!(2, rules.len());
+        assert_eq!("reinforcements", rules[0].name);
+        assert_eq!(vec![Condition::Tick(Comparison::GreaterOrEqual, 600),
+                        Condition::Units {
+                            player_id: 1.into(),
+                            unit_id: None,
+                            area: Some(Rect::of(10, 10, 5, 5)),
+                            comparison: Comparison::Less,
+                            count: 3,
+                        }],
+                   rules[0].conditions);
+        assert_eq!(vec![Effect::Announce("Help is on the way".to_string()),
+                        Effect::Spawn {
+                            player_id: 1.into(),
+                            unit_id: 83.into(),
+                            x: 12,
+                            y: 14,
+                        }],
+                   rules[0].effects);
+        assert!(!rules[0].repeat);
+        assert!(rules[1].repeat);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Script::parse("when tick > 5").is_err());
+        assert!(Script::parse("rule a\nwhen tick > 5").is_err());
+        assert!(Script::parse("rule a\nrule b\nend").is_err());
+        assert!(Script::parse("rule a\nwhen tick ~ 5\nend").is_err());
+        assert!(Script::parse("rule a\nwhen chance 150\nend").is_err());
+        assert!(Script::parse("rule a\nexplode\nend").is_err());
+        assert!(Script::parse("rule a\nwhen units 1 unit 83 in 1 2 3 4 >= 1\nend").is_ok());
+        assert!(Script::parse("rule a\nwhen resources 1 iron > 5\nend").is_err());
+    }
+
+    #[test]
+    fn test_objectives() {
+        let script = Script::parse("rule a\nobjective Hold the pass\ncomplete Find the ruins\nend").unwrap();
+        assert_eq!(vec![Effect::Objective("Hold the pass".to_string()),
+                        Effect::CompleteObjective("Find the ruins".to_string())],
+                   script.rules()[0].effects);
+        assert!(Script::parse("rule a\nobjective\nend").is_err());
+    }
+
+    #[test]
+    fn test_rules_fire_once() {
+        let mut scripts = Scripts::new(&[Script::parse("rule a\nwhen tick >= 600\nwin 2\nend").unwrap()]);
+        let mut api = FakeApi::default();
+        api.tick = 540;
+        scripts.update(&mut api);
+        assert!(api.log.is_empty());
+
+        api.tick = 600;
+        scripts.update(&mut api);
+        api.tick = 660;
+        scripts.update(&mut api);
+        assert_eq!(vec!["win 2".to_string()], api.log);
+    }
+
+    #[test]
+    fn test_repeat_and_chance() {
+        let mut scripts = Scripts::new(&[Script::parse(SCRIPT).unwrap()]);
+        let mut api = FakeApi::default();
+        api.tick = 60;
+        api.rolls = vec![false, true];
+        scripts.update(&mut api);
+        scripts.update(&mut api);
+        assert_eq!(vec!["chance 25".to_string(), "spawn 0 48 30 30".to_string(), "chance 25".to_string()],
+                   api.log);
+    }
+
+    #[test]
+    fn test_reload_keeps_fired_rules() {
+        let mut scripts = Scripts::new(&[Script::parse("rule a\nwin 1\nend").unwrap()]);
+        let mut api = FakeApi::default();
+        scripts.update(&mut api);
+
+        scripts.reload(&[Script::parse("rule a\nwin 2\nend\nrule b\nwin 3\nend").unwrap()]);
+        scripts.update(&mut api);
+        assert_eq!(vec!["win 1".to_string(), "win 3".to_string()], api.log);
+    }
+
+    #[test]
+    fn test_conditions_stop_early() {
+        let mut scripts = Scripts::new(&[Script::parse("rule a\nwhen units 1 > 0\nwhen chance 50\nwin 1\nend")
+                                             .unwrap()]);
+        let mut api = FakeApi::default();
+        scripts.update(&mut api);
+        // No units, so the chance isn't rolled
+        assert!(api.log.is_empty());
+    }
+
+    #[test]
+    fn test_resources_condition() {
+        let mut scripts = Scripts::new(&[Script::parse("rule a\nwhen resources 1 gold >= 500\nwin 1\nend")
+                                             .unwrap()]);
+        let mut api = FakeApi::default();
+        api.gold = 499;
+        scripts.update(&mut api);
+        assert!(api.log.is_empty());
+
+        api.gold = 500;
+        scripts.update(&mut api);
+        assert_eq!(vec!["win 1".to_string()], api.log);
+    }
+}
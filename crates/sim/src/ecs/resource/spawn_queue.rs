@@ -0,0 +1,75 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::{UnitMorph, UnitSpawn};
+use identifier::PlayerId;
+use std::mem;
+use types::Vector3;
+
+/// Units that systems want created or turned into other units; see `UnitFactory`
+#[derive(Clone)]
+pub struct SpawnQueue {
+    spawns: Vec<UnitSpawn>,
+    foundations: Vec<UnitSpawn>,
+    morphs: Vec<UnitMorph>,
+}
+
+persist_struct!(SpawnQueue { spawns, foundations, morphs });
+
+impl SpawnQueue {
+    pub fn new() -> SpawnQueue {
+        SpawnQueue {
+            spawns: Vec::new(),
+            foundations: Vec::new(),
+            morphs: Vec::new(),
+        }
+    }
+
+    pub fn queue(&mut self, spawn: UnitSpawn) {
+        self.spawns.push(spawn);
+    }
+
+    pub fn take(&mut self) -> Vec<UnitSpawn> {
+        mem::replace(&mut self.spawns, Vec::new())
+    }
+
+    /// Queues a building that villagers still have to build
+    pub fn queue_foundation(&mut self, spawn: UnitSpawn) {
+        self.foundations.push(spawn);
+    }
+
+    /// Whether the player laid a foundation at the position this tick, so it doesn't exist yet
+    pub fn has_foundation(&self, player_id: PlayerId, position: &Vector3) -> bool {
+        self.foundations.iter().any(|spawn| spawn.player_id == player_id && spawn.position == *position)
+    }
+
+    pub fn take_foundations(&mut self) -> Vec<UnitSpawn> {
+        mem::replace(&mut self.foundations, Vec::new())
+    }
+
+    pub fn queue_morph(&mut self, morph: UnitMorph) {
+        self.morphs.push(morph);
+    }
+
+    pub fn take_morphs(&mut self) -> Vec<UnitMorph> {
+        mem::replace(&mut self.morphs, Vec::new())
+    }
+}
@@ -0,0 +1,169 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::ResourceType;
+use ecs::GameSettings;
+use identifier::PlayerId;
+use scn::Scenario;
+use std::collections::BTreeMap;
+use types::Fixed;
+
+/// The order that starting resources are listed in (see `StartingResources::amounts`)
+static STARTING_RESOURCE_TYPES: [ResourceType; 4] =
+    [ResourceType::Food, ResourceType::Wood, ResourceType::Stone, ResourceType::Gold];
+
+/// The food, wood, stone, and gold each player has to spend
+#[derive(Clone, Debug, Default)]
+pub struct Stockpiles {
+    amounts: BTreeMap<(PlayerId, ResourceType), Fixed>,
+}
+
+persist_struct!(Stockpiles { amounts });
+
+impl Stockpiles {
+    pub fn new() -> Stockpiles {
+        Stockpiles { amounts: BTreeMap::new() }
+    }
+
+    /// Players start out with what the scenario gives them, unless the game's settings say to
+    /// give everyone the same, scaled by their handicap and with any bonus their difficulty gets
+    /// (gaia never has anything)
+    pub fn from_scenario(scenario: &Scenario, settings: &GameSettings) -> Stockpiles {
+        let mut stockpiles = Stockpiles::new();
+        for player_id in scenario.player_ids() {
+            if *player_id == 0 {
+                continue;
+            }
+            let amounts: Option<[Fixed; 4]> = match settings.starting_resources.amounts() {
+                Some(amounts) => {
+                    Some([amounts[0].into(), amounts[1].into(), amounts[2].into(), amounts[3].into()])
+                }
+                None => {
+                    scenario.find_player_resources(player_id).map(|resources| {
+                        [resources.food.into(),
+                         resources.wood.into(),
+                         resources.stone.into(),
+                         resources.gold.into()]
+                    })
+                }
+            };
+            let bonus = settings.bonus_resources(player_id);
+            let amounts = match amounts {
+                Some(amounts) => amounts,
+                None if bonus == [0, 0, 0, 0] => continue,
+                None => [Fixed::from(0); 4],
+            };
+
+            let handicap = Fixed::from(settings.handicap_percent(player_id) as i32) / Fixed::from(100);
+            for (index, &resource_type) in STARTING_RESOURCE_TYPES.iter().enumerate() {
+                let amount = amounts[index] * handicap + Fixed::from(bonus[index] as i32);
+                stockpiles.add(player_id, resource_type, amount);
+            }
+        }
+        stockpiles
+    }
+
+    pub fn amount(&self, player_id: PlayerId, resource_type: ResourceType) -> Fixed {
+        self.amounts.get(&(player_id, resource_type)).cloned().unwrap_or(0.into())
+    }
+
+    pub fn add(&mut self, player_id: PlayerId, resource_type: ResourceType, amount: Fixed) {
+        *self.amounts.entry((player_id, resource_type)).or_insert(0.into()) += amount;
+    }
+
+    /// Whether the player has enough of everything on the list (which can name a resource more
+    /// than once). Only the four stockpiled resources count; the rest (such as population) aren't
+    /// kept track of.
+    pub fn can_afford(&self, player_id: PlayerId, costs: &[(ResourceType, i16)]) -> bool {
+        let mut totals: BTreeMap<ResourceType, Fixed> = BTreeMap::new();
+        for &(resource_type, amount) in costs.iter().filter(|cost| stockpiled(cost.0)) {
+            *totals.entry(resource_type).or_insert(0.into()) += Fixed::from(amount);
+        }
+        totals.iter().all(|(&resource_type, &total)| self.amount(player_id, resource_type) >= total)
+    }
+
+    /// Takes the costs out of the player's stockpiles if they can afford all of them, and
+    /// returns whether they could; nothing is taken if they can't
+    pub fn spend(&mut self, player_id: PlayerId, costs: &[(ResourceType, i16)]) -> bool {
+        if !self.can_afford(player_id, costs) {
+            return false;
+        }
+        for &(resource_type, amount) in costs.iter().filter(|cost| stockpiled(cost.0)) {
+            self.add(player_id, resource_type, -Fixed::from(amount));
+        }
+        true
+    }
+
+    /// Gives back what `spend` took (for training and research that's cancelled)
+    pub fn refund(&mut self, player_id: PlayerId, costs: &[(ResourceType, i16)]) {
+        for &(resource_type, amount) in costs.iter().filter(|cost| stockpiled(cost.0)) {
+            self.add(player_id, resource_type, amount.into());
+        }
+    }
+}
+
+fn stockpiled(resource_type: ResourceType) -> bool {
+    STARTING_RESOURCE_TYPES.contains(&resource_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use dat::ResourceType;
+    use identifier::PlayerId;
+    use super::Stockpiles;
+    use types::Fixed;
+
+    #[test]
+    fn test_players_and_resources_are_separate() {
+        let (first, second): (PlayerId, PlayerId) = (1.into(), 2.into());
+        let mut stockpiles = Stockpiles::new();
+        stockpiles.add(first, ResourceType::Food, 10.into());
+        stockpiles.add(first, ResourceType::Food, fixed_const!(2.5));
+        stockpiles.add(second, ResourceType::Wood, 3.into());
+
+        assert_eq!(fixed_const!(12.5), stockpiles.amount(first, ResourceType::Food));
+        assert_eq!(Fixed::from(0), stockpiles.amount(first, ResourceType::Wood));
+        assert_eq!(Fixed::from(3), stockpiles.amount(second, ResourceType::Wood));
+    }
+
+    #[test]
+    fn test_spend_takes_all_or_nothing() {
+        let player: PlayerId = 1.into();
+        let mut stockpiles = Stockpiles::new();
+        stockpiles.add(player, ResourceType::Food, 60.into());
+        stockpiles.add(player, ResourceType::Wood, 20.into());
+
+        // A resource named twice has to be there twice over
+        assert!(!stockpiles.spend(player, &[(ResourceType::Food, 40), (ResourceType::Food, 40)]));
+        assert!(!stockpiles.spend(player, &[(ResourceType::Food, 50), (ResourceType::Wood, 25)]));
+        assert_eq!(Fixed::from(60), stockpiles.amount(player, ResourceType::Food));
+        assert_eq!(Fixed::from(20), stockpiles.amount(player, ResourceType::Wood));
+
+        // Population isn't stockpiled, so it doesn't stand in the way
+        assert!(stockpiles.spend(player, &[(ResourceType::Food, 50), (ResourceType::Unknown(4), 1)]));
+        assert_eq!(Fixed::from(10), stockpiles.amount(player, ResourceType::Food));
+        assert!(!stockpiles.can_afford(2.into(), &[(ResourceType::Wood, 1)]));
+
+        stockpiles.refund(player, &[(ResourceType::Food, 50), (ResourceType::Unknown(4), 1)]);
+        assert_eq!(Fixed::from(60), stockpiles.amount(player, ResourceType::Food));
+        assert_eq!(Fixed::from(0), stockpiles.amount(player, ResourceType::Unknown(4)));
+    }
+}
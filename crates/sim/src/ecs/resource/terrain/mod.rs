@@ -26,4 +26,4 @@ mod terrain;
 
 pub use self::border::BorderMatch;
 pub use self::elevation::{ElevationGraphic, ElevationMatch};
-pub use self::terrain::{BlendInfo, Terrain, Tile};
+pub use self::terrain::{BlendInfo, CHUNK_SIZE, Terrain, Tile};
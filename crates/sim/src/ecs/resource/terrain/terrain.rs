@@ -28,11 +28,15 @@ use super::dir;
 use super::elevation::ElevationMatrix;
 use types::Vector3;
 
+/// How many tiles there are along each side of a chunk. Edits bump the revision of the chunks
+/// they touch, so that renderers can cache whole chunks and only redo the ones that changed.
+pub const CHUNK_SIZE: i32 = 16;
+
+/// Kept to two bytes, since a 255x255 map has 65025 of them
 #[derive(Debug, Copy, Clone)]
 pub struct Tile {
     pub terrain_id: TerrainId,
     pub elevation: u8,
-    blend_cache_index: Option<u32>,
 }
 
 impl Tile {
@@ -41,7 +45,6 @@ impl Tile {
         Tile {
             terrain_id: terrain_id,
             elevation: elevation,
-            blend_cache_index: None,
         }
     }
 
@@ -49,7 +52,6 @@ impl Tile {
         Tile {
             terrain_id: scn_tile.terrain_id,
             elevation: scn_tile.elevation,
-            blend_cache_index: None,
         }
     }
 }
@@ -69,7 +71,7 @@ pub struct Terrain {
     height: i32,
     tiles: Vec<Tile>,
     empires: dat::EmpiresDbRef,
-    blend_cache: Vec<BlendInfo>,
+    chunk_revisions: Vec<u32>,
 }
 
 impl Terrain {
@@ -80,17 +82,29 @@ impl Terrain {
             height: height,
             tiles: tiles,
             empires: empires,
-            blend_cache: Vec::new(),
+            chunk_revisions: vec![0; chunk_count(width, height)],
         }
     }
 
     pub fn from(scn_map: &scn::Map, empires: dat::EmpiresDbRef) -> Terrain {
+        let (width, height) = (scn_map.width as i32, scn_map.height as i32);
         Terrain {
-            width: scn_map.width as i32,
-            height: scn_map.height as i32,
+            width: width,
+            height: height,
             tiles: scn_map.tiles.iter().map(|t| Tile::from(t)).collect(),
             empires: empires,
-            blend_cache: Vec::new(),
+            chunk_revisions: vec![0; chunk_count(width, height)],
+        }
+    }
+
+    /// The same map, with terrain and border data from another empires.dat (see hot reloading)
+    pub fn with_empires(&self, empires: dat::EmpiresDbRef) -> Terrain {
+        Terrain {
+            width: self.width,
+            height: self.height,
+            tiles: self.tiles.clone(),
+            empires: empires,
+            chunk_revisions: vec![0; self.chunk_revisions.len()],
         }
     }
 
@@ -126,6 +140,61 @@ impl Terrain {
         unsafe { &self.tiles.get_unchecked(self.tile_index(row, col)) }
     }
 
+    /// Changes a tile's terrain (in the editor); tiles outside of the map are ignored
+    pub fn set_terrain_id(&mut self, row: i32, col: i32, terrain_id: TerrainId) {
+        if self.contains(row, col) {
+            let tile_index = self.tile_index(row, col);
+            self.tiles[tile_index].terrain_id = terrain_id;
+            self.touch(row, col);
+        }
+    }
+
+    /// Changes a tile's elevation (in the editor), clamped to the elevation range;
+    /// tiles outside of the map are ignored
+    pub fn set_elevation(&mut self, row: i32, col: i32, elevation: i32) {
+        if self.contains(row, col) {
+            let (min_elevation, max_elevation) = self.elevation_range();
+            let elevation = cmp::max(min_elevation, cmp::min(elevation, max_elevation));
+            let tile_index = self.tile_index(row, col);
+            self.tiles[tile_index].elevation = elevation as u8;
+            self.touch(row, col);
+        }
+    }
+
+    /// Whether the tile is on the map
+    pub fn contains(&self, row: i32, col: i32) -> bool {
+        row >= 0 && col >= 0 && row < self.height && col < self.width
+    }
+
+    /// The chunk's revision, which changes whenever a tile in it (or next to it) does.
+    /// Chunks outside of the map never change.
+    pub fn chunk_revision(&self, chunk_row: i32, chunk_col: i32) -> u32 {
+        let (chunk_rows, chunk_cols) = self.chunk_dimensions();
+        if chunk_row < 0 || chunk_col < 0 || chunk_row >= chunk_rows || chunk_col >= chunk_cols {
+            return 0;
+        }
+        self.chunk_revisions[(chunk_row * chunk_cols + chunk_col) as usize]
+    }
+
+    /// How many (rows, columns) of chunks the map is split into
+    pub fn chunk_dimensions(&self) -> (i32, i32) {
+        ((self.height + CHUNK_SIZE - 1) / CHUNK_SIZE, (self.width + CHUNK_SIZE - 1) / CHUNK_SIZE)
+    }
+
+    /// A tile's blend depends on its neighbors too, so chunks that a neighbor is in change as well
+    fn touch(&mut self, row: i32, col: i32) {
+        let (chunk_rows, chunk_cols) = self.chunk_dimensions();
+        let first_chunk = (cmp::max(0, (row - 1) / CHUNK_SIZE), cmp::max(0, (col - 1) / CHUNK_SIZE));
+        let last_chunk = (cmp::min(chunk_rows - 1, (row + 1) / CHUNK_SIZE),
+                          cmp::min(chunk_cols - 1, (col + 1) / CHUNK_SIZE));
+        for chunk_row in first_chunk.0..(last_chunk.0 + 1) {
+            for chunk_col in first_chunk.1..(last_chunk.1 + 1) {
+                let index = (chunk_row * chunk_cols + chunk_col) as usize;
+                self.chunk_revisions[index] = self.chunk_revisions[index].wrapping_add(1);
+            }
+        }
+    }
+
     fn tile_at_relative<'a>(&'a self, row: i32, col: i32, direction: usize) -> &'a Tile {
         let row = row + (direction as i32 / 3) - 1;
         let col = col + (direction as i32 % 3) - 1;
@@ -135,22 +204,9 @@ impl Terrain {
     /// Returns tile blend info at the requested row/col while clamping at the edges of the terrain
     /// so that the request cannot go out of bounds. This means that the tiles at the edges
     /// will repeat indefinitely if you query outside of the terrain.
-    pub fn blend_at<'a>(&'a mut self, row: i32, col: i32) -> &'a BlendInfo {
-        if let Some(blend_cache_index) = self.tile_at_row_col(row, col).blend_cache_index {
-            &self.blend_cache[blend_cache_index as usize]
-        } else {
-            let blend_info = self.blend_at_no_cache(row, col);
-            self.blend_cache.push(blend_info);
-
-            let blend_cache_index = self.blend_cache.len() - 1;
-            let tile_index = self.tile_index(row, col);
-
-            self.tiles[tile_index].blend_cache_index = Some(blend_cache_index as u32);
-            &self.blend_cache[blend_cache_index]
-        }
-    }
-
-    fn blend_at_no_cache(&self, row: i32, col: i32) -> BlendInfo {
+    ///
+    /// Blends aren't cached here; the terrain renderer keeps what it works out for each chunk.
+    pub fn blend_at(&self, row: i32, col: i32) -> BlendInfo {
         let tile = self.tile_at_row_col(row, col);
         let terrain = self.empires.terrain(tile.terrain_id as TerrainId);
 
@@ -211,3 +267,37 @@ impl Terrain {
         None
     }
 }
+
+fn chunk_count(width: i32, height: i32) -> usize {
+    (((width + CHUNK_SIZE - 1) / CHUNK_SIZE) * ((height + CHUNK_SIZE - 1) / CHUNK_SIZE)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use dat::{EmpiresDb, EmpiresDbRef};
+    use super::{CHUNK_SIZE, Terrain, Tile};
+
+    #[test]
+    fn test_edits_touch_neighboring_chunks() {
+        let size = CHUNK_SIZE * 2 + 1;
+        let tiles = (0..size * size).map(|_| Tile::new(0.into(), 0)).collect();
+        let mut terrain = Terrain::new(size, size, tiles, EmpiresDbRef::new(EmpiresDb::new()));
+        assert_eq!((3, 3), terrain.chunk_dimensions());
+
+        // In the middle of a chunk, only that chunk changes
+        terrain.set_elevation(3, 3, 1);
+        assert_eq!(1, terrain.chunk_revision(0, 0));
+        assert_eq!(0, terrain.chunk_revision(0, 1));
+
+        // On the edge, the chunk next door is blended with it
+        terrain.set_terrain_id(CHUNK_SIZE - 1, 3, 1.into());
+        assert_eq!(2, terrain.chunk_revision(0, 0));
+        assert_eq!(1, terrain.chunk_revision(1, 0));
+        assert_eq!(0, terrain.chunk_revision(1, 1));
+
+        // Tiles off the map are ignored
+        terrain.set_elevation(-1, -1, 1);
+        assert_eq!(2, terrain.chunk_revision(0, 0));
+        assert_eq!(0, terrain.chunk_revision(-1, 0));
+    }
+}
@@ -0,0 +1,186 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::PlayerId;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use types::Fixed;
+
+/// How often the timeline takes a sample of every player
+pub const SAMPLE_SECONDS: u32 = 60;
+
+/// How a player was doing at one point in the game
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TimelineSample {
+    pub population: u32,
+    pub military: u32,
+    pub resources_gathered: u32,
+    pub score: u32,
+}
+
+persist_struct!(TimelineSample { population, military, resources_gathered, score });
+
+/// Samples of every player taken once a minute, for the graphs shown after the game
+/// and for exporting to other tools
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    time_until_sample: Fixed,
+
+    /// One entry per sample; the first one is taken right as the game starts
+    samples: Vec<BTreeMap<PlayerId, TimelineSample>>,
+}
+
+persist_struct!(Timeline { time_until_sample, samples });
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            time_until_sample: 0.into(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Counts down to the next sample; returns true if it's time to record one
+    pub fn update(&mut self, time_step: Fixed) -> bool {
+        self.time_until_sample -= time_step;
+        if self.time_until_sample <= 0.into() {
+            self.time_until_sample += SAMPLE_SECONDS.into();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn record(&mut self, samples: BTreeMap<PlayerId, TimelineSample>) {
+        self.samples.push(samples);
+    }
+
+    pub fn samples(&self) -> &[BTreeMap<PlayerId, TimelineSample>] {
+        &self.samples
+    }
+
+    /// One player's samples in order, with zeroes for any samples taken before they were around
+    pub fn player_samples(&self, player_id: PlayerId) -> Vec<TimelineSample> {
+        self.samples
+            .iter()
+            .map(|samples| samples.get(&player_id).cloned().unwrap_or_else(Default::default))
+            .collect()
+    }
+
+    /// Every player that appears in any sample, in player order
+    pub fn player_ids(&self) -> Vec<PlayerId> {
+        let mut player_ids: Vec<PlayerId> = Vec::new();
+        for samples in &self.samples {
+            for player_id in samples.keys() {
+                if !player_ids.contains(player_id) {
+                    player_ids.push(*player_id);
+                }
+            }
+        }
+        player_ids.sort();
+        player_ids
+    }
+
+    /// The samples as JSON, grouped by player:
+    /// `{"sample_seconds": 60, "players": [{"player_id": 1, "samples": [{"minute": 0, ...}, ...]}]}`
+    pub fn to_json(&self) -> String {
+        let players: Vec<String> = self.player_ids()
+            .into_iter()
+            .map(|player_id| {
+                let samples: Vec<String> = self.player_samples(player_id)
+                    .iter()
+                    .enumerate()
+                    .map(|(minute, sample)| {
+                        format!("{{\"minute\": {}, \"population\": {}, \"military\": {}, \
+                                 \"resources_gathered\": {}, \"score\": {}}}",
+                                minute,
+                                sample.population,
+                                sample.military,
+                                sample.resources_gathered,
+                                sample.score)
+                    })
+                    .collect();
+                format!("{{\"player_id\": {}, \"samples\": [{}]}}", *player_id, samples.join(", "))
+            })
+            .collect();
+        format!("{{\"sample_seconds\": {}, \"players\": [{}]}}\n",
+                SAMPLE_SECONDS,
+                players.join(", "))
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_name: P) -> io::Result<()> {
+        try!(File::create(file_name)).write_all(self.to_json().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::PlayerId;
+    use std::collections::BTreeMap;
+    use super::{Timeline, TimelineSample};
+    use types::Fixed;
+
+    fn sample(score: u32) -> TimelineSample {
+        TimelineSample { score: score, ..Default::default() }
+    }
+
+    #[test]
+    fn test_samples_once_a_minute() {
+        let mut timeline = Timeline::new();
+        let time_step = Fixed::from(1) / Fixed::from(2);
+        assert!(timeline.update(time_step));
+
+        let samples = (0..240).filter(|_| timeline.update(time_step)).count();
+        assert_eq!(2, samples);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut timeline = Timeline::new();
+        let mut first: BTreeMap<PlayerId, TimelineSample> = BTreeMap::new();
+        first.insert(1.into(), sample(5));
+        timeline.record(first);
+        let mut second: BTreeMap<PlayerId, TimelineSample> = BTreeMap::new();
+        second.insert(1.into(), sample(8));
+        second.insert(2.into(), TimelineSample { population: 3, ..Default::default() });
+        timeline.record(second);
+
+        assert_eq!(vec![sample(0), TimelineSample { population: 3, ..Default::default() }],
+                   timeline.player_samples(2.into()));
+        let entry = |minute: u32, population: u32, score: u32| {
+            format!("{{\"minute\": {}, \"population\": {}, \"military\": 0, \"resources_gathered\": 0, \
+                     \"score\": {}}}",
+                    minute,
+                    population,
+                    score)
+        };
+        assert_eq!(format!("{{\"sample_seconds\": 60, \"players\": [\
+                            {{\"player_id\": 1, \"samples\": [{}, {}]}}, \
+                            {{\"player_id\": 2, \"samples\": [{}, {}]}}]}}\n",
+                           entry(0, 0, 5),
+                           entry(1, 0, 8),
+                           entry(0, 0, 0),
+                           entry(1, 3, 0)),
+                   timeline.to_json());
+    }
+}
@@ -0,0 +1,356 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use action::{Reader, Writer};
+use ecs::Persist;
+use identifier::PlayerId;
+use specs::Index;
+use std::collections::{BTreeMap, BTreeSet};
+use types::Fixed;
+
+/// Game time in a year of the victory countdowns
+pub const SECONDS_PER_YEAR: Fixed = fixed_const!(0.5);
+
+/// How long a player has to hold on to win
+pub const VICTORY_COUNTDOWN_YEARS: u32 = 2000;
+
+/// Countdowns are announced again each time this many years go by
+const ANNOUNCEMENT_INTERVAL_YEARS: u32 = 500;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CountdownKind {
+    /// The player holds every artifact and ruin on the map
+    Artifacts,
+
+    /// The player finished building this wonder; each wonder has its own countdown
+    Wonder(Index),
+}
+
+impl Persist for CountdownKind {
+    fn write(&self, writer: &mut Writer) {
+        match *self {
+            CountdownKind::Artifacts => writer.byte(0),
+            CountdownKind::Wonder(wonder_id) => {
+                writer.byte(1);
+                wonder_id.write(writer);
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<CountdownKind, String> {
+        match try!(reader.byte()) {
+            0 => Ok(CountdownKind::Artifacts),
+            1 => Ok(CountdownKind::Wonder(try!(Index::read(reader)))),
+            tag => Err(format!("unknown countdown {}", tag)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Countdown {
+    pub kind: CountdownKind,
+    pub player_id: PlayerId,
+
+    /// Seconds to go
+    pub time_left: Fixed,
+}
+
+persist_struct!(Countdown { kind, player_id, time_left });
+
+impl Countdown {
+    /// Years to go, rounded up
+    pub fn years_left(&self) -> u32 {
+        let years = u32::from((self.time_left / SECONDS_PER_YEAR).truncate());
+        if Fixed::from(years) * SECONDS_PER_YEAR < self.time_left { years + 1 } else { years }
+    }
+}
+
+/// Something about a countdown that everyone should hear about
+#[derive(Clone, Debug, PartialEq)]
+pub enum CountdownUpdate {
+    YearsLeft(Countdown),
+    Won(Countdown),
+}
+
+/// Victory countdowns in progress, who's still in the game, and who won (if anyone has)
+#[derive(Clone, Debug)]
+pub struct Victory {
+    countdowns: Vec<Countdown>,
+    winner: Option<PlayerId>,
+
+    /// Players that still have units or buildings that can make more
+    contenders: BTreeSet<PlayerId>,
+
+    /// Players that are out of the game, in the order they went out
+    defeated: Vec<PlayerId>,
+
+    /// Conquest only games never start countdowns
+    conquest_only: bool,
+}
+
+persist_struct!(Victory { countdowns, winner, contenders, defeated, conquest_only });
+
+impl Victory {
+    pub fn new() -> Victory {
+        Victory {
+            countdowns: Vec::new(),
+            winner: None,
+            contenders: BTreeSet::new(),
+            defeated: Vec::new(),
+            conquest_only: false,
+        }
+    }
+
+    pub fn conquest_only() -> Victory {
+        Victory { conquest_only: true, ..Victory::new() }
+    }
+
+    pub fn winner(&self) -> Option<PlayerId> {
+        self.winner
+    }
+
+    /// Ends the game (for scenario scripts); returns false if someone has already won
+    pub fn declare_winner(&mut self, player_id: PlayerId) -> bool {
+        if self.winner.is_some() {
+            return false;
+        }
+        self.winner = Some(player_id);
+        true
+    }
+
+    pub fn is_defeated(&self, player_id: PlayerId) -> bool {
+        self.defeated.contains(&player_id)
+    }
+
+    pub fn defeated<'a>(&'a self) -> &'a [PlayerId] {
+        &self.defeated
+    }
+
+    /// Takes the player out of the game (they resigned, or lost everything) and stops their
+    /// countdowns. Returns false if they were already out.
+    pub fn defeat(&mut self, player_id: PlayerId) -> bool {
+        if self.is_defeated(player_id) {
+            return false;
+        }
+        self.contenders.remove(&player_id);
+        self.defeated.push(player_id);
+        self.countdowns.retain(|countdown| countdown.player_id != player_id);
+        true
+    }
+
+    /// Takes note of which players still have units or buildings that can make more, and
+    /// defeats the ones that just lost the last of them. Players that never had anything
+    /// (like an empty scenario slot) aren't defeated. Returns who was defeated, in player order.
+    pub fn update_standing(&mut self, standing: &BTreeSet<PlayerId>) -> Vec<PlayerId> {
+        let lost: Vec<PlayerId> = self.contenders.difference(standing).cloned().collect();
+        for &player_id in &lost {
+            self.defeat(player_id);
+        }
+        for &player_id in standing {
+            if !self.is_defeated(player_id) {
+                self.contenders.insert(player_id);
+            }
+        }
+        lost
+    }
+
+    /// Once someone has been defeated, the game is won by conquest when everyone left is on
+    /// the same team. The lowest numbered player left stands in for the team. Returns the
+    /// winner, if the game was just won.
+    pub fn check_conquest(&mut self, teams: &BTreeMap<PlayerId, u8>) -> Option<PlayerId> {
+        if self.winner.is_some() || self.defeated.is_empty() {
+            return None;
+        }
+        let mut left = self.contenders.iter();
+        let first = match left.next() {
+            Some(&player_id) => player_id,
+            None => return None,
+        };
+        let team = teams.get(&first);
+        if left.all(|player_id| teams.get(player_id) == team) {
+            self.winner = Some(first);
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    pub fn countdown<'a>(&'a self, kind: CountdownKind) -> Option<&'a Countdown> {
+        self.countdowns.iter().find(|countdown| countdown.kind == kind)
+    }
+
+    /// Starts the countdown for the player, replacing anyone else's countdown of the same kind.
+    /// Returns false if the player's countdown was already running, or if the game is conquest only.
+    pub fn start(&mut self, kind: CountdownKind, player_id: PlayerId) -> bool {
+        if self.conquest_only {
+            return false;
+        }
+        if self.countdown(kind).map(|countdown| countdown.player_id == player_id).unwrap_or(false) {
+            return false;
+        }
+        self.cancel(kind);
+        self.countdowns.push(Countdown {
+            kind: kind,
+            player_id: player_id,
+            time_left: Fixed::from(VICTORY_COUNTDOWN_YEARS) * SECONDS_PER_YEAR,
+        });
+        true
+    }
+
+    /// Stops the countdown, returning it if it was running
+    pub fn cancel(&mut self, kind: CountdownKind) -> Option<Countdown> {
+        match self.countdowns.iter().position(|countdown| countdown.kind == kind) {
+            Some(index) => Some(self.countdowns.remove(index)),
+            None => None,
+        }
+    }
+
+    /// Counts down, and returns the countdowns that passed a milestone or ran out.
+    /// The first countdown to run out wins the game; nothing counts down after that.
+    pub fn update(&mut self, time_step: Fixed) -> Vec<CountdownUpdate> {
+        let mut updates = Vec::new();
+        if self.winner.is_some() {
+            return updates;
+        }
+
+        for countdown in &mut self.countdowns {
+            let years_before = countdown.years_left();
+            countdown.time_left -= time_step;
+            let years_after = countdown.years_left();
+
+            if countdown.time_left <= 0.into() {
+                if self.winner.is_none() {
+                    self.winner = Some(countdown.player_id);
+                    updates.push(CountdownUpdate::Won(countdown.clone()));
+                }
+            } else if (years_before - 1) / ANNOUNCEMENT_INTERVAL_YEARS !=
+                      (years_after - 1) / ANNOUNCEMENT_INTERVAL_YEARS {
+                updates.push(CountdownUpdate::YearsLeft(countdown.clone()));
+            }
+        }
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::PlayerId;
+    use std::collections::{BTreeMap, BTreeSet};
+    use super::{CountdownKind, CountdownUpdate, SECONDS_PER_YEAR, VICTORY_COUNTDOWN_YEARS, Victory};
+    use types::Fixed;
+
+    #[test]
+    fn test_start_and_cancel() {
+        let (first, second): (PlayerId, PlayerId) = (1.into(), 2.into());
+        let mut victory = Victory::new();
+        assert!(victory.start(CountdownKind::Artifacts, first));
+        assert!(!victory.start(CountdownKind::Artifacts, first));
+        assert!(victory.start(CountdownKind::Artifacts, second));
+        assert_eq!(second, victory.countdown(CountdownKind::Artifacts).unwrap().player_id);
+        assert_eq!(VICTORY_COUNTDOWN_YEARS,
+                   victory.countdown(CountdownKind::Artifacts).unwrap().years_left());
+
+        assert!(victory.cancel(CountdownKind::Artifacts).is_some());
+        assert!(victory.cancel(CountdownKind::Artifacts).is_none());
+    }
+
+    #[test]
+    fn test_conquest_only_has_no_countdowns() {
+        let mut victory = Victory::conquest_only();
+        assert!(!victory.start(CountdownKind::Artifacts, 1.into()));
+        assert!(!victory.start(CountdownKind::Wonder(10), 1.into()));
+        assert!(victory.countdown(CountdownKind::Artifacts).is_none());
+    }
+
+    #[test]
+    fn test_wonder_countdowns_run_side_by_side() {
+        let (first, second): (PlayerId, PlayerId) = (1.into(), 2.into());
+        let mut victory = Victory::new();
+        assert!(victory.start(CountdownKind::Wonder(10), first));
+        assert!(victory.start(CountdownKind::Wonder(11), second));
+        assert_eq!(first, victory.countdown(CountdownKind::Wonder(10)).unwrap().player_id);
+
+        victory.update(Fixed::from(1000) * SECONDS_PER_YEAR);
+        assert_eq!(first, victory.cancel(CountdownKind::Wonder(10)).unwrap().player_id);
+        victory.update(Fixed::from(1000) * SECONDS_PER_YEAR);
+        assert_eq!(Some(second), victory.winner());
+    }
+
+    #[test]
+    fn test_countdown_milestones_and_win() {
+        let player: PlayerId = 3.into();
+        let mut victory = Victory::new();
+        victory.start(CountdownKind::Artifacts, player);
+
+        assert!(victory.update(SECONDS_PER_YEAR).is_empty());
+        let updates = victory.update(Fixed::from(499) * SECONDS_PER_YEAR);
+        assert_eq!(1, updates.len());
+        match updates[0] {
+            CountdownUpdate::YearsLeft(ref countdown) => assert_eq!(1500, countdown.years_left()),
+            _ => panic!("expected years left"),
+        }
+
+        let updates = victory.update(Fixed::from(1500) * SECONDS_PER_YEAR);
+        match updates[0] {
+            CountdownUpdate::Won(ref countdown) => assert_eq!(player, countdown.player_id),
+            _ => panic!("expected a win"),
+        }
+        assert_eq!(Some(player), victory.winner());
+        assert!(victory.update(SECONDS_PER_YEAR).is_empty());
+    }
+
+    #[test]
+    fn test_defeat_and_conquest() {
+        let (one, two, three): (PlayerId, PlayerId, PlayerId) = (1.into(), 2.into(), 3.into());
+        let teams: BTreeMap<PlayerId, u8> = vec![(one, 1), (two, 2), (three, 1)].into_iter().collect();
+        let mut victory = Victory::new();
+
+        // Everyone is still in it, and nobody has won just by playing alone
+        let everyone: BTreeSet<PlayerId> = vec![one, two, three].into_iter().collect();
+        assert!(victory.update_standing(&everyone).is_empty());
+        assert_eq!(None, victory.check_conquest(&teams));
+
+        victory.start(CountdownKind::Artifacts, two);
+        let without_two: BTreeSet<PlayerId> = vec![one, three].into_iter().collect();
+        assert_eq!(vec![two], victory.update_standing(&without_two));
+        assert!(victory.is_defeated(two));
+        assert!(victory.countdown(CountdownKind::Artifacts).is_none());
+
+        // A player doesn't come back by getting another unit
+        assert!(victory.update_standing(&everyone).is_empty());
+        assert!(!victory.defeat(two));
+        assert_eq!(Some(one), victory.check_conquest(&teams));
+        assert_eq!(Some(one), victory.winner());
+        assert_eq!(None, victory.check_conquest(&teams));
+    }
+
+    #[test]
+    fn test_no_conquest_while_enemies_are_left() {
+        let (one, two, three): (PlayerId, PlayerId, PlayerId) = (1.into(), 2.into(), 3.into());
+        let teams: BTreeMap<PlayerId, u8> = vec![(one, 1), (two, 2), (three, 3)].into_iter().collect();
+        let mut victory = Victory::new();
+        victory.update_standing(&vec![one, two, three].into_iter().collect());
+        assert!(victory.defeat(three));
+        assert_eq!(None, victory.check_conquest(&teams));
+        assert_eq!(vec![three], victory.defeated().to_vec());
+    }
+}
@@ -0,0 +1,319 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Reader, Writer};
+use ecs::Persist;
+use ecs::component::{ActionQueueComponent, ArtifactComponent, AttackGroundActionComponent,
+                     AttackMoveActionComponent, AttackUnitActionComponent, BuildActionComponent,
+                     CameraComponent, CarryComponent, ConstructionComponent, DecayComponent,
+                     GarrisonActionComponent, GarrisonedComponent, GatherActionComponent, GraphicComponent,
+                     HealthComponent, LineOfSightComponent, MoveToPositionActionComponent, OnScreenComponent,
+                     ProjectileComponent, ResourceComponent, SelectedUnitComponent, TrainingQueueComponent,
+                     TransformComponent, UnitComponent, VelocityComponent};
+use ecs::event::{Blast, BuildingCompleted, PlayerDefeated, PlayerResigned, ResearchCompleted, ResourceDepleted,
+                 UnitConverted, UnitDied};
+use ecs::resource::{ActionBatcher, Announcements, Events, Exploration, GameRng, Messages, OccupiedTiles,
+                    PlayerStats, PlayerTechs, Scripts, SimulationTick, SpawnQueue, Stockpiles, Timeline,
+                    Victory};
+use partition::GridPartition;
+use specs::{self, Index, Join};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const MAGIC: &'static [u8] = b"CHWS";
+const FORMAT_VERSION: u8 = 4;
+
+macro_rules! entity_snapshot {
+    ($($field:ident: $typ:ty,)*) => {
+        #[derive(Clone)]
+        struct EntitySnapshot {
+            id: Index,
+            $( $field: Option<$typ>, )*
+        }
+
+        persist_struct!(EntitySnapshot { id, $( $field ),* });
+
+        fn capture_entities(world: &specs::World) -> Vec<EntitySnapshot> {
+            let entities = world.entities();
+            let transforms = world.read::<TransformComponent>();
+            $( let $field = world.read::<$typ>(); )*
+
+            // Join iteration is in entity index order, which restoring relies on
+            (&entities, &transforms)
+                .iter()
+                .map(|(entity, _)| {
+                    EntitySnapshot {
+                        id: entity.get_id(),
+                        $( $field: $field.get(entity).cloned(), )*
+                    }
+                })
+                .collect()
+        }
+
+        fn restore_components(world: &mut specs::World, restored: &[(specs::Entity, &EntitySnapshot)]) {
+            $({
+                let mut storage = world.write::<$typ>();
+                for &(entity, snapshot) in restored {
+                    if let Some(ref component) = snapshot.$field {
+                        storage.insert(entity, component.clone());
+                    }
+                }
+            })*
+        }
+    }
+}
+
+// Every component registered with the world has to be listed here
+entity_snapshot! {
+    action_queue: ActionQueueComponent,
+    artifact: ArtifactComponent,
+    attack_ground_action: AttackGroundActionComponent,
+    attack_move_action: AttackMoveActionComponent,
+    attack_unit_action: AttackUnitActionComponent,
+    build_action: BuildActionComponent,
+    camera: CameraComponent,
+    carry: CarryComponent,
+    construction: ConstructionComponent,
+    decay: DecayComponent,
+    garrison_action: GarrisonActionComponent,
+    garrisoned: GarrisonedComponent,
+    gather_action: GatherActionComponent,
+    graphic: GraphicComponent,
+    health: HealthComponent,
+    line_of_sight: LineOfSightComponent,
+    move_to_position_action: MoveToPositionActionComponent,
+    on_screen: OnScreenComponent,
+    projectile: ProjectileComponent,
+    resource: ResourceComponent,
+    selected_unit: SelectedUnitComponent,
+    training_queue: TrainingQueueComponent,
+    transform: TransformComponent,
+    unit: UnitComponent,
+    velocity: VelocityComponent,
+}
+
+/// A copy of the world's entities and simulation resources at the end of a tick. Restoring it
+/// into a world created from the same scenario carries on exactly as the original did from that
+/// tick (including playing back the rest of a replay), so it's how the replay viewer seeks.
+/// Written to disk, it's where repro bundles start playing back from.
+///
+/// Resources that don't change after setup (terrain, players, the path finder) and input
+/// or render resources are left alone.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    entities: Vec<EntitySnapshot>,
+    tick: SimulationTick,
+    rng: GameRng,
+    action_batcher: ActionBatcher,
+    occupied_tiles: OccupiedTiles,
+    spawn_queue: SpawnQueue,
+    player_techs: PlayerTechs,
+    player_stats: PlayerStats,
+    stockpiles: Stockpiles,
+    victory: Victory,
+    exploration: Exploration,
+    announcements: Announcements,
+    messages: Messages,
+    timeline: Timeline,
+    scripts: Scripts,
+    grid: GridPartition,
+    unit_died: Events<UnitDied>,
+    unit_converted: Events<UnitConverted>,
+    building_completed: Events<BuildingCompleted>,
+    resource_depleted: Events<ResourceDepleted>,
+    research_completed: Events<ResearchCompleted>,
+    blasts: Events<Blast>,
+    player_resigned: Events<PlayerResigned>,
+    player_defeated: Events<PlayerDefeated>,
+}
+
+impl WorldSnapshot {
+    /// Entities are found through their transforms; everything in the world has a position
+    pub fn capture(world: &specs::World) -> WorldSnapshot {
+        WorldSnapshot {
+            entities: capture_entities(world),
+            tick: world.read_resource::<SimulationTick>().clone(),
+            rng: world.read_resource::<GameRng>().clone(),
+            action_batcher: world.read_resource::<ActionBatcher>().clone(),
+            occupied_tiles: world.read_resource::<OccupiedTiles>().clone(),
+            spawn_queue: world.read_resource::<SpawnQueue>().clone(),
+            player_techs: world.read_resource::<PlayerTechs>().clone(),
+            player_stats: world.read_resource::<PlayerStats>().clone(),
+            stockpiles: world.read_resource::<Stockpiles>().clone(),
+            victory: world.read_resource::<Victory>().clone(),
+            exploration: world.read_resource::<Exploration>().clone(),
+            announcements: world.read_resource::<Announcements>().clone(),
+            messages: world.read_resource::<Messages>().clone(),
+            timeline: world.read_resource::<Timeline>().clone(),
+            scripts: world.read_resource::<Scripts>().clone(),
+            grid: world.read_resource::<GridPartition>().clone(),
+            unit_died: world.read_resource::<Events<UnitDied>>().clone(),
+            unit_converted: world.read_resource::<Events<UnitConverted>>().clone(),
+            building_completed: world.read_resource::<Events<BuildingCompleted>>().clone(),
+            resource_depleted: world.read_resource::<Events<ResourceDepleted>>().clone(),
+            research_completed: world.read_resource::<Events<ResearchCompleted>>().clone(),
+            blasts: world.read_resource::<Events<Blast>>().clone(),
+            player_resigned: world.read_resource::<Events<PlayerResigned>>().clone(),
+            player_defeated: world.read_resource::<Events<PlayerDefeated>>().clone(),
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick.tick
+    }
+
+    /// Replaces every entity in the world with the snapshot's.
+    ///
+    /// Actions, occupied tiles, and the grid refer to entities by index, so each entity has to come
+    /// back with the index it had. The world hands out the lowest free index to new entities,
+    /// so after deleting everything we create one entity per index up to the highest one in the
+    /// snapshot, and delete the ones that weren't alive when it was taken.
+    pub fn restore(&self, world: &mut specs::World) {
+        let existing: Vec<specs::Entity> = {
+            let entities = world.entities();
+            let transforms = world.read::<TransformComponent>();
+            (&entities, &transforms).iter().map(|(entity, _)| entity).collect()
+        };
+        for entity in existing {
+            world.delete_now(entity);
+        }
+
+        let entity_count = self.entities.last().map(|e| e.id as usize + 1).unwrap_or(0);
+        let mut snapshots = self.entities.iter().peekable();
+        let mut restored = Vec::with_capacity(self.entities.len());
+        let mut unused = Vec::new();
+        for index in 0..entity_count {
+            let entity = world.create_now().build();
+            debug_assert!(entity.get_id() as usize == index,
+                          "restored entity {} came back as {}",
+                          index,
+                          entity.get_id());
+            if snapshots.peek().map(|s| s.id as usize == index).unwrap_or(false) {
+                restored.push((entity, snapshots.next().unwrap()));
+            } else {
+                unused.push(entity);
+            }
+        }
+        for entity in unused {
+            world.delete_now(entity);
+        }
+        restore_components(world, &restored);
+
+        // What's on screen is up to whoever is watching, so it's worked out again from their view
+        // rather than left as it was when the snapshot was taken
+        world.write::<OnScreenComponent>().clear();
+
+        *world.write_resource::<SimulationTick>() = self.tick.clone();
+        *world.write_resource::<GameRng>() = self.rng.clone();
+        *world.write_resource::<ActionBatcher>() = self.action_batcher.clone();
+        *world.write_resource::<OccupiedTiles>() = self.occupied_tiles.clone();
+        *world.write_resource::<SpawnQueue>() = self.spawn_queue.clone();
+        *world.write_resource::<PlayerTechs>() = self.player_techs.clone();
+        *world.write_resource::<PlayerStats>() = self.player_stats.clone();
+        *world.write_resource::<Stockpiles>() = self.stockpiles.clone();
+        *world.write_resource::<Victory>() = self.victory.clone();
+        *world.write_resource::<Exploration>() = self.exploration.clone();
+        *world.write_resource::<Announcements>() = self.announcements.clone();
+        *world.write_resource::<Messages>() = self.messages.clone();
+        *world.write_resource::<Timeline>() = self.timeline.clone();
+        *world.write_resource::<Scripts>() = self.scripts.clone();
+        *world.write_resource::<GridPartition>() = self.grid.clone();
+        *world.write_resource::<Events<UnitDied>>() = self.unit_died.clone();
+        *world.write_resource::<Events<UnitConverted>>() = self.unit_converted.clone();
+        *world.write_resource::<Events<BuildingCompleted>>() = self.building_completed.clone();
+        *world.write_resource::<Events<ResourceDepleted>>() = self.resource_depleted.clone();
+        *world.write_resource::<Events<ResearchCompleted>>() = self.research_completed.clone();
+        *world.write_resource::<Events<Blast>>() = self.blasts.clone();
+        *world.write_resource::<Events<PlayerResigned>>() = self.player_resigned.clone();
+        *world.write_resource::<Events<PlayerDefeated>>() = self.player_defeated.clone();
+    }
+
+    /// The snapshot in a binary format: `CHWS`, a version byte, and then every entity and
+    /// resource as `Persist` writes them
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.raw(MAGIC);
+        writer.byte(FORMAT_VERSION);
+        self.write(&mut writer);
+        writer.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<WorldSnapshot> {
+        let mut reader = Reader::new(bytes);
+        read_snapshot(&mut reader).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("snapshot byte {}: {}", reader.position(), err))
+        })
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_name: P) -> io::Result<()> {
+        try!(File::create(file_name)).write_all(&self.to_bytes())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> io::Result<WorldSnapshot> {
+        let mut bytes = Vec::new();
+        try!(try!(File::open(file_name)).read_to_end(&mut bytes));
+        WorldSnapshot::from_bytes(&bytes)
+    }
+}
+
+persist_struct!(WorldSnapshot {
+    entities,
+    tick,
+    rng,
+    action_batcher,
+    occupied_tiles,
+    spawn_queue,
+    player_techs,
+    player_stats,
+    stockpiles,
+    victory,
+    exploration,
+    announcements,
+    messages,
+    timeline,
+    scripts,
+    grid,
+    unit_died,
+    unit_converted,
+    building_completed,
+    resource_depleted,
+    research_completed,
+    blasts,
+    player_resigned,
+    player_defeated
+});
+
+fn read_snapshot(reader: &mut Reader) -> Result<WorldSnapshot, String> {
+    if try!(reader.raw(MAGIC.len())) != MAGIC {
+        return Err("not a snapshot".to_string());
+    }
+    let version = try!(reader.byte());
+    if version != FORMAT_VERSION {
+        return Err(format!("version {} isn't one this build understands", version));
+    }
+    let snapshot = try!(WorldSnapshot::read(reader));
+    if !reader.is_at_end() {
+        return Err("unexpected bytes after the snapshot".to_string());
+    }
+    Ok(snapshot)
+}
@@ -0,0 +1,94 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::component::{ActionQueueComponent, CarryComponent, ConstructionComponent, ResourceComponent,
+                     TrainingQueueComponent, TransformComponent, UnitComponent, VelocityComponent};
+use ecs::resource::{GameRng, PlayerTechs, SimulationTick};
+use specs::{self, Join};
+use types::Vector3;
+use util::hash::StateHasher;
+
+/// Fingerprints the simulation state of the world.
+///
+/// Two runs of the same scenario with the same commands must produce the same hash on every machine,
+/// so only simulation data goes in; anything that only exists for presentation (graphics, effects,
+/// the camera, input) is left out.
+pub fn state_hash(world: &specs::World) -> u64 {
+    let entities = world.entities();
+    let units = world.read::<UnitComponent>();
+    let transforms = world.read::<TransformComponent>();
+    let velocities = world.read::<VelocityComponent>();
+    let action_queues = world.read::<ActionQueueComponent>();
+    let training_queues = world.read::<TrainingQueueComponent>();
+    let resources = world.read::<ResourceComponent>();
+    let carries = world.read::<CarryComponent>();
+    let constructions = world.read::<ConstructionComponent>();
+
+    let mut hasher = StateHasher::new();
+    hasher.write_u64(world.read_resource::<SimulationTick>().tick);
+    for word in &world.read_resource::<GameRng>().state() {
+        hasher.write_u64(*word);
+    }
+    for (player_id, research_id) in world.read_resource::<PlayerTechs>().researched() {
+        hasher.write_u8(*player_id);
+        hasher.write_u32(*research_id);
+    }
+
+    // Join iteration is in entity index order, so this is stable
+    for (entity, unit, transform) in (&entities, &units, &transforms).iter() {
+        hasher.write_u32(entity.get_id());
+        hasher.write_u8(*unit.player_id);
+        hasher.write_u8(*unit.civilization_id);
+        hasher.write_u32(*unit.unit_id);
+        write_vector(&mut hasher, transform.position());
+        hasher.write_i64(transform.rotation.scaled);
+
+        if let Some(velocity) = velocities.get(entity) {
+            write_vector(&mut hasher, &velocity.velocity);
+        }
+        if let Some(action_queue) = action_queues.get(entity) {
+            hasher.write_u8(action_queue.current_action().is_some() as u8);
+            hasher.write_u8(action_queue.current_action_done() as u8);
+            hasher.write_u64(action_queue.pending_len() as u64);
+        }
+        if let Some(training_queue) = training_queues.get(entity) {
+            hasher.write_u64(training_queue.len() as u64);
+            hasher.write_i64(training_queue.progress.scaled);
+        }
+        if let Some(resource) = resources.get(entity) {
+            hasher.write_i64(resource.amount.scaled);
+        }
+        if let Some(carry) = carries.get(entity) {
+            hasher.write_i64(carry.amount.scaled);
+        }
+        if let Some(construction) = constructions.get(entity) {
+            hasher.write_i64(construction.progress.scaled);
+        }
+    }
+
+    hasher.finish()
+}
+
+fn write_vector(hasher: &mut StateHasher, vector: &Vector3) {
+    hasher.write_i64(vector.x.scaled);
+    hasher.write_i64(vector.y.scaled);
+    hasher.write_i64(vector.z.scaled);
+}
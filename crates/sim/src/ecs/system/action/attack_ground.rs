@@ -0,0 +1,148 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat::EmpiresDbRef;
+use ecs::component::{AttackGroundActionComponent, GraphicComponent, ProjectileComponent, TransformComponent,
+                     UnitComponent, VelocityComponent};
+use identifier::{CivilizationId, PlayerId, UnitId};
+use specs::{self, Join};
+use super::super::{System, SystemAccess};
+use types::{Fixed, Norm, Vector3};
+use util::combat;
+
+/// Used for projectiles that don't say how fast they fly
+const DEFAULT_PROJECTILE_SPEED: Fixed = fixed_const!(5.0);
+
+struct Shot {
+    player_id: PlayerId,
+    civilization_id: CivilizationId,
+    projectile_unit_id: UnitId,
+    origin: Vector3,
+    target: Vector3,
+    attacks: Vec<(i16, i16)>,
+    blast_radius: Fixed,
+    blast_attack_level: i8,
+}
+
+/// Keeps siege units firing at the spot they were told to attack until they're given
+/// another order. Units only fire while the spot is in range; the selection system sends
+/// them into range first.
+pub struct AttackGroundActionSystem {
+    empires: EmpiresDbRef,
+}
+
+impl AttackGroundActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> AttackGroundActionSystem {
+        AttackGroundActionSystem { empires: empires }
+    }
+}
+
+impl System for AttackGroundActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .write::<AttackGroundActionComponent>()
+            .write::<GraphicComponent>()
+            .write::<ProjectileComponent>()
+            .write::<TransformComponent>()
+            .write::<VelocityComponent>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, _entities, [
+            components(units: UnitComponent),
+            mut components(attack_grounds: AttackGroundActionComponent),
+            mut components(graphics: GraphicComponent),
+            mut components(projectiles: ProjectileComponent),
+            mut components(transforms: TransformComponent),
+            mut components(velocities: VelocityComponent),
+        ]);
+
+        let mut shots = Vec::new();
+        for (unit, attack, transform) in (&units, &mut attack_grounds, &mut transforms).iter() {
+            let params = match unit.db(&self.empires).battle_params {
+                Some(ref params) => params,
+                None => continue,
+            };
+            let projectile_unit_id = match params.projectile_unit_id {
+                Some(projectile_unit_id) => projectile_unit_id,
+                None => continue,
+            };
+
+            if attack.reload_time_left > 0.into() {
+                attack.reload_time_left -= time_step;
+                continue;
+            }
+
+            let distance = combat::ground_distance(transform.position(), &attack.target);
+            if distance > params.max_range.into() || distance < params.min_range.into() {
+                continue;
+            }
+
+            transform.face_towards(&attack.target);
+            attack.reload_time_left = params.reload_time.into();
+            shots.push(Shot {
+                player_id: unit.player_id,
+                civilization_id: unit.civilization_id,
+                projectile_unit_id: projectile_unit_id,
+                origin: *transform.position(),
+                target: attack.target,
+                attacks: params.attacks.clone(),
+                blast_radius: params.blast_width.into(),
+                blast_attack_level: params.blast_attack_level,
+            });
+        }
+
+        for shot in shots {
+            let found = self.empires.find_unit(shot.civilization_id, shot.projectile_unit_id);
+            let projectile_info = match found {
+                Some(projectile_info) => projectile_info,
+                None => continue,
+            };
+            let speed = match projectile_info.motion_params {
+                Some(ref params) if params.speed > 0.0 => params.speed.into(),
+                _ => DEFAULT_PROJECTILE_SPEED,
+            };
+            let mut direction = shot.target - shot.origin;
+            let distance = direction.normalize();
+
+            let mut graphic = GraphicComponent::new();
+            graphic.player_id = shot.player_id;
+            graphic.graphic_id = projectile_info.standing_graphic;
+            let mut velocity = VelocityComponent::new();
+            velocity.velocity = direction * speed;
+
+            let projectile = arg.create();
+            transforms.insert(projectile,
+                              TransformComponent::new(shot.origin, Fixed::atan2(direction.y, direction.x)));
+            graphics.insert(projectile, graphic);
+            velocities.insert(projectile, velocity);
+            projectiles.insert(projectile,
+                               ProjectileComponent::new(shot.player_id,
+                                                        shot.target,
+                                                        shot.attacks,
+                                                        shot.blast_radius,
+                                                        shot.blast_attack_level,
+                                                        distance / speed));
+        }
+    }
+}
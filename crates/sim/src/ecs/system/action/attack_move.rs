@@ -0,0 +1,209 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, AttackMoveParams, AttackUnitParams, MoveToPositionParams};
+use dat::EmpiresDbRef;
+use ecs::component::{ActionQueueComponent, AttackMoveActionComponent, CarryComponent, GarrisonedComponent,
+                     GraphicComponent, HealthComponent, LineOfSightComponent, TransformComponent,
+                     UnitComponent, VelocityComponent};
+use ecs::resource::{OccupiedTiles, PathFinder, Players, Terrain};
+use identifier::PlayerId;
+use specs::{self, Index, Join};
+use super::super::{System, SystemAccess};
+use super::move_to_position::follow_path;
+use types::{Fixed, Vector3};
+use util::combat;
+
+/// How far off its path a unit can be when it picks an attack-move back up before it finds
+/// a new path to the destination instead
+const REJOIN_DISTANCE: Fixed = fixed_const!(2);
+
+struct Enemy {
+    entity_id: Index,
+    player_id: PlayerId,
+    position: Vector3,
+    size: Fixed,
+    building: bool,
+}
+
+/// Walks units along their path like a move, except that they stop to fight any enemy that comes
+/// into sight. The fight is an `AttackUnit` (with a move into range first) put ahead of the rest
+/// of the attack-move, so once the enemy is dead (or gets away) the unit carries on.
+pub struct AttackMoveActionSystem {
+    empires: EmpiresDbRef,
+}
+
+impl AttackMoveActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> AttackMoveActionSystem {
+        AttackMoveActionSystem { empires: empires }
+    }
+}
+
+impl System for AttackMoveActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<CarryComponent>()
+            .read::<GarrisonedComponent>()
+            .read::<HealthComponent>()
+            .read::<LineOfSightComponent>()
+            .read::<OccupiedTiles>()
+            .read::<PathFinder>()
+            .read::<Players>()
+            .read::<Terrain>()
+            .write::<ActionQueueComponent>()
+            .write::<AttackMoveActionComponent>()
+            .write::<GraphicComponent>()
+            .write::<TransformComponent>()
+            .write::<VelocityComponent>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            components(carries: CarryComponent),
+            components(garrisoneds: GarrisonedComponent),
+            components(healths: HealthComponent),
+            components(line_of_sights: LineOfSightComponent),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(attack_moves: AttackMoveActionComponent),
+            mut components(graphics: GraphicComponent),
+            mut components(transforms: TransformComponent),
+            mut components(velocities: VelocityComponent),
+            resource(occupied_tiles: OccupiedTiles),
+            resource(path_finder: PathFinder),
+            resource(players: Players),
+            resource(terrain: Terrain),
+        ]);
+
+        // Looking for enemies means going through every unit, so don't unless someone's looking
+        if (&attack_moves).iter().next().is_none() {
+            return;
+        }
+
+        let mut enemies = Vec::new();
+        for (entity, unit, transform, health) in (&entities, &units, &transforms, &healths).iter() {
+            // Units inside buildings can't be attacked
+            if health.is_dead() || garrisoneds.get(entity).is_some() {
+                continue;
+            }
+            let unit_info = unit.db(&self.empires);
+            enemies.push(Enemy {
+                entity_id: entity.get_id(),
+                player_id: unit.player_id,
+                position: *transform.position(),
+                size: unit_info.collision_size_x.max(unit_info.collision_size_y).into(),
+                building: unit_info.is_building(),
+            });
+        }
+
+        let items = (&entities,
+                     &units,
+                     &mut attack_moves,
+                     &mut action_queues,
+                     &mut transforms,
+                     &mut velocities,
+                     &mut graphics);
+        for (entity, unit, attack_move, action_queue, transform, velocity, graphic) in items.iter() {
+            let unit_info = unit.db(&self.empires);
+            let position = *transform.position();
+
+            // Coming back from a fight, the unit may be well away from where it left the path
+            if !attack_move.started {
+                attack_move.started = true;
+                let rejoin = match (attack_move.path.first(), attack_move.path.last()) {
+                    (Some(first), Some(last)) => {
+                        if combat::ground_distance(&position, first) > REJOIN_DISTANCE {
+                            Some(*last)
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(destination) = rejoin {
+                    attack_move.path = path_finder.find_path(&*terrain,
+                                                             &*occupied_tiles,
+                                                             &position,
+                                                             &destination,
+                                                             unit_info.terrain_restriction);
+                }
+            }
+
+            let sight = line_of_sights.get(entity).map(|line_of_sight| line_of_sight.range);
+            let max_range = match unit_info.battle_params {
+                Some(ref params) if !params.attacks.is_empty() => Some(params.max_range),
+                _ => None,
+            };
+            if let (Some(sight), Some(max_range)) = (sight, max_range) {
+                let in_sight: Vec<(Index, Vector3, bool)> = enemies.iter()
+                    .filter(|enemy| {
+                        enemy.entity_id != entity.get_id() &&
+                        players.are_enemies(unit.player_id, enemy.player_id)
+                    })
+                    .map(|enemy| (enemy.entity_id, enemy.position, enemy.building))
+                    .collect();
+                let target = combat::attack_move_target(&position, sight, &in_sight)
+                    .and_then(|target_id| enemies.iter().find(|enemy| enemy.entity_id == target_id));
+                if let Some(target) = target {
+                    let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+                    let reach = Fixed::from(max_range) + size + target.size;
+                    let mut detour = Vec::new();
+                    let mut reachable = true;
+                    if combat::ground_distance(&position, &target.position) > reach {
+                        let path = path_finder.find_path(&*terrain,
+                                                         &*occupied_tiles,
+                                                         &position,
+                                                         &target.position,
+                                                         unit_info.terrain_restriction);
+                        let path = combat::path_into_range(&path, &target.position, reach);
+                        reachable = path.last()
+                            .map(|end| combat::ground_distance(end, &target.position) <= reach)
+                            .unwrap_or(false);
+                        detour.push(Action::MoveToPosition(MoveToPositionParams::new(path)));
+                    }
+
+                    // Enemies that can't be got at (across the water) are left alone
+                    if reachable {
+                        detour.push(Action::AttackUnit(AttackUnitParams::new(target.entity_id)));
+                        detour.push(Action::AttackMove(AttackMoveParams::new(attack_move.path.clone())));
+                        graphic.set_graphic(unit_info.standing_graphic);
+                        velocity.velocity = Vector3::new(0.into(), 0.into(), 0.into());
+                        action_queue.interrupt(detour);
+                        continue;
+                    }
+                }
+            }
+
+            let done = follow_path(unit_info,
+                                   carries.get(entity),
+                                   &mut attack_move.path,
+                                   transform,
+                                   velocity,
+                                   graphic,
+                                   &*terrain,
+                                   time_step);
+            if done {
+                action_queue.mark_current_done();
+            }
+        }
+    }
+}
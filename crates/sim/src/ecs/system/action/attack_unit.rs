@@ -0,0 +1,180 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat::EmpiresDbRef;
+use ecs::component::{ActionQueueComponent, AttackUnitActionComponent, GarrisonedComponent, HealthComponent,
+                     TransformComponent, UnitComponent};
+use ecs::event::Blast;
+use ecs::resource::Events;
+use identifier::PlayerId;
+use specs::{self, Index, Join};
+use std::collections::{HashMap, HashSet};
+use super::super::{System, SystemAccess};
+use types::{Fixed, Vector3};
+use util::{combat, garrison};
+
+/// How far past their collision boxes melee units can reach
+const MELEE_REACH: Fixed = fixed_const!(0.25);
+
+struct Target {
+    position: Vector3,
+    size: Fixed,
+    armors: Vec<(i16, i16)>,
+}
+
+struct Hit {
+    target_id: Index,
+    attacker_id: PlayerId,
+    attacker_entity_id: Index,
+    damage: Fixed,
+}
+
+/// Has units attack the unit they were told to attack until it dies. Units attack while the
+/// target is in range; the selection system sends them into range first.
+///
+/// Units with a blast width (war elephants and catapults) also hurt everything around their
+/// target, through a `Blast`. Buildings fire an extra arrow for each unit garrisoned inside.
+pub struct AttackUnitActionSystem {
+    empires: EmpiresDbRef,
+}
+
+impl AttackUnitActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> AttackUnitActionSystem {
+        AttackUnitActionSystem { empires: empires }
+    }
+}
+
+impl System for AttackUnitActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<GarrisonedComponent>()
+            .write::<ActionQueueComponent>()
+            .write::<AttackUnitActionComponent>()
+            .write::<HealthComponent>()
+            .write::<TransformComponent>()
+            .write::<Events<Blast>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            components(garrisoneds: GarrisonedComponent),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(attack_units: AttackUnitActionComponent),
+            mut components(healths: HealthComponent),
+            mut components(transforms: TransformComponent),
+            mut resource(blasts: Events<Blast>),
+        ]);
+
+        let mut occupants: HashMap<Index, usize> = HashMap::new();
+        for garrisoned in (&garrisoneds).iter() {
+            *occupants.entry(garrisoned.building_id).or_insert(0) += 1;
+        }
+
+        // Only the units that are being attacked need to be looked up
+        let target_ids: HashSet<Index> = (&attack_units).iter().map(|attack| attack.target_id).collect();
+        if target_ids.is_empty() {
+            return;
+        }
+
+        let mut targets = HashMap::new();
+        for (entity, unit, transform, health) in (&entities, &units, &transforms, &healths).iter() {
+            // Units inside buildings can't be attacked
+            if !target_ids.contains(&entity.get_id()) || health.is_dead() ||
+               garrisoneds.get(entity).is_some() {
+                continue;
+            }
+            let unit_info = unit.db(&self.empires);
+            targets.insert(entity.get_id(),
+                           Target {
+                               position: *transform.position(),
+                               size: unit_info.collision_size_x.max(unit_info.collision_size_y).into(),
+                               armors: combat::armors(unit_info).to_vec(),
+                           });
+        }
+
+        let mut hits = Vec::new();
+        for (entity, unit, attack, action_queue, transform) in
+            (&entities, &units, &mut attack_units, &mut action_queues, &mut transforms).iter() {
+            let unit_info = unit.db(&self.empires);
+            let (params, target) = match (unit_info.battle_params.as_ref(), targets.get(&attack.target_id)) {
+                (Some(params), Some(target)) => (params, target),
+                _ => {
+                    action_queue.mark_current_done();
+                    continue;
+                }
+            };
+
+            // TODO: Follow targets that move off instead of giving up on them
+            let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+            let gap = combat::ground_distance(transform.position(), &target.position) - size - target.size;
+            if gap > Fixed::from(params.max_range) + MELEE_REACH {
+                action_queue.mark_current_done();
+                continue;
+            }
+
+            // Keep facing the target between attacks since it may be moving around
+            transform.face_towards(&target.position);
+            if attack.reload_time_left > 0.into() {
+                attack.reload_time_left -= time_step;
+                continue;
+            }
+
+            // TODO: Fly projectiles at targets of ranged units instead of hitting them right away
+            // TODO: Have towers and town centers pick targets on their own; for now they only fire
+            // at what they're told to. (The original game's town centers have no attack at all.)
+            attack.reload_time_left = params.reload_time.into();
+            let arrows = if unit_info.is_building() {
+                garrison::arrows(occupants.get(&entity.get_id()).cloned().unwrap_or(0))
+            } else {
+                1
+            };
+            for _ in 0..arrows {
+                hits.push(Hit {
+                    target_id: attack.target_id,
+                    attacker_id: unit.player_id,
+                    attacker_entity_id: entity.get_id(),
+                    damage: combat::damage(&params.attacks, &target.armors).into(),
+                });
+            }
+            if params.blast_width > 0f32 {
+                blasts.emit(Blast {
+                    center: target.position,
+                    radius: params.blast_width.into(),
+                    attacks: params.attacks.clone(),
+                    attack_level: params.blast_attack_level,
+                    attacker_id: unit.player_id,
+                    primary_target_id: Some(attack.target_id),
+                });
+            }
+        }
+
+        for (entity, health) in (&entities, &mut healths).iter() {
+            for hit in hits.iter().filter(|hit| hit.target_id == entity.get_id()) {
+                health.hit_points = health.hit_points - hit.damage;
+                health.last_attacker = Some(hit.attacker_id);
+                health.last_attacker_id = Some(hit.attacker_entity_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, BuildParams, MoveToPositionParams};
+use dat::EmpiresDbRef;
+use ecs::component::{ActionQueueComponent, BuildActionComponent, ConstructionComponent, GraphicComponent,
+                     HealthComponent, TransformComponent, UnitComponent};
+use ecs::event::BuildingCompleted;
+use ecs::resource::{Events, OccupiedTiles, PathFinder, SpawnQueue, Terrain};
+use identifier::{PlayerId, UnitId};
+use specs::{self, Index, Join};
+use std::cmp;
+use std::collections::BTreeMap;
+use super::super::{System, SystemAccess};
+use types::{Fixed, Vector3};
+use util::combat;
+
+/// How far (between the edges of the two) a villager can be from the building it's working on
+const BUILD_REACH: Fixed = fixed_const!(1);
+
+/// Villagers walk a bit closer than they need to, since they stop near (not on) path nodes
+const APPROACH_SLACK: Fixed = fixed_const!(0.5);
+
+struct Foundation {
+    entity: specs::Entity,
+    player_id: PlayerId,
+    unit_id: UnitId,
+    position: Vector3,
+    size: Fixed,
+
+    /// Seconds of work it takes to build
+    build_time: Fixed,
+}
+
+/// Walks villagers up to the foundations they were sent to and has them build. Every villager
+/// working on a building adds their own work to it, so more villagers finish it sooner. The
+/// building's hit points go up as it's built, and once it's done it starts working like any
+/// other building.
+pub struct BuildActionSystem {
+    empires: EmpiresDbRef,
+}
+
+impl BuildActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> BuildActionSystem {
+        BuildActionSystem { empires: empires }
+    }
+}
+
+impl System for BuildActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<PathFinder>()
+            .read::<OccupiedTiles>()
+            .read::<Terrain>()
+            .read::<SpawnQueue>()
+            .write::<ActionQueueComponent>()
+            .write::<BuildActionComponent>()
+            .write::<ConstructionComponent>()
+            .write::<GraphicComponent>()
+            .write::<HealthComponent>()
+            .write::<TransformComponent>()
+            .write::<Events<BuildingCompleted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(builds: BuildActionComponent),
+            mut components(constructions: ConstructionComponent),
+            mut components(graphics: GraphicComponent),
+            mut components(healths: HealthComponent),
+            mut components(transforms: TransformComponent),
+            resource(path_finder: PathFinder),
+            resource(occupied_tiles: OccupiedTiles),
+            resource(terrain: Terrain),
+            resource(spawn_queue: SpawnQueue),
+            mut resource(building_completed: Events<BuildingCompleted>),
+        ]);
+
+        let mut foundations = BTreeMap::new();
+        for (entity, unit, transform, _, health) in
+            (&entities, &units, &transforms, &constructions, &healths).iter() {
+            if health.is_dead() {
+                continue;
+            }
+            let unit_info = unit.db(&self.empires);
+            foundations.insert(entity.get_id(),
+                               Foundation {
+                                   entity: entity,
+                                   player_id: unit.player_id,
+                                   unit_id: unit.unit_id,
+                                   position: *transform.position(),
+                                   size: unit_info.collision_size_x.max(unit_info.collision_size_y).into(),
+                                   build_time: unit_info.train_time().unwrap_or(0).into(),
+                               });
+        }
+
+        let mut finished: Vec<Index> = Vec::new();
+        for (unit, build_action, action_queue, graphic, transform) in
+            (&units, &mut builds, &mut action_queues, &mut graphics, &mut transforms).iter() {
+            let unit_info = unit.db(&self.empires);
+            let position = *transform.position();
+            let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+
+            if build_action.foundation_id.is_none() {
+                build_action.foundation_id = foundations.iter()
+                    .find(|&(_, foundation)| {
+                        foundation.player_id == unit.player_id && foundation.position == build_action.position
+                    })
+                    .map(|(&entity_id, _)| entity_id);
+            }
+            let foundation = build_action.foundation_id.and_then(|entity_id| foundations.get(&entity_id));
+            let foundation = match foundation {
+                Some(foundation) => foundation,
+                None => {
+                    // A foundation laid this tick goes up before the next one
+                    if build_action.foundation_id.is_none() &&
+                       spawn_queue.has_foundation(unit.player_id, &build_action.position) {
+                        continue;
+                    }
+                    // It's finished or destroyed
+                    graphic.set_graphic(unit_info.standing_graphic);
+                    action_queue.mark_current_done();
+                    continue;
+                }
+            };
+
+            let gap = combat::ground_distance(&position, &foundation.position) - size - foundation.size;
+            if gap > BUILD_REACH {
+                let direct = path_finder.find_path(&*terrain,
+                                                   &*occupied_tiles,
+                                                   &position,
+                                                   &foundation.position,
+                                                   unit_info.terrain_restriction);
+                let range = size + foundation.size + BUILD_REACH - APPROACH_SLACK;
+                let path = combat::path_into_range(&direct, &foundation.position, range);
+                let end = path.last().cloned().unwrap_or(position);
+                let end_gap = combat::ground_distance(&end, &foundation.position) - size - foundation.size;
+                if end_gap > BUILD_REACH {
+                    // It can't get to the foundation (it's walled in, or across the water)
+                    graphic.set_graphic(unit_info.standing_graphic);
+                    action_queue.mark_current_done();
+                } else {
+                    let params = BuildParams::new(build_action.building_id, build_action.position);
+                    action_queue.interrupt(vec![
+                        Action::MoveToPosition(MoveToPositionParams::new(path)),
+                        Action::Build(params),
+                    ]);
+                }
+                continue;
+            }
+
+            transform.face_towards(&foundation.position);
+            let action_graphic_id = unit_info.build_command()
+                .and_then(|command| command.action_graphic_id());
+            if action_graphic_id.is_some() && graphic.graphic_id != action_graphic_id {
+                graphic.set_graphic(action_graphic_id);
+            }
+
+            // Hit points come in step with the work, on top of whatever damage it's taken so far
+            let construction = constructions.get_mut(foundation.entity).unwrap();
+            let health = healths.get_mut(foundation.entity).unwrap();
+            if foundation.build_time > 0.into() {
+                let remaining = cmp::max(foundation.build_time - construction.progress, 0.into());
+                let work = cmp::min(time_step, remaining);
+                let gained = health.max_hit_points * work / foundation.build_time;
+                health.hit_points = cmp::min(health.max_hit_points, health.hit_points + gained);
+            }
+            construction.progress += time_step;
+            let foundation_id = foundation.entity.get_id();
+            if construction.progress >= foundation.build_time && !finished.contains(&foundation_id) {
+                finished.push(foundation_id);
+            }
+        }
+
+        for entity_id in finished {
+            let foundation = &foundations[&entity_id];
+            constructions.remove(foundation.entity);
+            if let Some(graphic) = graphics.get_mut(foundation.entity) {
+                let unit_info = units.get(foundation.entity).unwrap().db(&self.empires);
+                graphic.set_graphic(unit_info.standing_graphic);
+            }
+            building_completed.emit(BuildingCompleted {
+                entity_id: entity_id,
+                player_id: foundation.player_id,
+                unit_id: foundation.unit_id,
+            });
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::EmpiresDbRef;
+use ecs::component::{ActionQueueComponent, GarrisonActionComponent, GarrisonedComponent, HealthComponent,
+                     SelectedUnitComponent, TransformComponent, UnitComponent, VelocityComponent};
+use identifier::PlayerId;
+use partition::GridPartition;
+use specs::{self, Index, Join};
+use std::collections::HashMap;
+use super::super::{System, SystemAccess};
+use types::{Fixed, Vector3};
+use util::{combat, garrison};
+
+/// How far past their collision boxes units can get into a building from
+const GARRISON_REACH: Fixed = fixed_const!(1.5);
+
+struct Shelter {
+    player_id: PlayerId,
+    position: Vector3,
+    size: Fixed,
+    capacity: usize,
+}
+
+/// Puts units that have walked up to a building inside it, and lets them back out when they're
+/// told to or the building is destroyed. Units that come out carry on with whatever the town bell
+/// interrupted.
+pub struct GarrisonActionSystem {
+    empires: EmpiresDbRef,
+}
+
+impl GarrisonActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> GarrisonActionSystem {
+        GarrisonActionSystem { empires: empires }
+    }
+}
+
+impl System for GarrisonActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<HealthComponent>()
+            .read::<TransformComponent>()
+            .write::<ActionQueueComponent>()
+            .write::<GarrisonActionComponent>()
+            .write::<GarrisonedComponent>()
+            .write::<SelectedUnitComponent>()
+            .write::<VelocityComponent>()
+            .write::<GridPartition>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            components(healths: HealthComponent),
+            components(transforms: TransformComponent),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(garrisons: GarrisonActionComponent),
+            mut components(garrisoneds: GarrisonedComponent),
+            mut components(selected_units: SelectedUnitComponent),
+            mut components(velocities: VelocityComponent),
+            mut resource(grid: GridPartition),
+        ]);
+
+        let mut shelters = HashMap::new();
+        for (entity, unit, transform, health) in (&entities, &units, &transforms, &healths).iter() {
+            let unit_info = unit.db(&self.empires);
+            let capacity = garrison::capacity(unit_info);
+            if capacity > 0 && !health.is_dead() {
+                shelters.insert(entity.get_id(),
+                                Shelter {
+                                    player_id: unit.player_id,
+                                    position: *transform.position(),
+                                    size: unit_info.collision_size_x.max(unit_info.collision_size_y).into(),
+                                    capacity: capacity,
+                                });
+            }
+        }
+
+        let mut occupants: HashMap<Index, usize> = HashMap::new();
+        let mut leaving = Vec::new();
+        for (entity, garrisoned) in (&entities, &garrisoneds).iter() {
+            if garrisoned.leaving || !shelters.contains_key(&garrisoned.building_id) {
+                leaving.push(entity);
+            } else {
+                *occupants.entry(garrisoned.building_id).or_insert(0) += 1;
+            }
+        }
+
+        let mut entering = Vec::new();
+        for (entity, unit, action, action_queue, transform) in
+            (&entities, &units, &garrisons, &mut action_queues, &transforms).iter() {
+            action_queue.mark_current_done();
+            let shelter = match shelters.get(&action.building_id) {
+                Some(shelter) if shelter.player_id == unit.player_id => shelter,
+                _ => continue,
+            };
+            let count = occupants.get(&action.building_id).cloned().unwrap_or(0);
+            if count >= shelter.capacity {
+                continue;
+            }
+
+            let unit_info = unit.db(&self.empires);
+            let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+            let gap = combat::ground_distance(transform.position(), &shelter.position) - size - shelter.size;
+            if gap > GARRISON_REACH {
+                continue;
+            }
+
+            // Anything else the unit was told to do afterwards is dropped, since it's inside now
+            action_queue.clear();
+            occupants.insert(action.building_id, count + 1);
+            entering.push((entity,
+                           GarrisonedComponent::new(action.building_id,
+                                                    *transform.position(),
+                                                    action.town_bell)));
+        }
+
+        for entity in leaving {
+            garrisoneds.remove(entity);
+            velocities.insert(entity, VelocityComponent::new());
+            if let Some(action_queue) = action_queues.get_mut(entity) {
+                action_queue.resume();
+            }
+        }
+
+        // The velocity system puts units back into the grid once they have a velocity again
+        for (entity, garrisoned) in entering {
+            garrisoneds.insert(entity, garrisoned);
+            velocities.remove(entity);
+            selected_units.remove(entity);
+            grid.remove_entity(entity.get_id());
+        }
+    }
+}
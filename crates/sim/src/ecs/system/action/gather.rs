@@ -0,0 +1,479 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, GatherParams, MoveToPositionParams, Path};
+use dat::{EmpiresDbRef, ResourceType};
+use ecs::UnitMorph;
+use ecs::component::{ActionQueueComponent, CarryComponent, ConstructionComponent, DecayComponent,
+                     GatherActionComponent, GraphicComponent, HealthComponent, ResourceComponent,
+                     TransformComponent, UnitComponent};
+use ecs::event::ResourceDepleted;
+use ecs::resource::{Events, HostileTiles, OccupiedTiles, PathFinder, PlayerStats, Players, SpawnQueue,
+                    Stockpiles, Terrain};
+use identifier::{CivilizationId, PlayerId, UnitId, UnitTerrainRestrictionId};
+use specs::{self, Index, Join};
+use std::collections::BTreeMap;
+use super::super::{System, SystemAccess};
+use types::{Fixed, Vector3};
+use util::{combat, gather};
+
+/// How far (between the edges of the two) a gatherer can be from what it's working on.
+/// This is enough for a villager on the shore to reach fish in the water next to it.
+const GATHER_REACH: Fixed = fixed_const!(1);
+const DROP_OFF_REACH: Fixed = fixed_const!(1);
+
+/// Gatherers walk a bit closer than they need to, since they stop near (not on) path nodes
+const APPROACH_SLACK: Fixed = fixed_const!(0.5);
+
+/// How far a gatherer looks for more of the same once what it was working on runs out
+const SEARCH_RADIUS: Fixed = fixed_const!(8);
+
+/// Resources in the same square of this many tiles share their ranking of the drop sites
+const CLUSTER_SIZE: i32 = 8;
+
+/// How far around an enemy's buildings gatherers keep out of on the way to drop off, if they can
+const HOSTILE_RADIUS: i32 = 6;
+
+struct Source {
+    entity: specs::Entity,
+    civilization_id: CivilizationId,
+    unit_id: UnitId,
+    resource_type: ResourceType,
+    rots: bool,
+    position: Vector3,
+    size: Fixed,
+}
+
+struct DropSite {
+    entity_id: Index,
+    player_id: PlayerId,
+    unit_id: UnitId,
+    position: Vector3,
+    size: Fixed,
+}
+
+/// Drop sites are ranked for a kind of gatherer, of one player, working in one cluster
+type ClusterKey = (PlayerId, UnitId, (i32, i32));
+
+pub struct GatherActionSystem {
+    empires: EmpiresDbRef,
+
+    /// Drop sites, nearest first, by how far they are to walk to from the middle of the cluster.
+    /// These only count the terrain (not units), so every peer ranks them the same way no matter
+    /// when it joined, and they're thrown out whenever a building is finished, lost, or changes hands.
+    drop_site_rankings: BTreeMap<ClusterKey, Vec<Index>>,
+    buildings: Vec<(Index, PlayerId)>,
+}
+
+impl GatherActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> GatherActionSystem {
+        GatherActionSystem {
+            empires: empires,
+            drop_site_rankings: BTreeMap::new(),
+            buildings: Vec::new(),
+        }
+    }
+}
+
+/// The drop sites that can be walked to from `from`, nearest first (ties go to the lower entity ID)
+fn rank_drop_sites(path_finder: &PathFinder,
+                   terrain: &Terrain,
+                   occupied_tiles: &OccupiedTiles,
+                   from: &Vector3,
+                   sites: &[&DropSite],
+                   restriction_id: UnitTerrainRestrictionId,
+                   hostile_tiles: &HostileTiles)
+                   -> Vec<Index> {
+    let mut ranked: Vec<(i32, Index)> = Vec::new();
+    for site in sites {
+        let reach = i32::from(site.size) + 1;
+        let distance = path_finder.path_distance(terrain,
+                                                 occupied_tiles,
+                                                 from,
+                                                 &site.position,
+                                                 reach,
+                                                 restriction_id,
+                                                 hostile_tiles);
+        if let Some(distance) = distance {
+            ranked.push((distance, site.entity_id));
+        }
+    }
+    ranked.sort();
+    ranked.into_iter().map(|(_, entity_id)| entity_id).collect()
+}
+
+/// The way to the first of the ranked drop sites that a gatherer of the given size can get close
+/// enough to to drop off at (units can be in the way of the one that ranks best)
+fn approach_drop_site(path_finder: &PathFinder,
+                      terrain: &Terrain,
+                      occupied_tiles: &OccupiedTiles,
+                      from: &Vector3,
+                      size: Fixed,
+                      ranking: &[Index],
+                      sites: &[&DropSite],
+                      restriction_id: UnitTerrainRestrictionId,
+                      hostile_tiles: &HostileTiles)
+                      -> Option<Path> {
+    for &entity_id in ranking {
+        let site = match sites.iter().find(|site| site.entity_id == entity_id) {
+            Some(site) => site,
+            None => continue,
+        };
+        let path = path_finder.find_path_avoiding(terrain,
+                                                  occupied_tiles,
+                                                  from,
+                                                  &site.position,
+                                                  restriction_id,
+                                                  hostile_tiles);
+        let range = size + site.size + DROP_OFF_REACH - APPROACH_SLACK;
+        let path = combat::path_into_range(&path, &site.position, range);
+        let end = path.last().cloned().unwrap_or(*from);
+        if combat::ground_distance(&end, &site.position) - size - site.size <= DROP_OFF_REACH {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// The tiles near the buildings of the player's enemies
+fn hostile_tiles(players: &Players, player_id: PlayerId, drop_sites: &[DropSite]) -> HostileTiles {
+    let mut hostile_tiles = HostileTiles::new();
+    for site in drop_sites.iter().filter(|site| players.are_enemies(player_id, site.player_id)) {
+        hostile_tiles.add_around(&site.position, i32::from(site.size) + HOSTILE_RADIUS);
+    }
+    hostile_tiles
+}
+
+impl System for GatherActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<HealthComponent>()
+            .read::<ConstructionComponent>()
+            .read::<PathFinder>()
+            .read::<OccupiedTiles>()
+            .read::<Players>()
+            .read::<Terrain>()
+            .write::<ActionQueueComponent>()
+            .write::<CarryComponent>()
+            .write::<DecayComponent>()
+            .write::<GatherActionComponent>()
+            .write::<GraphicComponent>()
+            .write::<ResourceComponent>()
+            .write::<TransformComponent>()
+            .write::<PlayerStats>()
+            .write::<SpawnQueue>()
+            .write::<Stockpiles>()
+            .write::<Events<ResourceDepleted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            components(healths: HealthComponent),
+            components(constructions: ConstructionComponent),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(carries: CarryComponent),
+            mut components(decays: DecayComponent),
+            mut components(gathers: GatherActionComponent),
+            mut components(graphics: GraphicComponent),
+            mut components(resources: ResourceComponent),
+            mut components(transforms: TransformComponent),
+            resource(path_finder: PathFinder),
+            resource(occupied_tiles: OccupiedTiles),
+            resource(players: Players),
+            resource(terrain: Terrain),
+            mut resource(player_stats: PlayerStats),
+            mut resource(spawn_queue: SpawnQueue),
+            mut resource(stockpiles: Stockpiles),
+            mut resource(resource_depleted: Events<ResourceDepleted>),
+        ]);
+
+        // Ordered by entity so that every peer breaks ties between resources the same way
+        let mut sources = BTreeMap::new();
+        for (entity, unit, transform, resource) in (&entities, &units, &transforms, &resources).iter() {
+            if resource.amount <= 0.into() {
+                continue;
+            }
+            let unit_info = unit.db(&self.empires);
+            sources.insert(entity.get_id(),
+                           Source {
+                               entity: entity,
+                               civilization_id: unit.civilization_id,
+                               unit_id: unit.unit_id,
+                               resource_type: resource.resource_type,
+                               rots: resource.rot_rate > 0.into(),
+                               position: *transform.position(),
+                               size: unit_info.collision_size_x.max(unit_info.collision_size_y).into(),
+                           });
+        }
+
+        // Foundations don't take anything in until they're finished
+        let mut drop_sites = Vec::new();
+        for (entity, unit, transform, health) in (&entities, &units, &transforms, &healths).iter() {
+            let unit_info = unit.db(&self.empires);
+            if unit_info.is_building() && !health.is_dead() && constructions.get(entity).is_none() {
+                drop_sites.push(DropSite {
+                    entity_id: entity.get_id(),
+                    player_id: unit.player_id,
+                    unit_id: unit.unit_id,
+                    position: *transform.position(),
+                    size: unit_info.collision_size_x.max(unit_info.collision_size_y).into(),
+                });
+            }
+        }
+
+        let buildings: Vec<(Index, PlayerId)> = drop_sites.iter()
+            .map(|site| (site.entity_id, site.player_id))
+            .collect();
+        if buildings != self.buildings {
+            self.drop_site_rankings.clear();
+            self.buildings = buildings;
+        }
+
+        // Gatherers sharing a resource each get their own spot around it, in entity order
+        let mut sharing: BTreeMap<Index, Vec<Index>> = BTreeMap::new();
+        for (entity, gather_action) in (&entities, &gathers).iter() {
+            sharing.entry(gather_action.target_id).or_insert_with(Vec::new).push(entity.get_id());
+        }
+
+        for (entity, unit, gather_action, action_queue, graphic, transform) in
+            (&entities, &units, &mut gathers, &mut action_queues, &mut graphics, &mut transforms).iter() {
+            let unit_info = unit.db(&self.empires);
+            let civilization = self.empires.civilization(unit.civilization_id);
+            let position = *transform.position();
+            let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+
+            let capacity: Fixed = unit_info.resource_capacity().into();
+            let carried = carries.get(entity).map(|carry| carry.amount).unwrap_or(0.into());
+
+            // Once a resource runs out, gatherers move on to the closest one like it that they can
+            // work on, the way villagers go from one tree to the next. Hunters take their meat home
+            // first instead, since the next animal could be a long way off.
+            let carrying_meat = gather_action.perishable && carried > 0.into();
+            if !sources.contains_key(&gather_action.target_id) && !carrying_meat {
+                let resource_type = gather_action.resource_type
+                    .or_else(|| carries.get(entity).map(|carry| carry.resource_type));
+                let nearby: Vec<(Index, Vector3)> = sources.iter()
+                    .filter(|&(_, source)| {
+                        let source_info = self.empires.unit(source.civilization_id, source.unit_id);
+                        Some(source.resource_type) == resource_type &&
+                        combat::ground_distance(&position, &source.position) <= SEARCH_RADIUS &&
+                        civilization.gatherer_for(unit_info, source_info).is_some()
+                    })
+                    .map(|(&entity_id, source)| (entity_id, source.position))
+                    .collect();
+                if let Some(entity_id) = gather::closest(&position, &nearby) {
+                    gather_action.target_id = entity_id;
+                }
+            }
+            let target_id = gather_action.target_id;
+            let target = sources.get(&target_id);
+            let full = carried > 0.into() && (carried >= capacity || target.is_none());
+            if full {
+                let carry = carries.get(entity).unwrap().clone();
+                let drop_site_ids = unit_info.drop_site_ids();
+                let own_sites: Vec<&DropSite> = drop_sites.iter()
+                    .filter(|site| site.player_id == unit.player_id && drop_site_ids.contains(&site.unit_id))
+                    .collect();
+
+                let in_reach = own_sites.iter().any(|site| {
+                    combat::ground_distance(&position, &site.position) - size - site.size <= DROP_OFF_REACH
+                });
+                if in_reach {
+                    stockpiles.add(unit.player_id, carry.resource_type, carry.amount);
+                    player_stats.record_gathered(unit.player_id, u32::from(carry.amount));
+                    carries.get_mut(entity).unwrap().amount = 0.into();
+                    graphic.set_graphic(unit_info.standing_graphic);
+                    continue;
+                }
+
+                // Head for the drop site that's the shortest walk away, not the closest as the
+                // crow flies, since the closest dock could be across the water. Walks through
+                // enemy territory only count if there's no safer way.
+                let hostile_tiles = hostile_tiles(&*players, unit.player_id, &drop_sites);
+                let origin = target.map(|target| target.position).unwrap_or(position);
+                let cluster = (i32::from(origin.x) / CLUSTER_SIZE, i32::from(origin.y) / CLUSTER_SIZE);
+                let key = (unit.player_id, unit.unit_id, cluster);
+                if !self.drop_site_rankings.contains_key(&key) {
+                    let center = Vector3::new(Fixed::from(cluster.0 * CLUSTER_SIZE + CLUSTER_SIZE / 2),
+                                              Fixed::from(cluster.1 * CLUSTER_SIZE + CLUSTER_SIZE / 2),
+                                              origin.z);
+                    let ranking = rank_drop_sites(&*path_finder,
+                                                  &*terrain,
+                                                  &OccupiedTiles::new(),
+                                                  &center,
+                                                  &own_sites,
+                                                  unit_info.terrain_restriction,
+                                                  &hostile_tiles);
+                    self.drop_site_rankings.insert(key, ranking);
+                }
+                let mut approach = approach_drop_site(&*path_finder,
+                                                      &*terrain,
+                                                      &*occupied_tiles,
+                                                      &position,
+                                                      size,
+                                                      &self.drop_site_rankings[&key],
+                                                      &own_sites,
+                                                      unit_info.terrain_restriction,
+                                                      &hostile_tiles);
+
+                // The gatherer can be cut off from the middle of the cluster (which could be out in
+                // a lake, or across a river), in which case it goes by what it can get to from where it is
+                if approach.is_none() {
+                    let ranking = rank_drop_sites(&*path_finder,
+                                                  &*terrain,
+                                                  &*occupied_tiles,
+                                                  &position,
+                                                  &own_sites,
+                                                  unit_info.terrain_restriction,
+                                                  &hostile_tiles);
+                    approach = approach_drop_site(&*path_finder,
+                                                  &*terrain,
+                                                  &*occupied_tiles,
+                                                  &position,
+                                                  size,
+                                                  &ranking,
+                                                  &own_sites,
+                                                  unit_info.terrain_restriction,
+                                                  &hostile_tiles);
+                }
+
+                match approach {
+                    Some(path) => {
+                        action_queue.interrupt(vec![
+                            Action::MoveToPosition(MoveToPositionParams::new(path)),
+                            Action::Gather(GatherParams::new(target_id)),
+                        ]);
+                    }
+                    None => {
+                        // TODO: Let the player know that there's nowhere to take it
+                        graphic.set_graphic(unit_info.standing_graphic);
+                        action_queue.mark_current_done();
+                    }
+                }
+                continue;
+            }
+
+            let (target, target_info) = match target {
+                Some(target) => (target, self.empires.unit(target.civilization_id, target.unit_id)),
+                None => {
+                    graphic.set_graphic(unit_info.standing_graphic);
+                    action_queue.mark_current_done();
+                    continue;
+                }
+            };
+            gather_action.resource_type = Some(target.resource_type);
+            gather_action.perishable = target.rots;
+
+            // Villagers become whatever the task calls for (a fisherman for fish) before they start
+            let gatherer = match civilization.gatherer_for(unit_info, target_info) {
+                Some(gatherer) => gatherer,
+                None => {
+                    action_queue.mark_current_done();
+                    continue;
+                }
+            };
+            if gatherer.id != unit.unit_id {
+                spawn_queue.queue_morph(UnitMorph {
+                    entity_id: entity.get_id(),
+                    unit_id: gatherer.id,
+                });
+                continue;
+            }
+            let command = unit_info.gather_command(target_info).unwrap();
+            let resource_type = command.resource_type();
+
+            let gap = combat::ground_distance(&position, &target.position) - size - target.size;
+            if gap > GATHER_REACH {
+                let range = size + target.size + GATHER_REACH - APPROACH_SLACK;
+                let reaches = |path: &Vec<Vector3>| {
+                    let end = path.last().cloned().unwrap_or(position);
+                    combat::ground_distance(&end, &target.position) - size - target.size <= GATHER_REACH
+                };
+
+                // Head for this gatherer's own spot if others are working on it too, and
+                // otherwise (or if the spot can't be reached) for the closest side
+                let mut path = Vec::new();
+                let gatherers = sharing.get(&target_id).map(|ids| ids.as_slice()).unwrap_or(&[]);
+                if gatherers.len() > 1 {
+                    let slot = gatherers.iter().position(|&id| id == entity.get_id()).unwrap_or(0);
+                    let spot = gather::gather_spot(&target.position, range, slot);
+                    path = path_finder.find_path(&*terrain,
+                                                 &*occupied_tiles,
+                                                 &position,
+                                                 &spot,
+                                                 unit_info.terrain_restriction);
+                }
+                if !reaches(&path) {
+                    let direct = path_finder.find_path(&*terrain,
+                                                       &*occupied_tiles,
+                                                       &position,
+                                                       &target.position,
+                                                       unit_info.terrain_restriction);
+                    path = combat::path_into_range(&direct, &target.position, range);
+                }
+                if !reaches(&path) {
+                    // It can't get any closer (fish too far from the shore, a tree walled in)
+                    graphic.set_graphic(unit_info.standing_graphic);
+                    action_queue.mark_current_done();
+                } else {
+                    action_queue.interrupt(vec![
+                        Action::MoveToPosition(MoveToPositionParams::new(path)),
+                        Action::Gather(GatherParams::new(target_id)),
+                    ]);
+                }
+                continue;
+            }
+
+            // Switching to a different resource drops whatever was being carried
+            let carrying_other = carries.get(entity).map(|carry| carry.resource_type != resource_type);
+            if carrying_other != Some(false) {
+                carries.insert(entity, CarryComponent::new(resource_type, command.carrying_graphic_id()));
+            }
+
+            transform.face_towards(&target.position);
+            if command.action_graphic_id().is_some() && graphic.graphic_id != command.action_graphic_id() {
+                graphic.set_graphic(command.action_graphic_id());
+            }
+
+            let resource = resources.get_mut(target.entity).unwrap();
+            let carry = carries.get_mut(entity).unwrap();
+            let amount = gather::gather_amount(resource.amount,
+                                               unit_info.work_rate().into(),
+                                               time_step,
+                                               carry.amount,
+                                               capacity);
+            if amount <= 0.into() {
+                continue;
+            }
+            resource.amount -= amount;
+            carry.amount += amount;
+            if resource.amount <= 0.into() {
+                resource_depleted.emit(ResourceDepleted {
+                    entity_id: target_id,
+                    unit_id: target.unit_id,
+                });
+                decays.insert(target.entity, DecayComponent::new(0.into()));
+            }
+        }
+    }
+}
@@ -19,6 +19,18 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+mod attack_ground;
+mod attack_move;
+mod attack_unit;
+mod build;
+mod garrison;
+mod gather;
 mod move_to_position;
 
+pub use self::attack_ground::AttackGroundActionSystem;
+pub use self::attack_move::AttackMoveActionSystem;
+pub use self::attack_unit::AttackUnitActionSystem;
+pub use self::build::BuildActionSystem;
+pub use self::garrison::GarrisonActionSystem;
+pub use self::gather::GatherActionSystem;
 pub use self::move_to_position::MoveToPositionActionSystem;
@@ -0,0 +1,156 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::{self, EmpiresDbRef};
+use ecs::component::{UnitComponent, MoveToPositionActionComponent, TransformComponent, GraphicComponent,
+                     ActionQueueComponent, CarryComponent, VelocityComponent};
+use ecs::resource::Terrain;
+use ecs::resource::path_finder::Path;
+use specs::{self, Join};
+use super::super::{System, SystemAccess};
+use types::{Fixed, Norm, Vector3};
+use util::movement;
+
+const THRESHOLD: Fixed = fixed_const!(0.1);
+
+pub struct MoveToPositionActionSystem {
+    empires: EmpiresDbRef,
+}
+
+impl MoveToPositionActionSystem {
+    pub fn new(empires: EmpiresDbRef) -> MoveToPositionActionSystem {
+        MoveToPositionActionSystem { empires: empires }
+    }
+}
+
+impl System for MoveToPositionActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<CarryComponent>()
+            .read::<Terrain>()
+            .write::<TransformComponent>()
+            .write::<MoveToPositionActionComponent>()
+            .write::<VelocityComponent>()
+            .write::<GraphicComponent>()
+            .write::<ActionQueueComponent>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            components(carries: CarryComponent),
+            mut components(transforms: TransformComponent),
+            mut components(mtps: MoveToPositionActionComponent),
+            mut components(velocities: VelocityComponent),
+            mut components(graphics: GraphicComponent),
+            mut components(action_queues: ActionQueueComponent),
+            resource(terrain: Terrain),
+        ]);
+
+        let items = (&entities,
+                     &mut velocities,
+                     &mut transforms,
+                     &units,
+                     &mut graphics,
+                     &mut mtps,
+                     &mut action_queues);
+        for (entity, velocity, transform, unit, graphic, mtps, action_queue) in items.iter() {
+            let unit_info = unit.db(&self.empires);
+            let done = follow_path(unit_info,
+                                   carries.get(entity),
+                                   &mut mtps.path,
+                                   transform,
+                                   velocity,
+                                   graphic,
+                                   &*terrain,
+                                   time_step);
+            if done {
+                action_queue.mark_current_done();
+            }
+        }
+    }
+}
+
+/// Steps a unit along its path, dropping nodes as it reaches them. Returns true (with the unit
+/// standing still) once it's at the end, or if it can't move at all.
+pub fn follow_path(unit_info: &dat::Unit,
+                   carry: Option<&CarryComponent>,
+                   path: &mut Path,
+                   transform: &mut TransformComponent,
+                   velocity: &mut VelocityComponent,
+                   graphic: &mut GraphicComponent,
+                   terrain: &Terrain,
+                   time_step: Fixed)
+                   -> bool {
+    let done = if path.is_empty() {
+        true
+    } else {
+        let target = *path.first().unwrap();
+        let mut direction = target - *transform.position();
+        let distance = direction.normalize();
+
+        if distance <= THRESHOLD {
+            path.remove(0);
+            path.is_empty()
+        } else {
+            match unit_info.motion_params {
+                Some(ref params) => {
+                    // Gatherers walk around with what they're carrying
+                    let walking_graphic = match carry {
+                        Some(carry) if carry.amount > 0.into() && carry.graphic_id.is_some() => {
+                            carry.graphic_id
+                        }
+                        _ => params.walking_graphics[0],
+                    };
+                    if walking_graphic.is_some() && graphic.graphic_id != walking_graphic {
+                        graphic.set_graphic(walking_graphic)
+                    }
+                    let terrain_id = terrain.tile_at(*transform.position()).terrain_id;
+                    let speed = Fixed::from(params.speed) * movement::terrain_speed_multiplier(terrain_id);
+                    match movement::handling(unit_info) {
+                        Some(handling) => {
+                            let desired_heading = Fixed::atan2(direction.y, direction.x);
+                            let (heading, speed) = movement::steer(&handling,
+                                                                   transform.rotation,
+                                                                   velocity.velocity.length(),
+                                                                   desired_heading,
+                                                                   speed,
+                                                                   time_step);
+                            let (sin, cos) = heading.sin_cos();
+                            transform.rotation = heading;
+                            velocity.velocity = Vector3::new(cos * speed, sin * speed, 0.into());
+                        }
+                        None => velocity.velocity = direction * speed,
+                    }
+                    false
+                }
+                None => true,
+            }
+        }
+    };
+
+    if done {
+        graphic.set_graphic(unit_info.standing_graphic);
+        velocity.velocity = Vector3::new(0.into(), 0.into(), 0.into());
+    }
+    done
+}
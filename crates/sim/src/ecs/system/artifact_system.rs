@@ -0,0 +1,147 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat;
+use ecs::{ArtifactComponent, GraphicComponent, HealthComponent, TransformComponent, UnitComponent};
+use ecs::event::UnitConverted;
+use ecs::resource::Events;
+use identifier::PlayerId;
+use nalgebra::Vector2;
+use partition::GridPartition;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::{Fixed, ToPrimitive};
+use util::{self, combat};
+
+/// How long a player's units have to stand next to an artifact to take it over
+const CAPTURE_SECONDS: Fixed = fixed_const!(3.0);
+
+/// How close (past both collision boxes) a unit has to be to count as standing next to an artifact
+const CAPTURE_RANGE: Fixed = fixed_const!(0.5);
+
+/// Hands artifacts and ruins over to whichever player's units stand next to them. Units from
+/// more than one player standing next to the same artifact hold up the capture.
+pub struct ArtifactSystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl ArtifactSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> ArtifactSystem {
+        ArtifactSystem { empires: empires }
+    }
+}
+
+impl System for ArtifactSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<HealthComponent>()
+            .write::<ArtifactComponent>()
+            .write::<UnitComponent>()
+            .write::<GraphicComponent>()
+            .read::<GridPartition>()
+            .write::<Events<UnitConverted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(healths: HealthComponent),
+            mut components(artifacts: ArtifactComponent),
+            mut components(units: UnitComponent),
+            mut components(graphics: GraphicComponent),
+            resource(grid: GridPartition),
+            mut resource(conversions: Events<UnitConverted>),
+        ]);
+
+        let mut captured = Vec::new();
+        for (entity, artifact, unit, transform) in (&entities, &mut artifacts, &units, &transforms).iter() {
+            let artifact_info = unit.db(&self.empires);
+            let artifact_size: Fixed =
+                artifact_info.collision_size_x.max(artifact_info.collision_size_y).into();
+            let position = transform.position();
+            let center = Vector2::new(position.x.to_i32().unwrap(), position.y.to_i32().unwrap());
+            let reach = (artifact_size + CAPTURE_RANGE + 2.into()).to_i32().unwrap();
+            let nearby = grid.query_radius(&center, reach);
+
+            let mut capturer: Option<PlayerId> = None;
+            let mut contested = false;
+            for (other, other_unit, other_transform) in (&entities, &units, &transforms).iter() {
+                if other == entity || !nearby.contains(&other.get_id()) || *other_unit.player_id == 0 {
+                    continue;
+                }
+                if healths.get(other).map(|health| health.is_dead()).unwrap_or(false) {
+                    continue;
+                }
+
+                // Only units that can walk over to an artifact can capture it
+                let other_info = other_unit.db(&self.empires);
+                let walks = match other_info.motion_params {
+                    Some(ref params) => params.speed > 0f32,
+                    None => false,
+                };
+                if !walks {
+                    continue;
+                }
+
+                let other_size: Fixed = other_info.collision_size_x.max(other_info.collision_size_y).into();
+                let distance = combat::ground_distance(position, other_transform.position());
+                if distance - artifact_size - other_size > CAPTURE_RANGE {
+                    continue;
+                }
+
+                match capturer {
+                    Some(player_id) if player_id != other_unit.player_id => contested = true,
+                    _ => capturer = Some(other_unit.player_id),
+                }
+            }
+
+            match capturer {
+                Some(player_id) if !contested && player_id != unit.player_id => {
+                    if artifact.capturer != Some(player_id) {
+                        artifact.capturer = Some(player_id);
+                        artifact.capture_time = 0.into();
+                    }
+                    artifact.capture_time += time_step;
+                    if artifact.capture_time >= CAPTURE_SECONDS {
+                        artifact.reset_capture();
+                        captured.push((entity, player_id));
+                    }
+                }
+                // A contested artifact keeps its progress until one side is left standing
+                Some(_) if contested => {}
+                _ => artifact.reset_capture(),
+            }
+        }
+
+        for (entity, player_id) in captured {
+            if let Some(unit) = units.get_mut(entity) {
+                conversions.emit(UnitConverted {
+                    entity_id: entity.get_id(),
+                    from_player_id: unit.player_id,
+                    to_player_id: player_id,
+                });
+                util::unit::change_owner(unit, graphics.get_mut(entity), player_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat;
+use ecs::{HealthComponent, TransformComponent, UnitComponent};
+use ecs::event::Blast;
+use ecs::resource::{EventReader, Events};
+use nalgebra::Vector2;
+use partition::GridPartition;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::{Fixed, ToPrimitive};
+use util::combat;
+
+/// Damages the units caught in blasts (projectile impacts and trample)
+pub struct BlastSystem {
+    empires: dat::EmpiresDbRef,
+    blasts: EventReader,
+}
+
+impl BlastSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> BlastSystem {
+        BlastSystem {
+            empires: empires,
+            blasts: EventReader::new(),
+        }
+    }
+}
+
+impl System for BlastSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .write::<HealthComponent>()
+            .read::<Events<Blast>>()
+            .read::<GridPartition>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(units: UnitComponent),
+            mut components(healths: HealthComponent),
+            resource(blasts: Events<Blast>),
+            resource(grid: GridPartition),
+        ]);
+
+        for blast in blasts.read(&mut self.blasts) {
+            // Grid positions are whole tiles, so look a tile further to catch units on the edge
+            let center = Vector2::new(blast.center.x.to_i32().unwrap(), blast.center.y.to_i32().unwrap());
+            let reach = (blast.radius + 2.into()).to_i32().unwrap();
+            let nearby = grid.query_radius(&center, reach);
+
+            for (entity, unit, transform, health) in (&entities, &units, &transforms, &mut healths).iter() {
+                if !nearby.contains(&entity.get_id()) || health.is_dead() ||
+                   blast.primary_target_id == Some(entity.get_id()) {
+                    continue;
+                }
+
+                let unit_info = unit.db(&self.empires);
+                if !combat::blast_hits(blast.attack_level,
+                                       unit_info.blast_defense_level,
+                                       unit.player_id == blast.attacker_id) {
+                    continue;
+                }
+
+                // The blast hits anything whose collision box it reaches
+                let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+                let distance = combat::ground_distance(&blast.center, transform.position()) - size;
+                if distance > blast.radius {
+                    continue;
+                }
+
+                let damage = combat::damage(&blast.attacks, combat::armors(unit_info));
+                let damage: Fixed = combat::blast_falloff(damage, distance, blast.radius).into();
+                health.hit_points = health.hit_points - damage;
+                health.last_attacker = Some(blast.attacker_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use ecs::{HealthComponent, TrainingQueueComponent, TransformComponent, UnitComponent, UnitSpawn};
+use ecs::event::UnitDied;
+use ecs::resource::{EffectShape, EffectSpawner, Events, PlayerStats, SpawnQueue, Stockpiles};
+use identifier::DrsKey;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// How long the rubble of a razed building stays around
+const RUBBLE_LIFETIME_SECONDS: Fixed = fixed_const!(45.0);
+
+/// The one place where units die, no matter what killed them (combat, deletion, scripts).
+/// Anything that wants a unit dead just takes away its hit points.
+///
+/// Razed buildings collapse, leave rubble behind for a while, and count towards the attacker's
+/// razing statistics. Whatever they had queued is refunded.
+pub struct DeathSystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl DeathSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> DeathSystem {
+        DeathSystem { empires: empires }
+    }
+}
+
+impl System for DeathSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<HealthComponent>()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .write::<TrainingQueueComponent>()
+            .write::<Events<UnitDied>>()
+            .write::<PlayerStats>()
+            .write::<SpawnQueue>()
+            .write::<Stockpiles>()
+            .write::<EffectSpawner>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(healths: HealthComponent),
+            components(units: UnitComponent),
+            components(transforms: TransformComponent),
+            mut components(training_queues: TrainingQueueComponent),
+            mut resource(deaths: Events<UnitDied>),
+            mut resource(stats: PlayerStats),
+            mut resource(spawn_queue: SpawnQueue),
+            mut resource(stockpiles: Stockpiles),
+            mut resource(effects: EffectSpawner),
+        ]);
+
+        for (entity, health, transform, unit) in (&entities, &healths, &transforms, &units).iter() {
+            if !health.is_dead() {
+                continue;
+            }
+
+            let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
+            let building = unit_info.is_building();
+
+            // The TeardownSystem cleans up after the unit once it hears about it
+            stats.record_death(unit.player_id, health.last_attacker, building);
+            deaths.emit(UnitDied {
+                entity_id: entity.get_id(),
+                player_id: unit.player_id,
+                unit_id: unit.unit_id,
+            });
+
+            if let Some(training_queue) = training_queues.get_mut(entity) {
+                stockpiles.refund(unit.player_id, &training_queue.cancel_all());
+            }
+
+            if building {
+                // The collapse plays out on top of the rubble that replaces the building
+                let slp_id = unit_info.dying_graphic.and_then(|id| self.empires.graphic(id).slp_id);
+                if let Some(slp_id) = slp_id {
+                    let shape = EffectShape::new(unit.player_id, DrsKey::Graphics, slp_id);
+                    effects.spawn(shape, *transform.position());
+                }
+            }
+
+            // Leave a corpse (or rubble) behind
+            if let Some(dead_unit_id) = unit_info.dead_unit_id {
+                spawn_queue.queue(UnitSpawn {
+                    player_id: unit.player_id,
+                    civilization_id: unit.civilization_id,
+                    unit_id: dead_unit_id,
+                    position: *transform.position(),
+                    rotation: transform.rotation,
+                    lifetime: if building { Some(RUBBLE_LIFETIME_SECONDS) } else { None },
+                });
+            }
+
+            arg.delete(entity);
+        }
+    }
+}
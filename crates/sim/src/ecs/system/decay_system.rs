@@ -0,0 +1,63 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use ecs::DecayComponent;
+use ecs::resource::OccupiedTiles;
+use partition::GridPartition;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// Removes things that have been around for as long as they last (like rubble)
+pub struct DecaySystem;
+
+impl DecaySystem {
+    pub fn new() -> DecaySystem {
+        DecaySystem
+    }
+}
+
+impl System for DecaySystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .write::<DecayComponent>()
+            .write::<GridPartition>()
+            .write::<OccupiedTiles>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            mut components(decays: DecayComponent),
+            mut resource(grid: GridPartition),
+            mut resource(occupied_tiles: OccupiedTiles),
+        ]);
+
+        for (entity, decay) in (&entities, &mut decays).iter() {
+            decay.time_left -= time_step;
+            if decay.time_left <= 0.into() {
+                occupied_tiles.release(entity.get_id());
+                grid.remove_entity(entity.get_id());
+                arg.delete(entity);
+            }
+        }
+    }
+}
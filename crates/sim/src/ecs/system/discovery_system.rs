@@ -0,0 +1,114 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use ecs::{GraphicComponent, HealthComponent, LineOfSightComponent, TransformComponent, UnitComponent};
+use ecs::event::UnitConverted;
+use ecs::resource::Events;
+use identifier::PlayerId;
+use nalgebra::Vector2;
+use partition::GridPartition;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::{Fixed, ToPrimitive};
+use util::{self, combat};
+
+/// Farthest, in tiles, that any unit can see; bounds the search for units that find a gaia unit
+const MAX_DISCOVERY_RANGE: i32 = 20;
+
+/// Hands gaia units that convert on discovery (abandoned buildings, for example) over to the
+/// first player whose units see them. Wild animals stay with gaia.
+pub struct DiscoverySystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl DiscoverySystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> DiscoverySystem {
+        DiscoverySystem { empires: empires }
+    }
+}
+
+impl System for DiscoverySystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<HealthComponent>()
+            .read::<LineOfSightComponent>()
+            .write::<UnitComponent>()
+            .write::<GraphicComponent>()
+            .read::<GridPartition>()
+            .write::<Events<UnitConverted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(healths: HealthComponent),
+            components(line_of_sights: LineOfSightComponent),
+            mut components(units: UnitComponent),
+            mut components(graphics: GraphicComponent),
+            resource(grid: GridPartition),
+            mut resource(conversions: Events<UnitConverted>),
+        ]);
+
+        let mut discovered = Vec::new();
+        for (entity, unit, transform) in (&entities, &units, &transforms).iter() {
+            if *unit.player_id != 0 || !util::unit::converts_on_discovery(unit.db(&self.empires)) {
+                continue;
+            }
+
+            let position = transform.position();
+            let center = Vector2::new(position.x.to_i32().unwrap(), position.y.to_i32().unwrap());
+            let nearby = grid.query_radius(&center, MAX_DISCOVERY_RANGE);
+
+            // Entities join in ID order, so the same unit wins a tie on every machine
+            let mut discoverer: Option<PlayerId> = None;
+            for (other, other_unit, other_transform, line_of_sight) in
+                (&entities, &units, &transforms, &line_of_sights).iter() {
+                if !nearby.contains(&other.get_id()) || *other_unit.player_id == 0 {
+                    continue;
+                }
+                if healths.get(other).map(|health| health.is_dead()).unwrap_or(false) {
+                    continue;
+                }
+                if combat::ground_distance(position, other_transform.position()) <= line_of_sight.range {
+                    discoverer = Some(other_unit.player_id);
+                    break;
+                }
+            }
+
+            if let Some(player_id) = discoverer {
+                discovered.push((entity, player_id));
+            }
+        }
+
+        for (entity, player_id) in discovered {
+            if let Some(unit) = units.get_mut(entity) {
+                conversions.emit(UnitConverted {
+                    entity_id: entity.get_id(),
+                    from_player_id: unit.player_id,
+                    to_player_id: player_id,
+                });
+                util::unit::change_owner(unit, graphics.get_mut(entity), player_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use ecs::{LineOfSightComponent, TransformComponent, UnitComponent};
+use ecs::resource::Exploration;
+use identifier::PlayerId;
+use specs::{self, Index, Join};
+use std::collections::BTreeMap;
+use super::{System, SystemAccess};
+use types::{Fixed, ToPrimitive};
+
+/// Where a unit was and how far it saw the last time it explored
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Sight {
+    player_id: PlayerId,
+    tile: (i32, i32),
+    radius: i32,
+}
+
+/// Marks the tiles that each player's units see as explored. Units are only looked at again when
+/// they move to another tile or their sight changes. That's only a shortcut: exploring the same
+/// tiles again changes nothing, so the simulation doesn't depend on it.
+pub struct ExplorationSystem {
+    sights: BTreeMap<Index, Sight>,
+}
+
+impl ExplorationSystem {
+    pub fn new() -> ExplorationSystem {
+        ExplorationSystem { sights: BTreeMap::new() }
+    }
+}
+
+impl System for ExplorationSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .read::<LineOfSightComponent>()
+            .write::<Exploration>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(units: UnitComponent),
+            components(line_of_sights: LineOfSightComponent),
+            mut resource(exploration: Exploration),
+        ]);
+
+        // Gaia doesn't explore anything
+        let mut sights = BTreeMap::new();
+        for (entity, transform, unit, line_of_sight) in
+            (&entities, &transforms, &units, &line_of_sights).iter() {
+            if *unit.player_id == 0 {
+                continue;
+            }
+            let position = transform.position();
+            let sight = Sight {
+                player_id: unit.player_id,
+                tile: (position.y.to_i32().unwrap(), position.x.to_i32().unwrap()),
+                radius: line_of_sight.range.to_i32().unwrap(),
+            };
+            if self.sights.get(&entity.get_id()) != Some(&sight) {
+                exploration.explore(sight.player_id, sight.tile.0, sight.tile.1, sight.radius);
+            }
+            sights.insert(entity.get_id(), sight);
+        }
+        self.sights = sights;
+    }
+}
@@ -0,0 +1,169 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, AttackUnitParams, MoveToPositionParams};
+use dat;
+use ecs::{ActionQueueComponent, DecayComponent, HealthComponent, ResourceComponent, TransformComponent,
+          UnitComponent};
+use ecs::event::ResourceDepleted;
+use ecs::resource::{Events, OccupiedTiles, PathFinder, Terrain};
+use specs::{self, Index, Join};
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use super::{System, SystemAccess};
+use types::{Fixed, Vector3};
+use util::{self, combat};
+
+/// How far an animal that can't fight back runs from whatever hurt it
+const FLEE_DISTANCE: Fixed = fixed_const!(6);
+
+/// Wild animals react to being hurt the way their species does: the ones that can fight back
+/// (boars, elephants, lions) charge whoever hurt them, and the rest (gazelles) run off.
+///
+/// Carcasses rot away while they lie around, whether or not anyone is gathering from them, and
+/// are removed once nothing is left.
+pub struct HuntingSystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl HuntingSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> HuntingSystem {
+        HuntingSystem { empires: empires }
+    }
+}
+
+impl System for HuntingSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .read::<PathFinder>()
+            .read::<OccupiedTiles>()
+            .read::<Terrain>()
+            .write::<ActionQueueComponent>()
+            .write::<DecayComponent>()
+            .write::<HealthComponent>()
+            .write::<ResourceComponent>()
+            .write::<Events<ResourceDepleted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(units: UnitComponent),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(decays: DecayComponent),
+            mut components(healths: HealthComponent),
+            mut components(resources: ResourceComponent),
+            resource(path_finder: PathFinder),
+            resource(occupied_tiles: OccupiedTiles),
+            resource(terrain: Terrain),
+            mut resource(resource_depleted: Events<ResourceDepleted>),
+        ]);
+
+        let attacker_ids: HashSet<Index> = (&healths)
+            .iter()
+            .filter_map(|health| health.last_attacker_id)
+            .collect();
+        let mut attackers: HashMap<Index, (Vector3, Fixed)> = HashMap::new();
+        if !attacker_ids.is_empty() {
+            for (entity, unit, transform) in (&entities, &units, &transforms).iter() {
+                if attacker_ids.contains(&entity.get_id()) {
+                    let unit_info = unit.db(&self.empires);
+                    let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+                    attackers.insert(entity.get_id(), (*transform.position(), size));
+                }
+            }
+        }
+
+        for (entity, unit, health, action_queue, transform) in
+            (&entities, &units, &mut healths, &mut action_queues, &transforms).iter() {
+            let attacker_id = match health.last_attacker_id.take() {
+                Some(attacker_id) => attacker_id,
+                None => continue,
+            };
+            let unit_info = unit.db(&self.empires);
+            if health.is_dead() || !util::unit::is_animal(unit_info) {
+                continue;
+            }
+            let moves = unit_info.motion_params.as_ref().map(|params| params.speed > 0f32).unwrap_or(false);
+            let (attacker_position, attacker_size) = match attackers.get(&attacker_id) {
+                Some(&attacker) if moves => attacker,
+                _ => continue,
+            };
+            let position = *transform.position();
+
+            let fights_back = unit_info.battle_params
+                .as_ref()
+                .map(|params| params.attacks.iter().any(|&(_, amount)| amount > 0))
+                .unwrap_or(false);
+            if fights_back {
+                let already_on_it = match *action_queue.current_action() {
+                    Some(Action::AttackUnit(ref params)) => {
+                        params.target_id == attacker_id && !action_queue.current_action_done()
+                    }
+                    _ => false,
+                };
+                if already_on_it {
+                    continue;
+                }
+
+                let params = unit_info.battle_params.as_ref().unwrap();
+                let size: Fixed = unit_info.collision_size_x.max(unit_info.collision_size_y).into();
+                let reach = Fixed::from(params.max_range) + size + attacker_size;
+                let mut charge = Vec::new();
+                if combat::ground_distance(&position, &attacker_position) > reach {
+                    let path = path_finder.find_path(&*terrain,
+                                                     &*occupied_tiles,
+                                                     &position,
+                                                     &attacker_position,
+                                                     unit_info.terrain_restriction);
+                    let path = combat::path_into_range(&path, &attacker_position, reach);
+                    charge.push(Action::MoveToPosition(MoveToPositionParams::new(path)));
+                }
+                charge.push(Action::AttackUnit(AttackUnitParams::new(attacker_id)));
+                action_queue.interrupt(charge);
+            } else {
+                let destination = combat::flee_point(&position, &attacker_position, FLEE_DISTANCE);
+                let path = path_finder.find_path(&*terrain,
+                                                 &*occupied_tiles,
+                                                 &position,
+                                                 &destination,
+                                                 unit_info.terrain_restriction);
+                action_queue.interrupt(vec![Action::MoveToPosition(MoveToPositionParams::new(path))]);
+            }
+        }
+
+        for (entity, unit, resource) in (&entities, &units, &mut resources).iter() {
+            if resource.rot_rate <= 0.into() || resource.amount <= 0.into() {
+                continue;
+            }
+            resource.amount -= cmp::min(resource.rot_rate * time_step, resource.amount);
+            if resource.amount <= 0.into() {
+                resource_depleted.emit(ResourceDepleted {
+                    entity_id: entity.get_id(),
+                    unit_id: unit.unit_id,
+                });
+                decays.insert(entity, DecayComponent::new(0.into()));
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod action;
+mod artifact_system;
+mod blast_system;
+mod death_system;
+mod decay_system;
+mod discovery_system;
+mod exploration_system;
+mod hunting_system;
+mod occupied_tile_system;
+mod projectile_system;
+mod schedule;
+mod score_system;
+mod script_system;
+mod system;
+mod teardown_system;
+mod tech_system;
+mod training_system;
+mod unit_action_system;
+mod velocity_system;
+mod victory_system;
+
+pub use self::action::*;
+pub use self::artifact_system::ArtifactSystem;
+pub use self::blast_system::BlastSystem;
+pub use self::death_system::DeathSystem;
+pub use self::decay_system::DecaySystem;
+pub use self::discovery_system::DiscoverySystem;
+pub use self::exploration_system::ExplorationSystem;
+pub use self::hunting_system::HuntingSystem;
+pub use self::occupied_tile_system::OccupiedTileSystem;
+pub use self::projectile_system::ProjectileSystem;
+pub use self::schedule::assign_stages;
+pub use self::score_system::ScoreSystem;
+pub use self::script_system::ScriptSystem;
+pub use self::system::{System, SystemAccess, SystemWrapper};
+pub use self::teardown_system::TeardownSystem;
+pub use self::tech_system::TechSystem;
+pub use self::training_system::TrainingSystem;
+pub use self::unit_action_system::UnitActionSystem;
+pub use self::velocity_system::VelocitySystem;
+pub use self::victory_system::VictorySystem;
@@ -20,13 +20,15 @@
 // SOFTWARE.
 
 use dat;
-use ecs::{TransformComponent, UnitComponent};
+use ecs::{TransformComponent, UnitComponent, VelocityComponent};
 use ecs::resource::OccupiedTiles;
 use specs::{self, Join};
-use super::System;
-use types::{Fixed, ToPrimitive};
+use super::{System, SystemAccess};
+use types::{Fixed, Norm};
 use util::unit;
 
+/// Keeps the footprints of moving tile blockers up to date. Footprints are occupied when entities
+/// are created (see `UnitFactory`) and released when they're removed; this only handles movement.
 pub struct OccupiedTileSystem {
     empires: dat::EmpiresDbRef,
 }
@@ -38,40 +40,30 @@ impl OccupiedTileSystem {
 }
 
 impl System for OccupiedTileSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .read::<VelocityComponent>()
+            .write::<OccupiedTiles>()
+    }
+
     fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
-        fetch_components!(arg, _entities, [
+        fetch_components!(arg, entities, [
             components(transforms: TransformComponent),
             components(units: UnitComponent),
+            components(velocities: VelocityComponent),
             mut resource(occupied_tiles: OccupiedTiles),
         ]);
 
-        occupied_tiles.tiles.clear();
-        for (transform, unit) in (&transforms, &units).iter() {
-            let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
-            let unit_blocks_tiles = match unit_info.interaction_mode {
-                dat::InteractionMode::Building => true,
-                dat::InteractionMode::Resource => {
-                    match unit_info.motion_params {
-                        Some(ref params) => params.speed < 0.001f32,
-                        None => true,
-                    }
-                }
-                _ => false,
-            };
-
-            if unit_blocks_tiles {
-                let collision_box = unit::collision_box(unit_info, transform);
-
-                let (start_row, end_row) = (collision_box.min.y.to_i32().unwrap(),
-                                            collision_box.max.y.to_i32().unwrap());
-                let (start_col, end_col) = (collision_box.min.x.to_i32().unwrap(),
-                                            collision_box.max.x.to_i32().unwrap());
-                for row in start_row..(end_row + 1) {
-                    for col in start_col..(end_col + 1) {
-                        occupied_tiles.tiles.insert((row, col));
-                    }
-                }
+        for (entity, transform, unit, velocity) in (&entities, &transforms, &units, &velocities).iter() {
+            if velocity.velocity.length_squared() == Fixed::from(0) ||
+               occupied_tiles.footprint(entity.get_id()).is_none() {
+                continue;
             }
+
+            let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
+            occupied_tiles.update(entity.get_id(), unit::footprint_tiles(unit_info, transform));
         }
     }
 }
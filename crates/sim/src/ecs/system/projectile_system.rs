@@ -0,0 +1,71 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use ecs::ProjectileComponent;
+use ecs::event::Blast;
+use ecs::resource::Events;
+use partition::GridPartition;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// Lands projectiles once they've flown for long enough; the damage is done by the blast
+pub struct ProjectileSystem;
+
+impl ProjectileSystem {
+    pub fn new() -> ProjectileSystem {
+        ProjectileSystem
+    }
+}
+
+impl System for ProjectileSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .write::<ProjectileComponent>()
+            .write::<Events<Blast>>()
+            .write::<GridPartition>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            mut components(projectiles: ProjectileComponent),
+            mut resource(blasts: Events<Blast>),
+            mut resource(grid: GridPartition),
+        ]);
+
+        for (entity, projectile) in (&entities, &mut projectiles).iter() {
+            projectile.time_left -= time_step;
+            if projectile.time_left <= 0.into() {
+                blasts.emit(Blast {
+                    center: projectile.target,
+                    radius: projectile.blast_radius,
+                    attacks: projectile.attacks.clone(),
+                    attack_level: projectile.blast_attack_level,
+                    attacker_id: projectile.owner_id,
+                    primary_target_id: None,
+                });
+                grid.remove_entity(entity.get_id());
+                arg.delete(entity);
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::SystemAccess;
+
+/// Assigns each system (given in declaration order) to a stage. Systems in the same stage never
+/// conflict with each other, so they can all run at once. A system always lands in a later stage
+/// than every earlier declared system it conflicts with, so the order in which systems touch shared
+/// simulation state is always the declaration order, no matter how the threads get scheduled.
+pub fn assign_stages(accesses: &[SystemAccess]) -> Vec<usize> {
+    let mut stages: Vec<usize> = Vec::with_capacity(accesses.len());
+    for (index, access) in accesses.iter().enumerate() {
+        let stage = (0..index)
+            .filter(|&earlier| accesses[earlier].conflicts_with(access))
+            .map(|earlier| stages[earlier] + 1)
+            .max()
+            .unwrap_or(0);
+        stages.push(stage);
+    }
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assign_stages;
+    use super::super::SystemAccess;
+
+    struct A;
+    struct B;
+    struct C;
+
+    #[test]
+    fn test_readers_share_a_stage() {
+        let accesses = vec![SystemAccess::new().read::<A>(),
+                            SystemAccess::new().read::<A>().read::<B>(),
+                            SystemAccess::new().read::<B>()];
+        assert_eq!(vec![0, 0, 0], assign_stages(&accesses));
+    }
+
+    #[test]
+    fn test_conflicts_keep_declaration_order() {
+        let accesses = vec![SystemAccess::new().write::<A>(),
+                            SystemAccess::new().read::<A>().write::<B>(),
+                            SystemAccess::new().write::<C>(),
+                            SystemAccess::new().read::<B>(),
+                            SystemAccess::new().write::<A>()];
+        assert_eq!(vec![0, 1, 0, 2, 2], assign_stages(&accesses));
+    }
+
+    #[test]
+    fn test_conflicts_with() {
+        let reads_a = SystemAccess::new().read::<A>();
+        let writes_a = SystemAccess::new().write::<A>();
+        let writes_b = SystemAccess::new().write::<B>();
+        assert!(!reads_a.conflicts_with(&reads_a));
+        assert!(reads_a.conflicts_with(&writes_a));
+        assert!(writes_a.conflicts_with(&reads_a));
+        assert!(writes_a.conflicts_with(&writes_a));
+        assert!(!writes_a.conflicts_with(&writes_b));
+    }
+}
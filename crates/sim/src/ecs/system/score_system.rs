@@ -0,0 +1,126 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use ecs::{ArtifactComponent, HealthComponent, UnitComponent};
+use ecs::resource::{PlayerStats, PlayerTechs, Players, ScoreInputs, Scores, Timeline, TimelineSample};
+use identifier::PlayerId;
+use specs::{self, Join};
+use std::collections::BTreeMap;
+use super::{System, SystemAccess};
+use types::Fixed;
+use util::selection::{self, SelectionClass};
+use util::unit;
+
+/// Keeps every player's score up to date, and samples how everyone's doing onto the timeline
+pub struct ScoreSystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl ScoreSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> ScoreSystem {
+        ScoreSystem { empires: empires }
+    }
+}
+
+impl System for ScoreSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<HealthComponent>()
+            .read::<ArtifactComponent>()
+            .read::<Players>()
+            .read::<PlayerStats>()
+            .read::<PlayerTechs>()
+            .write::<Scores>()
+            .write::<Timeline>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, _entities, [
+            components(units: UnitComponent),
+            components(healths: HealthComponent),
+            components(artifacts: ArtifactComponent),
+            resource(players: Players),
+            resource(stats: PlayerStats),
+            resource(techs: PlayerTechs),
+            mut resource(scores: Scores),
+            mut resource(timeline: Timeline),
+        ]);
+
+        // Gaia doesn't get a score
+        let mut inputs: BTreeMap<PlayerId, ScoreInputs> = BTreeMap::new();
+        for player_id in players.player_ids() {
+            if *player_id != 0 {
+                let player_stats = stats.player(player_id);
+                inputs.insert(player_id,
+                              ScoreInputs {
+                                  units_killed: player_stats.units_killed,
+                                  buildings_razed: player_stats.buildings_razed,
+                                  ..Default::default()
+                              });
+            }
+        }
+
+        // Only things that can be killed count; corpses and rubble don't have health
+        let mut military: BTreeMap<PlayerId, u32> = BTreeMap::new();
+        for (unit, _) in (&units, &healths).iter() {
+            if let Some(input) = inputs.get_mut(&unit.player_id) {
+                let unit_info = unit.db(&self.empires);
+                if unit::is_wonder(unit.unit_id) {
+                    input.wonders += 1;
+                } else if !unit_info.is_building() {
+                    input.population += 1;
+                }
+                if selection::selection_class(unit_info) == SelectionClass::Military {
+                    *military.entry(unit.player_id).or_insert(0) += 1;
+                }
+            }
+        }
+        for (unit, _) in (&units, &artifacts).iter() {
+            if let Some(input) = inputs.get_mut(&unit.player_id) {
+                input.artifacts += 1;
+            }
+        }
+        for (player_id, _) in techs.researched() {
+            if let Some(input) = inputs.get_mut(&player_id) {
+                input.researched += 1;
+            }
+        }
+
+        scores.update(&inputs);
+
+        if timeline.update(time_step) {
+            let samples = inputs.iter()
+                .map(|(&player_id, input)| {
+                    (player_id,
+                     TimelineSample {
+                         population: input.population,
+                         military: military.get(&player_id).cloned().unwrap_or(0),
+                         resources_gathered: stats.player(player_id).resources_gathered,
+                         score: scores.score(player_id).total(),
+                     })
+                })
+                .collect();
+            timeline.record(samples);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::ResourceType;
+use ecs::{HealthComponent, TransformComponent, UnitComponent, UnitSpawn};
+use ecs::resource::{Announcements, GameRng, Messages, Players, SCRIPT_INTERVAL_TICKS, ScriptApi, Scripts,
+                    SimulationTick, SpawnQueue, Stockpiles, Terrain, Victory};
+use identifier::{PlayerId, UnitId};
+use specs::{self, Join};
+use std::cmp;
+use super::{System, SystemAccess};
+use types::{Fixed, Rect, Vector3};
+
+/// Runs the game's scripts once a second, at the end of the tick
+pub struct ScriptSystem;
+
+impl ScriptSystem {
+    pub fn new() -> ScriptSystem {
+        ScriptSystem
+    }
+}
+
+impl System for ScriptSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<TransformComponent>()
+            .read::<HealthComponent>()
+            .read::<SimulationTick>()
+            .read::<Players>()
+            .read::<Terrain>()
+            .read::<Stockpiles>()
+            .write::<Scripts>()
+            .write::<GameRng>()
+            .write::<SpawnQueue>()
+            .write::<Announcements>()
+            .write::<Messages>()
+            .write::<Victory>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, _entities, [
+            components(units: UnitComponent),
+            components(transforms: TransformComponent),
+            components(healths: HealthComponent),
+            resource(tick: SimulationTick),
+            resource(players: Players),
+            resource(terrain: Terrain),
+            resource(stockpiles: Stockpiles),
+            mut resource(scripts: Scripts),
+            mut resource(rng: GameRng),
+            mut resource(spawn_queue: SpawnQueue),
+            mut resource(announcements: Announcements),
+            mut resource(messages: Messages),
+            mut resource(victory: Victory),
+        ]);
+
+        if tick.tick % SCRIPT_INTERVAL_TICKS != 0 {
+            return;
+        }
+
+        // Things without hit points (corpses, rubble) don't count as units
+        let living_units = (&units, &transforms, &healths)
+            .iter()
+            .map(|(unit, transform, _)| {
+                let position = transform.position();
+                LivingUnit {
+                    player_id: unit.player_id,
+                    unit_id: unit.unit_id,
+                    x: position.x.into(),
+                    y: position.y.into(),
+                }
+            })
+            .collect();
+
+        let mut api = WorldScriptApi {
+            tick: tick.tick,
+            living_units: living_units,
+            players: &*players,
+            terrain: &*terrain,
+            stockpiles: &*stockpiles,
+            rng: &mut *rng,
+            spawn_queue: &mut *spawn_queue,
+            announcements: &mut *announcements,
+            messages: &mut *messages,
+            victory: &mut *victory,
+        };
+        scripts.update(&mut api);
+    }
+}
+
+struct LivingUnit {
+    player_id: PlayerId,
+    unit_id: UnitId,
+    /// Tile the unit is on
+    x: i32,
+    y: i32,
+}
+
+/// What scripts get to see of the world
+struct WorldScriptApi<'a> {
+    tick: u64,
+    living_units: Vec<LivingUnit>,
+    players: &'a Players,
+    terrain: &'a Terrain,
+    stockpiles: &'a Stockpiles,
+    rng: &'a mut GameRng,
+    spawn_queue: &'a mut SpawnQueue,
+    announcements: &'a mut Announcements,
+    messages: &'a mut Messages,
+    victory: &'a mut Victory,
+}
+
+impl<'a> WorldScriptApi<'a> {
+    /// Scripts run once a second, so this is exact
+    fn seconds(&self) -> u64 {
+        self.tick / SCRIPT_INTERVAL_TICKS
+    }
+}
+
+impl<'a> ScriptApi for WorldScriptApi<'a> {
+    fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    fn unit_count(&self, player_id: PlayerId, unit_id: Option<UnitId>, area: Option<Rect>) -> u32 {
+        self.living_units
+            .iter()
+            .filter(|unit| {
+                unit.player_id == player_id && unit_id.map(|id| id == unit.unit_id).unwrap_or(true) &&
+                area.map(|area| area.contains(unit.x, unit.y)).unwrap_or(true)
+            })
+            .count() as u32
+    }
+
+    fn resource_amount(&self, player_id: PlayerId, resource_type: ResourceType) -> u32 {
+        let amount: i32 = self.stockpiles.amount(player_id, resource_type).into();
+        cmp::max(amount, 0) as u32
+    }
+
+    fn chance(&mut self, percent: u32) -> bool {
+        self.rng.gen_range(0, 100) < percent
+    }
+
+    fn announce(&mut self, text: &str) {
+        let seconds = self.seconds();
+        self.announcements.announce(text.to_string());
+        self.messages.add_message(seconds, text.to_string());
+    }
+
+    fn add_objective(&mut self, text: &str) {
+        let seconds = self.seconds();
+        if self.messages.add_objective(seconds, text.to_string()) {
+            self.announcements.announce(format!("New objective: {}", text));
+        }
+    }
+
+    fn complete_objective(&mut self, text: &str) {
+        let seconds = self.seconds();
+        if self.messages.complete_objective(seconds, text) {
+            self.announcements.announce(format!("Objective complete: {}", text));
+        }
+    }
+
+    fn spawn(&mut self, player_id: PlayerId, unit_id: UnitId, x: i32, y: i32) {
+        let civ_id = match self.players.player(player_id) {
+            Some(player) => player.civ_id,
+            None => return,
+        };
+        let elevation = self.terrain.tile_at_row_col(y, x).elevation;
+        let half: Fixed = Fixed::from(1) / 2.into();
+        self.spawn_queue.queue(UnitSpawn {
+            player_id: player_id,
+            civilization_id: civ_id,
+            unit_id: unit_id,
+            position: Vector3::new(Fixed::from(x) + half, Fixed::from(y) + half, elevation.into()),
+            rotation: 0.into(),
+            lifetime: None,
+        });
+    }
+
+    fn win(&mut self, player_id: PlayerId) {
+        self.victory.declare_winner(player_id);
+    }
+}
@@ -0,0 +1,85 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use specs;
+use std::any::{Any, TypeId};
+use std::collections::HashSet;
+use super::super::world::SystemGroup;
+use types::Fixed;
+
+pub trait System: Send {
+    /// Declares every component and resource that `update` fetches. The scheduler uses this
+    /// to run systems that don't conflict in parallel, so it must match the fetch exactly.
+    fn access(&self) -> SystemAccess;
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed);
+}
+
+/// The components and resources a system reads and writes
+#[derive(Clone, Debug, Default)]
+pub struct SystemAccess {
+    reads: HashSet<TypeId>,
+    writes: HashSet<TypeId>,
+}
+
+impl SystemAccess {
+    pub fn new() -> SystemAccess {
+        SystemAccess {
+            reads: HashSet::new(),
+            writes: HashSet::new(),
+        }
+    }
+
+    /// Declares read access to a component or resource type
+    pub fn read<T: Any>(mut self) -> SystemAccess {
+        self.reads.insert(TypeId::of::<T>());
+        self
+    }
+
+    /// Declares write access to a component or resource type
+    pub fn write<T: Any>(mut self) -> SystemAccess {
+        self.writes.insert(TypeId::of::<T>());
+        self
+    }
+
+    /// Two systems conflict if either one writes something that the other one uses
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        self.writes.iter().any(|t| other.reads.contains(t) || other.writes.contains(t)) ||
+        other.writes.iter().any(|t| self.reads.contains(t))
+    }
+}
+
+pub struct SystemWrapper(Box<System>);
+
+impl SystemWrapper {
+    pub fn new(system: Box<System>) -> SystemWrapper {
+        SystemWrapper(system)
+    }
+}
+
+impl specs::System<(SystemGroup, Fixed)> for SystemWrapper {
+    fn run(&mut self, arg: specs::RunArg, params: (SystemGroup, Fixed)) {
+        match params.0 {
+            SystemGroup::Normal => self.0.update(arg, params.1),
+            _ => arg.fetch(|_| {}),
+        }
+    }
+}
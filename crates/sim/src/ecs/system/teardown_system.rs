@@ -0,0 +1,123 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::component::{ActionQueueComponent, AttackUnitActionComponent, GarrisonActionComponent,
+                     SelectedUnitComponent};
+use ecs::event::{UnitConverted, UnitDied};
+use ecs::resource::{ActionBatcher, EventReader, Events, OccupiedTiles};
+use partition::GridPartition;
+use specs::{self, Index, Join};
+use std::collections::HashSet;
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// Makes sure nothing keeps hold of units that died or changed hands. Dead units are taken out
+/// of the selection, the grid partition, the occupied tiles, and the action batcher, and units
+/// that were on their way to attack or garrison in one give up on it. Converted units are
+/// deselected and forget the orders their old owner gave them.
+///
+/// Entity indices are reused once the entity is deleted, so anything left pointing at a dead
+/// unit would end up pointing at whatever is created next.
+pub struct TeardownSystem {
+    unit_died: EventReader,
+    unit_converted: EventReader,
+}
+
+impl TeardownSystem {
+    pub fn new() -> TeardownSystem {
+        TeardownSystem {
+            unit_died: EventReader::new(),
+            unit_converted: EventReader::new(),
+        }
+    }
+}
+
+impl System for TeardownSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<AttackUnitActionComponent>()
+            .read::<GarrisonActionComponent>()
+            .read::<Events<UnitDied>>()
+            .read::<Events<UnitConverted>>()
+            .write::<ActionQueueComponent>()
+            .write::<SelectedUnitComponent>()
+            .write::<ActionBatcher>()
+            .write::<GridPartition>()
+            .write::<OccupiedTiles>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(attack_units: AttackUnitActionComponent),
+            components(garrisons: GarrisonActionComponent),
+            resource(unit_died: Events<UnitDied>),
+            resource(unit_converted: Events<UnitConverted>),
+            mut components(action_queues: ActionQueueComponent),
+            mut components(selected_units: SelectedUnitComponent),
+            mut resource(action_batcher: ActionBatcher),
+            mut resource(grid: GridPartition),
+            mut resource(occupied_tiles: OccupiedTiles),
+        ]);
+
+        let mut dead: HashSet<Index> = HashSet::new();
+        for death in unit_died.read(&mut self.unit_died) {
+            occupied_tiles.release(death.entity_id);
+            grid.remove_entity(death.entity_id);
+            action_batcher.forget(death.entity_id);
+            dead.insert(death.entity_id);
+        }
+
+        let mut converted: HashSet<Index> = HashSet::new();
+        for conversion in unit_converted.read(&mut self.unit_converted) {
+            action_batcher.forget(conversion.entity_id);
+            converted.insert(conversion.entity_id);
+        }
+
+        if dead.is_empty() && converted.is_empty() {
+            return;
+        }
+
+        let deselected: Vec<specs::Entity> = (&entities, &selected_units)
+            .iter()
+            .map(|(entity, _)| entity)
+            .filter(|entity| dead.contains(&entity.get_id()) || converted.contains(&entity.get_id()))
+            .collect();
+        for entity in deselected {
+            selected_units.remove(entity);
+        }
+
+        for (entity, action_queue) in (&entities, &mut action_queues).iter() {
+            if converted.contains(&entity.get_id()) {
+                action_queue.clear();
+                continue;
+            }
+            let target_died = attack_units.get(entity)
+                .map(|attack| dead.contains(&attack.target_id))
+                .unwrap_or(false);
+            let shelter_died = garrisons.get(entity)
+                .map(|garrison| dead.contains(&garrison.building_id))
+                .unwrap_or(false);
+            if target_died || shelter_died {
+                action_queue.mark_current_done();
+            }
+        }
+    }
+}
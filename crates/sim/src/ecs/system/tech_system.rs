@@ -0,0 +1,106 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat::{self, ResearchEffect, ResearchEffectValue};
+use ecs::{UnitComponent, UnitMorph};
+use ecs::event::ResearchCompleted;
+use ecs::resource::{EventReader, Events, PlayerTechs, SpawnQueue};
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// Civ header (player resource) that lets a player see what their allies see once it's set
+const REVEAL_ALLY_RESOURCE: i16 = 50;
+
+/// Applies the effects of finished research to the player who researched it
+pub struct TechSystem {
+    empires: dat::EmpiresDbRef,
+    research_completed: EventReader,
+}
+
+impl TechSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> TechSystem {
+        TechSystem {
+            empires: empires,
+            research_completed: EventReader::new(),
+        }
+    }
+}
+
+impl System for TechSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<UnitComponent>()
+            .read::<Events<ResearchCompleted>>()
+            .write::<PlayerTechs>()
+            .write::<SpawnQueue>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(units: UnitComponent),
+            resource(research_completed: Events<ResearchCompleted>),
+            mut resource(techs: PlayerTechs),
+            mut resource(spawn_queue: SpawnQueue),
+        ]);
+
+        for research in research_completed.read(&mut self.research_completed) {
+            if !techs.complete_research(research.player_id, research.research_id) {
+                continue;
+            }
+            let effects = match self.empires.research_effects(research.research_id) {
+                Some(effects) => effects,
+                None => continue,
+            };
+
+            // TODO: Attribute changes, enabling units, research costs, and the rest of the civ header
+            for effect in &effects.effects {
+                if let ResearchEffect::CivHeader { target_civ_header_id, ref effect } = *effect {
+                    if target_civ_header_id == REVEAL_ALLY_RESOURCE && reveals(effect) {
+                        techs.share_exploration(research.player_id);
+                    }
+                }
+                if let ResearchEffect::UpgradeUnit { source_unit_id, target_unit_id } = *effect {
+                    techs.upgrade_unit(research.player_id, source_unit_id, target_unit_id);
+
+                    // Units the player already has are changed at the start of the next tick
+                    for (entity, unit) in (&entities, &units).iter() {
+                        if unit.player_id == research.player_id && unit.unit_id == source_unit_id {
+                            spawn_queue.queue_morph(UnitMorph {
+                                entity_id: entity.get_id(),
+                                unit_id: target_unit_id,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the effect turns a flag resource on (starting from off)
+fn reveals(effect: &ResearchEffectValue) -> bool {
+    match *effect {
+        ResearchEffectValue::SetTo(value) | ResearchEffectValue::Add(value) => value > 0.0,
+        ResearchEffectValue::MultiplyBy(_) => false,
+    }
+}
@@ -0,0 +1,111 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat;
+use ecs::{Production, TrainingQueueComponent, TransformComponent, UnitComponent, UnitSpawn};
+use ecs::event::ResearchCompleted;
+use ecs::resource::{Events, SpawnQueue};
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::{Fixed, Vector3};
+
+/// Works on whatever is at the front of each building's production queue. Trained units are
+/// sent out next to the building once they're done, and finished research goes to the
+/// TechSystem.
+pub struct TrainingSystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl TrainingSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> TrainingSystem {
+        TrainingSystem { empires: empires }
+    }
+}
+
+impl System for TrainingSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .write::<TrainingQueueComponent>()
+            .write::<SpawnQueue>()
+            .write::<Events<ResearchCompleted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(units: UnitComponent),
+            mut components(training_queues: TrainingQueueComponent),
+            mut resource(spawn_queue: SpawnQueue),
+            mut resource(research_completed: Events<ResearchCompleted>),
+        ]);
+
+        for (entity, training_queue, unit, transform) in
+            (&entities, &mut training_queues, &units, &transforms).iter() {
+            let production = match training_queue.current() {
+                Some(production) => production,
+                None => continue,
+            };
+            // Only units and research that empires.dat has can be queued (and paid for)
+            let production_time: Fixed = match production {
+                Production::Unit(unit_id) => {
+                    self.empires.unit(unit.civilization_id, unit_id).train_time().unwrap_or(0).into()
+                }
+                Production::Research(research_id) => self.empires.research(research_id).time_seconds.into(),
+            };
+
+            training_queue.progress += time_step;
+            if training_queue.progress < production_time {
+                continue;
+            }
+            training_queue.finish_current();
+
+            let trainee_id = match production {
+                Production::Unit(unit_id) => unit_id,
+                Production::Research(research_id) => {
+                    research_completed.emit(ResearchCompleted {
+                        entity_id: entity.get_id(),
+                        player_id: unit.player_id,
+                        research_id: research_id,
+                    });
+                    continue;
+                }
+            };
+            let trainee_info = self.empires.unit(unit.civilization_id, trainee_id);
+
+            // New units come out just past the corner of the building
+            let building_info = unit.db(&self.empires);
+            let position = transform.position();
+            let offset_x: Fixed = (building_info.collision_size_x + trainee_info.collision_size_x).into();
+            let offset_y: Fixed = (building_info.collision_size_y + trainee_info.collision_size_y).into();
+            spawn_queue.queue(UnitSpawn {
+                player_id: unit.player_id,
+                civilization_id: unit.civilization_id,
+                unit_id: trainee_id,
+                position: Vector3::new(position.x + offset_x, position.y + offset_y, position.z),
+                rotation: 0.into(),
+                lifetime: None,
+            });
+        }
+    }
+}
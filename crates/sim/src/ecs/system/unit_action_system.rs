@@ -0,0 +1,395 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, BuildParams};
+use dat;
+use ecs::UnitSpawn;
+use ecs::component::{MoveToPositionActionComponent, AttackGroundActionComponent, AttackUnitActionComponent,
+                     AttackMoveActionComponent, BuildActionComponent, GarrisonActionComponent,
+                     GatherActionComponent, ActionQueueComponent, ConstructionComponent,
+                     GarrisonedComponent, HealthComponent, Production, TrainingQueueComponent,
+                     TransformComponent, UnitComponent};
+use ecs::event::PlayerResigned;
+use ecs::resource::{ActionBatcher, Events, OccupiedTiles, PlayerTechs, SimulationTick, SpawnQueue,
+                    Stockpiles};
+use identifier::PlayerId;
+use specs::{self, Join};
+use super::{System, SystemAccess};
+use types::Fixed;
+use util::unit;
+
+// This is just a temporary batch length value
+// It'll be subject to the latencies of networking later
+const TURN_LENGTH_SECONDS: Fixed = fixed_const!(0.1);
+
+macro_rules! detach_action_component {
+    ($action:expr, $entity:expr, $mtps:expr, $attack_grounds:expr, $attack_units:expr,
+     $attack_moves:expr, $builds:expr, $garrisons:expr, $gathers:expr) => {
+        match $action {
+            Action::MoveToPosition(_) => { $mtps.remove($entity); }
+            Action::AttackGround(_) => { $attack_grounds.remove($entity); }
+            Action::AttackUnit(_) => { $attack_units.remove($entity); }
+            Action::AttackMove(_) => { $attack_moves.remove($entity); }
+            Action::Build(_) => { $builds.remove($entity); }
+            Action::Garrison(_) => { $garrisons.remove($entity); }
+            Action::Gather(_) => { $gathers.remove($entity); }
+            _ => panic!("Failed to detach unknown action: {:?}", $action)
+        }
+    }
+}
+
+macro_rules! attach_action_component {
+    ($action:expr, $entity:expr, $mtps:expr, $attack_grounds:expr, $attack_units:expr,
+     $attack_moves:expr, $builds:expr, $garrisons:expr, $gathers:expr) => {
+        match $action {
+            Action::MoveToPosition(ref params) => {
+                $mtps.insert($entity, MoveToPositionActionComponent::new(params.path.clone()));
+            }
+            Action::AttackGround(ref params) => {
+                $attack_grounds.insert($entity, AttackGroundActionComponent::new(params.target));
+            }
+            Action::AttackUnit(ref params) => {
+                $attack_units.insert($entity, AttackUnitActionComponent::new(params.target_id));
+            }
+            Action::AttackMove(ref params) => {
+                $attack_moves.insert($entity, AttackMoveActionComponent::new(params.path.clone()));
+            }
+            Action::Build(ref params) => {
+                $builds.insert($entity, BuildActionComponent::new(params.building_id, params.position));
+            }
+            Action::Garrison(ref params) => {
+                $garrisons.insert($entity,
+                                  GarrisonActionComponent::new(params.building_id, params.town_bell));
+            }
+            Action::Gather(ref params) => {
+                $gathers.insert($entity, GatherActionComponent::new(params.target_id));
+            }
+            _ => panic!("Failed to attach unknown action: {:?}", $action)
+        }
+    }
+}
+
+/// This system exists to take the actions batched up in the ActionBatcher
+/// synchronize them across the network (in multiplayer), and then add
+/// the actions to each individual unit.
+///
+/// Training, research, and new buildings are paid for here, when they're ordered. Orders that
+/// the player can't afford are dropped.
+pub struct UnitActionSystem {
+    empires: dat::EmpiresDbRef,
+    turn_accumulator: Fixed,
+}
+
+impl UnitActionSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> UnitActionSystem {
+        UnitActionSystem {
+            empires: empires,
+            turn_accumulator: 0.into(),
+        }
+    }
+
+    /// What the production costs, if the building can make it: the building has to train the unit
+    /// or do the research, and research can only be done once per player
+    fn production_costs(&self,
+                        building: &UnitComponent,
+                        training_queue: &TrainingQueueComponent,
+                        techs: &PlayerTechs,
+                        production: Production)
+                        -> Option<Vec<(dat::ResourceType, i16)>> {
+        match production {
+            Production::Unit(unit_id) => {
+                self.empires
+                    .civilization(building.civilization_id)
+                    .units_trained_at(building.unit_id)
+                    .iter()
+                    .find(|trainee| trainee.id == unit_id)
+                    .map(|trainee| trainee.costs())
+            }
+            Production::Research(research_id) => {
+                let player_id = building.player_id;
+                if techs.has_researched(player_id, research_id) || training_queue.contains(production) {
+                    return None;
+                }
+                self.empires
+                    .researched_at(building.unit_id)
+                    .iter()
+                    .find(|research| {
+                        research.id == research_id &&
+                        research.required_research_ids().iter().all(|&id| techs.has_researched(player_id, id))
+                    })
+                    .map(|research| research.costs())
+            }
+        }
+    }
+
+    /// Pays for the production and adds it to the end of the building's queue; the TrainingSystem
+    /// takes it from there. Buildings that can't make it (or have a full queue, or are still being
+    /// built) ignore it, and so does a building whose player can't afford it.
+    fn queue_production(&self,
+                        building: Option<&UnitComponent>,
+                        training_queue: Option<&mut TrainingQueueComponent>,
+                        under_construction: bool,
+                        production: Production,
+                        techs: &PlayerTechs,
+                        stockpiles: &mut Stockpiles) {
+        let (building, training_queue) = match (building, training_queue) {
+            (Some(building), Some(training_queue)) => (building, training_queue),
+            _ => return,
+        };
+        if training_queue.is_full() || under_construction {
+            return;
+        }
+        if let Some(costs) = self.production_costs(building, training_queue, techs, production) {
+            if stockpiles.spend(building.player_id, &costs) {
+                training_queue.push(production, costs);
+            }
+        }
+    }
+
+    /// Pays for the building and lays its foundation, unless the builder can't build it, something
+    /// is already in the way, or the player can't afford it. The foundation goes up before the
+    /// next tick.
+    fn lay_foundation(&self,
+                      builder: &UnitComponent,
+                      params: &BuildParams,
+                      occupied_tiles: &OccupiedTiles,
+                      stockpiles: &mut Stockpiles,
+                      spawn_queue: &mut SpawnQueue)
+                      -> bool {
+        let building_info = self.empires
+            .civilization(builder.civilization_id)
+            .units_trained_at(builder.unit_id)
+            .into_iter()
+            .find(|trainee| trainee.id == params.building_id && trainee.is_building());
+        let building_info = match building_info {
+            Some(building_info) => building_info,
+            None => return false,
+        };
+        let transform = TransformComponent::new(params.position, 0.into());
+        let footprint = unit::footprint_tiles(building_info, &transform);
+        if footprint.iter().any(|tile| occupied_tiles.is_occupied(tile)) ||
+           !stockpiles.spend(builder.player_id, &building_info.costs()) {
+            return false;
+        }
+        spawn_queue.queue_foundation(UnitSpawn {
+            player_id: builder.player_id,
+            civilization_id: builder.civilization_id,
+            unit_id: params.building_id,
+            position: params.position,
+            rotation: 0.into(),
+            lifetime: None,
+        });
+        true
+    }
+}
+
+impl System for UnitActionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .write::<ActionQueueComponent>()
+            .write::<MoveToPositionActionComponent>()
+            .write::<AttackGroundActionComponent>()
+            .write::<AttackUnitActionComponent>()
+            .write::<AttackMoveActionComponent>()
+            .write::<BuildActionComponent>()
+            .write::<GarrisonActionComponent>()
+            .write::<GatherActionComponent>()
+            .write::<GarrisonedComponent>()
+            .write::<HealthComponent>()
+            .write::<TrainingQueueComponent>()
+            .read::<ConstructionComponent>()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .read::<OccupiedTiles>()
+            .read::<PlayerTechs>()
+            .read::<SimulationTick>()
+            .write::<ActionBatcher>()
+            .write::<SpawnQueue>()
+            .write::<Stockpiles>()
+            .write::<Events<PlayerResigned>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            mut components(action_queues: ActionQueueComponent),
+            mut components(mtps: MoveToPositionActionComponent),
+            mut components(attack_grounds: AttackGroundActionComponent),
+            mut components(attack_units: AttackUnitActionComponent),
+            mut components(attack_moves: AttackMoveActionComponent),
+            mut components(builds: BuildActionComponent),
+            mut components(garrisons: GarrisonActionComponent),
+            mut components(gathers: GatherActionComponent),
+            mut components(garrisoneds: GarrisonedComponent),
+            mut components(healths: HealthComponent),
+            mut components(training_queues: TrainingQueueComponent),
+            components(constructions: ConstructionComponent),
+            components(transforms: TransformComponent),
+            components(units: UnitComponent),
+            resource(occupied_tiles: OccupiedTiles),
+            resource(techs: PlayerTechs),
+            resource(tick: SimulationTick),
+            mut resource(action_batcher: ActionBatcher),
+            mut resource(spawn_queue: SpawnQueue),
+            mut resource(stockpiles: Stockpiles),
+            mut resource(player_resigned: Events<PlayerResigned>),
+        ]);
+
+        self.turn_accumulator += time_step;
+        if self.turn_accumulator >= TURN_LENGTH_SECONDS {
+            self.turn_accumulator -= TURN_LENGTH_SECONDS;
+
+            let action_batch = action_batcher.consume_actions(tick.tick);
+
+            // Foundations laid this turn, and whether they could be, so that the rest of the
+            // villagers sent to build them help out instead of laying (and paying for) their own
+            let mut foundations: Vec<(PlayerId, BuildParams, bool)> = Vec::new();
+
+            // Most turns nobody gave any orders, so there's no need to go through every unit
+            if !action_batch.is_empty() {
+                for (entity, action_queue) in (&entities, &mut action_queues).iter() {
+                    if let Some(actions) = action_batch.get(&entity.get_id()) {
+                        for action in actions {
+                            match *action {
+                                Action::ClearQueue => {
+                                    action_queue.clear();
+
+                                    // Clearing a building's queue cancels its training and research
+                                    if let (Some(unit), Some(training_queue)) =
+                                           (units.get(entity), training_queues.get_mut(entity)) {
+                                        stockpiles.refund(unit.player_id, &training_queue.cancel_all());
+                                    }
+                                }
+                                Action::RememberQueue => action_queue.remember(),
+                                Action::Ungarrison => {
+                                    // The GarrisonActionSystem lets garrisoned units out; units that
+                                    // are still on their way in just turn back
+                                    if let Some(garrisoned) = garrisoneds.get_mut(entity) {
+                                        garrisoned.leaving = true;
+                                    } else {
+                                        action_queue.clear();
+                                        action_queue.resume();
+                                    }
+                                }
+                                Action::Kill => {
+                                    // The DeathSystem takes it from here
+                                    if let Some(health) = healths.get_mut(entity) {
+                                        health.hit_points = 0.into();
+                                    }
+                                }
+                                Action::Resign => {
+                                    // The VictorySystem takes it from here
+                                    if let Some(unit) = units.get(entity) {
+                                        player_resigned.emit(PlayerResigned { player_id: unit.player_id });
+                                    }
+                                }
+                                Action::Research(research_id) => {
+                                    self.queue_production(units.get(entity),
+                                                          training_queues.get_mut(entity),
+                                                          constructions.get(entity).is_some(),
+                                                          Production::Research(research_id),
+                                                          &*techs,
+                                                          &mut *stockpiles);
+                                }
+                                Action::Train(unit_id) => {
+                                    self.queue_production(units.get(entity),
+                                                          training_queues.get_mut(entity),
+                                                          constructions.get(entity).is_some(),
+                                                          Production::Unit(unit_id),
+                                                          &*techs,
+                                                          &mut *stockpiles);
+                                }
+                                Action::Build(ref params) => {
+                                    let unit = match units.get(entity) {
+                                        Some(unit) => unit,
+                                        None => continue,
+                                    };
+                                    // Villagers sent to help with a foundation that's already there
+                                    // just go and build it
+                                    let existing = (&units, &transforms, &constructions)
+                                        .iter()
+                                        .any(|(other, transform, _)| {
+                                            other.player_id == unit.player_id &&
+                                            *transform.position() == params.position
+                                        });
+                                    let laid = foundations.iter()
+                                        .find(|&&(player_id, ref laid, _)| {
+                                            player_id == unit.player_id && laid == params
+                                        })
+                                        .map(|&(_, _, laid)| laid);
+                                    let building = match laid {
+                                        Some(laid) => laid,
+                                        None if existing => true,
+                                        None => {
+                                            let laid = self.lay_foundation(unit,
+                                                                           params,
+                                                                           &*occupied_tiles,
+                                                                           &mut *stockpiles,
+                                                                           &mut *spawn_queue);
+                                            foundations.push((unit.player_id, params.clone(), laid));
+                                            laid
+                                        }
+                                    };
+                                    if building {
+                                        action_queue.add(action.clone());
+                                    }
+                                }
+                                _ => action_queue.add(action.clone()),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for (entity, action_queue) in (&entities, &mut action_queues).iter() {
+            // If action queue has no current action, then take
+            // an action from the top of the queue and initialize it
+            // by creating a component on that entity for the action type.
+            // Handle the actual action via separate systems.
+            if action_queue.current_action_done() {
+                if let &Some(ref action) = action_queue.current_action() {
+                    detach_action_component!(*action,
+                                             entity,
+                                             &mut mtps,
+                                             &mut attack_grounds,
+                                             &mut attack_units,
+                                             &mut attack_moves,
+                                             &mut builds,
+                                             &mut garrisons,
+                                             &mut gathers);
+                }
+                action_queue.next_action();
+
+                if let &Some(ref action) = action_queue.current_action() {
+                    attach_action_component!(*action,
+                                             entity,
+                                             &mut mtps,
+                                             &mut attack_grounds,
+                                             &mut attack_units,
+                                             &mut attack_moves,
+                                             &mut builds,
+                                             &mut garrisons,
+                                             &mut gathers);
+                }
+            }
+        }
+    }
+}
@@ -23,7 +23,7 @@ use ecs::{TransformComponent, VelocityComponent};
 use nalgebra::Vector2;
 use partition::GridPartition;
 use specs::{self, Join};
-use super::System;
+use super::{System, SystemAccess};
 use types::{Fixed, Norm};
 
 pub struct VelocitySystem;
@@ -37,6 +37,13 @@ impl VelocitySystem {
 }
 
 impl System for VelocitySystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<VelocityComponent>()
+            .write::<TransformComponent>()
+            .write::<GridPartition>()
+    }
+
     fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
         fetch_components!(arg, entities, [
             components(velocities: VelocityComponent),
@@ -49,14 +56,9 @@ impl System for VelocitySystem {
                 let new_pos = *transform.position() + velocity.velocity * time_step;
                 transform.set_position(new_pos);
 
-                transform.rotation = {
-                    // As long as the direction isn't used for anything more than rendering,
-                    // it should be fine to use the floating point arc tangent.
-                    // Otherwise, we'll need to implement that in the fixed point code.
-                    let vel_x_floating: f32 = velocity.velocity.x.into();
-                    let vel_y_floating: f32 = velocity.velocity.y.into();
-                    vel_y_floating.atan2(vel_x_floating).into()
-                };
+                if velocity.velocity.length_squared() > MOVEMENT_THRESHOLD {
+                    transform.face_direction(velocity.velocity.x, velocity.velocity.y);
+                }
 
                 grid.update_entity(entity.get_id(),
                                    &Vector2::new(new_pos.x.into(), new_pos.y.into()));
@@ -0,0 +1,245 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat;
+use ecs::{ArtifactComponent, ConstructionComponent, GraphicComponent, HealthComponent, UnitComponent};
+use ecs::event::{BuildingCompleted, PlayerDefeated, PlayerResigned, UnitConverted, UnitDied};
+use ecs::resource::{Announcements, CountdownKind, CountdownUpdate, EventReader, Events, Exploration,
+                    PlayerStats, Players, Victory};
+use identifier::PlayerId;
+use specs::{self, Join};
+use std::collections::{BTreeMap, BTreeSet};
+use super::{System, SystemAccess};
+use types::Fixed;
+use util::unit;
+
+/// Takes players out of the game when they resign or lose everything, starts and stops the
+/// victory countdowns, and lets everyone know how it's all going.
+///
+/// A player is defeated once they have no units left and no buildings that can make more.
+/// Whatever they still have (walls, houses, farms) goes over to gaia, and their allies get
+/// everything they explored. When everyone left is on one team, that team has won by conquest.
+pub struct VictorySystem {
+    empires: dat::EmpiresDbRef,
+    building_completed: EventReader,
+    player_resigned: EventReader,
+    unit_converted: EventReader,
+    unit_died: EventReader,
+}
+
+impl VictorySystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> VictorySystem {
+        VictorySystem {
+            empires: empires,
+            building_completed: EventReader::new(),
+            player_resigned: EventReader::new(),
+            unit_converted: EventReader::new(),
+            unit_died: EventReader::new(),
+        }
+    }
+}
+
+impl System for VictorySystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<ArtifactComponent>()
+            .read::<HealthComponent>()
+            .read::<ConstructionComponent>()
+            .read::<Events<BuildingCompleted>>()
+            .read::<Events<PlayerResigned>>()
+            .read::<Events<UnitDied>>()
+            .read::<Players>()
+            .write::<GraphicComponent>()
+            .write::<UnitComponent>()
+            .write::<Victory>()
+            .write::<Announcements>()
+            .write::<PlayerStats>()
+            .write::<Exploration>()
+            .write::<Events<PlayerDefeated>>()
+            .write::<Events<UnitConverted>>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(artifacts: ArtifactComponent),
+            components(healths: HealthComponent),
+            components(constructions: ConstructionComponent),
+            mut components(graphics: GraphicComponent),
+            mut components(units: UnitComponent),
+            resource(building_completed: Events<BuildingCompleted>),
+            resource(player_resigned: Events<PlayerResigned>),
+            resource(unit_died: Events<UnitDied>),
+            resource(players: Players),
+            mut resource(victory: Victory),
+            mut resource(announcements: Announcements),
+            mut resource(stats: PlayerStats),
+            mut resource(exploration: Exploration),
+            mut resource(player_defeated: Events<PlayerDefeated>),
+            mut resource(conversions: Events<UnitConverted>),
+        ]);
+
+        let mut out = Vec::new();
+        for resignation in player_resigned.read(&mut self.player_resigned) {
+            if victory.defeat(resignation.player_id) {
+                out.push((resignation.player_id, true));
+            }
+        }
+
+        // Corpses, rubble, and resources don't have health, so they don't keep anyone in the game
+        let mut standing = BTreeSet::new();
+        for (entity, unit, health) in (&entities, &units, &healths).iter() {
+            if *unit.player_id == 0 || health.is_dead() || standing.contains(&unit.player_id) {
+                continue;
+            }
+            // Foundations can't train anything until they're built
+            let trains = || {
+                constructions.get(entity).is_none() &&
+                !self.empires.civilization(unit.civilization_id).units_trained_at(unit.unit_id).is_empty()
+            };
+            if !unit.db(&self.empires).is_building() || trains() {
+                standing.insert(unit.player_id);
+            }
+        }
+        for player_id in victory.update_standing(&standing) {
+            out.push((player_id, false));
+        }
+
+        if !out.is_empty() {
+            let mut defeated = BTreeSet::new();
+            for (player_id, resigned) in out {
+                if resigned {
+                    announcements.announce(format!("{} has resigned.", players.name(player_id)));
+                } else {
+                    announcements.announce(format!("{} has been defeated.", players.name(player_id)));
+                }
+                stats.record_defeat(player_id, resigned);
+                player_defeated.emit(PlayerDefeated {
+                    player_id: player_id,
+                    resigned: resigned,
+                });
+                defeated.insert(player_id);
+            }
+
+            for &player_id in &defeated {
+                for ally_id in players.player_ids() {
+                    if ally_id != player_id && *ally_id != 0 && !players.are_enemies(player_id, ally_id) &&
+                       !victory.is_defeated(ally_id) {
+                        exploration.share(player_id, ally_id);
+                    }
+                }
+            }
+
+            for (entity, unit) in (&entities, &mut units).iter() {
+                if defeated.contains(&unit.player_id) {
+                    conversions.emit(UnitConverted {
+                        entity_id: entity.get_id(),
+                        from_player_id: unit.player_id,
+                        to_player_id: 0.into(),
+                    });
+                    unit::change_owner(unit, graphics.get_mut(entity), 0.into());
+                }
+            }
+
+            let teams: BTreeMap<PlayerId, u8> = players.player_ids()
+                .into_iter()
+                .filter_map(|player_id| players.player(player_id).map(|player| (player_id, player.team)))
+                .collect();
+            if let Some(winner) = victory.check_conquest(&teams) {
+                announcements.announce(format!("{} has won the game!", players.name(winner)));
+            }
+        }
+
+        // The countdown runs while one player (other than gaia) holds every artifact on the map
+        let mut holder: Option<PlayerId> = None;
+        let mut shared = false;
+        for (_, unit) in (&artifacts, &units).iter() {
+            match holder {
+                Some(player_id) if player_id != unit.player_id => shared = true,
+                _ => holder = Some(unit.player_id),
+            }
+        }
+        let holder = match holder {
+            Some(player_id) if !shared && *player_id != 0 => Some(player_id),
+            _ => None,
+        };
+
+        match holder {
+            Some(player_id) => {
+                if victory.start(CountdownKind::Artifacts, player_id) {
+                    announcements.announce(format!("{} has captured all of the artifacts and ruins.",
+                                                   players.name(player_id)));
+                }
+            }
+            None => {
+                if let Some(countdown) = victory.cancel(CountdownKind::Artifacts) {
+                    announcements.announce(format!("{} has lost control of the artifacts and ruins.",
+                                                   players.name(countdown.player_id)));
+                }
+            }
+        }
+
+        // Scenarios can start with wonders on the map, but gaia's don't count down
+        for building in building_completed.read(&mut self.building_completed) {
+            if *building.player_id != 0 && unit::is_wonder(building.unit_id) &&
+               victory.start(CountdownKind::Wonder(building.entity_id), building.player_id) {
+                announcements.announce(format!("{} has built a wonder.", players.name(building.player_id)));
+            }
+        }
+        // A wonder that changes hands starts counting down again for its new owner
+        for conversion in conversions.read(&mut self.unit_converted) {
+            let kind = CountdownKind::Wonder(conversion.entity_id);
+            if victory.countdown(kind).is_none() {
+                continue;
+            }
+            if *conversion.to_player_id == 0 {
+                victory.cancel(kind);
+                announcements.announce(format!("{}'s wonder has been lost.",
+                                               players.name(conversion.from_player_id)));
+            } else if victory.start(kind, conversion.to_player_id) {
+                announcements.announce(format!("{} has taken {}'s wonder.",
+                                               players.name(conversion.to_player_id),
+                                               players.name(conversion.from_player_id)));
+            }
+        }
+        for death in unit_died.read(&mut self.unit_died) {
+            if let Some(countdown) = victory.cancel(CountdownKind::Wonder(death.entity_id)) {
+                announcements.announce(format!("{}'s wonder has been destroyed.",
+                                               players.name(countdown.player_id)));
+            }
+        }
+
+        for update in victory.update(time_step) {
+            match update {
+                CountdownUpdate::YearsLeft(countdown) => {
+                    announcements.announce(format!("{} will win in {} years.",
+                                                   players.name(countdown.player_id),
+                                                   countdown.years_left()));
+                }
+                CountdownUpdate::Won(countdown) => {
+                    announcements.announce(format!("{} has won the game!",
+                                                   players.name(countdown.player_id)));
+                }
+            }
+        }
+    }
+}
+
@@ -0,0 +1,260 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::{EmpiresDbRef, InteractionMode};
+use ecs::component::{ActionQueueComponent, ArtifactComponent, ConstructionComponent, DecayComponent,
+                     GraphicComponent, HealthComponent, LineOfSightComponent, ResourceComponent,
+                     TrainingQueueComponent, TransformComponent, UnitComponent, VelocityComponent};
+use ecs::resource::{OccupiedTiles, PlayerTechs, SpawnQueue};
+use identifier::{CivilizationId, PlayerId, UnitId};
+use nalgebra::Vector2;
+use partition::GridPartition;
+use specs::{self, Index, Join};
+use types::{Fixed, Vector3};
+use util::unit;
+
+/// Everything needed to create a unit
+#[derive(Clone, Debug)]
+pub struct UnitSpawn {
+    pub player_id: PlayerId,
+    pub civilization_id: CivilizationId,
+    pub unit_id: UnitId,
+    pub position: Vector3,
+    pub rotation: Fixed,
+
+    /// Seconds until the unit goes away on its own (rubble); None for units that stay
+    pub lifetime: Option<Fixed>,
+}
+
+persist_struct!(UnitSpawn { player_id, civilization_id, unit_id, position, rotation, lifetime });
+
+/// Turns an existing unit into a different kind of unit (such as when it's upgraded)
+#[derive(Clone, Debug)]
+pub struct UnitMorph {
+    pub entity_id: Index,
+    pub unit_id: UnitId,
+}
+
+persist_struct!(UnitMorph { entity_id, unit_id });
+
+/// Builds complete unit entities out of their empires.dat definitions. This is the only place
+/// that should know which components make up a unit.
+///
+/// Entities can only be created with full component access between ticks, so systems that
+/// want to create units (training, map generation, the console) push a `UnitSpawn` onto the
+/// `SpawnQueue` resource instead, and the factory creates them before the next tick.
+#[derive(Clone)]
+pub struct UnitFactory {
+    empires: EmpiresDbRef,
+}
+
+impl UnitFactory {
+    pub fn new(empires: EmpiresDbRef) -> UnitFactory {
+        UnitFactory { empires: empires }
+    }
+
+    /// Creates the unit, or returns None if empires.dat doesn't define it (scenarios made for the
+    /// full game can use units that the demo's data doesn't have)
+    ///
+    /// Units come out as whatever the player has upgraded them to, so training a Clubman after
+    /// researching the Axeman gives an Axeman.
+    pub fn create(&self, world: &mut specs::World, spawn: &UnitSpawn) -> Option<specs::Entity> {
+        let unit_id = world.read_resource::<PlayerTechs>().upgraded_unit(spawn.player_id, spawn.unit_id);
+        let unit_info = match self.empires.find_unit(spawn.civilization_id, unit_id) {
+            Some(unit_info) => unit_info,
+            None => {
+                log_warn!("Skipping unit {:?} for civilization {:?}: it isn't in empires.dat",
+                          unit_id,
+                          spawn.civilization_id);
+                return None;
+            }
+        };
+        let transform = TransformComponent::new(spawn.position, spawn.rotation);
+
+        let mut graphic = GraphicComponent::new();
+        graphic.player_id = spawn.player_id;
+        graphic.graphic_id = unit_info.standing_graphic;
+
+        let footprint = if unit::blocks_tiles(unit_info) {
+            Some(unit::footprint_tiles(unit_info, &transform))
+        } else {
+            None
+        };
+
+        let entity = world.create_now()
+            .with(ActionQueueComponent::new())
+            .with(transform)
+            .with(graphic)
+            .with(UnitComponent::new(spawn.player_id, spawn.civilization_id, unit_id))
+            .with(VelocityComponent::new())
+            .with(LineOfSightComponent::new(unit_info.line_of_sight.into()))
+            .build();
+
+        // Things without hit points (corpses, rubble, decorations) can't be killed
+        if unit_info.hit_points > 0 {
+            world.write::<HealthComponent>().insert(entity, HealthComponent::new(unit_info.hit_points.into()));
+        }
+
+        // Anything that trains units or researches technologies has a queue for them
+        if !self.empires.civilization(spawn.civilization_id).units_trained_at(unit_id).is_empty() ||
+           !self.empires.researched_at(unit_id).is_empty() {
+            world.write::<TrainingQueueComponent>().insert(entity, TrainingQueueComponent::new());
+        }
+
+        if unit::is_artifact(unit_info) {
+            world.write::<ArtifactComponent>().insert(entity, ArtifactComponent::new());
+        }
+
+        // TODO: Scatter fish (and the rest) over the map once there's a random map generator;
+        // until then they only come from scenarios
+        if unit_info.interaction_mode == InteractionMode::Resource {
+            if let Some((resource_type, amount)) = unit_info.stored_resource() {
+                let mut resource = ResourceComponent::new(resource_type, amount.into());
+                resource.rot_rate = unit_info.resource_decay().max(0f32).into();
+                world.write::<ResourceComponent>().insert(entity, resource);
+            }
+        }
+
+        if let Some(lifetime) = spawn.lifetime {
+            world.write::<DecayComponent>().insert(entity, DecayComponent::new(lifetime));
+        }
+
+        if let Some(footprint) = footprint {
+            world.write_resource::<OccupiedTiles>().occupy(entity.get_id(), footprint);
+        }
+        world.write_resource::<GridPartition>()
+            .update_entity(entity.get_id(),
+                           &Vector2::new(spawn.position.x.into(), spawn.position.y.into()));
+        Some(entity)
+    }
+
+    /// Creates a building that still has to be built. It starts out with one hit point and gets
+    /// the rest as it's built.
+    pub fn create_foundation(&self, world: &mut specs::World, spawn: &UnitSpawn) -> Option<specs::Entity> {
+        let entity = match self.create(world, spawn) {
+            Some(entity) => entity,
+            None => return None,
+        };
+        // It may have been upgraded into something else
+        let unit_id = world.read::<UnitComponent>().get(entity).unwrap().unit_id;
+        let unit_info = self.empires.unit(spawn.civilization_id, unit_id);
+        world.write::<ConstructionComponent>().insert(entity, ConstructionComponent::new());
+        if let Some(health) = world.write::<HealthComponent>().get_mut(entity) {
+            health.hit_points = 1.into();
+        }
+        if let Some(graphic) = world.write::<GraphicComponent>().get_mut(entity) {
+            graphic.set_graphic(unit_info.construction_graphic_id().or(unit_info.standing_graphic));
+        }
+        Some(entity)
+    }
+
+    /// Creates every unit that systems queued up during the last tick, in the order they were queued,
+    /// then the foundations, then applies the queued morphs
+    pub fn create_queued(&self, world: &mut specs::World) {
+        let spawns = world.write_resource::<SpawnQueue>().take();
+        for spawn in &spawns {
+            self.create(world, spawn);
+        }
+        let foundations = world.write_resource::<SpawnQueue>().take_foundations();
+        for spawn in &foundations {
+            self.create_foundation(world, spawn);
+        }
+        let morphs = world.write_resource::<SpawnQueue>().take_morphs();
+        for morph in &morphs {
+            self.morph(world, morph);
+        }
+    }
+
+    /// Changes a unit into another kind of unit in place. The entity keeps its ID, position,
+    /// action queue, and selection; its stats, graphics, and footprint become the new unit's.
+    /// Damage carries over in proportion to the new unit's hit points.
+    pub fn morph(&self, world: &mut specs::World, morph: &UnitMorph) {
+        let entity = {
+            let entities = world.entities();
+            let units = world.read::<UnitComponent>();
+            let found = (&entities, &units).iter().find(|&(entity, _)| entity.get_id() == morph.entity_id);
+            match found {
+                Some((entity, _)) => entity,
+                // The unit died before it could be morphed
+                None => return,
+            }
+        };
+
+        let (old_info, new_info) = {
+            let mut units = world.write::<UnitComponent>();
+            let unit = units.get_mut(entity).unwrap();
+            let new_info = match self.empires.find_unit(unit.civilization_id, morph.unit_id) {
+                Some(unit_info) => unit_info,
+                None => {
+                    log_warn!("Can't turn entity {} into unit {:?}: it isn't in empires.dat",
+                              morph.entity_id,
+                              morph.unit_id);
+                    return;
+                }
+            };
+            let old_info = self.empires.unit(unit.civilization_id, unit.unit_id);
+            unit.unit_id = morph.unit_id;
+            (old_info, new_info)
+        };
+
+        if let Some(graphic) = world.write::<GraphicComponent>().get_mut(entity) {
+            let old_walking = old_info.motion_params.as_ref().and_then(|params| params.walking_graphics[0]);
+            let new_walking = new_info.motion_params.as_ref().and_then(|params| params.walking_graphics[0]);
+            if graphic.graphic_id == old_info.standing_graphic {
+                graphic.set_graphic(new_info.standing_graphic);
+            } else if old_walking.is_some() && graphic.graphic_id == old_walking {
+                graphic.set_graphic(new_walking.or(new_info.standing_graphic));
+            }
+        }
+
+        if new_info.hit_points > 0 {
+            let mut healths = world.write::<HealthComponent>();
+            let max_hit_points: Fixed = new_info.hit_points.into();
+            let health = match healths.get(entity) {
+                Some(health) => {
+                    let mut health = health.clone();
+                    health.hit_points = health.hit_points * max_hit_points / health.max_hit_points;
+                    health.max_hit_points = max_hit_points;
+                    health
+                }
+                None => HealthComponent::new(max_hit_points),
+            };
+            healths.insert(entity, health);
+        }
+
+        if let Some(line_of_sight) = world.write::<LineOfSightComponent>().get_mut(entity) {
+            *line_of_sight = LineOfSightComponent::new(new_info.line_of_sight.into());
+        }
+
+        let footprint = if unit::blocks_tiles(new_info) {
+            let transforms = world.read::<TransformComponent>();
+            Some(unit::footprint_tiles(new_info, transforms.get(entity).unwrap()))
+        } else {
+            None
+        };
+        let mut occupied_tiles = world.write_resource::<OccupiedTiles>();
+        if let Some(footprint) = footprint {
+            occupied_tiles.update(entity.get_id(), footprint);
+        } else {
+            occupied_tiles.release(entity.get_id());
+        }
+    }
+}
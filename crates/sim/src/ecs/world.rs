@@ -0,0 +1,285 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::EmpiresDbRef;
+use ecs::event::{Blast, BuildingCompleted, PlayerDefeated, PlayerResigned, ResearchCompleted, ResourceDepleted,
+                 UnitConverted, UnitDied};
+use ecs::game_setup::{GameSetup, VictoryCondition};
+use ecs::unit_factory::{UnitFactory, UnitSpawn};
+use ecs::resource::{ActionBatcher, Announcements, EffectSpawner, Events, Exploration, GameRng, Messages,
+                    OccupiedTiles, PathFinder, PlayerStats, PlayerTechs, Players, Scores, Scripts,
+                    SimulationTick, SpawnQueue, Stockpiles, Terrain, Timeline, Victory};
+use ecs::system::{System, SystemAccess, SystemWrapper, assign_stages, ArtifactSystem,
+                  AttackGroundActionSystem, AttackMoveActionSystem, AttackUnitActionSystem, BlastSystem,
+                  BuildActionSystem, DeathSystem, DecaySystem, DiscoverySystem, ExplorationSystem,
+                  GarrisonActionSystem, GatherActionSystem, HuntingSystem, MoveToPositionActionSystem,
+                  OccupiedTileSystem, ProjectileSystem, ScoreSystem, ScriptSystem, TeardownSystem, TechSystem,
+                  TrainingSystem, UnitActionSystem, VelocitySystem, VictorySystem};
+use logging;
+use partition::GridPartition;
+use scn;
+use specs;
+use super::component::{ArtifactComponent, AttackGroundActionComponent, AttackMoveActionComponent, AttackUnitActionComponent, BuildActionComponent, CarryComponent, ConstructionComponent, GarrisonActionComponent, GatherActionComponent, GarrisonedComponent, ProjectileComponent, HealthComponent, LineOfSightComponent, DecayComponent, UnitComponent, OnScreenComponent, CameraComponent, MoveToPositionActionComponent, ResourceComponent, TransformComponent, GraphicComponent, ActionQueueComponent, VelocityComponent, SelectedUnitComponent, TrainingQueueComponent};
+use types::{Fixed, Vector3};
+
+const NUM_THREADS: usize = 4;
+const GRID_CELL_SIZE: i32 = 10; // in tiles
+const SIMULATION_PRIORITY: i32 = 1000;
+
+/// How many ticks the simulation runs per second of game time
+pub const UPDATES_PER_SECOND: u64 = 60;
+
+pub type WorldPlanner = specs::Planner<(SystemGroup, Fixed)>;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SystemGroup {
+    Normal,
+    Render,
+}
+
+/// Creates a planner for a world with the scenario's units in it and everything the simulation
+/// needs to run it. No systems are scheduled yet (see `simulation_systems`), and nothing the view
+/// needs is added; that's up to whoever is showing the world, if anyone is.
+pub fn create_simulation_planner(empires: &EmpiresDbRef,
+                                 scenario: &scn::Scenario,
+                                 setup: &GameSetup)
+                                 -> WorldPlanner {
+    let mut world = specs::World::new();
+    register_components(&mut world);
+    add_resources(&mut world, empires, scenario, setup);
+
+    // Create entities for each unit in the SCN
+    let unit_factory = UnitFactory::new(empires.clone());
+    for player_id in scenario.player_ids() {
+        let civ_id = match setup.player(player_id) {
+            Some(player) => player.civilization_id,
+            None => scenario.player_civilization_id(player_id),
+        };
+        for unit in scenario.player_units(player_id) {
+            // TODO: Use the bulk creation iterator for better performance
+            let spawn = UnitSpawn {
+                player_id: player_id,
+                civilization_id: civ_id,
+                unit_id: unit.unit_id,
+                position: Vector3::new(unit.position_x.into(),
+                                       unit.position_y.into(),
+                                       unit.position_z.into()),
+                rotation: unit.rotation.into(),
+                lifetime: None,
+            };
+            let entity = unit_factory.create(&mut world, &spawn);
+
+            // Buildings placed by the scenario start out finished (which starts wonder countdowns)
+            if let Some(entity) = entity {
+                let unit_id = world.read::<UnitComponent>().get(entity).map(|unit| unit.unit_id);
+                if let Some(unit_id) = unit_id {
+                    if empires.unit(civ_id, unit_id).is_building() {
+                        world.write_resource::<Events<BuildingCompleted>>().emit(BuildingCompleted {
+                            entity_id: entity.get_id(),
+                            player_id: player_id,
+                            unit_id: unit_id,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    world.add_resource(unit_factory);
+
+    WorldPlanner::new(world, NUM_THREADS)
+}
+
+/// Length of a single simulation tick. Everything that advances the simulation must use this
+/// so that the windowed and headless modes stay in lock step.
+pub fn time_step_seconds() -> Fixed {
+    Fixed::from(1) / Fixed::from(UPDATES_PER_SECOND as i32)
+}
+
+pub fn update_world(planner: &mut WorldPlanner, time_step: Fixed) {
+    {
+        let world = planner.mut_world();
+        let tick = {
+            let mut simulation_tick = world.write_resource::<SimulationTick>();
+            simulation_tick.tick += 1;
+            simulation_tick.tick
+        };
+        logging::set_tick(tick);
+        update_events(world);
+        world.write_resource::<Announcements>().update(time_step);
+
+        let unit_factory = world.read_resource::<UnitFactory>().clone();
+        unit_factory.create_queued(world);
+    }
+    planner.dispatch((SystemGroup::Normal, time_step));
+    planner.wait();
+}
+
+/// The systems that advance the simulation, in the order that they run in when they conflict
+pub fn simulation_systems(empires: &EmpiresDbRef) -> Vec<(&'static str, Box<System>)> {
+    let mut systems: Vec<(&'static str, Box<System>)> = Vec::new();
+    system!(systems, VelocitySystem);
+    system!(systems, UnitActionSystem, UnitActionSystem::new(empires.clone()));
+    system!(systems, TechSystem, TechSystem::new(empires.clone()));
+    system!(systems, TrainingSystem, TrainingSystem::new(empires.clone()));
+    system!(systems,
+            MoveToPositionActionSystem,
+            MoveToPositionActionSystem::new(empires.clone()));
+    system!(systems,
+            AttackGroundActionSystem,
+            AttackGroundActionSystem::new(empires.clone()));
+    system!(systems,
+            AttackUnitActionSystem,
+            AttackUnitActionSystem::new(empires.clone()));
+    system!(systems,
+            AttackMoveActionSystem,
+            AttackMoveActionSystem::new(empires.clone()));
+    system!(systems, BuildActionSystem, BuildActionSystem::new(empires.clone()));
+    system!(systems,
+            GarrisonActionSystem,
+            GarrisonActionSystem::new(empires.clone()));
+    system!(systems,
+            GatherActionSystem,
+            GatherActionSystem::new(empires.clone()));
+    system!(systems, ProjectileSystem);
+    system!(systems, BlastSystem, BlastSystem::new(empires.clone()));
+    system!(systems,
+            OccupiedTileSystem,
+            OccupiedTileSystem::new(empires.clone()));
+    system!(systems, DeathSystem, DeathSystem::new(empires.clone()));
+    system!(systems, HuntingSystem, HuntingSystem::new(empires.clone()));
+    system!(systems, DecaySystem);
+    system!(systems, ArtifactSystem, ArtifactSystem::new(empires.clone()));
+    system!(systems, DiscoverySystem, DiscoverySystem::new(empires.clone()));
+    system!(systems, ExplorationSystem);
+    system!(systems, TeardownSystem);
+    system!(systems, VictorySystem, VictorySystem::new(empires.clone()));
+    system!(systems, ScriptSystem);
+    system!(systems, ScoreSystem, ScoreSystem::new(empires.clone()));
+    systems
+}
+
+/// Adds the systems to the planner so that those that conflict with each other run in the
+/// order they're listed in, and the rest run alongside them
+pub fn schedule_systems(planner: &mut WorldPlanner, systems: Vec<(&'static str, Box<System>)>) {
+    let accesses: Vec<SystemAccess> = systems.iter().map(|&(_, ref system)| system.access()).collect();
+    let stages = assign_stages(&accesses);
+    for ((name, system), stage) in systems.into_iter().zip(stages) {
+        // Systems with equal priority keep their declaration order
+        let priority = SIMULATION_PRIORITY - stage as i32;
+        planner.add_system(SystemWrapper::new(system), name, priority);
+    }
+}
+
+fn register_components(world: &mut specs::World) {
+    world.register::<ActionQueueComponent>();
+    world.register::<ArtifactComponent>();
+    world.register::<AttackGroundActionComponent>();
+    world.register::<AttackMoveActionComponent>();
+    world.register::<AttackUnitActionComponent>();
+    world.register::<BuildActionComponent>();
+    world.register::<CameraComponent>();
+    world.register::<CarryComponent>();
+    world.register::<ConstructionComponent>();
+    world.register::<DecayComponent>();
+    world.register::<GarrisonActionComponent>();
+    world.register::<GarrisonedComponent>();
+    world.register::<GatherActionComponent>();
+    world.register::<GraphicComponent>();
+    world.register::<HealthComponent>();
+    world.register::<LineOfSightComponent>();
+    world.register::<MoveToPositionActionComponent>();
+    world.register::<OnScreenComponent>();
+    world.register::<ProjectileComponent>();
+    world.register::<ResourceComponent>();
+    world.register::<SelectedUnitComponent>();
+    world.register::<TrainingQueueComponent>();
+    world.register::<TransformComponent>();
+    world.register::<UnitComponent>();
+    world.register::<VelocityComponent>();
+}
+
+fn add_resources(world: &mut specs::World,
+                 empires: &EmpiresDbRef,
+                 scenario: &scn::Scenario,
+                 setup: &GameSetup) {
+    world.add_resource(EffectSpawner::new());
+    world.add_resource(GridPartition::new(GRID_CELL_SIZE, GRID_CELL_SIZE));
+
+    // The camera is the first entity whether anything is shown or not, so the scenario's units
+    // always get the same entity ids
+    world.create_now()
+        .with(TransformComponent::new(Vector3::new(0.into(), 0.into(), 0.into()), 0.into()))
+        .with(VelocityComponent::new())
+        .with(CameraComponent)
+        .build();
+
+    // Players
+    world.add_resource(Players::from_setup(scenario, setup));
+    world.add_resource(setup.settings.clone());
+
+    // Simulation resources
+    world.add_resource(SimulationTick::new());
+    world.add_resource(GameRng::new(setup.seed));
+
+    // Events
+    world.add_resource(Events::<UnitDied>::new());
+    world.add_resource(Events::<UnitConverted>::new());
+    world.add_resource(Events::<BuildingCompleted>::new());
+    world.add_resource(Events::<ResourceDepleted>::new());
+    world.add_resource(Events::<ResearchCompleted>::new());
+    world.add_resource(Events::<Blast>::new());
+    world.add_resource(Events::<PlayerResigned>::new());
+    world.add_resource(Events::<PlayerDefeated>::new());
+
+    // Unit resources
+    world.add_resource(ActionBatcher::new());
+    world.add_resource(SpawnQueue::new());
+    world.add_resource(PlayerTechs::new());
+    world.add_resource(PlayerStats::new());
+    world.add_resource(Stockpiles::from_scenario(scenario, &setup.settings));
+    world.add_resource(match setup.settings.victory_condition {
+        VictoryCondition::Standard => Victory::new(),
+        VictoryCondition::Conquest => Victory::conquest_only(),
+    });
+    world.add_resource(Announcements::new());
+    world.add_resource(Messages::from_scenario(scenario));
+    world.add_resource(Scores::new());
+    world.add_resource(Timeline::new());
+    world.add_resource(Scripts::new(&setup.scripts));
+
+    // Terrain resources
+    world.add_resource(OccupiedTiles::new());
+    world.add_resource(PathFinder::new(empires.clone()));
+    let terrain = Terrain::from(&scenario.map, empires.clone());
+    world.add_resource(Exploration::new(terrain.width(), terrain.height(), setup.settings.map_visibility));
+    world.add_resource(terrain);
+}
+
+fn update_events(world: &mut specs::World) {
+    world.write_resource::<Events<UnitDied>>().update();
+    world.write_resource::<Events<UnitConverted>>().update();
+    world.write_resource::<Events<BuildingCompleted>>().update();
+    world.write_resource::<Events<ResourceDepleted>>().update();
+    world.write_resource::<Events<ResearchCompleted>>().update();
+    world.write_resource::<Events<Blast>>().update();
+    world.write_resource::<Events<PlayerResigned>>().update();
+    world.write_resource::<Events<PlayerDefeated>>().update();
+}
@@ -19,14 +19,26 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::MoveToPositionParams;
+extern crate chariot_dat as dat;
+extern crate chariot_identifier as identifier;
+extern crate chariot_scn as scn;
 
-/// Enum of possible actions a unit can undertake
-#[derive(Clone, Debug)]
-pub enum Action {
-    /// Clears a unit's action queue
-    ClearQueue,
+#[macro_use]
+extern crate chariot_types as types;
 
-    /// Instructs a unit to move to a given position on the map
-    MoveToPosition(MoveToPositionParams),
-}
+#[macro_use]
+extern crate lazy_static;
+
+extern crate nalgebra;
+extern crate specs;
+
+#[macro_use]
+mod macros;
+
+pub mod action;
+#[cfg(feature = "bot_api")]
+pub mod client;
+pub mod ecs;
+pub mod logging;
+pub mod partition;
+pub mod util;
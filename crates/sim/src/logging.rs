@@ -0,0 +1,264 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Game-wide logging. Records are filtered by level per module, tagged with the simulation tick
+//! they happened on, and go to stdout, an optional log file, and the in-game log overlay.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+/// Environment variable with the filter to log with, such as "info,ecs::system=debug"
+pub const LOG_FILTER_VAR: &'static str = "CHARIOT_LOG";
+
+/// How many of the latest records are kept around for the overlay
+const RECENT_RECORD_COUNT: usize = 100;
+
+/// The game and the simulation log the same way, so records leave off which of them it was
+const CRATE_PREFIXES: [&'static str; 2] = ["chariot::", "chariot_sim::"];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        use self::Level::*;
+        match *self {
+            Error => "error",
+            Warn => "warn",
+            Info => "info",
+            Debug => "debug",
+            Trace => "trace",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Level> {
+        use self::Level::*;
+        [Error, Warn, Info, Debug, Trace].iter().cloned().find(|level| level.name() == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub tick: u64,
+    pub level: Level,
+    /// Module path without the crate name, such as "ecs::unit_factory"
+    pub module: String,
+    pub message: String,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "[tick {}] {} {}: {}",
+               self.tick,
+               self.level.name().to_uppercase(),
+               self.module,
+               self.message)
+    }
+}
+
+/// Which levels get logged for which modules. A module gets the level of the longest module path
+/// in the filter that it's in, or the default level if there isn't one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    default: Level,
+    modules: Vec<(String, Level)>,
+}
+
+impl Filter {
+    pub fn new(default: Level) -> Filter {
+        Filter {
+            default: default,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Reads a comma separated filter, where each part is either a level for every module or
+    /// "module=level" for one module and everything in it
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter::new(Level::Info);
+        for part in spec.split(',').map(|part| part.trim()).filter(|part| !part.is_empty()) {
+            let (module, level_name) = match part.find('=') {
+                Some(index) => (Some(&part[..index]), &part[index + 1..]),
+                None => (None, part),
+            };
+            let level = try!(Level::from_name(level_name)
+                .ok_or(format!("\"{}\" isn't a log level in \"{}\"", level_name, part)));
+            match module {
+                Some(module) => filter.modules.push((module.to_string(), level)),
+                None => filter.default = level,
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn level_for(&self, module: &str) -> Level {
+        self.modules
+            .iter()
+            .filter(|&&(ref path, _)| {
+                module == path || (module.starts_with(&path[..]) && module[path.len()..].starts_with("::"))
+            })
+            .max_by_key(|&&(ref path, _)| path.len())
+            .map(|&(_, level)| level)
+            .unwrap_or(self.default)
+    }
+
+    pub fn enabled(&self, level: Level, module: &str) -> bool {
+        level <= self.level_for(module)
+    }
+}
+
+struct Logger {
+    filter: Filter,
+    file: Option<File>,
+    recent: VecDeque<Record>,
+}
+
+lazy_static! {
+    static ref LOGGER: Mutex<Logger> = Mutex::new(Logger {
+        filter: Filter::new(Level::Info),
+        file: None,
+        recent: VecDeque::new(),
+    });
+}
+
+static TICK: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Sets up the filter, and the file that records are also written to
+pub fn init(filter: Filter, file_name: Option<&Path>) -> io::Result<()> {
+    let file = match file_name {
+        Some(file_name) => Some(try!(File::create(file_name))),
+        None => None,
+    };
+    let mut logger = LOGGER.lock().unwrap();
+    logger.filter = filter;
+    logger.file = file;
+    Ok(())
+}
+
+/// Sets the simulation tick that the following records happened on
+pub fn set_tick(tick: u64) {
+    TICK.store(tick as usize, Ordering::Relaxed);
+}
+
+/// Logs a message if the filter lets it through; use the log_* macros instead of calling this
+pub fn record(level: Level, module_path: &str, message: String) {
+    let module = module_name(module_path);
+
+    // A panic while logging shouldn't stop everything else from logging
+    let mut logger = match LOGGER.lock() {
+        Ok(logger) => logger,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if !logger.filter.enabled(level, module) {
+        return;
+    }
+
+    let record = Record {
+        tick: TICK.load(Ordering::Relaxed) as u64,
+        level: level,
+        module: module.to_string(),
+        message: message,
+    };
+    println!("{}", record);
+
+    let failed = match logger.file {
+        Some(ref mut file) => writeln!(file, "{}", record).is_err(),
+        None => false,
+    };
+    if failed {
+        println!("Failed to write to the log file; only logging to stdout from now on");
+        logger.file = None;
+    }
+
+    if logger.recent.len() == RECENT_RECORD_COUNT {
+        logger.recent.pop_front();
+    }
+    logger.recent.push_back(record);
+}
+
+/// The latest records, oldest first
+fn module_name(module_path: &str) -> &str {
+    CRATE_PREFIXES.iter()
+        .find(|prefix| module_path.starts_with(**prefix))
+        .map(|prefix| &module_path[prefix.len()..])
+        .unwrap_or(module_path)
+}
+
+pub fn recent_records(count: usize) -> Vec<Record> {
+    let logger = match LOGGER.lock() {
+        Ok(logger) => logger,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let skip = logger.recent.len().saturating_sub(count);
+    logger.recent.iter().skip(skip).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, Level, Record, module_name};
+
+    #[test]
+    fn test_parse_filter() {
+        let spec = "warn, ecs::system=debug,ecs::system::unit_selection_system=trace";
+        let filter = Filter::parse(spec).unwrap();
+        assert_eq!(Level::Warn, filter.level_for("game"));
+        assert_eq!(Level::Warn, filter.level_for("ecs::systems"));
+        assert_eq!(Level::Debug, filter.level_for("ecs::system"));
+        assert_eq!(Level::Debug, filter.level_for("ecs::system::death_system"));
+        assert_eq!(Level::Trace, filter.level_for("ecs::system::unit_selection_system"));
+
+        assert!(filter.enabled(Level::Error, "game"));
+        assert!(!filter.enabled(Level::Info, "game"));
+
+        assert_eq!(Filter::new(Level::Info), Filter::parse("").unwrap());
+        assert!(Filter::parse("ecs=loud").is_err());
+    }
+
+    #[test]
+    fn test_module_name() {
+        assert_eq!("ecs::unit_factory", module_name("chariot_sim::ecs::unit_factory"));
+        assert_eq!("game::hud", module_name("chariot::game::hud"));
+        assert_eq!("chariot_dat", module_name("chariot_dat"));
+    }
+
+    #[test]
+    fn test_record_display() {
+        let record = Record {
+            tick: 120,
+            level: Level::Warn,
+            module: "ecs::unit_factory".to_string(),
+            message: "Skipping unit".to_string(),
+        };
+        assert_eq!("[tick 120] WARN ecs::unit_factory: Skipping unit", record.to_string());
+    }
+}
@@ -0,0 +1,118 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// These log through the logging module, tagged with where they were logged from
+#[macro_export]
+macro_rules! log_error {
+    ( $($args:tt)* ) => {
+        $crate::logging::record($crate::logging::Level::Error, module_path!(), format!($($args)*))
+    }
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ( $($args:tt)* ) => {
+        $crate::logging::record($crate::logging::Level::Warn, module_path!(), format!($($args)*))
+    }
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ( $($args:tt)* ) => {
+        $crate::logging::record($crate::logging::Level::Info, module_path!(), format!($($args)*))
+    }
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ( $($args:tt)* ) => {
+        $crate::logging::record($crate::logging::Level::Debug, module_path!(), format!($($args)*))
+    }
+}
+
+// Implements `ecs::Persist` for a struct by persisting each of the listed fields in turn; every
+// field has to be listed, in the order the struct declares them
+#[macro_export]
+macro_rules! persist_struct {
+    ($name:ident) => {
+        impl $crate::ecs::Persist for $name {
+            fn write(&self, _writer: &mut $crate::action::Writer) {}
+
+            fn read(_reader: &mut $crate::action::Reader) -> Result<$name, String> {
+                Ok($name)
+            }
+        }
+    };
+    ($name:ident { $($field:ident),* }) => {
+        impl $crate::ecs::Persist for $name {
+            fn write(&self, writer: &mut $crate::action::Writer) {
+                $( $crate::ecs::Persist::write(&self.$field, writer); )*
+            }
+
+            fn read(reader: &mut $crate::action::Reader) -> Result<$name, String> {
+                Ok($name {
+                    $( $field: try!($crate::ecs::Persist::read(reader)), )*
+                })
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! fetch_components {
+    (
+        $arg:expr,
+        $entities:ident,
+        [
+            $( components($name:ident: $typ:path), )*
+            $( mut components($mut_name:ident: $mut_typ:path), )*
+            $( resource($res_name:ident: $res_typ:path), )*
+            $( mut resource($mut_res_name:ident: $mut_res_typ:path), )*
+        ]
+    ) => {
+        let (
+            $entities,
+            $( $name, )*
+            $( $res_name, )*
+            $( mut $mut_name, )*
+            $( mut $mut_res_name, )*
+        ) = $arg.fetch(|w| {
+            (
+                w.entities(),
+                $( w.read::<$typ>(), )*
+                $( w.read_resource::<$res_typ>(), )*
+                $( w.write::<$mut_typ>(), )*
+                $( w.write_resource::<$mut_res_typ>(), )*
+            )
+        });
+    };
+}
+
+// Pushes a system onto a list for `ecs::schedule_systems`, named after its type
+#[macro_export]
+macro_rules! system {
+    ($systems:expr, $typ:ident) => {
+        $systems.push((stringify!($typ), Box::new($typ::new()) as Box<$crate::ecs::system::System>));
+    };
+    ($systems:expr, $typ:ident, $inst:expr) => {
+        $systems.push((stringify!($typ), Box::new($inst) as Box<$crate::ecs::system::System>));
+    };
+}
@@ -19,16 +19,20 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use action::{Reader, Writer};
+use ecs::Persist;
 use nalgebra::Vector2;
 
 use std::collections::{HashMap, HashSet};
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
 struct CellKey {
     row: i32,
     col: i32,
 }
 
+persist_struct!(CellKey { row, col });
+
 impl CellKey {
     pub fn new(row: i32, col: i32) -> CellKey {
         CellKey {
@@ -38,10 +42,13 @@ impl CellKey {
     }
 }
 
+#[derive(Clone)]
 struct Cell {
     entities: Vec<GridEntity>,
 }
 
+persist_struct!(Cell { entities });
+
 impl Cell {
     fn new() -> Cell {
         Cell { entities: Vec::new() }
@@ -51,6 +58,14 @@ impl Cell {
         self.entities.push(entity);
     }
 
+    /// Moves an entity that's already in the cell, or adds it if it isn't
+    fn update(&mut self, entity: GridEntity) {
+        match self.entities.iter_mut().find(|other| other.entity_id == entity.entity_id) {
+            Some(other) => other.position = entity.position,
+            None => self.entities.push(entity),
+        }
+    }
+
     fn remove(&mut self, entity_id: u32) {
         if let Some(index) = self.entities.iter().position(|entity| entity.entity_id == entity_id) {
             self.entities.swap_remove(index);
@@ -69,6 +84,21 @@ pub struct GridEntity {
     position: Vector2<i32>,
 }
 
+impl Persist for GridEntity {
+    fn write(&self, writer: &mut Writer) {
+        self.entity_id.write(writer);
+        self.position.x.write(writer);
+        self.position.y.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<GridEntity, String> {
+        let entity_id = try!(u32::read(reader));
+        let x = try!(i32::read(reader));
+        let y = try!(i32::read(reader));
+        Ok(GridEntity::new(entity_id, Vector2::new(x, y)))
+    }
+}
+
 impl GridEntity {
     fn new(entity_id: u32, position: Vector2<i32>) -> GridEntity {
         GridEntity {
@@ -78,6 +108,7 @@ impl GridEntity {
     }
 }
 
+#[derive(Clone)]
 pub struct GridPartition {
     cell_width: i32,
     cell_height: i32,
@@ -85,6 +116,8 @@ pub struct GridPartition {
     cells: HashMap<CellKey, Cell>,
 }
 
+persist_struct!(GridPartition { cell_width, cell_height, entities, cells });
+
 /// Infinite grid spatial partition
 impl GridPartition {
     pub fn new(cell_width: i32, cell_height: i32) -> GridPartition {
@@ -99,13 +132,17 @@ impl GridPartition {
     /// Tells the grid where an entity is so that it can be queried later
     pub fn update_entity(&mut self, entity_id: u32, position: &Vector2<i32>) {
         let cell_key = self.cell_key(&position);
-        if !self.entities.contains_key(&entity_id) {
-            self.entities.insert(entity_id, cell_key);
-        } else {
-            let old_cell_key = *self.entities.get(&entity_id).unwrap();
+        let entity = GridEntity::new(entity_id, *position);
+
+        // Moving units call this every tick, and most of the time they're still in the same cell
+        if self.entities.get(&entity_id) == Some(&cell_key) {
+            self.cell_mut(cell_key).update(entity);
+            return;
+        }
+        if let Some(old_cell_key) = self.entities.insert(entity_id, cell_key) {
             self.remove_from_cell(old_cell_key, entity_id);
         }
-        self.add_to_cell(cell_key, GridEntity::new(entity_id, *position));
+        self.add_to_cell(cell_key, entity);
     }
 
     /// Returns the entity IDs that lie in the cells overlapped by the given bounds
@@ -131,6 +168,34 @@ impl GridPartition {
         entities
     }
 
+    /// Returns the entity IDs whose (tile) positions are within the radius of the center
+    pub fn query_radius(&self, center: &Vector2<i32>, radius: i32) -> HashSet<u32> {
+        let start = self.row_col(&Vector2::new(center.x - radius, center.y - radius));
+        let end = self.row_col(&Vector2::new(center.x + radius, center.y + radius));
+
+        let mut entities = HashSet::new();
+        for row in start.y..(end.y + 1) {
+            for col in start.x..(end.x + 1) {
+                if let Some(cell) = self.cell(CellKey::new(row, col)) {
+                    for entity in cell.entities() {
+                        let (dx, dy) = (entity.position.x - center.x, entity.position.y - center.y);
+                        if dx * dx + dy * dy <= radius * radius {
+                            entities.insert(entity.entity_id);
+                        }
+                    }
+                }
+            }
+        }
+        entities
+    }
+
+    /// Forgets about an entity (such as when it dies)
+    pub fn remove_entity(&mut self, entity_id: u32) {
+        if let Some(cell_key) = self.entities.remove(&entity_id) {
+            self.remove_from_cell(cell_key, entity_id);
+        }
+    }
+
     pub fn contains(&self, entity_id: u32) -> bool {
         self.entities.contains_key(&entity_id)
     }
@@ -234,6 +299,32 @@ mod tests {
         assert_eq!(&vec![entity1], grid.cell_mut(CellKey::new(1, 2)).entities());
     }
 
+    #[test]
+    fn test_grid_update_entity_within_cell() {
+        let mut grid = GridPartition::new(10, 10);
+        grid.update_entity(1, &v(5, 5));
+        grid.update_entity(2, &v(6, 6));
+        grid.update_entity(1, &v(7, 5));
+
+        let entity1 = GridEntity::new(1, Vector2::new(7, 5));
+        let entity2 = GridEntity::new(2, Vector2::new(6, 6));
+        assert_eq!(&vec![entity1, entity2], grid.cell_mut(CellKey::new(0, 0)).entities());
+        assert_eq!(ids![1], grid.query(&v(7, 0), &v(9, 5)));
+    }
+
+    #[test]
+    fn test_grid_remove_entity() {
+        let mut grid = GridPartition::new(10, 10);
+        grid.update_entity(1, &v(5, 5));
+        grid.update_entity(1, &v(25, 15));
+        grid.update_entity(2, &v(26, 15));
+
+        grid.remove_entity(1);
+        grid.remove_entity(3); // shouldn't panic
+        assert!(!grid.contains(1));
+        assert_eq!(ids![2], grid.query(&v(0, 0), &v(30, 30)));
+    }
+
     #[test]
     fn test_grid_query() {
         let mut grid = GridPartition::new(10, 10);
@@ -247,4 +338,17 @@ mod tests {
         assert_eq!(ids![3], grid.query(&v(9, 0), &v(20, 10)));
         assert_eq!(ids![3], grid.query(&v(10, 0), &v(20, 10)));
     }
+
+    #[test]
+    fn test_grid_query_radius() {
+        let mut grid = GridPartition::new(10, 10);
+        grid.update_entity(1, &v(10, 10));
+        grid.update_entity(2, &v(13, 14));
+        grid.update_entity(3, &v(14, 14));
+        grid.update_entity(4, &v(7, 10));
+
+        assert_eq!(ids![1], grid.query_radius(&v(10, 10), 0));
+        assert_eq!(ids![1, 2, 4], grid.query_radius(&v(10, 10), 5));
+        assert_eq!(ids![1, 4], grid.query_radius(&v(9, 10), 3));
+    }
 }
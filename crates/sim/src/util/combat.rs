@@ -0,0 +1,201 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat;
+use specs::Index;
+use std::cmp;
+use types::{Fixed, Norm, Vector3};
+
+/// Damage done by one attack, empires.dat style: each attack class only hurts targets that have
+/// armor of the same class, armor takes away from the attack, and every hit does at least 1
+pub fn damage(attacks: &[(i16, i16)], armors: &[(i16, i16)]) -> i16 {
+    let mut total = 0;
+    for &(attack_class, attack) in attacks {
+        for &(armor_class, armor) in armors {
+            if attack_class == armor_class {
+                total += cmp::max(0, attack - armor);
+            }
+        }
+    }
+    cmp::max(1, total)
+}
+
+/// The unit's armor classes and amounts (units that can't fight have none)
+pub fn armors<'a>(unit_info: &'a dat::Unit) -> &'a [(i16, i16)] {
+    match unit_info.battle_params {
+        Some(ref params) => &params.armors,
+        None => &[],
+    }
+}
+
+/// Whether a blast hurts a unit. Units are only affected by blasts with the same or a lower level
+/// than their blast defense level. Level 0 blasts (catapult stones) also hit the attacker's own
+/// units, as they did in the original; other blasts (trample) only hurt other players' units.
+pub fn blast_hits(attack_level: i8, defense_level: i8, own_unit: bool) -> bool {
+    attack_level <= defense_level && (attack_level == 0 || !own_unit)
+}
+
+/// Damage a unit `distance` tiles from the center of a blast takes: all of it at the center,
+/// falling off to half at the edge (and never less than 1)
+pub fn blast_falloff(damage: i16, distance: Fixed, radius: Fixed) -> i16 {
+    if radius <= 0.into() || distance <= 0.into() {
+        return damage;
+    }
+    let distance = cmp::min(distance, radius);
+    let damage: Fixed = damage.into();
+    let scaled = damage - damage * distance / (radius * 2.into());
+    cmp::max(1, i16::from(scaled))
+}
+
+/// Whether the unit fires splash damage projectiles, so it can be told to attack a spot on the
+/// ground (catapults and ballistas)
+pub fn can_attack_ground(unit_info: &dat::Unit) -> bool {
+    match unit_info.battle_params {
+        Some(ref params) => params.blast_width > 0.0 && params.projectile_unit_id.is_some(),
+        None => false,
+    }
+}
+
+/// Distance between two points along the ground (ignoring elevation), which is what ranges
+/// are measured in
+pub fn ground_distance(from: &Vector3, to: &Vector3) -> Fixed {
+    Vector3::new(to.x - from.x, to.y - from.y, 0.into()).length()
+}
+
+/// Cuts a path off at the first point that's within range of the target, so a unit walking it
+/// stops as soon as it can attack
+pub fn path_into_range(path: &[Vector3], target: &Vector3, range: Fixed) -> Vec<Vector3> {
+    let mut trimmed = Vec::new();
+    for node in path {
+        trimmed.push(*node);
+        if ground_distance(node, target) <= range {
+            break;
+        }
+    }
+    trimmed
+}
+
+/// Where to run to, the given distance straight away from whatever is chasing it off. If the
+/// two stand in the same spot, it runs off along the x axis.
+pub fn flee_point(from: &Vector3, threat: &Vector3, distance: Fixed) -> Vector3 {
+    let mut away = Vector3::new(from.x - threat.x, from.y - threat.y, 0.into());
+    if away.normalize() == 0.into() {
+        away = Vector3::new(1.into(), 0.into(), 0.into());
+    }
+    Vector3::new(from.x + away.x * distance, from.y + away.y * distance, from.z)
+}
+
+/// What a unit on an attack-move goes after: the closest enemy (id, position, is building) within
+/// sight. Units come ahead of buildings, which aren't going anywhere or fighting back. Ties go to
+/// whichever comes first, so every peer picks the same.
+pub fn attack_move_target(from: &Vector3, sight: Fixed, enemies: &[(Index, Vector3, bool)]) -> Option<Index> {
+    let mut best: Option<(bool, Fixed, Index)> = None;
+    for &(entity_id, ref position, building) in enemies {
+        let distance = ground_distance(from, position);
+        if distance > sight {
+            continue;
+        }
+        match best {
+            Some((best_building, best_distance, _))
+                if (best_building, best_distance) <= (building, distance) => {}
+            _ => best = Some((building, distance, entity_id)),
+        }
+    }
+    best.map(|(_, _, entity_id)| entity_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attack_move_target, blast_falloff, blast_hits, damage, flee_point, path_into_range};
+    use types::{Fixed, Vector3};
+
+    #[test]
+    fn test_damage_by_class() {
+        // Melee (class 4) attack against melee armor
+        assert_eq!(5, damage(&[(4, 7)], &[(4, 2), (3, 0)]));
+        // Pierce (class 3) attack on a unit without pierce armor does nothing but the minimum
+        assert_eq!(1, damage(&[(3, 5)], &[(4, 2)]));
+        // Each matching class adds up
+        assert_eq!(9, damage(&[(4, 5), (11, 6)], &[(4, 0), (11, 2)]));
+    }
+
+    #[test]
+    fn test_damage_minimum() {
+        assert_eq!(1, damage(&[(4, 1)], &[(4, 10)]));
+        assert_eq!(1, damage(&[], &[]));
+    }
+
+    #[test]
+    fn test_blast_hits() {
+        // Catapults hit their own units as well as everyone else's
+        assert!(blast_hits(0, 0, true));
+        assert!(blast_hits(0, 2, false));
+        // Trample spares the attacker's units
+        assert!(blast_hits(1, 2, false));
+        assert!(!blast_hits(1, 2, true));
+        // Units with a lower defense level are out of reach
+        assert!(!blast_hits(2, 1, false));
+    }
+
+    #[test]
+    fn test_blast_falloff() {
+        assert_eq!(40, blast_falloff(40, 0.into(), 2.into()));
+        assert_eq!(30, blast_falloff(40, 1.into(), 2.into()));
+        assert_eq!(20, blast_falloff(40, 2.into(), 2.into()));
+        assert_eq!(20, blast_falloff(40, 5.into(), 2.into()));
+        assert_eq!(1, blast_falloff(1, 2.into(), 2.into()));
+        assert_eq!(40, blast_falloff(40, 1.into(), 0.into()));
+    }
+
+    #[test]
+    fn test_path_into_range() {
+        let point = |x: i32| Vector3::new(x.into(), 0.into(), 0.into());
+        let path = vec![point(1), point(2), point(3), point(4)];
+        let target = point(10);
+        assert_eq!(vec![point(1), point(2), point(3)],
+                   path_into_range(&path, &target, 7.into()));
+        assert_eq!(path, path_into_range(&path, &target, Fixed::from(2)));
+        assert_eq!(vec![point(1)], path_into_range(&path, &target, 20.into()));
+    }
+
+    #[test]
+    fn test_attack_move_target() {
+        let point = |x: i32| Vector3::new(x.into(), 0.into(), 0.into());
+        let from = point(0);
+        assert_eq!(None, attack_move_target(&from, 5.into(), &[(1, point(6), false)]));
+        assert_eq!(Some(2),
+                   attack_move_target(&from, 5.into(), &[(1, point(4), false), (2, point(3), false)]));
+        // A building right next to the unit still comes after a unit further off
+        assert_eq!(Some(1),
+                   attack_move_target(&from, 5.into(), &[(1, point(4), false), (2, point(1), true)]));
+        assert_eq!(Some(2), attack_move_target(&from, 5.into(), &[(2, point(1), true), (3, point(1), true)]));
+    }
+
+    #[test]
+    fn test_flee_point() {
+        let at = |x: i32, y: i32| Vector3::new(x.into(), y.into(), 0.into());
+        assert_eq!(at(8, 2), flee_point(&at(2, 2), &at(0, 2), 6.into()));
+        assert_eq!(at(2, -4), flee_point(&at(2, 2), &at(2, 5), 6.into()));
+        // Right on top of the threat still gets it moving
+        assert_eq!(at(6, 2), flee_point(&at(2, 2), &at(2, 2), 4.into()));
+    }
+}
@@ -0,0 +1,216 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Works out what a right-click means from what's under the mouse
+
+use dat;
+use util::garrison;
+
+/// Unit classes in empires.dat
+const TRADE_BOAT_CLASS_ID: i16 = 2;
+const VILLAGER_CLASS_ID: i16 = 4;
+const TRANSPORT_BOAT_CLASS_ID: i16 = 20;
+const FISHING_BOAT_CLASS_ID: i16 = 21;
+const WAR_BOAT_CLASS_ID: i16 = 22;
+
+/// How the player giving the command is related to the owner of the unit under the mouse
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Relation {
+    Own,
+    Ally,
+    Enemy,
+    Gaia,
+}
+
+/// What matters about the unit under the mouse
+#[derive(Copy, Clone, Debug)]
+pub struct ContextTarget {
+    pub relation: Relation,
+    pub resource: bool,
+    pub building: bool,
+    pub damaged: bool,
+    pub foundation: bool,
+    pub transport: bool,
+
+    /// A building that units can garrison in
+    pub shelter: bool,
+}
+
+/// What matters about a unit being given the command
+#[derive(Copy, Clone, Debug)]
+pub struct Commander {
+    pub can_attack: bool,
+
+    /// Villagers gather, build, and repair
+    pub worker: bool,
+
+    /// Villagers and fishing boats gather resources
+    pub gatherer: bool,
+    pub can_board: bool,
+}
+
+impl ContextTarget {
+    /// `foundation` is for buildings that haven't been finished yet
+    pub fn new(unit_info: &dat::Unit, relation: Relation, damaged: bool, foundation: bool) -> ContextTarget {
+        ContextTarget {
+            relation: relation,
+            resource: unit_info.interaction_mode == dat::InteractionMode::Resource,
+            building: unit_info.is_building(),
+            damaged: damaged,
+            foundation: foundation,
+            transport: unit_info.class_id() == TRANSPORT_BOAT_CLASS_ID,
+            shelter: garrison::capacity(unit_info) > 0,
+        }
+    }
+}
+
+impl Commander {
+    pub fn new(unit_info: &dat::Unit) -> Commander {
+        let moves = match unit_info.motion_params {
+            Some(ref params) => params.speed > 0f32,
+            None => false,
+        };
+        let can_attack = match unit_info.battle_params {
+            Some(ref params) => !params.attacks.is_empty(),
+            None => false,
+        };
+        let ship = [TRADE_BOAT_CLASS_ID, TRANSPORT_BOAT_CLASS_ID, FISHING_BOAT_CLASS_ID, WAR_BOAT_CLASS_ID]
+            .contains(&unit_info.class_id());
+        Commander {
+            can_attack: can_attack,
+            worker: unit_info.class_id() == VILLAGER_CLASS_ID,
+            gatherer: [VILLAGER_CLASS_ID, FISHING_BOAT_CLASS_ID].contains(&unit_info.class_id()),
+            can_board: moves && !ship,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ContextCommand {
+    Move,
+    Attack,
+    Gather,
+    Repair,
+    Build,
+    Garrison,
+}
+
+/// The command a unit gets for a right-click on the target (or on the ground, with no target)
+pub fn context_command(commander: &Commander, target: Option<&ContextTarget>) -> ContextCommand {
+    let target = match target {
+        Some(target) => target,
+        None => return ContextCommand::Move,
+    };
+
+    if target.resource {
+        return if commander.gatherer { ContextCommand::Gather } else { ContextCommand::Move };
+    }
+
+    match target.relation {
+        Relation::Own | Relation::Ally => {
+            if target.foundation && commander.worker {
+                ContextCommand::Build
+            } else if target.building && target.damaged && commander.worker {
+                ContextCommand::Repair
+            } else if target.transport && commander.can_board && target.relation == Relation::Own {
+                ContextCommand::Garrison
+            } else if target.shelter && commander.worker && target.relation == Relation::Own {
+                ContextCommand::Garrison
+            } else {
+                ContextCommand::Move
+            }
+        }
+        Relation::Enemy | Relation::Gaia => {
+            if commander.can_attack { ContextCommand::Attack } else { ContextCommand::Move }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Commander, ContextCommand, ContextTarget, Relation, context_command};
+
+    fn target(relation: Relation) -> ContextTarget {
+        ContextTarget {
+            relation: relation,
+            resource: false,
+            building: false,
+            damaged: false,
+            foundation: false,
+            transport: false,
+            shelter: false,
+        }
+    }
+
+    const SOLDIER: Commander = Commander {
+        can_attack: true,
+        worker: false,
+        gatherer: false,
+        can_board: true,
+    };
+    const VILLAGER: Commander = Commander {
+        can_attack: true,
+        worker: true,
+        gatherer: true,
+        can_board: true,
+    };
+
+    #[test]
+    fn test_ground_and_enemies() {
+        assert_eq!(ContextCommand::Move, context_command(&SOLDIER, None));
+        assert_eq!(ContextCommand::Attack, context_command(&SOLDIER, Some(&target(Relation::Enemy))));
+        assert_eq!(ContextCommand::Move, context_command(&SOLDIER, Some(&target(Relation::Ally))));
+
+        let unarmed = Commander { can_attack: false, ..SOLDIER };
+        assert_eq!(ContextCommand::Move, context_command(&unarmed, Some(&target(Relation::Enemy))));
+    }
+
+    #[test]
+    fn test_resources() {
+        let tree = ContextTarget { resource: true, ..target(Relation::Gaia) };
+        assert_eq!(ContextCommand::Gather, context_command(&VILLAGER, Some(&tree)));
+        assert_eq!(ContextCommand::Move, context_command(&SOLDIER, Some(&tree)));
+
+        let fishing_boat = Commander { can_attack: false, gatherer: true, can_board: false, ..SOLDIER };
+        let fish = ContextTarget { resource: true, ..target(Relation::Gaia) };
+        assert_eq!(ContextCommand::Gather, context_command(&fishing_boat, Some(&fish)));
+    }
+
+    #[test]
+    fn test_own_buildings() {
+        let damaged = ContextTarget { building: true, damaged: true, ..target(Relation::Own) };
+        assert_eq!(ContextCommand::Repair, context_command(&VILLAGER, Some(&damaged)));
+        assert_eq!(ContextCommand::Move, context_command(&SOLDIER, Some(&damaged)));
+
+        let foundation = ContextTarget { building: true, foundation: true, ..target(Relation::Ally) };
+        assert_eq!(ContextCommand::Build, context_command(&VILLAGER, Some(&foundation)));
+
+        let transport = ContextTarget { transport: true, ..target(Relation::Own) };
+        assert_eq!(ContextCommand::Garrison, context_command(&SOLDIER, Some(&transport)));
+        let allied_transport = ContextTarget { transport: true, ..target(Relation::Ally) };
+        assert_eq!(ContextCommand::Move, context_command(&SOLDIER, Some(&allied_transport)));
+
+        let town_center = ContextTarget { building: true, shelter: true, ..target(Relation::Own) };
+        assert_eq!(ContextCommand::Garrison, context_command(&VILLAGER, Some(&town_center)));
+        assert_eq!(ContextCommand::Move, context_command(&SOLDIER, Some(&town_center)));
+    }
+}
@@ -0,0 +1,117 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Which buildings units can garrison in, and who goes where when the town bell rings
+
+use dat;
+use specs::Index;
+use types::{Fixed, Vector3};
+use util::combat;
+
+/// The town center in empires.dat
+const TOWN_CENTER_UNIT_ID: u32 = 109;
+const TOWER_CLASS_ID: i16 = 52;
+
+const TOWN_CENTER_CAPACITY: usize = 15;
+const TOWER_CAPACITY: usize = 5;
+
+/// How far from the building the town bell was rung at villagers hear it, in tiles
+pub const TOWN_BELL_RADIUS: i32 = 20;
+
+/// How many units fit inside the unit. The original game has no garrisons, so its data leaves
+/// town centers and towers empty; they get the capacities they were given later in the series.
+pub fn capacity(unit_info: &dat::Unit) -> usize {
+    if unit_info.garrison_capability() > 0 {
+        unit_info.garrison_capability() as usize
+    } else if *unit_info.id == TOWN_CENTER_UNIT_ID {
+        TOWN_CENTER_CAPACITY
+    } else if unit_info.class_id() == TOWER_CLASS_ID {
+        TOWER_CAPACITY
+    } else {
+        0
+    }
+}
+
+/// Buildings fire an extra arrow for every unit inside
+pub fn arrows(occupants: usize) -> usize {
+    1 + occupants
+}
+
+/// A building that units can garrison in
+#[derive(Clone, Debug)]
+pub struct Shelter {
+    pub entity_id: Index,
+    pub position: Vector3,
+    pub space: usize,
+}
+
+/// Sends each villager to the closest shelter that still has room for it; villagers closest to
+/// a shelter get first pick of its space. Returns pairs of villager and shelter IDs.
+pub fn assign_shelters(villagers: &[(Index, Vector3)], shelters: &[Shelter]) -> Vec<(Index, Index)> {
+    let mut options: Vec<(Fixed, usize, usize)> = Vec::new();
+    for (villager_index, &(_, ref position)) in villagers.iter().enumerate() {
+        for (shelter_index, shelter) in shelters.iter().enumerate() {
+            let distance = combat::ground_distance(position, &shelter.position);
+            options.push((distance, villager_index, shelter_index));
+        }
+    }
+    options.sort();
+
+    let mut space: Vec<usize> = shelters.iter().map(|shelter| shelter.space).collect();
+    let mut assigned = vec![false; villagers.len()];
+    let mut assignments = Vec::new();
+    for (_, villager_index, shelter_index) in options {
+        if assigned[villager_index] || space[shelter_index] == 0 {
+            continue;
+        }
+        assigned[villager_index] = true;
+        space[shelter_index] -= 1;
+        assignments.push((villagers[villager_index].0, shelters[shelter_index].entity_id));
+    }
+    assignments.sort_by_key(|&(villager_id, _)| villager_id);
+    assignments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shelter, assign_shelters};
+    use types::Vector3;
+
+    fn position(x: i32, y: i32) -> Vector3 {
+        Vector3::new(x.into(), y.into(), 0.into())
+    }
+
+    #[test]
+    fn test_assign_shelters() {
+        let shelters = vec![
+            Shelter { entity_id: 10, position: position(0, 0), space: 1 },
+            Shelter { entity_id: 11, position: position(10, 0), space: 5 },
+        ];
+        let villagers = vec![(1, position(2, 0)), (2, position(1, 0)), (3, position(9, 0))];
+
+        // The closer villager gets the last spot in the first shelter, and the other one goes to
+        // the next closest
+        assert_eq!(vec![(1, 11), (2, 10), (3, 11)], assign_shelters(&villagers, &shelters));
+
+        let full = vec![Shelter { entity_id: 10, position: position(0, 0), space: 0 }];
+        assert!(assign_shelters(&villagers, &full).is_empty());
+    }
+}
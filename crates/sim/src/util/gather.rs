@@ -0,0 +1,111 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use specs::Index;
+use std::cmp;
+use types::{Fixed, Vector3};
+use util::combat;
+
+/// Directions, around what's being gathered, of the spots that gatherers sharing it stand at.
+/// The first four are the sides; the rest are the corners.
+static GATHER_SPOT_DIRECTIONS: [(Fixed, Fixed); 8] = [(fixed_const!(1), fixed_const!(0)),
+                                                      (fixed_const!(-1), fixed_const!(0)),
+                                                      (fixed_const!(0), fixed_const!(1)),
+                                                      (fixed_const!(0), fixed_const!(-1)),
+                                                      (fixed_const!(0.7071), fixed_const!(0.7071)),
+                                                      (fixed_const!(-0.7071), fixed_const!(-0.7071)),
+                                                      (fixed_const!(0.7071), fixed_const!(-0.7071)),
+                                                      (fixed_const!(-0.7071), fixed_const!(0.7071))];
+
+/// How much a gatherer gets in one step: as much as it works for, but no more than the resource
+/// has left or than it has room to carry
+pub fn gather_amount(left: Fixed,
+                     work_rate: Fixed,
+                     time_step: Fixed,
+                     carried: Fixed,
+                     capacity: Fixed)
+                     -> Fixed {
+    let room = cmp::max(capacity - carried, 0.into());
+    cmp::max(cmp::min(cmp::min(work_rate * time_step, left), room), 0.into())
+}
+
+/// The candidate nearest to `from`; ties go to whichever comes first, so every peer picks the same
+pub fn closest(from: &Vector3, candidates: &[(Index, Vector3)]) -> Option<Index> {
+    let mut best: Option<(Fixed, Index)> = None;
+    for &(entity_id, ref position) in candidates {
+        let distance = combat::ground_distance(from, position);
+        match best {
+            Some((best_distance, _)) if best_distance <= distance => {}
+            _ => best = Some((distance, entity_id)),
+        }
+    }
+    best.map(|(_, entity_id)| entity_id)
+}
+
+/// Where the gatherer in the given slot stands when several share something (villagers around
+/// a carcass), so they spread out around it instead of crowding one side. Past eight
+/// gatherers, the spots are shared.
+pub fn gather_spot(center: &Vector3, distance: Fixed, slot: usize) -> Vector3 {
+    let (x, y) = GATHER_SPOT_DIRECTIONS[slot % GATHER_SPOT_DIRECTIONS.len()];
+    Vector3::new(center.x + x * distance, center.y + y * distance, center.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest, gather_amount, gather_spot};
+    use types::{Fixed, Vector3};
+    use util::combat;
+
+    #[test]
+    fn test_gather_amount_limits() {
+        let step = fixed_const!(0.5);
+        assert_eq!(Fixed::from(1), gather_amount(100.into(), 2.into(), step, 0.into(), 10.into()));
+        // Running out of room or out of resource cuts it short
+        assert_eq!(fixed_const!(0.25),
+                   gather_amount(100.into(), 2.into(), step, fixed_const!(9.75), 10.into()));
+        assert_eq!(fixed_const!(0.5), gather_amount(fixed_const!(0.5), 2.into(), step, 0.into(), 10.into()));
+        assert_eq!(Fixed::from(0), gather_amount(100.into(), 2.into(), step, 12.into(), 10.into()));
+    }
+
+    #[test]
+    fn test_closest() {
+        let at = |x: i32, y: i32| Vector3::new(x.into(), y.into(), 0.into());
+        assert_eq!(None, closest(&at(0, 0), &[]));
+        assert_eq!(Some(7), closest(&at(0, 0), &[(3, at(5, 5)), (7, at(1, 2)), (9, at(-4, 0))]));
+        assert_eq!(Some(3), closest(&at(0, 0), &[(3, at(2, 0)), (7, at(0, 2))]));
+    }
+
+    #[test]
+    fn test_gather_spots_surround_the_center() {
+        let center = Vector3::new(10.into(), 10.into(), 0.into());
+        let distance = Fixed::from(2);
+        assert_eq!(Vector3::new(12.into(), 10.into(), 0.into()), gather_spot(&center, distance, 0));
+        assert_eq!(Vector3::new(8.into(), 10.into(), 0.into()), gather_spot(&center, distance, 1));
+        assert_eq!(gather_spot(&center, distance, 0), gather_spot(&center, distance, 8));
+
+        let spots: Vec<Vector3> = (0..8).map(|slot| gather_spot(&center, distance, slot)).collect();
+        for (index, spot) in spots.iter().enumerate() {
+            let from_center = combat::ground_distance(&center, spot);
+            assert!(from_center > fixed_const!(1.99) && from_center < fixed_const!(2.01));
+            assert!(!spots[..index].contains(spot));
+        }
+    }
+}
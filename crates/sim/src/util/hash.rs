@@ -0,0 +1,94 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// Hasher used to fingerprint simulation state (FNV-1a, 64-bit).
+///
+/// The standard library's hasher is randomly keyed and its algorithm isn't guaranteed to stay
+/// the same between Rust releases, so it can't be used for values that get compared across runs,
+/// machines, or builds (replays, regression tests, desync checks).
+pub struct StateHasher {
+    state: u64,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+impl StateHasher {
+    pub fn new() -> StateHasher {
+        StateHasher { state: FNV_OFFSET_BASIS }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u8(&mut self, val: u8) {
+        self.write(&[val]);
+    }
+
+    pub fn write_u32(&mut self, val: u32) {
+        // Always little endian so that the hash is the same on every platform
+        self.write(&[val as u8, (val >> 8) as u8, (val >> 16) as u8, (val >> 24) as u8]);
+    }
+
+    pub fn write_u64(&mut self, val: u64) {
+        self.write_u32(val as u32);
+        self.write_u32((val >> 32) as u32);
+    }
+
+    pub fn write_i64(&mut self, val: i64) {
+        self.write_u64(val as u64);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateHasher;
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = StateHasher::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(0xcbf29ce484222325, hash(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, hash(b"a"));
+        assert_eq!(0x85944171f73967e8, hash(b"foobar"));
+    }
+
+    #[test]
+    fn test_write_u64_is_little_endian() {
+        let mut a = StateHasher::new();
+        a.write_u64(0x0102030405060708);
+
+        let mut b = StateHasher::new();
+        b.write(&[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(a.finish(), b.finish());
+    }
+}
@@ -19,4 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod combat;
+pub mod context_command;
+pub mod garrison;
+pub mod gather;
+pub mod hash;
+pub mod movement;
+pub mod selection;
+pub mod training;
 pub mod unit;
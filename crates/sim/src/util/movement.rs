@@ -0,0 +1,200 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use identifier::TerrainId;
+use std::ops::Rem;
+use types::Fixed;
+
+/// Classes in empires.dat that can't turn on the spot or start moving at full speed:
+/// boats (trade, transport, fishing, war) and siege weapons
+const SHIP_CLASS_IDS: [i16; 4] = [2, 20, 21, 22];
+const SIEGE_CLASS_ID: i16 = 13;
+
+/// Shallows are walkable water that slows down everything wading through it. AoE's terrain
+/// table has no speed field (terrain restrictions only say what's passable), so this lives here.
+const SHALLOWS_TERRAIN_ID: u8 = 4;
+const SHALLOWS_SPEED_MULTIPLIER: Fixed = fixed_const!(0.75);
+
+/// What it costs the path finder to walk one tile at full speed. Slower terrain costs more in
+/// proportion, so 12 keeps the cost of shallows (16) whole.
+pub const STEP_COST: i32 = 12;
+
+/// How a unit gets up to speed and changes direction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Handling {
+    /// Speed gained or lost per second
+    pub acceleration: Fixed,
+
+    /// Radians turned per second
+    pub turn_rate: Fixed,
+}
+
+/// Returns how the unit handles, or None if it turns instantly and
+/// starts and stops at full speed (like soldiers and villagers do)
+pub fn handling(unit_info: &dat::Unit) -> Option<Handling> {
+    let class_id = unit_info.class_id();
+    if SHIP_CLASS_IDS.contains(&class_id) {
+        Some(Handling {
+            acceleration: fixed_const!(1.0),
+            turn_rate: fixed_const!(2.0),
+        })
+    } else if class_id == SIEGE_CLASS_ID {
+        Some(Handling {
+            acceleration: fixed_const!(1.5),
+            turn_rate: fixed_const!(1.5),
+        })
+    } else {
+        None
+    }
+}
+
+/// Multiplier applied to the speed of units moving over the given terrain
+pub fn terrain_speed_multiplier(terrain_id: TerrainId) -> Fixed {
+    if *terrain_id == SHALLOWS_TERRAIN_ID { SHALLOWS_SPEED_MULTIPLIER } else { 1.into() }
+}
+
+/// What it costs the path finder to walk onto a tile of the given terrain. This uses the same
+/// multiplier units move at, so the route that looks cheapest is also the quickest to walk.
+pub fn terrain_step_cost(terrain_id: TerrainId) -> i32 {
+    step_cost(terrain_speed_multiplier(terrain_id))
+}
+
+fn step_cost(speed_multiplier: Fixed) -> i32 {
+    if speed_multiplier <= 0.into() {
+        STEP_COST
+    } else {
+        i32::from(Fixed::from(STEP_COST) / speed_multiplier)
+    }
+}
+
+/// Moves `current` towards `target` by no more than `max_change`
+pub fn approach(current: Fixed, target: Fixed, max_change: Fixed) -> Fixed {
+    if current < target {
+        if target - current > max_change { current + max_change } else { target }
+    } else {
+        if current - target > max_change { current - max_change } else { target }
+    }
+}
+
+/// Signed difference between two angles, in the range [-PI, PI]
+pub fn angle_difference(from: Fixed, to: Fixed) -> Fixed {
+    let difference = (to - from).rem(Fixed::two_pi());
+    if difference > Fixed::pi() {
+        difference - Fixed::two_pi()
+    } else if difference < -Fixed::pi() {
+        difference + Fixed::two_pi()
+    } else {
+        difference
+    }
+}
+
+/// Turns from `current` towards `target` the short way around, by no more than `max_turn`
+pub fn turn_towards(current: Fixed, target: Fixed, max_turn: Fixed) -> Fixed {
+    let difference = angle_difference(current, target);
+    if difference.abs() <= max_turn {
+        target
+    } else if difference > 0.into() {
+        current + max_turn
+    } else {
+        current - max_turn
+    }
+}
+
+/// Returns the new (heading, speed) of a unit with the given handling that wants to head
+/// towards `desired_heading` at `desired_speed`. Units facing more than a quarter turn away from
+/// where they want to go slow down to a stop while turning, so that they don't circle around
+/// their destination.
+pub fn steer(handling: &Handling,
+             heading: Fixed,
+             speed: Fixed,
+             desired_heading: Fixed,
+             desired_speed: Fixed,
+             time_step: Fixed)
+             -> (Fixed, Fixed) {
+    let new_heading = turn_towards(heading, desired_heading, handling.turn_rate * time_step);
+    let target_speed = if angle_difference(new_heading, desired_heading).abs() > Fixed::half_pi() {
+        0.into()
+    } else {
+        desired_speed
+    };
+    (new_heading, approach(speed, target_speed, handling.acceleration * time_step))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Handling, STEP_COST, angle_difference, approach, steer, terrain_step_cost, turn_towards};
+    use types::Fixed;
+
+    #[test]
+    fn test_terrain_step_cost() {
+        assert_eq!(STEP_COST, terrain_step_cost(0.into()));
+        // Shallows are walked at three quarters speed, so they cost a third more
+        assert_eq!(16, terrain_step_cost(4.into()));
+    }
+
+    #[test]
+    fn test_approach() {
+        assert_eq!(Fixed::from(1), approach(0.into(), 3.into(), 1.into()));
+        assert_eq!(Fixed::from(3), approach(2.into(), 3.into(), 2.into()));
+        assert_eq!(Fixed::from(2), approach(3.into(), 0.into(), 1.into()));
+        assert_eq!(Fixed::from(0), approach(1.into(), 0.into(), 5.into()));
+    }
+
+    #[test]
+    fn test_angle_difference() {
+        assert_eq!(Fixed::from(1), angle_difference(1.into(), 2.into()));
+        assert_eq!(Fixed::from(-1), angle_difference(2.into(), 1.into()));
+
+        // Going from just below a full turn to just above zero is a small turn to the left
+        assert_eq!(Fixed::from(2), angle_difference(Fixed::two_pi() - 1.into(), 1.into()));
+        assert_eq!(Fixed::from(-2), angle_difference(1.into(), Fixed::two_pi() - 1.into()));
+    }
+
+    #[test]
+    fn test_turn_towards() {
+        assert_eq!(Fixed::from(2), turn_towards(1.into(), 2.into(), 3.into()));
+        assert_eq!(Fixed::from(2), turn_towards(1.into(), 3.into(), 1.into()));
+        assert_eq!(Fixed::from(0), turn_towards(1.into(), (-2).into(), 1.into()));
+
+        // Turns the short way around through zero
+        let current = Fixed::from(6);
+        assert_eq!(current + 1.into(), turn_towards(current, 1.into(), 1.into()));
+    }
+
+    #[test]
+    fn test_steer() {
+        let handling = Handling {
+            acceleration: 1.into(),
+            turn_rate: 1.into(),
+        };
+
+        // Speeds up while heading roughly where it wants to go
+        assert_eq!((Fixed::from(0), Fixed::from(1)),
+                   steer(&handling, 0.into(), 0.into(), 0.into(), 2.into(), 1.into()));
+        assert_eq!((Fixed::from(1), Fixed::from(2)),
+                   steer(&handling, 0.into(), 1.into(), 1.into(), 2.into(), 1.into()));
+
+        // Slows down while turning around
+        assert_eq!((Fixed::from(1), Fixed::from(1)),
+                   steer(&handling, 0.into(), 2.into(), 3.into(), 2.into(), 1.into()));
+    }
+}
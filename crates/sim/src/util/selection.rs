@@ -0,0 +1,133 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Rules for what ends up selected, shared by click and box selection so they agree
+
+use dat;
+use identifier::PlayerId;
+use specs::Index;
+
+/// Unit classes in empires.dat that count as civilians
+const CIVILIAN_CLASS_IDS: [i16; 3] = [2 /* trade boat */, 4 /* villager */, 21 /* fishing boat */];
+
+/// When a selection box covers several kinds of units, only the highest kind is selected
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum SelectionClass {
+    Other,
+    Building,
+    Civilian,
+    Military,
+}
+
+pub fn selection_class(unit_info: &dat::Unit) -> SelectionClass {
+    if unit_info.is_building() {
+        return SelectionClass::Building;
+    }
+    if CIVILIAN_CLASS_IDS.contains(&unit_info.class_id()) {
+        return SelectionClass::Civilian;
+    }
+    let moves = match unit_info.motion_params {
+        Some(ref params) => params.speed > 0f32,
+        None => false,
+    };
+    if moves && unit_info.battle_params.is_some() {
+        SelectionClass::Military
+    } else {
+        SelectionClass::Other
+    }
+}
+
+/// A unit that could be selected
+#[derive(Copy, Clone, Debug)]
+pub struct Candidate {
+    pub entity_id: Index,
+    pub player_id: PlayerId,
+    pub class: SelectionClass,
+}
+
+/// Picks what a selection box selects out of the units inside it: only the local player's
+/// units, and of those only the highest class (so dragging over an army and the villagers
+/// behind it selects just the army)
+pub fn filter_box_selection(candidates: &[Candidate], local_player_id: PlayerId) -> Vec<Index> {
+    let own = candidates.iter().filter(|candidate| candidate.player_id == local_player_id);
+    let best = match own.clone().map(|candidate| candidate.class).max() {
+        Some(class) => class,
+        None => return Vec::new(),
+    };
+    own.filter(|candidate| candidate.class == best).map(|candidate| candidate.entity_id).collect()
+}
+
+/// Whether a unit can join an existing selection (rather than replacing it). Other players'
+/// units are only ever selected on their own, so that one selection never mixes owners.
+pub fn can_add_to_selection(selected_owners: &[PlayerId],
+                            player_id: PlayerId,
+                            local_player_id: PlayerId)
+                            -> bool {
+    player_id == local_player_id && selected_owners.iter().all(|&owner| owner == local_player_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::PlayerId;
+    use super::{Candidate, SelectionClass, can_add_to_selection, filter_box_selection};
+
+    fn candidate(entity_id: u32, player_id: u8, class: SelectionClass) -> Candidate {
+        Candidate {
+            entity_id: entity_id,
+            player_id: (player_id as usize).into(),
+            class: class,
+        }
+    }
+
+    #[test]
+    fn test_box_selection_prefers_military() {
+        let candidates = [candidate(1, 1, SelectionClass::Civilian),
+                          candidate(2, 1, SelectionClass::Military),
+                          candidate(3, 1, SelectionClass::Building),
+                          candidate(4, 1, SelectionClass::Military)];
+        assert_eq!(vec![2, 4], filter_box_selection(&candidates, 1.into()));
+
+        let candidates = [candidate(1, 1, SelectionClass::Building),
+                          candidate(2, 1, SelectionClass::Civilian)];
+        assert_eq!(vec![2], filter_box_selection(&candidates, 1.into()));
+    }
+
+    #[test]
+    fn test_box_selection_only_takes_own_units() {
+        let candidates = [candidate(1, 2, SelectionClass::Military),
+                          candidate(2, 0, SelectionClass::Other),
+                          candidate(3, 1, SelectionClass::Civilian)];
+        assert_eq!(vec![3], filter_box_selection(&candidates, 1.into()));
+        assert!(filter_box_selection(&candidates[..2], 1.into()).is_empty());
+    }
+
+    #[test]
+    fn test_selections_never_mix_owners() {
+        let local: PlayerId = 1.into();
+        let enemy: PlayerId = 2.into();
+        assert!(can_add_to_selection(&[], local, local));
+        assert!(can_add_to_selection(&[local, local], local, local));
+        assert!(!can_add_to_selection(&[local], enemy, local));
+        assert!(!can_add_to_selection(&[enemy], local, local));
+        assert!(!can_add_to_selection(&[], enemy, local));
+    }
+}
@@ -0,0 +1,68 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+/// Picks which queue each of `count` new units goes into when one train command is given to
+/// several buildings at once: each unit goes into the shortest queue (the first one on ties),
+/// skipping queues that are full. Returns one queue index per unit that fits.
+pub fn distribute(queue_lengths: &[usize], count: usize, max_length: usize) -> Vec<usize> {
+    let mut lengths = queue_lengths.to_vec();
+    let mut picks = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut shortest: Option<usize> = None;
+        for (index, &length) in lengths.iter().enumerate() {
+            if length >= max_length {
+                continue;
+            }
+            match shortest {
+                Some(best) if lengths[best] <= length => {}
+                _ => shortest = Some(index),
+            }
+        }
+        match shortest {
+            Some(index) => {
+                lengths[index] += 1;
+                picks.push(index);
+            }
+            None => break,
+        }
+    }
+    picks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distribute;
+
+    #[test]
+    fn test_fills_shortest_queue_first() {
+        assert_eq!(vec![1], distribute(&[2, 0, 1], 1, 5));
+        assert_eq!(vec![1, 1, 2, 0], distribute(&[2, 0, 1], 4, 5));
+        assert_eq!(vec![0, 1, 0, 1], distribute(&[0, 0], 4, 5));
+    }
+
+    #[test]
+    fn test_skips_full_queues() {
+        assert_eq!(vec![1, 1], distribute(&[5, 3], 3, 5));
+        assert!(distribute(&[5, 5], 1, 5).is_empty());
+        assert!(distribute(&[], 1, 5).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat;
+use ecs::{GraphicComponent, TransformComponent, UnitComponent};
+use ecs::resource::OccupiedTile;
+
+use identifier::{PlayerId, UnitId};
+use nalgebra::Vector3;
+use std::cmp;
+use types::{AABox, Fixed, ToPrimitive};
+
+pub fn selection_box(unit_info: &dat::Unit, transform: &TransformComponent) -> AABox {
+    let position = transform.position();
+    AABox::new(Vector3::new(position.x - unit_info.selection_shape_size_x.into(),
+                            position.y - unit_info.selection_shape_size_y.into(),
+                            position.z + unit_info.selection_shape_size_z.into()),
+               Vector3::new(position.x + unit_info.selection_shape_size_x.into(),
+                            position.y + unit_info.selection_shape_size_y.into(),
+                            position.z))
+}
+
+pub fn collision_box(unit_info: &dat::Unit, transform: &TransformComponent) -> AABox {
+    let position = transform.position();
+    AABox::new(Vector3::new(position.x - unit_info.collision_size_x.into(),
+                            position.y - unit_info.collision_size_y.into(),
+                            position.z + unit_info.collision_size_z.into()),
+               Vector3::new(position.x + unit_info.collision_size_x.into(),
+                            position.y + unit_info.collision_size_y.into(),
+                            position.z))
+}
+
+/// Class of artifacts and ruins in empires.dat
+const ARTIFACT_CLASS_ID: i16 = 1;
+
+/// Whether the unit is an artifact or ruin that players capture by standing next to it
+pub fn is_artifact(unit_info: &dat::Unit) -> bool {
+    unit_info.class_id() == ARTIFACT_CLASS_ID
+}
+
+/// Classes of the wild animals in empires.dat, which stay with gaia no matter who finds them
+const ANIMAL_CLASS_IDS: [i16; 2] = [9, 10];
+
+/// Whether the unit is a wild animal (a gazelle, boar, or lion) rather than someone's livestock
+pub fn is_animal(unit_info: &dat::Unit) -> bool {
+    ANIMAL_CLASS_IDS.contains(&unit_info.class_id())
+}
+
+/// Whether a gaia unit changes over to the first player whose units find it. Artifacts change
+/// hands by being captured instead, and resources and animals always stay with gaia.
+pub fn converts_on_discovery(unit_info: &dat::Unit) -> bool {
+    if is_artifact(unit_info) || is_animal(unit_info) {
+        return false;
+    }
+    match unit_info.interaction_mode {
+        dat::InteractionMode::Resource => false,
+        _ => unit_info.converts_on_discovery(),
+    }
+}
+
+/// Hands the unit over to another player, recoloring it to match
+pub fn change_owner(unit: &mut UnitComponent, graphic: Option<&mut GraphicComponent>, player_id: PlayerId) {
+    unit.player_id = player_id;
+    if let Some(graphic) = graphic {
+        graphic.player_id = player_id;
+    }
+}
+
+/// The wonder building in empires.dat
+const WONDER_UNIT_ID: u32 = 276;
+
+/// Whether the unit is a wonder, which starts a victory countdown once it's built
+pub fn is_wonder(unit_id: UnitId) -> bool {
+    *unit_id == WONDER_UNIT_ID
+}
+
+/// Whether the unit keeps other units off of the tiles underneath it
+/// (buildings, and resources that don't move, such as trees and mines)
+pub fn blocks_tiles(unit_info: &dat::Unit) -> bool {
+    match unit_info.interaction_mode {
+        dat::InteractionMode::Building => true,
+        dat::InteractionMode::Resource => {
+            match unit_info.motion_params {
+                Some(ref params) => params.speed < 0.001f32,
+                None => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Every tile that the unit's collision box overlaps. A box edge that lies exactly on a tile
+/// boundary doesn't count as overlapping the next tile, so neighboring trees don't share tiles.
+pub fn footprint_tiles(unit_info: &dat::Unit, transform: &TransformComponent) -> Vec<OccupiedTile> {
+    let collision_box = collision_box(unit_info, transform);
+    let last_tile = |max: Fixed, min: i32| cmp::max(min, (max - Fixed { scaled: 1 }).to_i32().unwrap());
+    let start_row = collision_box.min.y.to_i32().unwrap();
+    let start_col = collision_box.min.x.to_i32().unwrap();
+    let end_row = last_tile(collision_box.max.y, start_row);
+    let end_col = last_tile(collision_box.max.x, start_col);
+
+    let mut tiles = Vec::new();
+    for row in start_row..(end_row + 1) {
+        for col in start_col..(end_col + 1) {
+            tiles.push((row, col));
+        }
+    }
+    tiles
+}
@@ -19,8 +19,6 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use sdl2;
-
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct Color {
     pub r: u8,
@@ -47,9 +45,3 @@ impl Color {
         }
     }
 }
-
-impl Into<sdl2::pixels::Color> for Color {
-    fn into(self) -> sdl2::pixels::Color {
-        sdl2::pixels::Color::RGBA(self.r, self.g, self.b, self.a)
-    }
-}
@@ -41,6 +41,7 @@ macro_rules! fixed_const {
 
 const FIXED_PI: Fixed = fixed_const!(std::f64::consts::PI);
 const FIXED_TWO_PI: Fixed = fixed_const!(2.0 * std::f64::consts::PI);
+const FIXED_HALF_PI: Fixed = fixed_const!(0.5 * std::f64::consts::PI);
 
 /// 32-bit fixed point number
 #[derive(Eq, PartialEq, Copy, Clone, Default, Ord, PartialOrd)]
@@ -63,6 +64,11 @@ impl Fixed {
         FIXED_TWO_PI
     }
 
+    #[inline]
+    pub fn half_pi() -> Fixed {
+        FIXED_HALF_PI
+    }
+
     #[inline]
     pub fn abs(&self) -> Fixed {
         Fixed::new(self.scaled.abs())
@@ -86,28 +92,146 @@ impl Fixed {
         Fixed::new((self.scaled + ONE_HALF) & INTEGER_MASK)
     }
 
+    /// Square root, rounded down to the nearest representable value.
+    ///
+    /// Like the trigonometric functions below, this only uses integer arithmetic,
+    /// so it returns bit-identical results on every platform.
     pub fn sqrt(&self) -> Fixed {
-        if *self == Fixed::one() {
-            return *self;
-        }
         if *self < Fixed::zero() {
             panic!("Tried to take the square root of negative number");
         }
 
-        let epsilon: Fixed = fixed_const!(0.0001);
-        let mut last = *self;
-        let mut root = *self;
-        loop {
-            root = fixed_const!(0.5) * (root + *self / root);
-            if (last - root).abs() <= epsilon {
-                break;
+        // Digit-by-digit square root of (scaled << SCALE_BITS), which needs up to 88 bits.
+        // Rather than building that number, pull its bits out two at a time.
+        let value = self.scaled as u64;
+        let total_bits = 64 + SCALE_BITS;
+        let bit_at = |position: i64| -> u64 {
+            if position >= SCALE_BITS { (value >> (position - SCALE_BITS)) & 1 } else { 0 }
+        };
+
+        let mut root: u64 = 0;
+        let mut remainder: u64 = 0;
+        let mut position = total_bits - 2;
+        while position >= 0 {
+            remainder = (remainder << 2) | (bit_at(position + 1) << 1) | bit_at(position);
+            let trial = (root << 2) | 1;
+            root <<= 1;
+            if remainder >= trial {
+                remainder -= trial;
+                root |= 1;
+            }
+            position -= 2;
+        }
+        Fixed::new(root as i64)
+    }
+
+    /// Sine of an angle in radians
+    pub fn sin(&self) -> Fixed {
+        self.sin_cos().0
+    }
+
+    /// Cosine of an angle in radians
+    pub fn cos(&self) -> Fixed {
+        self.sin_cos().1
+    }
+
+    /// Returns (sine, cosine) of an angle in radians, computed with CORDIC
+    pub fn sin_cos(&self) -> (Fixed, Fixed) {
+        // Reduce to [-pi, pi], then to [-pi/2, pi/2] (where CORDIC converges) by rotating half a turn
+        let mut angle = (self.scaled << CORDIC_EXTRA_BITS) % CORDIC_TWO_PI;
+        if angle > CORDIC_PI {
+            angle -= CORDIC_TWO_PI;
+        } else if angle < -CORDIC_PI {
+            angle += CORDIC_TWO_PI;
+        }
+        let mut sign = 1;
+        if angle > CORDIC_HALF_PI {
+            angle -= CORDIC_PI;
+            sign = -1;
+        } else if angle < -CORDIC_HALF_PI {
+            angle += CORDIC_PI;
+            sign = -1;
+        }
+
+        let (mut x, mut y) = (CORDIC_GAIN, 0i64);
+        for (i, atan) in CORDIC_ATAN_TABLE.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if angle >= 0 {
+                x -= dx;
+                y += dy;
+                angle -= *atan;
+            } else {
+                x += dx;
+                y -= dy;
+                angle += *atan;
+            }
+        }
+        (Fixed::new(sign * from_cordic(y)), Fixed::new(sign * from_cordic(x)))
+    }
+
+    /// Four quadrant arc tangent of y/x in radians, in the range [-pi, pi] (give or take the last bit).
+    /// Returns zero for (0, 0).
+    pub fn atan2(y: Fixed, x: Fixed) -> Fixed {
+        let (mut x, mut y) = (x.scaled, y.scaled);
+        if x == 0 && y == 0 {
+            return Fixed::zero();
+        }
+
+        // Rotate into the right half plane, where CORDIC converges
+        let mut angle = 0i64;
+        if x < 0 {
+            let (old_x, old_y) = (x, y);
+            if old_y >= 0 {
+                x = old_y;
+                y = -old_x;
+                angle = CORDIC_HALF_PI;
+            } else {
+                x = -old_y;
+                y = old_x;
+                angle = -CORDIC_HALF_PI;
+            }
+        }
+
+        // Only the direction matters, so scale small vectors up to keep precision
+        while x.abs() < (1 << 40) && y.abs() < (1 << 40) {
+            x <<= 1;
+            y <<= 1;
+        }
+
+        for (i, atan) in CORDIC_ATAN_TABLE.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if y > 0 {
+                x += dx;
+                y -= dy;
+                angle += *atan;
+            } else {
+                x -= dx;
+                y += dy;
+                angle -= *atan;
             }
-            last = root;
         }
-        root
+        Fixed::new(from_cordic(angle))
     }
 }
 
+// CORDIC runs with extra fractional bits so that rounding errors don't pile up in the last bits
+const CORDIC_EXTRA_BITS: i64 = 6;
+const CORDIC_PI: i64 = 3373259426; // pi * 2^30
+const CORDIC_HALF_PI: i64 = 1686629713;
+const CORDIC_TWO_PI: i64 = 2 * CORDIC_PI;
+const CORDIC_GAIN: i64 = 652032874; // Product of 1/sqrt(1 + 2^(-2i)) * 2^30
+
+// atan(2^-i) * 2^30; written out so that nothing depends on the platform's float implementation
+const CORDIC_ATAN_TABLE: [i64; 31] = [843314857, 497837829, 263043837, 133525159, 67021687, 33543516,
+                                      16775851, 8388437, 4194283, 2097149, 1048576, 524288, 262144,
+                                      131072, 65536, 32768, 16384, 8192, 4096, 2048, 1024, 512, 256, 128,
+                                      64, 32, 16, 8, 4, 2, 1];
+
+#[inline]
+fn from_cordic(val: i64) -> i64 {
+    (val + (1 << (CORDIC_EXTRA_BITS - 1))) >> CORDIC_EXTRA_BITS
+}
+
 impl ToPrimitive for Fixed {
     #[inline]
     fn to_i32(&self) -> Option<i32> {
@@ -585,6 +709,64 @@ mod tests {
                    format!("{}", Fixed::two_pi().to_f32().unwrap()));
     }
 
+    #[test]
+    fn test_sqrt_against_f64() {
+        for i in 0..20000 {
+            let val = Fixed::from(i as f64 * 0.173);
+            let expected = val.to_f64().unwrap().sqrt();
+            let actual = val.sqrt().to_f64().unwrap();
+            assert!((expected - actual).abs() < 0.000001,
+                    "sqrt({}) = {}, expected {}",
+                    val,
+                    actual,
+                    expected);
+        }
+        assert_eq!(Fixed::from(31622), Fixed::from(1000000000).sqrt().truncate());
+    }
+
+    #[test]
+    fn test_sin_cos_against_f64() {
+        for i in -20000..20001 {
+            let angle = Fixed::from(i as f64 * 0.001);
+            let (sin, cos) = angle.sin_cos();
+            let angle = angle.to_f64().unwrap();
+            assert!((angle.sin() - sin.to_f64().unwrap()).abs() < 0.000001, "sin({})", angle);
+            assert!((angle.cos() - cos.to_f64().unwrap()).abs() < 0.000001, "cos({})", angle);
+        }
+        assert_eq!(Fixed::zero(), Fixed::zero().sin());
+        assert_eq!(Fixed::one(), Fixed::zero().cos());
+    }
+
+    #[test]
+    fn test_atan2_against_f64() {
+        for x in -50..51 {
+            for y in -50..51 {
+                let (x, y) = (Fixed::from(x as f64 * 0.37), Fixed::from(y as f64 * 0.53));
+                let expected = y.to_f64().unwrap().atan2(x.to_f64().unwrap());
+                let actual = Fixed::atan2(y, x).to_f64().unwrap();
+                assert!((expected - actual).abs() < 0.000001,
+                        "atan2({}, {}) = {}, expected {}",
+                        y,
+                        x,
+                        actual,
+                        expected);
+            }
+        }
+        assert_eq!(Fixed::zero(), Fixed::atan2(0.into(), 0.into()));
+    }
+
+    #[test]
+    fn test_math_bit_identical() {
+        // These must never change, since the simulation (and every replay) depends on them
+        assert_eq!(23726566, Fixed::from(2).sqrt().scaled);
+        assert_eq!((14117540, 9064768),
+                   {
+                       let (sin, cos) = Fixed::from(1).sin_cos();
+                       (sin.scaled, cos.scaled)
+                   });
+        assert_eq!(41911021, Fixed::atan2(3.into(), (-4).into()).scaled);
+    }
+
     // Commented out benchmarks since they don't compile on stable rustc
     // Wrapped in a function so that rustfmt doesn't touch the comment formatting
     // use test::{self, Bencher};
@@ -23,7 +23,6 @@
 // #![feature(test)]
 // extern crate test;
 
-extern crate sdl2;
 extern crate nalgebra;
 extern crate num;
 
@@ -19,11 +19,9 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use sdl2;
-
 use std::cmp;
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -45,6 +43,11 @@ impl Rect {
         }
     }
 
+    /// Whether the point is inside the rectangle (including its top and left edges)
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h
+    }
+
     pub fn translate(&mut self, x: i32, y: i32) {
         self.x += x;
         self.y += y;
@@ -57,9 +60,3 @@ impl Rect {
         self.h = cmp::max(self.y + self.h, other.y + other.h) - self.y;
     }
 }
-
-impl Into<sdl2::rect::Rect> for Rect {
-    fn into(self) -> sdl2::rect::Rect {
-        sdl2::rect::Rect::new(self.x, self.y, self.w as u32, self.h as u32)
-    }
-}
@@ -28,7 +28,10 @@ pub trait Norm {
     fn length_squared(&self) -> Fixed;
     fn length(&self) -> Fixed;
 
+    /// Returns a unit length copy of the vector, or the zero vector if it has no length
     fn normalized(&self) -> Vector3;
+
+    /// Makes the vector unit length (unless it has no length) and returns its previous length
     fn normalize(&mut self) -> Fixed;
 }
 
@@ -47,12 +50,14 @@ impl Norm for Vector3 {
 
     fn normalized(&self) -> Vector3 {
         let len = self.length();
-        *self / len
+        if len == Fixed::from(0) { *self } else { *self / len }
     }
 
     fn normalize(&mut self) -> Fixed {
         let len = self.length();
-        *self /= len;
+        if len != Fixed::from(0) {
+            *self /= len;
+        }
         len
     }
 }
@@ -88,6 +93,21 @@ mod test {
         assert_eq!(1, direction_length.to_i32().unwrap());
     }
 
+    #[test]
+    fn test_normalize_zero_length() {
+        let mut zero: Vector3 = Vector3::new(0.into(), 0.into(), 0.into());
+        assert_eq!(zero, zero.normalized());
+        assert_eq!(Fixed::from(0), zero.normalize());
+        assert_eq!(Vector3::new(0.into(), 0.into(), 0.into()), zero);
+    }
+
+    #[test]
+    fn test_length() {
+        let vector: Vector3 = Vector3::new(3.into(), 4.into(), 12.into());
+        assert_eq!(Fixed::from(13), vector.length());
+        assert_eq!(Fixed::from(169), vector.length_squared());
+    }
+
     // Commented out benchmarks since they don't compile on stable rustc
     // Wrapped in a function so that rustfmt doesn't touch the comment formatting
     // use test::{self, Bencher};
@@ -19,11 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod component;
-pub mod resource;
 pub mod render_system;
+pub mod resource;
 pub mod system;
 mod world;
 
-pub use self::component::*;
-pub use self::world::{SystemGroup, WorldPlanner, create_world_planner};
+// The simulation lives in chariot_sim; what's here only takes input for it and shows it
+pub use sim::ecs::component::*;
+pub use sim::ecs::{GameSettings, GameSetup, MapVisibility, Persist, PlayerSetup, StartingPositions,
+                   StartingResources, SystemGroup, UPDATES_PER_SECOND, UnitFactory, UnitSpawn,
+                   VictoryCondition, WorldPlanner, WorldSnapshot, state_hash, time_step_seconds,
+                   update_world};
+pub use self::world::{create_headless_world_planner, create_world_planner};
@@ -19,41 +19,46 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use ecs::{DecalComponent, TransformComponent};
-use ecs::resource::{RenderCommands, ViewProjector};
-use resource::{RenderCommand, ShapeKey};
-use specs::{self, Join};
+use dat;
+use ecs::resource::{Ambience, RenderCommands};
+use resource::{DrsKey, RenderCommand, ShapeKey};
+use specs;
 use super::RenderSystem;
 use types::Fixed;
 
-pub struct DecalRenderSystem;
+/// Birds fly over everything on the map
+const BIRD_LAYER: u16 = 100;
 
-impl DecalRenderSystem {
-    pub fn new() -> DecalRenderSystem {
-        DecalRenderSystem
+pub struct AmbienceRenderSystem {
+    empires: dat::EmpiresDbRef,
+}
+
+impl AmbienceRenderSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> AmbienceRenderSystem {
+        AmbienceRenderSystem { empires: empires }
     }
 }
 
-impl RenderSystem for DecalRenderSystem {
+impl RenderSystem for AmbienceRenderSystem {
     fn render(&mut self, arg: specs::RunArg, lerp: Fixed) {
         fetch_components!(arg, _entities, [
-            components(transforms: TransformComponent),
-            components(decals: DecalComponent),
-            resource(projector: ViewProjector),
+            resource(ambience: Ambience),
             mut resource(render_commands: RenderCommands),
         ]);
 
-        for (transform, decal) in (&transforms, &decals).iter() {
-            let position = projector.project(&transform.lerped_position(lerp));
-            let shape_key = ShapeKey::new(decal.drs_key,
-                                          decal.slp_file_id,
-                                          decal.player_color_id.into());
-            render_commands.push(RenderCommand::new_shape(20,
+        let slp_id = match ambience.bird_graphic().and_then(|id| self.empires.graphic(id).slp_id) {
+            Some(slp_id) => slp_id,
+            None => return,
+        };
+        for bird in ambience.birds() {
+            let position = bird.lerped_position(lerp);
+            let shape_key = ShapeKey::new(DrsKey::Graphics, slp_id, 0.into());
+            render_commands.push(RenderCommand::new_shape(BIRD_LAYER,
                                                           position.y,
                                                           shape_key,
-                                                          decal.frame,
+                                                          bird.frame,
                                                           position,
-                                                          false,
+                                                          bird.flies_left(),
                                                           false));
         }
     }
@@ -0,0 +1,73 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::resource::{EffectSpawner, Players, RenderCommands, ViewProjector, Viewport};
+use resource::{RenderCommand, ShapeKey};
+use specs;
+use super::RenderSystem;
+use types::Fixed;
+
+/// How far (in pixels) past the edges of the screen an effect can be and still show up,
+/// since its graphic reaches out from where it's anchored
+const OFF_SCREEN_MARGIN: i32 = 128;
+
+/// Draws every effect in one pass over the pool, skipping the ones that are off screen
+pub struct EffectRenderSystem;
+
+impl EffectRenderSystem {
+    pub fn new() -> EffectRenderSystem {
+        EffectRenderSystem
+    }
+}
+
+impl RenderSystem for EffectRenderSystem {
+    fn render(&mut self, arg: specs::RunArg, _lerp: Fixed) {
+        fetch_components!(arg, _entities, [
+            resource(effects: EffectSpawner),
+            resource(players: Players),
+            resource(projector: ViewProjector),
+            resource(viewport: Viewport),
+            mut resource(render_commands: RenderCommands),
+        ]);
+
+        let top_left = viewport.top_left_i32();
+        for effect in effects.effects().iter().filter(|effect| effect.is_alive()) {
+            let position = projector.project(&effect.position);
+            if position.x < top_left.x - OFF_SCREEN_MARGIN ||
+               position.y < top_left.y - OFF_SCREEN_MARGIN ||
+               position.x > top_left.x + viewport.size.x + OFF_SCREEN_MARGIN ||
+               position.y > top_left.y + viewport.size.y + OFF_SCREEN_MARGIN {
+                continue;
+            }
+
+            let shape_key = ShapeKey::new(effect.shape.drs_key,
+                                          effect.shape.slp_file_id,
+                                          players.sprite_color(effect.shape.player_id));
+            render_commands.push(RenderCommand::new_shape(20,
+                                                          position.y,
+                                                          shape_key,
+                                                          effect.frame,
+                                                          position,
+                                                          false,
+                                                          false));
+        }
+    }
+}
@@ -21,14 +21,16 @@
 
 use dat;
 use ecs::{GraphicComponent, OnScreenComponent, TransformComponent};
-use ecs::resource::{RenderCommands, ViewProjector};
+use ecs::resource::{FogOfWar, Players, RenderCommands, TileVisibility, ViewProjector};
 use identifier::{GraphicId, PlayerColorId};
 use nalgebra::Vector2;
 use resource::{DrsKey, RenderCommand, ShapeKey};
 use specs::{self, Join};
 use super::RenderSystem;
-use types::Fixed;
+use types::{Fixed, ToPrimitive};
 
+/// Draws the units that the local player can see: everything in sight, and gaia's units (trees and
+/// mines, which don't go anywhere) on explored tiles
 pub struct GraphicRenderSystem {
     empires: dat::EmpiresDbRef,
 }
@@ -79,16 +81,27 @@ impl RenderSystem for GraphicRenderSystem {
             components(graphics: GraphicComponent),
             components(on_screen: OnScreenComponent),
             resource(projector: ViewProjector),
+            resource(players: Players),
+            resource(fog: FogOfWar),
             mut resource(render_commands: RenderCommands),
         ]);
 
         for (transform, graphic, _on_screen) in (&transforms, &graphics, &on_screen).iter() {
+            let tile = transform.position();
+            let shown = match fog.visibility(tile.y.to_i32().unwrap(), tile.x.to_i32().unwrap()) {
+                TileVisibility::Visible => true,
+                TileVisibility::Explored => *graphic.player_id == 0,
+                TileVisibility::Unexplored => false,
+            };
+            if !shown {
+                continue;
+            }
             if let Some(graphic_id) = graphic.graphic_id {
                 let position = projector.project(&transform.lerped_position(lerp));
                 self.render_graphic(&mut render_commands,
                                     &projector,
                                     &position,
-                                    graphic.player_color_id,
+                                    players.sprite_color(graphic.player_id),
                                     graphic_id,
                                     graphic.frame,
                                     graphic.flip_horizontal,
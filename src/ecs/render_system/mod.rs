@@ -19,14 +19,16 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod decal_render_system;
+mod ambience_render_system;
+mod effect_render_system;
 mod graphic_render_system;
 mod render_system;
 mod terrain_render_system;
 mod tile_debug_render_system;
 mod unit_selection_render_system;
 
-pub use self::decal_render_system::DecalRenderSystem;
+pub use self::ambience_render_system::AmbienceRenderSystem;
+pub use self::effect_render_system::EffectRenderSystem;
 pub use self::graphic_render_system::GraphicRenderSystem;
 pub use self::render_system::{RenderSystem, RenderSystemWrapper};
 pub use self::terrain_render_system::TerrainRenderSystem;
@@ -21,12 +21,14 @@
 
 
 use dat;
-use ecs::resource::{RenderCommands, Terrain, ViewProjector, Viewport};
-use ecs::resource::terrain::{BlendInfo, BorderMatch, ElevationGraphic, ElevationMatch};
+use ecs::resource::{AnimationClock, FogOfWar, RenderCommands, Terrain, TileVisibility, ViewProjector,
+                    Viewport};
+use ecs::resource::terrain::{BlendInfo, BorderMatch, CHUNK_SIZE, ElevationGraphic, ElevationMatch};
 use identifier::{SlpFileId, TerrainBorderId, TerrainId};
 
 use nalgebra::Vector2;
-use resource::{DrsKey, RenderCommand, ShapeKey};
+use resource::{CompositeKey, DrsKey, RenderCommand, RenderShapeParams, ShapeKey, ShapeMetadataKey,
+               ShapeMetadataStoreRef};
 use specs;
 use std::cmp;
 
@@ -45,6 +47,48 @@ pub struct Tile<T> {
     pub id: T,
     pub slp_id: SlpFileId,
     pub frame_range: Vec<u32>,
+    animation: Option<TileAnimation>,
+}
+
+/// How an animated tile (water, or a shoreline) picks its frame. The SLPs of animated terrain
+/// have a block of frames for each step of the animation, one block after the other, so a step
+/// further along is the same frame a block further in.
+#[derive(Copy, Clone)]
+struct TileAnimation {
+    animation: dat::TerrainAnimation,
+    frames_per_step: u16,
+}
+
+impl TileAnimation {
+    /// Works out whether the tile can animate from how many frames its SLP has
+    fn new(animation: Option<dat::TerrainAnimation>,
+           shape_count: Option<u32>,
+           end_frame: u32)
+           -> Option<TileAnimation> {
+        let (animation, shape_count) = match (animation, shape_count) {
+            (Some(animation), Some(shape_count)) => (animation, shape_count),
+            _ => return None,
+        };
+        let frames_per_step = shape_count / animation.frame_count as u32;
+        if frames_per_step == 0 || end_frame > frames_per_step {
+            return None;
+        }
+        Some(TileAnimation {
+            animation: animation,
+            frames_per_step: frames_per_step as u16,
+        })
+    }
+
+    /// The cached command for the tile's first step, moved along to the step showing now
+    fn animate(&self, command: RenderCommand, clock: &AnimationClock) -> RenderCommand {
+        match command {
+            RenderCommand::RenderShape(order, mut params) => {
+                params.frame_num += clock.terrain_frame(&self.animation) * self.frames_per_step;
+                RenderCommand::RenderShape(order, params)
+            }
+            command => command,
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
@@ -64,10 +108,40 @@ impl<T> TileKey<T> {
     }
 }
 
+/// What a chunk of terrain draws, worked out once and kept until the terrain in it changes (or
+/// more of it is explored)
+struct Chunk {
+    revision: u32,
+    explored_revision: u32,
+
+    /// Whether any of its tiles were built into the chunk's composite
+    has_composite: bool,
+
+    /// The shapes of the tiles that animate (and any borders on them), which can't be part of the
+    /// composite, along with where the tile is for culling and how to animate it. They're kept on
+    /// their first frame and moved along as they're drawn.
+    animated: Vec<(Vector2<i32>, RenderCommand, Option<TileAnimation>)>,
+}
+
+/// Draws the terrain a chunk at a time. Blending a tile with its neighbors and finding its
+/// graphics is most of the work, so each chunk is only worked out again when the terrain says
+/// it changed (an edit in the editor), or the fog of war says more of it has been explored.
+/// Tiles that haven't been explored are left out. Chunks that scroll off screen are dropped, so
+/// what's kept follows the size of the view rather than the size of the map.
+///
+/// The tiles that don't animate are drawn into a texture for the chunk (a composite, see
+/// `ShapeManager::build_composite`), so a frame is a copy per chunk instead of one per tile.
+/// The composites go under everything else on the terrain layer.
+///
+/// Water and shorelines animate if empires.dat says they do, with as many steps as their SLPs
+/// have room for. They're drawn a tile at a time over the composites. Shallows are a terrain of
+/// their own and fish are gaia units, so they're drawn like any other terrain and unit.
 pub struct TerrainRenderSystem {
     empires: dat::EmpiresDbRef,
+    shape_metadata: ShapeMetadataStoreRef,
     tiles: HashMap<TileKey<TerrainId>, Tile<TerrainId>>,
     borders: HashMap<TileKey<TerrainBorderId>, Tile<TerrainBorderId>>,
+    chunks: HashMap<(i32, i32), Chunk>,
 }
 
 impl RenderSystem for TerrainRenderSystem {
@@ -75,7 +149,9 @@ impl RenderSystem for TerrainRenderSystem {
         fetch_components!(arg, _entities, [
             resource(projector: ViewProjector),
             resource(viewport: Viewport),
-            mut resource(terrain: Terrain),
+            resource(terrain: Terrain),
+            resource(fog: FogOfWar),
+            resource(animation_clock: AnimationClock),
             mut resource(render_commands: RenderCommands),
         ]);
 
@@ -90,12 +166,56 @@ impl RenderSystem for TerrainRenderSystem {
         bounds.w = bounds.x + viewport.size.x + 2 * tile_width;
         bounds.h = bounds.y + viewport.size.y + 2 * tile_height;
 
-        for row in area.y..(area.y + area.h) {
-            for col in (area.x..(area.x + area.w)).rev() {
-                if row >= 0 && row < terrain.width() && col >= 0 && col < terrain.height() {
-                    let pos = projector.project(&Vector3::new(col.into(), row.into(), 0.into()));
+        let (chunk_rows, chunk_cols) = terrain.chunk_dimensions();
+        let first_chunk = (cmp::max(0, area.y / CHUNK_SIZE), cmp::max(0, area.x / CHUNK_SIZE));
+        let last_chunk = (cmp::min(chunk_rows - 1, (area.y + area.h) / CHUNK_SIZE),
+                          cmp::min(chunk_cols - 1, (area.x + area.w) / CHUNK_SIZE));
+
+        // Forget chunks that are well off screen
+        let off_screen: Vec<(i32, i32)> = self.chunks
+            .keys()
+            .filter(|&&(chunk_row, chunk_col)| {
+                chunk_row < first_chunk.0 - 1 || chunk_row > last_chunk.0 + 1 ||
+                chunk_col < first_chunk.1 - 1 || chunk_col > last_chunk.1 + 1
+            })
+            .cloned()
+            .collect();
+        for chunk_key in off_screen {
+            if let Some(chunk) = self.chunks.remove(&chunk_key) {
+                if chunk.has_composite {
+                    render_commands.forget_composite(composite_key(chunk_key));
+                }
+            }
+        }
+
+        for chunk_row in first_chunk.0..(last_chunk.0 + 1) {
+            for chunk_col in (first_chunk.1..(last_chunk.1 + 1)).rev() {
+                let chunk_key = (chunk_row, chunk_col);
+                let revision = terrain.chunk_revision(chunk_row, chunk_col);
+                let explored_revision = fog.explored_revision(chunk_row, chunk_col);
+                let stale = self.chunks
+                    .get(&chunk_key)
+                    .map(|chunk| chunk.revision != revision || chunk.explored_revision != explored_revision)
+                    .unwrap_or(true);
+                if stale {
+                    let (chunk, shapes) =
+                        self.build_chunk(&*projector, &*terrain, &*fog, chunk_row, chunk_col);
+                    render_commands.build_composite(composite_key(chunk_key), shapes);
+                    self.chunks.insert(chunk_key, chunk);
+                }
+
+                let chunk = &self.chunks[&chunk_key];
+                if chunk.has_composite {
+                    render_commands.push(RenderCommand::new_composite(TERRAIN_LAYER,
+                                                                      i32::min_value(),
+                                                                      composite_key(chunk_key)));
+                }
+                for &(pos, command, animation) in &chunk.animated {
                     if pos.x > bounds.x && pos.y > bounds.y && pos.x < bounds.w && pos.y < bounds.h {
-                        self.blend_and_render_tile(&mut *render_commands, row, col, &mut terrain);
+                        render_commands.push(match animation {
+                            Some(animation) => animation.animate(command, &*animation_clock),
+                            None => command,
+                        });
                     }
                 }
             }
@@ -103,20 +223,70 @@ impl RenderSystem for TerrainRenderSystem {
     }
 }
 
+fn composite_key(chunk_key: (i32, i32)) -> CompositeKey {
+    CompositeKey::new(TERRAIN_LAYER, chunk_key.0, chunk_key.1)
+}
+
 impl TerrainRenderSystem {
-    pub fn new(empires: dat::EmpiresDbRef) -> TerrainRenderSystem {
+    pub fn new(empires: dat::EmpiresDbRef, shape_metadata: ShapeMetadataStoreRef) -> TerrainRenderSystem {
         TerrainRenderSystem {
             empires: empires,
+            shape_metadata: shape_metadata,
             tiles: HashMap::new(),
             borders: HashMap::new(),
+            chunks: HashMap::new(),
         }
     }
 
+    fn build_chunk(&mut self,
+                   projector: &ViewProjector,
+                   terrain: &Terrain,
+                   fog: &FogOfWar,
+                   chunk_row: i32,
+                   chunk_col: i32)
+                   -> (Chunk, Vec<RenderShapeParams>) {
+        let mut shapes = Vec::new();
+        let mut animated = Vec::new();
+        let (first_row, first_col) = (chunk_row * CHUNK_SIZE, chunk_col * CHUNK_SIZE);
+        let last_row = cmp::min(terrain.height(), first_row + CHUNK_SIZE);
+        let last_col = cmp::min(terrain.width(), first_col + CHUNK_SIZE);
+        for row in first_row..last_row {
+            for col in (first_col..last_col).rev() {
+                if fog.visibility(row, col) == TileVisibility::Unexplored {
+                    continue;
+                }
+                let pos = projector.project(&Vector3::new(col.into(), row.into(), 0.into()));
+                let mut tile_commands = Vec::new();
+                self.blend_and_render_tile(&mut tile_commands, row, col, terrain);
+
+                // A border on an animated tile has to stay over it, so it isn't drawn with the
+                // composite either
+                if tile_commands.iter().any(|&(_, animation)| animation.is_some()) {
+                    animated.extend(tile_commands.into_iter()
+                        .map(|(command, animation)| (pos, command, animation)));
+                } else {
+                    for (command, _) in tile_commands {
+                        if let RenderCommand::RenderShape(_, params) = command {
+                            shapes.push(params);
+                        }
+                    }
+                }
+            }
+        }
+        let chunk = Chunk {
+            revision: terrain.chunk_revision(chunk_row, chunk_col),
+            explored_revision: fog.explored_revision(chunk_row, chunk_col),
+            has_composite: !shapes.is_empty(),
+            animated: animated,
+        };
+        (chunk, shapes)
+    }
+
     fn blend_and_render_tile(&mut self,
-                             render_commands: &mut RenderCommands,
+                             render_commands: &mut Vec<(RenderCommand, Option<TileAnimation>)>,
                              row: i32,
                              col: i32,
-                             terrain: &mut Terrain) {
+                             terrain: &Terrain) {
         let blended_tile = terrain.blend_at(row, col);
         let elevation_match = self.resolve_elevation(&blended_tile);
 
@@ -155,7 +325,7 @@ impl TerrainRenderSystem {
     }
 
     fn render_tile<T>(&self,
-                      render_commands: &mut RenderCommands,
+                      render_commands: &mut Vec<(RenderCommand, Option<TileAnimation>)>,
                       drs_key: DrsKey,
                       tile: &Tile<T>,
                       render_offset_y: f32,
@@ -164,17 +334,18 @@ impl TerrainRenderSystem {
         let (x, y) = self.project_row_col(row, col, render_offset_y);
         let frame_num = ((row + 1) * (col - row)) as usize % tile.frame_range.len();
 
-        render_commands.push(RenderCommand::new_shape(TERRAIN_LAYER,
-                                                      y,
-                                                      ShapeKey::new(drs_key, tile.slp_id, 0.into()),
-                                                      tile.frame_range[frame_num] as u16,
-                                                      Vector2::new(x, y),
-                                                      false,
-                                                      false));
+        let command = RenderCommand::new_shape(TERRAIN_LAYER,
+                                               y,
+                                               ShapeKey::new(drs_key, tile.slp_id, 0.into()),
+                                               tile.frame_range[frame_num] as u16,
+                                               Vector2::new(x, y),
+                                               false,
+                                               false);
+        render_commands.push((command, tile.animation));
     }
 
     fn render_borders(&mut self,
-                      render_commands: &mut RenderCommands,
+                      render_commands: &mut Vec<(RenderCommand, Option<TileAnimation>)>,
                       border_id: TerrainBorderId,
                       border_indices: &'static [u16],
                       elevation_index: u8,
@@ -209,8 +380,7 @@ impl TerrainRenderSystem {
         match ElevationMatch::find_match(blended_tile.elevation_matrix) {
             Some(elevation_match) => elevation_match,
             None => {
-                println!("Elevation match failed:\n{:?}",
-                         blended_tile.elevation_matrix);
+                log_warn!("Elevation match failed:\n{:?}", blended_tile.elevation_matrix);
                 &DEFAULT_ELEVATION
             }
         }
@@ -230,10 +400,15 @@ impl TerrainRenderSystem {
             terrain_def.slp_id.unwrap()
         };
 
+        // Terrain drawn with another terrain's graphics animates the way that one does
+        let animation = terrain_def.animation().or_else(|| {
+            terrain_def.terrain_to_draw.and_then(|terrain_id| self.empires.terrain(terrain_id).animation())
+        });
         Tile {
             id: blended_tile.terrain_id,
             slp_id: slp_id,
             frame_range: frames,
+            animation: TileAnimation::new(animation, self.shape_count(DrsKey::Terrain, slp_id), end_frame),
         }
     }
 
@@ -252,6 +427,15 @@ impl TerrainRenderSystem {
             id: border_id,
             slp_id: border.slp_id,
             frame_range: frames,
+            animation: TileAnimation::new(border.animation(),
+                                          self.shape_count(DrsKey::Border, border.slp_id),
+                                          end_frame),
         }
     }
+
+    fn shape_count(&self, drs_key: DrsKey, slp_id: SlpFileId) -> Option<u32> {
+        self.shape_metadata
+            .get(&ShapeMetadataKey::new(drs_key, slp_id))
+            .map(|metadata| metadata.shape_count)
+    }
 }
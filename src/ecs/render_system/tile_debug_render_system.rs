@@ -42,7 +42,7 @@ impl RenderSystem for TileDebugRenderSystem {
             resource(view_projector: ViewProjector),
             resource(viewport: Viewport),
             resource(keyboard_key_states: KeyboardKeyStates),
-            mut resource(terrain: Terrain),
+            resource(terrain: Terrain),
             mut resource(render_commands: RenderCommands),
         ]);
 
@@ -53,12 +53,12 @@ impl RenderSystem for TileDebugRenderSystem {
             let row: i32 = tile_pos.y.round().into();
             let col: i32 = tile_pos.x.round().into();
             let actual_tile = *terrain.tile_at(tile_pos);
-            let blend_info = *terrain.blend_at(row, col);
-            println!("\nTile under cursor ({}, {}):\n{:?}\n{:#?}\n",
-                     row,
-                     col,
-                     actual_tile,
-                     blend_info);
+            let blend_info = terrain.blend_at(row, col);
+            log_debug!("Tile under cursor ({}, {}):\n{:?}\n{:#?}",
+                       row,
+                       col,
+                       actual_tile,
+                       blend_info);
         }
 
         // Draw a cactus at the tile's position
@@ -20,14 +20,21 @@
 // SOFTWARE.
 
 use dat;
-use ecs::{OnScreenComponent, SelectedUnitComponent, TransformComponent, UnitComponent};
-use ecs::resource::{RenderCommands, ViewProjector};
-use resource::RenderCommand;
+use identifier::UnitId;
+use ecs::{ActionQueueComponent, OnScreenComponent, SelectedUnitComponent, TransformComponent, UnitComponent};
+use ecs::resource::{MouseState, Players, RenderCommands, SubSelection, ViewProjector, Viewport};
+use resource::{DrsKey, RenderCommand, ShapeKey};
 use specs::{self, Join};
 use super::RenderSystem;
+use nalgebra::{Vector2, convert};
 use types::{Color, Fixed, Vector3};
 use util::unit;
 
+/// The flag that marks where units were told to go (in interfac.drs)
+const WAYPOINT_FLAG_SLP_ID: usize = 50405;
+
+const WAYPOINT_NUMBER_SCALE: u32 = 2;
+
 pub struct UnitSelectionRenderSystem {
     empires: dat::EmpiresDbRef,
 }
@@ -45,17 +52,48 @@ impl RenderSystem for UnitSelectionRenderSystem {
             components(units: UnitComponent),
             components(on_screen: OnScreenComponent),
             components(selected_units: SelectedUnitComponent),
+            components(action_queues: ActionQueueComponent),
             resource(projector: ViewProjector),
+            resource(players: Players),
+            resource(mouse_state: MouseState),
+            resource(viewport: Viewport),
+            resource(sub_selection: SubSelection),
             mut resource(render_commands: RenderCommands),
         ]);
 
+        // The box being dragged out to select units
+        if let Some(rect) = mouse_state.drag_rect() {
+            let viewport_pos: Vector2<i32> = convert(viewport.lerped_top_left(lerp));
+            let (left, top) = (rect.x + viewport_pos.x, rect.y + viewport_pos.y);
+            let (right, bottom) = (left + rect.w, top + rect.h);
+            let corners = [Vector2::new(left, top),
+                           Vector2::new(right, top),
+                           Vector2::new(right, bottom),
+                           Vector2::new(left, bottom)];
+            for i in 0..4 {
+                render_commands.push(RenderCommand::new_line(2,
+                                                             0,
+                                                             Color::rgb(255, 255, 255),
+                                                             corners[i],
+                                                             corners[(i + 1) % 4]));
+            }
+        }
+
+        // Units outside of the active kind in a mixed selection (see SubSelection) are drawn dimmer
+        let selected_kinds: Vec<UnitId> =
+            (&units, &selected_units).iter().map(|(unit, _)| unit.unit_id).collect();
+
         let items = (&transforms, &units, &selected_units, &on_screen);
         for (transform, unit, _selected_unit, _on_screen) in items.iter() {
             let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
             let unit_box = unit::selection_box(unit_info, transform);
             let position = projector.project(&transform.lerped_position(lerp));
 
-            let color = Color::rgb(255, 255, 255);
+            let color = if sub_selection.includes(unit.unit_id, &selected_kinds) {
+                Color::rgb(255, 255, 255)
+            } else {
+                Color::rgb(128, 128, 128)
+            };
             let points: [Vector3; 4] = [unit_box.min,
                                         Vector3::new(unit_box.max.x, unit_box.min.y, unit_box.min.z),
                                         Vector3::new(unit_box.max.x, unit_box.max.y, unit_box.min.z),
@@ -68,5 +106,29 @@ impl RenderSystem for UnitSelectionRenderSystem {
                                                              projector.project(&points[(i + 1) % 4])));
             }
         }
+
+        // Queued orders (given while holding Ctrl) show up as a route of numbered flags
+        let route_color = Color::rgb(255, 255, 255);
+        let items = (&transforms, &units, &action_queues, &selected_units);
+        for (transform, unit, action_queue, _selected_unit) in items.iter() {
+            if !players.can_command(unit.player_id) {
+                continue;
+            }
+            let mut from = projector.project(&transform.lerped_position(lerp));
+            for (index, waypoint) in action_queue.waypoints().iter().enumerate() {
+                let to = projector.project(waypoint);
+                render_commands.push(RenderCommand::new_line(1, to.y, route_color, from, to));
+
+                let shape_key = ShapeKey::new(DrsKey::Interfac, WAYPOINT_FLAG_SLP_ID.into(), 0.into());
+                render_commands.push(RenderCommand::new_shape(20, to.y, shape_key, 0, to, false, false));
+                render_commands.push(RenderCommand::new_number(21,
+                                                               to.y,
+                                                               (index + 1) as u16,
+                                                               to + Vector2::new(6, -24),
+                                                               WAYPOINT_NUMBER_SCALE,
+                                                               route_color));
+                from = to;
+            }
+        }
     }
 }
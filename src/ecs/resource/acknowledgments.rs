@@ -0,0 +1,123 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::SoundGroupId;
+use std::collections::BTreeMap;
+use types::Fixed;
+
+/// Selecting or ordering units again within this long of the last acknowledgment stays quiet,
+/// so that clicking around (or selecting a whole army) doesn't talk over itself
+const ACKNOWLEDGMENT_COOLDOWN: Fixed = fixed_const!(0.75);
+
+/// What a unit is replying to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AcknowledgmentKind {
+    Select,
+    Move,
+    Attack,
+}
+
+/// A voice line to play: which of the sound group's sounds it is
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Acknowledgment {
+    pub kind: AcknowledgmentKind,
+    pub sound_group_id: SoundGroupId,
+    pub variant: usize,
+}
+
+// TODO: Play `latest` once there's audio output to play it on
+/// The voice lines units reply with when the local player selects or orders them. Each sound
+/// group's lines are played in turn, and only one unit answers for a selection or an order.
+pub struct Acknowledgments {
+    seconds_until_ready: Fixed,
+    next_variants: BTreeMap<SoundGroupId, usize>,
+    latest: Option<Acknowledgment>,
+}
+
+impl Acknowledgments {
+    pub fn new() -> Acknowledgments {
+        Acknowledgments {
+            seconds_until_ready: 0.into(),
+            next_variants: BTreeMap::new(),
+            latest: None,
+        }
+    }
+
+    /// The most recent line, if one should be playing
+    pub fn latest(&self) -> Option<Acknowledgment> {
+        self.latest
+    }
+
+    pub fn update(&mut self, time_step: Fixed) {
+        if self.seconds_until_ready > 0.into() {
+            self.seconds_until_ready -= time_step;
+        }
+    }
+
+    /// Has a unit answer with the next of the group's `variant_count` lines, unless another unit
+    /// just did. Returns whether it got to.
+    pub fn acknowledge(&mut self,
+                       kind: AcknowledgmentKind,
+                       sound_group_id: SoundGroupId,
+                       variant_count: usize)
+                       -> bool {
+        if variant_count == 0 || self.seconds_until_ready > 0.into() {
+            return false;
+        }
+        let next_variant = self.next_variants.entry(sound_group_id).or_insert(0);
+        let variant = *next_variant % variant_count;
+        *next_variant = variant + 1;
+
+        self.latest = Some(Acknowledgment {
+            kind: kind,
+            sound_group_id: sound_group_id,
+            variant: variant,
+        });
+        self.seconds_until_ready = ACKNOWLEDGMENT_COOLDOWN;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::SoundGroupId;
+    use super::{ACKNOWLEDGMENT_COOLDOWN, AcknowledgmentKind, Acknowledgments};
+
+    #[test]
+    fn test_variants_cycle_and_rate_limit() {
+        let (villager, soldier): (SoundGroupId, SoundGroupId) = (10.into(), 20.into());
+        let mut acknowledgments = Acknowledgments::new();
+        let mut variants = Vec::new();
+        for _ in 0..4 {
+            assert!(acknowledgments.acknowledge(AcknowledgmentKind::Select, villager, 3));
+            variants.push(acknowledgments.latest().unwrap().variant);
+
+            // Everyone else selected at the same time stays quiet
+            assert!(!acknowledgments.acknowledge(AcknowledgmentKind::Select, soldier, 2));
+            acknowledgments.update(ACKNOWLEDGMENT_COOLDOWN);
+        }
+        assert_eq!(vec![0, 1, 2, 0], variants);
+
+        assert!(acknowledgments.acknowledge(AcknowledgmentKind::Attack, soldier, 2));
+        assert_eq!(soldier, acknowledgments.latest().unwrap().sound_group_id);
+        assert_eq!(0, acknowledgments.latest().unwrap().variant);
+    }
+}
@@ -0,0 +1,249 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use identifier::{GraphicId, SoundGroupId};
+use nalgebra::Vector2;
+use std::cmp;
+use std::collections::BTreeMap;
+use super::GameRng;
+use types::Fixed;
+
+/// Volume of the ambient loop, relative to sound effects
+pub const AMBIENT_VOLUME: f32 = 0.25;
+
+/// A terrain's ambient sound only plays once the terrain covers this much of the visible tiles
+const SOUND_COVERAGE: Fixed = fixed_const!(0.25);
+
+/// Shortest and longest wait, in seconds, between flocks of birds
+const MIN_FLOCK_SECONDS: u32 = 20;
+const MAX_FLOCK_SECONDS: u32 = 45;
+
+const MAX_FLOCK_SIZE: u32 = 3;
+
+/// How fast birds fly across the screen, in pixels per second
+const BIRD_SPEED: Fixed = fixed_const!(120.0);
+
+/// How far off screen (in pixels) birds start, and how much farther they go before they're removed
+const BIRD_MARGIN: i32 = 64;
+
+/// Names of the graphics (in empires.dat) that the flyover birds use, in order of preference
+const BIRD_GRAPHIC_NAMES: [&'static str; 2] = ["SEAGULL", "BIRD"];
+
+// Hardcoded framerate for now
+const SECONDS_PER_FRAME: Fixed = fixed_const!(0.1);
+
+/// A bird flying across the screen. Birds are purely decorative: they aren't entities, so they
+/// never touch the simulation, and they live in screen (pixel) coordinates rather than on the map.
+#[derive(Clone, Debug)]
+pub struct Bird {
+    position: Vector2<Fixed>,
+    last_position: Vector2<Fixed>,
+    velocity: Vector2<Fixed>,
+    pub frame: u16,
+    frame_time: Fixed,
+}
+
+impl Bird {
+    /// Position to render at, `lerp` of the way from the previous update's to the current one
+    pub fn lerped_position(&self, lerp: Fixed) -> Vector2<i32> {
+        let lerped = self.last_position + (self.position - self.last_position) * lerp;
+        Vector2::new(lerped.x.into(), lerped.y.into())
+    }
+
+    pub fn flies_left(&self) -> bool {
+        self.velocity.x < 0.into()
+    }
+}
+
+// TODO: Loop `sound_group_id` at `AMBIENT_VOLUME` once there's audio output to play it on
+/// The ambient layer: the looping sound picked from the terrain in view, and the birds that fly
+/// across the screen now and then. None of it affects the game, so it draws its random numbers
+/// from its own generator rather than the simulation's.
+pub struct Ambience {
+    rng: GameRng,
+    bird_graphic: Option<(GraphicId, u16)>,
+    birds: Vec<Bird>,
+    seconds_until_flock: Fixed,
+    sound_group_id: Option<SoundGroupId>,
+}
+
+impl Ambience {
+    /// `bird_graphic` is the graphic the birds fly with and its frame count (see
+    /// `find_bird_graphic`); without one, there are no birds
+    pub fn new(seed: u64, bird_graphic: Option<(GraphicId, u16)>) -> Ambience {
+        let mut rng = GameRng::new(seed);
+        let seconds_until_flock = rng.gen_range(MIN_FLOCK_SECONDS, MAX_FLOCK_SECONDS).into();
+        Ambience {
+            rng: rng,
+            bird_graphic: bird_graphic,
+            birds: Vec::new(),
+            seconds_until_flock: seconds_until_flock,
+            sound_group_id: None,
+        }
+    }
+
+    pub fn bird_graphic(&self) -> Option<GraphicId> {
+        self.bird_graphic.map(|(graphic_id, _)| graphic_id)
+    }
+
+    pub fn birds(&self) -> &[Bird] {
+        &self.birds
+    }
+
+    /// The ambient sound that should be looping, if any
+    pub fn sound_group_id(&self) -> Option<SoundGroupId> {
+        self.sound_group_id
+    }
+
+    /// Picks the ambient sound from how many of the visible tiles have each terrain sound
+    pub fn update_sound(&mut self, coverage: &BTreeMap<SoundGroupId, u32>, visible_tiles: u32) {
+        self.sound_group_id = dominant_sound(coverage, visible_tiles);
+    }
+
+    /// Flies the birds along, forgets the ones that have left the screen (`top_left` and `size`,
+    /// in pixels), and sends a new flock across when it's time
+    pub fn update_birds(&mut self, top_left: Vector2<Fixed>, size: Vector2<i32>, time_step: Fixed) {
+        let frame_count = match self.bird_graphic {
+            Some((_, frame_count)) => frame_count,
+            None => return,
+        };
+        let margin = Fixed::from(2 * BIRD_MARGIN);
+        let (left, top) = (top_left.x - margin, top_left.y - margin);
+        let (right, bottom) = (top_left.x + size.x.into() + margin, top_left.y + size.y.into() + margin);
+        for bird in &mut self.birds {
+            bird.last_position = bird.position;
+            bird.position = bird.position + bird.velocity * time_step;
+            bird.frame_time += time_step;
+            if bird.frame_time >= SECONDS_PER_FRAME {
+                bird.frame_time -= SECONDS_PER_FRAME;
+                bird.frame = (bird.frame + 1) % cmp::max(frame_count, 1);
+            }
+        }
+        self.birds.retain(|bird| {
+            bird.position.x >= left && bird.position.x <= right && bird.position.y >= top &&
+            bird.position.y <= bottom
+        });
+
+        self.seconds_until_flock -= time_step;
+        if self.seconds_until_flock <= 0.into() {
+            self.seconds_until_flock = self.rng.gen_range(MIN_FLOCK_SECONDS, MAX_FLOCK_SECONDS).into();
+            self.send_flock(top_left, size, frame_count);
+        }
+    }
+
+    /// Sends a few birds across the upper part of the screen from one side to the other
+    fn send_flock(&mut self, top_left: Vector2<Fixed>, size: Vector2<i32>, frame_count: u16) {
+        let from_left = self.rng.chance(fixed_const!(0.5));
+        let start_x = if from_left {
+            top_left.x - BIRD_MARGIN.into()
+        } else {
+            top_left.x + (size.x + BIRD_MARGIN).into()
+        };
+        let start_y = top_left.y + self.rng.gen_range(0, cmp::max(size.y * 2 / 3, 1) as u32).into();
+        let speed = if from_left { BIRD_SPEED } else { -BIRD_SPEED };
+
+        // Drift up or down a little, so flocks don't all fly dead level
+        let drift = (self.rng.gen_fixed() - fixed_const!(0.5)) * BIRD_SPEED / 4.into();
+
+        let flock_size = self.rng.gen_range(1, MAX_FLOCK_SIZE + 1);
+        for index in 0..flock_size {
+            // Trail off behind the leader in a loose V
+            let side = if index % 2 == 0 { 1 } else { -1 };
+            let offset = Vector2::new(-speed / BIRD_SPEED * Fixed::from(24 * index),
+                                      Fixed::from(12 * side * index as i32));
+            let position = Vector2::new(start_x, start_y) + offset;
+            let frame = self.rng.gen_range(0, cmp::max(frame_count, 1) as u32) as u16;
+            self.birds.push(Bird {
+                position: position,
+                last_position: position,
+                velocity: Vector2::new(speed, drift),
+                frame: frame,
+                frame_time: 0.into(),
+            });
+        }
+    }
+}
+
+/// The bird graphic and its frame count; data without one just goes without birds
+pub fn find_bird_graphic(empires: &dat::EmpiresDbRef) -> Option<(GraphicId, u16)> {
+    for name in &BIRD_GRAPHIC_NAMES {
+        let found = empires.graphics()
+            .iter()
+            .find(|graphic| graphic.slp_id.is_some() && graphic.name.to_uppercase().starts_with(name));
+        if let Some(graphic) = found {
+            return Some((graphic.id, graphic.frame_count));
+        }
+    }
+    None
+}
+
+/// The sound of the terrain covering the most visible tiles, as long as it covers enough of them
+/// (ties go to the lower sound ID, so the pick doesn't flicker)
+fn dominant_sound(coverage: &BTreeMap<SoundGroupId, u32>, visible_tiles: u32) -> Option<SoundGroupId> {
+    let mut dominant: Option<(SoundGroupId, u32)> = None;
+    for (&sound_group_id, &tiles) in coverage {
+        if dominant.map(|(_, most)| tiles > most).unwrap_or(true) {
+            dominant = Some((sound_group_id, tiles));
+        }
+    }
+    match dominant {
+        Some((sound_group_id, tiles)) if visible_tiles > 0 &&
+                                         Fixed::from(tiles) / Fixed::from(visible_tiles) >=
+                                         SOUND_COVERAGE => Some(sound_group_id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+    use std::collections::BTreeMap;
+    use super::{Ambience, MAX_FLOCK_SECONDS, dominant_sound};
+    use types::Fixed;
+
+    #[test]
+    fn test_dominant_sound() {
+        let mut coverage = BTreeMap::new();
+        assert_eq!(None, dominant_sound(&coverage, 0));
+
+        coverage.insert(7.into(), 10);
+        assert_eq!(None, dominant_sound(&coverage, 100));
+
+        coverage.insert(3.into(), 40);
+        coverage.insert(5.into(), 40);
+        assert_eq!(Some(3.into()), dominant_sound(&coverage, 100));
+    }
+
+    #[test]
+    fn test_birds_cross_the_screen() {
+        let mut ambience = Ambience::new(1, Some((1.into(), 10)));
+        let (top_left, size) = (Vector2::new(0.into(), 0.into()), Vector2::new(640, 480));
+        ambience.update_birds(top_left, size, Fixed::from(MAX_FLOCK_SECONDS));
+        assert!(!ambience.birds().is_empty());
+
+        // Crossing 640 pixels plus the margins at 120 pixels per second takes well under 10 seconds
+        for _ in 0..10 {
+            ambience.update_birds(top_left, size, Fixed::from(1));
+        }
+        assert!(ambience.birds().is_empty());
+    }
+}
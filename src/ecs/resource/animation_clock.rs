@@ -0,0 +1,83 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use std::cmp;
+use types::Fixed;
+
+/// Keeps time for the animations that aren't attached to an entity, such as water rippling and
+/// shorelines lapping at the land. Only the view uses it, so it isn't part of the simulation.
+pub struct AnimationClock {
+    seconds: Fixed,
+}
+
+impl AnimationClock {
+    pub fn new() -> AnimationClock {
+        AnimationClock { seconds: 0.into() }
+    }
+
+    pub fn update(&mut self, time_step: Fixed) {
+        self.seconds += time_step;
+    }
+
+    /// Which of the terrain animation's frames is showing right now
+    pub fn terrain_frame(&self, animation: &dat::TerrainAnimation) -> u16 {
+        terrain_frame_at(self.seconds, animation)
+    }
+}
+
+/// Terrain animations play their frames one after the other, then hold the last one for the
+/// pause frames and the pause between loops before starting over
+fn terrain_frame_at(seconds: Fixed, animation: &dat::TerrainAnimation) -> u16 {
+    let frame_interval = Fixed::from(animation.frame_interval);
+    let loop_seconds = frame_interval * Fixed::from(animation.frame_count + animation.pause_frames) +
+                       Fixed::from(animation.pause_between_loops);
+    if frame_interval <= 0.into() || loop_seconds <= 0.into() {
+        return 0;
+    }
+    let frame = u32::from((seconds % loop_seconds) / frame_interval);
+    cmp::min(frame, animation.frame_count as u32 - 1) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use dat;
+    use super::terrain_frame_at;
+    use types::Fixed;
+
+    #[test]
+    fn test_terrain_frame_at() {
+        // Four frames half a second apart, then two pause frames and another second of pause
+        let animation = dat::TerrainAnimation {
+            frame_count: 4,
+            pause_frames: 2,
+            frame_interval: 0.5,
+            pause_between_loops: 1.0,
+        };
+        let frame_at = |seconds: f32| terrain_frame_at(Fixed::from(seconds), &animation);
+        assert_eq!(0, frame_at(0.0));
+        assert_eq!(1, frame_at(0.6));
+        assert_eq!(3, frame_at(1.6));
+        assert_eq!(3, frame_at(3.9));
+        assert_eq!(0, frame_at(4.1));
+        assert_eq!(2, frame_at(5.1));
+    }
+}
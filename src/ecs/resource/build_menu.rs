@@ -0,0 +1,191 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use identifier::UnitId;
+
+/// Villagers have one build menu for civilian buildings and one for military ones
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BuildPage {
+    Civilian,
+    Military,
+}
+
+impl BuildPage {
+    pub fn title(&self) -> &'static str {
+        match *self {
+            BuildPage::Civilian => "Build",
+            BuildPage::Military => "Build Military",
+        }
+    }
+}
+
+/// A building in a build menu, and the letter that picks it
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildOption {
+    pub unit_id: UnitId,
+    pub name: String,
+    pub hotkey: Option<char>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum BuildMenuState {
+    Closed,
+    Open(BuildPage, Vec<BuildOption>),
+    Placing(UnitId),
+}
+
+/// Build shortcuts for the selected villagers: a key opens one of the build menus, and a second
+/// key (shown next to each building) starts placing that building. Escape backs out of either.
+/// Only the local player's view uses this, so it isn't part of the simulation.
+#[derive(Clone, Debug)]
+pub struct BuildMenu {
+    state: BuildMenuState,
+}
+
+impl BuildMenu {
+    pub fn new() -> BuildMenu {
+        BuildMenu { state: BuildMenuState::Closed }
+    }
+
+    /// Whether a menu is open or a building is being placed; either way, letters are for the menu
+    pub fn is_open(&self) -> bool {
+        self.state != BuildMenuState::Closed
+    }
+
+    pub fn page(&self) -> Option<BuildPage> {
+        match self.state {
+            BuildMenuState::Open(page, _) => Some(page),
+            _ => None,
+        }
+    }
+
+    /// The buildings in the open menu, in the order they were given
+    pub fn options<'a>(&'a self) -> &'a [BuildOption] {
+        match self.state {
+            BuildMenuState::Open(_, ref options) => options,
+            _ => &[],
+        }
+    }
+
+    /// The building waiting for the player to pick where it goes, if any
+    pub fn placing(&self) -> Option<UnitId> {
+        match self.state {
+            BuildMenuState::Placing(unit_id) => Some(unit_id),
+            _ => None,
+        }
+    }
+
+    /// Opens the menu with the given buildings (ID and name). Each gets the first letter of its
+    /// name that no building before it has taken.
+    pub fn open(&mut self, page: BuildPage, buildings: Vec<(UnitId, String)>) {
+        let mut taken = Vec::new();
+        let options = buildings.into_iter()
+            .map(|(unit_id, name)| {
+                let hotkey = name.chars().filter_map(upper_letter).find(|letter| !taken.contains(letter));
+                if let Some(letter) = hotkey {
+                    taken.push(letter);
+                }
+                BuildOption {
+                    unit_id: unit_id,
+                    name: name,
+                    hotkey: hotkey,
+                }
+            })
+            .collect();
+        self.state = BuildMenuState::Open(page, options);
+    }
+
+    /// Starts placing the building the letter stands for in the open menu.
+    /// Returns false if nothing in the menu goes with the letter.
+    pub fn pick(&mut self, letter: char) -> bool {
+        let letter = upper_letter(letter);
+        let unit_id = self.options()
+            .iter()
+            .find(|option| option.hotkey.is_some() && option.hotkey == letter)
+            .map(|option| option.unit_id);
+        match unit_id {
+            Some(unit_id) => {
+                self.state = BuildMenuState::Placing(unit_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.state = BuildMenuState::Closed;
+    }
+}
+
+/// The letter in upper case, or nothing for anything that isn't a letter
+fn upper_letter(letter: char) -> Option<char> {
+    if letter >= 'A' && letter <= 'Z' {
+        Some(letter)
+    } else if letter >= 'a' && letter <= 'z' {
+        Some((letter as u8 - b'a' + b'A') as char)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::UnitId;
+    use super::{BuildMenu, BuildPage};
+
+    fn buildings() -> Vec<(UnitId, String)> {
+        vec![(70.into(), "House".to_string()),
+             (68.into(), "Granary".to_string()),
+             (109.into(), "Town Center".to_string()),
+             (104.into(), "Temple".to_string()),
+             (103.into(), "Storage Pit".to_string()),
+             (1.into(), "??".to_string())]
+    }
+
+    #[test]
+    fn test_hotkeys_skip_taken_letters() {
+        let mut menu = BuildMenu::new();
+        menu.open(BuildPage::Civilian, buildings());
+        let hotkeys: Vec<Option<char>> = menu.options().iter().map(|option| option.hotkey).collect();
+        assert_eq!(vec![Some('H'), Some('G'), Some('T'), Some('E'), Some('S'), None], hotkeys);
+        assert_eq!(Some(BuildPage::Civilian), menu.page());
+    }
+
+    #[test]
+    fn test_pick_starts_placement() {
+        let mut menu = BuildMenu::new();
+        assert!(!menu.is_open());
+        assert!(!menu.pick('h'));
+
+        menu.open(BuildPage::Civilian, buildings());
+        assert!(!menu.pick('q'));
+        assert!(menu.pick('t'));
+        assert_eq!(Some(109.into()), menu.placing());
+        assert!(menu.is_open());
+        assert!(menu.options().is_empty());
+
+        // Letters don't do anything while placing
+        assert!(!menu.pick('h'));
+        menu.close();
+        assert!(!menu.is_open());
+        assert_eq!(None, menu.placing());
+    }
+}
@@ -0,0 +1,142 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use types::{Fixed, Vector3};
+
+/// How many camera positions can be bookmarked (one for each of F1 to F4)
+pub const BOOKMARK_COUNT: usize = 4;
+
+/// How long the camera takes to glide over to a bookmark
+const GLIDE_SECONDS: Fixed = fixed_const!(0.4);
+
+#[derive(Clone, Debug)]
+struct Glide {
+    slot: usize,
+    from: Vector3,
+    to: Vector3,
+    elapsed: Fixed,
+}
+
+/// Camera positions the player saved (Ctrl+F1 to Ctrl+F4) to jump back to (F1 to F4). Jumping
+/// glides the camera over instead of cutting straight there; pressing the same key again or
+/// scrolling skips the rest of the glide.
+#[derive(Clone, Debug)]
+pub struct CameraBookmarks {
+    bookmarks: [Option<Vector3>; BOOKMARK_COUNT],
+    glide: Option<Glide>,
+}
+
+impl CameraBookmarks {
+    pub fn new() -> CameraBookmarks {
+        CameraBookmarks {
+            bookmarks: [None; BOOKMARK_COUNT],
+            glide: None,
+        }
+    }
+
+    pub fn set(&mut self, slot: usize, position: Vector3) {
+        self.bookmarks[slot] = Some(position);
+    }
+
+    pub fn get(&self, slot: usize) -> Option<Vector3> {
+        self.bookmarks[slot]
+    }
+
+    /// Starts gliding from `camera_position` to the bookmark in the slot. If the camera is already
+    /// on its way there, it skips straight to it instead. Returns where the camera should be now.
+    pub fn jump(&mut self, slot: usize, camera_position: Vector3) -> Vector3 {
+        let target = match self.bookmarks[slot] {
+            Some(target) => target,
+            None => return camera_position,
+        };
+        let skip = self.glide.as_ref().map(|glide| glide.slot == slot).unwrap_or(false);
+        if skip {
+            self.glide = None;
+            return target;
+        }
+        self.glide = Some(Glide {
+            slot: slot,
+            from: camera_position,
+            to: target,
+            elapsed: 0.into(),
+        });
+        camera_position
+    }
+
+    pub fn cancel_glide(&mut self) {
+        self.glide = None;
+    }
+
+    /// Moves the glide along by `time_step`, and returns where the camera should be, if it's gliding
+    pub fn update_glide(&mut self, time_step: Fixed) -> Option<Vector3> {
+        let (position, done) = match self.glide {
+            Some(ref mut glide) => {
+                glide.elapsed += time_step;
+                if glide.elapsed >= GLIDE_SECONDS {
+                    (glide.to, true)
+                } else {
+                    // Eases in and out (smoothstep), so the camera doesn't lurch at either end
+                    let t = glide.elapsed / GLIDE_SECONDS;
+                    let eased = t * t * (Fixed::from(3) - Fixed::from(2) * t);
+                    (glide.from + (glide.to - glide.from) * eased, false)
+                }
+            }
+            None => return None,
+        };
+        if done {
+            self.glide = None;
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CameraBookmarks;
+    use types::{Fixed, Vector3};
+
+    fn position(x: i32, y: i32) -> Vector3 {
+        Vector3::new(x.into(), y.into(), 0.into())
+    }
+
+    #[test]
+    fn test_glide() {
+        let mut bookmarks = CameraBookmarks::new();
+        assert_eq!(position(5, 5), bookmarks.jump(0, position(5, 5)));
+        assert_eq!(None, bookmarks.update_glide(fixed_const!(0.1)));
+
+        bookmarks.set(1, position(100, 0));
+        assert_eq!(position(0, 0), bookmarks.jump(1, position(0, 0)));
+        assert_eq!(Some(position(50, 0)), bookmarks.update_glide(fixed_const!(0.2)));
+        assert_eq!(Some(position(100, 0)), bookmarks.update_glide(fixed_const!(0.2)));
+        assert_eq!(None, bookmarks.update_glide(fixed_const!(0.1)));
+    }
+
+    #[test]
+    fn test_skip_glide() {
+        let mut bookmarks = CameraBookmarks::new();
+        bookmarks.set(2, position(100, 40));
+        bookmarks.jump(2, position(0, 0));
+        bookmarks.update_glide(fixed_const!(0.1));
+        assert_eq!(position(100, 40), bookmarks.jump(2, position(10, 4)));
+        assert_eq!(None, bookmarks.update_glide(Fixed::from(1)));
+    }
+}
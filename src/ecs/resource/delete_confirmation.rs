@@ -0,0 +1,77 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use specs::Index;
+
+/// Deleting buildings takes a second press of the delete key. The first press arms the deletion
+/// for the selection at the time, and changing the selection calls it off again.
+#[derive(Clone, Debug)]
+pub struct DeleteConfirmation {
+    armed_for: Option<Vec<Index>>,
+}
+
+impl DeleteConfirmation {
+    pub fn new() -> DeleteConfirmation {
+        DeleteConfirmation { armed_for: None }
+    }
+
+    /// Waits for the second press to delete the selection
+    pub fn arm(&mut self, selection: Vec<Index>) {
+        self.armed_for = Some(selection);
+    }
+
+    pub fn disarm(&mut self) {
+        self.armed_for = None;
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed_for.is_some()
+    }
+
+    /// Calls the deletion off if the selection isn't the one it was armed for anymore
+    pub fn update(&mut self, selection: &[Index]) {
+        let changed = match self.armed_for {
+            Some(ref armed_for) => &armed_for[..] != selection,
+            None => false,
+        };
+        if changed {
+            self.armed_for = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeleteConfirmation;
+
+    #[test]
+    fn test_changing_the_selection_disarms() {
+        let mut confirmation = DeleteConfirmation::new();
+        confirmation.arm(vec![1, 2]);
+        confirmation.update(&[1, 2]);
+        assert!(confirmation.is_armed());
+
+        confirmation.update(&[1]);
+        assert!(!confirmation.is_armed());
+        confirmation.update(&[1, 2]);
+        assert!(!confirmation.is_armed());
+    }
+}
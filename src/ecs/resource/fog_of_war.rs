@@ -0,0 +1,392 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::MapVisibility;
+use identifier::PlayerId;
+use specs::Index;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use super::terrain::CHUNK_SIZE;
+
+/// How much of a tile the viewer can see
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TileVisibility {
+    /// Never seen, so nothing on it is drawn
+    Unexplored,
+    /// Seen before but out of sight now: the terrain (and gaia's units) show, nobody else's units do
+    Explored,
+    Visible,
+}
+
+/// Where a unit is, and how far it sees for the viewer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct UnitSight {
+    player_id: PlayerId,
+    tile: (i32, i32),
+
+    /// In tiles; `None` if the viewer doesn't share the unit's vision
+    radius: Option<i32>,
+}
+
+/// What the local player can see of the map, merged from the sight of every unit whose vision
+/// they share (see `Players::vision_player_ids`), and who is where for the minimap. Every player
+/// sees something different, so it's kept out of the simulation (and out of saves).
+///
+/// Units are only looked at again when they move or their sight changes, and each tile keeps a
+/// count of the units that see it, so only the tiles a unit leaves or comes into sight of change.
+/// Chunks get revisions the way the terrain's do, so the terrain and the minimap only redraw the
+/// chunks where something changed.
+pub struct FogOfWar {
+    width: i32,
+    height: i32,
+
+    /// Everything is in sight all the time (see `MapVisibility::Revealed`)
+    revealed: bool,
+
+    /// How many units see each tile
+    sight_counts: Vec<u16>,
+    explored: Vec<bool>,
+    units: BTreeMap<Index, UnitSight>,
+
+    /// Everyone on each tile, with the player they belong to
+    occupants: BTreeMap<(i32, i32), BTreeMap<Index, PlayerId>>,
+
+    /// Bumped when a tile in the chunk comes into or goes out of sight, or a unit comes or goes
+    chunk_revisions: Vec<u32>,
+
+    /// Bumped when more of the chunk is explored, which is all that the terrain cares about
+    explored_revisions: Vec<u32>,
+
+    /// The player and revision of the simulation's exploration that was last merged in
+    merged_exploration: Option<(PlayerId, u32)>,
+}
+
+impl FogOfWar {
+    pub fn new(width: i32, height: i32, visibility: MapVisibility) -> FogOfWar {
+        let tile_count = (width * height) as usize;
+        let chunk_count = (((width + CHUNK_SIZE - 1) / CHUNK_SIZE) *
+                           ((height + CHUNK_SIZE - 1) / CHUNK_SIZE)) as usize;
+        FogOfWar {
+            width: width,
+            height: height,
+            revealed: visibility == MapVisibility::Revealed,
+            sight_counts: vec![0; tile_count],
+            explored: vec![visibility != MapVisibility::Normal; tile_count],
+            units: BTreeMap::new(),
+            occupants: BTreeMap::new(),
+            chunk_revisions: vec![0; chunk_count],
+            explored_revisions: vec![0; chunk_count],
+            merged_exploration: None,
+        }
+    }
+
+    pub fn visibility(&self, row: i32, col: i32) -> TileVisibility {
+        let index = match self.tile_index(row, col) {
+            Some(index) => index,
+            None => return TileVisibility::Unexplored,
+        };
+        if self.revealed || self.sight_counts[index] > 0 {
+            TileVisibility::Visible
+        } else if self.explored[index] {
+            TileVisibility::Explored
+        } else {
+            TileVisibility::Unexplored
+        }
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.revealed
+    }
+
+    /// Shows the whole map as if it were all in sight (see `MapVisibility::Revealed`), or goes
+    /// back to showing only what's been seen. Every chunk changes, so everything is redrawn.
+    pub fn set_revealed(&mut self, revealed: bool) {
+        if self.revealed == revealed {
+            return;
+        }
+        self.revealed = revealed;
+        for revision in self.chunk_revisions.iter_mut().chain(self.explored_revisions.iter_mut()) {
+            *revision = revision.wrapping_add(1);
+        }
+    }
+
+    /// Puts the unit (and its sight, if `radius` is given) on the tile it's on now. Nothing
+    /// changes if it's where it was with the same sight.
+    pub fn update_unit(&mut self,
+                       entity_id: Index,
+                       player_id: PlayerId,
+                       row: i32,
+                       col: i32,
+                       radius: Option<i32>) {
+        let sight = UnitSight {
+            player_id: player_id,
+            tile: (row, col),
+            radius: radius,
+        };
+        let previous = self.units.get(&entity_id).cloned();
+        if previous == Some(sight) {
+            return;
+        }
+        if let Some(previous) = previous {
+            self.forget(entity_id, previous);
+        }
+        self.see(entity_id, sight);
+        self.units.insert(entity_id, sight);
+    }
+
+    /// Forgets the units that aren't in the set (the ones that have been removed from the world)
+    pub fn retain_units(&mut self, entity_ids: &BTreeSet<Index>) {
+        let removed: Vec<Index> = self.units
+            .keys()
+            .filter(|entity_id| !entity_ids.contains(entity_id))
+            .cloned()
+            .collect();
+        for entity_id in removed {
+            if let Some(sight) = self.units.remove(&entity_id) {
+                self.forget(entity_id, sight);
+            }
+        }
+    }
+
+    /// Adds what the simulation says the player has explored (which includes the maps that
+    /// defeated allies left them, and survives loading a saved game). Nothing is looked at
+    /// unless the revision changed since the last time.
+    pub fn merge_exploration(&mut self, player_id: PlayerId, revision: u32, tiles: &[bool]) {
+        if self.merged_exploration == Some((player_id, revision)) {
+            return;
+        }
+        self.merged_exploration = Some((player_id, revision));
+        for index in 0..cmp::min(tiles.len(), self.explored.len()) {
+            if tiles[index] && !self.explored[index] {
+                let (row, col) = (index as i32 / self.width, index as i32 % self.width);
+                self.explore(row, col, index);
+            }
+        }
+    }
+
+    /// Who the first unit (in entity order) on the tile belongs to, if anyone is on it
+    pub fn occupant(&self, row: i32, col: i32) -> Option<PlayerId> {
+        self.occupants.get(&(row, col)).and_then(|units| units.values().next().cloned())
+    }
+
+    /// The chunk's revision, which changes whenever what the minimap shows of it does.
+    /// Chunks outside of the map never change.
+    pub fn chunk_revision(&self, chunk_row: i32, chunk_col: i32) -> u32 {
+        self.chunk_index(chunk_row, chunk_col).map(|index| self.chunk_revisions[index]).unwrap_or(0)
+    }
+
+    /// The revision of how much of the chunk has been explored
+    pub fn explored_revision(&self, chunk_row: i32, chunk_col: i32) -> u32 {
+        self.chunk_index(chunk_row, chunk_col).map(|index| self.explored_revisions[index]).unwrap_or(0)
+    }
+
+    fn see(&mut self, entity_id: Index, sight: UnitSight) {
+        self.occupants.entry(sight.tile).or_insert_with(BTreeMap::new).insert(entity_id, sight.player_id);
+        self.touch(sight.tile.0, sight.tile.1);
+        if let Some(radius) = sight.radius {
+            self.change_sight(sight.tile, radius, true);
+        }
+    }
+
+    fn forget(&mut self, entity_id: Index, sight: UnitSight) {
+        let emptied = match self.occupants.get_mut(&sight.tile) {
+            Some(units) => {
+                units.remove(&entity_id);
+                units.is_empty()
+            }
+            None => false,
+        };
+        if emptied {
+            self.occupants.remove(&sight.tile);
+        }
+        self.touch(sight.tile.0, sight.tile.1);
+        if let Some(radius) = sight.radius {
+            self.change_sight(sight.tile, radius, false);
+        }
+    }
+
+    /// Adds (or takes away) one unit's sight from every tile within the radius. Only the tiles
+    /// that come into or go out of sight change what's drawn.
+    fn change_sight(&mut self, center: (i32, i32), radius: i32, adding: bool) {
+        let (center_row, center_col) = center;
+        for row in (center_row - radius)..(center_row + radius + 1) {
+            for col in (center_col - radius)..(center_col + radius + 1) {
+                let (row_offset, col_offset) = (row - center_row, col - center_col);
+                if row_offset * row_offset + col_offset * col_offset > radius * radius {
+                    continue;
+                }
+                let index = match self.tile_index(row, col) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                if adding {
+                    self.sight_counts[index] += 1;
+                    if self.sight_counts[index] == 1 {
+                        self.touch(row, col);
+                    }
+                    if !self.explored[index] {
+                        self.explore(row, col, index);
+                    }
+                } else if self.sight_counts[index] > 0 {
+                    self.sight_counts[index] -= 1;
+                    if self.sight_counts[index] == 0 {
+                        self.touch(row, col);
+                    }
+                }
+            }
+        }
+    }
+
+    fn explore(&mut self, row: i32, col: i32, index: usize) {
+        self.explored[index] = true;
+        if let Some(chunk_index) = self.chunk_index(row / CHUNK_SIZE, col / CHUNK_SIZE) {
+            self.explored_revisions[chunk_index] = self.explored_revisions[chunk_index].wrapping_add(1);
+        }
+    }
+
+    fn touch(&mut self, row: i32, col: i32) {
+        if self.tile_index(row, col).is_none() {
+            return;
+        }
+        if let Some(index) = self.chunk_index(row / CHUNK_SIZE, col / CHUNK_SIZE) {
+            self.chunk_revisions[index] = self.chunk_revisions[index].wrapping_add(1);
+        }
+    }
+
+    fn tile_index(&self, row: i32, col: i32) -> Option<usize> {
+        if row < 0 || col < 0 || row >= self.height || col >= self.width {
+            return None;
+        }
+        Some((row * self.width + col) as usize)
+    }
+
+    fn chunk_index(&self, chunk_row: i32, chunk_col: i32) -> Option<usize> {
+        let chunk_rows = (self.height + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let chunk_cols = (self.width + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        if chunk_row < 0 || chunk_col < 0 || chunk_row >= chunk_rows || chunk_col >= chunk_cols {
+            return None;
+        }
+        Some((chunk_row * chunk_cols + chunk_col) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::MapVisibility;
+    use std::collections::BTreeSet;
+    use super::{FogOfWar, TileVisibility};
+    use super::super::terrain::CHUNK_SIZE;
+
+    #[test]
+    fn test_sight_follows_units() {
+        let mut fog = FogOfWar::new(CHUNK_SIZE * 3, CHUNK_SIZE * 3, MapVisibility::Normal);
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(5, 5));
+
+        fog.update_unit(1, 1.into(), 5, 5, Some(2));
+        assert_eq!(TileVisibility::Visible, fog.visibility(5, 7));
+        assert_eq!(TileVisibility::Visible, fog.visibility(4, 4));
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(5, 8));
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(7, 7));
+        let explored_revision = fog.explored_revision(0, 0);
+        assert!(explored_revision != 0);
+        let revision = fog.chunk_revision(0, 0);
+
+        // Standing still doesn't change anything
+        fog.update_unit(1, 1.into(), 5, 5, Some(2));
+        assert_eq!(revision, fog.chunk_revision(0, 0));
+
+        // What's left behind stays explored
+        let (row, col) = (CHUNK_SIZE + 5, CHUNK_SIZE + 5);
+        fog.update_unit(1, 1.into(), row, col, Some(2));
+        assert_eq!(TileVisibility::Explored, fog.visibility(5, 5));
+        assert_eq!(TileVisibility::Visible, fog.visibility(row, col));
+        assert!(fog.chunk_revision(0, 0) != revision);
+        assert_eq!(explored_revision, fog.explored_revision(0, 0));
+        assert!(fog.explored_revision(1, 1) != 0);
+        assert_eq!(0, fog.chunk_revision(2, 2));
+    }
+
+    #[test]
+    fn test_overlapping_and_removed_units() {
+        let mut fog = FogOfWar::new(CHUNK_SIZE, CHUNK_SIZE, MapVisibility::Normal);
+        fog.update_unit(1, 1.into(), 5, 5, Some(2));
+        fog.update_unit(2, 1.into(), 5, 6, Some(2));
+        fog.update_unit(3, 2.into(), 9, 9, None);
+        assert_eq!(Some(2.into()), fog.occupant(9, 9));
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(9, 9));
+
+        let mut alive = BTreeSet::new();
+        alive.insert(2);
+        fog.retain_units(&alive);
+        assert_eq!(None, fog.occupant(5, 5));
+        assert_eq!(None, fog.occupant(9, 9));
+        assert_eq!(Some(1.into()), fog.occupant(5, 6));
+        assert_eq!(TileVisibility::Visible, fog.visibility(5, 5));
+        assert_eq!(TileVisibility::Explored, fog.visibility(5, 3));
+    }
+
+    #[test]
+    fn test_merge_exploration() {
+        let mut fog = FogOfWar::new(CHUNK_SIZE, CHUNK_SIZE, MapVisibility::Normal);
+        let mut tiles = vec![false; (CHUNK_SIZE * CHUNK_SIZE) as usize];
+        tiles[(3 * CHUNK_SIZE + 4) as usize] = true;
+        fog.merge_exploration(1.into(), 1, &tiles);
+        assert_eq!(TileVisibility::Explored, fog.visibility(3, 4));
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(4, 3));
+        let explored_revision = fog.explored_revision(0, 0);
+        assert!(explored_revision != 0);
+
+        // The same revision isn't merged again
+        tiles[(4 * CHUNK_SIZE + 3) as usize] = true;
+        fog.merge_exploration(1.into(), 1, &tiles);
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(4, 3));
+        fog.merge_exploration(1.into(), 2, &tiles);
+        assert_eq!(TileVisibility::Explored, fog.visibility(4, 3));
+    }
+
+    #[test]
+    fn test_map_visibility() {
+        let explored = FogOfWar::new(CHUNK_SIZE, CHUNK_SIZE, MapVisibility::Explored);
+        assert_eq!(TileVisibility::Explored, explored.visibility(3, 3));
+        let revealed = FogOfWar::new(CHUNK_SIZE, CHUNK_SIZE, MapVisibility::Revealed);
+        assert_eq!(TileVisibility::Visible, revealed.visibility(3, 3));
+        assert_eq!(TileVisibility::Unexplored, revealed.visibility(-1, 3));
+    }
+
+    #[test]
+    fn test_set_revealed() {
+        let mut fog = FogOfWar::new(CHUNK_SIZE, CHUNK_SIZE, MapVisibility::Normal);
+        fog.update_unit(1, 1.into(), 5, 5, Some(2));
+        let revision = fog.chunk_revision(0, 0);
+        let explored_revision = fog.explored_revision(0, 0);
+
+        fog.set_revealed(true);
+        assert!(fog.is_revealed());
+        assert_eq!(TileVisibility::Visible, fog.visibility(9, 9));
+        assert!(fog.chunk_revision(0, 0) != revision);
+        assert!(fog.explored_revision(0, 0) != explored_revision);
+
+        // Going back only shows what the units have seen, and revealing the map didn't explore it
+        fog.set_revealed(false);
+        assert_eq!(TileVisibility::Unexplored, fog.visibility(9, 9));
+        assert_eq!(TileVisibility::Visible, fog.visibility(5, 5));
+    }
+}
@@ -19,18 +19,26 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use media::{Key, KeyStates, MouseButton};
+use media::{Key, KeyState, KeyStates, MouseButton};
 
 use nalgebra::Vector2;
 
+use std::cmp;
 use std::collections::HashMap;
+use types::Rect;
 
 pub type KeyboardKeyStates = KeyStates<Key>;
 pub type MouseKeyStates = KeyStates<MouseButton>;
 
+/// How far (in pixels) the mouse has to move with the left button held to drag out a selection box
+const DRAG_THRESHOLD: i32 = 4;
+
 pub struct MouseState {
     pub position: Vector2<i32>,
     pub key_states: MouseKeyStates,
+
+    /// Where the left button went down, while it's held (and on the frame it's released)
+    pub drag_start: Option<Vector2<i32>>,
 }
 
 impl MouseState {
@@ -38,6 +46,33 @@ impl MouseState {
         MouseState {
             position: Vector2::new(0, 0),
             key_states: MouseKeyStates::new(HashMap::new()),
+            drag_start: None,
+        }
+    }
+
+    /// Keeps track of where the left button went down; call after updating the key states
+    pub fn update_drag(&mut self) {
+        match self.key_states.key_state(MouseButton::Left) {
+            KeyState::TransitionDown => self.drag_start = Some(self.position),
+            KeyState::Up => self.drag_start = None,
+            _ => {}
+        }
+    }
+
+    /// The screen rectangle being dragged out with the left button, if the mouse has moved far
+    /// enough since the button went down to count as a drag rather than a click
+    pub fn drag_rect(&self) -> Option<Rect> {
+        let start = match self.drag_start {
+            Some(start) => start,
+            None => return None,
+        };
+        let (width, height) = ((self.position.x - start.x).abs(), (self.position.y - start.y).abs());
+        if cmp::max(width, height) < DRAG_THRESHOLD {
+            return None;
         }
+        Some(Rect::of(cmp::min(start.x, self.position.x),
+                      cmp::min(start.y, self.position.y),
+                      width,
+                      height))
     }
 }
@@ -19,22 +19,31 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod action_batcher;
+mod acknowledgments;
+mod ambience;
+mod animation_clock;
+mod build_menu;
+mod camera_bookmarks;
+mod delete_confirmation;
+mod fog_of_war;
 mod input;
-pub mod path_finder;
-mod occupied_tiles;
-mod players;
 mod render;
-pub mod terrain;
+mod sub_selection;
 mod view_projector;
 mod viewport;
 
-pub use self::action_batcher::ActionBatcher;
+// The simulation's resources live in chariot_sim; the ones here only serve input and the view
+pub use sim::ecs::resource::*;
+
+pub use self::acknowledgments::{Acknowledgment, AcknowledgmentKind, Acknowledgments};
+pub use self::ambience::{AMBIENT_VOLUME, Ambience, Bird, find_bird_graphic};
+pub use self::animation_clock::AnimationClock;
+pub use self::build_menu::{BuildMenu, BuildOption, BuildPage};
+pub use self::camera_bookmarks::{BOOKMARK_COUNT, CameraBookmarks};
+pub use self::delete_confirmation::DeleteConfirmation;
+pub use self::fog_of_war::{FogOfWar, TileVisibility};
 pub use self::input::{KeyboardKeyStates, MouseState};
-pub use self::occupied_tiles::OccupiedTiles;
-pub use self::path_finder::PathFinder;
-pub use self::players::{Player, Players};
 pub use self::render::RenderCommands;
-pub use self::terrain::{Terrain, Tile};
+pub use self::sub_selection::SubSelection;
 pub use self::view_projector::ViewProjector;
 pub use self::viewport::Viewport;
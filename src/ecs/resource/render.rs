@@ -20,22 +20,46 @@
 // SOFTWARE.
 
 use media::Renderer;
-use resource::{RenderCommand, ShapeManager};
+use resource::{CompositeKey, RenderCommand, RenderShapeParams, ShapeManager};
 
 pub struct RenderCommands {
     commands: Vec<RenderCommand>,
+
+    /// Composites to build before the commands are drawn, and ones that aren't needed anymore
+    composites: Vec<(CompositeKey, Vec<RenderShapeParams>)>,
+    forgotten_composites: Vec<CompositeKey>,
 }
 
 impl RenderCommands {
     pub fn new() -> RenderCommands {
-        RenderCommands { commands: Vec::new() }
+        RenderCommands {
+            commands: Vec::new(),
+            composites: Vec::new(),
+            forgotten_composites: Vec::new(),
+        }
     }
 
     pub fn push(&mut self, render_command: RenderCommand) {
         self.commands.push(render_command);
     }
 
+    /// Has the shapes drawn into a texture under the key before this frame is drawn, so that
+    /// `RenderCommand::new_composite` can draw them all at once from then on
+    pub fn build_composite(&mut self, key: CompositeKey, shapes: Vec<RenderShapeParams>) {
+        self.composites.push((key, shapes));
+    }
+
+    pub fn forget_composite(&mut self, key: CompositeKey) {
+        self.forgotten_composites.push(key);
+    }
+
     pub fn execute(&mut self, renderer: &mut Renderer, shape_manager: &mut ShapeManager) {
+        for key in self.forgotten_composites.drain(..) {
+            shape_manager.forget_composite(&key);
+        }
+        for (key, shapes) in self.composites.drain(..) {
+            shape_manager.build_composite(key, &shapes, renderer);
+        }
         RenderCommand::render_all(renderer, shape_manager, &mut self.commands);
     }
 
@@ -0,0 +1,109 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use identifier::UnitId;
+
+/// Which kind of unit in a mixed selection the command panel works on. Tab cycles through the
+/// kinds of units selected (the same grouping as the selection's portraits), and commands that
+/// come from the command panel only go to the active kind.
+#[derive(Clone, Debug)]
+pub struct SubSelection {
+    active: Option<UnitId>,
+}
+
+impl SubSelection {
+    pub fn new() -> SubSelection {
+        SubSelection { active: None }
+    }
+
+    /// Goes back to working on every selected unit (when the selection changes)
+    pub fn reset(&mut self) {
+        self.active = None;
+    }
+
+    /// The active kind of unit, if it's still part of the selection
+    pub fn active(&self, selected_kinds: &[UnitId]) -> Option<UnitId> {
+        match self.active {
+            Some(unit_id) if selected_kinds.contains(&unit_id) => Some(unit_id),
+            _ => None,
+        }
+    }
+
+    /// Whether commands from the command panel go to units of this kind
+    pub fn includes(&self, unit_id: UnitId, selected_kinds: &[UnitId]) -> bool {
+        match self.active(selected_kinds) {
+            Some(active) => active == unit_id,
+            None => true,
+        }
+    }
+
+    /// Moves on to the next kind of unit in the selection, in unit ID order. A selection of a
+    /// single kind has nothing to cycle through.
+    pub fn cycle(&mut self, selected_kinds: &[UnitId]) {
+        let mut kinds = selected_kinds.to_vec();
+        kinds.sort();
+        kinds.dedup();
+        if kinds.len() < 2 {
+            self.active = None;
+            return;
+        }
+        self.active = match self.active(&kinds) {
+            Some(active) => kinds.iter().cloned().find(|&unit_id| unit_id > active).or(Some(kinds[0])),
+            None => Some(kinds[0]),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::UnitId;
+    use super::SubSelection;
+
+    #[test]
+    fn test_cycle_through_kinds() {
+        let (archer, axeman): (UnitId, UnitId) = (4.into(), 74.into());
+        let kinds = [axeman, archer, axeman];
+        let mut sub_selection = SubSelection::new();
+        assert_eq!(None, sub_selection.active(&kinds));
+        assert!(sub_selection.includes(archer, &kinds) && sub_selection.includes(axeman, &kinds));
+
+        sub_selection.cycle(&kinds);
+        assert_eq!(Some(archer), sub_selection.active(&kinds));
+        assert!(!sub_selection.includes(axeman, &kinds));
+        sub_selection.cycle(&kinds);
+        assert_eq!(Some(axeman), sub_selection.active(&kinds));
+        sub_selection.cycle(&kinds);
+        assert_eq!(Some(archer), sub_selection.active(&kinds));
+    }
+
+    #[test]
+    fn test_active_kind_must_still_be_selected() {
+        let (archer, axeman): (UnitId, UnitId) = (4.into(), 74.into());
+        let mut sub_selection = SubSelection::new();
+        sub_selection.cycle(&[archer, axeman]);
+        assert_eq!(None, sub_selection.active(&[axeman]));
+        assert!(sub_selection.includes(axeman, &[axeman]));
+
+        sub_selection.cycle(&[axeman]);
+        assert_eq!(None, sub_selection.active(&[archer, axeman]));
+    }
+}
@@ -22,10 +22,14 @@
 use nalgebra::Vector2;
 use types::Fixed;
 
+#[derive(Clone)]
 pub struct Viewport {
     current_top_left: Vector2<Fixed>,
     last_top_left: Vector2<Fixed>,
     pub size: Vector2<i32>,
+
+    /// How fast the arrow keys scroll the view, as a multiple of the normal speed
+    pub scroll_speed: Fixed,
 }
 
 impl Viewport {
@@ -34,6 +38,7 @@ impl Viewport {
             current_top_left: Vector2::new(0.into(), 0.into()),
             last_top_left: Vector2::new(0.into(), 0.into()),
             size: Vector2::new(w, h),
+            scroll_speed: 1.into(),
         }
     }
 
@@ -51,8 +56,9 @@ impl Viewport {
         self.current_top_left = top_left;
     }
 
+    /// Top left corner to render at, `lerp` of the way from the previous tick's to the current one
     pub fn lerped_top_left(&self, lerp: Fixed) -> Vector2<i32> {
-        let lerped = self.current_top_left + (self.current_top_left - self.last_top_left) * lerp;
+        let lerped = self.last_top_left + (self.current_top_left - self.last_top_left) * lerp;
         Vector2::new(lerped.x.into(), lerped.y.into())
     }
 }
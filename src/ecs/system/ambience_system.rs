@@ -0,0 +1,90 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat;
+use ecs::resource::{Ambience, Terrain, ViewProjector, Viewport};
+use specs;
+use std::collections::BTreeMap;
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// How often, in seconds, to look at the terrain in view to pick the ambient sound
+const SOUND_CHECK_SECONDS: Fixed = fixed_const!(1.0);
+
+/// Keeps the ambient layer going: picks the looping sound from the terrain in view, and flies
+/// birds across the screen. Only touches the `Ambience` resource, so it never affects the game.
+pub struct AmbienceSystem {
+    empires: dat::EmpiresDbRef,
+    seconds_until_sound_check: Fixed,
+}
+
+impl AmbienceSystem {
+    pub fn new(empires: dat::EmpiresDbRef) -> AmbienceSystem {
+        AmbienceSystem {
+            empires: empires,
+            seconds_until_sound_check: 0.into(),
+        }
+    }
+}
+
+impl System for AmbienceSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<Viewport>()
+            .read::<ViewProjector>()
+            .read::<Terrain>()
+            .write::<Ambience>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
+        fetch_components!(arg, _entities, [
+            resource(viewport: Viewport),
+            resource(projector: ViewProjector),
+            resource(terrain: Terrain),
+            mut resource(ambience: Ambience),
+        ]);
+
+        ambience.update_birds(*viewport.top_left(), viewport.size, time_step);
+
+        self.seconds_until_sound_check -= time_step;
+        if self.seconds_until_sound_check > 0.into() {
+            return;
+        }
+        self.seconds_until_sound_check = SOUND_CHECK_SECONDS;
+
+        let area = projector.calculate_visible_world_coords(&viewport, &*terrain);
+        let mut coverage = BTreeMap::new();
+        let mut visible_tiles = 0;
+        for row in area.y..(area.y + area.h) {
+            for col in area.x..(area.x + area.w) {
+                if !terrain.contains(row, col) {
+                    continue;
+                }
+                visible_tiles += 1;
+                let terrain_id = terrain.tile_at_row_col(row, col).terrain_id;
+                if let Some(sound_group_id) = self.empires.terrain(terrain_id).sound_group_id() {
+                    *coverage.entry(sound_group_id).or_insert(0) += 1;
+                }
+            }
+        }
+        ambience.update_sound(&coverage, visible_tiles);
+    }
+}
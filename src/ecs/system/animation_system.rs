@@ -21,10 +21,11 @@
 
 use dat;
 use ecs::{GraphicComponent, TransformComponent};
+use ecs::resource::AnimationClock;
 use resource::{DrsKey, ShapeMetadataKey, ShapeMetadataStoreRef};
 use specs::{self, Join};
 use std::ops::Rem;
-use super::System;
+use super::{System, SystemAccess};
 use types::Fixed;
 
 pub struct AnimationSystem {
@@ -70,16 +71,28 @@ impl AnimationSystem {
 }
 
 impl System for AnimationSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .write::<GraphicComponent>()
+            .write::<AnimationClock>()
+    }
+
     fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
         fetch_components!(arg, _entities, [
             components(transforms: TransformComponent),
             mut components(graphics: GraphicComponent),
+            mut resource(animation_clock: AnimationClock),
         ]);
 
+        animation_clock.update(time_step);
+
         for (transform, graphic) in (&transforms, &mut graphics).iter() {
             if let Some(graphic_id) = graphic.graphic_id {
                 let graphic_info = self.empires.graphic(graphic_id);
-                if graphic_info.frame_count > 1 {
+                // Single frame graphics still need to be updated if they have several angles
+                // so that idle units keep facing the way they were last moving or attacking
+                if graphic_info.frame_count > 1 || graphic_info.angle_count > 1 {
                     self.update_graphic(graphic, transform.rotation, graphic_info, time_step);
                 }
             }
@@ -160,6 +173,11 @@ mod tests {
                    start_frame_and_mirroring(rad(271), 30, 6, 8));
         assert_eq!((4u16 * 6, false),
                    start_frame_and_mirroring(rad(316), 30, 6, 8));
+
+        // Single frame graphics with five angles in the SLP
+        assert_eq!((3u16, true), start_frame_and_mirroring(rad(1), 5, 1, 8));
+        assert_eq!((1u16, false), start_frame_and_mirroring(rad(181), 5, 1, 8));
+        assert_eq!((4u16, false), start_frame_and_mirroring(rad(316), 5, 1, 8));
     }
 
     #[test]
@@ -19,11 +19,11 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use ecs::{CameraComponent, VelocityComponent};
-use ecs::resource::KeyboardKeyStates;
-use media::Key;
+use ecs::{CameraComponent, TransformComponent, VelocityComponent};
+use ecs::resource::{BOOKMARK_COUNT, CameraBookmarks, KeyboardKeyStates, Viewport};
+use media::{Key, KeyState};
 use specs::{self, Join};
-use super::System;
+use super::{System, SystemAccess};
 use types::{Fixed, Norm, Vector3};
 
 // TODO: Doesn't currently match the camera speed in the original game
@@ -31,6 +31,8 @@ const CAMERA_SPEED: Fixed = fixed_const!(400);
 
 const ZERO_THRESHOLD: Fixed = fixed_const!(0.0001);
 
+const BOOKMARK_KEYS: [Key; BOOKMARK_COUNT] = [Key::F1, Key::F2, Key::F3, Key::F4];
+
 pub struct CameraInputSystem;
 
 impl CameraInputSystem {
@@ -40,14 +42,40 @@ impl CameraInputSystem {
 }
 
 impl System for CameraInputSystem {
-    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<CameraComponent>()
+            .read::<TransformComponent>()
+            .write::<VelocityComponent>()
+            .read::<KeyboardKeyStates>()
+            .read::<Viewport>()
+            .write::<CameraBookmarks>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
         fetch_components!(arg, _entities, [
             components(cameras: CameraComponent),
+            components(transforms: TransformComponent),
             mut components(velocities: VelocityComponent),
             resource(keyboard_key_states: KeyboardKeyStates),
+            resource(viewport: Viewport),
+            mut resource(bookmarks: CameraBookmarks),
         ]);
 
-        for (velocity, _camera) in (&mut velocities, &cameras).iter() {
+        for (velocity, transform, _camera) in (&mut velocities, &transforms, &cameras).iter() {
+            let camera_position = *transform.position();
+            let mut jumped_to = None;
+            for (slot, &key) in BOOKMARK_KEYS.iter().enumerate() {
+                if keyboard_key_states.key_state(key) != KeyState::TransitionDown {
+                    continue;
+                }
+                if keyboard_key_states.is_down(Key::CtrlLeft) {
+                    bookmarks.set(slot, camera_position);
+                } else {
+                    jumped_to = Some(bookmarks.jump(slot, camera_position));
+                }
+            }
+
             let mut new_velocity = Vector3::new(0.into(), 0.into(), 0.into());
 
             if keyboard_key_states.key_state(Key::Up).is_down() {
@@ -64,9 +92,18 @@ impl System for CameraInputSystem {
 
             if new_velocity.length_squared() > ZERO_THRESHOLD {
                 new_velocity.normalize();
+                bookmarks.cancel_glide();
+                velocity.velocity = new_velocity * (CAMERA_SPEED * viewport.scroll_speed);
+                continue;
             }
 
-            velocity.velocity = new_velocity * CAMERA_SPEED;
+            // The velocity system does the moving (on the next tick), so aim for wherever the
+            // glide should be by then
+            let target = bookmarks.update_glide(time_step).or(jumped_to);
+            velocity.velocity = match target {
+                Some(target) if time_step > 0.into() => (target - camera_position) / time_step,
+                _ => new_velocity,
+            };
         }
     }
 }
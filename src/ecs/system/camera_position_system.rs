@@ -23,7 +23,7 @@ use ecs::{CameraComponent, TransformComponent};
 use ecs::resource::Viewport;
 use nalgebra::Vector2;
 use specs::{self, Join};
-use super::System;
+use super::{System, SystemAccess};
 use types::Fixed;
 
 pub struct CameraPositionSystem;
@@ -35,6 +35,13 @@ impl CameraPositionSystem {
 }
 
 impl System for CameraPositionSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<CameraComponent>()
+            .write::<Viewport>()
+    }
+
     fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
         fetch_components!(arg, _entities, [
             components(transforms: TransformComponent),
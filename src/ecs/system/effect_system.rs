@@ -19,41 +19,35 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use ecs::DecalComponent;
+use ecs::resource::EffectSpawner;
 use resource::{ShapeMetadataKey, ShapeMetadataStoreRef};
-use specs::{self, Join};
-use super::System;
+use specs;
+use super::{System, SystemAccess};
 use types::Fixed;
 
-// Hardcoded framerate for now
-const SECONDS_PER_FRAME: Fixed = fixed_const!(0.1);
-
-pub struct DecalSystem {
+/// Plays the effects, and lets them go once they're done
+pub struct EffectSystem {
     shape_metadata: ShapeMetadataStoreRef,
 }
 
-impl DecalSystem {
-    pub fn new(shape_metadata: ShapeMetadataStoreRef) -> DecalSystem {
-        DecalSystem { shape_metadata: shape_metadata }
+impl EffectSystem {
+    pub fn new(shape_metadata: ShapeMetadataStoreRef) -> EffectSystem {
+        EffectSystem { shape_metadata: shape_metadata }
     }
 }
 
-impl System for DecalSystem {
+impl System for EffectSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new().write::<EffectSpawner>()
+    }
+
     fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
-        fetch_components!(arg, entities, [ mut components(decals: DecalComponent), ]);
+        fetch_components!(arg, _entities, [ mut resource(effects: EffectSpawner), ]);
 
-        for (entity, decal) in (&entities, &mut decals).iter() {
-            let shape_key = ShapeMetadataKey::new(decal.drs_key, decal.slp_file_id);
-            if let Some(shape_metadata) = self.shape_metadata.get(&shape_key) {
-                decal.frame_time += time_step;
-                if decal.frame_time >= SECONDS_PER_FRAME {
-                    decal.frame_time -= SECONDS_PER_FRAME;
-                    decal.frame += 1;
-                    if decal.frame >= shape_metadata.shape_count as u16 {
-                        arg.delete(entity);
-                    }
-                }
-            }
-        }
+        let shape_metadata = &self.shape_metadata;
+        effects.update(time_step, |shape| {
+            let shape_key = ShapeMetadataKey::new(shape.drs_key, shape.slp_file_id);
+            shape_metadata.get(&shape_key).map(|metadata| metadata.shape_count as u16)
+        });
     }
 }
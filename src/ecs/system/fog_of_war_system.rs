@@ -0,0 +1,87 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::{LineOfSightComponent, TransformComponent, UnitComponent};
+use ecs::resource::{Exploration, FogOfWar, PlayerTechs, Players};
+use specs::{self, Join};
+use std::collections::BTreeSet;
+use super::{System, SystemAccess};
+use types::{Fixed, ToPrimitive};
+
+/// Moves the fog of war along with the units, merging in the sight of everyone the local player
+/// shares vision with, and what the simulation says they've explored. Observers see what the
+/// player they're watching sees.
+pub struct FogOfWarSystem;
+
+impl FogOfWarSystem {
+    pub fn new() -> FogOfWarSystem {
+        FogOfWarSystem
+    }
+}
+
+impl System for FogOfWarSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<TransformComponent>()
+            .read::<UnitComponent>()
+            .read::<LineOfSightComponent>()
+            .read::<Players>()
+            .read::<PlayerTechs>()
+            .read::<Exploration>()
+            .write::<FogOfWar>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, entities, [
+            components(transforms: TransformComponent),
+            components(units: UnitComponent),
+            components(line_of_sights: LineOfSightComponent),
+            resource(players: Players),
+            resource(techs: PlayerTechs),
+            resource(exploration: Exploration),
+            mut resource(fog: FogOfWar),
+        ]);
+
+        let local_player_id = players.local_player_id();
+        if let Some(tiles) = exploration.tiles(local_player_id) {
+            fog.merge_exploration(local_player_id, exploration.revision(local_player_id), tiles);
+        }
+
+        let vision_player_ids = players.vision_player_ids(&*techs, local_player_id);
+        let mut entity_ids = BTreeSet::new();
+        for (entity, transform, unit) in (&entities, &transforms, &units).iter() {
+            let radius = match line_of_sights.get(entity) {
+                Some(line_of_sight) if vision_player_ids.contains(&unit.player_id) => {
+                    line_of_sight.range.to_i32()
+                }
+                _ => None,
+            };
+            let position = transform.position();
+            fog.update_unit(entity.get_id(),
+                            unit.player_id,
+                            position.y.to_i32().unwrap(),
+                            position.x.to_i32().unwrap(),
+                            radius);
+            entity_ids.insert(entity.get_id());
+        }
+        fog.retain_units(&entity_ids);
+    }
+}
@@ -23,20 +23,58 @@ use ecs::OnScreenComponent;
 use ecs::resource::{Terrain, ViewProjector, Viewport};
 use nalgebra::Vector2;
 use partition::GridPartition;
-use specs::{self, Join};
-use super::System;
-use types::Fixed;
+use specs::{self, InsertResult, Join};
+use std::collections::{HashMap, HashSet};
+use super::{System, SystemAccess};
+use types::{Fixed, Rect};
 
-/// System the updates the grid partition with the latest entity positions
-pub struct GridSystem;
+/// How many tiles past the edges of the screen entities get marked as on screen, since the
+/// sprites of units standing just off screen (tall buildings especially) can still reach into it
+const ENTER_MARGIN: i32 = 2;
+
+/// How many tiles past the edges of the screen entities that are already on screen have to go
+/// before they stop being on screen. Being larger than the enter margin keeps entities sitting
+/// right on the boundary from flickering on and off screen.
+const LEAVE_MARGIN: i32 = 4;
+
+/// System that marks the entities the grid partition finds in view as being on screen. Only
+/// the entities that come into or go out of view have their markers changed.
+pub struct GridSystem {
+    /// The entities that were marked on screen last time, by ID
+    visible: HashMap<u32, specs::Entity>,
+}
 
 impl GridSystem {
     pub fn new() -> GridSystem {
-        GridSystem
+        GridSystem { visible: HashMap::new() }
     }
 }
 
+/// The entity with the ID. The grid only knows entities by ID, and the only other way specs has
+/// of finding an entity is joining, which would go through all of them.
+fn entity_for_id(entities: &specs::Entities, entity_id: u32) -> specs::Entity {
+    let (_, mut value) = entities.open();
+    // Getting an entity looks its generation up with a bounds check, so it's fine for an ID that
+    // isn't in the join's mask; if the entity is gone, its markers just can't be changed
+    unsafe { <&specs::Entities as Join>::get(&mut value, entity_id) }
+}
+
+fn query(grid: &GridPartition, region: &Rect, margin: i32) -> HashSet<u32> {
+    let start = Vector2::new(region.x - margin, region.y - margin);
+    let end = Vector2::new(region.x + region.w + margin, region.y + region.h + margin);
+    grid.query(&start, &end)
+}
+
 impl System for GridSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .write::<OnScreenComponent>()
+            .read::<Viewport>()
+            .read::<ViewProjector>()
+            .read::<GridPartition>()
+            .read::<Terrain>()
+    }
+
     fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
         fetch_components!(arg, entities, [
             mut components(on_screen: OnScreenComponent),
@@ -47,14 +85,45 @@ impl System for GridSystem {
         ]);
 
         let visible_region = projector.calculate_visible_world_coords(&viewport, &*terrain);
-        let start_region = Vector2::new(visible_region.x, visible_region.y);
-        let end_region = start_region + Vector2::new(visible_region.w, visible_region.h);
-        let visible_entities = grid.query(&start_region, &end_region);
-
-        on_screen.clear();
-        for entity in (&entities).iter() {
-            if visible_entities.contains(&entity.get_id()) {
-                on_screen.insert(entity, OnScreenComponent);
+        let mut visible = query(&*grid, &visible_region, ENTER_MARGIN);
+        for entity_id in query(&*grid, &visible_region, LEAVE_MARGIN) {
+            if self.visible.contains_key(&entity_id) {
+                visible.insert(entity_id);
+            }
+        }
+
+        // Entities that were deleted took their markers with them, so they're forgotten (and
+        // marked again below if their ID went to a new entity that's in view)
+        let deleted: Vec<u32> = self.visible
+            .iter()
+            .filter(|&(_, &entity)| on_screen.get(entity).is_none())
+            .map(|(&entity_id, _)| entity_id)
+            .collect();
+        for entity_id in deleted {
+            self.visible.remove(&entity_id);
+        }
+
+        let left: Vec<u32> = self.visible
+            .keys()
+            .filter(|entity_id| !visible.contains(entity_id))
+            .cloned()
+            .collect();
+        for entity_id in left {
+            if let Some(entity) = self.visible.remove(&entity_id) {
+                on_screen.remove(entity);
+            }
+        }
+
+        for entity_id in visible {
+            if self.visible.contains_key(&entity_id) {
+                continue;
+            }
+            let entity = entity_for_id(&entities, entity_id);
+            match on_screen.insert(entity, OnScreenComponent) {
+                InsertResult::EntityIsDead(_) => {}
+                _ => {
+                    self.visible.insert(entity_id, entity);
+                }
             }
         }
     }
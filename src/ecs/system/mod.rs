@@ -19,26 +19,25 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod action;
+mod ambience_system;
 mod animation_system;
 mod camera_input_system;
 mod camera_position_system;
-mod decal_system;
+mod effect_system;
+mod fog_of_war_system;
 mod grid_system;
-mod occupied_tile_system;
-mod system;
-mod unit_action_system;
+mod observer_system;
 mod unit_selection_system;
-mod velocity_system;
 
-pub use self::action::*;
+// The simulation's systems live in chariot_sim; the ones here only drive input and the view
+pub use sim::ecs::system::*;
+
+pub use self::ambience_system::AmbienceSystem;
 pub use self::animation_system::AnimationSystem;
 pub use self::camera_input_system::CameraInputSystem;
 pub use self::camera_position_system::CameraPositionSystem;
-pub use self::decal_system::DecalSystem;
+pub use self::effect_system::EffectSystem;
+pub use self::fog_of_war_system::FogOfWarSystem;
 pub use self::grid_system::GridSystem;
-pub use self::occupied_tile_system::OccupiedTileSystem;
-pub use self::system::{System, SystemWrapper};
-pub use self::unit_action_system::UnitActionSystem;
-pub use self::unit_selection_system::UnitSelectionSystem;
-pub use self::velocity_system::VelocitySystem;
+pub use self::observer_system::ObserverSystem;
+pub use self::unit_selection_system::{MouseRay, UnitSelectionSystem, calculate_mouse_ray};
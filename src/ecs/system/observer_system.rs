@@ -0,0 +1,64 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Lets observers switch whose perspective they're watching the game from.
+
+use ecs::resource::{KeyboardKeyStates, Players};
+use media::{Key, KeyState};
+use specs;
+use super::{System, SystemAccess};
+use types::Fixed;
+
+/// Pressing one of these keys views the game as that player
+const PLAYER_KEYS: [Key; 8] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7,
+                               Key::Num8];
+
+pub struct ObserverSystem;
+
+impl ObserverSystem {
+    pub fn new() -> ObserverSystem {
+        ObserverSystem
+    }
+}
+
+impl System for ObserverSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<KeyboardKeyStates>()
+            .write::<Players>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+        fetch_components!(arg, _entities, [
+            resource(keyboard_key_states: KeyboardKeyStates),
+            mut resource(players: Players),
+        ]);
+
+        if !players.is_observing() {
+            return;
+        }
+        for (index, key) in PLAYER_KEYS.iter().enumerate() {
+            if keyboard_key_states.key_state(*key) == KeyState::TransitionDown {
+                players.observe_player((index + 1).into());
+            }
+        }
+    }
+}
@@ -20,30 +20,46 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-//! This system is responsible for unit selection and queuing up a MoveToPosition action.
+//! This system is responsible for unit selection and for turning clicks and hotkeys into actions
+//! for the selected units (see util::context_command for what a right-click does).
 
-use action::{Action, MoveToPositionParams};
+use action::{Action, AttackGroundParams, AttackMoveParams, AttackUnitParams, BuildParams, GarrisonParams,
+             GatherParams, MoveToPositionParams};
 use dat;
-use ecs::{DecalComponent, OnScreenComponent, SelectedUnitComponent, TransformComponent, UnitComponent};
+use ecs::{ConstructionComponent, GarrisonActionComponent, GarrisonedComponent, HealthComponent,
+          MAX_TRAINING_QUEUE_LENGTH, OnScreenComponent, SelectedUnitComponent, TrainingQueueComponent,
+          TransformComponent, UnitComponent};
 
 use ecs::resource::{
+    AcknowledgmentKind,
+    Acknowledgments,
+    BuildMenu,
+    BuildPage,
+    DeleteConfirmation,
+    EffectShape,
+    EffectSpawner,
     MouseState,
     KeyboardKeyStates,
     PathFinder,
     Players,
+    ReachableTiles,
     ViewProjector,
     Viewport,
     OccupiedTiles,
     Terrain,
     ActionBatcher,
+    SubSelection,
 };
 
+use identifier::{CivilizationId, PlayerId, SoundGroupId, UnitId};
 use media::{KeyState, MouseButton, Key};
-use resource::DrsKey;
-use specs::{self, Join};
-use super::System;
+use specs::{self, Index, Join};
+use std::collections::HashMap;
+use super::{System, SystemAccess};
 use types::{Fixed, Vector3};
-use util::unit;
+use util::{combat, garrison, selection, training, unit};
+use util::selection::SelectionClass;
+use util::context_command::{Commander, ContextCommand, ContextTarget, Relation, context_command};
 
 pub struct UnitSelectionSystem {
     empires: dat::EmpiresDbRef,
@@ -53,16 +69,76 @@ impl UnitSelectionSystem {
     pub fn new(empires: dat::EmpiresDbRef) -> UnitSelectionSystem {
         UnitSelectionSystem { empires: empires }
     }
+
+    /// Has a unit answer with a line from the sound group, if it has one
+    fn acknowledge(&self,
+                   acknowledgments: &mut Acknowledgments,
+                   kind: AcknowledgmentKind,
+                   sound_group_id: Option<SoundGroupId>) {
+        if let Some(sound_group_id) = sound_group_id {
+            let variant_count = self.empires
+                .sound_effect_groups()
+                .get(*sound_group_id as usize)
+                .map(|group| group.sound_effects.len())
+                .unwrap_or(0);
+            acknowledgments.acknowledge(kind, sound_group_id, variant_count);
+        }
+    }
+
+    /// What the villager can build, as listed in the build menu for the page
+    fn buildings(&self,
+                 civilization_id: CivilizationId,
+                 builder_id: UnitId,
+                 page: BuildPage)
+                 -> Vec<(UnitId, String)> {
+        let civilization = self.empires.civilization(civilization_id);
+        civilization.units_trained_at(builder_id)
+            .into_iter()
+            .filter(|building| building.is_building() && build_page(civilization, building) == page)
+            .map(|building| (building.id, building.name().to_string()))
+            .collect()
+    }
 }
 
 impl System for UnitSelectionSystem {
-    fn update(&mut self, arg: specs::RunArg, _time_step: Fixed) {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<OnScreenComponent>()
+            .read::<UnitComponent>()
+            .read::<HealthComponent>()
+            .read::<ConstructionComponent>()
+            .read::<TrainingQueueComponent>()
+            .read::<GarrisonActionComponent>()
+            .read::<GarrisonedComponent>()
+            .write::<SelectedUnitComponent>()
+            .read::<TransformComponent>()
+            .read::<KeyboardKeyStates>()
+            .read::<MouseState>()
+            .read::<PathFinder>()
+            .read::<Players>()
+            .read::<ViewProjector>()
+            .read::<Viewport>()
+            .read::<OccupiedTiles>()
+            .read::<Terrain>()
+            .write::<ActionBatcher>()
+            .write::<SubSelection>()
+            .write::<Acknowledgments>()
+            .write::<EffectSpawner>()
+            .write::<BuildMenu>()
+            .write::<DeleteConfirmation>()
+    }
+
+    fn update(&mut self, arg: specs::RunArg, time_step: Fixed) {
         fetch_components!(arg, entities, [
             components(on_screen: OnScreenComponent),
             components(units: UnitComponent),
-            mut components(decals: DecalComponent),
+            components(healths: HealthComponent),
+            components(constructions: ConstructionComponent),
+            components(training_queues: TrainingQueueComponent),
+            components(garrison_actions: GarrisonActionComponent),
+            components(garrisoneds: GarrisonedComponent),
             mut components(selected_units: SelectedUnitComponent),
-            mut components(transforms: TransformComponent),
+            components(transforms: TransformComponent),
             resource(keyboard_state: KeyboardKeyStates),
             resource(mouse_state: MouseState),
             resource(path_finder: PathFinder),
@@ -72,76 +148,575 @@ impl System for UnitSelectionSystem {
             resource(occupied_tiles: OccupiedTiles),
             resource(terrain: Terrain),
             mut resource(action_batcher: ActionBatcher),
+            mut resource(sub_selection: SubSelection),
+            mut resource(acknowledgments: Acknowledgments),
+            mut resource(effects: EffectSpawner),
+            mut resource(build_menu: BuildMenu),
+            mut resource(delete_confirmation: DeleteConfirmation),
         ]);
 
-        if mouse_state.key_states.key_state(MouseButton::Left) == KeyState::TransitionUp {
-            // Holding the left shift key while left clicking a unit will add them to the current selection.
-            if keyboard_state.is_up(Key::ShiftLeft) {
-                selected_units.clear();
+        acknowledgments.update(time_step);
+
+        let left_clicked = mouse_state.key_states.key_state(MouseButton::Left) == KeyState::TransitionUp;
+        let right_clicked = mouse_state.key_states.key_state(MouseButton::Right) == KeyState::TransitionUp;
+
+        // With villagers selected, B opens the civilian build menu and V the military one. While
+        // a menu is open (or a building is being placed), letters and clicks are for it alone.
+        let pressed = |key| keyboard_state.key_state(key) == KeyState::TransitionDown;
+        let build_page = if pressed(Key::B) {
+            Some(BuildPage::Civilian)
+        } else if pressed(Key::V) {
+            Some(BuildPage::Military)
+        } else {
+            None
+        };
+        let using_build_menu = build_menu.is_open();
+        let mut placement_clicked = false;
+        if build_page.is_some() || using_build_menu {
+            let builder = (&units, &selected_units)
+                .iter()
+                .map(|(unit, _)| unit)
+                .find(|unit| {
+                    players.can_command(unit.player_id) &&
+                    self.empires
+                        .civilization(unit.civilization_id)
+                        .units_trained_at(unit.unit_id)
+                        .iter()
+                        .any(|trainee| trainee.is_building())
+                })
+                .map(|unit| (unit.civilization_id, unit.unit_id));
+
+            match builder {
+                None => build_menu.close(),
+                Some(_) if pressed(Key::Escape) => build_menu.close(),
+                Some((civilization_id, builder_id)) => {
+                    // Letters in the open menu come first, so B and V pick buildings there too
+                    let letter = build_menu.options()
+                        .iter()
+                        .filter_map(|option| option.hotkey)
+                        .find(|&letter| Key::from_letter(letter).map(&pressed).unwrap_or(false));
+                    if let Some(letter) = letter {
+                        build_menu.pick(letter);
+                    } else if let Some(page) = build_page {
+                        build_menu.open(page, self.buildings(civilization_id, builder_id, page));
+                    } else if build_menu.placing().is_some() && (left_clicked || right_clicked) {
+                        // Left-clicking puts the building there; right-clicking cancels
+                        if left_clicked {
+                            let building_id = build_menu.placing().unwrap();
+                            let target = calculate_mouse_ray(&viewport,
+                                                             &mouse_state,
+                                                             &view_projector,
+                                                             &terrain)
+                                .world_coord;
+                            let position = Vector3::new(target.x.round(), target.y.round(), target.z);
+                            for (entity, unit, _) in (&entities, &units, &selected_units).iter() {
+                                let can_build = players.can_command(unit.player_id) &&
+                                                self.empires
+                                                    .civilization(unit.civilization_id)
+                                                    .units_trained_at(unit.unit_id)
+                                                    .iter()
+                                                    .any(|trainee| trainee.id == building_id);
+                                if !can_build {
+                                    continue;
+                                }
+                                if keyboard_state.is_up(Key::CtrlLeft) {
+                                    action_batcher.queue_for_entity(entity.get_id(), Action::ClearQueue);
+                                }
+                                let params = BuildParams::new(building_id, position);
+                                action_batcher.queue_for_entity(entity.get_id(), Action::Build(params));
+                            }
+                        }
+                        build_menu.close();
+                        placement_clicked = true;
+                    }
+                }
             }
+        }
+        let using_build_menu = using_build_menu || build_menu.is_open();
 
-            let mouse_ray = calculate_mouse_ray(&viewport, &mouse_state, &view_projector, &terrain);
+        let left_clicked = left_clicked && !placement_clicked;
+        let right_clicked = right_clicked && !placement_clicked;
+        let mouse_ray = calculate_mouse_ray(&viewport, &mouse_state, &view_projector, &terrain);
+        // Cast a ray from the mouse position through to the terrain and find the first unit whose
+        // axis-aligned box intersects the ray; clicks (left or right) are about that unit
+        let mut unit_under_mouse = None;
+        if left_clicked || right_clicked {
             for (entity, _, unit, transform) in (&entities, &on_screen, &units, &transforms).iter() {
                 let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
-                if unit_info.interaction_mode != dat::InteractionMode::NonInteracting {
-                    let unit_box = unit::selection_box(unit_info, transform);
+                let unit_box = unit::selection_box(unit_info, transform);
+                if unit_info.interaction_mode != dat::InteractionMode::NonInteracting &&
+                   unit_box.intersects_ray(&mouse_ray.origin, &mouse_ray.direction) {
+                    unit_under_mouse = Some(entity);
+                    break;
+                }
+            }
+        }
+
+        if left_clicked {
+            let local_player_id = players.local_player().player_id;
+
+            // Holding the left shift key while selecting adds to the current selection
+            // (as long as that doesn't mix players; see util::selection)
+            let mut picked = Vec::new();
+            if let Some(drag_rect) = mouse_state.drag_rect() {
+                let viewport_pos = viewport.top_left_i32();
+                let mut candidates = Vec::new();
+                for (entity, _, unit, transform) in (&entities, &on_screen, &units, &transforms).iter() {
+                    let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
+                    if unit_info.interaction_mode == dat::InteractionMode::NonInteracting {
+                        continue;
+                    }
+                    let position = view_projector.project(transform.position()) - viewport_pos;
+                    if drag_rect.contains(position.x, position.y) {
+                        candidates.push(selection::Candidate {
+                            entity_id: entity.get_id(),
+                            player_id: unit.player_id,
+                            class: selection::selection_class(unit_info),
+                        });
+                    }
+                }
+                for entity_id in selection::filter_box_selection(&candidates, local_player_id) {
+                    picked.push((entity_id, local_player_id));
+                }
+            } else if let Some(entity) = unit_under_mouse {
+                picked.push((entity.get_id(), units.get(entity).unwrap().player_id));
+            }
+
+            let selected_owners: Vec<PlayerId> =
+                (&units, &selected_units).iter().map(|(unit, _)| unit.player_id).collect();
+            let adding = keyboard_state.is_down(Key::ShiftLeft) &&
+                         picked.iter().all(|&(_, player_id)| {
+                             selection::can_add_to_selection(&selected_owners, player_id, local_player_id)
+                         });
+            if !adding {
+                selected_units.clear();
+            }
+            sub_selection.reset();
+            // The first of the player's own units to be picked answers for all of them
+            let mut responder = None;
+            for (entity, unit, _) in (&entities, &units, &on_screen).iter() {
+                if picked.iter().any(|&(entity_id, _)| entity_id == entity.get_id()) {
+                    selected_units.insert(entity, SelectedUnitComponent);
+                    if responder.is_none() && players.can_command(unit.player_id) {
+                        let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
+                        responder = unit_info.selection_sound_id();
+                    }
+                }
+            }
+            self.acknowledge(&mut *acknowledgments, AcknowledgmentKind::Select, responder);
+        }
+
+        let selected_kinds: Vec<UnitId> =
+            (&units, &selected_units).iter().map(|(unit, _)| unit.unit_id).collect();
+        if keyboard_state.key_state(Key::Tab) == KeyState::TransitionDown {
+            sub_selection.cycle(&selected_kinds);
+        }
+
+        // Deleting buildings has to be confirmed with the same selection
+        let selection: Vec<Index> =
+            (&entities, &selected_units).iter().map(|(entity, _)| entity.get_id()).collect();
+        delete_confirmation.update(&selection);
+
+        if keyboard_state.key_state(Key::C) == KeyState::TransitionDown && !using_build_menu {
+            // Train commands go to every selected building of the same kind as the first one, and
+            // each new unit joins whichever of their queues is shortest. Shift trains a full queue's worth.
+            let mut building = None;
+            let mut trainers = Vec::new();
+            for (entity, unit, training_queue, _selected_unit) in
+                (&entities, &units, &training_queues, &selected_units).iter() {
+                if !players.can_command(unit.player_id) ||
+                   !sub_selection.includes(unit.unit_id, &selected_kinds) {
+                    continue;
+                }
+                match building {
+                    Some((_, unit_id)) if unit_id != unit.unit_id => continue,
+                    None => building = Some((unit.civilization_id, unit.unit_id)),
+                    _ => {}
+                }
+                trainers.push((entity.get_id(), training_queue.len()));
+            }
+
+            if let Some((civilization_id, building_id)) = building {
+                let trainees = self.empires.civilization(civilization_id).units_trained_at(building_id);
+                if let Some(trainee) = trainees.first() {
+                    let count = if keyboard_state.is_down(Key::ShiftLeft) {
+                        MAX_TRAINING_QUEUE_LENGTH
+                    } else {
+                        1
+                    };
+                    let queue_lengths: Vec<usize> = trainers.iter().map(|&(_, length)| length).collect();
+                    for index in training::distribute(&queue_lengths, count, MAX_TRAINING_QUEUE_LENGTH) {
+                        action_batcher.queue_for_entity(trainers[index].0, Action::Train(trainee.id));
+                    }
+                }
+            }
+        }
+
+        if keyboard_state.key_state(Key::Delete) == KeyState::TransitionDown {
+            let mut doomed = Vec::new();
+            let mut includes_building = false;
+            for (entity, unit, _selected_unit) in (&entities, &units, &selected_units).iter() {
+                if !players.can_command(unit.player_id) {
+                    continue;
+                }
+                let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
+                includes_building |= unit_info.interaction_mode == dat::InteractionMode::Building;
+                doomed.push(entity.get_id());
+            }
+
+            if includes_building && !delete_confirmation.is_armed() {
+                delete_confirmation.arm(selection);
+            } else {
+                for entity_id in doomed {
+                    action_batcher.queue_for_entity(entity_id, Action::Kill);
+                }
+                delete_confirmation.disarm();
+            }
+        }
+
+        // Backspace calls off everything the selected units were told to do
+        if keyboard_state.key_state(Key::Backspace) == KeyState::TransitionDown {
+            for (entity, unit, _selected_unit) in (&entities, &units, &selected_units).iter() {
+                if players.can_command(unit.player_id) {
+                    action_batcher.queue_for_entity(entity.get_id(), Action::ClearQueue);
+                }
+            }
+        }
+
+        if keyboard_state.key_state(Key::H) == KeyState::TransitionDown && !using_build_menu {
+            // Ringing the town bell at a selected town center or tower sends the player's villagers
+            // around it into the closest buildings with room. Ringing it again lets them back out
+            // to what they were doing.
+            let mut bell = None;
+            for (unit, transform, _selected_unit) in (&units, &transforms, &selected_units).iter() {
+                if players.can_command(unit.player_id) && garrison::capacity(unit.db(&self.empires)) > 0 {
+                    bell = Some((unit.player_id, *transform.position()));
+                    break;
+                }
+            }
 
-                    // Cast a ray from the mouse position through to the terrain and select any unit
-                    // whose axis-aligned box intersects the ray.
-                    if unit_box.intersects_ray(&mouse_ray.origin, &mouse_ray.direction) {
-                        selected_units.insert(entity, SelectedUnitComponent);
-                        break;
+            if let Some((player_id, bell_position)) = bell {
+                let mut answered = Vec::new();
+                for (entity, unit, garrisoned) in (&entities, &units, &garrisoneds).iter() {
+                    if unit.player_id == player_id && garrisoned.town_bell {
+                        answered.push(entity.get_id());
+                    }
+                }
+                for (entity, unit, garrison_action) in (&entities, &units, &garrison_actions).iter() {
+                    if unit.player_id == player_id && garrison_action.town_bell {
+                        answered.push(entity.get_id());
+                    }
+                }
+
+                if !answered.is_empty() {
+                    for entity_id in answered {
+                        action_batcher.queue_for_entity(entity_id, Action::Ungarrison);
+                    }
+                } else {
+                    let mut occupants: HashMap<Index, usize> = HashMap::new();
+                    for garrisoned in (&garrisoneds).iter() {
+                        *occupants.entry(garrisoned.building_id).or_insert(0) += 1;
+                    }
+
+                    let mut shelters = Vec::new();
+                    let mut villagers = Vec::new();
+                    let mut restrictions = HashMap::new();
+                    for (entity, unit, transform) in (&entities, &units, &transforms).iter() {
+                        if unit.player_id != player_id || garrisoneds.get(entity).is_some() {
+                            continue;
+                        }
+                        let unit_info = unit.db(&self.empires);
+                        let capacity = garrison::capacity(unit_info);
+                        let in_earshot = combat::ground_distance(transform.position(), &bell_position) <=
+                                         Fixed::from(garrison::TOWN_BELL_RADIUS);
+                        if capacity > 0 {
+                            let used = occupants.get(&entity.get_id()).cloned().unwrap_or(0);
+                            shelters.push(garrison::Shelter {
+                                entity_id: entity.get_id(),
+                                position: *transform.position(),
+                                space: capacity.saturating_sub(used),
+                            });
+                        } else if in_earshot && Commander::new(unit_info).worker {
+                            villagers.push((entity.get_id(), *transform.position()));
+                            restrictions.insert(entity.get_id(), unit_info.terrain_restriction);
+                        }
+                    }
+
+                    for (villager_id, shelter_id) in garrison::assign_shelters(&villagers, &shelters) {
+                        let from = villagers.iter().find(|&&(id, _)| id == villager_id).unwrap().1;
+                        let to = shelters.iter()
+                            .find(|shelter| shelter.entity_id == shelter_id)
+                            .unwrap()
+                            .position;
+                        let path = path_finder.find_path(&*terrain,
+                                                         &*occupied_tiles,
+                                                         &from,
+                                                         &to,
+                                                         restrictions[&villager_id]);
+                        let approach = Action::MoveToPosition(MoveToPositionParams::new(path));
+                        let garrison = Action::Garrison(GarrisonParams::new(shelter_id, true));
+                        action_batcher.queue_for_entity(villager_id, Action::RememberQueue);
+                        action_batcher.queue_for_entity(villager_id, approach);
+                        action_batcher.queue_for_entity(villager_id, garrison);
                     }
                 }
             }
         }
 
-        if mouse_state.key_states.key_state(MouseButton::Right) == KeyState::TransitionUp {
-            let mouse_ray = calculate_mouse_ray(&viewport, &mouse_state, &view_projector, &terrain);
-            let mut moving_unit = false;
+        if right_clicked {
+            // Holding left-alt tells siege units to attack the ground instead of moving there
+            let attack_ground = keyboard_state.is_down(Key::AltLeft);
+            // Holding A sends units to the spot, fighting whatever they run into on the way
+            let attack_move = !attack_ground && keyboard_state.is_down(Key::A);
+            let target = match unit_under_mouse {
+                Some(entity) if !attack_ground && !attack_move => {
+                    let unit = units.get(entity).unwrap();
+                    let damaged = healths.get(entity)
+                        .map(|health| health.hit_points < health.max_hit_points)
+                        .unwrap_or(false);
+                    Some((entity.get_id(),
+                          unit.player_id,
+                          self.empires.unit(unit.civilization_id, unit.unit_id),
+                          damaged,
+                          *transforms.get(entity).unwrap().position()))
+                }
+                _ => None,
+            };
+            let foundation = match unit_under_mouse {
+                Some(entity) => constructions.get(entity).is_some(),
+                None => false,
+            };
+
+            // Flood fills are shared by units that can already walk to each other
+            let mut reachable_tiles: Vec<ReachableTiles> = Vec::new();
+            let mut unreachable = false;
+            let mut flag_position = None;
+            let mut responder = None;
             for (entity, transform, unit, _selected_unit) in (&entities, &transforms, &units, &selected_units).iter() {
-                if unit.player_id != players.local_player().player_id {
+                if !players.can_command(unit.player_id) {
                     continue;
                 }
 
                 let unit_info = self.empires.unit(unit.civilization_id, unit.unit_id);
-                let path = path_finder.find_path(&*terrain,
-                                                    &*occupied_tiles,
-                                                    transform.position(),
-                                                    &mouse_ray.world_coord,
-                                                    unit_info.terrain_restriction);
+                if attack_ground && !combat::can_attack_ground(unit_info) {
+                    continue;
+                }
+                let command = match target {
+                    Some((target_id, owner_id, target_info, damaged, _)) if target_id != entity.get_id() => {
+                        let relation = relation(&players, unit.player_id, owner_id);
+                        let context_target = ContextTarget::new(target_info, relation, damaged, foundation);
+                        context_command(&Commander::new(unit_info), Some(&context_target))
+                    }
+                    _ => ContextCommand::Move,
+                };
+
+                let shelter = match target {
+                    Some((_, _, target_info, _, _)) => {
+                        command == ContextCommand::Garrison && garrison::capacity(target_info) > 0
+                    }
+                    None => false,
+                };
+
+                let gather = match target {
+                    Some((_, _, target_info, _, _)) => {
+                        let civilization = self.empires.civilization(unit.civilization_id);
+                        command == ContextCommand::Gather &&
+                        civilization.gatherer_for(unit_info, target_info).is_some()
+                    }
+                    None => false,
+                };
+
+                // Villagers only help with their own player's foundations
+                let build = match target {
+                    Some((_, owner_id, _, _, _)) => {
+                        command == ContextCommand::Build && owner_id == unit.player_id
+                    }
+                    None => false,
+                };
+
+                // Clamp the destination to somewhere the unit can actually get to, rather than having
+                // the path finder search the whole map for a path into the sea
+                let mut destination = mouse_ray.world_coord;
+                if command != ContextCommand::Attack && !shelter && !gather && !build {
+                    let restriction_id = unit_info.terrain_restriction;
+                    let known = reachable_tiles.iter().position(|tiles| {
+                        tiles.restriction_id().as_index() == restriction_id.as_index() &&
+                        tiles.contains(transform.position())
+                    });
+                    let index = match known {
+                        Some(index) => index,
+                        None => {
+                            reachable_tiles.push(path_finder.reachable_tiles(&*terrain,
+                                                                             transform.position(),
+                                                                             restriction_id));
+                            reachable_tiles.len() - 1
+                        }
+                    };
+                    match path_finder.passable_destination(&*terrain, &reachable_tiles[index], &destination) {
+                        Some(passable) => destination = passable,
+                        None => {
+                            // Siege can still fire at the spot if it's already in range
+                            let in_range = attack_ground &&
+                                           combat::ground_distance(transform.position(), &destination) <=
+                                           Fixed::from(unit_info.battle_params.as_ref().unwrap().max_range);
+                            if !in_range {
+                                unreachable = true;
+                                continue;
+                            }
+                            destination = *transform.position();
+                        }
+                    }
+                }
+
+                // Only the first unit to take the order answers
+                if responder.is_none() {
+                    responder = if command == ContextCommand::Attack || attack_ground || attack_move {
+                        Some((AcknowledgmentKind::Attack, unit_info.attack_sound_id()))
+                    } else {
+                        Some((AcknowledgmentKind::Move, unit_info.move_sound_id()))
+                    };
+                }
+
                 // Enqueue sequential actions by holding left-control.
                 if keyboard_state.is_up(Key::CtrlLeft) {
                     action_batcher.queue_for_entity(entity.get_id(), Action::ClearQueue);
                 }
 
-                action_batcher.queue_for_entity(entity.get_id(),
-                                                Action::MoveToPosition(MoveToPositionParams::new(path)));
-                moving_unit = true;
+                if command == ContextCommand::Attack {
+                    let (target_id, _, target_info, _, target_position) = target.unwrap();
+                    let reach = Fixed::from(unit_info.battle_params.as_ref().unwrap().max_range) +
+                                Fixed::from(unit_info.collision_size_x.max(unit_info.collision_size_y)) +
+                                Fixed::from(target_info.collision_size_x.max(target_info.collision_size_y));
+                    if combat::ground_distance(transform.position(), &target_position) > reach {
+                        let path = path_finder.find_path(&*terrain,
+                                                         &*occupied_tiles,
+                                                         transform.position(),
+                                                         &target_position,
+                                                         unit_info.terrain_restriction);
+                        let path = combat::path_into_range(&path, &target_position, reach);
+                        let approach = Action::MoveToPosition(MoveToPositionParams::new(path));
+                        action_batcher.queue_for_entity(entity.get_id(), approach);
+                    }
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::AttackUnit(AttackUnitParams::new(target_id)));
+                    continue;
+                }
+
+                // The GatherActionSystem walks the unit over (a villager goes to the shore for fish)
+                if gather {
+                    let (target_id, _, _, _, _) = target.unwrap();
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::Gather(GatherParams::new(target_id)));
+                    continue;
+                }
+
+                // The BuildActionSystem walks the villager over too
+                if build {
+                    let (_, _, target_info, _, target_position) = target.unwrap();
+                    let params = BuildParams::new(target_info.id, target_position);
+                    action_batcher.queue_for_entity(entity.get_id(), Action::Build(params));
+                    continue;
+                }
+
+                if shelter {
+                    let (target_id, _, _, _, target_position) = target.unwrap();
+                    let path = path_finder.find_path(&*terrain,
+                                                     &*occupied_tiles,
+                                                     transform.position(),
+                                                     &target_position,
+                                                     unit_info.terrain_restriction);
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::MoveToPosition(MoveToPositionParams::new(path)));
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::Garrison(GarrisonParams::new(target_id, false)));
+                    continue;
+                }
+
+                // TODO: Repair and board transports once there are actions for them;
+                // until then units walk over to what was clicked
+                let path = path_finder.find_path(&*terrain,
+                                                 &*occupied_tiles,
+                                                 transform.position(),
+                                                 &destination,
+                                                 unit_info.terrain_restriction);
+                if attack_ground {
+                    let target = mouse_ray.world_coord;
+                    let range: Fixed = unit_info.battle_params.as_ref().unwrap().max_range.into();
+                    if combat::ground_distance(transform.position(), &target) > range {
+                        let path = combat::path_into_range(&path, &target, range);
+                        let approach = Action::MoveToPosition(MoveToPositionParams::new(path));
+                        action_batcher.queue_for_entity(entity.get_id(), approach);
+                    }
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::AttackGround(AttackGroundParams::new(target)));
+                } else if attack_move {
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::AttackMove(AttackMoveParams::new(path)));
+                } else {
+                    action_batcher.queue_for_entity(entity.get_id(),
+                                                    Action::MoveToPosition(MoveToPositionParams::new(path)));
+                }
+                if flag_position.is_none() {
+                    flag_position = Some(destination);
+                }
+            }
+
+            if let Some((kind, sound_group_id)) = responder {
+                self.acknowledge(&mut *acknowledgments, kind, sound_group_id);
             }
 
-            if moving_unit {
-                let decal = arg.create();
-                transforms.insert(decal,
-                                  TransformComponent::new(mouse_ray.world_coord, 0.into()));
-                decals.insert(decal,
-                              DecalComponent::new(0.into(), DrsKey::Interfac, 50405.into()));
+            if let Some(position) = flag_position {
+                effects.spawn(EffectShape::move_flag(), position);
+            } else if unreachable {
+                // TODO: Play the "can't do that" sound once there's audio
+                log_debug!("None of the selected units can get to {:?}", mouse_ray.world_coord);
             }
         }
     }
 }
 
-struct MouseRay {
-    world_coord: Vector3,
-    origin: Vector3,
-    direction: Vector3,
+/// Where the mouse points on the terrain, and the ray through it for picking units
+pub struct MouseRay {
+    pub world_coord: Vector3,
+    pub origin: Vector3,
+    pub direction: Vector3,
+}
+
+/// Towers and buildings that train soldiers go in the military build menu; everything else
+/// (houses, docks, town centers, wonders) is civilian
+fn build_page(civilization: &dat::Civilization, building: &dat::Unit) -> BuildPage {
+    let attacks = building.battle_params
+        .as_ref()
+        .map(|params| params.attacks.iter().any(|&(_, amount)| amount > 0))
+        .unwrap_or(false);
+    let trains_soldiers = civilization.units_trained_at(building.id)
+        .iter()
+        .any(|trainee| selection::selection_class(trainee) == SelectionClass::Military);
+    if attacks || trains_soldiers {
+        BuildPage::Military
+    } else {
+        BuildPage::Civilian
+    }
+}
+
+fn relation(players: &Players, player_id: PlayerId, other_id: PlayerId) -> Relation {
+    let team = |player_id| players.player(player_id).map(|player| player.team);
+    if other_id == player_id {
+        Relation::Own
+    } else if *other_id == 0 {
+        Relation::Gaia
+    } else if team(player_id).is_some() && team(player_id) == team(other_id) {
+        Relation::Ally
+    } else {
+        Relation::Enemy
+    }
 }
 
-fn calculate_mouse_ray(viewport: &Viewport,
-                       mouse_state: &MouseState,
-                       view_projector: &ViewProjector,
-                       terrain: &Terrain)
-                       -> MouseRay {
+pub fn calculate_mouse_ray(viewport: &Viewport,
+                           mouse_state: &MouseState,
+                           view_projector: &ViewProjector,
+                           terrain: &Terrain)
+                           -> MouseRay {
     let viewport_pos = viewport.top_left_i32();
     let mouse_pos = mouse_state.position + viewport_pos;
 
@@ -20,159 +20,122 @@
 // SOFTWARE.
 
 use dat::EmpiresDbRef;
-use ecs::render_system::{TerrainRenderSystem, GraphicRenderSystem, UnitSelectionRenderSystem, RenderSystemWrapper, DecalRenderSystem, TileDebugRenderSystem};
-use ecs::resource::{ViewProjector, RenderCommands, PathFinder, KeyboardKeyStates, Players, OccupiedTiles, ActionBatcher, Viewport, Terrain, MouseState};
-use ecs::system::{VelocitySystem, SystemWrapper, DecalSystem, AnimationSystem, UnitSelectionSystem, OccupiedTileSystem, CameraPositionSystem, MoveToPositionActionSystem, UnitActionSystem, GridSystem, CameraInputSystem};
+use ecs::{GameSetup, MapVisibility, SystemGroup, WorldPlanner};
+use ecs::render_system::{AmbienceRenderSystem, EffectRenderSystem, GraphicRenderSystem, RenderSystemWrapper,
+                         TerrainRenderSystem, TileDebugRenderSystem, UnitSelectionRenderSystem};
+use ecs::resource::{Acknowledgments, Ambience, AnimationClock, BuildMenu, CameraBookmarks, DeleteConfirmation,
+                    FogOfWar, KeyboardKeyStates, MouseState, RenderCommands, SubSelection, ViewProjector,
+                    Viewport, find_bird_graphic};
+use ecs::system::{System, AmbienceSystem, AnimationSystem, CameraInputSystem, CameraPositionSystem,
+                  EffectSystem, FogOfWarSystem, GridSystem, ObserverSystem, UnitSelectionSystem,
+                  VelocitySystem};
 use media::MediaRef;
-use partition::GridPartition;
+use nalgebra::Vector2;
 use resource::ShapeMetadataStoreRef;
 use scn;
+use sim;
 use specs;
 use std::collections::HashMap;
-use super::component::{DecalComponent, UnitComponent, OnScreenComponent, CameraComponent, MoveToPositionActionComponent, TransformComponent, GraphicComponent, ActionQueueComponent, VelocityComponent, SelectedUnitComponent};
-use types::{Fixed, Vector3};
 
-const NUM_THREADS: usize = 4;
-const GRID_CELL_SIZE: i32 = 10; // in tiles
-
-pub type WorldPlanner = specs::Planner<(SystemGroup, Fixed)>;
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub enum SystemGroup {
-    Normal,
-    Render,
-}
+const HEADLESS_VIEWPORT_WIDTH: u32 = 1024;
+const HEADLESS_VIEWPORT_HEIGHT: u32 = 768;
 
 pub fn create_world_planner(media: MediaRef,
                             empires: EmpiresDbRef,
                             shape_metadata: ShapeMetadataStoreRef,
-                            scenario: &scn::Scenario)
+                            scenario: &scn::Scenario,
+                            setup: &GameSetup)
                             -> WorldPlanner {
-    let mut world = specs::World::new();
-    register_components(&mut world);
-    add_resources(&mut world, &media, &empires, scenario);
-
-    // Create entities for each unit in the SCN
-    for player_id in scenario.player_ids() {
-        let units = scenario.player_units(player_id);
-        let civ_id = scenario.player_civilization_id(player_id);
-        for unit in units {
-            let transform_component = TransformComponent::new(Vector3::new(unit.position_x.into(),
-                                                                           unit.position_y.into(),
-                                                                           unit.position_z.into()),
-                                                              unit.rotation.into());
-
-            let unit_info = empires.unit(civ_id, unit.unit_id);
-
-            let mut graphic_component = GraphicComponent::new();
-            graphic_component.player_color_id = player_id.into();
-            graphic_component.graphic_id = unit_info.standing_graphic;
-
-            // TODO: Use the bulk creation iterator for better performance
-            world.create_now()
-                .with(ActionQueueComponent::new())
-                .with(transform_component)
-                .with(graphic_component)
-                .with(UnitComponent::new(player_id, civ_id, unit.unit_id))
-                .with(VelocityComponent::new())
-                .build();
-        }
-    }
-
-    let mut planner = WorldPlanner::new(world, NUM_THREADS);
-    attach_systems(&mut planner, &empires, &shape_metadata);
-    attach_render_systems(&mut planner, &empires);
+    let viewport_size = media.borrow().viewport_size();
+    let mut planner = create_viewed_planner(viewport_size, &empires, &shape_metadata, scenario, setup);
+    attach_render_systems(&mut planner, &empires, &shape_metadata);
     planner
 }
 
-fn register_components(world: &mut specs::World) {
-    world.register::<ActionQueueComponent>();
-    world.register::<CameraComponent>();
-    world.register::<DecalComponent>();
-    world.register::<GraphicComponent>();
-    world.register::<MoveToPositionActionComponent>();
-    world.register::<OnScreenComponent>();
-    world.register::<SelectedUnitComponent>();
-    world.register::<TransformComponent>();
-    world.register::<UnitComponent>();
-    world.register::<VelocityComponent>();
+pub fn create_headless_world_planner(empires: EmpiresDbRef,
+                                     shape_metadata: ShapeMetadataStoreRef,
+                                     scenario: &scn::Scenario,
+                                     setup: &GameSetup)
+                                     -> WorldPlanner {
+    let viewport_size = Vector2::new(HEADLESS_VIEWPORT_WIDTH, HEADLESS_VIEWPORT_HEIGHT);
+    create_viewed_planner(viewport_size, &empires, &shape_metadata, scenario, setup)
+}
+
+/// The simulation, along with the input and view resources and systems that go on top of it
+fn create_viewed_planner(viewport_size: Vector2<u32>,
+                         empires: &EmpiresDbRef,
+                         shape_metadata: &ShapeMetadataStoreRef,
+                         scenario: &scn::Scenario,
+                         setup: &GameSetup)
+                         -> WorldPlanner {
+    let mut planner = sim::ecs::create_simulation_planner(empires, scenario, setup);
+    add_resources(planner.mut_world(), &viewport_size, empires, scenario, setup);
+    attach_systems(&mut planner, empires, shape_metadata, setup.editor);
+    planner
 }
 
 fn add_resources(world: &mut specs::World,
-                 media: &MediaRef,
+                 viewport_size: &Vector2<u32>,
                  empires: &EmpiresDbRef,
-                 scenario: &scn::Scenario) {
-    let viewport_size = media.borrow().viewport_size();
+                 scenario: &scn::Scenario,
+                 setup: &GameSetup) {
     let (tile_half_width, tile_half_height) = empires.tile_half_sizes();
 
     // Input resources
     world.add_resource(KeyboardKeyStates::new(HashMap::new()));
     world.add_resource(MouseState::new());
+    world.add_resource(SubSelection::new());
+    world.add_resource(BuildMenu::new());
+    world.add_resource(DeleteConfirmation::new());
+    world.add_resource(Acknowledgments::new());
 
     // Render resources
     world.add_resource(RenderCommands::new());
     world.add_resource(ViewProjector::new(tile_half_width, tile_half_height));
-    world.add_resource(GridPartition::new(GRID_CELL_SIZE, GRID_CELL_SIZE));
+    world.add_resource(Ambience::new(setup.seed, find_bird_graphic(empires)));
+    world.add_resource(AnimationClock::new());
 
-    // Camera resources and entity
+    // Camera resources (the camera entity is the simulation's)
     world.add_resource(Viewport::new(viewport_size.x as i32, viewport_size.y as i32));
-    world.create_now()
-        .with(TransformComponent::new(Vector3::new(0.into(), 0.into(), 0.into()), 0.into()))
-        .with(VelocityComponent::new())
-        .with(CameraComponent)
-        .build();
+    world.add_resource(CameraBookmarks::new());
 
-    // Players
-    // TODO: Local player currently hardcoded to 1; should be determined in menu
-    world.add_resource(Players::from_scenario(scenario, 1.into()));
-
-    // Unit resources
-    world.add_resource(ActionBatcher::new());
-
-    // Terrain resources
-    world.add_resource(OccupiedTiles::new());
-    world.add_resource(PathFinder::new(empires.clone()));
-    world.add_resource(Terrain::from(&scenario.map, empires.clone()));
-}
-
-macro_rules! system {
-    ($planner:expr, $typ:ident, $priority:expr) => {
-        $planner.add_system(SystemWrapper::new(Box::new($typ::new())), stringify!($typ), $priority);
-    };
-    ($planner:expr, $typ:ident, $inst:expr, $priority:expr) => {
-        $planner.add_system(SystemWrapper::new(Box::new($inst)), stringify!($typ), $priority);
-    };
+    // The editor shows the whole map
+    let map_visibility = if setup.editor { MapVisibility::Revealed } else { setup.settings.map_visibility };
+    world.add_resource(FogOfWar::new(scenario.map.width as i32, scenario.map.height as i32, map_visibility));
 }
 
 fn attach_systems(planner: &mut WorldPlanner,
                   empires: &EmpiresDbRef,
-                  shape_metadata: &ShapeMetadataStoreRef) {
-    system!(planner, VelocitySystem, 1000);
-    system!(planner, CameraInputSystem, 1000);
-    system!(planner, CameraPositionSystem, 1000);
-    system!(planner, CameraPositionSystem, 1000);
-    system!(planner, GridSystem, 1000);
-    system!(planner,
-            DecalSystem,
-            DecalSystem::new(shape_metadata.clone()),
-            1000);
-    system!(planner,
+                  shape_metadata: &ShapeMetadataStoreRef,
+                  editor: bool) {
+    // The editor only moves the camera around; the simulation doesn't run in it
+    let mut systems: Vec<(&'static str, Box<System>)> = if editor {
+        let mut systems: Vec<(&'static str, Box<System>)> = Vec::new();
+        system!(systems, VelocitySystem);
+        systems
+    } else {
+        sim::ecs::simulation_systems(empires)
+    };
+
+    // These run after the simulation systems they conflict with, so they see each tick's results
+    system!(systems, CameraInputSystem);
+    system!(systems, CameraPositionSystem);
+    if !editor {
+        system!(systems, ObserverSystem);
+    }
+    system!(systems, GridSystem);
+    system!(systems, FogOfWarSystem);
+    system!(systems, EffectSystem, EffectSystem::new(shape_metadata.clone()));
+    system!(systems,
             AnimationSystem,
-            AnimationSystem::new(empires.clone(), shape_metadata.clone()),
-            1000);
-    system!(planner, UnitActionSystem, UnitActionSystem::new(), 1000);
-    system!(planner,
-            UnitSelectionSystem,
-            UnitSelectionSystem::new(empires.clone()),
-            1000);
-    system!(planner,
-            MoveToPositionActionSystem,
-            MoveToPositionActionSystem::new(empires.clone()),
-            1000);
-    system!(planner,
-            OccupiedTileSystem,
-            OccupiedTileSystem::new(empires.clone()),
-            1000);
+            AnimationSystem::new(empires.clone(), shape_metadata.clone()));
+    if !editor {
+        system!(systems,
+                UnitSelectionSystem,
+                UnitSelectionSystem::new(empires.clone()));
+        system!(systems, AmbienceSystem, AmbienceSystem::new(empires.clone()));
+    }
+    sim::ecs::schedule_systems(planner, systems);
 }
 
 macro_rules! render_system {
@@ -184,12 +147,14 @@ macro_rules! render_system {
     };
 }
 
-fn attach_render_systems(planner: &mut WorldPlanner, empires: &EmpiresDbRef) {
+fn attach_render_systems(planner: &mut WorldPlanner,
+                         empires: &EmpiresDbRef,
+                         shape_metadata: &ShapeMetadataStoreRef) {
     render_system!(planner,
                    TerrainRenderSystem,
-                   TerrainRenderSystem::new(empires.clone()),
+                   TerrainRenderSystem::new(empires.clone(), shape_metadata.clone()),
                    1000);
-    render_system!(planner, DecalRenderSystem, 1000);
+    render_system!(planner, EffectRenderSystem, 1000);
     render_system!(planner,
                    GraphicRenderSystem,
                    GraphicRenderSystem::new(empires.clone()),
@@ -198,5 +163,9 @@ fn attach_render_systems(planner: &mut WorldPlanner, empires: &EmpiresDbRef) {
                    UnitSelectionRenderSystem,
                    UnitSelectionRenderSystem::new(empires.clone()),
                    1000);
+    render_system!(planner,
+                   AmbienceRenderSystem,
+                   AmbienceRenderSystem::new(empires.clone()),
+                   1000);
     render_system!(planner, TileDebugRenderSystem, 1000);
 }
@@ -0,0 +1,128 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Saves the game every few minutes, so that a crash or a power cut late in a long game doesn't
+//! lose all of it. Auto-saves are save games like any other (see `save`), kept in their own
+//! directory.
+
+use game::capture::timestamp;
+use game::save::{AUTOSAVE_DIR, GameSource, SAVE_EXTENSION, SaveGame, save_file_names};
+use specs;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use types::Fixed;
+
+pub struct AutoSaver {
+    interval: Fixed,
+    keep: usize,
+    seconds_until_save: Fixed,
+    writer: Option<JoinHandle<io::Result<PathBuf>>>,
+}
+
+impl AutoSaver {
+    /// Saves every `interval_minutes` of game time, keeping the latest `keep` saves
+    pub fn new(interval_minutes: u32, keep: usize) -> AutoSaver {
+        let interval = Fixed::from(interval_minutes as i32 * 60);
+        AutoSaver {
+            interval: interval,
+            keep: keep,
+            seconds_until_save: interval,
+            writer: None,
+        }
+    }
+
+    /// Called after every tick. The save is written on another thread from a snapshot of the
+    /// world, so the game doesn't hitch while the file is written.
+    pub fn update(&mut self, world: &specs::World, source: &GameSource, time_step: Fixed) {
+        self.seconds_until_save -= time_step;
+        if self.seconds_until_save > 0.into() {
+            return;
+        }
+        self.seconds_until_save = self.interval;
+
+        if self.writer.is_some() {
+            self.finish_writing();
+        }
+
+        let save = SaveGame::capture(world, source);
+        let keep = self.keep;
+        self.writer = Some(thread::spawn(move || {
+            let dir = PathBuf::from(AUTOSAVE_DIR);
+            try!(fs::create_dir_all(&dir));
+            let file_name = dir.join(format!("{}.{}", timestamp(), SAVE_EXTENSION));
+            try!(save.write_to_file(&file_name));
+            try!(remove_old_autosaves(&dir, keep));
+            Ok(file_name)
+        }));
+    }
+
+    /// Waits for the last save to be written
+    pub fn finish_writing(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            match writer.join() {
+                Ok(Ok(file_name)) => log_info!("Auto-saved to {}", file_name.display()),
+                Ok(Err(err)) => log_error!("Failed to auto-save: {}", err),
+                Err(_) => log_error!("Failed to auto-save: the writer thread panicked"),
+            }
+        }
+    }
+}
+
+fn autosave_names(dir: &Path) -> io::Result<Vec<String>> {
+    let paths = try!(save_file_names(dir));
+    Ok(paths.iter()
+        .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
+        .map(|name| name.to_string())
+        .collect())
+}
+
+fn remove_old_autosaves(dir: &Path, keep: usize) -> io::Result<()> {
+    for name in expired(try!(autosave_names(dir)), keep) {
+        try!(fs::remove_file(dir.join(name)));
+    }
+    Ok(())
+}
+
+/// Auto-saves are named after when they were written, so the oldest sort first
+fn expired(mut names: Vec<String>, keep: usize) -> Vec<String> {
+    names.sort();
+    let count = names.len().saturating_sub(keep);
+    names.truncate(count);
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expired;
+
+    #[test]
+    fn test_expired() {
+        let names = vec!["chariot-20161104-120500-000.sav".to_string(),
+                         "chariot-20161104-115500-000.sav".to_string(),
+                         "chariot-20161104-120000-000.sav".to_string()];
+        assert_eq!(vec!["chariot-20161104-115500-000.sav".to_string()],
+                   expired(names.clone(), 2));
+        assert!(expired(names.clone(), 3).is_empty());
+        assert_eq!(3, expired(names, 0).len());
+    }
+}
@@ -0,0 +1,381 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{Action, CommandLog, MoveToPositionParams};
+use dat::EmpiresDbRef;
+use drs::DrsFileType;
+use ecs::{UnitComponent, UnitSpawn};
+use ecs::resource::{ActionBatcher, OccupiedTiles, PathFinder, Players, SpawnQueue, Terrain, Tile};
+use identifier::{CivilizationId, PlayerId, UnitId, UnitTerrainRestrictionId};
+use resource::DrsKey;
+use scn;
+use slp::SlpFile;
+use specs::Join;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use super::game::time_step_nanos;
+use super::game_data::GameData;
+use super::headless::HeadlessGame;
+use time;
+use types::{Fixed, Vector3};
+
+/// Benchmarks that get slower than their baseline by more than this are reported as regressions
+const REGRESSION_TOLERANCE_PERCENT: i64 = 20;
+
+const PATH_MAP_SIZE: i32 = 255;
+const PATH_REQUEST_COUNT: usize = 200;
+const SIMULATED_UNIT_COUNT: usize = 500;
+const SIMULATED_TICK_COUNT: u64 = 1000;
+
+const STRESS_PLAYER_COUNT: usize = 4;
+const STRESS_UNIT_COUNT: usize = 1000;
+const STRESS_TICK_COUNT: u64 = 300;
+
+const GRASS_TERRAIN_ID: usize = 0;
+const WATER_TERRAIN_ID: usize = 1;
+const VILLAGER_UNIT_ID: u32 = 83;
+
+/// Nanoseconds per iteration of each benchmark, by name. Baseline files have one `<name> <nanos>`
+/// line per benchmark, and lines starting with `#` are comments.
+pub type BenchResults = BTreeMap<String, u64>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BenchOutcome {
+    New,
+    /// How much slower (or faster, if negative) it got, in percent
+    Within(i64),
+    Regressed(i64),
+}
+
+pub fn parse_baseline(text: &str) -> io::Result<BenchResults> {
+    let mut results = BenchResults::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let nanos = match fields.len() {
+            2 => fields[1].parse::<u64>().ok(),
+            _ => None,
+        };
+        match nanos {
+            Some(nanos) => results.insert(fields[0].to_string(), nanos),
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          format!("benchmark baseline line {}: expected \"<name> <nanos>\"",
+                                                  line_number + 1)))
+            }
+        };
+    }
+    Ok(results)
+}
+
+pub fn format_baseline(results: &BenchResults) -> String {
+    let mut text = "# Nanoseconds per iteration; regenerate with --bench <file> --bless\n".to_string();
+    for (name, nanos) in results {
+        text.push_str(&format!("{} {}\n", name, nanos));
+    }
+    text
+}
+
+pub fn compare(baseline: Option<u64>, nanos: u64) -> BenchOutcome {
+    match baseline {
+        Some(baseline) => {
+            let change = (nanos as i64 - baseline as i64) * 100 / ::std::cmp::max(1, baseline as i64);
+            if change > REGRESSION_TOLERANCE_PERCENT {
+                BenchOutcome::Regressed(change)
+            } else {
+                BenchOutcome::Within(change)
+            }
+        }
+        None => BenchOutcome::New,
+    }
+}
+
+/// Runs every benchmark and compares it against the baseline file, or writes the baseline if there
+/// isn't one yet (or `bless` is given). Returns false if anything regressed.
+pub fn run_benchmarks(data: &GameData,
+                      scenario: &scn::Scenario,
+                      baseline_file: &Path,
+                      bless: bool)
+                      -> bool {
+    let baseline = if baseline_file.exists() && !bless {
+        match read_baseline(baseline_file) {
+            Ok(baseline) => Some(baseline),
+            Err(err) => {
+                println!("ERROR  Failed to read {:?}: {}", baseline_file, err);
+                return false;
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut results = BenchResults::new();
+    results.insert("path_long".to_string(), bench_long_path(data.empires_db()));
+    results.insert("path_requests".to_string(), bench_path_requests(data.empires_db()));
+    results.insert("slp_decode_graphics".to_string(), bench_slp_decoding(data));
+    results.insert("simulate_ticks".to_string(), bench_simulation(data, scenario));
+    results.insert("simulate_stress_ticks".to_string(), bench_stress(data, scenario));
+
+    let mut all_passed = true;
+    for (name, &nanos) in &results {
+        let millis = nanos as f64 / 1000000.0;
+        match compare(baseline.as_ref().and_then(|baseline| baseline.get(name).cloned()), nanos) {
+            BenchOutcome::New => println!("NEW    {}: {:.3} ms", name, millis),
+            BenchOutcome::Within(change) => println!("PASS   {}: {:.3} ms ({:+}%)", name, millis, change),
+            BenchOutcome::Regressed(change) => {
+                println!("FAIL   {}: {:.3} ms ({:+}%)", name, millis, change);
+                all_passed = false;
+            }
+        }
+    }
+
+    // The stress test has to keep up with the game no matter what the baseline says
+    let stress_nanos = results["simulate_stress_ticks"];
+    if stress_nanos > time_step_nanos() {
+        println!("FAIL   simulate_stress_ticks: {:.3} ms per tick is slower than real time ({:.3} ms)",
+                 stress_nanos as f64 / 1000000.0,
+                 time_step_nanos() as f64 / 1000000.0);
+        all_passed = false;
+    }
+
+    if baseline.is_none() {
+        let written = File::create(baseline_file)
+            .and_then(|mut file| file.write_all(format_baseline(&results).as_bytes()));
+        match written {
+            Ok(_) => println!("Saved the baseline to {:?}", baseline_file),
+            Err(err) => {
+                println!("ERROR  Failed to write {:?}: {}", baseline_file, err);
+                all_passed = false;
+            }
+        }
+    }
+    all_passed
+}
+
+fn read_baseline(file_name: &Path) -> io::Result<BenchResults> {
+    let mut text = String::new();
+    try!(try!(File::open(file_name)).read_to_string(&mut text));
+    parse_baseline(&text)
+}
+
+/// Average nanoseconds per call of the given function
+fn time_iterations<F: FnMut()>(iterations: u64, mut f: F) -> u64 {
+    let start = time::precise_time_ns();
+    for _ in 0..iterations {
+        f();
+    }
+    (time::precise_time_ns() - start) / iterations
+}
+
+/// Open grass with a river down the middle that can only be crossed at the far end, so that paths
+/// across the map have a long way around to find
+fn path_terrain(empires: EmpiresDbRef) -> Terrain {
+    let mut tiles = Vec::new();
+    for row in 0..PATH_MAP_SIZE {
+        for col in 0..PATH_MAP_SIZE {
+            let river = (col - PATH_MAP_SIZE / 2).abs() <= 1 && row < PATH_MAP_SIZE - 8;
+            let terrain_id = if river { WATER_TERRAIN_ID } else { GRASS_TERRAIN_ID };
+            tiles.push(Tile::new(terrain_id.into(), 0));
+        }
+    }
+    Terrain::new(PATH_MAP_SIZE, PATH_MAP_SIZE, tiles, empires)
+}
+
+fn villager_restriction(empires: &EmpiresDbRef) -> UnitTerrainRestrictionId {
+    let civilization_id: CivilizationId = 1.into();
+    empires.find_unit(civilization_id, VILLAGER_UNIT_ID.into())
+        .map(|unit| unit.terrain_restriction)
+        .unwrap_or_else(|| {
+            unrecoverable!("Benchmarks need the villager (unit {}) in empires.dat", VILLAGER_UNIT_ID);
+        })
+}
+
+fn tile_center(row: i32, col: i32) -> Vector3 {
+    let half = Fixed::from(1) / Fixed::from(2);
+    Vector3::new(Fixed::from(col) + half, Fixed::from(row) + half, 0.into())
+}
+
+fn bench_long_path(empires: EmpiresDbRef) -> u64 {
+    let restriction = villager_restriction(&empires);
+    let terrain = path_terrain(empires.clone());
+    let path_finder = PathFinder::new(empires);
+    let occupied_tiles = OccupiedTiles::new();
+    let (from, to) = (tile_center(2, 2), tile_center(2, PATH_MAP_SIZE - 3));
+    time_iterations(5, || {
+        path_finder.find_path(&terrain, &occupied_tiles, &from, &to, restriction);
+    })
+}
+
+fn bench_path_requests(empires: EmpiresDbRef) -> u64 {
+    let restriction = villager_restriction(&empires);
+    let terrain = path_terrain(empires.clone());
+    let path_finder = PathFinder::new(empires);
+    let occupied_tiles = OccupiedTiles::new();
+    let requests: Vec<(Vector3, Vector3)> = (0..PATH_REQUEST_COUNT as i32)
+        .map(|index| {
+            let (row, col) = (8 + (index / 20) * 12, 8 + (index % 20) * 5);
+            (tile_center(row, col), tile_center(PATH_MAP_SIZE - 1 - row, PATH_MAP_SIZE - 1 - col))
+        })
+        .collect();
+    time_iterations(1, || {
+        for &(ref from, ref to) in &requests {
+            path_finder.find_path(&terrain, &occupied_tiles, from, to, restriction);
+        }
+    })
+}
+
+fn bench_slp_decoding(data: &GameData) -> u64 {
+    let drs_manager = data.drs_manager();
+    let drs_manager = drs_manager.borrow();
+    let contents: Vec<Vec<u8>> = drs_manager.file_ids(DrsKey::Graphics, DrsFileType::Slp)
+        .into_iter()
+        .filter_map(|file_id| {
+            match drs_manager.find_file_contents(DrsKey::Graphics, DrsFileType::Slp, file_id) {
+                Ok(Some(contents)) => Some(contents.into_owned()),
+                _ => None,
+            }
+        })
+        .collect();
+    time_iterations(1, || {
+        for slp_contents in &contents {
+            if let Err(err) = SlpFile::read_from(&mut io::Cursor::new(&slp_contents[..]), 1) {
+                println!("ERROR  Failed to decode an SLP: {}", err);
+            }
+        }
+    })
+}
+
+/// Fills the scenario with villagers that all walk to the other side of the map at once
+fn bench_simulation(data: &GameData, scenario: &scn::Scenario) -> u64 {
+    bench_walkers(data, scenario, 1, SIMULATED_UNIT_COUNT, SIMULATED_TICK_COUNT)
+}
+
+/// The late game at the original's scale: four players crossing the map with 250 villagers each
+fn bench_stress(data: &GameData, scenario: &scn::Scenario) -> u64 {
+    bench_walkers(data, scenario, STRESS_PLAYER_COUNT, STRESS_UNIT_COUNT, STRESS_TICK_COUNT)
+}
+
+/// Nanoseconds per tick with `unit_count` extra villagers walking to the other side of the map,
+/// shared out between the scenario's first `player_count` players
+fn bench_walkers(data: &GameData,
+                 scenario: &scn::Scenario,
+                 player_count: usize,
+                 unit_count: usize,
+                 tick_count: u64)
+                 -> u64 {
+    let unit_id: UnitId = VILLAGER_UNIT_ID.into();
+    let mut game = HeadlessGame::new(data, scenario, CommandLog::new(), &[]);
+    let player_ids: Vec<(PlayerId, CivilizationId)> = {
+        let world = game.planner().mut_world();
+        let players = world.read_resource::<Players>();
+        let mut player_ids: Vec<(PlayerId, CivilizationId)> = players.player_ids()
+            .into_iter()
+            .filter(|player_id| **player_id != 0)
+            .take(player_count)
+            .map(|player_id| (player_id, players.player(player_id).unwrap().civ_id))
+            .collect();
+        if player_ids.is_empty() {
+            player_ids.push((1.into(), 1.into()));
+        }
+        player_ids
+    };
+
+    {
+        let world = game.planner().mut_world();
+        let (width, height) = {
+            let terrain = world.read_resource::<Terrain>();
+            (terrain.width(), terrain.height())
+        };
+        let columns = (unit_count as f64).sqrt().ceil() as i32;
+        let mut spawn_queue = world.write_resource::<SpawnQueue>();
+        for index in 0..unit_count as i32 {
+            let (row, col) = (index / columns * 2 % height, index % columns * 2 % width);
+            let (player_id, civilization_id) = player_ids[index as usize % player_ids.len()];
+            spawn_queue.queue(UnitSpawn {
+                player_id: player_id,
+                civilization_id: civilization_id,
+                unit_id: unit_id,
+                position: tile_center(row, col),
+                rotation: 0.into(),
+                lifetime: None,
+            });
+        }
+    }
+
+    // The villagers only exist after a tick, and then they're sent off
+    game.run(1);
+    {
+        let world = game.planner().mut_world();
+        let (width, height) = {
+            let terrain = world.read_resource::<Terrain>();
+            (terrain.width(), terrain.height())
+        };
+        let owner_ids: Vec<PlayerId> = player_ids.iter().map(|&(player_id, _)| player_id).collect();
+        let mover_ids: Vec<_> = {
+            let entities = world.entities();
+            let units = world.read::<UnitComponent>();
+            let villagers = (&entities, &units)
+                .iter()
+                .filter(|&(_, unit)| unit.unit_id == unit_id && owner_ids.contains(&unit.player_id))
+                .map(|(entity, _)| entity.get_id())
+                .collect();
+            villagers
+        };
+        let mut action_batcher = world.write_resource::<ActionBatcher>();
+        for (index, entity_id) in mover_ids.into_iter().enumerate() {
+            let index = index as i32;
+            let target = tile_center(height - 1 - index % height, width - 1 - index * 7 % width);
+            let action = Action::MoveToPosition(MoveToPositionParams::new(vec![target]));
+            action_batcher.queue_for_entity(entity_id, action);
+        }
+    }
+
+    time_iterations(1, || {
+        game.run(tick_count);
+    }) / tick_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BenchOutcome, BenchResults, compare, format_baseline, parse_baseline};
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let mut results = BenchResults::new();
+        results.insert("path_long".to_string(), 1500000);
+        results.insert("simulate_ticks".to_string(), 250000);
+        assert_eq!(results, parse_baseline(&format_baseline(&results)).unwrap());
+        assert!(parse_baseline("path_long fast").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(BenchOutcome::New, compare(None, 100));
+        assert_eq!(BenchOutcome::Within(-50), compare(Some(200), 100));
+        assert_eq!(BenchOutcome::Within(20), compare(Some(100), 120));
+        assert_eq!(BenchOutcome::Regressed(21), compare(Some(100), 121));
+    }
+}
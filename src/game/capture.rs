@@ -0,0 +1,319 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use media::CapturedFrame;
+use png::{self, HasParameters};
+use time;
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+const SCREENSHOT_DIR: &'static str = "screenshots";
+const CAPTURE_DIR: &'static str = "captures";
+
+/// Frames per second to record at; frames rendered in between are skipped
+const CAPTURE_FRAMES_PER_SECOND: u64 = 30;
+
+/// What recorded frames are written to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CaptureFormat {
+    /// Numbered PNGs in a directory per recording
+    PngSequence,
+
+    /// An MP4 encoded by piping raw frames to ffmpeg (which must be on the PATH)
+    Ffmpeg,
+}
+
+/// Takes screenshots (F10) and records frame sequences (F11 starts and stops). PNG encoding and
+/// writing happens on a background thread so that the game doesn't hitch while capturing.
+pub struct FrameCapture {
+    format: CaptureFormat,
+    screenshot_requested: bool,
+    recording: bool,
+    last_frame_time: u64,
+    jobs: Sender<Job>,
+    writer_thread: Option<JoinHandle<()>>,
+}
+
+enum Job {
+    Screenshot(CapturedFrame),
+    StartRecording(CaptureFormat),
+    Frame(CapturedFrame),
+    StopRecording,
+    Shutdown,
+}
+
+impl FrameCapture {
+    pub fn new() -> FrameCapture {
+        let (sender, receiver) = mpsc::channel();
+        let writer_thread = thread::spawn(move || {
+            let mut writer = CaptureWriter { sink: None };
+            for job in receiver.iter() {
+                if let Job::Shutdown = job {
+                    break;
+                }
+                writer.handle(job);
+            }
+            writer.stop_recording();
+        });
+        FrameCapture {
+            format: CaptureFormat::PngSequence,
+            screenshot_requested: false,
+            recording: false,
+            last_frame_time: 0,
+            jobs: sender,
+            writer_thread: Some(writer_thread),
+        }
+    }
+
+    /// Takes effect the next time recording starts
+    pub fn set_format(&mut self, format: CaptureFormat) {
+        self.format = format;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    pub fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    pub fn toggle_recording(&mut self) {
+        self.recording = !self.recording;
+        if self.recording {
+            self.last_frame_time = 0;
+            self.send(Job::StartRecording(self.format));
+        } else {
+            self.send(Job::StopRecording);
+        }
+    }
+
+    /// Whether `capture` needs the frame that was just rendered
+    pub fn wants_frame(&self) -> bool {
+        self.screenshot_requested ||
+        (self.recording &&
+         time::precise_time_ns() - self.last_frame_time >= 1000000000 / CAPTURE_FRAMES_PER_SECOND)
+    }
+
+    pub fn capture(&mut self, frame: CapturedFrame) {
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            if !self.recording {
+                self.send(Job::Screenshot(frame));
+                return;
+            }
+            self.send(Job::Screenshot(frame.clone()));
+        }
+        if self.recording {
+            self.last_frame_time = time::precise_time_ns();
+            self.send(Job::Frame(frame));
+        }
+    }
+
+    fn send(&self, job: Job) {
+        if self.jobs.send(job).is_err() {
+            log_warn!("Frame capture stopped unexpectedly");
+        }
+    }
+}
+
+impl Drop for FrameCapture {
+    /// Waits for the writer thread so that queued screenshots and recordings get finished
+    fn drop(&mut self) {
+        self.send(Job::Shutdown);
+        if let Some(writer_thread) = self.writer_thread.take() {
+            writer_thread.join().ok();
+        }
+    }
+}
+
+enum CaptureSink {
+    PngSequence {
+        dir: PathBuf,
+        next_index: u32,
+    },
+
+    /// ffmpeg needs to know the frame size up front, so it's started with the first frame
+    PendingFfmpeg {
+        file_name: PathBuf,
+    },
+
+    Ffmpeg {
+        child: Child,
+        file_name: PathBuf,
+        width: u32,
+        height: u32,
+    },
+}
+
+struct CaptureWriter {
+    sink: Option<CaptureSink>,
+}
+
+impl CaptureWriter {
+    fn handle(&mut self, job: Job) {
+        match job {
+            Job::Screenshot(frame) => {
+                let file_name = Path::new(SCREENSHOT_DIR).join(format!("{}.png", timestamp()));
+                let result = fs::create_dir_all(SCREENSHOT_DIR)
+                    .map_err(|err| err.to_string())
+                    .and_then(|_| write_png(&file_name, &frame));
+                match result {
+                    Ok(_) => log_info!("Saved screenshot to {:?}", file_name),
+                    Err(err) => log_error!("Failed to save screenshot {:?}: {}", file_name, err),
+                }
+            }
+            Job::StartRecording(format) => {
+                self.stop_recording();
+                let dir = Path::new(CAPTURE_DIR).join(timestamp());
+                let created = match format {
+                    CaptureFormat::PngSequence => fs::create_dir_all(&dir),
+                    CaptureFormat::Ffmpeg => fs::create_dir_all(CAPTURE_DIR),
+                };
+                if let Err(err) = created {
+                    log_error!("Failed to start recording: {}", err);
+                    return;
+                }
+                self.sink = Some(match format {
+                    CaptureFormat::PngSequence => {
+                        log_info!("Recording frames to {:?}", dir);
+                        CaptureSink::PngSequence {
+                            dir: dir,
+                            next_index: 0,
+                        }
+                    }
+                    CaptureFormat::Ffmpeg => {
+                        CaptureSink::PendingFfmpeg { file_name: dir.with_extension("mp4") }
+                    }
+                });
+            }
+            Job::Frame(frame) => self.write_frame(frame),
+            Job::StopRecording => self.stop_recording(),
+            Job::Shutdown => {}
+        }
+    }
+
+    fn write_frame(&mut self, frame: CapturedFrame) {
+        self.sink = match self.sink.take() {
+            None => None,
+            Some(CaptureSink::PngSequence { dir, next_index }) => {
+                let file_name = dir.join(format!("frame-{:05}.png", next_index));
+                if let Err(err) = write_png(&file_name, &frame) {
+                    log_error!("Failed to write {:?}: {}", file_name, err);
+                }
+                Some(CaptureSink::PngSequence {
+                    dir: dir,
+                    next_index: next_index + 1,
+                })
+            }
+            Some(CaptureSink::PendingFfmpeg { file_name }) => {
+                match start_ffmpeg(&file_name, frame.width, frame.height) {
+                    Ok(child) => {
+                        log_info!("Recording to {:?} with ffmpeg", file_name);
+                        self.sink = Some(CaptureSink::Ffmpeg {
+                            child: child,
+                            file_name: file_name,
+                            width: frame.width,
+                            height: frame.height,
+                        });
+                        return self.write_frame(frame);
+                    }
+                    Err(err) => {
+                        log_error!("Failed to start ffmpeg (is it installed?): {}", err);
+                        None
+                    }
+                }
+            }
+            Some(CaptureSink::Ffmpeg { mut child, file_name, width, height }) => {
+                if frame.width != width || frame.height != height {
+                    log_warn!("The window size changed, so recording stopped");
+                    finish_ffmpeg(child, &file_name);
+                    return;
+                }
+                let result = child.stdin.as_mut().unwrap().write_all(&frame.pixels);
+                if let Err(err) = result {
+                    log_error!("Failed to send a frame to ffmpeg: {}", err);
+                    finish_ffmpeg(child, &file_name);
+                    return;
+                }
+                Some(CaptureSink::Ffmpeg {
+                    child: child,
+                    file_name: file_name,
+                    width: width,
+                    height: height,
+                })
+            }
+        };
+    }
+
+    fn stop_recording(&mut self) {
+        match self.sink.take() {
+            Some(CaptureSink::PngSequence { dir, next_index }) => {
+                log_info!("Recorded {} frames to {:?}", next_index, dir);
+            }
+            Some(CaptureSink::Ffmpeg { child, file_name, .. }) => finish_ffmpeg(child, &file_name),
+            Some(CaptureSink::PendingFfmpeg { .. }) | None => {}
+        }
+    }
+}
+
+fn start_ffmpeg(file_name: &Path, width: u32, height: u32) -> ::std::io::Result<Child> {
+    Command::new("ffmpeg")
+        .args(&["-loglevel", "error", "-y", "-f", "rawvideo", "-pix_fmt", "rgba", "-s"])
+        .arg(format!("{}x{}", width, height))
+        .arg("-r")
+        .arg(CAPTURE_FRAMES_PER_SECOND.to_string())
+        .args(&["-i", "-", "-pix_fmt", "yuv420p", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .arg(file_name)
+        .stdin(Stdio::piped())
+        .spawn()
+}
+
+fn finish_ffmpeg(mut child: Child, file_name: &Path) {
+    // Closing its input tells ffmpeg that there are no more frames
+    drop(child.stdin.take());
+    match child.wait() {
+        Ok(status) if status.success() => log_info!("Saved recording to {:?}", file_name),
+        Ok(status) => log_error!("ffmpeg failed to encode {:?} ({})", file_name, status),
+        Err(err) => log_error!("Failed to wait for ffmpeg: {}", err),
+    }
+}
+
+fn write_png(file_name: &Path, frame: &CapturedFrame) -> Result<(), String> {
+    let file = try!(File::create(file_name).map_err(|err| err.to_string()));
+    let mut encoder = png::Encoder::new(BufWriter::new(file), frame.width, frame.height);
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    let mut writer = try!(encoder.write_header().map_err(|err| err.to_string()));
+    writer.write_image_data(&frame.pixels).map_err(|err| err.to_string())
+}
+
+/// Local time, to the millisecond, in a form that sorts and works as a file name
+pub fn timestamp() -> String {
+    let now = time::now();
+    format!("chariot-{}-{:03}",
+            now.strftime("%Y%m%d-%H%M%S").unwrap(),
+            now.tm_nsec / 1000000)
+}
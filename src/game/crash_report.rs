@@ -0,0 +1,365 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::CommandLog;
+use ecs::{self, GameSettings, WorldSnapshot};
+use ecs::resource::{ActionBatcher, GameRng, SimulationTick};
+use specs;
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, PanicInfo};
+use std::path::PathBuf;
+use std::sync::{Mutex, TryLockError};
+use super::capture::timestamp;
+use super::repro::ReproBundle;
+
+pub const CRASH_DIR: &'static str = "crashes";
+pub const REPRO_DIR: &'static str = "repros";
+
+/// How many of the latest commands are listed in the report itself; the command log has them all
+const RECENT_COMMAND_COUNT: usize = 20;
+
+/// Hashing the whole state every tick would slow the game down, so it's only done this often
+const STATE_HASH_INTERVAL_TICKS: u64 = 60;
+
+/// How often a snapshot is taken for repro bundles to start from. Bundles start from the one
+/// before the latest, so they cover at least this many ticks.
+const REPRO_WINDOW_TICKS: u64 = 30 * 60;
+
+/// Where a crash report's repro bundle goes, inside the report's directory
+const REPRO_SUBDIR: &'static str = "repro";
+
+/// What the game was doing, kept up to date every tick so that it's at hand if the game panics
+struct CrashContext {
+    scenario_file: String,
+    script_files: Vec<String>,
+    seed: Option<u64>,
+    tick: u64,
+    /// The state hash now and then (by tick) since the oldest snapshot
+    state_hashes: VecDeque<(u64, u64)>,
+
+    /// The snapshot repro bundles start from, and the latest one once there's a newer one
+    snapshots: VecDeque<WorldSnapshot>,
+    commands: CommandLog,
+}
+
+impl CrashContext {
+    fn record_state_hash(&mut self, tick: u64, hash: u64) {
+        self.state_hashes.push_back((tick, hash));
+    }
+
+    fn record_snapshot(&mut self, snapshot: WorldSnapshot) {
+        self.snapshots.push_back(snapshot);
+        if self.snapshots.len() > 2 {
+            self.snapshots.pop_front();
+        }
+        let start_tick = self.snapshots.front().unwrap().tick();
+        while self.state_hashes.front().map(|&(tick, _)| tick < start_tick).unwrap_or(false) {
+            self.state_hashes.pop_front();
+        }
+    }
+
+    /// The whole command log, with the seed
+    fn command_log(&self) -> CommandLog {
+        let mut commands = self.commands.clone();
+        if let Some(seed) = self.seed {
+            commands.set_seed(seed);
+        }
+        commands
+    }
+
+    /// Everything `--repro` needs to play back the last stretch of the game: the bundle, the
+    /// commands after the snapshot it starts from (with the seed), and the snapshot
+    fn repro_bundle(&self, reason: &str) -> (ReproBundle, CommandLog, Option<WorldSnapshot>) {
+        let snapshot = self.snapshots.front().cloned();
+        let start_tick = snapshot.as_ref().map(|snapshot| snapshot.tick()).unwrap_or(0);
+        let bundle = ReproBundle {
+            reason: reason.to_string(),
+            scenario: PathBuf::from(&self.scenario_file),
+            scripts: self.script_files.iter().map(PathBuf::from).collect(),
+            start_tick: start_tick,
+            end_tick: self.tick,
+            hashes: self.state_hashes.iter().cloned().collect::<BTreeMap<u64, u64>>(),
+        };
+
+        let mut commands = CommandLog::new();
+        if let Some(seed) = self.seed {
+            commands.set_seed(seed);
+        }
+        if let Some(settings) = self.commands.settings() {
+            commands.set_settings(settings);
+        }
+        for entry in self.commands.entries() {
+            // Commands consumed on the start tick are already in the snapshot
+            if snapshot.is_none() || entry.tick > start_tick {
+                commands.push(entry.clone());
+            }
+        }
+        (bundle, commands, snapshot)
+    }
+}
+
+lazy_static! {
+    static ref CONTEXT: Mutex<Option<CrashContext>> = Mutex::new(None);
+}
+
+// TODO: Add a backtrace once there's a way to get one on stable Rust (RUST_BACKTRACE=1 prints one
+// with the panic message in the meantime), and an auto-save
+/// Writes a crash report to the crash directory whenever the game panics, after the usual panic
+/// message. Each report gets its own directory with a summary, the command log it needs to be
+/// replayed with --replay, and a repro bundle for --repro in its `repro` directory.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_report(info) {
+            Ok(Some(dir)) => log_info!("Wrote a crash report to {:?}", dir),
+            Ok(None) => {}
+            Err(err) => log_error!("Failed to write a crash report: {}", err),
+        }
+    }));
+}
+
+/// Starts keeping track of a new game for crash reports
+pub fn begin_game(scenario_file: &str, script_files: &[String]) {
+    let mut context = match CONTEXT.lock() {
+        Ok(context) => context,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    *context = Some(CrashContext {
+        scenario_file: scenario_file.to_string(),
+        script_files: script_files.to_vec(),
+        seed: None,
+        tick: 0,
+        state_hashes: VecDeque::new(),
+        snapshots: VecDeque::new(),
+        commands: CommandLog::new(),
+    });
+}
+
+/// Catches up on the tick that just ran: the commands consumed on it, and now and then the state
+/// hash and a snapshot
+pub fn record_tick(world: &specs::World) {
+    if !tracking() {
+        return;
+    }
+
+    let tick = world.read_resource::<SimulationTick>().tick;
+    let seed = world.read_resource::<GameRng>().seed();
+    let settings = world.read_resource::<GameSettings>();
+    let state_hash = if tick % STATE_HASH_INTERVAL_TICKS == 0 {
+        Some((tick, ecs::state_hash(world)))
+    } else {
+        None
+    };
+    let action_batcher = world.read_resource::<ActionBatcher>();
+    let history = action_batcher.history().entries();
+    let new_count = history.iter().rev().take_while(|entry| entry.tick == tick).count();
+
+    let mut context = match CONTEXT.lock() {
+        Ok(context) => context,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(ref mut context) = *context {
+        context.tick = tick;
+        context.seed = Some(seed);
+        if context.commands.settings().is_none() {
+            context.commands.set_settings(&settings.to_field());
+        }
+        if let Some((tick, hash)) = state_hash {
+            context.record_state_hash(tick, hash);
+        }
+        if context.snapshots.is_empty() || tick % REPRO_WINDOW_TICKS == 0 {
+            context.record_snapshot(WorldSnapshot::capture(world));
+        }
+        for entry in history.iter().skip(history.len() - new_count) {
+            context.commands.push(entry.clone());
+        }
+    }
+}
+
+/// Writes a repro bundle of the last stretch of the game to its own directory in the repro
+/// directory, for when something has gone wrong that doesn't panic (such as a desync). The state
+/// hash right now is added to the ones that were recorded along the way.
+pub fn write_repro_bundle(world: &specs::World, reason: &str) -> io::Result<Option<PathBuf>> {
+    let tick = world.read_resource::<SimulationTick>().tick;
+    let hash = ecs::state_hash(world);
+    let (bundle, commands, snapshot) = {
+        let mut context = match CONTEXT.lock() {
+            Ok(context) => context,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match *context {
+            Some(ref mut context) => {
+                context.record_state_hash(tick, hash);
+                context.repro_bundle(reason)
+            }
+            None => return Ok(None),
+        }
+    };
+
+    let dir = PathBuf::from(REPRO_DIR).join(timestamp());
+    try!(bundle.write_to_dir(&dir, &commands, snapshot.as_ref()));
+    Ok(Some(dir))
+}
+
+/// Whether there's a game to keep track of; regression runs and benchmarks don't have one
+fn tracking() -> bool {
+    match CONTEXT.lock() {
+        Ok(context) => context.is_some(),
+        Err(poisoned) => poisoned.into_inner().is_some(),
+    }
+}
+
+fn write_report(info: &PanicInfo) -> io::Result<Option<PathBuf>> {
+    // Nothing that's done with the context locked should panic, but if something did, waiting for
+    // the lock here would hang the game instead of letting it exit
+    let context = match CONTEXT.try_lock() {
+        Ok(context) => context,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return Ok(None),
+    };
+    let context = match *context {
+        Some(ref context) => context,
+        None => return Ok(None),
+    };
+
+    let dir = PathBuf::from(CRASH_DIR).join(timestamp());
+    try!(fs::create_dir_all(&dir));
+
+    let message = match info.payload().downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => {
+            match info.payload().downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "(no message)".to_string(),
+            }
+        }
+    };
+    let location = info.location()
+        .map(|location| format!("{}:{}", location.file(), location.line()))
+        .unwrap_or("(unknown)".to_string());
+    let reason = format!("Panicked at {}: {}", location, message);
+    try!(context.command_log().write_to_file(dir.join("commands.log")));
+    let (bundle, commands, snapshot) = context.repro_bundle(&reason);
+    try!(bundle.write_to_dir(dir.join(REPRO_SUBDIR), &commands, snapshot.as_ref()));
+
+    let mut report = try!(fs::File::create(dir.join("report.txt")));
+    try!(report.write_all(report_text(context, &message, &location).as_bytes()));
+    Ok(Some(dir))
+}
+
+fn report_text(context: &CrashContext, message: &str, location: &str) -> String {
+    let mut text = String::new();
+    text.push_str(&format!("Panicked at {}: {}\n", location, message));
+    text.push_str(&format!("Scenario: {}\n", context.scenario_file));
+    for script_file in &context.script_files {
+        text.push_str(&format!("Script: {}\n", script_file));
+    }
+    match context.seed {
+        Some(seed) => text.push_str(&format!("Seed: {}\n", seed)),
+        None => text.push_str("Seed: (before the first tick)\n"),
+    }
+    text.push_str(&format!("Tick: {}\n", context.tick));
+    if let Some(&(tick, hash)) = context.state_hashes.back() {
+        text.push_str(&format!("State hash at tick {}: {:016x}\n", tick, hash));
+    }
+
+    let entries = context.commands.entries();
+    text.push_str(&format!("\nLast {} of {} commands (all of them are in commands.log):\n",
+                           cmp::min(RECENT_COMMAND_COUNT, entries.len()),
+                           entries.len()));
+    let mut recent = CommandLog::new();
+    for entry in entries.iter().skip(entries.len().saturating_sub(RECENT_COMMAND_COUNT)) {
+        recent.push(entry.clone());
+    }
+    text.push_str(&recent.to_text());
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use action::{Action, CommandLog, CommandLogEntry};
+    use std::collections::VecDeque;
+    use super::{CrashContext, REPRO_WINDOW_TICKS, report_text};
+
+    #[test]
+    fn test_report_text() {
+        let mut commands = CommandLog::new();
+        for tick in 0..25 {
+            commands.push(CommandLogEntry {
+                tick: tick,
+                entity_id: 3,
+                action: Action::Kill,
+            });
+        }
+        let context = CrashContext {
+            scenario_file: "MUF7E5_1.SCN".to_string(),
+            script_files: vec!["rules.txt".to_string()],
+            seed: Some(42),
+            tick: 24,
+            state_hashes: vec![(0, 0xabc)].into_iter().collect::<VecDeque<_>>(),
+            snapshots: VecDeque::new(),
+            commands: commands,
+        };
+
+        let text = report_text(&context, "oops", "src/main.rs:1");
+        let header = "Panicked at src/main.rs:1: oops\nScenario: MUF7E5_1.SCN\nScript: rules.txt\nSeed: 42\n\
+                      Tick: 24\nState hash at tick 0: 0000000000000abc\n";
+        assert!(text.starts_with(header));
+        assert!(text.contains("Last 20 of 25 commands"));
+        assert!(!text.contains("\n4 3 "));
+        assert!(text.contains("\n5 3 "));
+    }
+
+    #[test]
+    fn test_repro_bundle_without_a_snapshot() {
+        let mut context = CrashContext {
+            scenario_file: "MUF7E5_1.SCN".to_string(),
+            script_files: Vec::new(),
+            seed: Some(42),
+            tick: 0,
+            state_hashes: VecDeque::new(),
+            snapshots: VecDeque::new(),
+            commands: CommandLog::new(),
+        };
+        for tick in 0..(REPRO_WINDOW_TICKS / 60 + 3) {
+            context.tick = tick * 60;
+            context.record_state_hash(tick * 60, tick);
+        }
+        context.commands.push(CommandLogEntry {
+            tick: 0,
+            entity_id: 3,
+            action: Action::Kill,
+        });
+
+        // With nothing to start from, the bundle plays the whole game back
+        let (bundle, commands, snapshot) = context.repro_bundle("desync");
+        assert!(snapshot.is_none());
+        assert_eq!(0, bundle.start_tick);
+        assert_eq!(context.tick, bundle.end_tick);
+        assert_eq!(REPRO_WINDOW_TICKS / 60 + 3, bundle.hashes.len() as u64);
+        assert_eq!(Some(42), commands.seed());
+        assert_eq!(1, commands.entries().len());
+    }
+}
@@ -0,0 +1,163 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The row of buttons along the bottom of the scenario editor. Like the pause menu, it only keeps
+//! track of which buttons are on and where they are; `EditorGameState` decides what clicking each
+//! one does.
+
+use types::Rect;
+
+pub const BUTTON_WIDTH: i32 = 84;
+pub const BUTTON_HEIGHT: i32 = 24;
+const BUTTON_SPACING: i32 = 4;
+
+/// Space between the buttons and the edges of the screen
+pub const MARGIN: i32 = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ToolbarButton {
+    Terrain,
+    Elevation,
+    Units,
+    Players,
+    Undo,
+    Redo,
+    Save,
+    Load,
+}
+
+static BUTTONS: [ToolbarButton; 8] = [ToolbarButton::Terrain,
+                                      ToolbarButton::Elevation,
+                                      ToolbarButton::Units,
+                                      ToolbarButton::Players,
+                                      ToolbarButton::Undo,
+                                      ToolbarButton::Redo,
+                                      ToolbarButton::Save,
+                                      ToolbarButton::Load];
+
+impl ToolbarButton {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            ToolbarButton::Terrain => "Terrain",
+            ToolbarButton::Elevation => "Elevation",
+            ToolbarButton::Units => "Units",
+            ToolbarButton::Players => "Players",
+            ToolbarButton::Undo => "Undo",
+            ToolbarButton::Redo => "Redo",
+            ToolbarButton::Save => "Save",
+            ToolbarButton::Load => "Load",
+        }
+    }
+}
+
+pub struct EditorToolbar {
+    /// The button of the tool in use, which is drawn pressed
+    selected: ToolbarButton,
+    disabled: Vec<ToolbarButton>,
+
+    /// What happened when the scenario was last saved or loaded
+    status: Option<String>,
+}
+
+impl EditorToolbar {
+    pub fn new() -> EditorToolbar {
+        EditorToolbar {
+            selected: ToolbarButton::Terrain,
+            disabled: Vec::new(),
+            status: None,
+        }
+    }
+
+    pub fn buttons(&self) -> &'static [ToolbarButton] {
+        &BUTTONS
+    }
+
+    pub fn select(&mut self, button: ToolbarButton) {
+        self.selected = button;
+    }
+
+    pub fn is_selected(&self, button: ToolbarButton) -> bool {
+        self.selected == button
+    }
+
+    pub fn set_enabled(&mut self, button: ToolbarButton, enabled: bool) {
+        self.disabled.retain(|&disabled| disabled != button);
+        if !enabled {
+            self.disabled.push(button);
+        }
+    }
+
+    pub fn is_enabled(&self, button: ToolbarButton) -> bool {
+        !self.disabled.contains(&button)
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_ref().map(|status| &status[..])
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = Some(status);
+    }
+
+    /// Where the button at the index is drawn on a screen of the given height
+    pub fn button_rect(&self, index: usize, screen_height: i32) -> Rect {
+        Rect::of(MARGIN + index as i32 * (BUTTON_WIDTH + BUTTON_SPACING),
+                 screen_height - MARGIN - BUTTON_HEIGHT,
+                 BUTTON_WIDTH,
+                 BUTTON_HEIGHT)
+    }
+
+    /// Whether the screen position is over any of the buttons, enabled or not
+    pub fn is_over(&self, x: i32, y: i32, screen_height: i32) -> bool {
+        (0..BUTTONS.len()).any(|index| self.button_rect(index, screen_height).contains(x, y))
+    }
+
+    /// The enabled button at the screen position, if there is one
+    pub fn button_at(&self, x: i32, y: i32, screen_height: i32) -> Option<ToolbarButton> {
+        BUTTONS.iter()
+            .enumerate()
+            .find(|&(index, _)| self.button_rect(index, screen_height).contains(x, y))
+            .map(|(_, &button)| button)
+            .and_then(|button| if self.is_enabled(button) { Some(button) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUTTON_HEIGHT, BUTTON_WIDTH, EditorToolbar, MARGIN, ToolbarButton};
+
+    #[test]
+    fn test_button_at() {
+        let mut toolbar = EditorToolbar::new();
+        let y = 600 - MARGIN - BUTTON_HEIGHT / 2;
+        assert_eq!(Some(ToolbarButton::Terrain), toolbar.button_at(MARGIN, y, 600));
+        assert_eq!(Some(ToolbarButton::Elevation), toolbar.button_at(MARGIN + BUTTON_WIDTH + 10, y, 600));
+        assert_eq!(None, toolbar.button_at(MARGIN + BUTTON_WIDTH + 1, y, 600));
+        assert_eq!(None, toolbar.button_at(MARGIN, 300, 600));
+
+        let undo_x = toolbar.button_rect(4, 600).x;
+        assert_eq!(Some(ToolbarButton::Undo), toolbar.button_at(undo_x, y, 600));
+        toolbar.set_enabled(ToolbarButton::Undo, false);
+        assert_eq!(None, toolbar.button_at(undo_x, y, 600));
+        assert!(toolbar.is_over(undo_x, y, 600));
+        assert!(!toolbar.is_over(MARGIN, 300, 600));
+    }
+}
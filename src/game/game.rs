@@ -19,10 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use dat::{EmpiresDb, EmpiresDbRef};
-use media::{self, MediaRef};
-use resource::{DrsManager, DrsManagerRef, GameDir, ShapeManager, ShapeManagerRef, ShapeMetadataStore,
+use dat::EmpiresDbRef;
+use ecs::{UPDATES_PER_SECOND, time_step_seconds};
+use logging;
+use media::{self, Key, KeyState, MediaRef};
+use resource::{DrsManagerRef, GameDir, PlayerColorScheme, ShapeManager, ShapeManagerRef,
                ShapeMetadataStoreRef};
+use super::capture::FrameCapture;
+use super::game_data::GameData;
+use super::hud;
 use super::state::GameState;
 use time;
 use types::Fixed;
@@ -31,51 +36,46 @@ const WINDOW_TITLE: &'static str = "Chariot";
 const WINDOW_WIDTH: u32 = 1024;
 const WINDOW_HEIGHT: u32 = 768;
 
+/// How many of the latest log records the log overlay shows
+const LOG_OVERLAY_LINES: usize = 20;
+
+/// How long a tick can take for the game to keep up with real time
+pub fn time_step_nanos() -> u64 {
+    1000000000 / UPDATES_PER_SECOND
+}
+
 pub struct Game {
-    game_dir: GameDir,
-    drs_manager: DrsManagerRef,
+    data: GameData,
     shape_manager: ShapeManagerRef,
-    shape_metadata: ShapeMetadataStoreRef,
-    empires: EmpiresDbRef,
     media: MediaRef,
+    capture: FrameCapture,
+    show_log: bool,
     states: Vec<Box<GameState>>,
+    accumulator: u64,
+    last_frame_time: Option<u64>,
 }
 
 impl Game {
-    pub fn new(game_data_dir: &str) -> Game {
-        let game_dir = GameDir::new(game_data_dir).unwrap_or_else(|err| {
-            unrecoverable!("{}", err);
-        });
+    pub fn new(game_data_dir: &str, mods_dir: &str, player_color_scheme: PlayerColorScheme) -> Game {
+        let data = GameData::load(game_data_dir, Some(mods_dir));
 
-        let drs_manager = DrsManager::new(&game_dir);
-        if let Err(err) = drs_manager.borrow_mut().preload() {
-            unrecoverable!("Failed to preload DRS archives: {}", err);
-        }
-
-        let shape_manager = ShapeManager::new(drs_manager.clone()).unwrap_or_else(|err| {
+        let shape_manager = ShapeManager::new(data.drs_manager(), player_color_scheme).unwrap_or_else(|err| {
             unrecoverable!("Failed to initialize the shape manager: {}", err);
         });
 
-        let shape_metadata = ShapeMetadataStoreRef::new(ShapeMetadataStore::load(&*drs_manager.borrow()));
-
-        let empires_dat_location = game_dir.find_file("data/empires.dat").unwrap();
-        let empires = EmpiresDbRef::new(EmpiresDb::read_from_file(empires_dat_location)
-            .unwrap_or_else(|err| {
-                unrecoverable!("Failed to load empires.dat: {}", err);
-            }));
-
         let media = media::create_media(WINDOW_WIDTH, WINDOW_HEIGHT, WINDOW_TITLE).unwrap_or_else(|err| {
             unrecoverable!("Failed to create media window: {}", err);
         });
 
         Game {
-            game_dir: game_dir,
-            drs_manager: drs_manager,
+            data: data,
             shape_manager: shape_manager,
-            shape_metadata: shape_metadata,
-            empires: empires,
             media: media,
+            capture: FrameCapture::new(),
+            show_log: false,
             states: Vec::new(),
+            accumulator: 0,
+            last_frame_time: None,
         }
     }
 
@@ -88,29 +88,72 @@ impl Game {
     }
 
     pub fn game_loop(&mut self) {
-        let time_step_nanos = 1000000000 / 60u64;
-        let time_step_seconds = Fixed::from(1) / Fixed::from(60);
-
-        let mut accumulator: u64 = 0;
-        let mut last_time = time::precise_time_ns();
-
         while self.media.borrow().is_open() {
-            self.media.borrow_mut().renderer().present();
+            if !self.frame(time::precise_time_ns()) {
+                break;
+            }
+        }
 
-            let new_time = time::precise_time_ns();
-            accumulator += new_time - last_time;
-            last_time = new_time;
+        // Give the states a chance to clean up (such as writing out recorded commands)
+        while !self.states.is_empty() {
+            self.pop_state();
+        }
+    }
 
-            while accumulator >= time_step_nanos {
-                self.media.borrow_mut().update();
-                self.update(time_step_seconds);
-                accumulator -= time_step_nanos;
+    /// Presents the last frame, runs however many updates are due by `now` (in nanoseconds) and
+    /// renders the next one. Returns false once the game has quit. It's separate from `game_loop`
+    /// so that a platform that owns the main loop, like a browser calling it from
+    /// requestAnimationFrame, can drive the game one frame at a time.
+    pub fn frame(&mut self, now: u64) -> bool {
+        let time_step_nanos = time_step_nanos();
+        let time_step_seconds = time_step_seconds();
+
+        // The last frame is read back before presenting, since presenting clears it
+        if self.capture.wants_frame() {
+            match self.media.borrow_mut().renderer().read_pixels() {
+                Ok(frame) => self.capture.capture(frame),
+                Err(err) => log_error!("Failed to capture the frame: {}", err),
             }
+        }
+        self.media.borrow_mut().renderer().present();
 
-            let lerp = Fixed::from(accumulator as f64 / time_step_nanos as f64);
-            if let Some(state) = self.current_state() {
-                state.render(lerp);
-            }
+        let last_time = self.last_frame_time.unwrap_or(now);
+        self.accumulator += now - last_time;
+        self.last_frame_time = Some(now);
+
+        while self.accumulator >= time_step_nanos {
+            self.media.borrow_mut().update();
+            self.update_capture_keys();
+            self.update(time_step_seconds);
+            self.accumulator -= time_step_nanos;
+        }
+
+        // Quitting the last state quits the game
+        if self.states.is_empty() {
+            return false;
+        }
+
+        let lerp = Fixed::from(self.accumulator as f64 / time_step_nanos as f64);
+        if let Some(state) = self.current_state() {
+            state.render(lerp);
+        }
+        if self.show_log {
+            let records = logging::recent_records(LOG_OVERLAY_LINES);
+            hud::render_log(self.media.borrow_mut().renderer(), &records);
+        }
+        true
+    }
+
+    fn update_capture_keys(&mut self) {
+        let media = self.media.borrow();
+        if media.key_states().key_state(Key::F10) == KeyState::TransitionDown {
+            self.capture.request_screenshot();
+        }
+        if media.key_states().key_state(Key::F11) == KeyState::TransitionDown {
+            self.capture.toggle_recording();
+        }
+        if media.key_states().key_state(Key::Backquote) == KeyState::TransitionDown {
+            self.show_log = !self.show_log;
         }
     }
 
@@ -150,12 +193,16 @@ impl Game {
         }
     }
 
+    pub fn data<'a>(&'a self) -> &'a GameData {
+        &self.data
+    }
+
     pub fn game_dir<'a>(&'a self) -> &'a GameDir {
-        &self.game_dir
+        self.data.game_dir()
     }
 
     pub fn drs_manager(&self) -> DrsManagerRef {
-        self.drs_manager.clone()
+        self.data.drs_manager()
     }
 
     pub fn shape_manager(&self) -> ShapeManagerRef {
@@ -163,11 +210,15 @@ impl Game {
     }
 
     pub fn shape_metadata(&self) -> ShapeMetadataStoreRef {
-        self.shape_metadata.clone()
+        self.data.shape_metadata()
     }
 
     pub fn empires_db(&self) -> EmpiresDbRef {
-        self.empires.clone()
+        self.data.empires_db()
+    }
+
+    pub fn frame_capture<'a>(&'a mut self) -> &'a mut FrameCapture {
+        &mut self.capture
     }
 
     pub fn media(&self) -> MediaRef {
@@ -0,0 +1,98 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::{EmpiresDb, EmpiresDbRef};
+use resource::{DrsManager, DrsManagerRef, GameDir, ModOverrides, ShapeMetadataStore, ShapeMetadataStoreRef};
+use std::path::{Path, PathBuf};
+
+/// Game data needed to run the simulation. None of it depends on a window or
+/// renderer, so the same data is used by both the windowed and headless modes.
+pub struct GameData {
+    game_dir: GameDir,
+    drs_manager: DrsManagerRef,
+    shape_metadata: ShapeMetadataStoreRef,
+    empires: EmpiresDbRef,
+    empires_location: PathBuf,
+}
+
+impl GameData {
+    /// Mods enabled in the mods directory's manifest take precedence over the original game data.
+    /// Without a mods directory, only the original game data is loaded.
+    pub fn load(game_data_dir: &str, mods_dir: Option<&str>) -> GameData {
+        let game_dir = GameDir::new(game_data_dir).unwrap_or_else(|err| {
+            unrecoverable!("{}", err);
+        });
+        let overrides = match mods_dir {
+            Some(mods_dir) => {
+                ModOverrides::load(mods_dir).unwrap_or_else(|err| {
+                    unrecoverable!("Failed to load mods: {}", err);
+                })
+            }
+            None => ModOverrides::none(),
+        };
+
+        let drs_manager = DrsManager::new(&game_dir, &overrides);
+        if let Err(err) = drs_manager.borrow_mut().preload() {
+            unrecoverable!("Failed to preload DRS archives: {}", err);
+        }
+
+        let shape_metadata = ShapeMetadataStoreRef::new(ShapeMetadataStore::load(&*drs_manager.borrow()));
+
+        let empires_dat_location = overrides.find_data_file("data/empires.dat")
+            .unwrap_or_else(|| game_dir.find_file("data/empires.dat").unwrap());
+        let empires = EmpiresDbRef::new(EmpiresDb::read_from_file(&empires_dat_location)
+            .unwrap_or_else(|err| {
+                unrecoverable!("Failed to load empires.dat: {}", err);
+            }));
+        if !empires.is_complete() {
+            log_warn!("empires.dat is missing {:?}; continuing without them", empires.missing_sections());
+        }
+
+        GameData {
+            game_dir: game_dir,
+            drs_manager: drs_manager,
+            shape_metadata: shape_metadata,
+            empires: empires,
+            empires_location: empires_dat_location,
+        }
+    }
+
+    pub fn game_dir<'a>(&'a self) -> &'a GameDir {
+        &self.game_dir
+    }
+
+    pub fn drs_manager(&self) -> DrsManagerRef {
+        self.drs_manager.clone()
+    }
+
+    pub fn shape_metadata(&self) -> ShapeMetadataStoreRef {
+        self.shape_metadata.clone()
+    }
+
+    pub fn empires_db(&self) -> EmpiresDbRef {
+        self.empires.clone()
+    }
+
+    /// The empires.dat that was loaded (a mod's, if one overrides it)
+    pub fn empires_location<'a>(&'a self) -> &'a Path {
+        &self.empires_location
+    }
+}
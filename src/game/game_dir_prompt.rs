@@ -0,0 +1,111 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use resource::{GameDir, detect_game_dir, save_game_dir, saved_game_dir};
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the game data directory is looked for when it's not given, saved, or detected
+const DEFAULT_GAME_DATA_DIR: &'static str = "game";
+
+/// Figures out which game data directory to use. In order, that's the one asked for on the
+/// command line, the one saved from last time, a "game" directory in the working directory,
+/// one found in the usual install locations, or failing all that, one typed in at a prompt.
+/// Directories that were asked for, detected, or typed in are saved for next time.
+pub fn locate_game_data_dir(requested: Option<&str>) -> String {
+    let saved = saved_game_dir();
+    if let Some(requested) = requested {
+        // An invalid directory is still used so that loading it reports what's wrong with it
+        let requested_path = Path::new(requested);
+        if GameDir::is_game_dir(requested_path) {
+            let absolute = absolute_path(requested_path);
+            if saved.as_ref() != Some(&absolute) {
+                remember(&absolute);
+            }
+        }
+        return requested.to_string();
+    }
+
+    if let Some(saved) = saved {
+        if GameDir::is_game_dir(&saved) {
+            return saved.to_string_lossy().into_owned();
+        }
+        println!("The saved game data directory {:?} doesn't have the game data anymore", saved);
+    }
+
+    if GameDir::is_game_dir(DEFAULT_GAME_DATA_DIR) {
+        return DEFAULT_GAME_DATA_DIR.to_string();
+    }
+
+    if let Some(detected) = detect_game_dir() {
+        println!("Found the game data in {:?}", detected);
+        remember(&detected);
+        return detected.to_string_lossy().into_owned();
+    }
+
+    match prompt_for_game_data_dir() {
+        Some(dir) => {
+            remember(&dir);
+            dir.to_string_lossy().into_owned()
+        }
+        // Loading the default directory reports the error along with where to find the game data
+        None => DEFAULT_GAME_DATA_DIR.to_string(),
+    }
+}
+
+fn remember(dir: &Path) {
+    match save_game_dir(dir) {
+        Ok(file_name) => println!("Saved the game data directory in {:?}", file_name),
+        Err(err) => println!("Failed to save the game data directory: {}", err),
+    }
+}
+
+/// Asks for the game data directory until a valid one is given, or returns None if nothing is
+fn prompt_for_game_data_dir() -> Option<PathBuf> {
+    println!("Couldn't find the Age of Empires game data. It's the directory with language.dll and the \
+              data directory in it, which is the GAME directory on the game CD.");
+    let stdin = io::stdin();
+    loop {
+        print!("Game data directory (leave empty to quit): ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            return None;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if GameDir::is_game_dir(line) {
+            return Some(absolute_path(Path::new(line)));
+        }
+        println!("{:?} doesn't have the game data in it", line);
+    }
+}
+
+/// Relative directories are saved as absolute ones so that they still work when the game is
+/// run from somewhere else
+fn absolute_path(dir: &Path) -> PathBuf {
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
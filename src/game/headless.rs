@@ -0,0 +1,106 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::CommandLog;
+use ecs::{self, GameSettings, GameSetup, WorldPlanner};
+use ecs::resource::{ActionBatcher, Script, SimulationTick, Timeline};
+use scn;
+use std::io;
+use std::path::Path;
+use super::crash_report;
+use super::game_data::GameData;
+
+/// Seed used when neither the command log nor the command line provides one
+pub const DEFAULT_SEED: u64 = 0;
+
+/// Summary printed when a headless run finishes
+#[derive(Copy, Clone, Debug)]
+pub struct HeadlessSummary {
+    pub ticks_run: u64,
+    pub state_hash: u64,
+}
+
+/// The seed and settings a game was recorded with
+pub fn recorded_setup(commands: &CommandLog) -> GameSetup {
+    let mut setup = GameSetup::new(commands.seed().unwrap_or(DEFAULT_SEED));
+    if let Some(settings) = commands.settings() {
+        setup.settings = GameSettings::parse(settings).unwrap_or_else(|err| {
+            unrecoverable!("Invalid game settings in the command log: {}", err);
+        });
+    }
+    setup
+}
+
+/// Runs the simulation without a window, renderer, or audio, as fast as possible.
+/// The only input comes from a command log (a hand written script or a recorded replay).
+pub struct HeadlessGame {
+    planner: WorldPlanner,
+}
+
+impl HeadlessGame {
+    /// The simulation is seeded from the command log so that recorded games replay faithfully
+    /// (as long as they're given the same scripts that the game was played with)
+    pub fn new(data: &GameData,
+               scenario: &scn::Scenario,
+               commands: CommandLog,
+               scripts: &[Script])
+               -> HeadlessGame {
+        let mut setup = recorded_setup(&commands);
+        setup.scripts = scripts.to_vec();
+        let mut planner =
+            ecs::create_headless_world_planner(data.empires_db(), data.shape_metadata(), scenario, &setup);
+        planner.mut_world().write_resource::<ActionBatcher>().play_back(commands);
+        HeadlessGame { planner: planner }
+    }
+
+    pub fn run(&mut self, tick_count: u64) -> HeadlessSummary {
+        for _ in 0..tick_count {
+            self.run_tick();
+        }
+        self.summary()
+    }
+
+    /// Runs a single tick and returns which one it was
+    pub fn run_tick(&mut self) -> u64 {
+        ecs::update_world(&mut self.planner, ecs::time_step_seconds());
+        let world = self.planner.mut_world();
+        crash_report::record_tick(world);
+        world.read_resource::<SimulationTick>().tick
+    }
+
+    pub fn planner(&mut self) -> &mut WorldPlanner {
+        &mut self.planner
+    }
+
+    /// Writes the per-minute samples of every player to the given file as JSON
+    pub fn write_timeline<P: AsRef<Path>>(&mut self, file_name: P) -> io::Result<()> {
+        self.planner.mut_world().read_resource::<Timeline>().write_to_file(file_name)
+    }
+
+    pub fn summary(&mut self) -> HeadlessSummary {
+        let world = self.planner.mut_world();
+        let ticks_run = world.read_resource::<SimulationTick>().tick;
+        HeadlessSummary {
+            ticks_run: ticks_run,
+            state_hash: ecs::state_hash(world),
+        }
+    }
+}
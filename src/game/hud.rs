@@ -0,0 +1,810 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use dat::ResourceType;
+use ecs::resource::{Announcements, BuildMenu, Messages, PlayerTechs, Players, Scores, Stockpiles, Timeline};
+use game::editor_toolbar::{self, EditorToolbar};
+use game::keyboard_cursor::KeyboardCursor;
+use game::pause_menu::{PauseMenu, PauseMenuItem};
+use game::tech_tree::{self, TechNode, TechTree};
+use identifier::PlayerId;
+use logging::{Level, Record};
+use media::{Controller, Renderer};
+use media::font;
+use nalgebra::Vector2;
+use resource::PlayerColorScheme;
+use std::cmp;
+use types::{Color, Rect};
+
+const ANNOUNCEMENT_SCALE: u32 = 2;
+const ANNOUNCEMENT_TOP: i32 = 48;
+const ANNOUNCEMENT_LINE_SPACING: i32 = 6;
+
+const STOCKPILE_SCALE: u32 = 2;
+const STOCKPILE_MARGIN: i32 = 8;
+const STOCKPILE_LINE_SPACING: i32 = 4;
+
+const SCORE_SCALE: u32 = 2;
+const SCORE_MARGIN: i32 = 8;
+const SCORE_LINE_SPACING: i32 = 4;
+
+/// Everyone's scores show up once a player learns Writing
+const WRITING_RESEARCH_ID: usize = 114;
+
+const TOOLBAR_SCALE: u32 = 1;
+const TOOLBAR_PADDING: i32 = 8;
+
+const RADIAL_MENU_RADIUS: f32 = 96.0;
+const RADIAL_MENU_SCALE: u32 = 2;
+const CONTROLLER_CURSOR_SIZE: i32 = 8;
+
+const COMMAND_PANEL_SCALE: u32 = 2;
+const COMMAND_PANEL_LINE_SPACING: i32 = 4;
+
+const LOG_SCALE: u32 = 1;
+const LOG_LINE_SPACING: i32 = 2;
+
+const TIMELINE_WIDTH: i32 = 480;
+const TIMELINE_HEIGHT: i32 = 240;
+const TIMELINE_PADDING: i32 = 12;
+const TIMELINE_TEXT_SCALE: u32 = 1;
+
+const MESSAGES_WIDTH: i32 = 560;
+const MESSAGES_PADDING: i32 = 12;
+const MESSAGES_SCALE: u32 = 1;
+const MESSAGES_LINE_SPACING: i32 = 3;
+
+/// How many of the latest messages the panel lists
+const MESSAGE_LOG_LINES: usize = 8;
+
+const PAUSE_MENU_WIDTH: i32 = 360;
+const PAUSE_MENU_PADDING: i32 = 12;
+const PAUSE_MENU_SCALE: u32 = 2;
+const PAUSE_MENU_LINE_SPACING: i32 = 6;
+
+/// Most saves listed at once; the list scrolls to keep the selected one in view
+const PAUSE_MENU_SAVES_SHOWN: usize = 10;
+
+const WAITING_SCALE: u32 = 2;
+const WAITING_TOP: i32 = 160;
+const WAITING_PADDING: i32 = 12;
+const WAITING_LINE_SPACING: i32 = 6;
+
+const BUILD_MENU_WIDTH: i32 = 280;
+const BUILD_MENU_MARGIN: i32 = 64;
+
+const TECH_TREE_SCALE: u32 = 1;
+const TECH_TREE_PADDING: i32 = 8;
+const TECH_TREE_DETAILS_WIDTH: i32 = 320;
+const TECH_TREE_ARROW_SIZE: i32 = 4;
+
+/// Draws the announcements centered near the top of the screen, newest at the bottom
+pub fn render_announcements(renderer: &mut Renderer, announcements: &Announcements) {
+    let screen_width = renderer.viewport_size().x as i32;
+    let line_height = (font::GLYPH_HEIGHT * ANNOUNCEMENT_SCALE) as i32 + ANNOUNCEMENT_LINE_SPACING;
+
+    let mut y = ANNOUNCEMENT_TOP;
+    for announcement in announcements.active() {
+        let width = font::text_width(&announcement.text, ANNOUNCEMENT_SCALE) as i32;
+        let x = (screen_width - width) / 2;
+        render_shadowed_text(renderer, &announcement.text, x, y, ANNOUNCEMENT_SCALE);
+        y += line_height;
+    }
+}
+
+/// Draws how much of each resource the local player has in the top left corner. Observers get a
+/// line for every player instead.
+pub fn render_stockpile(renderer: &mut Renderer, stockpiles: &Stockpiles, players: &Players) {
+    if !players.is_observing() {
+        let amounts = stockpile_amounts(stockpiles, players.local_player_id());
+        render_shadowed_text(renderer, &amounts, STOCKPILE_MARGIN, STOCKPILE_MARGIN, STOCKPILE_SCALE);
+        return;
+    }
+
+    let line_height = (font::GLYPH_HEIGHT * STOCKPILE_SCALE) as i32 + STOCKPILE_LINE_SPACING;
+    let mut y = STOCKPILE_MARGIN;
+    for player_id in players.player_ids().into_iter().filter(|player_id| **player_id != 0) {
+        let line = format!("{}: {}", players.name(player_id), stockpile_amounts(stockpiles, player_id));
+        render_shadowed_text(renderer, &line, STOCKPILE_MARGIN, y, STOCKPILE_SCALE);
+        y += line_height;
+    }
+}
+
+fn stockpile_amounts(stockpiles: &Stockpiles, player_id: PlayerId) -> String {
+    let amounts: Vec<String> = [("Food", ResourceType::Food),
+                                ("Wood", ResourceType::Wood),
+                                ("Gold", ResourceType::Gold),
+                                ("Stone", ResourceType::Stone)]
+        .iter()
+        .map(|&(name, resource_type)| {
+            format!("{}: {}", name, i32::from(stockpiles.amount(player_id, resource_type)))
+        })
+        .collect();
+    amounts.join("   ")
+}
+
+/// Draws the local player's score and rank in the top right corner. Observers, and players who
+/// have researched Writing, get everyone's scores listed underneath.
+pub fn render_scores(renderer: &mut Renderer, scores: &Scores, players: &Players, techs: &PlayerTechs) {
+    let local_player_id = players.local_player_id();
+    let ranking = scores.ranking();
+
+    let mut lines = Vec::new();
+    if !players.is_observing() {
+        if let Some(rank) = scores.rank(local_player_id) {
+            lines.push(format!("Score: {} ({} of {})",
+                               scores.score(local_player_id).total(),
+                               rank,
+                               ranking.len()));
+        }
+    }
+    if players.is_observing() || techs.has_researched(local_player_id, WRITING_RESEARCH_ID.into()) {
+        for &(player_id, total) in &ranking {
+            lines.push(format!("{}: {}", players.name(player_id), total));
+        }
+    }
+
+    let screen_width = renderer.viewport_size().x as i32;
+    let line_height = (font::GLYPH_HEIGHT * SCORE_SCALE) as i32 + SCORE_LINE_SPACING;
+    let mut y = SCORE_MARGIN;
+    for line in &lines {
+        let x = screen_width - SCORE_MARGIN - font::text_width(line, SCORE_SCALE) as i32;
+        render_shadowed_text(renderer, line, x, y, SCORE_SCALE);
+        y += line_height;
+    }
+}
+
+/// Graphs everyone's score over the course of the game in the middle of the screen
+pub fn render_timeline(renderer: &mut Renderer,
+                       timeline: &Timeline,
+                       players: &Players,
+                       player_color_scheme: PlayerColorScheme) {
+    let screen_size = renderer.viewport_size();
+    let panel = Rect::of((screen_size.x as i32 - TIMELINE_WIDTH) / 2,
+                         (screen_size.y as i32 - TIMELINE_HEIGHT) / 2,
+                         TIMELINE_WIDTH,
+                         TIMELINE_HEIGHT);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    let title = "Score over time";
+    let title_height = (font::GLYPH_HEIGHT * TIMELINE_TEXT_SCALE) as i32 + TIMELINE_PADDING;
+    render_shadowed_text(renderer,
+                         title,
+                         panel.x + TIMELINE_PADDING,
+                         panel.y + TIMELINE_PADDING,
+                         TIMELINE_TEXT_SCALE);
+
+    let player_ids: Vec<PlayerId> = timeline.player_ids();
+    let legend_width = player_ids.iter()
+        .map(|&player_id| font::text_width(&players.name(player_id), TIMELINE_TEXT_SCALE) as i32)
+        .max()
+        .unwrap_or(0) + TIMELINE_PADDING;
+    let graph = Rect::of(panel.x + TIMELINE_PADDING,
+                         panel.y + TIMELINE_PADDING + title_height,
+                         panel.w - 3 * TIMELINE_PADDING - legend_width,
+                         panel.h - 2 * TIMELINE_PADDING - title_height);
+    renderer.set_render_color(Color::rgb(128, 128, 128));
+    renderer.render_screen_line(Vector2::new(graph.x, graph.y), Vector2::new(graph.x, graph.y + graph.h));
+    renderer.render_screen_line(Vector2::new(graph.x, graph.y + graph.h),
+                                Vector2::new(graph.x + graph.w, graph.y + graph.h));
+
+    let sample_count = timeline.samples().len() as i32;
+    let highest_score = player_ids.iter()
+        .flat_map(|&player_id| timeline.player_samples(player_id).into_iter().map(|sample| sample.score))
+        .max()
+        .unwrap_or(0);
+    let point = |index: i32, score: u32| {
+        let x = graph.x + graph.w * index / cmp::max(1, sample_count - 1);
+        let y = graph.y + graph.h - graph.h * score as i32 / cmp::max(1, highest_score as i32);
+        Vector2::new(x, y)
+    };
+
+    let line_height = (font::GLYPH_HEIGHT * TIMELINE_TEXT_SCALE) as i32 + TIMELINE_PADDING / 2;
+    for (row, &player_id) in player_ids.iter().enumerate() {
+        let color = players.player(player_id)
+            .map(|player| {
+                let (r, g, b) = player_color_scheme.color(*player.player_color_id as usize);
+                Color::rgb(r, g, b)
+            })
+            .unwrap_or(Color::rgb(255, 255, 255));
+
+        let samples = timeline.player_samples(player_id);
+        renderer.set_render_color(color);
+        for index in 1..samples.len() {
+            renderer.render_screen_line(point(index as i32 - 1, samples[index - 1].score),
+                                        point(index as i32, samples[index].score));
+        }
+
+        let legend_x = graph.x + graph.w + TIMELINE_PADDING;
+        renderer.render_text(&players.name(player_id),
+                             &Vector2::new(legend_x, graph.y + row as i32 * line_height),
+                             TIMELINE_TEXT_SCALE,
+                             color);
+    }
+}
+
+/// The objectives panel in the middle of the screen: the objectives the scripts have set, the
+/// scenario's instructions, hints, and history, and the latest messages
+pub fn render_messages(renderer: &mut Renderer, messages: &Messages) {
+    let text_width = MESSAGES_WIDTH - 2 * MESSAGES_PADDING;
+    let heading = Color::rgb(255, 220, 0);
+    let mut lines: Vec<(String, Color)> = Vec::new();
+    {
+        let mut section = |title: &str, text_lines: Vec<String>| {
+            if text_lines.is_empty() {
+                return;
+            }
+            if !lines.is_empty() {
+                lines.push((String::new(), heading));
+            }
+            lines.push((title.to_string(), heading));
+            for line in text_lines {
+                lines.push((line, Color::rgb(255, 255, 255)));
+            }
+        };
+
+        section("Objectives",
+                messages.objectives()
+                    .iter()
+                    .flat_map(|objective| {
+                        let mark = if objective.complete { "[x]" } else { "[ ]" };
+                        wrap_text(&format!("{} {}", mark, objective.text), text_width, MESSAGES_SCALE)
+                    })
+                    .collect());
+        section("Instructions", wrap_text(messages.instructions(), text_width, MESSAGES_SCALE));
+        section("Hints", wrap_text(messages.hints(), text_width, MESSAGES_SCALE));
+        section("History", wrap_text(messages.history(), text_width, MESSAGES_SCALE));
+
+        let log = messages.log();
+        section("Messages",
+                log[log.len() - cmp::min(log.len(), MESSAGE_LOG_LINES)..]
+                    .iter()
+                    .flat_map(|message| {
+                        let text = format!("{}:{:02} {}",
+                                           message.seconds / 60,
+                                           message.seconds % 60,
+                                           message.text);
+                        wrap_text(&text, text_width, MESSAGES_SCALE)
+                    })
+                    .collect());
+    }
+    if lines.is_empty() {
+        let text = "This scenario has no objectives or instructions";
+        lines.push((text.to_string(), Color::rgb(255, 255, 255)));
+    }
+    lines.push((String::new(), heading));
+    lines.push(("Press O to close".to_string(), Color::rgb(160, 160, 160)));
+
+    // Whatever doesn't fit on the screen is cut off at the bottom
+    let screen_size = renderer.viewport_size();
+    let line_height = (font::GLYPH_HEIGHT * MESSAGES_SCALE) as i32 + MESSAGES_LINE_SPACING;
+    let height = cmp::min(lines.len() as i32 * line_height + 2 * MESSAGES_PADDING,
+                          screen_size.y as i32 - 2 * MESSAGES_PADDING);
+    let panel = Rect::of((screen_size.x as i32 - MESSAGES_WIDTH) / 2,
+                         (screen_size.y as i32 - height) / 2,
+                         MESSAGES_WIDTH,
+                         height);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    let visible_lines = ((panel.h - 2 * MESSAGES_PADDING) / line_height) as usize;
+    for (row, &(ref line, color)) in lines.iter().take(visible_lines).enumerate() {
+        renderer.render_text(line,
+                             &Vector2::new(panel.x + MESSAGES_PADDING,
+                                           panel.y + MESSAGES_PADDING + row as i32 * line_height),
+                             MESSAGES_SCALE,
+                             color);
+    }
+}
+
+/// Draws the pause menu in the middle of the screen, with the selected item highlighted and
+/// the items that can't be picked right now greyed out (or the saves to load, once Load game
+/// has been picked)
+pub fn render_pause_menu(renderer: &mut Renderer, menu: &PauseMenu, paused: bool) {
+    let heading = Color::rgb(255, 220, 0);
+    let hint = Color::rgb(160, 160, 160);
+    let mut selected_row = 0;
+    let lines = match menu.saves() {
+        Some(saves) => {
+            let mut lines = vec![("Load game".to_string(), heading), (String::new(), heading)];
+            let first = (menu.selected_save() + 1).saturating_sub(PAUSE_MENU_SAVES_SHOWN);
+            for (index, save) in saves.iter().enumerate().skip(first).take(PAUSE_MENU_SAVES_SHOWN) {
+                if index == menu.selected_save() {
+                    selected_row = lines.len();
+                }
+                lines.push((save.clone(), Color::rgb(255, 255, 255)));
+            }
+            lines.push((String::new(), heading));
+            lines.push(("Enter loads, Esc goes back".to_string(), hint));
+            lines
+        }
+        None => pause_menu_lines(menu, paused, &mut selected_row),
+    };
+
+    let screen_size = renderer.viewport_size();
+    let line_height = (font::GLYPH_HEIGHT * PAUSE_MENU_SCALE) as i32 + PAUSE_MENU_LINE_SPACING;
+    let height = lines.len() as i32 * line_height + 2 * PAUSE_MENU_PADDING;
+    let panel = Rect::of((screen_size.x as i32 - PAUSE_MENU_WIDTH) / 2,
+                         (screen_size.y as i32 - height) / 2,
+                         PAUSE_MENU_WIDTH,
+                         height);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    let selected_y = panel.y + PAUSE_MENU_PADDING + selected_row as i32 * line_height;
+    renderer.set_render_color(Color::rgb(64, 64, 96));
+    renderer.fill_screen_rect(Rect::of(panel.x + PAUSE_MENU_PADDING / 2,
+                                       selected_y - PAUSE_MENU_LINE_SPACING / 2,
+                                       panel.w - PAUSE_MENU_PADDING,
+                                       line_height));
+
+    for (row, &(ref line, color)) in lines.iter().enumerate() {
+        renderer.render_text(line,
+                             &Vector2::new(panel.x + PAUSE_MENU_PADDING,
+                                           panel.y + PAUSE_MENU_PADDING + row as i32 * line_height),
+                             PAUSE_MENU_SCALE,
+                             color);
+    }
+}
+
+/// The pause menu's own items, with the options' current values and what to do next
+fn pause_menu_lines(menu: &PauseMenu, paused: bool, selected_row: &mut usize) -> Vec<(String, Color)> {
+    let heading = Color::rgb(255, 220, 0);
+    let hint = Color::rgb(160, 160, 160);
+    let title = if paused { "Paused" } else { "Menu" };
+    let mut lines = vec![(title.to_string(), heading), (String::new(), heading)];
+    for &item in menu.items() {
+        if item == menu.selected_item() {
+            *selected_row = lines.len();
+        }
+        let label = match item {
+            PauseMenuItem::ScrollSpeed => format!("{}: {}%", item.label(), menu.scroll_speed_percent()),
+            _ => item.label().to_string(),
+        };
+        let color = if menu.is_enabled(item) {
+            Color::rgb(255, 255, 255)
+        } else {
+            Color::rgb(96, 96, 96)
+        };
+        lines.push((label, color));
+    }
+    lines.push((String::new(), heading));
+    if let Some(status) = menu.status() {
+        lines.push((status.to_string(), heading));
+    }
+    lines.push(("Enter picks, Esc resumes".to_string(), hint));
+    if menu.selected_item() == PauseMenuItem::ScrollSpeed {
+        lines.push(("Left and Right change it".to_string(), hint));
+    }
+    lines
+}
+
+/// Draws the open build menu (each building next to the letter that picks it) in the lower left
+/// corner, or a reminder to pick a spot for the building that's being placed
+pub fn render_build_menu(renderer: &mut Renderer, menu: &BuildMenu) {
+    let heading = Color::rgb(255, 220, 0);
+    let hint = Color::rgb(160, 160, 160);
+    let mut lines = Vec::new();
+    match menu.page() {
+        Some(page) => {
+            lines.push((page.title().to_string(), heading));
+            lines.push((String::new(), heading));
+            for option in menu.options() {
+                let label = match option.hotkey {
+                    Some(letter) => format!("{}  {}", letter, option.name),
+                    None => format!("   {}", option.name),
+                };
+                lines.push((label, Color::rgb(255, 255, 255)));
+            }
+            lines.push((String::new(), heading));
+            lines.push(("Esc cancels".to_string(), hint));
+        }
+        None => {
+            lines.push(("Click where it goes".to_string(), heading));
+            lines.push(("Right-click or Esc cancels".to_string(), hint));
+        }
+    }
+
+    let screen_size = renderer.viewport_size();
+    let line_height = (font::GLYPH_HEIGHT * PAUSE_MENU_SCALE) as i32 + PAUSE_MENU_LINE_SPACING;
+    let height = lines.len() as i32 * line_height + 2 * PAUSE_MENU_PADDING;
+    let panel = Rect::of(BUILD_MENU_MARGIN,
+                         screen_size.y as i32 - BUILD_MENU_MARGIN - height,
+                         BUILD_MENU_WIDTH,
+                         height);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    for (row, &(ref line, color)) in lines.iter().enumerate() {
+        renderer.render_text(line,
+                             &Vector2::new(panel.x + PAUSE_MENU_PADDING,
+                                           panel.y + PAUSE_MENU_PADDING + row as i32 * line_height),
+                             PAUSE_MENU_SCALE,
+                             color);
+    }
+}
+
+/// Tells the player there's something new in the objectives panel while it's closed
+pub fn render_unread_messages(renderer: &mut Renderer) {
+    let text = "New objectives (press O)";
+    let screen_size = renderer.viewport_size();
+    let line_height = (font::GLYPH_HEIGHT * SCORE_SCALE) as i32 + SCORE_LINE_SPACING;
+    render_shadowed_text(renderer,
+                         text,
+                         (screen_size.x as i32 - font::text_width(text, SCORE_SCALE) as i32) / 2,
+                         screen_size.y as i32 - SCORE_MARGIN - line_height,
+                         SCORE_SCALE);
+}
+
+/// Asks for the second press of the delete key that deletes the selected buildings
+pub fn render_delete_confirmation(renderer: &mut Renderer) {
+    let text = "Press Delete again to delete the selected buildings";
+    let screen_size = renderer.viewport_size();
+    let line_height = (font::GLYPH_HEIGHT * SCORE_SCALE) as i32 + SCORE_LINE_SPACING;
+    render_shadowed_text(renderer,
+                         text,
+                         (screen_size.x as i32 - font::text_width(text, SCORE_SCALE) as i32) / 2,
+                         screen_size.y as i32 - SCORE_MARGIN - 2 * line_height,
+                         SCORE_SCALE);
+}
+
+/// Draws the dialog a LAN game shows while it's held up on someone, centered below the
+/// announcements
+pub fn render_waiting(renderer: &mut Renderer, lines: &[String]) {
+    let screen_width = renderer.viewport_size().x as i32;
+    let line_height = (font::GLYPH_HEIGHT * WAITING_SCALE) as i32 + WAITING_LINE_SPACING;
+    let width = lines.iter().map(|line| font::text_width(line, WAITING_SCALE) as i32).max().unwrap_or(0);
+    let panel = Rect::of((screen_width - width) / 2 - WAITING_PADDING,
+                         WAITING_TOP,
+                         width + 2 * WAITING_PADDING,
+                         lines.len() as i32 * line_height + 2 * WAITING_PADDING);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    for (row, line) in lines.iter().enumerate() {
+        let x = (screen_width - font::text_width(line, WAITING_SCALE) as i32) / 2;
+        renderer.render_text(line,
+                             &Vector2::new(x, panel.y + WAITING_PADDING + row as i32 * line_height),
+                             WAITING_SCALE,
+                             Color::rgb(255, 255, 255));
+    }
+}
+
+/// Covers the screen with the tech tree: researched nodes are green, and what the player can never
+/// get is greyed out. The selected node's cost and effects are listed in the bottom right.
+pub fn render_tech_tree(renderer: &mut Renderer, tree: &TechTree, techs: &PlayerTechs, player_id: PlayerId) {
+    let screen_size = renderer.viewport_size();
+    let (screen_width, screen_height) = (screen_size.x as i32, screen_size.y as i32);
+    renderer.set_render_color(Color::rgb(16, 16, 16));
+    renderer.fill_screen_rect(Rect::of(0, 0, screen_width, screen_height));
+
+    for node in tree.nodes() {
+        let rect = tree.node_rect(node);
+        let color = if node.available {
+            Color::rgb(192, 192, 192)
+        } else {
+            Color::rgb(72, 72, 72)
+        };
+        renderer.set_render_color(color);
+        for required in node.required.iter().filter_map(|&required_id| tree.node(required_id)) {
+            let from_rect = tree.node_rect(required);
+            let from = Vector2::new(from_rect.x + from_rect.w, from_rect.y + from_rect.h / 2);
+            let to = Vector2::new(rect.x, rect.y + rect.h / 2);
+            renderer.render_screen_line(from, to);
+            for &offset in &[-TECH_TREE_ARROW_SIZE, TECH_TREE_ARROW_SIZE] {
+                renderer.render_screen_line(Vector2::new(to.x - TECH_TREE_ARROW_SIZE, to.y + offset), to);
+            }
+        }
+    }
+
+    let selected_id = tree.selected().map(|node| node.research_id);
+    let text_height = (font::GLYPH_HEIGHT * TECH_TREE_SCALE) as i32;
+    for node in tree.nodes() {
+        let rect = tree.node_rect(node);
+        if selected_id == Some(node.research_id) {
+            renderer.set_render_color(Color::rgb(255, 220, 0));
+            renderer.fill_screen_rect(Rect::of(rect.x - 1, rect.y - 1, rect.w + 2, rect.h + 2));
+        }
+        let (background, text_color) = if techs.has_researched(player_id, node.research_id) {
+            (Color::rgb(40, 96, 40), Color::rgb(255, 255, 255))
+        } else if node.available {
+            (Color::rgb(48, 48, 72), Color::rgb(255, 255, 255))
+        } else {
+            (Color::rgb(32, 32, 32), Color::rgb(110, 110, 110))
+        };
+        renderer.set_render_color(background);
+        renderer.fill_screen_rect(rect);
+
+        let name = fit_text(&node.name, tech_tree::NODE_WIDTH - TECH_TREE_PADDING, TECH_TREE_SCALE);
+        renderer.render_text(&name,
+                             &Vector2::new(rect.x + TECH_TREE_PADDING / 2,
+                                           rect.y + (tech_tree::NODE_HEIGHT - text_height) / 2),
+                             TECH_TREE_SCALE,
+                             text_color);
+    }
+
+    renderer.set_render_color(Color::rgb(16, 16, 16));
+    renderer.fill_screen_rect(Rect::of(0, 0, screen_width, 2 * TECH_TREE_PADDING + text_height));
+    render_shadowed_text(renderer,
+                         &format!("Tech tree: {} (T to close)", tree.civilization_name()),
+                         TECH_TREE_PADDING,
+                         TECH_TREE_PADDING,
+                         TECH_TREE_SCALE);
+
+    if let Some(node) = tree.selected() {
+        render_tech_details(renderer, tree, node, screen_width, screen_height);
+    }
+}
+
+fn render_tech_details(renderer: &mut Renderer,
+                       tree: &TechTree,
+                       node: &TechNode,
+                       screen_width: i32,
+                       screen_height: i32) {
+    let mut lines = vec![node.name.clone()];
+    if !node.costs.is_empty() {
+        let costs: Vec<String> = node.costs
+            .iter()
+            .map(|&(resource_type, amount)| format!("{} {:?}", amount, resource_type))
+            .collect();
+        lines.push(format!("Cost: {}", costs.join(", ")));
+    }
+    lines.push(format!("Time: {}s", node.time_seconds));
+    let required: Vec<&str> = node.required
+        .iter()
+        .filter_map(|&required_id| tree.node(required_id))
+        .map(|required| &required.name[..])
+        .collect();
+    if !required.is_empty() {
+        lines.push(format!("Requires: {}", required.join(", ")));
+    }
+    if node.disabled {
+        lines.push("Not available to this civilization".to_string());
+    } else if !node.available {
+        lines.push("Requires research this civilization can't do".to_string());
+    }
+    lines.extend(node.effects.iter().cloned());
+
+    let line_height = (font::GLYPH_HEIGHT * TECH_TREE_SCALE) as i32 + TECH_TREE_PADDING / 2;
+    let panel = Rect::of(screen_width - TECH_TREE_DETAILS_WIDTH,
+                         screen_height - lines.len() as i32 * line_height - 2 * TECH_TREE_PADDING,
+                         TECH_TREE_DETAILS_WIDTH,
+                         lines.len() as i32 * line_height + 2 * TECH_TREE_PADDING);
+    renderer.set_render_color(Color::rgb(40, 40, 40));
+    renderer.fill_screen_rect(panel);
+    for (row, line) in lines.iter().enumerate() {
+        let line = fit_text(line, TECH_TREE_DETAILS_WIDTH - 2 * TECH_TREE_PADDING, TECH_TREE_SCALE);
+        render_shadowed_text(renderer,
+                             &line,
+                             panel.x + TECH_TREE_PADDING,
+                             panel.y + TECH_TREE_PADDING + row as i32 * line_height,
+                             TECH_TREE_SCALE);
+    }
+}
+
+/// Draws the editor's buttons along the bottom left of the screen, and lists the current tool and
+/// options in a panel just above them
+pub fn render_editor_toolbar(renderer: &mut Renderer, toolbar: &EditorToolbar, lines: &[String]) {
+    let screen_height = renderer.viewport_size().y as i32;
+    for (index, &button) in toolbar.buttons().iter().enumerate() {
+        let rect = toolbar.button_rect(index, screen_height);
+        let (fill, text) = if !toolbar.is_enabled(button) {
+            (Color::rgb(24, 24, 24), Color::rgb(96, 96, 96))
+        } else if toolbar.is_selected(button) {
+            (Color::rgb(64, 64, 96), Color::rgb(255, 220, 0))
+        } else {
+            (Color::rgb(40, 40, 40), Color::rgb(255, 255, 255))
+        };
+        renderer.set_render_color(fill);
+        renderer.fill_screen_rect(rect);
+        let label = button.label();
+        let label_width = font::text_width(label, TOOLBAR_SCALE) as i32;
+        let label_height = (font::GLYPH_HEIGHT * TOOLBAR_SCALE) as i32;
+        renderer.render_text(label,
+                             &Vector2::new(rect.x + (rect.w - label_width) / 2,
+                                           rect.y + (rect.h - label_height) / 2),
+                             TOOLBAR_SCALE,
+                             text);
+    }
+
+    let line_height = (font::GLYPH_HEIGHT * TOOLBAR_SCALE) as i32 + TOOLBAR_PADDING / 2;
+    let width = lines.iter().map(|line| font::text_width(line, TOOLBAR_SCALE) as i32).max().unwrap_or(0);
+    let height = lines.len() as i32 * line_height;
+
+    let bottom = screen_height - 2 * editor_toolbar::MARGIN - editor_toolbar::BUTTON_HEIGHT;
+    let panel = Rect::of(0,
+                         bottom - height - 2 * TOOLBAR_PADDING,
+                         width + 2 * TOOLBAR_PADDING,
+                         height + 2 * TOOLBAR_PADDING);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    for (row, line) in lines.iter().enumerate() {
+        render_shadowed_text(renderer,
+                             line,
+                             panel.x + TOOLBAR_PADDING,
+                             panel.y + TOOLBAR_PADDING + row as i32 * line_height,
+                             TOOLBAR_SCALE);
+    }
+}
+
+/// Draws the controller's radial command menu around the cursor while it's open, or a crosshair
+/// where the cursor is while the controller is moving it (since the system's cursor doesn't follow)
+pub fn render_controller(renderer: &mut Renderer, controller: &Controller, cursor: Vector2<i32>) {
+    use std::f32::consts::PI;
+
+    let menu = match controller.menu() {
+        Some(menu) => menu,
+        None => {
+            if controller.cursor_moved() {
+                renderer.set_render_color(Color::rgb(255, 255, 255));
+                renderer.render_screen_line(Vector2::new(cursor.x - CONTROLLER_CURSOR_SIZE, cursor.y),
+                                            Vector2::new(cursor.x + CONTROLLER_CURSOR_SIZE, cursor.y));
+                renderer.render_screen_line(Vector2::new(cursor.x, cursor.y - CONTROLLER_CURSOR_SIZE),
+                                            Vector2::new(cursor.x, cursor.y + CONTROLLER_CURSOR_SIZE));
+            }
+            return;
+        }
+    };
+
+    // Items go clockwise from the top, the same way the stick picks them
+    let slice = 2.0 * PI / menu.items().len() as f32;
+    let text_height = (font::GLYPH_HEIGHT * RADIAL_MENU_SCALE) as i32;
+    for (index, item) in menu.items().iter().enumerate() {
+        let angle = index as f32 * slice;
+        let width = font::text_width(item.label, RADIAL_MENU_SCALE) as i32;
+        let x = cursor.x + (angle.sin() * RADIAL_MENU_RADIUS) as i32 - width / 2;
+        let y = cursor.y - (angle.cos() * RADIAL_MENU_RADIUS) as i32 - text_height / 2;
+
+        let color = if menu.highlighted() == Some(index) {
+            Color::rgb(255, 220, 0)
+        } else {
+            Color::rgb(255, 255, 255)
+        };
+        renderer.set_render_color(Color::rgb(24, 24, 24));
+        renderer.fill_screen_rect(Rect::of(x - TOOLBAR_PADDING / 2,
+                                           y - TOOLBAR_PADDING / 2,
+                                           width + TOOLBAR_PADDING,
+                                           text_height + TOOLBAR_PADDING));
+        renderer.render_text(item.label, &Vector2::new(x, y), RADIAL_MENU_SCALE, color);
+    }
+}
+
+/// Draws the keyboard cursor, and the command panel beside it while it's open
+pub fn render_keyboard_cursor(renderer: &mut Renderer, cursor: &KeyboardCursor) {
+    let position = cursor.position();
+    renderer.set_render_color(Color::rgb(255, 255, 255));
+    renderer.render_screen_line(Vector2::new(position.x - CONTROLLER_CURSOR_SIZE, position.y),
+                                Vector2::new(position.x + CONTROLLER_CURSOR_SIZE, position.y));
+    renderer.render_screen_line(Vector2::new(position.x, position.y - CONTROLLER_CURSOR_SIZE),
+                                Vector2::new(position.x, position.y + CONTROLLER_CURSOR_SIZE));
+
+    let (items, highlighted) = match cursor.panel() {
+        Some(panel) => panel,
+        None => return,
+    };
+    let line_height = (font::GLYPH_HEIGHT * COMMAND_PANEL_SCALE) as i32 + COMMAND_PANEL_LINE_SPACING;
+    let width = items.iter()
+        .map(|item| font::text_width(item.label, COMMAND_PANEL_SCALE) as i32)
+        .max()
+        .unwrap_or(0);
+    let screen_size = renderer.viewport_size();
+    let panel_width = width + 2 * TOOLBAR_PADDING;
+    let panel_height = items.len() as i32 * line_height + 2 * TOOLBAR_PADDING;
+
+    // Beside the cursor, but kept on screen
+    let left = cmp::min(position.x + 2 * CONTROLLER_CURSOR_SIZE, screen_size.x as i32 - panel_width);
+    let top = cmp::min(position.y, screen_size.y as i32 - panel_height);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(Rect::of(left, top, panel_width, panel_height));
+
+    for (index, item) in items.iter().enumerate() {
+        let color = if index == highlighted {
+            Color::rgb(255, 220, 0)
+        } else {
+            Color::rgb(255, 255, 255)
+        };
+        renderer.render_text(item.label,
+                             &Vector2::new(left + TOOLBAR_PADDING,
+                                           top + TOOLBAR_PADDING + index as i32 * line_height),
+                             COMMAND_PANEL_SCALE,
+                             color);
+    }
+}
+
+/// Draws the given log records over the top left of the screen, oldest first
+pub fn render_log(renderer: &mut Renderer, records: &[Record]) {
+    let line_height = (font::GLYPH_HEIGHT * LOG_SCALE) as i32 + LOG_LINE_SPACING;
+    let lines: Vec<String> = records.iter().map(|record| record.to_string()).collect();
+    let width = lines.iter().map(|line| font::text_width(line, LOG_SCALE) as i32).max().unwrap_or(0);
+
+    let panel = Rect::of(0,
+                         0,
+                         width + 2 * TOOLBAR_PADDING,
+                         lines.len() as i32 * line_height + 2 * TOOLBAR_PADDING);
+    renderer.set_render_color(Color::rgb(24, 24, 24));
+    renderer.fill_screen_rect(panel);
+
+    for (row, (record, line)) in records.iter().zip(lines.iter()).enumerate() {
+        let color = match record.level {
+            Level::Error => Color::rgb(255, 80, 80),
+            Level::Warn => Color::rgb(255, 220, 0),
+            Level::Info => Color::rgb(255, 255, 255),
+            Level::Debug | Level::Trace => Color::rgb(160, 160, 160),
+        };
+        renderer.render_text(line,
+                             &Vector2::new(TOOLBAR_PADDING, TOOLBAR_PADDING + row as i32 * line_height),
+                             LOG_SCALE,
+                             color);
+    }
+}
+
+/// White text with a drop shadow, which keeps it readable over bright terrain
+fn render_shadowed_text(renderer: &mut Renderer, text: &str, x: i32, y: i32, scale: u32) {
+    let shadow_offset = scale as i32 / 2;
+    renderer.render_text(text,
+                         &Vector2::new(x + shadow_offset, y + shadow_offset),
+                         scale,
+                         Color::rgb(0, 0, 0));
+    renderer.render_text(text, &Vector2::new(x, y), scale, Color::rgb(255, 255, 255));
+}
+
+/// Cuts the text short (with "..." on the end) if it's wider than `width` pixels
+fn fit_text(text: &str, width: i32, scale: u32) -> String {
+    if font::text_width(text, scale) as i32 <= width {
+        return text.to_string();
+    }
+    let mut fitted = text.to_string();
+    while !fitted.is_empty() && font::text_width(&format!("{}...", fitted), scale) as i32 > width {
+        fitted.pop();
+    }
+    format!("{}...", fitted)
+}
+
+/// Breaks the text into lines no wider than `width` pixels, at spaces where it can. Line breaks in
+/// the text are kept.
+fn wrap_text(text: &str, width: i32, scale: u32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if font::text_width(&candidate, scale) as i32 > width && !line.is_empty() {
+                lines.push(line);
+                line = word.to_string();
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    // Blank lines at the end say nothing
+    while lines.last().map(|line| line.is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+    lines
+}
@@ -0,0 +1,270 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::resource::{KeyboardKeyStates, MouseState};
+use media::{Key, KeyState, MouseButton, RadialAction, RadialMenu, RadialMenuItem};
+use nalgebra::Vector2;
+use std::cmp;
+use types::Fixed;
+
+static ARROWS: [(Key, i32, i32); 4] = [(Key::Left, -1, 0),
+                                      (Key::Right, 1, 0),
+                                      (Key::Up, 0, -1),
+                                      (Key::Down, 0, 1)];
+
+/// Plays the game without a mouse: the arrow keys move the cursor (and scroll the camera once it's
+/// pushed against the edge of the screen), Enter clicks, slash right clicks, period jumps to the
+/// next of the player's units on screen, and Q opens the command panel. Works on the input after
+/// it's copied into the world, so the systems can't tell the keyboard from the mouse. These keys
+/// aren't bound to anything else, so Tab, Space, and the rest still reach the game.
+pub struct KeyboardCursor {
+    /// Pixels a second that the cursor moves while an arrow key is held
+    speed: u32,
+    position: Vector2<i32>,
+    /// Where the mouse itself was last update; moving it takes the cursor to wherever it went
+    mouse_position: Option<Vector2<i32>>,
+    /// Which of the on screen units period goes to next
+    next_unit: usize,
+    menu: RadialMenu,
+    /// The highlighted item, while the command panel is open
+    highlighted: Option<usize>,
+    pending_press: Option<Key>,
+    held_for_command: Option<Key>,
+}
+
+impl KeyboardCursor {
+    pub fn new(speed: u32) -> KeyboardCursor {
+        KeyboardCursor {
+            speed: speed,
+            position: Vector2::new(0, 0),
+            mouse_position: None,
+            next_unit: 0,
+            menu: RadialMenu::new(),
+            highlighted: None,
+            pending_press: None,
+            held_for_command: None,
+        }
+    }
+
+    pub fn position(&self) -> Vector2<i32> {
+        self.position
+    }
+
+    /// The command panel's items and which one is highlighted, while it's open
+    pub fn panel(&self) -> Option<(&[RadialMenuItem], usize)> {
+        self.highlighted.map(|highlighted| (self.menu.items(), highlighted))
+    }
+
+    /// Turns this update's keys into cursor movement and mouse buttons. `own_units` are the screen
+    /// positions of the local player's units that are on screen, in a stable order.
+    pub fn update(&mut self,
+                  keys: &mut KeyboardKeyStates,
+                  mouse: &mut MouseState,
+                  own_units: &[Vector2<i32>],
+                  screen_size: Vector2<i32>,
+                  time_step: Fixed) {
+        let pressed = |keys: &KeyboardKeyStates, key| keys.key_state(key) == KeyState::TransitionDown;
+
+        if self.mouse_position != Some(mouse.position) {
+            self.position = mouse.position;
+            self.mouse_position = Some(mouse.position);
+        }
+
+        if pressed(keys, Key::Q) {
+            self.highlighted = match self.highlighted {
+                Some(_) => None,
+                None => Some(0),
+            };
+        }
+        keys.0.remove(&Key::Q);
+
+        if let Some(highlighted) = self.highlighted {
+            let count = self.menu.items().len();
+            let mut highlighted = highlighted;
+            if pressed(keys, Key::Up) {
+                highlighted = (highlighted + count - 1) % count;
+            }
+            if pressed(keys, Key::Down) {
+                highlighted = (highlighted + 1) % count;
+            }
+            self.highlighted = Some(highlighted);
+            if pressed(keys, Key::Return) {
+                match self.menu.items()[highlighted].action {
+                    RadialAction::Press(key) => self.pending_press = Some(key),
+                    RadialAction::HoldForNextCommand(key) => self.held_for_command = Some(key),
+                }
+                self.highlighted = None;
+            }
+            // The panel has the arrows and Enter to itself while it's open
+            for key in &[Key::Up, Key::Down, Key::Left, Key::Right, Key::Return] {
+                keys.0.remove(key);
+            }
+        }
+
+        // Arrows move the cursor, except into the edge of the screen, where they're left for the camera
+        let step = cmp::max(1, i32::from(Fixed::from(self.speed as i32) * time_step));
+        for &(key, x, y) in &ARROWS {
+            if !keys.is_down(key) {
+                continue;
+            }
+            let at_edge = (x < 0 && self.position.x <= 0) ||
+                          (x > 0 && self.position.x >= screen_size.x - 1) ||
+                          (y < 0 && self.position.y <= 0) ||
+                          (y > 0 && self.position.y >= screen_size.y - 1);
+            if !at_edge {
+                self.position.x = clamp(self.position.x + x * step, screen_size.x);
+                self.position.y = clamp(self.position.y + y * step, screen_size.y);
+                keys.0.remove(&key);
+            }
+        }
+
+        if pressed(keys, Key::Period) && !own_units.is_empty() {
+            let index = self.next_unit % own_units.len();
+            self.position = Vector2::new(clamp(own_units[index].x, screen_size.x),
+                                         clamp(own_units[index].y, screen_size.y));
+            self.next_unit = index + 1;
+        }
+        keys.0.remove(&Key::Period);
+
+        // A tap from the panel is held for one update so that it transitions down and then up
+        if let Some(key) = self.pending_press.take() {
+            keys.0.insert(key, KeyState::TransitionDown);
+        }
+
+        for &(key, button) in &[(Key::Return, MouseButton::Left), (Key::Slash, MouseButton::Right)] {
+            let state = keys.key_state(key);
+            keys.0.remove(&key);
+            if state != KeyState::Up && mouse.key_states.key_state(button) == KeyState::Up {
+                mouse.key_states.0.insert(button, state);
+            }
+        }
+
+        // Held keys stay down until the right button is let go, since that's when commands are given
+        if let Some(key) = self.held_for_command {
+            keys.0.insert(key, KeyState::Down);
+            if mouse.key_states.key_state(MouseButton::Right) == KeyState::TransitionUp {
+                self.held_for_command = None;
+            }
+        }
+
+        mouse.position = self.position;
+    }
+}
+
+fn clamp(value: i32, size: i32) -> i32 {
+    cmp::max(0, cmp::min(value, size - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::resource::{KeyboardKeyStates, MouseState};
+    use media::{Key, KeyState, KeyStates, MouseButton};
+    use nalgebra::Vector2;
+    use std::collections::HashMap;
+    use super::KeyboardCursor;
+    use types::Fixed;
+
+    fn update(cursor: &mut KeyboardCursor,
+              keys: &[(Key, KeyState)],
+              units: &[Vector2<i32>])
+              -> KeyboardKeyStates {
+        let mut key_states = KeyStates::new(keys.iter().cloned().collect::<HashMap<_, _>>());
+        let mut mouse = MouseState::new();
+        mouse.position = Vector2::new(100, 100);
+        cursor.update(&mut key_states,
+                      &mut mouse,
+                      units,
+                      Vector2::new(120, 200),
+                      Fixed::from(1) / Fixed::from(60));
+        key_states
+    }
+
+    #[test]
+    fn test_arrows_move_the_cursor_until_the_edge() {
+        let mut cursor = KeyboardCursor::new(600);
+        let keys = update(&mut cursor, &[(Key::Right, KeyState::Down), (Key::Up, KeyState::Down)], &[]);
+        assert_eq!(Vector2::new(110, 90), cursor.position());
+        assert!(keys.0.is_empty());
+
+        update(&mut cursor, &[(Key::Right, KeyState::Down)], &[]);
+        assert_eq!(Vector2::new(119, 90), cursor.position());
+
+        // Pushed against the edge, the camera gets the key instead
+        let keys = update(&mut cursor, &[(Key::Right, KeyState::Down)], &[]);
+        assert_eq!(Vector2::new(119, 90), cursor.position());
+        assert!(keys.is_down(Key::Right));
+    }
+
+    #[test]
+    fn test_period_cycles_through_units() {
+        let mut cursor = KeyboardCursor::new(600);
+        let units = [Vector2::new(10, 20), Vector2::new(30, 40)];
+        let period = [(Key::Period, KeyState::TransitionDown)];
+        for &expected in &[units[0], units[1], units[0]] {
+            let keys = update(&mut cursor, &period, &units);
+            assert_eq!(expected, cursor.position());
+            assert!(keys.0.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_command_panel() {
+        let mut cursor = KeyboardCursor::new(600);
+        update(&mut cursor, &[(Key::Q, KeyState::TransitionDown)], &[]);
+        assert_eq!(0, cursor.panel().unwrap().1);
+        update(&mut cursor, &[(Key::Down, KeyState::TransitionDown)], &[]);
+        assert_eq!(1, cursor.panel().unwrap().1);
+        assert_eq!(Vector2::new(100, 100), cursor.position());
+
+        // Attack ground holds Alt until the next right click is let go
+        let keys = update(&mut cursor, &[(Key::Return, KeyState::TransitionDown)], &[]);
+        assert!(cursor.panel().is_none());
+        assert!(keys.is_down(Key::AltLeft));
+        assert!(update(&mut cursor, &[(Key::Slash, KeyState::Down)], &[]).is_down(Key::AltLeft));
+        assert!(update(&mut cursor, &[(Key::Slash, KeyState::TransitionUp)], &[]).is_down(Key::AltLeft));
+        assert!(!update(&mut cursor, &[], &[]).is_down(Key::AltLeft));
+    }
+
+    #[test]
+    fn test_enter_and_slash_click() {
+        let mut cursor = KeyboardCursor::new(600);
+        let mut keys = KeyStates::new(HashMap::new());
+        keys.0.insert(Key::Return, KeyState::TransitionDown);
+        keys.0.insert(Key::Slash, KeyState::TransitionUp);
+        let mut mouse = MouseState::new();
+        cursor.update(&mut keys, &mut mouse, &[], Vector2::new(120, 200), Fixed::from(0));
+        assert_eq!(KeyState::TransitionDown, mouse.key_states.key_state(MouseButton::Left));
+        assert_eq!(KeyState::TransitionUp, mouse.key_states.key_state(MouseButton::Right));
+        assert!(keys.0.is_empty());
+    }
+
+    #[test]
+    fn test_other_keys_reach_the_game() {
+        let mut cursor = KeyboardCursor::new(600);
+        let units = [Vector2::new(10, 20)];
+        let keys = update(&mut cursor,
+                          &[(Key::Tab, KeyState::TransitionDown), (Key::Space, KeyState::TransitionUp)],
+                          &units);
+        assert_eq!(Vector2::new(100, 100), cursor.position());
+        assert_eq!(KeyState::TransitionDown, keys.key_state(Key::Tab));
+        assert_eq!(KeyState::TransitionUp, keys.key_state(Key::Space));
+    }
+}
@@ -0,0 +1,311 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::GameSetup;
+use net::{GameBrowser, Lobby, LobbyClient, LobbyEvent, LobbyHost, LobbyMessage, LobbySettings,
+          LockstepSession};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::net::SocketAddr;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+use time;
+
+// TODO: Replace these console lobbies with a lobby screen once there's a menu
+
+const POLL_INTERVAL_MS: u64 = 50;
+const REJOIN_INTERVAL_MS: u64 = 500;
+
+/// What can be typed into the console while waiting in a lobby
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LobbyCommand {
+    Ready(bool),
+    /// Votes to drop the player in the slot
+    Drop(usize),
+    /// Host only: starts the game once everyone is ready
+    Start,
+    /// Host only: changes how many players the lobby has room for
+    Players(u8),
+    Leave,
+}
+
+/// Hosts a lobby on the LAN until the host starts the game (which everyone has to be ready for).
+/// `choices` are the host's own civilization/color/team requests, and players who go quiet
+/// for `drop_timeout` seconds are dropped. Returns the setup to play with and the session to play
+/// over, or None if the host leaves instead.
+pub fn host_lan_game(game_name: &str,
+                     player_name: &str,
+                     settings: LobbySettings,
+                     choices: &[LobbyMessage],
+                     drop_timeout: u32)
+                     -> Option<(GameSetup, LockstepSession)> {
+    let mut host = LobbyHost::new(game_name, player_name, settings, drop_timeout).unwrap_or_else(|err| {
+        unrecoverable!("Failed to host \"{}\": {}", game_name, err);
+    });
+    for choice in choices {
+        host.apply(choice);
+    }
+    println!("Hosting \"{}\"; type \"ready\" when you're ready and \"start\" once everyone is \
+              (\"players N\" makes room for N players, and \"leave\" hands the lobby to someone else)",
+             game_name);
+    run_host(host, game_name, player_name, drop_timeout, &console_commands())
+}
+
+/// Joins the first game found on the LAN (as a player, or just to watch) and waits for the host
+/// to start it. If the host drops, this player either takes over hosting or follows whoever did.
+/// Returns the lobby's settings (for the scenario to load) along with the setup to play with and
+/// the session to play over, or None if the player leaves.
+pub fn join_lan_game(player_name: &str,
+                     choices: &[LobbyMessage],
+                     observer: bool,
+                     drop_timeout: u32)
+                     -> Option<(LobbySettings, GameSetup, LockstepSession)> {
+    let mut browser = GameBrowser::new().unwrap_or_else(|err| {
+        unrecoverable!("Failed to look for games: {}", err);
+    });
+    println!("Looking for games on the LAN...");
+    let mut game = None;
+    while game.is_none() {
+        browser.poll().unwrap_or_else(|err| {
+            unrecoverable!("Failed to look for games: {}", err);
+        });
+        game = browser.games().first().map(|&game| game.clone());
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+    let game = game.unwrap();
+    let game_name = game.announcement.game_name.clone();
+
+    println!("Joining \"{}\" at {}", game_name, game.lobby_address);
+    let mut client = LobbyClient::join(&game.lobby_address, player_name, observer, drop_timeout)
+        .unwrap_or_else(|err| {
+            unrecoverable!("Failed to join \"{}\": {}", game_name, err);
+        });
+
+    if observer {
+        println!("Type \"leave\" to stop watching");
+    } else {
+        println!("Type \"ready\" when you're ready (or \"unready\" to change your mind), and \"leave\" to \
+                  leave");
+    }
+    let commands = console_commands();
+    let mut chosen = false;
+    loop {
+        let event = client.poll().unwrap_or_else(|err| {
+            unrecoverable!("Lobby failed: {}", err);
+        });
+        match event {
+            LobbyEvent::Nothing => {}
+            LobbyEvent::Changed => {
+                if let Some(lobby) = client.lobby() {
+                    print_lobby(lobby, client.waiting());
+                }
+            }
+            LobbyEvent::Started(setup) => {
+                let settings = client.lobby().unwrap().settings().clone();
+                let session = LockstepSession::client(&game_name, player_name, client, drop_timeout);
+                return Some((settings, setup, session));
+            }
+            LobbyEvent::TakeOver => {
+                println!("Lost the host; taking over \"{}\"", game_name);
+                let lobby = client.lobby().unwrap().clone();
+                let settings = lobby.settings().clone();
+                let host = LobbyHost::take_over(&game_name, lobby, client.slot().unwrap(), drop_timeout)
+                    .unwrap_or_else(|err| {
+                        unrecoverable!("Failed to take over \"{}\": {}", game_name, err);
+                    });
+                return run_host(host, &game_name, player_name, drop_timeout, &commands)
+                    .map(|(setup, session)| (settings, setup, session));
+            }
+            LobbyEvent::HostMoved(address) => {
+                println!("Lost the host; rejoining \"{}\" at {}", game_name, address);
+                client = rejoin(&address, &game_name, player_name, observer, drop_timeout);
+                chosen = false;
+            }
+        }
+        while let Ok(command) = commands.try_recv() {
+            match parse_command(&command) {
+                Some(LobbyCommand::Leave) => {
+                    let _ = client.leave();
+                    return None;
+                }
+                Some(_) if client.slot().is_none() => println!("Observers can only leave"),
+                Some(LobbyCommand::Ready(ready)) => request(&mut client, &LobbyMessage::SetReady(ready)),
+                Some(LobbyCommand::Drop(slot)) => request(&mut client, &LobbyMessage::VoteDrop(slot)),
+                Some(LobbyCommand::Start) |
+                Some(LobbyCommand::Players(_)) => println!("Only the host can do that"),
+                None => println!("Unknown command \"{}\"", command),
+            }
+        }
+        if !chosen && client.slot().is_some() {
+            // Observers don't have a slot to make choices for
+            for choice in choices {
+                request(&mut client, choice);
+            }
+            chosen = true;
+        }
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+}
+
+/// Runs the lobby until the host starts the game, or returns None if the host leaves
+fn run_host(mut host: LobbyHost,
+            game_name: &str,
+            player_name: &str,
+            drop_timeout: u32,
+            commands: &Receiver<String>)
+            -> Option<(GameSetup, LockstepSession)> {
+    // The scenario decides how many players there can be at most
+    let max_players = host.lobby().settings().max_players;
+    print_lobby(host.lobby(), host.waiting());
+    loop {
+        let mut changed = host.poll().unwrap_or_else(|err| {
+            unrecoverable!("Lobby failed: {}", err);
+        });
+        while let Ok(command) = commands.try_recv() {
+            match parse_command(&command) {
+                Some(LobbyCommand::Ready(ready)) => {
+                    host.apply(&LobbyMessage::SetReady(ready));
+                    changed = true;
+                }
+                Some(LobbyCommand::Drop(slot)) => changed |= host.vote_drop(slot),
+                Some(LobbyCommand::Start) if host.can_start() => {
+                    // A different game every time; the seed is handed to everyone in the start message
+                    let setup = host.start(time::precise_time_ns());
+                    return Some((setup, LockstepSession::host(game_name, player_name, host, drop_timeout)));
+                }
+                Some(LobbyCommand::Start) => {
+                    println!("The game can start once there are at least two players, all of them ready")
+                }
+                Some(LobbyCommand::Players(count)) if count < 2 || count > max_players => {
+                    println!("The scenario has room for 2 to {} players", max_players)
+                }
+                Some(LobbyCommand::Players(count)) => {
+                    let mut settings = host.lobby().settings().clone();
+                    settings.max_players = count;
+                    match host.configure(settings) {
+                        Ok(()) => changed = true,
+                        Err(reason) => println!("{}", reason),
+                    }
+                }
+                // Whoever is next in line takes over hosting
+                Some(LobbyCommand::Leave) => return None,
+                None => println!("Unknown command \"{}\"", command),
+            }
+        }
+        if changed {
+            print_lobby(host.lobby(), host.waiting());
+        }
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+}
+
+/// Follows the lobby to whoever took over hosting it, giving them until the drop timeout to
+/// start listening
+fn rejoin(address: &SocketAddr,
+          game_name: &str,
+          player_name: &str,
+          observer: bool,
+          drop_timeout: u32)
+          -> LobbyClient {
+    let deadline = time::precise_time_ns() + drop_timeout as u64 * 1_000_000_000;
+    loop {
+        match LobbyClient::join(address, player_name, observer, drop_timeout) {
+            Ok(client) => return client,
+            Err(ref err) if time::precise_time_ns() >= deadline => {
+                unrecoverable!("Failed to rejoin \"{}\": {}", game_name, err);
+            }
+            Err(_) => {}
+        }
+        thread::sleep(Duration::from_millis(REJOIN_INTERVAL_MS));
+    }
+}
+
+/// Lines typed into the console while waiting in a lobby
+fn console_commands() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line.trim().to_string(),
+                Err(_) => break,
+            };
+            if !line.is_empty() && sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Players are numbered as they are in the lobby, so "drop N" votes to drop the player in slot N - 1
+fn parse_command(command: &str) -> Option<LobbyCommand> {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let number = match words.len() {
+        2 => words[1].parse::<usize>().ok(),
+        _ => None,
+    };
+    match (words.first().cloned(), words.len(), number) {
+        (Some("ready"), 1, _) => Some(LobbyCommand::Ready(true)),
+        (Some("unready"), 1, _) => Some(LobbyCommand::Ready(false)),
+        (Some("drop"), 2, Some(player)) if player > 0 => Some(LobbyCommand::Drop(player - 1)),
+        (Some("start"), 1, _) => Some(LobbyCommand::Start),
+        (Some("players"), 2, Some(count)) if count <= u8::max_value() as usize => {
+            Some(LobbyCommand::Players(count as u8))
+        }
+        (Some("leave"), 1, _) => Some(LobbyCommand::Leave),
+        _ => None,
+    }
+}
+
+fn request(client: &mut LobbyClient, message: &LobbyMessage) {
+    client.request(message).unwrap_or_else(|err| {
+        unrecoverable!("Lobby failed: {}", err);
+    });
+}
+
+fn print_lobby(lobby: &Lobby, waiting: &BTreeMap<usize, u32>) {
+    println!("Scenario: {}", lobby.settings().scenario);
+    for (slot, player) in lobby.slots().iter().enumerate() {
+        match *player {
+            Some(ref player) => {
+                println!("  Player {}: {} (civilization {}, color {}, team {}{}){}",
+                         *Lobby::player_id(slot),
+                         player.name,
+                         *player.civilization_id,
+                         *player.player_color_id,
+                         player.team,
+                         if player.allied_vision { ", allied vision" } else { "" },
+                         if player.ready { " ready" } else { "" })
+            }
+            None => println!("  Player {}: open", *Lobby::player_id(slot)),
+        }
+    }
+    for (&slot, &seconds_left) in waiting {
+        if let Some(player) = lobby.slot(slot) {
+            println!("Waiting for {}; they'll be dropped in {}s (type \"drop {}\" to drop them now)",
+                     player.name,
+                     seconds_left,
+                     *Lobby::player_id(slot));
+        }
+    }
+}
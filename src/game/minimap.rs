@@ -0,0 +1,200 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::EmpiresDb;
+use ecs::resource::{FogOfWar, Players, Terrain, TileVisibility};
+use ecs::resource::terrain::CHUNK_SIZE;
+use media::{self, Renderer, Texture, TextureBuilder};
+use resource::minimap_palette_index;
+use std::cmp;
+use std::collections::HashMap;
+use types::{Color, Rect};
+
+/// The most pixels the minimap takes up along either side; small maps get more than a pixel a tile
+const MAX_SIZE: i32 = 200;
+const MARGIN: i32 = 8;
+
+/// How many out of date chunks are redrawn each frame, so that a lot changing at once (exploring a
+/// big stretch of the map, or a fresh start) is spread over a few frames instead of one
+const REDRAWS_PER_FRAME: usize = 8;
+
+/// A chunk of the minimap, drawn into a texture with a pixel per tile
+struct MinimapChunk {
+    terrain_revision: u32,
+    fog_revision: u32,
+
+    /// Which frame it was drawn on, so that the chunks that have been out of date the longest are
+    /// redrawn first
+    drawn_frame: u64,
+    texture: Texture,
+}
+
+/// Draws the map in the bottom right corner of the screen, a chunk at a time. Chunks are kept until
+/// the terrain or the fog of war says something in them changed (see their chunk revisions), so a
+/// frame where nothing moves copies the chunks' textures without redrawing any of them.
+///
+/// The map is drawn square, with rows going down and columns going right, rather than turned the
+/// way the view is.
+pub struct Minimap {
+    chunks: HashMap<(i32, i32), MinimapChunk>,
+    frame: u64,
+}
+
+impl Minimap {
+    pub fn new() -> Minimap {
+        Minimap {
+            chunks: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn render(&mut self,
+                  renderer: &mut Renderer,
+                  palette: &[u32],
+                  empires: &EmpiresDb,
+                  terrain: &Terrain,
+                  fog: &FogOfWar,
+                  players: &Players) {
+        self.frame += 1;
+        self.redraw_stale_chunks(renderer, palette, empires, terrain, fog, players);
+
+        let (width, height) = (terrain.width(), terrain.height());
+        let pixel_size = cmp::max(1, MAX_SIZE / cmp::max(1, cmp::max(width, height)));
+        let screen_size = renderer.viewport_size();
+        let (x, y) = (screen_size.x as i32 - MARGIN - width * pixel_size,
+                      screen_size.y as i32 - MARGIN - height * pixel_size);
+        renderer.set_render_color(Color::rgb(0, 0, 0));
+        renderer.fill_screen_rect(Rect::of(x, y, width * pixel_size, height * pixel_size));
+
+        for (&(chunk_row, chunk_col), chunk) in &self.chunks {
+            let (rows, cols) = chunk_size(terrain, chunk_row, chunk_col);
+            renderer.render_screen_texture(&chunk.texture,
+                                           Rect::of(x + chunk_col * CHUNK_SIZE * pixel_size,
+                                                    y + chunk_row * CHUNK_SIZE * pixel_size,
+                                                    cols * pixel_size,
+                                                    rows * pixel_size));
+        }
+    }
+
+    fn redraw_stale_chunks(&mut self,
+                           renderer: &mut Renderer,
+                           palette: &[u32],
+                           empires: &EmpiresDb,
+                           terrain: &Terrain,
+                           fog: &FogOfWar,
+                           players: &Players) {
+        let (chunk_rows, chunk_cols) = terrain.chunk_dimensions();
+        let mut stale = Vec::new();
+        for chunk_row in 0..chunk_rows {
+            for chunk_col in 0..chunk_cols {
+                let drawn_frame = match self.chunks.get(&(chunk_row, chunk_col)) {
+                    Some(chunk) => {
+                        if chunk.terrain_revision == terrain.chunk_revision(chunk_row, chunk_col) &&
+                           chunk.fog_revision == fog.chunk_revision(chunk_row, chunk_col) {
+                            continue;
+                        }
+                        chunk.drawn_frame
+                    }
+                    None => 0,
+                };
+                stale.push((drawn_frame, chunk_row, chunk_col));
+            }
+        }
+        stale.sort();
+
+        for &(_, chunk_row, chunk_col) in stale.iter().take(REDRAWS_PER_FRAME) {
+            match self.draw_chunk(renderer, palette, empires, terrain, fog, players, chunk_row, chunk_col) {
+                Ok(chunk) => {
+                    self.chunks.insert((chunk_row, chunk_col), chunk);
+                }
+                Err(err) => {
+                    log_warn!("Failed to draw the minimap: {}", err);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn draw_chunk(&self,
+                  renderer: &mut Renderer,
+                  palette: &[u32],
+                  empires: &EmpiresDb,
+                  terrain: &Terrain,
+                  fog: &FogOfWar,
+                  players: &Players,
+                  chunk_row: i32,
+                  chunk_col: i32)
+                  -> media::Result<MinimapChunk> {
+        let (rows, cols) = chunk_size(terrain, chunk_row, chunk_col);
+        let (first_row, first_col) = (chunk_row * CHUNK_SIZE, chunk_col * CHUNK_SIZE);
+        let mut pixels = Vec::with_capacity((rows * cols) as usize);
+        for row in first_row..(first_row + rows) {
+            for col in first_col..(first_col + cols) {
+                pixels.push(tile_color(empires, terrain, fog, players, row, col));
+            }
+        }
+
+        let rect = Rect::of(0, 0, cols, rows);
+        let texture = try!(try!(TextureBuilder::new(cols as u32, rows as u32, palette))
+            .blit_shape(&pixels, rect, rect)
+            .build(renderer));
+        Ok(MinimapChunk {
+            terrain_revision: terrain.chunk_revision(chunk_row, chunk_col),
+            fog_revision: fog.chunk_revision(chunk_row, chunk_col),
+            drawn_frame: self.frame,
+            texture: texture,
+        })
+    }
+}
+
+/// How many (rows, columns) of tiles the chunk has; the last ones along each edge may be cut short
+fn chunk_size(terrain: &Terrain, chunk_row: i32, chunk_col: i32) -> (i32, i32) {
+    (cmp::min(CHUNK_SIZE, terrain.height() - chunk_row * CHUNK_SIZE),
+     cmp::min(CHUNK_SIZE, terrain.width() - chunk_col * CHUNK_SIZE))
+}
+
+/// The palette index the tile is drawn in: the color of the player whose unit is on it, if it's in
+/// sight, or else its terrain's. Tiles out of sight only get every other pixel drawn, so they come
+/// out darker against the black underneath, and unexplored tiles are left black (since palette
+/// index 0 is transparent).
+fn tile_color(empires: &EmpiresDb,
+              terrain: &Terrain,
+              fog: &FogOfWar,
+              players: &Players,
+              row: i32,
+              col: i32)
+              -> u8 {
+    match fog.visibility(row, col) {
+        TileVisibility::Unexplored => return 0,
+        TileVisibility::Explored if (row + col) % 2 == 1 => return 0,
+        TileVisibility::Explored => {}
+        TileVisibility::Visible => {
+            if let Some(player_id) = fog.occupant(row, col) {
+                if let Some(player) = players.player(player_id) {
+                    if *player_id != 0 {
+                        return minimap_palette_index(*player.player_color_id as usize);
+                    }
+                }
+            }
+        }
+    }
+    empires.terrain(terrain.tile_at_row_col(row, col).terrain_id).minimap_color()
+}
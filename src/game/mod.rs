@@ -19,8 +19,37 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+mod autosave;
+mod bench;
+mod capture;
+mod crash_report;
+mod editor_toolbar;
+mod file_watcher;
 mod game;
+mod game_data;
+mod game_dir_prompt;
+mod headless;
+mod hud;
+mod keyboard_cursor;
+mod lan;
+mod minimap;
+mod pause_menu;
+mod regression;
+mod repro;
+mod save;
 mod state;
+mod tech_tree;
 
+pub use self::autosave::AutoSaver;
+pub use self::bench::run_benchmarks;
+pub use self::capture::CaptureFormat;
+pub use self::crash_report::{begin_game as begin_crash_report, install_panic_hook};
 pub use self::game::Game;
-pub use self::state::{GameState, ScenarioGameState};
+pub use self::game_data::GameData;
+pub use self::game_dir_prompt::locate_game_data_dir;
+pub use self::headless::HeadlessGame;
+pub use self::lan::{host_lan_game, join_lan_game};
+pub use self::regression::run_regression_cases;
+pub use self::repro::run_repro_bundle;
+pub use self::save::{GameSource, SaveGame};
+pub use self::state::{EditorGameState, GameState, ReplayGameState, ScenarioGameState};
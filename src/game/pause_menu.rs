@@ -0,0 +1,283 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The menu that Escape opens during a game. It only keeps track of what's selected and what the
+//! options are set to; the game state decides what picking each item does (see
+//! `ScenarioGameState::handle_pause_menu_controls`).
+
+/// Scroll speeds offered in the menu, as percentages of the normal speed
+const SCROLL_SPEEDS: [u32; 7] = [25, 50, 75, 100, 150, 200, 300];
+const NORMAL_SCROLL_SPEED_INDEX: usize = 3;
+
+// TODO: Add volume once there's audio, and hotkey bindings once keys can be rebound
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PauseMenuItem {
+    Resume,
+    SaveGame,
+    LoadGame,
+    ScrollSpeed,
+    Resign,
+    // TODO: Quit to the main menu once there is one; for now this quits the game
+    Quit,
+}
+
+static ITEMS: [PauseMenuItem; 6] = [PauseMenuItem::Resume,
+                                    PauseMenuItem::SaveGame,
+                                    PauseMenuItem::LoadGame,
+                                    PauseMenuItem::ScrollSpeed,
+                                    PauseMenuItem::Resign,
+                                    PauseMenuItem::Quit];
+
+impl PauseMenuItem {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            PauseMenuItem::Resume => "Resume",
+            PauseMenuItem::SaveGame => "Save game",
+            PauseMenuItem::LoadGame => "Load game",
+            PauseMenuItem::ScrollSpeed => "Scroll speed",
+            PauseMenuItem::Resign => "Resign",
+            PauseMenuItem::Quit => "Quit",
+        }
+    }
+}
+
+pub struct PauseMenu {
+    open: bool,
+    selected: usize,
+    disabled: Vec<PauseMenuItem>,
+
+    /// The item that has to be picked a second time before it happens (resigning)
+    armed: Option<PauseMenuItem>,
+
+    /// What happened when the last item was picked
+    status: Option<String>,
+
+    /// Kept while the menu is closed, like the rest of the options
+    scroll_speed_index: usize,
+
+    /// The saves to pick from once Load game has been picked, and which one is selected
+    saves: Option<Vec<String>>,
+    selected_save: usize,
+}
+
+impl PauseMenu {
+    pub fn new() -> PauseMenu {
+        PauseMenu {
+            open: false,
+            selected: 0,
+            disabled: Vec::new(),
+            armed: None,
+            status: None,
+            scroll_speed_index: NORMAL_SCROLL_SPEED_INDEX,
+            saves: None,
+            selected_save: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens the menu with the first item selected
+    pub fn open(&mut self) {
+        self.open = true;
+        self.selected = 0;
+        self.armed = None;
+        self.status = None;
+        self.saves = None;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub fn items(&self) -> &'static [PauseMenuItem] {
+        &ITEMS
+    }
+
+    pub fn selected_item(&self) -> PauseMenuItem {
+        ITEMS[self.selected]
+    }
+
+    /// Moves the selection up (negative) or down (positive), wrapping around at either end and
+    /// skipping the items that can't be picked right now. While the saves are listed, it moves
+    /// through them instead.
+    pub fn move_selection(&mut self, offset: i32) {
+        if let Some(ref saves) = self.saves {
+            let count = saves.len() as i32;
+            self.selected_save = (((self.selected_save as i32 + offset) % count + count) % count) as usize;
+            return;
+        }
+
+        let count = ITEMS.len() as i32;
+        let mut selected = self.selected as i32;
+        for _ in 0..count {
+            selected = ((selected + offset) % count + count) % count;
+            if self.is_enabled(ITEMS[selected as usize]) {
+                break;
+            }
+        }
+        self.selected = selected as usize;
+        self.armed = None;
+    }
+
+    pub fn is_enabled(&self, item: PauseMenuItem) -> bool {
+        !self.disabled.contains(&item)
+    }
+
+    pub fn set_enabled(&mut self, item: PauseMenuItem, enabled: bool) {
+        self.disabled.retain(|&disabled| disabled != item);
+        if !enabled {
+            self.disabled.push(item);
+        }
+    }
+
+    /// True if the item was already picked once since the selection last moved; otherwise
+    /// remembers that it was, so that picking it again goes through
+    pub fn confirm(&mut self, item: PauseMenuItem) -> bool {
+        if self.armed == Some(item) {
+            self.armed = None;
+            true
+        } else {
+            self.armed = Some(item);
+            false
+        }
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_ref().map(|status| &status[..])
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = Some(status);
+    }
+
+    /// Lists the saves (by their labels) in place of the items, with the first one selected
+    pub fn show_saves(&mut self, labels: Vec<String>) {
+        self.saves = if labels.is_empty() { None } else { Some(labels) };
+        self.selected_save = 0;
+        self.status = None;
+    }
+
+    /// Goes back to the items from the list of saves
+    pub fn close_saves(&mut self) {
+        self.saves = None;
+    }
+
+    pub fn saves(&self) -> Option<&[String]> {
+        self.saves.as_ref().map(|saves| &saves[..])
+    }
+
+    pub fn selected_save(&self) -> usize {
+        self.selected_save
+    }
+
+    pub fn scroll_speed_percent(&self) -> u32 {
+        SCROLL_SPEEDS[self.scroll_speed_index]
+    }
+
+    /// Steps through the scroll speeds, stopping at the slowest and fastest
+    pub fn change_scroll_speed(&mut self, offset: i32) {
+        let index = self.scroll_speed_index as i32 + offset;
+        self.scroll_speed_index = if index < 0 {
+            0
+        } else if index as usize >= SCROLL_SPEEDS.len() {
+            SCROLL_SPEEDS.len() - 1
+        } else {
+            index as usize
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_wraps_and_skips_disabled_items() {
+        let mut menu = PauseMenu::new();
+        menu.open();
+        assert_eq!(PauseMenuItem::Resume, menu.selected_item());
+        menu.move_selection(-1);
+        assert_eq!(PauseMenuItem::Quit, menu.selected_item());
+        menu.move_selection(1);
+        assert_eq!(PauseMenuItem::Resume, menu.selected_item());
+
+        menu.set_enabled(PauseMenuItem::LoadGame, false);
+        menu.move_selection(1);
+        menu.move_selection(1);
+        assert_eq!(PauseMenuItem::ScrollSpeed, menu.selected_item());
+        menu.move_selection(-1);
+        assert_eq!(PauseMenuItem::SaveGame, menu.selected_item());
+
+        menu.set_enabled(PauseMenuItem::LoadGame, true);
+        menu.move_selection(1);
+        assert_eq!(PauseMenuItem::LoadGame, menu.selected_item());
+    }
+
+    #[test]
+    fn test_confirm_needs_a_second_pick() {
+        let mut menu = PauseMenu::new();
+        menu.open();
+        assert!(!menu.confirm(PauseMenuItem::Resign));
+        assert!(menu.confirm(PauseMenuItem::Resign));
+        assert!(!menu.confirm(PauseMenuItem::Resign));
+        menu.move_selection(1);
+        assert!(!menu.confirm(PauseMenuItem::Resign));
+    }
+
+    #[test]
+    fn test_saves_take_the_place_of_the_items() {
+        let mut menu = PauseMenu::new();
+        menu.open();
+        menu.move_selection(1);
+        menu.show_saves(vec!["latest".to_string(), "older".to_string()]);
+        assert_eq!(0, menu.selected_save());
+        menu.move_selection(-1);
+        assert_eq!(1, menu.selected_save());
+        menu.move_selection(1);
+        assert_eq!(0, menu.selected_save());
+        assert_eq!(PauseMenuItem::SaveGame, menu.selected_item());
+
+        menu.close_saves();
+        assert!(menu.saves().is_none());
+        menu.move_selection(1);
+        assert_eq!(PauseMenuItem::LoadGame, menu.selected_item());
+
+        menu.show_saves(Vec::new());
+        assert!(menu.saves().is_none());
+        menu.show_saves(vec!["latest".to_string()]);
+        menu.open();
+        assert!(menu.saves().is_none());
+    }
+
+    #[test]
+    fn test_scroll_speed_stops_at_the_ends() {
+        let mut menu = PauseMenu::new();
+        assert_eq!(100, menu.scroll_speed_percent());
+        menu.change_scroll_speed(1);
+        assert_eq!(150, menu.scroll_speed_percent());
+        menu.change_scroll_speed(10);
+        assert_eq!(300, menu.scroll_speed_percent());
+        menu.change_scroll_speed(-10);
+        assert_eq!(25, menu.scroll_speed_percent());
+    }
+}
@@ -0,0 +1,257 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::CommandLog;
+use scn;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use super::game_data::GameData;
+use super::headless::HeadlessGame;
+
+/// A recorded game that must keep producing the same final state.
+///
+/// Case files are plain text with one `key value` pair per line:
+///
+/// ```text
+/// # Archers walking across the river
+/// scenario SCENARIO/MUF7E5_1.SCN
+/// commands archers.log
+/// ticks 600
+/// hash 6b7a3e1f0c2d4958
+/// ```
+///
+/// The scenario is relative to the game data directory (it comes from the retail CD, so it can't
+/// be checked in) and the command log is relative to the case file. A case without a hash hasn't
+/// been blessed yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegressionCase {
+    pub scenario: PathBuf,
+    pub commands: PathBuf,
+    pub ticks: u64,
+    pub expected_hash: Option<u64>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RegressionOutcome {
+    Passed,
+    Failed { expected: u64, actual: u64 },
+    Blessed(u64),
+}
+
+impl RegressionCase {
+    pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> io::Result<RegressionCase> {
+        let file_name = file_name.as_ref();
+        let mut text = String::new();
+        try!(try!(File::open(file_name)).read_to_string(&mut text));
+        RegressionCase::parse(&text, file_name.parent().unwrap_or(Path::new("")))
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_name: P) -> io::Result<()> {
+        let file_name = file_name.as_ref();
+        let text = self.to_text(file_name.parent().unwrap_or(Path::new("")));
+        try!(File::create(file_name)).write_all(text.as_bytes())
+    }
+
+    /// Parses a case; `case_dir` is the directory the command log path is relative to
+    pub fn parse(text: &str, case_dir: &Path) -> io::Result<RegressionCase> {
+        let (mut scenario, mut commands, mut ticks, mut expected_hash) = (None, None, None, None);
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(2, char::is_whitespace);
+            let key = fields.next().unwrap();
+            let value = fields.next().map(|v| v.trim()).unwrap_or("");
+            let error = |message: &str| {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               format!("regression case line {}: {}", line_number + 1, message))
+            };
+            if value.is_empty() {
+                return Err(error(&format!("missing value for \"{}\"", key)));
+            }
+
+            match key {
+                "scenario" => scenario = Some(PathBuf::from(value)),
+                "commands" => commands = Some(case_dir.join(value)),
+                "ticks" => ticks = Some(try!(value.parse::<u64>().map_err(|_| error("invalid tick count")))),
+                "hash" => {
+                    expected_hash = Some(try!(u64::from_str_radix(value, 16)
+                        .map_err(|_| error("invalid hash"))))
+                }
+                _ => return Err(error(&format!("unknown key \"{}\"", key))),
+            }
+        }
+
+        let missing = |key: &str| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("regression case is missing \"{}\"", key))
+        };
+        Ok(RegressionCase {
+            scenario: try!(scenario.ok_or_else(|| missing("scenario"))),
+            commands: try!(commands.ok_or_else(|| missing("commands"))),
+            ticks: try!(ticks.ok_or_else(|| missing("ticks"))),
+            expected_hash: expected_hash,
+        })
+    }
+
+    pub fn to_text(&self, case_dir: &Path) -> String {
+        let commands = self.commands.strip_prefix(case_dir).unwrap_or(&self.commands);
+        let mut text = format!("scenario {}\ncommands {}\nticks {}\n",
+                               self.scenario.display(),
+                               commands.display(),
+                               self.ticks);
+        if let Some(hash) = self.expected_hash {
+            text.push_str(&format!("hash {:016x}\n", hash));
+        }
+        text
+    }
+
+    /// Runs the case headlessly and returns the final state hash
+    pub fn run(&self, data: &GameData) -> Result<u64, String> {
+        let scenario_file_name = try!(data.game_dir()
+            .find_file(&self.scenario)
+            .map_err(|err| format!("{}", err)));
+        let scenario = try!(scn::Scenario::read_from_file(&scenario_file_name)
+            .map_err(|err| format!("Failed to load scenario \"{}\": {}", scenario_file_name.display(), err)));
+        let commands = try!(CommandLog::read_from_file(&self.commands)
+            .map_err(|err| format!("Failed to load command log \"{}\": {}", self.commands.display(), err)));
+
+        let mut game = HeadlessGame::new(data, &scenario, commands, &[]);
+        Ok(game.run(self.ticks).state_hash)
+    }
+
+    pub fn check(&self, actual_hash: u64) -> RegressionOutcome {
+        match self.expected_hash {
+            Some(expected) if expected == actual_hash => RegressionOutcome::Passed,
+            Some(expected) => {
+                RegressionOutcome::Failed {
+                    expected: expected,
+                    actual: actual_hash,
+                }
+            }
+            None => RegressionOutcome::Blessed(actual_hash),
+        }
+    }
+}
+
+/// Runs every given case file and prints a line per case. With `bless`, failing and
+/// unblessed cases get their stored hash replaced. Returns true if everything passed.
+pub fn run_regression_cases(data: &GameData, case_file_names: &[&str], bless: bool) -> bool {
+    let mut all_passed = true;
+    for case_file_name in case_file_names {
+        let mut case = match RegressionCase::read_from_file(case_file_name) {
+            Ok(case) => case,
+            Err(err) => {
+                println!("ERROR  {}: {}", case_file_name, err);
+                all_passed = false;
+                continue;
+            }
+        };
+
+        let actual_hash = match case.run(data) {
+            Ok(hash) => hash,
+            Err(err) => {
+                println!("ERROR  {}: {}", case_file_name, err);
+                all_passed = false;
+                continue;
+            }
+        };
+
+        match case.check(actual_hash) {
+            RegressionOutcome::Passed => println!("PASS   {}", case_file_name),
+            RegressionOutcome::Failed { expected, actual } if !bless => {
+                println!("FAIL   {}: expected hash {:016x}, got {:016x}",
+                         case_file_name,
+                         expected,
+                         actual);
+                all_passed = false;
+            }
+            RegressionOutcome::Failed { actual: hash, .. } |
+            RegressionOutcome::Blessed(hash) => {
+                case.expected_hash = Some(hash);
+                match case.write_to_file(case_file_name) {
+                    Ok(_) => println!("BLESS  {}: {:016x}", case_file_name, hash),
+                    Err(err) => {
+                        println!("ERROR  {}: failed to bless: {}", case_file_name, err);
+                        all_passed = false;
+                    }
+                }
+            }
+        }
+    }
+    all_passed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use super::{RegressionCase, RegressionOutcome};
+
+    fn sample_case() -> RegressionCase {
+        RegressionCase {
+            scenario: PathBuf::from("SCENARIO/MUF7E5_1.SCN"),
+            commands: PathBuf::from("regression/archers.log"),
+            ticks: 600,
+            expected_hash: Some(0x6b7a3e1f0c2d4958),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "# comment\nscenario SCENARIO/MUF7E5_1.SCN\ncommands archers.log\n\n\
+                    ticks 600\nhash 6b7a3e1f0c2d4958\n";
+        assert_eq!(sample_case(), RegressionCase::parse(text, Path::new("regression")).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let case = sample_case();
+        let text = case.to_text(Path::new("regression"));
+        assert!(text.contains("commands archers.log\n"));
+        assert_eq!(case, RegressionCase::parse(&text, Path::new("regression")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let dir = Path::new("");
+        assert!(RegressionCase::parse("commands a.log\nticks 5", dir).is_err());
+        assert!(RegressionCase::parse("scenario a.scn\ncommands a.log\nticks five", dir).is_err());
+        assert!(RegressionCase::parse("scenario a.scn\ncommands a.log\nticks 5\nhash xyz", dir).is_err());
+        assert!(RegressionCase::parse("scenario a.scn\ncommands a.log\nticks 5\ncolor blue", dir).is_err());
+        assert!(RegressionCase::parse("scenario\ncommands a.log\nticks 5", dir).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let mut case = sample_case();
+        assert_eq!(RegressionOutcome::Passed, case.check(0x6b7a3e1f0c2d4958));
+        assert_eq!(RegressionOutcome::Failed {
+                       expected: 0x6b7a3e1f0c2d4958,
+                       actual: 1,
+                   },
+                   case.check(1));
+        case.expected_hash = None;
+        assert_eq!(RegressionOutcome::Blessed(1), case.check(1));
+    }
+}
@@ -0,0 +1,383 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Reproduction bundles for desyncs and crashes. A bundle covers the last stretch of a game:
+//! it holds a snapshot of the world where that stretch starts, the commands from then on, and the
+//! state hashes the game saw along the way, so that `--repro` can play it back headlessly and point
+//! at the first tick that came out different.
+//!
+//! The runner restores the snapshot into a world created from the bundle's scenario and scripts,
+//! and plays the window from there. It then plays the window again from the same snapshot to also
+//! catch anything that doesn't play back the same way from the same state. Bundles without a
+//! snapshot (the game hadn't finished a tick yet) play from the start of the game.
+
+use action::CommandLog;
+use ecs::{self, WorldSnapshot};
+use ecs::resource::{ActionBatcher, Script, SimulationTick};
+use scn;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use super::game_data::GameData;
+use super::headless::HeadlessGame;
+
+pub const BUNDLE_FILE_NAME: &'static str = "repro.txt";
+pub const COMMANDS_FILE_NAME: &'static str = "commands.log";
+pub const SNAPSHOT_FILE_NAME: &'static str = "snapshot.bin";
+
+/// What a game recorded about its last few seconds, for the headless runner to check it against.
+///
+/// Bundle files are plain text with one `key value` pair per line, next to the command log and
+/// the snapshot:
+///
+/// ```text
+/// reason Panicked at src/ecs/system/gather.rs:120: no drop site
+/// scenario game/SCENARIO/MUF7E5_1.SCN
+/// script rules.txt
+/// start 1200
+/// end 3000
+/// hash 1200 6b7a3e1f0c2d4958
+/// hash 1260 0c2d49586b7a3e1f
+/// ```
+///
+/// The scenario and scripts are the paths the game was started with. The snapshot was taken at
+/// the end of the start tick, and the command log has the commands after it. Each hash line is a
+/// tick and the state hash at the end of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReproBundle {
+    pub reason: String,
+    pub scenario: PathBuf,
+    pub scripts: Vec<PathBuf>,
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub hashes: BTreeMap<u64, u64>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReproOutcome {
+    /// Every recorded hash matched, and the window played back the same way twice
+    Matched,
+    /// The state at the end of the tick wasn't what the game recorded
+    Diverged { tick: u64, expected: u64, actual: u64 },
+    /// Playing the window again from the same snapshot came out different at the end of the tick
+    Nondeterministic { tick: u64 },
+}
+
+impl ReproBundle {
+    /// Writes the bundle, its command log, and the snapshot it starts from to the given directory
+    pub fn write_to_dir<P: AsRef<Path>>(&self,
+                                        dir: P,
+                                        commands: &CommandLog,
+                                        snapshot: Option<&WorldSnapshot>)
+                                        -> io::Result<()> {
+        let dir = dir.as_ref();
+        try!(fs::create_dir_all(dir));
+        try!(commands.write_to_file(dir.join(COMMANDS_FILE_NAME)));
+        if let Some(snapshot) = snapshot {
+            try!(snapshot.write_to_file(dir.join(SNAPSHOT_FILE_NAME)));
+        }
+        try!(File::create(dir.join(BUNDLE_FILE_NAME))).write_all(self.to_text().as_bytes())
+    }
+
+    pub fn read_from_dir<P: AsRef<Path>>(dir: P)
+                                         -> io::Result<(ReproBundle, CommandLog, Option<WorldSnapshot>)> {
+        let dir = dir.as_ref();
+        let mut text = String::new();
+        try!(try!(File::open(dir.join(BUNDLE_FILE_NAME))).read_to_string(&mut text));
+        let bundle = try!(ReproBundle::parse(&text));
+        let commands = try!(CommandLog::read_from_file(dir.join(COMMANDS_FILE_NAME)));
+        let snapshot_file = dir.join(SNAPSHOT_FILE_NAME);
+        let snapshot = if snapshot_file.exists() {
+            Some(try!(WorldSnapshot::read_from_file(snapshot_file)))
+        } else {
+            None
+        };
+        Ok((bundle, commands, snapshot))
+    }
+
+    pub fn parse(text: &str) -> io::Result<ReproBundle> {
+        let mut bundle = ReproBundle {
+            reason: String::new(),
+            scenario: PathBuf::new(),
+            scripts: Vec::new(),
+            start_tick: 0,
+            end_tick: 0,
+            hashes: BTreeMap::new(),
+        };
+        let (mut has_scenario, mut has_end) = (false, false);
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(2, char::is_whitespace);
+            let key = fields.next().unwrap();
+            let value = fields.next().map(|v| v.trim()).unwrap_or("");
+            let error = |message: &str| {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               format!("repro bundle line {}: {}", line_number + 1, message))
+            };
+            if value.is_empty() {
+                return Err(error(&format!("missing value for \"{}\"", key)));
+            }
+            let parse_tick = |tick: &str| tick.parse::<u64>().map_err(|_| error("invalid tick"));
+
+            match key {
+                "reason" => bundle.reason = value.to_string(),
+                "scenario" => {
+                    bundle.scenario = PathBuf::from(value);
+                    has_scenario = true;
+                }
+                "script" => bundle.scripts.push(PathBuf::from(value)),
+                "start" => bundle.start_tick = try!(parse_tick(value)),
+                "end" => {
+                    bundle.end_tick = try!(parse_tick(value));
+                    has_end = true;
+                }
+                "hash" => {
+                    let mut words = value.split_whitespace();
+                    let tick = try!(parse_tick(words.next().unwrap_or("")));
+                    let hash = try!(words.next()
+                        .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+                        .ok_or_else(|| error("invalid hash")));
+                    bundle.hashes.insert(tick, hash);
+                }
+                _ => return Err(error(&format!("unknown key \"{}\"", key))),
+            }
+        }
+
+        let missing = |key: &str| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("repro bundle is missing \"{}\"", key))
+        };
+        if !has_scenario {
+            return Err(missing("scenario"));
+        }
+        if !has_end {
+            return Err(missing("end"));
+        }
+        if bundle.end_tick < bundle.start_tick {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "repro bundle ends before it starts"));
+        }
+        Ok(bundle)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if !self.reason.is_empty() {
+            // The reason is a single line, even if the panic message that it came from wasn't
+            let reason: Vec<&str> = self.reason.lines().collect();
+            text.push_str(&format!("reason {}\n", reason.join(" ")));
+        }
+        text.push_str(&format!("scenario {}\n", self.scenario.display()));
+        for script in &self.scripts {
+            text.push_str(&format!("script {}\n", script.display()));
+        }
+        text.push_str(&format!("start {}\nend {}\n", self.start_tick, self.end_tick));
+        for (tick, hash) in &self.hashes {
+            text.push_str(&format!("hash {} {:016x}\n", tick, hash));
+        }
+        text
+    }
+
+    /// Plays the bundle back headlessly: from the snapshot if there is one (or up to the start of
+    /// the window as fast as possible if there isn't), then through the window twice
+    pub fn run(&self,
+               data: &GameData,
+               commands: CommandLog,
+               snapshot: Option<&WorldSnapshot>)
+               -> Result<ReproOutcome, String> {
+        let scenario = try!(scn::Scenario::read_from_file(&self.scenario)
+            .map_err(|err| format!("Failed to load scenario \"{}\": {}", self.scenario.display(), err)));
+        let mut scripts = Vec::new();
+        for script in &self.scripts {
+            scripts.push(try!(Script::read_from_file(script)
+                .map_err(|err| format!("Failed to load script \"{}\": {}", script.display(), err))));
+        }
+
+        let mut game = HeadlessGame::new(data, &scenario, commands.clone(), &scripts);
+        if let Some(snapshot) = snapshot {
+            if snapshot.tick() != self.start_tick {
+                return Err(format!("The snapshot is from tick {}, but the bundle starts at tick {}",
+                                   snapshot.tick(),
+                                   self.start_tick));
+            }
+            let world = game.planner().mut_world();
+            snapshot.restore(world);
+            // The snapshot has the game's own playback, which the window's commands replace
+            world.write_resource::<ActionBatcher>().play_back(commands);
+            if let Some(outcome) = self.check(self.start_tick, Some(ecs::state_hash(world))) {
+                return Ok(outcome);
+            }
+        }
+        while current_tick(&mut game) < self.start_tick {
+            let tick = game.run_tick();
+            // Hashing every tick would slow down getting there, so only the recorded ones are checked
+            let hash = if self.hashes.contains_key(&tick) {
+                Some(ecs::state_hash(game.planner().mut_world()))
+            } else {
+                None
+            };
+            if let Some(outcome) = self.check(tick, hash) {
+                return Ok(outcome);
+            }
+        }
+
+        let snapshot = WorldSnapshot::capture(game.planner().mut_world());
+        let mut first_run = Vec::new();
+        while current_tick(&mut game) < self.end_tick {
+            let tick = game.run_tick();
+            let hash = ecs::state_hash(game.planner().mut_world());
+            if let Some(outcome) = self.check(tick, Some(hash)) {
+                return Ok(outcome);
+            }
+            first_run.push((tick, hash));
+        }
+
+        snapshot.restore(game.planner().mut_world());
+        for &(tick, hash) in &first_run {
+            game.run_tick();
+            if ecs::state_hash(game.planner().mut_world()) != hash {
+                return Ok(ReproOutcome::Nondeterministic { tick: tick });
+            }
+        }
+        Ok(ReproOutcome::Matched)
+    }
+
+    /// Compares the hash at the end of the tick (if it was worked out) with the recorded one
+    fn check(&self, tick: u64, actual: Option<u64>) -> Option<ReproOutcome> {
+        match (self.hashes.get(&tick), actual) {
+            (Some(&expected), Some(actual)) if expected != actual => {
+                Some(ReproOutcome::Diverged {
+                    tick: tick,
+                    expected: expected,
+                    actual: actual,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn current_tick(game: &mut HeadlessGame) -> u64 {
+    game.planner().mut_world().read_resource::<SimulationTick>().tick
+}
+
+/// Plays back the bundle in the given directory and prints what came of it.
+/// Returns true if it played back just as the game recorded it.
+pub fn run_repro_bundle(data: &GameData, dir: &str) -> bool {
+    let (bundle, commands, snapshot) = match ReproBundle::read_from_dir(dir) {
+        Ok(bundle) => bundle,
+        Err(err) => {
+            println!("Failed to load repro bundle \"{}\": {}", dir, err);
+            return false;
+        }
+    };
+    if !bundle.reason.is_empty() {
+        println!("Reproducing: {}", bundle.reason);
+    }
+    println!("Playing back ticks {} to {} of {}",
+             bundle.start_tick,
+             bundle.end_tick,
+             bundle.scenario.display());
+
+    match bundle.run(data, commands, snapshot.as_ref()) {
+        Ok(ReproOutcome::Matched) => {
+            println!("Every recorded state hash matched, and the window played back the same way twice");
+            true
+        }
+        Ok(ReproOutcome::Diverged { tick, expected, actual }) => {
+            println!("Diverged at tick {}: expected hash {:016x}, got {:016x}",
+                     tick,
+                     expected,
+                     actual);
+            false
+        }
+        Ok(ReproOutcome::Nondeterministic { tick }) => {
+            println!("Playing the window again from the same snapshot came out different at tick {}",
+                     tick);
+            false
+        }
+        Err(err) => {
+            println!("ERROR  {}", err);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+    use super::{ReproBundle, ReproOutcome};
+
+    fn sample_bundle() -> ReproBundle {
+        let mut hashes = BTreeMap::new();
+        hashes.insert(1200, 0x6b7a3e1f0c2d4958);
+        hashes.insert(1260, 0x0c2d49586b7a3e1f);
+        ReproBundle {
+            reason: "Panicked at src/main.rs:1: oops".to_string(),
+            scenario: PathBuf::from("game/SCENARIO/MUF7E5_1.SCN"),
+            scripts: vec![PathBuf::from("rules.txt")],
+            start_tick: 1200,
+            end_tick: 1300,
+            hashes: hashes,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bundle = sample_bundle();
+        let text = bundle.to_text();
+        assert!(text.contains("\nhash 1260 0c2d49586b7a3e1f\n"));
+        assert_eq!(bundle, ReproBundle::parse(&text).unwrap());
+
+        let mut multiline = bundle.clone();
+        multiline.reason = "first\nsecond".to_string();
+        assert_eq!("first second", ReproBundle::parse(&multiline.to_text()).unwrap().reason);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ReproBundle::parse("end 5").is_err());
+        assert!(ReproBundle::parse("scenario a.scn").is_err());
+        assert!(ReproBundle::parse("scenario a.scn\nstart 6\nend 5").is_err());
+        assert!(ReproBundle::parse("scenario a.scn\nend 5\nhash 5").is_err());
+        assert!(ReproBundle::parse("scenario a.scn\nend 5\nhash 5 xyz").is_err());
+        assert!(ReproBundle::parse("scenario a.scn\nend 5\ncolor blue").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let bundle = sample_bundle();
+        assert_eq!(None, bundle.check(1200, Some(0x6b7a3e1f0c2d4958)));
+        assert_eq!(None, bundle.check(1201, Some(1)));
+        assert_eq!(None, bundle.check(1260, None));
+        assert_eq!(Some(ReproOutcome::Diverged {
+                       tick: 1260,
+                       expected: 0x0c2d49586b7a3e1f,
+                       actual: 1,
+                   }),
+                   bundle.check(1260, Some(1)));
+    }
+}
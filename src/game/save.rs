@@ -0,0 +1,265 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Saved games. A save is a snapshot of the world along with what it takes to create a world to
+//! restore it into: the scenario and scripts the game was started from (by path, so they have to
+//! still be there), and the game's seed and settings.
+//!
+//! Save files start with `CHSV` and a version byte, followed by the scenario's path, the scripts'
+//! paths, the seed, the settings (written as in a command log), and then the snapshot in its own
+//! format.
+
+use action::{Reader, Writer};
+use ecs::{GameSettings, Persist, WorldSnapshot};
+use ecs::resource::GameRng;
+use specs;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub const SAVE_DIR: &'static str = "saves";
+pub const AUTOSAVE_DIR: &'static str = "autosaves";
+pub const SAVE_EXTENSION: &'static str = "sav";
+
+const MAGIC: &'static [u8] = b"CHSV";
+const FORMAT_VERSION: u8 = 1;
+
+/// The files a game was started from, which loading a save of it needs again
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSource {
+    pub scenario: PathBuf,
+    pub scripts: Vec<PathBuf>,
+}
+
+pub struct SaveGame {
+    pub source: GameSource,
+    pub seed: u64,
+    pub settings: GameSettings,
+    pub snapshot: WorldSnapshot,
+}
+
+/// A save file that `list_saves` found
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveFile {
+    pub path: PathBuf,
+    pub auto_save: bool,
+}
+
+impl SaveFile {
+    /// What the pause menu lists it as: when it was saved (which is what it's named after), and
+    /// whether it's an auto-save
+    pub fn label(&self) -> String {
+        let name = self.path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or(String::new());
+        // Named by `capture::timestamp`, as in chariot-20161104-120500-000
+        let timestamped = name.len() == 27 && name.starts_with("chariot-") && name.bytes().all(|b| b < 0x80);
+        let label = if timestamped {
+            let digits = &name[8..];
+            format!("{}-{}-{} {}:{}:{}",
+                    &digits[0..4],
+                    &digits[4..6],
+                    &digits[6..8],
+                    &digits[9..11],
+                    &digits[11..13],
+                    &digits[13..15])
+        } else {
+            name
+        };
+        if self.auto_save {
+            format!("{} (auto)", label)
+        } else {
+            label
+        }
+    }
+}
+
+impl SaveGame {
+    pub fn capture(world: &specs::World, source: &GameSource) -> SaveGame {
+        SaveGame {
+            source: source.clone(),
+            seed: world.read_resource::<GameRng>().seed(),
+            settings: world.read_resource::<GameSettings>().clone(),
+            snapshot: WorldSnapshot::capture(world),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        write_header(&mut writer, &self.source, self.seed, &self.settings);
+        writer.blob(&self.snapshot.to_bytes());
+        writer.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<SaveGame> {
+        let mut reader = Reader::new(bytes);
+        let (source, seed, settings) = try!(read_header(&mut reader).map_err(|err| invalid(&reader, err)));
+        let snapshot = try!(reader.blob().map_err(|err| invalid(&reader, err)));
+        if !reader.is_at_end() {
+            return Err(invalid(&reader, "unexpected bytes after the snapshot".to_string()));
+        }
+        Ok(SaveGame {
+            source: source,
+            seed: seed,
+            settings: settings,
+            snapshot: try!(WorldSnapshot::from_bytes(snapshot)),
+        })
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_name: P) -> io::Result<()> {
+        try!(File::create(file_name)).write_all(&self.to_bytes())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(file_name: P) -> io::Result<SaveGame> {
+        SaveGame::from_bytes(&try!(read_file(file_name.as_ref())))
+    }
+}
+
+/// Saves (newest first) and then auto-saves (newest first) of games started from the source. Files
+/// that can't be read are left out.
+pub fn list_saves(source: &GameSource) -> Vec<SaveFile> {
+    let mut saves = Vec::new();
+    for &(dir, auto_save) in &[(SAVE_DIR, false), (AUTOSAVE_DIR, true)] {
+        let mut paths = match save_file_names(Path::new(dir)) {
+            Ok(paths) => paths,
+            Err(_) => continue,
+        };
+        // Saves are named after when they were written, so the newest sort last
+        paths.sort();
+        for path in paths.into_iter().rev() {
+            match read_file(&path).and_then(|bytes| read_source(&bytes)) {
+                Ok(ref save_source) if save_source == source => {
+                    saves.push(SaveFile {
+                        path: path,
+                        auto_save: auto_save,
+                    })
+                }
+                Ok(_) => {}
+                Err(err) => log_warn!("Skipping save \"{}\": {}", path.display(), err),
+            }
+        }
+    }
+    saves
+}
+
+/// Every save file in the directory
+pub fn save_file_names(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in try!(fs::read_dir(dir)) {
+        let path = try!(entry).path();
+        if path.extension().map(|extension| extension == SAVE_EXTENSION).unwrap_or(false) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+fn read_file(file_name: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    try!(try!(File::open(file_name)).read_to_end(&mut bytes));
+    Ok(bytes)
+}
+
+/// Which game the save is of, without reading the snapshot
+fn read_source(bytes: &[u8]) -> io::Result<GameSource> {
+    let mut reader = Reader::new(bytes);
+    read_header(&mut reader).map(|(source, _, _)| source).map_err(|err| invalid(&reader, err))
+}
+
+fn invalid(reader: &Reader, err: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData,
+                   format!("save byte {}: {}", reader.position(), err))
+}
+
+fn write_header(writer: &mut Writer, source: &GameSource, seed: u64, settings: &GameSettings) {
+    writer.raw(MAGIC);
+    writer.byte(FORMAT_VERSION);
+    writer.text(&source.scenario.to_string_lossy());
+    let scripts: Vec<String> = source.scripts
+        .iter()
+        .map(|script| script.to_string_lossy().into_owned())
+        .collect();
+    scripts.write(writer);
+    seed.write(writer);
+    writer.text(&settings.to_field());
+}
+
+fn read_header(reader: &mut Reader) -> Result<(GameSource, u64, GameSettings), String> {
+    if try!(reader.raw(MAGIC.len())) != MAGIC {
+        return Err("not a save".to_string());
+    }
+    let version = try!(reader.byte());
+    if version != FORMAT_VERSION {
+        return Err(format!("version {} isn't one this build understands", version));
+    }
+    let scenario = PathBuf::from(try!(reader.text()));
+    let scripts = try!(Vec::<String>::read(reader)).into_iter().map(PathBuf::from).collect();
+    let seed = try!(u64::read(reader));
+    let settings = try!(GameSettings::parse(&try!(reader.text())));
+    let source = GameSource {
+        scenario: scenario,
+        scripts: scripts,
+    };
+    Ok((source, seed, settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use action::{Reader, Writer};
+    use ecs::{GameSettings, VictoryCondition};
+    use std::path::PathBuf;
+    use super::{GameSource, SaveFile, read_header, read_source, write_header};
+
+    #[test]
+    fn test_header_round_trip() {
+        let source = GameSource {
+            scenario: PathBuf::from("game/SCENARIO/MUF7E5_1.SCN"),
+            scripts: vec![PathBuf::from("rules.txt"), PathBuf::from("more rules.txt")],
+        };
+        let mut settings = GameSettings::new();
+        settings.victory_condition = VictoryCondition::Conquest;
+        let mut writer = Writer::new();
+        write_header(&mut writer, &source, 42, &settings);
+        writer.blob(b"snapshot");
+        let bytes = writer.into_bytes();
+
+        let mut reader = Reader::new(&bytes);
+        assert_eq!((source.clone(), 42, settings), read_header(&mut reader).unwrap());
+        assert_eq!(&b"snapshot"[..], reader.blob().unwrap());
+        assert_eq!(source, read_source(&bytes).unwrap());
+        assert!(read_source(&bytes[..10]).is_err());
+        assert!(read_source(b"CHCL\x03").is_err());
+    }
+
+    #[test]
+    fn test_label() {
+        let mut save = SaveFile {
+            path: PathBuf::from("autosaves/chariot-20161104-120500-000.sav"),
+            auto_save: true,
+        };
+        assert_eq!("2016-11-04 12:05:00 (auto)", save.label());
+        save.auto_save = false;
+        assert_eq!("2016-11-04 12:05:00", save.label());
+        save.path = PathBuf::from("saves/before the siege.sav");
+        assert_eq!("before the siege", save.label());
+    }
+}
@@ -0,0 +1,953 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::{self, ResourceType};
+use ecs::{GameSetup, TransformComponent, UnitComponent, UnitFactory, UnitSpawn};
+use ecs::resource::{MouseState, OccupiedTiles, Players, Stockpiles, Terrain, ViewProjector, Viewport};
+use ecs::system::{MouseRay, calculate_mouse_ray};
+use game::{Game, GameState};
+use game::editor_toolbar::{EditorToolbar, ToolbarButton};
+use game::headless::DEFAULT_SEED;
+use game::hud;
+use identifier::{CivilizationId, PlayerId, TerrainId, UnitId};
+use media::{Key, KeyState, KeyStates, MediaRef, MouseButton};
+use partition::GridPartition;
+use scn;
+use specs::{self, Join};
+use std::cmp;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use super::editor_history::{Edit, EditHistory};
+use super::scenario_game_state::ScenarioGameState;
+use types::{Fixed, Vector3};
+use util::unit;
+
+const MAX_BRUSH_SIZE: i32 = 9;
+
+/// How many edits can be undone
+const UNDO_LIMIT: usize = 100;
+
+/// Teams are numbered from 1 up to this, like players are
+const MAX_TEAM: u8 = 8;
+
+/// How far minus/equals skip through the terrains or units with shift held
+const PALETTE_SKIP: usize = 10;
+
+/// How much minus/equals change a starting resource by with the player tool (ten times as much
+/// with shift held)
+const RESOURCE_STEP: i32 = 100;
+
+/// The starting resources that the player tool edits, in the order page up/down go through them
+static STARTING_RESOURCES: [(&'static str, ResourceType); 4] = [("food", ResourceType::Food),
+                                                               ("wood", ResourceType::Wood),
+                                                               ("gold", ResourceType::Gold),
+                                                               ("stone", ResourceType::Stone)];
+
+/// Players without a civilization (usually gaia) place units from this one
+const DEFAULT_CIVILIZATION_ID: usize = 1;
+
+/// The state that units are saved with; the game doesn't read it back (see `create_world_planner`)
+const SAVED_UNIT_STATE: u8 = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Tool {
+    Terrain,
+    Elevation,
+    Units,
+    Players,
+}
+
+impl Tool {
+    fn next(self) -> Tool {
+        match self {
+            Tool::Terrain => Tool::Elevation,
+            Tool::Elevation => Tool::Units,
+            Tool::Units => Tool::Players,
+            Tool::Players => Tool::Terrain,
+        }
+    }
+
+    fn button(self) -> ToolbarButton {
+        match self {
+            Tool::Terrain => ToolbarButton::Terrain,
+            Tool::Elevation => ToolbarButton::Elevation,
+            Tool::Units => ToolbarButton::Units,
+            Tool::Players => ToolbarButton::Players,
+        }
+    }
+}
+
+/// Edits a scenario in place: paints terrain and elevation with a square brush, places and
+/// deletes units for any player, and sets each player's team and starting resources. The map is
+/// shown the way it is in game, but nothing moves, fights, or trains.
+///
+/// Controls: tab (or the toolbar) switches between the terrain, elevation, unit, and player tools;
+/// the number keys pick the player that units are placed for (home picks gaia); minus/equals pick
+/// the terrain or unit (ten at a time with shift held); page up/down change the brush size; ctrl+z
+/// undoes and ctrl+y (or ctrl+shift+z) redoes; ctrl+s saves the scenario to the file it came from,
+/// and ctrl+o loads it from there again, dropping any edits since.
+///
+/// With the terrain tool, the left button paints and the right button picks up the terrain under
+/// the mouse. With the elevation tool, the left button raises and the right button lowers.
+/// With the unit tool, the left button places and the right button deletes. With the player tool,
+/// page up/down pick a resource and minus/equals take away or add to the player's starting amount
+/// of it, and T moves the player onto the next team.
+pub struct EditorGameState {
+    media: MediaRef,
+    empires: dat::EmpiresDbRef,
+    scenario_state: ScenarioGameState,
+    /// Where the scenario is saved to and loaded from
+    file_name: PathBuf,
+    toolbar: EditorToolbar,
+    tool: Tool,
+    player_id: PlayerId,
+    brush_size: i32,
+    /// Every terrain that can be painted, by ID
+    terrain_ids: Vec<TerrainId>,
+    terrain_index: usize,
+    /// Index into the current player's placeable units; wraps if their civilization has fewer
+    unit_index: usize,
+    /// Index into `STARTING_RESOURCES` of the resource the player tool edits
+    resource_index: usize,
+    /// The tile the brush was last applied at while a button is held, so holding it still
+    /// doesn't keep painting over the same tiles
+    last_tile: Option<(i32, i32)>,
+    /// The elevation that the current stroke of the elevation tool levels tiles to
+    stroke_elevation: i32,
+    /// What's been painted since the mouse button went down; it becomes a single edit when
+    /// the button comes back up
+    stroke: Option<PaintStroke>,
+    history: EditHistory<specs::World>,
+}
+
+impl EditorGameState {
+    pub fn new(g: &Game, scenario: scn::Scenario, file_name: PathBuf) -> EditorGameState {
+        let mut setup = GameSetup::new(DEFAULT_SEED);
+        setup.editor = true;
+
+        let empires = g.empires_db();
+        let terrain_ids = empires.terrain_block()
+            .terrains()
+            .iter()
+            .filter(|terrain| terrain.enabled() && terrain.slp_id.is_some())
+            .map(|terrain| terrain.id)
+            .collect();
+
+        EditorGameState {
+            media: g.media(),
+            empires: empires,
+            scenario_state: ScenarioGameState::new(g, scenario, &setup),
+            file_name: file_name,
+            toolbar: EditorToolbar::new(),
+            tool: Tool::Terrain,
+            player_id: 1.into(),
+            brush_size: 1,
+            terrain_ids: terrain_ids,
+            terrain_index: 0,
+            unit_index: 0,
+            resource_index: 0,
+            last_tile: None,
+            stroke_elevation: 0,
+            stroke: None,
+            history: EditHistory::new(UNDO_LIMIT),
+        }
+    }
+
+    fn handle_keys(&mut self) {
+        let keys = self.media.borrow().key_states().clone();
+        let pressed = |key| keys.key_state(key) == KeyState::TransitionDown;
+        let shift = is_down(keys.key_state(Key::ShiftLeft));
+        let skip = if shift {
+            PALETTE_SKIP
+        } else {
+            1
+        };
+
+        if pressed(Key::Tab) {
+            self.tool = self.tool.next();
+        }
+
+        if is_down(keys.key_state(Key::CtrlLeft)) {
+            let redo = pressed(Key::Y) || (pressed(Key::Z) && shift);
+            let undo = pressed(Key::Z) && !redo;
+            if undo || redo {
+                self.undo_or_redo(undo);
+                return;
+            }
+            if pressed(Key::S) {
+                self.save();
+                return;
+            }
+            if pressed(Key::O) {
+                self.load();
+                return;
+            }
+        }
+
+        let player_keys = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7,
+                           Key::Num8];
+        for (index, &key) in player_keys.iter().enumerate() {
+            if pressed(key) {
+                self.player_id = (index + 1).into();
+            }
+        }
+        if pressed(Key::Home) {
+            self.player_id = 0.into();
+        }
+
+        if self.tool == Tool::Players {
+            for &(key, forward) in &[(Key::PageUp, true), (Key::PageDown, false)] {
+                if pressed(key) {
+                    self.resource_index = step(self.resource_index, STARTING_RESOURCES.len(), 1, forward);
+                }
+            }
+            if pressed(Key::T) {
+                self.edit_player(|state| state.team = state.team % MAX_TEAM + 1);
+            }
+        } else {
+            if pressed(Key::PageUp) {
+                self.brush_size = cmp::min(MAX_BRUSH_SIZE, self.brush_size + 1);
+            }
+            if pressed(Key::PageDown) {
+                self.brush_size = cmp::max(1, self.brush_size - 1);
+            }
+        }
+
+        for &(key, forward) in &[(Key::Equals, true), (Key::Minus, false)] {
+            if !pressed(key) {
+                continue;
+            }
+            match self.tool {
+                Tool::Terrain => {
+                    self.terrain_index = step(self.terrain_index, self.terrain_ids.len(), skip, forward);
+                }
+                Tool::Units => {
+                    let unit_count = self.placeable_units().len();
+                    self.unit_index = step(self.unit_index, unit_count, skip, forward);
+                }
+                Tool::Players => {
+                    let resource_index = self.resource_index;
+                    let change = RESOURCE_STEP * skip as i32;
+                    let change = if forward { change } else { -change };
+                    self.edit_player(|state| {
+                        let amount = state.amounts[resource_index] + Fixed::from(change);
+                        state.amounts[resource_index] = if amount < Fixed::from(0) {
+                            0.into()
+                        } else {
+                            amount
+                        };
+                    });
+                }
+                Tool::Elevation => {}
+            }
+        }
+    }
+
+    /// Clicking a button does what it says. The rest of the editor doesn't see clicks on the
+    /// toolbar, so they don't paint or place anything under it.
+    fn handle_toolbar(&mut self) {
+        let (position, left, screen_height) = {
+            let media = self.media.borrow();
+            (media.mouse_position(),
+             media.mouse_button_states().key_state(MouseButton::Left),
+             media.viewport_size().y as i32)
+        };
+        if !self.toolbar.is_over(position.x, position.y, screen_height) {
+            return;
+        }
+
+        if left == KeyState::TransitionDown {
+            match self.toolbar.button_at(position.x, position.y, screen_height) {
+                Some(ToolbarButton::Terrain) => self.tool = Tool::Terrain,
+                Some(ToolbarButton::Elevation) => self.tool = Tool::Elevation,
+                Some(ToolbarButton::Units) => self.tool = Tool::Units,
+                Some(ToolbarButton::Players) => self.tool = Tool::Players,
+                Some(ToolbarButton::Undo) => self.undo_or_redo(true),
+                Some(ToolbarButton::Redo) => self.undo_or_redo(false),
+                Some(ToolbarButton::Save) => self.save(),
+                Some(ToolbarButton::Load) => self.load(),
+                None => {}
+            }
+        }
+
+        let world = self.scenario_state.planner().mut_world();
+        let mut mouse_state = world.write_resource::<MouseState>();
+        mouse_state.key_states = KeyStates::new(HashMap::new());
+        mouse_state.drag_start = None;
+    }
+
+    fn undo_or_redo(&mut self, undo: bool) {
+        self.finish_stroke();
+        let world = self.scenario_state.planner().mut_world();
+        if undo {
+            self.history.undo(world);
+        } else {
+            self.history.redo(world);
+        }
+    }
+
+    /// Writes the scenario as it's been edited to the file it was loaded from
+    fn save(&mut self) {
+        self.finish_stroke();
+        let scenario = self.edited_scenario();
+        match scenario.write_to_file(&self.file_name) {
+            Ok(()) => {
+                log_info!("Saved the scenario to {}", self.file_name.display());
+                self.toolbar.set_status(format!("Saved to {}", self.file_name.display()));
+            }
+            Err(err) => {
+                log_error!("Failed to save the scenario to {}: {}", self.file_name.display(), err);
+                self.toolbar.set_status("Failed to save the scenario".to_string());
+            }
+        }
+    }
+
+    /// Loads the scenario from its file again, dropping any edits that weren't saved (and the
+    /// undo history along with them)
+    fn load(&mut self) {
+        match scn::Scenario::read_from_file(&self.file_name) {
+            Ok(scenario) => {
+                self.scenario_state.load_scenario(scenario);
+                self.stroke = None;
+                self.last_tile = None;
+                self.history = EditHistory::new(UNDO_LIMIT);
+                self.toolbar.set_status(format!("Loaded {}", self.file_name.display()));
+            }
+            Err(err) => {
+                log_error!("Failed to load the scenario from {}: {}", self.file_name.display(), err);
+                self.toolbar.set_status("Failed to load the scenario".to_string());
+            }
+        }
+    }
+
+    /// The loaded scenario with the edited terrain, units, teams, and starting resources in place
+    /// of its own
+    fn edited_scenario(&mut self) -> scn::Scenario {
+        let mut scenario = self.scenario_state.scenario().clone();
+        let world = self.scenario_state.planner().mut_world();
+
+        let terrain = world.read_resource::<Terrain>();
+        for row in 0..terrain.height() {
+            for col in 0..terrain.width() {
+                let tile = terrain.tile_at_row_col(row, col);
+                scenario.map.tiles[(row * terrain.width() + col) as usize] =
+                    scn::MapTile::new(tile.terrain_id, tile.elevation);
+            }
+        }
+
+        let mut player_units: Vec<Vec<scn::PlayerUnit>> =
+            scenario.player_ids().iter().map(|_| Vec::new()).collect();
+        let units = world.read::<UnitComponent>();
+        let transforms = world.read::<TransformComponent>();
+        for (unit, transform) in (&units, &transforms).iter() {
+            if let Some(units_of_player) = player_units.get_mut(*unit.player_id as usize) {
+                let position = transform.position();
+                units_of_player.push(scn::PlayerUnit {
+                    position_x: position.x.into(),
+                    position_y: position.y.into(),
+                    position_z: position.z.into(),
+                    spawn_id: None,
+                    unit_id: unit.unit_id,
+                    state: SAVED_UNIT_STATE,
+                    rotation: transform.rotation.into(),
+                });
+            }
+        }
+        scenario.set_player_units(player_units);
+
+        // Rewriting the stances would lose any the teams can't express, so only do it if a team changed
+        let players = world.read_resource::<Players>();
+        let teams: Vec<u8> = scenario.player_ids()
+            .into_iter()
+            .map(|player_id| players.player(player_id).map(|player| player.team).unwrap_or(*player_id))
+            .collect();
+        if teams != scenario.player_teams() {
+            scenario.set_player_teams(&teams);
+        }
+
+        let stockpiles = world.read_resource::<Stockpiles>();
+        for player_id in scenario.player_ids().into_iter().filter(|player_id| **player_id != 0) {
+            let amount = |resource_type| f32::from(stockpiles.amount(player_id, resource_type));
+            scenario.set_player_resources(scn::PlayerResources {
+                player_id: player_id,
+                food: amount(ResourceType::Food),
+                wood: amount(ResourceType::Wood),
+                gold: amount(ResourceType::Gold),
+                stone: amount(ResourceType::Stone),
+            });
+        }
+        scenario
+    }
+
+    fn handle_mouse(&mut self) {
+        let (left, right, mouse_ray) = {
+            let world = self.scenario_state.planner().mut_world();
+            let mouse_state = world.read_resource::<MouseState>();
+            let mouse_ray = calculate_mouse_ray(&*world.read_resource::<Viewport>(),
+                                                &*mouse_state,
+                                                &*world.read_resource::<ViewProjector>(),
+                                                &*world.read_resource::<Terrain>());
+            (mouse_state.key_states.key_state(MouseButton::Left),
+             mouse_state.key_states.key_state(MouseButton::Right),
+             mouse_ray)
+        };
+        if !is_down(left) && !is_down(right) {
+            self.last_tile = None;
+            self.finish_stroke();
+            return;
+        }
+
+        let tile: (i32, i32) = (mouse_ray.world_coord.y.into(), mouse_ray.world_coord.x.into());
+        let pressed = left == KeyState::TransitionDown || right == KeyState::TransitionDown;
+        match self.tool {
+            Tool::Terrain => {
+                if right == KeyState::TransitionDown {
+                    self.pick_terrain(tile);
+                } else if is_down(left) && (pressed || self.last_tile != Some(tile)) {
+                    self.apply_brush(tile);
+                }
+            }
+            Tool::Elevation => {
+                if pressed {
+                    let elevation = self.tile_elevation(tile);
+                    self.stroke_elevation = if is_down(left) {
+                        elevation + 1
+                    } else {
+                        elevation - 1
+                    };
+                }
+                if pressed || self.last_tile != Some(tile) {
+                    self.apply_brush(tile);
+                }
+            }
+            Tool::Units => {
+                if left == KeyState::TransitionDown {
+                    self.place_unit(mouse_ray.world_coord);
+                } else if right == KeyState::TransitionDown {
+                    self.delete_unit(&mouse_ray);
+                }
+            }
+            Tool::Players => {}
+        }
+    }
+
+    fn tile_elevation(&mut self, (row, col): (i32, i32)) -> i32 {
+        let world = self.scenario_state.planner().mut_world();
+        let terrain = world.read_resource::<Terrain>();
+        terrain.tile_at_row_col(row, col).elevation as i32
+    }
+
+    fn pick_terrain(&mut self, (row, col): (i32, i32)) {
+        let terrain_id = {
+            let world = self.scenario_state.planner().mut_world();
+            let terrain = world.read_resource::<Terrain>();
+            terrain.tile_at_row_col(row, col).terrain_id
+        };
+        if let Some(index) = self.terrain_ids.iter().position(|&id| id == terrain_id) {
+            self.terrain_index = index;
+        }
+    }
+
+    fn apply_brush(&mut self, (row, col): (i32, i32)) {
+        self.last_tile = Some((row, col));
+        let terrain_id = match self.terrain_ids.get(self.terrain_index) {
+            Some(&terrain_id) => terrain_id,
+            None => return,
+        };
+
+        if self.stroke.is_none() {
+            self.stroke = Some(PaintStroke::new());
+        }
+        let stroke = self.stroke.as_mut().unwrap();
+        let world = self.scenario_state.planner().mut_world();
+        let mut terrain = world.write_resource::<Terrain>();
+        for (row, col) in brush_tiles(row, col, self.brush_size, terrain.width(), terrain.height()) {
+            stroke.remember(&terrain, (row, col));
+            match self.tool {
+                Tool::Terrain => terrain.set_terrain_id(row, col, terrain_id),
+                Tool::Elevation => terrain.set_elevation(row, col, self.stroke_elevation),
+                Tool::Units | Tool::Players => {}
+            }
+        }
+    }
+
+    /// Pushes what's been painted since the mouse button went down onto the undo stack
+    fn finish_stroke(&mut self) {
+        if let Some(mut stroke) = self.stroke.take() {
+            let world = self.scenario_state.planner().mut_world();
+            stroke.finish(&*world.read_resource::<Terrain>());
+            self.history.push(Box::new(stroke));
+        }
+    }
+
+    /// Changes the current player's team or starting resources as an edit that can be undone.
+    /// Gaia has neither, so it can't be edited.
+    fn edit_player<F: FnOnce(&mut PlayerState)>(&mut self, change: F) {
+        if *self.player_id == 0 {
+            return;
+        }
+        let player_id = self.player_id;
+        let world = self.scenario_state.planner().mut_world();
+        let before = match PlayerState::of(world, player_id) {
+            Some(state) => state,
+            None => return,
+        };
+        let mut after = before;
+        change(&mut after);
+        if after != before {
+            after.apply(world, player_id);
+            self.history.push(Box::new(PlayerEdit {
+                player_id: player_id,
+                before: before,
+                after: after,
+            }));
+        }
+    }
+
+    fn civilization_id(&mut self) -> CivilizationId {
+        let world = self.scenario_state.planner().mut_world();
+        let players = world.read_resource::<Players>();
+        match players.player(self.player_id).map(|player| player.civ_id) {
+            Some(civ_id) if *civ_id != 0 => civ_id,
+            _ => DEFAULT_CIVILIZATION_ID.into(),
+        }
+    }
+
+    /// Every unit the current player's civilization has, by ID
+    fn placeable_units(&mut self) -> Vec<UnitId> {
+        let civ_id = self.civilization_id();
+        self.empires
+            .civilization(civ_id)
+            .units()
+            .into_iter()
+            .filter(|unit| unit.enabled)
+            .map(|unit| unit.id)
+            .collect()
+    }
+
+    fn selected_unit(&mut self) -> Option<UnitId> {
+        let unit_ids = self.placeable_units();
+        if unit_ids.is_empty() {
+            None
+        } else {
+            Some(unit_ids[self.unit_index % unit_ids.len()])
+        }
+    }
+
+    /// Places the selected unit at the given position, unless it would overlap something.
+    /// Buildings snap to the middle of the tile.
+    fn place_unit(&mut self, position: Vector3) {
+        let unit_id = match self.selected_unit() {
+            Some(unit_id) => unit_id,
+            None => return,
+        };
+        let civ_id = self.civilization_id();
+        let unit_info = self.empires.unit(civ_id, unit_id);
+
+        let mut position = position;
+        if unit_info.is_building() {
+            let half: Fixed = Fixed::from(1) / 2.into();
+            position.x = position.x.truncate() + half;
+            position.y = position.y.truncate() + half;
+        }
+
+        let world = self.scenario_state.planner().mut_world();
+        if unit::blocks_tiles(unit_info) {
+            let occupied_tiles = world.read_resource::<OccupiedTiles>();
+            let footprint = unit::footprint_tiles(unit_info, &TransformComponent::new(position, 0.into()));
+            if footprint.iter().any(|tile| occupied_tiles.is_occupied(tile)) {
+                return;
+            }
+        }
+
+        let spawn = UnitSpawn {
+            player_id: self.player_id,
+            civilization_id: civ_id,
+            unit_id: unit_id,
+            position: position,
+            rotation: 0.into(),
+            lifetime: None,
+        };
+        if let Some(spawn) = create_unit(world, &spawn) {
+            self.history.push(Box::new(UnitEdit {
+                spawn: spawn,
+                placed: true,
+            }));
+        }
+    }
+
+    /// Deletes the first unit under the mouse
+    fn delete_unit(&mut self, mouse_ray: &MouseRay) {
+        let world = self.scenario_state.planner().mut_world();
+        let (entity, spawn) = {
+            let entities = world.entities();
+            let units = world.read::<UnitComponent>();
+            let transforms = world.read::<TransformComponent>();
+            let empires = &self.empires;
+            let found = (&entities, &units, &transforms).iter().find(|&(_, unit, transform)| {
+                let unit_info = empires.unit(unit.civilization_id, unit.unit_id);
+                unit::selection_box(unit_info, transform)
+                    .intersects_ray(&mouse_ray.origin, &mouse_ray.direction)
+            });
+            match found {
+                Some((entity, unit, transform)) => (entity, unit_spawn(unit, transform)),
+                None => return,
+            }
+        };
+
+        remove_entity(world, entity);
+        self.history.push(Box::new(UnitEdit {
+            spawn: spawn,
+            placed: false,
+        }));
+    }
+
+    fn toolbar_lines(&mut self) -> Vec<String> {
+        let player_name = {
+            let world = self.scenario_state.planner().mut_world();
+            let players = world.read_resource::<Players>();
+            players.name(self.player_id)
+        };
+
+        let mut lines = vec![format!("Player: {} (1-8, home for gaia)", player_name)];
+        match self.tool {
+            Tool::Terrain => {
+                let terrain_name = match self.terrain_ids.get(self.terrain_index) {
+                    Some(&terrain_id) => self.empires.terrain(terrain_id).name().to_string(),
+                    None => "none".to_string(),
+                };
+                lines.push(format!("Tool: terrain (tab to change), painting {} (-/=)", terrain_name));
+            }
+            Tool::Elevation => {
+                lines.push("Tool: elevation (tab to change), left click raises, right click lowers"
+                    .to_string());
+            }
+            Tool::Units => {
+                let civ_id = self.civilization_id();
+                let unit_name = match self.selected_unit() {
+                    Some(unit_id) => self.empires.unit(civ_id, unit_id).name().to_string(),
+                    None => "none".to_string(),
+                };
+                lines.push(format!("Tool: units (tab to change), placing {} (-/=)", unit_name));
+            }
+            Tool::Players => {
+                let (player_id, resource_index) = (self.player_id, self.resource_index);
+                let world = self.scenario_state.planner().mut_world();
+                match PlayerState::of(world, player_id) {
+                    Some(ref state) if *player_id != 0 => {
+                        lines.push(format!("Tool: players (tab to change), on team {} (t)", state.team));
+                        let amounts: Vec<String> = STARTING_RESOURCES.iter()
+                            .zip(state.amounts.iter())
+                            .enumerate()
+                            .map(|(index, (&(name, _), &amount))| {
+                                let marker = if index == resource_index { "*" } else { "" };
+                                format!("{}{}: {}", marker, name, i32::from(amount))
+                            })
+                            .collect();
+                        lines.push(format!("Starts with {} (page up/down, -/=)", amounts.join("   ")));
+                    }
+                    _ => lines.push("Tool: players (tab to change), pick a player to edit (1-8)".to_string()),
+                }
+            }
+        }
+        if self.tool == Tool::Terrain || self.tool == Tool::Elevation {
+            lines.push(format!("Brush: {} (page up/down)", self.brush_size));
+        }
+        if self.history.can_undo() || self.history.can_redo() {
+            lines.push("Undo: ctrl+z, redo: ctrl+y".to_string());
+        }
+        lines.push("Save: ctrl+s, load: ctrl+o".to_string());
+        if let Some(status) = self.toolbar.status() {
+            lines.push(status.to_string());
+        }
+        lines
+    }
+}
+
+impl GameState for EditorGameState {
+    fn start(&mut self) {}
+
+    fn stop(&mut self) {}
+
+    fn update(&mut self, time_step: Fixed) -> bool {
+        self.scenario_state.update_input_resources(time_step);
+        self.handle_toolbar();
+        self.handle_keys();
+        self.handle_mouse();
+        self.scenario_state.tick(time_step);
+        true
+    }
+
+    fn render(&mut self, lerp: Fixed) {
+        self.scenario_state.render(lerp);
+        let lines = self.toolbar_lines();
+        self.toolbar.select(self.tool.button());
+        self.toolbar.set_enabled(ToolbarButton::Undo, self.history.can_undo());
+        self.toolbar.set_enabled(ToolbarButton::Redo, self.history.can_redo());
+        hud::render_editor_toolbar(self.media.borrow_mut().renderer(), &self.toolbar, &lines);
+    }
+}
+
+/// A tile's terrain and elevation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct TileState {
+    terrain_id: TerrainId,
+    elevation: i32,
+}
+
+impl TileState {
+    fn of(terrain: &Terrain, (row, col): (i32, i32)) -> TileState {
+        let tile = terrain.tile_at_row_col(row, col);
+        TileState {
+            terrain_id: tile.terrain_id,
+            elevation: tile.elevation as i32,
+        }
+    }
+
+    fn apply(&self, terrain: &mut Terrain, (row, col): (i32, i32)) {
+        terrain.set_terrain_id(row, col, self.terrain_id);
+        terrain.set_elevation(row, col, self.elevation);
+    }
+}
+
+/// Every tile painted while a mouse button was held down, as it was before and after
+struct PaintStroke {
+    tiles: Vec<(i32, i32)>,
+    before: Vec<TileState>,
+    after: Vec<TileState>,
+}
+
+impl PaintStroke {
+    fn new() -> PaintStroke {
+        PaintStroke {
+            tiles: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Keeps track of how a tile is before the stroke first paints it
+    fn remember(&mut self, terrain: &Terrain, tile: (i32, i32)) {
+        if !self.tiles.contains(&tile) {
+            self.tiles.push(tile);
+            self.before.push(TileState::of(terrain, tile));
+        }
+    }
+
+    fn finish(&mut self, terrain: &Terrain) {
+        self.after = self.tiles.iter().map(|&tile| TileState::of(terrain, tile)).collect();
+    }
+}
+
+impl Edit<specs::World> for PaintStroke {
+    fn undo(&mut self, world: &mut specs::World) {
+        let mut terrain = world.write_resource::<Terrain>();
+        for (&tile, state) in self.tiles.iter().zip(&self.before) {
+            state.apply(&mut terrain, tile);
+        }
+    }
+
+    fn redo(&mut self, world: &mut specs::World) {
+        let mut terrain = world.write_resource::<Terrain>();
+        for (&tile, state) in self.tiles.iter().zip(&self.after) {
+            state.apply(&mut terrain, tile);
+        }
+    }
+}
+
+/// A player's team and starting resources (in `STARTING_RESOURCES` order)
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct PlayerState {
+    team: u8,
+    amounts: [Fixed; 4],
+}
+
+impl PlayerState {
+    fn of(world: &specs::World, player_id: PlayerId) -> Option<PlayerState> {
+        let team = match world.read_resource::<Players>().player(player_id) {
+            Some(player) => player.team,
+            None => return None,
+        };
+        let stockpiles = world.read_resource::<Stockpiles>();
+        let mut amounts = [Fixed::from(0); 4];
+        for (amount, &(_, resource_type)) in amounts.iter_mut().zip(STARTING_RESOURCES.iter()) {
+            *amount = stockpiles.amount(player_id, resource_type);
+        }
+        Some(PlayerState {
+            team: team,
+            amounts: amounts,
+        })
+    }
+
+    fn apply(&self, world: &mut specs::World, player_id: PlayerId) {
+        world.write_resource::<Players>().set_team(player_id, self.team);
+        let mut stockpiles = world.write_resource::<Stockpiles>();
+        for (&amount, &(_, resource_type)) in self.amounts.iter().zip(STARTING_RESOURCES.iter()) {
+            let current = stockpiles.amount(player_id, resource_type);
+            stockpiles.add(player_id, resource_type, amount - current);
+        }
+    }
+}
+
+/// Changing a player's team or one of their starting resources
+struct PlayerEdit {
+    player_id: PlayerId,
+    before: PlayerState,
+    after: PlayerState,
+}
+
+impl Edit<specs::World> for PlayerEdit {
+    fn undo(&mut self, world: &mut specs::World) {
+        self.before.apply(world, self.player_id);
+    }
+
+    fn redo(&mut self, world: &mut specs::World) {
+        self.after.apply(world, self.player_id);
+    }
+}
+
+/// Placing a unit, or deleting one. Undoing and redoing creates the unit again as a different
+/// entity, so units are found again by what and where they are rather than by entity.
+struct UnitEdit {
+    spawn: UnitSpawn,
+    placed: bool,
+}
+
+impl UnitEdit {
+    fn set_placed(&self, world: &mut specs::World, placed: bool) {
+        if placed {
+            create_unit(world, &self.spawn);
+        } else if let Some(entity) = find_unit(world, &self.spawn) {
+            remove_entity(world, entity);
+        }
+    }
+}
+
+impl Edit<specs::World> for UnitEdit {
+    fn undo(&mut self, world: &mut specs::World) {
+        self.set_placed(world, !self.placed);
+    }
+
+    fn redo(&mut self, world: &mut specs::World) {
+        self.set_placed(world, self.placed);
+    }
+}
+
+/// Creates the unit; returns a spawn that creates it again exactly (the factory can hand out
+/// an upgraded unit instead of the one asked for)
+fn create_unit(world: &mut specs::World, spawn: &UnitSpawn) -> Option<UnitSpawn> {
+    let unit_factory = world.read_resource::<UnitFactory>().clone();
+    unit_factory.create(world, spawn).map(|entity| {
+        let mut spawn = spawn.clone();
+        if let Some(unit) = world.read::<UnitComponent>().get(entity) {
+            spawn.unit_id = unit.unit_id;
+        }
+        spawn
+    })
+}
+
+fn find_unit(world: &mut specs::World, spawn: &UnitSpawn) -> Option<specs::Entity> {
+    let entities = world.entities();
+    let units = world.read::<UnitComponent>();
+    let transforms = world.read::<TransformComponent>();
+    let found = (&entities, &units, &transforms).iter().find(|&(_, unit, transform)| {
+        unit.player_id == spawn.player_id && unit.unit_id == spawn.unit_id &&
+        *transform.position() == spawn.position
+    });
+    found.map(|(entity, _, _)| entity)
+}
+
+fn unit_spawn(unit: &UnitComponent, transform: &TransformComponent) -> UnitSpawn {
+    UnitSpawn {
+        player_id: unit.player_id,
+        civilization_id: unit.civilization_id,
+        unit_id: unit.unit_id,
+        position: *transform.position(),
+        rotation: transform.rotation,
+        lifetime: None,
+    }
+}
+
+fn remove_entity(world: &mut specs::World, entity: specs::Entity) {
+    world.write_resource::<GridPartition>().remove_entity(entity.get_id());
+    world.write_resource::<OccupiedTiles>().release(entity.get_id());
+    world.delete_now(entity);
+}
+
+fn is_down(key_state: KeyState) -> bool {
+    key_state == KeyState::TransitionDown || key_state == KeyState::Down
+}
+
+/// Moves an index into a list of the given length by the given amount, wrapping around the ends
+fn step(index: usize, len: usize, amount: usize, forward: bool) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let amount = amount % len;
+    if forward {
+        (index + amount) % len
+    } else {
+        (index + len - amount) % len
+    }
+}
+
+/// The tiles covered by a square brush of the given size centered on the given tile (odd sizes
+/// are centered exactly; even ones lean toward the bottom right), clipped to the map
+fn brush_tiles(row: i32, col: i32, size: i32, width: i32, height: i32) -> Vec<(i32, i32)> {
+    let start_offset = (size - 1) / 2;
+    let mut tiles = Vec::new();
+    for r in cmp::max(0, row - start_offset)..cmp::min(height, row - start_offset + size) {
+        for c in cmp::max(0, col - start_offset)..cmp::min(width, col - start_offset + size) {
+            tiles.push((r, c));
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{brush_tiles, step};
+
+    #[test]
+    fn test_brush_tiles() {
+        assert_eq!(vec![(5, 5)], brush_tiles(5, 5, 1, 10, 10));
+        assert_eq!(vec![(4, 4), (4, 5), (4, 6), (5, 4), (5, 5), (5, 6), (6, 4), (6, 5), (6, 6)],
+                   brush_tiles(5, 5, 3, 10, 10));
+        assert_eq!(vec![(5, 5), (5, 6), (6, 5), (6, 6)], brush_tiles(5, 5, 2, 10, 10));
+    }
+
+    #[test]
+    fn test_brush_tiles_clipped() {
+        assert_eq!(vec![(0, 0), (0, 1), (1, 0), (1, 1)], brush_tiles(0, 0, 3, 10, 10));
+        assert_eq!(vec![(9, 9)], brush_tiles(9, 9, 2, 10, 10));
+        assert!(brush_tiles(-5, -5, 3, 10, 10).is_empty());
+    }
+
+    #[test]
+    fn test_step() {
+        assert_eq!(1, step(0, 5, 1, true));
+        assert_eq!(0, step(4, 5, 1, true));
+        assert_eq!(4, step(0, 5, 1, false));
+        assert_eq!(3, step(3, 5, 10, true));
+        assert_eq!(2, step(0, 5, 13, false));
+        assert_eq!(0, step(0, 0, 1, true));
+    }
+}
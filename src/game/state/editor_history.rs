@@ -0,0 +1,156 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::VecDeque;
+
+/// A change that the editor can undo and redo. Edits are made directly on whatever they
+/// change (the world, for the scenario editor) and are never recorded as game commands,
+/// so the simulation doesn't know or care that they exist.
+pub trait Edit<T> {
+    fn undo(&mut self, target: &mut T);
+    fn redo(&mut self, target: &mut T);
+}
+
+/// The editor's undo and redo stacks. Only the most recent `limit` edits can be undone;
+/// older ones fall off the bottom of the stack.
+pub struct EditHistory<T> {
+    limit: usize,
+    undo_stack: VecDeque<Box<Edit<T>>>,
+    redo_stack: Vec<Box<Edit<T>>>,
+}
+
+impl<T> EditHistory<T> {
+    pub fn new(limit: usize) -> EditHistory<T> {
+        EditHistory {
+            limit: limit,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Records an edit that has already been made. Making a new edit forgets anything
+    /// that was undone, since it can't be redone on top of the new edit.
+    pub fn push(&mut self, edit: Box<Edit<T>>) {
+        self.redo_stack.clear();
+        self.undo_stack.push_back(edit);
+        while self.undo_stack.len() > self.limit {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Undoes the most recent edit; returns false if there was nothing to undo
+    pub fn undo(&mut self, target: &mut T) -> bool {
+        match self.undo_stack.pop_back() {
+            Some(mut edit) => {
+                edit.undo(target);
+                self.redo_stack.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redoes the most recently undone edit; returns false if there was nothing to redo
+    pub fn redo(&mut self, target: &mut T) -> bool {
+        match self.redo_stack.pop() {
+            Some(mut edit) => {
+                edit.redo(target);
+                self.undo_stack.push_back(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edit, EditHistory};
+
+    struct Push(i32);
+
+    impl Edit<Vec<i32>> for Push {
+        fn undo(&mut self, target: &mut Vec<i32>) {
+            assert_eq!(Some(self.0), target.pop());
+        }
+
+        fn redo(&mut self, target: &mut Vec<i32>) {
+            target.push(self.0);
+        }
+    }
+
+    fn push(history: &mut EditHistory<Vec<i32>>, target: &mut Vec<i32>, value: i32) {
+        let mut edit = Push(value);
+        edit.redo(target);
+        history.push(Box::new(edit));
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (mut history, mut target) = (EditHistory::new(10), Vec::new());
+        push(&mut history, &mut target, 1);
+        push(&mut history, &mut target, 2);
+
+        assert!(history.undo(&mut target));
+        assert_eq!(vec![1], target);
+        assert!(history.undo(&mut target));
+        assert!(target.is_empty());
+        assert!(!history.undo(&mut target));
+
+        assert!(history.redo(&mut target));
+        assert!(history.redo(&mut target));
+        assert_eq!(vec![1, 2], target);
+        assert!(!history.redo(&mut target));
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let (mut history, mut target) = (EditHistory::new(10), Vec::new());
+        push(&mut history, &mut target, 1);
+        history.undo(&mut target);
+        assert!(history.can_redo());
+
+        push(&mut history, &mut target, 2);
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut target));
+        assert_eq!(vec![2], target);
+    }
+
+    #[test]
+    fn test_limit() {
+        let (mut history, mut target) = (EditHistory::new(2), Vec::new());
+        for value in 1..5 {
+            push(&mut history, &mut target, value);
+        }
+        assert!(history.undo(&mut target));
+        assert!(history.undo(&mut target));
+        assert!(!history.can_undo());
+        assert_eq!(vec![1, 2], target);
+    }
+}
@@ -33,6 +33,7 @@ pub trait GameState {
 
     /// Called once per render cycle. Multiple or fractional updates can
     /// occur between render calls, so an interpolator (lerp) is passed in
-    /// so that smooth rendering can be achieved.
+    /// so that smooth rendering can be achieved. The lerp is how far along
+    /// the next update is, from 0 (just updated) up to 1.
     fn render(&mut self, lerp: Fixed);
 }
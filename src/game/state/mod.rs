@@ -19,8 +19,13 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+mod editor_game_state;
+mod editor_history;
 mod game_state;
+mod replay_game_state;
 mod scenario_game_state;
 
+pub use self::editor_game_state::EditorGameState;
 pub use self::game_state::GameState;
+pub use self::replay_game_state::ReplayGameState;
 pub use self::scenario_game_state::ScenarioGameState;
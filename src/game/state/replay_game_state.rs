@@ -0,0 +1,268 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::CommandLog;
+use ecs::{CameraComponent, TransformComponent, WorldSnapshot};
+use ecs::resource::{ActionBatcher, FogOfWar, Script, SimulationTick};
+use game::{Game, GameState};
+use game::headless;
+use media::{Key, KeyState, MediaRef};
+use scn;
+use specs::{self, Join};
+use super::scenario_game_state::ScenarioGameState;
+use types::Fixed;
+
+/// How often the viewer checkpoints the game state while playing (10 seconds of game time)
+const CHECKPOINT_INTERVAL_TICKS: u64 = 600;
+
+/// How far the seek keys jump (30 seconds of game time)
+const SEEK_TICKS: u64 = 1800;
+
+/// Playback speeds, in quarters of normal speed
+const PLAYBACK_SPEEDS: [u32; 6] = [1, 2, 4, 8, 16, 32];
+const NORMAL_SPEED_INDEX: usize = 2;
+
+/// Keeps track of how many ticks to run per update at the chosen playback speed
+#[derive(Clone, Debug)]
+struct PlaybackSpeed {
+    index: usize,
+    paused: bool,
+    quarter_ticks: u32,
+}
+
+impl PlaybackSpeed {
+    fn new() -> PlaybackSpeed {
+        PlaybackSpeed {
+            index: NORMAL_SPEED_INDEX,
+            paused: false,
+            quarter_ticks: 0,
+        }
+    }
+
+    fn faster(&mut self) {
+        if self.index + 1 < PLAYBACK_SPEEDS.len() {
+            self.index += 1;
+        }
+    }
+
+    fn slower(&mut self) {
+        if self.index > 0 {
+            self.index -= 1;
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Number of simulation ticks to run for one real update
+    fn ticks_this_update(&mut self) -> u32 {
+        if self.paused {
+            return 0;
+        }
+        self.quarter_ticks += PLAYBACK_SPEEDS[self.index];
+        let ticks = self.quarter_ticks / 4;
+        self.quarter_ticks %= 4;
+        ticks
+    }
+}
+
+/// Plays back a recorded game. Everyone watches as an observer (the number keys switch whose
+/// perspective is shown), and the viewer checkpoints the game as it goes so that it can seek.
+///
+/// Controls: space pauses, minus/equals change the speed, page up/down seek backward/forward,
+/// home goes back to the start, and F toggles the fog of war.
+pub struct ReplayGameState {
+    media: MediaRef,
+    scenario_state: ScenarioGameState,
+    speed: PlaybackSpeed,
+    time_step: Fixed,
+    /// Whether the last update ran the simulation; when it didn't (paused or slow playback),
+    /// nothing is moving so there's nothing to interpolate between
+    ticked: bool,
+    /// In tick order; the first one is always the start of the game
+    checkpoints: Vec<WorldSnapshot>,
+}
+
+impl ReplayGameState {
+    pub fn new(g: &Game,
+               scenario: scn::Scenario,
+               commands: CommandLog,
+               scripts: &[Script])
+               -> ReplayGameState {
+        let mut setup = headless::recorded_setup(&commands);
+        setup.observer = true;
+        setup.scripts = scripts.to_vec();
+
+        let mut scenario_state = ScenarioGameState::new(g, scenario, &setup);
+        let checkpoint = {
+            let world = scenario_state.planner().mut_world();
+            world.write_resource::<ActionBatcher>().play_back(commands);
+            WorldSnapshot::capture(world)
+        };
+
+        ReplayGameState {
+            media: g.media(),
+            scenario_state: scenario_state,
+            speed: PlaybackSpeed::new(),
+            time_step: 0.into(),
+            ticked: false,
+            checkpoints: vec![checkpoint],
+        }
+    }
+
+    fn current_tick(&mut self) -> u64 {
+        self.scenario_state.planner().mut_world().read_resource::<SimulationTick>().tick
+    }
+
+    fn tick(&mut self) {
+        self.scenario_state.tick(self.time_step);
+
+        let tick = self.current_tick();
+        let last_checkpoint_tick = self.checkpoints.last().map(|c| c.tick()).unwrap_or(0);
+        if tick % CHECKPOINT_INTERVAL_TICKS == 0 && tick > last_checkpoint_tick {
+            let checkpoint = WorldSnapshot::capture(self.scenario_state.planner().mut_world());
+            self.checkpoints.push(checkpoint);
+        }
+    }
+
+    /// Jumps to the given tick by restoring the closest checkpoint before it (when going backward)
+    /// and simulating the rest of the way
+    fn seek(&mut self, target_tick: u64) {
+        if target_tick < self.current_tick() {
+            let index = self.checkpoints.iter().rposition(|c| c.tick() <= target_tick).unwrap_or(0);
+            let world = self.scenario_state.planner().mut_world();
+
+            // The camera belongs to the viewer rather than the game, so it stays where it is
+            let camera_transform = camera_transform(world);
+            self.checkpoints[index].restore(world);
+            if let Some(camera_transform) = camera_transform {
+                set_camera_transform(world, camera_transform);
+            }
+        }
+        while self.current_tick() < target_tick {
+            self.tick();
+        }
+    }
+
+    fn handle_controls(&mut self) {
+        let keys = self.media.borrow().key_states().clone();
+        let pressed = |key| keys.key_state(key) == KeyState::TransitionDown;
+
+        if pressed(Key::Space) {
+            self.speed.toggle_pause();
+        }
+        if pressed(Key::Equals) {
+            self.speed.faster();
+        }
+        if pressed(Key::Minus) {
+            self.speed.slower();
+        }
+        if pressed(Key::F) {
+            let world = self.scenario_state.planner().mut_world();
+            let mut fog = world.write_resource::<FogOfWar>();
+            let revealed = fog.is_revealed();
+            fog.set_revealed(!revealed);
+        }
+
+        let tick = self.current_tick();
+        if pressed(Key::PageUp) {
+            self.seek(tick.saturating_sub(SEEK_TICKS));
+        } else if pressed(Key::PageDown) {
+            self.seek(tick + SEEK_TICKS);
+        } else if pressed(Key::Home) {
+            self.seek(0);
+        }
+    }
+}
+
+fn camera_transform(world: &specs::World) -> Option<TransformComponent> {
+    let cameras = world.read::<CameraComponent>();
+    let transforms = world.read::<TransformComponent>();
+    (&cameras, &transforms).iter().map(|(_, transform)| transform.clone()).next()
+}
+
+fn set_camera_transform(world: &mut specs::World, camera_transform: TransformComponent) {
+    let cameras = world.read::<CameraComponent>();
+    let mut transforms = world.write::<TransformComponent>();
+    for (_, transform) in (&cameras, &mut transforms).iter() {
+        *transform = camera_transform.clone();
+    }
+}
+
+impl GameState for ReplayGameState {
+    fn start(&mut self) {}
+
+    fn stop(&mut self) {}
+
+    fn update(&mut self, time_step: Fixed) -> bool {
+        self.time_step = time_step;
+        self.scenario_state.update_input_resources(time_step);
+        self.handle_controls();
+        let ticks = self.speed.ticks_this_update();
+        for _ in 0..ticks {
+            self.tick();
+        }
+        self.ticked = ticks > 0;
+        true
+    }
+
+    fn render(&mut self, lerp: Fixed) {
+        let lerp = if self.ticked { lerp } else { 1.into() };
+        self.scenario_state.render(lerp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlaybackSpeed;
+
+    fn ticks_over(speed: &mut PlaybackSpeed, updates: usize) -> u32 {
+        (0..updates).map(|_| speed.ticks_this_update()).sum()
+    }
+
+    #[test]
+    fn test_playback_speed() {
+        let mut speed = PlaybackSpeed::new();
+        assert_eq!(8, ticks_over(&mut speed, 8));
+
+        speed.faster();
+        assert_eq!(16, ticks_over(&mut speed, 8));
+
+        speed.slower();
+        speed.slower();
+        speed.slower();
+        assert_eq!(2, ticks_over(&mut speed, 8));
+
+        // Can't go any slower than the slowest speed
+        speed.slower();
+        assert_eq!(2, ticks_over(&mut speed, 8));
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut speed = PlaybackSpeed::new();
+        speed.toggle_pause();
+        assert_eq!(0, ticks_over(&mut speed, 8));
+        speed.toggle_pause();
+        assert_eq!(8, ticks_over(&mut speed, 8));
+    }
+}
@@ -19,28 +19,582 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use ecs;
-use ecs::resource::{KeyboardKeyStates, MouseState, RenderCommands, Viewport};
-use game::{Game, GameState};
-use media::MediaRef;
+use action::{Action, CommandLogEntry};
+use dat::{EmpiresDb, EmpiresDbRef};
+use ecs::{self, GameSettings, GameSetup, OnScreenComponent, TransformComponent, UnitComponent, WorldSnapshot};
+use ecs::resource::{ActionBatcher, Announcements, BuildMenu, DeleteConfirmation, FogOfWar, GameRng,
+                    KeyboardKeyStates, Messages, MouseState, PlayerTechs, Players, RenderCommands, Scores,
+                    Script, Scripts, SimulationTick, Stockpiles, Terrain, Timeline, Victory, ViewProjector,
+                    Viewport};
+use game::{AutoSaver, Game, GameState};
+use game::capture::timestamp;
+use game::crash_report;
+use game::file_watcher::FileWatcher;
+use game::hud;
+use game::keyboard_cursor::KeyboardCursor;
+use game::minimap::Minimap;
+use game::pause_menu::{PauseMenu, PauseMenuItem};
+use game::save::{self, GameSource, SAVE_DIR, SAVE_EXTENSION, SaveFile, SaveGame};
+use game::tech_tree::{self, TechTree};
+use media::{Key, KeyState, KeyStates, MediaRef, MouseButton};
 use nalgebra::{Vector2, convert};
-use resource::ShapeManagerRef;
+use net::{COMMAND_DELAY_TICKS, Lobby, LockstepSession, Turn};
+use resource::{ShapeManagerRef, ShapeMetadataStoreRef};
 use scn;
+use specs::Join;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 use types::Fixed;
 
+/// How often (in updates) to check whether any hot reloaded files changed
+const HOT_RELOAD_CHECK_UPDATES: u32 = 60;
+
+/// Files that are loaded again when they change on disk (see `enable_hot_reload`)
+struct HotReload {
+    empires_file: PathBuf,
+    script_files: Vec<PathBuf>,
+    watcher: FileWatcher,
+    updates_until_check: u32,
+}
+
 pub struct ScenarioGameState {
     media: MediaRef,
+    empires: EmpiresDbRef,
     shape_manager: ShapeManagerRef,
+    shape_metadata: ShapeMetadataStoreRef,
+    scenario: scn::Scenario,
+    setup: GameSetup,
     planner: ecs::WorldPlanner,
+    record_file: Option<PathBuf>,
+    timeline_file: Option<PathBuf>,
+    hot_reload: Option<HotReload>,
+    auto_saver: Option<AutoSaver>,
+
+    /// Whether the objectives panel is open (see `hud::render_messages`)
+    messages_open: bool,
+
+    /// Open while the player is looking at the tech tree, which takes over the mouse and keyboard
+    tech_tree: Option<TechTree>,
+
+    /// Escape opens it; single player games are paused while it's open
+    pause_menu: PauseMenu,
+
+    /// Stands in for the mouse when playing from the keyboard alone (see `use_keyboard_cursor`)
+    keyboard_cursor: Option<KeyboardCursor>,
+
+    /// Where the game was started from, without which it can't be saved (see `enable_saving`)
+    source: Option<GameSource>,
+
+    /// The saves listed in the pause menu after picking Load game
+    save_files: Vec<SaveFile>,
+
+    /// What LAN games are played over (see `play_over_lan`)
+    session: Option<LockstepSession>,
+
+    /// Starts over whenever the world does, since the chunk revisions it keeps up with do too
+    minimap: Minimap,
 }
 
 impl ScenarioGameState {
-    pub fn new(g: &Game, scenario: scn::Scenario) -> ScenarioGameState {
+    pub fn new(g: &Game, scenario: scn::Scenario, setup: &GameSetup) -> ScenarioGameState {
+        let planner = ecs::create_world_planner(g.media(),
+                                                g.empires_db(),
+                                                g.shape_metadata(),
+                                                &scenario,
+                                                setup);
         ScenarioGameState {
             media: g.media(),
+            empires: g.empires_db(),
             shape_manager: g.shape_manager(),
-            planner: ecs::create_world_planner(g.media(), g.empires_db(), g.shape_metadata(), &scenario),
+            shape_metadata: g.shape_metadata(),
+            scenario: scenario,
+            setup: setup.clone(),
+            planner: planner,
+            record_file: None,
+            timeline_file: None,
+            hot_reload: None,
+            auto_saver: None,
+            messages_open: false,
+            tech_tree: None,
+            pause_menu: PauseMenu::new(),
+            keyboard_cursor: None,
+            source: None,
+            save_files: Vec::new(),
+            session: None,
+            minimap: Minimap::new(),
+        }
+    }
+
+    /// Plays a LAN game over the session: what the local player does is held back to be sent
+    /// to everyone, and each tick waits until everyone's commands for it have arrived
+    pub fn play_over_lan(&mut self, session: LockstepSession) {
+        self.planner.mut_world().write_resource::<ActionBatcher>().delay_actions(COMMAND_DELAY_TICKS);
+        self.session = Some(session);
+    }
+
+    /// Writes every command issued during the game to the given file when the state stops,
+    /// so that the game can be replayed (see headless mode).
+    pub fn record_to(&mut self, record_file: PathBuf) {
+        self.record_file = Some(record_file);
+    }
+
+    fn write_recording(&mut self) {
+        if let Some(ref record_file) = self.record_file {
+            let world = self.planner.mut_world();
+            let mut commands = world.read_resource::<ActionBatcher>().history().clone();
+            commands.set_seed(world.read_resource::<GameRng>().seed());
+            commands.set_settings(&world.read_resource::<GameSettings>().to_field());
+            if let Err(err) = commands.write_to_file(record_file) {
+                log_error!("Failed to write command log \"{}\": {}", record_file.display(), err);
+            }
+        }
+    }
+
+    /// Lets the arrow keys move the cursor (`speed` pixels a second) and Enter and slash click, so
+    /// the game can be played without a mouse
+    pub fn use_keyboard_cursor(&mut self, speed: u32) {
+        self.keyboard_cursor = Some(KeyboardCursor::new(speed));
+    }
+
+    /// Lets the game be saved (from the pause menu and by auto-saves), and lists the saves of
+    /// games started from the same files for loading
+    pub fn enable_saving(&mut self, source: GameSource) {
+        self.source = Some(source);
+    }
+
+    /// Saves the game every `interval_minutes` minutes of game time, keeping the latest `keep` saves.
+    /// Needs saving to be enabled too.
+    pub fn auto_save(&mut self, interval_minutes: u32, keep: usize) {
+        self.auto_saver = Some(AutoSaver::new(interval_minutes, keep));
+    }
+
+    /// Writes the per-minute samples of every player to the given file (as JSON) when the state stops
+    pub fn export_timeline_to(&mut self, timeline_file: PathBuf) {
+        self.timeline_file = Some(timeline_file);
+    }
+
+    fn write_timeline(&mut self) {
+        if let Some(ref timeline_file) = self.timeline_file {
+            let world = self.planner.mut_world();
+            if let Err(err) = world.read_resource::<Timeline>().write_to_file(timeline_file) {
+                log_error!("Failed to write timeline \"{}\": {}", timeline_file.display(), err);
+            }
+        }
+    }
+
+    // TODO: Reload graphics and sounds too
+    /// Development mode: whenever empires.dat or one of the scripts changes on disk, it's loaded
+    /// again and swapped into the running game between ticks. Games whose data changed part way
+    /// through won't play back the same way from their recorded commands.
+    pub fn enable_hot_reload(&mut self, empires_file: PathBuf, script_files: Vec<PathBuf>) {
+        let mut watched_files = script_files.clone();
+        watched_files.push(empires_file.clone());
+        self.hot_reload = Some(HotReload {
+            empires_file: empires_file,
+            script_files: script_files,
+            watcher: FileWatcher::new(watched_files),
+            updates_until_check: HOT_RELOAD_CHECK_UPDATES,
+        });
+    }
+
+    fn check_hot_reload(&mut self) {
+        let changed = match self.hot_reload {
+            Some(ref mut hot_reload) if hot_reload.updates_until_check == 0 => {
+                hot_reload.updates_until_check = HOT_RELOAD_CHECK_UPDATES;
+                hot_reload.watcher.changed()
+            }
+            Some(ref mut hot_reload) => {
+                hot_reload.updates_until_check -= 1;
+                false
+            }
+            None => false,
+        };
+        if changed {
+            if let Err(err) = self.reload() {
+                log_warn!("Not reloading: {}", err);
+            }
+        }
+    }
+
+    /// Rebuilds the world around freshly loaded data. Systems hold on to the empires.dat they
+    /// were created with, so rather than swapping the data out from under them, the world is
+    /// snapshotted and restored into a new one that was created with the new data.
+    fn reload(&mut self) -> Result<(), String> {
+        let (empires_file, script_files) = match self.hot_reload {
+            Some(ref hot_reload) => (hot_reload.empires_file.clone(), hot_reload.script_files.clone()),
+            None => return Ok(()),
+        };
+
+        let empires = EmpiresDbRef::new(try!(EmpiresDb::read_from_file(&empires_file)
+            .map_err(|err| format!("failed to load \"{}\": {}", empires_file.display(), err))));
+        let mut scripts = Vec::new();
+        for script_file in &script_files {
+            scripts.push(try!(Script::read_from_file(script_file)
+                .map_err(|err| format!("failed to load \"{}\": {}", script_file.display(), err))));
+        }
+
+        // Everything in the game has to still exist in the new data
+        {
+            let world = self.planner.mut_world();
+            let units = world.read::<UnitComponent>();
+            for unit in (&units).iter() {
+                if empires.find_unit(unit.civilization_id, unit.unit_id).is_none() {
+                    return Err(format!("unit {:?} of civilization {:?} isn't in the new empires.dat",
+                                       unit.unit_id,
+                                       unit.civilization_id));
+                }
+            }
+        }
+
+        // The snapshot only has the simulation, so where the camera is, who's being observed, and
+        // the terrain and scores come across separately
+        let snapshot = WorldSnapshot::capture(self.planner.mut_world());
+        let (viewport, players, terrain, scores) = {
+            let world = self.planner.mut_world();
+            (world.read_resource::<Viewport>().clone(),
+             world.read_resource::<Players>().clone(),
+             world.read_resource::<Terrain>().with_empires(empires.clone()),
+             world.read_resource::<Scores>().clone())
+        };
+        let mut setup = self.setup.clone();
+        setup.scripts = scripts.clone();
+        let mut planner = ecs::create_world_planner(self.media.clone(),
+                                                    empires.clone(),
+                                                    self.shape_metadata.clone(),
+                                                    &self.scenario,
+                                                    &setup);
+        {
+            let world = planner.mut_world();
+            snapshot.restore(world);
+            *world.write_resource::<Viewport>() = viewport;
+            *world.write_resource::<Players>() = players;
+            *world.write_resource::<Terrain>() = terrain;
+            *world.write_resource::<Scores>() = scores;
+            world.write_resource::<Scripts>().reload(&scripts);
+            world.write_resource::<Announcements>().announce("Reloaded game data".to_string());
+        }
+        self.planner = planner;
+        self.minimap = Minimap::new();
+        self.setup = setup;
+        if let Some(civilization_id) = self.tech_tree.as_ref().map(|tree| tree.civilization_id()) {
+            self.tech_tree = Some(TechTree::new(&empires, civilization_id));
+        }
+        self.empires = empires;
+        self.apply_options();
+        Ok(())
+    }
+
+    /// Lobby games always have players; only single player games can be paused or loaded
+    fn is_single_player(&self) -> bool {
+        self.setup.players.is_empty()
+    }
+
+    /// While a build menu is open, Escape and the letter keys belong to it
+    fn is_build_menu_open(&mut self) -> bool {
+        self.planner.mut_world().read_resource::<BuildMenu>().is_open()
+    }
+
+    /// Escape opens and closes the pause menu (or closes the tech tree, if that's open). While the
+    /// menu is open, the arrow keys and Enter work it, and the game doesn't see any of the input.
+    /// Returns false once the player quits.
+    fn handle_pause_menu_controls(&mut self) -> bool {
+        let pressed_keys: Vec<Key> = {
+            let media = self.media.borrow();
+            let keys = media.key_states();
+            [Key::Escape, Key::Up, Key::Down, Key::Left, Key::Right, Key::Return]
+                .iter()
+                .cloned()
+                .filter(|&key| keys.key_state(key) == KeyState::TransitionDown)
+                .collect()
+        };
+        let pressed = |key| pressed_keys.contains(&key);
+
+        if pressed(Key::Escape) {
+            if self.pause_menu.saves().is_some() {
+                self.pause_menu.close_saves();
+            } else if self.pause_menu.is_open() {
+                self.pause_menu.close();
+            } else if self.tech_tree.is_some() {
+                self.tech_tree = None;
+            } else if !self.is_build_menu_open() {
+                self.open_pause_menu();
+            }
+        }
+        if !self.pause_menu.is_open() {
+            return true;
+        }
+
+        if pressed(Key::Up) {
+            self.pause_menu.move_selection(-1);
+        }
+        if pressed(Key::Down) {
+            self.pause_menu.move_selection(1);
+        }
+        if self.pause_menu.selected_item() == PauseMenuItem::ScrollSpeed {
+            for &(key, offset) in &[(Key::Left, -1), (Key::Right, 1)] {
+                if pressed(key) {
+                    self.pause_menu.change_scroll_speed(offset);
+                    self.apply_options();
+                }
+            }
+        }
+
+        let mut keep_running = true;
+        let item = self.pause_menu.selected_item();
+        if pressed(Key::Return) {
+            if self.pause_menu.saves().is_some() {
+                let save_file = self.save_files[self.pause_menu.selected_save()].clone();
+                self.load_from_file(&save_file);
+            } else if self.pause_menu.is_enabled(item) {
+                keep_running = self.pick(item);
+            }
+        }
+
+        let world = self.planner.mut_world();
+        *world.write_resource::<KeyboardKeyStates>() = KeyStates::new(HashMap::new());
+        let mut mouse_state = world.write_resource::<MouseState>();
+        mouse_state.key_states = KeyStates::new(HashMap::new());
+        mouse_state.drag_start = None;
+        keep_running
+    }
+
+    fn open_pause_menu(&mut self) {
+        let single_player = self.is_single_player();
+        let observing = self.planner.mut_world().read_resource::<Players>().is_observing();
+        let can_save = self.source.is_some();
+        self.pause_menu.set_enabled(PauseMenuItem::SaveGame, can_save);
+        self.pause_menu.set_enabled(PauseMenuItem::LoadGame, single_player && can_save);
+        self.pause_menu.set_enabled(PauseMenuItem::Resign, !observing);
+        self.pause_menu.open();
+    }
+
+    /// Does whatever the picked menu item does; returns false if it's time to quit
+    fn pick(&mut self, item: PauseMenuItem) -> bool {
+        match item {
+            PauseMenuItem::Resume => self.pause_menu.close(),
+            PauseMenuItem::SaveGame => {
+                match self.save_game() {
+                    Ok(file_name) => {
+                        log_info!("Saved the game to {}", file_name.display());
+                        self.pause_menu.set_status("Game saved".to_string());
+                    }
+                    Err(err) => {
+                        log_error!("Failed to save the game: {}", err);
+                        self.pause_menu.set_status("Failed to save the game".to_string());
+                    }
+                }
+            }
+            PauseMenuItem::LoadGame => {
+                self.save_files = self.source.as_ref().map(save::list_saves).unwrap_or(Vec::new());
+                if self.save_files.is_empty() {
+                    self.pause_menu.set_status("There are no saves of this game yet".to_string());
+                } else {
+                    let labels = self.save_files.iter().map(SaveFile::label).collect();
+                    self.pause_menu.show_saves(labels);
+                }
+            }
+            PauseMenuItem::ScrollSpeed => {}
+            PauseMenuItem::Resign => {
+                if self.pause_menu.confirm(item) {
+                    self.resign();
+                    self.pause_menu.close();
+                } else {
+                    self.pause_menu.set_status("Press Enter again to resign".to_string());
+                }
+            }
+            PauseMenuItem::Quit => return false,
+        }
+        true
+    }
+
+    /// Writes the game to the saves directory, named after when it was saved
+    fn save_game(&mut self) -> io::Result<PathBuf> {
+        let source = match self.source {
+            Some(ref source) => source,
+            None => return Err(io::Error::new(io::ErrorKind::Other, "saving isn't enabled")),
+        };
+        let save = SaveGame::capture(self.planner.mut_world(), source);
+        let dir = PathBuf::from(SAVE_DIR);
+        try!(fs::create_dir_all(&dir));
+        let file_name = dir.join(format!("{}.{}", timestamp(), SAVE_EXTENSION));
+        try!(save.write_to_file(&file_name));
+        Ok(file_name)
+    }
+
+    /// Goes back to where the save was made. The world has to have been created from the same
+    /// scenario and scripts as the saved game (`list_saves` only lists saves of those).
+    pub fn load(&mut self, save: &SaveGame) {
+        let world = self.planner.mut_world();
+        save.snapshot.restore(world);
+        // Saves made during LAN games hold back the local player's actions
+        world.write_resource::<ActionBatcher>().delay_actions(0);
+        *world.write_resource::<GameSettings>() = save.settings.clone();
+
+        // What was explored isn't saved, so all that's known is what the units see
+        let (width, height) = {
+            let terrain = world.read_resource::<Terrain>();
+            (terrain.width(), terrain.height())
+        };
+        *world.write_resource::<FogOfWar>() = FogOfWar::new(width, height, save.settings.map_visibility);
+        self.minimap = Minimap::new();
+        world.write_resource::<Announcements>().announce("Loaded the saved game".to_string());
+    }
+
+    fn load_from_file(&mut self, save_file: &SaveFile) {
+        match SaveGame::read_from_file(&save_file.path) {
+            Ok(save) => {
+                self.load(&save);
+                self.pause_menu.close();
+            }
+            Err(err) => {
+                log_error!("Failed to load \"{}\": {}", save_file.path.display(), err);
+                self.pause_menu.close_saves();
+                self.pause_menu.set_status("Failed to load the game".to_string());
+            }
+        }
+    }
+
+    /// Resigning hands everything the local player has left over to gaia. It goes through the
+    /// ActionBatcher like any other command (carried by any one of the player's units), so it's
+    /// recorded and every peer sees the same thing.
+    fn resign(&mut self) {
+        let world = self.planner.mut_world();
+        let local_player_id = world.read_resource::<Players>().local_player_id();
+        let entities = world.entities();
+        let units = world.read::<UnitComponent>();
+        let mut action_batcher = world.write_resource::<ActionBatcher>();
+        for (entity, unit) in (&entities, &units).iter() {
+            if unit.player_id == local_player_id {
+                action_batcher.queue_for_entity(entity.get_id(), Action::Resign);
+                break;
+            }
+        }
+    }
+
+    /// Hands the options picked in the pause menu to the world
+    fn apply_options(&mut self) {
+        let scroll_speed = Fixed::from(self.pause_menu.scroll_speed_percent() as i32) / Fixed::from(100);
+        self.planner.mut_world().write_resource::<Viewport>().scroll_speed = scroll_speed;
+    }
+
+    /// Runs a single simulation tick
+    pub fn tick(&mut self, time_step: Fixed) {
+        {
+            let world = self.planner.mut_world();
+            let mut render_commands = world.write_resource::<RenderCommands>();
+            render_commands.clear_debug();
+        }
+
+        ecs::update_world(&mut self.planner, time_step);
+        crash_report::record_tick(self.planner.mut_world());
+        if let (Some(auto_saver), Some(source)) = (self.auto_saver.as_mut(), self.source.as_ref()) {
+            auto_saver.update(self.planner.mut_world(), source, time_step);
+        }
+    }
+
+    /// Runs the next tick, unless this is a LAN game that's still waiting on someone's commands
+    /// for it. Once the game can't go on over the network, it goes on alone.
+    fn advance(&mut self, time_step: Fixed) {
+        if self.session.is_none() {
+            self.tick(time_step);
+            return;
+        }
+        let turn = {
+            let session = self.session.as_mut().unwrap();
+            session.poll().map(|()| session.take_turn())
+        };
+        let turn = match turn {
+            Ok(Some(turn)) => turn,
+            Ok(None) => return,
+            Err(err) => {
+                log_error!("Lost the LAN game: {}", err);
+                self.play_alone();
+                return;
+            }
+        };
+        self.schedule_turn(turn);
+        self.tick(time_step);
+
+        let (tick, commands) = {
+            let world = self.planner.mut_world();
+            let tick = world.read_resource::<SimulationTick>().tick;
+            (tick, world.write_resource::<ActionBatcher>().take_outgoing())
+        };
+        if let Some(ref mut session) = self.session {
+            session.send(tick + COMMAND_DELAY_TICKS, &commands);
+        }
+    }
+
+    /// Hands everyone's commands for the tick to the ActionBatcher, along with a resignation for
+    /// each player dropped on it (carried by any one of their units, like in `resign`)
+    fn schedule_turn(&mut self, turn: Turn) {
+        let world = self.planner.mut_world();
+        let mut commands = turn.commands;
+        for slot in turn.dropped {
+            let player_id = Lobby::player_id(slot);
+            let entities = world.entities();
+            let units = world.read::<UnitComponent>();
+            for (entity, unit) in (&entities, &units).iter() {
+                if unit.player_id == player_id {
+                    commands.push(CommandLogEntry {
+                        tick: turn.tick,
+                        entity_id: entity.get_id(),
+                        action: Action::Resign,
+                    });
+                    break;
+                }
+            }
+            world.write_resource::<Announcements>()
+                .announce(format!("Player {} was dropped from the game", *player_id));
         }
+        world.write_resource::<ActionBatcher>().schedule(commands);
+    }
+
+    /// Stops playing over the network, with everyone else's units left where they are
+    fn play_alone(&mut self) {
+        self.session = None;
+        let world = self.planner.mut_world();
+        world.write_resource::<ActionBatcher>().delay_actions(0);
+        world.write_resource::<Announcements>().announce("Lost the connection to the game".to_string());
+    }
+
+    /// F8 votes to drop whoever the game is waiting on
+    fn handle_drop_vote_controls(&mut self) {
+        if self.media.borrow().key_states().key_state(Key::F8) != KeyState::TransitionDown {
+            return;
+        }
+        if let Some(ref mut session) = self.session {
+            session.vote_drop();
+        }
+    }
+
+    /// What the waiting dialog says: who the game is held up on and how to drop them, or nothing
+    /// if it isn't held up
+    fn waiting_lines(&self) -> Vec<String> {
+        let session = match self.session {
+            Some(ref session) => session,
+            None => return Vec::new(),
+        };
+        if session.is_rejoining() {
+            return vec!["The host dropped; reconnecting to whoever took over...".to_string()];
+        }
+        let waiting = session.waiting();
+        let mut lines: Vec<String> = waiting.iter()
+            .map(|player| {
+                match player.seconds_left {
+                    Some(seconds_left) => {
+                        format!("Waiting for {}; they'll be dropped in {}s", player.name, seconds_left)
+                    }
+                    None => format!("Waiting for {}...", player.name),
+                }
+            })
+            .collect();
+        if session.slot().is_some() && waiting.iter().any(|player| player.seconds_left.is_some()) {
+            lines.push("Press F8 to vote to drop them now".to_string());
+        }
+        lines
     }
 
     fn update_viewport(&mut self, lerp: Fixed) {
@@ -49,7 +603,34 @@ impl ScenarioGameState {
         self.media.borrow_mut().renderer().set_camera_position(&top_left);
     }
 
-    fn update_input_resources(&mut self) {
+    pub fn planner(&mut self) -> &mut ecs::WorldPlanner {
+        &mut self.planner
+    }
+
+    /// The scenario the game was started from, as it was loaded
+    pub fn scenario(&self) -> &scn::Scenario {
+        &self.scenario
+    }
+
+    /// Starts over from the given scenario, with the same setup (the editor loads scenarios this way)
+    pub fn load_scenario(&mut self, scenario: scn::Scenario) {
+        self.planner = ecs::create_world_planner(self.media.clone(),
+                                                 self.empires.clone(),
+                                                 self.shape_metadata.clone(),
+                                                 &scenario,
+                                                 &self.setup);
+        self.minimap = Minimap::new();
+        self.scenario = scenario;
+        self.apply_options();
+    }
+
+    /// Copies this frame's keyboard and mouse state into the world for the systems to see, by way of
+    /// the keyboard cursor if it's in use
+    pub fn update_input_resources(&mut self, time_step: Fixed) {
+        let own_units = match self.keyboard_cursor {
+            Some(_) => self.on_screen_units(),
+            None => Vec::new(),
+        };
         let world = self.planner.mut_world();
         let (mut keys, mut mouse_state) = {
             (world.write_resource::<KeyboardKeyStates>(), world.write_resource::<MouseState>())
@@ -59,39 +640,209 @@ impl ScenarioGameState {
         *keys = media.key_states().clone();
         (*mouse_state).position = media.mouse_position().clone();
         (*mouse_state).key_states = media.mouse_button_states().clone();
+        if let Some(ref mut cursor) = self.keyboard_cursor {
+            let size = media.viewport_size();
+            cursor.update(&mut *keys,
+                          &mut *mouse_state,
+                          &own_units,
+                          Vector2::new(size.x as i32, size.y as i32),
+                          time_step);
+        }
+        mouse_state.update_drag();
+    }
+
+    /// Where the local player's units on screen are drawn, in a stable order for Tab to go through
+    fn on_screen_units(&mut self) -> Vec<Vector2<i32>> {
+        let world = self.planner.mut_world();
+        let local_player_id = world.read_resource::<Players>().local_player_id();
+        let projector = world.read_resource::<ViewProjector>();
+        let top_left = world.read_resource::<Viewport>().top_left_i32();
+        let units = world.read::<UnitComponent>();
+        let transforms = world.read::<TransformComponent>();
+        let on_screen = world.read::<OnScreenComponent>();
+        (&units, &transforms, &on_screen)
+            .iter()
+            .filter(|&(unit, _, _)| unit.player_id == local_player_id)
+            .map(|(_, transform, _)| projector.project(transform.position()) - top_left)
+            .collect()
+    }
+
+    /// O opens and closes the objectives panel. Anything new that shows up while it's open counts
+    /// as read.
+    fn handle_messages_controls(&mut self) {
+        let pressed = self.media.borrow().key_states().key_state(Key::O) == KeyState::TransitionDown;
+        if pressed && !self.is_build_menu_open() {
+            self.messages_open = !self.messages_open;
+        }
+        if self.messages_open {
+            self.planner.mut_world().write_resource::<Messages>().mark_read();
+        }
+    }
+
+    /// F9 writes a repro bundle of the last stretch of the game, for when something looks like it
+    /// went out of sync
+    fn handle_repro_controls(&mut self) {
+        if self.media.borrow().key_states().key_state(Key::F9) != KeyState::TransitionDown {
+            return;
+        }
+        match crash_report::write_repro_bundle(self.planner.mut_world(), "Requested with F9") {
+            Ok(Some(dir)) => log_info!("Wrote a repro bundle to {}", dir.display()),
+            Ok(None) => {}
+            Err(err) => log_error!("Failed to write a repro bundle: {}", err),
+        }
+    }
+
+    /// T opens and closes the tech tree. While it's open, the arrow keys scroll it and clicking
+    /// a node selects it, and the game doesn't see any of the input.
+    fn handle_tech_tree_controls(&mut self) {
+        let build_menu_open = self.is_build_menu_open();
+        let media = self.media.borrow();
+        let keys = media.key_states();
+        let pressed = |key| keys.key_state(key) == KeyState::TransitionDown;
+
+        let world = self.planner.mut_world();
+        if pressed(Key::T) && !build_menu_open {
+            self.tech_tree = match self.tech_tree {
+                Some(_) => None,
+                None => {
+                    let civilization_id = world.read_resource::<Players>().local_player().civ_id;
+                    Some(TechTree::new(&self.empires, civilization_id))
+                }
+            };
+        }
+
+        let tree = match self.tech_tree {
+            Some(ref mut tree) => tree,
+            None => return,
+        };
+        for &(key, x, y) in &[(Key::Left, -1, 0), (Key::Right, 1, 0), (Key::Up, 0, -1), (Key::Down, 0, 1)] {
+            if keys.is_down(key) {
+                tree.scroll_by(x * tech_tree::SCROLL_STEP, y * tech_tree::SCROLL_STEP);
+            }
+        }
+        if media.mouse_button_states().key_state(MouseButton::Left) == KeyState::TransitionDown {
+            tree.select_at(media.mouse_position());
+        }
+
+        *world.write_resource::<KeyboardKeyStates>() = KeyStates::new(HashMap::new());
+        let mut mouse_state = world.write_resource::<MouseState>();
+        mouse_state.key_states = KeyStates::new(HashMap::new());
+        mouse_state.drag_start = None;
     }
 }
 
 impl GameState for ScenarioGameState {
     fn start(&mut self) {}
 
-    fn stop(&mut self) {}
+    fn stop(&mut self) {
+        self.write_recording();
+        self.write_timeline();
+        if let Some(ref mut auto_saver) = self.auto_saver {
+            auto_saver.finish_writing();
+        }
+        if let Some(session) = self.session.take() {
+            session.leave();
+        }
+    }
 
     fn update(&mut self, time_step: Fixed) -> bool {
-        self.update_input_resources();
-
-        {
-            let world = self.planner.mut_world();
-            let mut render_commands = world.write_resource::<RenderCommands>();
-            render_commands.clear_debug();
+        self.check_hot_reload();
+        self.update_input_resources(time_step);
+        if !self.handle_pause_menu_controls() {
+            return false;
         }
-
-        self.planner.dispatch((ecs::SystemGroup::Normal, time_step));
-        self.planner.wait();
-
+        if self.pause_menu.is_open() {
+            // Everyone else in a LAN game keeps playing
+            if !self.is_single_player() {
+                self.advance(time_step);
+            }
+            return true;
+        }
+        self.handle_messages_controls();
+        self.handle_repro_controls();
+        self.handle_drop_vote_controls();
+        self.handle_tech_tree_controls();
+        self.advance(time_step);
         true
     }
 
     fn render(&mut self, lerp: Fixed) {
         self.update_viewport(lerp);
+        let paused = self.is_single_player();
+        let waiting_lines = self.waiting_lines();
 
         self.planner.dispatch((ecs::SystemGroup::Render, lerp));
         self.planner.wait();
 
         let world = self.planner.mut_world();
+        let mut media = self.media.borrow_mut();
         let mut render_commands = world.write_resource::<RenderCommands>();
-        render_commands.execute(self.media.borrow_mut().renderer(),
-                                &mut *self.shape_manager.borrow_mut());
+        render_commands.execute(media.renderer(), &mut *self.shape_manager.borrow_mut());
         render_commands.clear_rendered();
+
+        self.minimap.render(media.renderer(),
+                            self.shape_manager.borrow().palette(),
+                            &*self.empires,
+                            &*world.read_resource::<Terrain>(),
+                            &*world.read_resource::<FogOfWar>(),
+                            &*world.read_resource::<Players>());
+        hud::render_stockpile(media.renderer(),
+                              &*world.read_resource::<Stockpiles>(),
+                              &*world.read_resource::<Players>());
+        hud::render_announcements(media.renderer(), &*world.read_resource::<Announcements>());
+        hud::render_scores(media.renderer(),
+                           &*world.read_resource::<Scores>(),
+                           &*world.read_resource::<Players>(),
+                           &*world.read_resource::<PlayerTechs>());
+
+        // Cloned since the renderer can't be borrowed while the controller is
+        if let Some(controller) = media.controller().cloned() {
+            let cursor = media.mouse_position();
+            hud::render_controller(media.renderer(), &controller, cursor);
+        }
+
+        // Once someone has won, the timeline shows how everyone got there
+        if world.read_resource::<Victory>().winner().is_some() {
+            hud::render_timeline(media.renderer(),
+                                 &*world.read_resource::<Timeline>(),
+                                 &*world.read_resource::<Players>(),
+                                 self.shape_manager.borrow().player_color_scheme());
+        }
+
+        let messages = world.read_resource::<Messages>();
+        if self.messages_open {
+            hud::render_messages(media.renderer(), &*messages);
+        } else if messages.has_unread() {
+            hud::render_unread_messages(media.renderer());
+        }
+
+        if world.read_resource::<DeleteConfirmation>().is_armed() {
+            hud::render_delete_confirmation(media.renderer());
+        }
+
+        let build_menu = world.read_resource::<BuildMenu>();
+        if build_menu.is_open() {
+            hud::render_build_menu(media.renderer(), &*build_menu);
+        }
+
+        if let Some(ref cursor) = self.keyboard_cursor {
+            hud::render_keyboard_cursor(media.renderer(), cursor);
+        }
+
+        if let Some(ref tree) = self.tech_tree {
+            let players = world.read_resource::<Players>();
+            hud::render_tech_tree(media.renderer(),
+                                  tree,
+                                  &*world.read_resource::<PlayerTechs>(),
+                                  players.local_player_id());
+        }
+
+        if !waiting_lines.is_empty() {
+            hud::render_waiting(media.renderer(), &waiting_lines);
+        }
+
+        if self.pause_menu.is_open() {
+            hud::render_pause_menu(media.renderer(), &self.pause_menu, paused);
+        }
     }
 }
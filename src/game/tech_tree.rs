@@ -0,0 +1,354 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::{EmpiresDb, ResearchEffect, ResearchEffectValue, ResourceType};
+use identifier::{CivilizationId, ResearchId, UnitId};
+use nalgebra::Vector2;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use types::Rect;
+
+pub const NODE_WIDTH: i32 = 128;
+pub const NODE_HEIGHT: i32 = 16;
+const COLUMN_SPACING: i32 = 32;
+const ROW_SPACING: i32 = 6;
+
+/// Space left above the tree for its title
+const TOP_MARGIN: i32 = 40;
+const SIDE_MARGIN: i32 = 16;
+
+/// How far (in pixels) the arrow keys scroll the tree
+pub const SCROLL_STEP: i32 = NODE_HEIGHT + ROW_SPACING;
+
+pub struct TechNode {
+    pub research_id: ResearchId,
+    pub name: String,
+
+    /// The prerequisites that are in the tree too, which are the ones that get arrows
+    pub required: Vec<ResearchId>,
+
+    /// False if the civilization can't do this research, or can't do something it requires
+    pub available: bool,
+
+    /// True if it's the civilization's tech tree that takes this research away (rather than one
+    /// of its prerequisites)
+    pub disabled: bool,
+
+    pub costs: Vec<(ResourceType, i16)>,
+    pub time_seconds: i16,
+    pub effects: Vec<String>,
+    column: usize,
+    row: usize,
+}
+
+/// Every research that's done at a building, laid out in columns by how many prerequisites deep
+/// it is. Everything comes from the dat file, so modded data gets its own tree.
+pub struct TechTree {
+    civilization_id: CivilizationId,
+    civilization_name: String,
+    nodes: Vec<TechNode>,
+    selected: Option<ResearchId>,
+    scroll: Vector2<i32>,
+}
+
+impl TechTree {
+    pub fn new(empires: &EmpiresDb, civilization_id: CivilizationId) -> TechTree {
+        let requirements: BTreeMap<ResearchId, Vec<ResearchId>> = empires.researches()
+            .iter()
+            .map(|research| (research.id, research.required_research_ids()))
+            .collect();
+        let enabled: BTreeSet<ResearchId> = empires.researches()
+            .iter()
+            .filter(|research| empires.is_research_available(civilization_id, research.id))
+            .map(|research| research.id)
+            .collect();
+        let available = availability(&requirements, &enabled);
+
+        // Research that isn't done anywhere (such as the civilization bonuses) isn't shown
+        let shown: BTreeMap<ResearchId, Vec<ResearchId>> = empires.researches()
+            .iter()
+            .filter(|research| research.location.is_some())
+            .map(|research| (research.id, research.required_research_ids()))
+            .collect();
+        let positions = layout(&shown);
+
+        let nodes = empires.researches()
+            .iter()
+            .filter(|research| shown.contains_key(&research.id))
+            .map(|research| {
+                let (column, row) = positions[&research.id];
+                TechNode {
+                    research_id: research.id,
+                    name: research.name.clone(),
+                    required: shown[&research.id]
+                        .iter()
+                        .cloned()
+                        .filter(|required_id| shown.contains_key(required_id))
+                        .collect(),
+                    available: available.contains(&research.id),
+                    disabled: !enabled.contains(&research.id),
+                    costs: research.costs(),
+                    time_seconds: research.time_seconds,
+                    effects: describe_effects(empires, civilization_id, research.id),
+                    column: column,
+                    row: row,
+                }
+            })
+            .collect();
+
+        TechTree {
+            civilization_id: civilization_id,
+            civilization_name: empires.civilization(civilization_id).name().to_string(),
+            nodes: nodes,
+            selected: None,
+            scroll: Vector2::new(0, 0),
+        }
+    }
+
+    pub fn civilization_id(&self) -> CivilizationId {
+        self.civilization_id
+    }
+
+    pub fn civilization_name(&self) -> &str {
+        &self.civilization_name
+    }
+
+    pub fn nodes(&self) -> &[TechNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, research_id: ResearchId) -> Option<&TechNode> {
+        self.nodes.iter().find(|node| node.research_id == research_id)
+    }
+
+    pub fn selected(&self) -> Option<&TechNode> {
+        self.selected.and_then(|research_id| self.node(research_id))
+    }
+
+    /// Where the node is drawn on the screen, after scrolling
+    pub fn node_rect(&self, node: &TechNode) -> Rect {
+        Rect::of(SIDE_MARGIN + node.column as i32 * (NODE_WIDTH + COLUMN_SPACING) - self.scroll.x,
+                 TOP_MARGIN + node.row as i32 * (NODE_HEIGHT + ROW_SPACING) - self.scroll.y,
+                 NODE_WIDTH,
+                 NODE_HEIGHT)
+    }
+
+    /// Selects the node under the screen position, or clears the selection if there isn't one
+    pub fn select_at(&mut self, position: Vector2<i32>) {
+        self.selected = self.nodes
+            .iter()
+            .find(|node| self.node_rect(node).contains(position.x, position.y))
+            .map(|node| node.research_id);
+    }
+
+    /// Scrolls by the given number of pixels, stopping at the edges of the tree
+    pub fn scroll_by(&mut self, x: i32, y: i32) {
+        let columns = self.nodes.iter().map(|node| node.column as i32 + 1).max().unwrap_or(0);
+        let rows = self.nodes.iter().map(|node| node.row as i32 + 1).max().unwrap_or(0);
+        let max_x = cmp::max(0, (columns - 1) * (NODE_WIDTH + COLUMN_SPACING));
+        let max_y = cmp::max(0, (rows - 1) * (NODE_HEIGHT + ROW_SPACING));
+        self.scroll.x = cmp::min(max_x, cmp::max(0, self.scroll.x + x));
+        self.scroll.y = cmp::min(max_y, cmp::max(0, self.scroll.y + y));
+    }
+}
+
+/// The (column, row) of every research: the column is how long its longest chain of prerequisites
+/// is, and each column is ordered by ID. Prerequisites that aren't in `requirements` don't count.
+fn layout(requirements: &BTreeMap<ResearchId, Vec<ResearchId>>) -> BTreeMap<ResearchId, (usize, usize)> {
+    let mut depths = BTreeMap::new();
+    for &research_id in requirements.keys() {
+        depth(research_id, requirements, &mut depths, &mut BTreeSet::new());
+    }
+
+    let mut rows: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut positions = BTreeMap::new();
+    for (&research_id, &column) in &depths {
+        let row = rows.entry(column).or_insert(0);
+        positions.insert(research_id, (column, *row));
+        *row += 1;
+    }
+    positions
+}
+
+fn depth(research_id: ResearchId,
+         requirements: &BTreeMap<ResearchId, Vec<ResearchId>>,
+         depths: &mut BTreeMap<ResearchId, usize>,
+         visiting: &mut BTreeSet<ResearchId>)
+         -> usize {
+    if let Some(&depth) = depths.get(&research_id) {
+        return depth;
+    }
+    // Broken data can have research that (eventually) requires itself; the cycle stops here
+    if !visiting.insert(research_id) {
+        return 0;
+    }
+    let mut result = 0;
+    for &required_id in requirements.get(&research_id).map(|required| &required[..]).unwrap_or(&[]) {
+        if requirements.contains_key(&required_id) {
+            result = cmp::max(result, depth(required_id, requirements, depths, visiting) + 1);
+        }
+    }
+    visiting.remove(&research_id);
+    depths.insert(research_id, result);
+    result
+}
+
+/// The research that is enabled and only requires (directly or not) research that is too
+fn availability(requirements: &BTreeMap<ResearchId, Vec<ResearchId>>,
+                enabled: &BTreeSet<ResearchId>)
+                -> BTreeSet<ResearchId> {
+    let mut available = BTreeSet::new();
+    loop {
+        let newly_available: Vec<ResearchId> = requirements.iter()
+            .filter(|&(research_id, required)| {
+                !available.contains(research_id) && enabled.contains(research_id) &&
+                required.iter().all(|required_id| available.contains(required_id))
+            })
+            .map(|(&research_id, _)| research_id)
+            .collect();
+        if newly_available.is_empty() {
+            return available;
+        }
+        available.extend(newly_available);
+    }
+}
+
+fn describe_effects(empires: &EmpiresDb,
+                    civilization_id: CivilizationId,
+                    research_id: ResearchId)
+                    -> Vec<String> {
+    let unit_name = |unit_id: UnitId| {
+        empires.find_unit(civilization_id, unit_id)
+            .map(|unit| unit.name().to_string())
+            .unwrap_or_else(|| format!("unit {}", *unit_id))
+    };
+    let research_name = |research_id: ResearchId| {
+        empires.find_research(research_id)
+            .map(|research| research.name.clone())
+            .unwrap_or_else(|| format!("research {}", *research_id))
+    };
+    let value = |value: &ResearchEffectValue| {
+        match *value {
+            ResearchEffectValue::SetTo(amount) => format!("set to {}", amount),
+            ResearchEffectValue::Add(amount) => format!("{:+}", amount),
+            ResearchEffectValue::MultiplyBy(amount) => format!("x{}", amount),
+        }
+    };
+
+    let effects = match empires.research_effects(research_id) {
+        Some(effects) => &effects.effects,
+        None => return Vec::new(),
+    };
+    effects.iter()
+        .filter_map(|effect| {
+            Some(match *effect {
+                ResearchEffect::UnitAttribute { target_unit_id,
+                                                target_unit_class_id,
+                                                ref attribute_id,
+                                                ref effect } => {
+                    let target = match (target_unit_id, target_unit_class_id) {
+                        (Some(unit_id), _) => unit_name(unit_id),
+                        (None, Some(class_id)) => format!("class {}", *class_id),
+                        (None, None) => "all units".to_string(),
+                    };
+                    format!("{:?} {} for {}", attribute_id, value(effect), target)
+                }
+                ResearchEffect::CivHeader { target_civ_header_id, ref effect } => {
+                    format!("Civilization value {} {}", target_civ_header_id, value(effect))
+                }
+                ResearchEffect::SetUnitEnabled { target_unit_id: Some(unit_id), enabled } => {
+                    format!("{} {}", if enabled { "Enables" } else { "Disables" }, unit_name(unit_id))
+                }
+                ResearchEffect::UpgradeUnit { source_unit_id, target_unit_id } => {
+                    format!("Upgrades {} to {}", unit_name(source_unit_id), unit_name(target_unit_id))
+                }
+                ResearchEffect::ResearchCost { research_id, resource_type, ref effect } => {
+                    format!("{:?} cost of {} {}", resource_type, research_name(research_id), value(effect))
+                }
+                ResearchEffect::DisableResearch { research_id } => {
+                    format!("Disables {}", research_name(research_id))
+                }
+                ResearchEffect::GainResearch { research_id } => {
+                    format!("Gives {}", research_name(research_id))
+                }
+                ResearchEffect::SetUnitEnabled { target_unit_id: None, .. } |
+                ResearchEffect::Unknown { .. } => return None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{availability, layout};
+    use identifier::ResearchId;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn requirements(pairs: Vec<(usize, Vec<usize>)>) -> BTreeMap<ResearchId, Vec<ResearchId>> {
+        pairs.into_iter()
+            .map(|(research_id, required)| {
+                let required: Vec<ResearchId> =
+                    required.into_iter().map(|required_id| required_id.into()).collect();
+                (research_id.into(), required)
+            })
+            .collect()
+    }
+
+    fn ids(research_ids: &[usize]) -> BTreeSet<ResearchId> {
+        research_ids.iter().map(|&research_id| research_id.into()).collect()
+    }
+
+    #[test]
+    fn test_layout() {
+        // 3 needs 1 and 2, and 2 needs 1, so 3 goes two columns over; 4 needs something not shown
+        let positions = layout(&requirements(vec![(1, vec![]),
+                                                  (2, vec![1]),
+                                                  (3, vec![1, 2]),
+                                                  (4, vec![99]),
+                                                  (5, vec![1])]));
+        let position = |research_id: usize| {
+            let research_id: ResearchId = research_id.into();
+            positions[&research_id]
+        };
+        assert_eq!((0, 0), position(1));
+        assert_eq!((1, 0), position(2));
+        assert_eq!((2, 0), position(3));
+        assert_eq!((0, 1), position(4));
+        assert_eq!((1, 1), position(5));
+    }
+
+    #[test]
+    fn test_layout_with_cycle() {
+        let positions = layout(&requirements(vec![(1, vec![2]), (2, vec![1])]));
+        assert_eq!(2, positions.len());
+    }
+
+    #[test]
+    fn test_availability() {
+        let requirements = requirements(vec![(1, vec![]),
+                                             (2, vec![1]),
+                                             (3, vec![2]),
+                                             (4, vec![1]),
+                                             (5, vec![5])]);
+        // Disabling 2 takes 3 with it, and research can't require itself into existence
+        assert_eq!(ids(&[1, 4]), availability(&requirements, &ids(&[1, 3, 4, 5])));
+    }
+}
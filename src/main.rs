@@ -29,6 +29,9 @@ extern crate chariot_media as media;
 extern crate chariot_resource as resource;
 extern crate chariot_identifier as identifier;
 
+#[macro_use]
+extern crate chariot_sim as sim;
+
 #[macro_use]
 extern crate chariot_types as types;
 
@@ -38,19 +41,42 @@ extern crate lazy_static;
 extern crate clap;
 extern crate nalgebra;
 extern crate num;
+extern crate png;
 extern crate specs;
 extern crate time;
 
 #[macro_use]
 mod macros;
 
-mod action;
 mod ecs;
 mod game;
-mod partition;
-mod util;
+mod net;
+
+use sim::{logging, partition, util};
+use sim::action::{self, CommandLog};
+use ecs::{GameSettings, GameSetup, MapVisibility, StartingPositions, StartingResources, VictoryCondition};
+use ecs::resource::Script;
+use game::{CaptureFormat, EditorGameState, Game, GameData, GameSource, GameState, HeadlessGame,
+           ReplayGameState, SaveGame, ScenarioGameState, begin_crash_report, host_lan_game,
+           install_panic_hook, join_lan_game, locate_game_data_dir, run_benchmarks, run_regression_cases,
+           run_repro_bundle};
+use net::{LobbyMessage, LobbySettings};
+use resource::PlayerColorScheme;
+use std::cmp;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Player count for LAN games unless the scenario has fewer players
+const LAN_MAX_PLAYERS: u8 = 8;
+
+const DEFAULT_AUTOSAVE_MINUTES: u32 = 5;
+const DEFAULT_AUTOSAVES_KEPT: usize = 3;
+
+/// Pixels a second that the keyboard cursor moves
+const DEFAULT_CURSOR_SPEED: u32 = 600;
 
-use game::{Game, GameState, ScenarioGameState};
+/// How long a LAN lobby waits on a player (or host) who has gone quiet before dropping them
+const DEFAULT_DROP_TIMEOUT_SECONDS: u32 = 30;
 
 fn main() {
     let arg_matches = clap::App::new("Chariot")
@@ -59,25 +85,531 @@ fn main() {
             .short("d")
             .long("game-data-dir")
             .value_name("GAME_DATA_DIR")
-            .help("Sets the directory to look in for game data. Defaults to \"game\".")
+            .help("Sets the directory to look in for game data, and remembers it. Otherwise, the remembered \
+                   directory, \"game\", or a detected install is used.")
             .takes_value(true))
+        .arg(clap::Arg::with_name("mods_dir")
+            .long("mods-dir")
+            .value_name("MODS_DIR")
+            .help("Sets the directory to look in for mods (see its mods.txt). Defaults to \"mods\".")
+            .takes_value(true)
+            .conflicts_with_all(&["regression", "repro", "bench", "headless"]))
         .arg(clap::Arg::with_name("SCENARIO")
-            .required(true)
+            .required_unless_one(&["regression", "repro", "join_lan", "load"])
             .help("Scenario file to load (temporary while there's no menu)"))
+        .arg(clap::Arg::with_name("headless")
+            .long("headless")
+            .help("Runs the simulation without a window, renderer, or audio, then prints a summary"))
+        .arg(clap::Arg::with_name("replay")
+            .long("replay")
+            .value_name("COMMAND_LOG")
+            .help("Command log (script or recorded game) to play back. Opens the replay viewer unless running \
+                   headless.")
+            .takes_value(true)
+            .conflicts_with_all(&["record", "host_lan", "join_lan"]))
+        .arg(clap::Arg::with_name("editor")
+            .long("editor")
+            .help("Opens the scenario in the scenario editor")
+            .conflicts_with_all(&["headless", "replay", "record", "host_lan", "join_lan"]))
+        .arg(clap::Arg::with_name("ticks")
+            .long("ticks")
+            .value_name("TICKS")
+            .help("Number of ticks to run in headless mode. Defaults to the tick of the last command.")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help("Seeds the game's random number generator. When playing back a command log, this overrides \
+                   its seed.")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("starting_resources")
+            .long("starting-resources")
+            .value_name("LEVEL")
+            .help("Gives every player the same resources to start with instead of what the scenario says")
+            .takes_value(true)
+            .possible_values(&["scenario", "low", "medium", "high"]))
+        .arg(clap::Arg::with_name("map_visibility")
+            .long("map")
+            .value_name("VISIBILITY")
+            .help("How much of the map is visible from the start. Defaults to \"normal\".")
+            .takes_value(true)
+            .possible_values(&["normal", "explored", "revealed"]))
+        .arg(clap::Arg::with_name("random_positions")
+            .long("random-positions")
+            .help("Shuffles which of the scenario's players everyone in a LAN game plays as"))
+        .arg(clap::Arg::with_name("conquest")
+            .long("conquest")
+            .help("Only conquest wins the game; artifacts, ruins, and wonders don't count down"))
+        .arg(clap::Arg::with_name("difficulty")
+            .long("difficulty")
+            .value_name("PLAYER=LEVEL")
+            .help("Makes the player a computer player (easiest, easy, moderate, hard, or hardest); the \
+                   hardest ones start with extra resources")
+            .takes_value(true)
+            .multiple(true))
+        .arg(clap::Arg::with_name("handicap")
+            .long("handicap")
+            .value_name("PLAYER=PERCENT")
+            .help("Gives the player the given percent of the usual starting resources")
+            .takes_value(true)
+            .multiple(true))
+        .arg(clap::Arg::with_name("record")
+            .long("record")
+            .value_name("COMMAND_LOG")
+            .help("Records every command issued during the game to the given file")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("autosave")
+            .long("autosave")
+            .value_name("MINUTES")
+            .help("How many minutes of game time to play between auto-saves (5 unless given); 0 turns \
+                   auto-saving off")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("autosave_keep")
+            .long("autosave-keep")
+            .value_name("COUNT")
+            .help("How many auto-saves to keep (3 unless given); older ones are deleted")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("load")
+            .long("load")
+            .value_name("SAVE_FILE")
+            .help("Loads a saved game; the scenario and scripts it was started from have to still be where \
+                   they were")
+            .takes_value(true)
+            .conflicts_with_all(&["SCENARIO", "script", "seed", "headless", "replay", "editor", "regression",
+                                  "repro", "bench", "host_lan", "join_lan"]))
+        .arg(clap::Arg::with_name("timeline")
+            .long("timeline")
+            .value_name("JSON_FILE")
+            .help("Writes every player's population, military, resources gathered, and score for each \
+                   minute of the game to the given file when the game ends")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("script")
+            .long("script")
+            .value_name("SCRIPT_FILE")
+            .help("Adds the rules in the given script to the game. Command logs only play back the same \
+                   way with the same scripts.")
+            .takes_value(true)
+            .multiple(true))
+        .arg(clap::Arg::with_name("keyboard_cursor")
+            .long("keyboard-cursor")
+            .help("Plays without a mouse: the arrow keys move the cursor, Enter and slash click, period \
+                   jumps between units, and Q opens the command panel")
+            .conflicts_with_all(&["headless", "replay", "editor"]))
+        .arg(clap::Arg::with_name("cursor_speed")
+            .long("cursor-speed")
+            .value_name("PIXELS")
+            .help("How many pixels a second the keyboard cursor moves (600 unless given)")
+            .requires("keyboard_cursor")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("dev")
+            .long("dev")
+            .help("Development mode: picks up changes to empires.dat and the scripts while the game runs")
+            .conflicts_with_all(&["headless", "replay", "editor", "record", "host_lan", "join_lan"]))
+        .arg(clap::Arg::with_name("regression")
+            .long("regression")
+            .value_name("CASE_FILE")
+            .help("Runs the given regression cases headlessly and compares their final state hashes")
+            .takes_value(true)
+            .multiple(true))
+        .arg(clap::Arg::with_name("repro")
+            .long("repro")
+            .value_name("BUNDLE_DIR")
+            .help("Plays back a repro bundle (written with crash reports, or with F9 during a game) \
+                   headlessly and reports the first tick that doesn't match what the game recorded")
+            .takes_value(true)
+            .conflicts_with_all(&["regression", "SCENARIO"]))
+        .arg(clap::Arg::with_name("bench")
+            .long("bench")
+            .value_name("BASELINE_FILE")
+            .help("Runs the benchmarks on the scenario and compares them against the given baseline, or \
+                   saves it if there isn't one yet")
+            .takes_value(true)
+            .conflicts_with_all(&["headless", "replay", "editor", "regression", "host_lan", "join_lan"]))
+        .arg(clap::Arg::with_name("bless")
+            .long("bless")
+            .help("Stores the current state hash in regression cases that fail or have none, or replaces the \
+                   benchmark baseline"))
+        .arg(clap::Arg::with_name("host_lan")
+            .long("host-lan")
+            .value_name("GAME_NAME")
+            .help("Hosts a LAN game of the scenario; it starts once everyone who joined is ready")
+            .takes_value(true)
+            .conflicts_with_all(&["headless", "join_lan"]))
+        .arg(clap::Arg::with_name("join_lan")
+            .long("join-lan")
+            .help("Joins the first LAN game that's found; the scenario must be in the game data too")
+            .conflicts_with_all(&["headless", "SCENARIO"]))
+        .arg(clap::Arg::with_name("observe")
+            .long("observe")
+            .requires("join_lan")
+            .help("Watches the LAN game instead of playing in it; the number keys switch whose view is shown"))
+        .arg(clap::Arg::with_name("drop_timeout")
+            .long("drop-timeout")
+            .value_name("SECONDS")
+            .help("How long to wait on a LAN player who stopped responding before dropping them (30 unless \
+                   given)")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("player_name")
+            .long("player-name")
+            .value_name("NAME")
+            .help("Name to use in LAN games. Defaults to \"Player\".")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("civ")
+            .long("civ")
+            .value_name("CIVILIZATION_ID")
+            .help("Civilization to play as in LAN games")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("color")
+            .long("color")
+            .value_name("PLAYER_COLOR_ID")
+            .help("Player color to use in LAN games")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("team")
+            .long("team")
+            .value_name("TEAM")
+            .help("Team to be on in LAN games")
+            .takes_value(true))
+        .arg(clap::Arg::with_name("allied_vision")
+            .long("allied-vision")
+            .help("Shows allies what your units see in LAN games, if they've turned it on too"))
+        .arg(clap::Arg::with_name("capture_ffmpeg")
+            .long("capture-ffmpeg")
+            .help("Records (with F11) to an MP4 by piping frames to ffmpeg instead of writing numbered PNGs"))
+        .arg(clap::Arg::with_name("player_colors")
+            .long("player-colors")
+            .value_name("SCHEME")
+            .help("Colors to draw the players in. Defaults to \"original\".")
+            .takes_value(true)
+            .possible_values(&["original", "high-contrast", "colorblind-safe"]))
+        .arg(clap::Arg::with_name("log_file")
+            .long("log-file")
+            .value_name("LOG_FILE")
+            .help("Writes everything that's logged to the given file too. Set CHARIOT_LOG (such as to \
+                   \"info,ecs::system=debug\") to choose what gets logged.")
+            .takes_value(true))
         .get_matches();
 
-    let game_data_dir = arg_matches.value_of("game_data_dir").unwrap_or("game");
-    let scenario_file_name = arg_matches.value_of("SCENARIO").unwrap();
+    let log_filter = match env::var(logging::LOG_FILTER_VAR) {
+        Ok(spec) => {
+            logging::Filter::parse(&spec).unwrap_or_else(|err| {
+                unrecoverable!("Invalid {}: {}", logging::LOG_FILTER_VAR, err);
+            })
+        }
+        Err(_) => logging::Filter::new(logging::Level::Info),
+    };
+    let log_file = arg_matches.value_of("log_file").map(Path::new);
+    if let Err(err) = logging::init(log_filter, log_file) {
+        unrecoverable!("Failed to open the log file: {}", err);
+    }
+    install_panic_hook();
+
+    let game_data_dir = &locate_game_data_dir(arg_matches.value_of("game_data_dir"));
+    // Only the windowed game loads mods. Regression cases, repro bundles, benchmarks, and headless
+    // runs always use the original game data, since their results have to match on every machine.
+    let mods_dir = arg_matches.value_of("mods_dir").unwrap_or("mods");
+
+    if let Some(case_file_names) = arg_matches.values_of("regression") {
+        let data = GameData::load(game_data_dir, None);
+        let case_file_names: Vec<&str> = case_file_names.collect();
+        if !run_regression_cases(&data, &case_file_names, arg_matches.is_present("bless")) {
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let scenario = scn::Scenario::read_from_file(scenario_file_name).unwrap_or_else(|err| {
+    if let Some(bundle_dir) = arg_matches.value_of("repro") {
+        let data = GameData::load(game_data_dir, None);
+        if !run_repro_bundle(&data, bundle_dir) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let player_name = arg_matches.value_of("player_name").unwrap_or("Player");
+    let lobby_choices = parse_lobby_choices(&arg_matches);
+    let drop_timeout = arg_matches.value_of("drop_timeout")
+        .map(|seconds| {
+            seconds.parse::<u32>().unwrap_or_else(|_| {
+                unrecoverable!("Invalid drop timeout: {}", seconds);
+            })
+        })
+        .unwrap_or(DEFAULT_DROP_TIMEOUT_SECONDS);
+
+    let save = arg_matches.value_of("load").map(|save_file_name| {
+        SaveGame::read_from_file(save_file_name).unwrap_or_else(|err| {
+            unrecoverable!("Failed to load saved game \"{}\": {}", save_file_name, err);
+        })
+    });
+
+    let mut lan_setup = None;
+    let mut lan_session = None;
+    let scenario_file_name = if let Some(ref save) = save {
+        save.source.scenario.to_string_lossy().into_owned()
+    } else if arg_matches.is_present("join_lan") {
+        let joined = join_lan_game(player_name,
+                                   &lobby_choices,
+                                   arg_matches.is_present("observe"),
+                                   drop_timeout);
+        let (settings, setup, session) = match joined {
+            Some(joined) => joined,
+            None => return,
+        };
+        lan_setup = Some(setup);
+        lan_session = Some(session);
+        Path::new(game_data_dir).join("SCENARIO").join(settings.scenario).to_string_lossy().into_owned()
+    } else {
+        arg_matches.value_of("SCENARIO").unwrap().to_string()
+    };
+
+    let scenario = scn::Scenario::read_from_file(&scenario_file_name).unwrap_or_else(|err| {
         unrecoverable!("Failed to load scenario \"{}\": {}",
                        scenario_file_name,
                        err);
     });
 
-    let mut game = Game::new(game_data_dir);
-    let initial_state = Box::new(ScenarioGameState::new(&game, scenario));
+    if let Some(baseline_file_name) = arg_matches.value_of("bench") {
+        let data = GameData::load(game_data_dir, None);
+        let bless = arg_matches.is_present("bless");
+        if !run_benchmarks(&data, &scenario, Path::new(baseline_file_name), bless) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let game_settings = match save {
+        Some(ref save) => save.settings.clone(),
+        None => parse_game_settings(&arg_matches),
+    };
+    if let Some(game_name) = arg_matches.value_of("host_lan") {
+        let settings = LobbySettings {
+            scenario: Path::new(&scenario_file_name).file_name().unwrap().to_string_lossy().into_owned(),
+            // Gaia is one of the scenario's players but doesn't get a slot
+            max_players: cmp::min(LAN_MAX_PLAYERS as usize, scenario.player_ids().len() - 1) as u8,
+            game: game_settings.clone(),
+        };
+        let hosted = host_lan_game(game_name, player_name, settings, &lobby_choices, drop_timeout);
+        let (setup, session) = match hosted {
+            Some(hosted) => hosted,
+            None => return,
+        };
+        lan_setup = Some(setup);
+        lan_session = Some(session);
+    }
+
+    let seed = match save {
+        Some(ref save) => Some(save.seed),
+        None => {
+            arg_matches.value_of("seed").map(|seed| {
+                seed.parse::<u64>().unwrap_or_else(|_| {
+                    unrecoverable!("Invalid seed: {}", seed);
+                })
+            })
+        }
+    };
+
+    let script_file_names: Vec<String> = match save {
+        Some(ref save) => {
+            save.source
+                .scripts
+                .iter()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .collect()
+        }
+        None => {
+            arg_matches.values_of("script")
+                .map(|file_names| file_names.map(|file_name| file_name.to_string()).collect())
+                .unwrap_or(Vec::new())
+        }
+    };
+    let scripts: Vec<Script> = script_file_names.iter()
+        .map(|file_name| {
+            Script::read_from_file(file_name).unwrap_or_else(|err| {
+                unrecoverable!("Failed to load script \"{}\": {}", file_name, err);
+            })
+        })
+        .collect();
+
+    let replay = arg_matches.value_of("replay").map(|replay_file_name| {
+        let mut commands = CommandLog::read_from_file(replay_file_name).unwrap_or_else(|err| {
+            unrecoverable!("Failed to load command log \"{}\": {}", replay_file_name, err);
+        });
+        if let Some(seed) = seed {
+            commands.set_seed(seed);
+        }
+        commands
+    });
+
+    if arg_matches.is_present("headless") {
+        let commands = replay.unwrap_or_else(|| {
+            let mut commands = CommandLog::new();
+            if let Some(seed) = seed {
+                commands.set_seed(seed);
+            }
+            commands
+        });
+        let tick_count = match arg_matches.value_of("ticks") {
+            Some(ticks) => {
+                ticks.parse::<u64>().unwrap_or_else(|_| {
+                    unrecoverable!("Invalid tick count: {}", ticks);
+                })
+            }
+            None => commands.last_tick().unwrap_or(0),
+        };
+
+        let data = GameData::load(game_data_dir, None);
+        begin_crash_report(&scenario_file_name, &script_file_names);
+        let mut headless_game = HeadlessGame::new(&data, &scenario, commands, &scripts);
+        let summary = headless_game.run(tick_count);
+        println!("Ticks run: {}", summary.ticks_run);
+        println!("Final state hash: {:016x}", summary.state_hash);
+        if let Some(timeline_file_name) = arg_matches.value_of("timeline") {
+            if let Err(err) = headless_game.write_timeline(timeline_file_name) {
+                log_error!("Failed to write timeline \"{}\": {}", timeline_file_name, err);
+            }
+        }
+        return;
+    }
+
+    let player_color_scheme = arg_matches.value_of("player_colors")
+        .and_then(PlayerColorScheme::from_name)
+        .unwrap_or(PlayerColorScheme::Original);
+    let mut game = Game::new(game_data_dir, mods_dir, player_color_scheme);
+    if arg_matches.is_present("capture_ffmpeg") {
+        game.frame_capture().set_format(CaptureFormat::Ffmpeg);
+    }
+    if arg_matches.is_present("editor") {
+        let file_name = PathBuf::from(&scenario_file_name);
+        let initial_state = Box::new(EditorGameState::new(&game, scenario, file_name));
+        game.push_state(initial_state as Box<GameState>);
+        game.game_loop();
+        return;
+    }
+    if let Some(commands) = replay {
+        let initial_state = Box::new(ReplayGameState::new(&game, scenario, commands, &scripts));
+        game.push_state(initial_state as Box<GameState>);
+        game.game_loop();
+        return;
+    }
+
+    begin_crash_report(&scenario_file_name, &script_file_names);
+    let mut setup = lan_setup.unwrap_or_else(|| {
+        // Every game gets a different seed unless one was asked for; it's recorded with the commands
+        let mut setup = GameSetup::new(seed.unwrap_or_else(time::precise_time_ns));
+        setup.settings = game_settings;
+        setup
+    });
+    setup.scripts = scripts;
+    let mut initial_state = Box::new(ScenarioGameState::new(&game, scenario, &setup));
+    initial_state.enable_saving(GameSource {
+        scenario: PathBuf::from(&scenario_file_name),
+        scripts: script_file_names.iter().map(PathBuf::from).collect(),
+    });
+    if let Some(ref save) = save {
+        initial_state.load(save);
+    }
+    if let Some(session) = lan_session {
+        initial_state.play_over_lan(session);
+    }
+    if let Some(record_file_name) = arg_matches.value_of("record") {
+        initial_state.record_to(PathBuf::from(record_file_name));
+    }
+    if let Some(timeline_file_name) = arg_matches.value_of("timeline") {
+        initial_state.export_timeline_to(PathBuf::from(timeline_file_name));
+    }
+    let autosave_minutes = arg_matches.value_of("autosave")
+        .map(|minutes| {
+            minutes.parse::<u32>().unwrap_or_else(|_| {
+                unrecoverable!("Invalid auto-save interval: {}", minutes);
+            })
+        })
+        .unwrap_or(DEFAULT_AUTOSAVE_MINUTES);
+    let autosave_keep = arg_matches.value_of("autosave_keep")
+        .map(|count| {
+            count.parse::<usize>().unwrap_or_else(|_| {
+                unrecoverable!("Invalid auto-save count: {}", count);
+            })
+        })
+        .unwrap_or(DEFAULT_AUTOSAVES_KEPT);
+    if autosave_minutes > 0 && autosave_keep > 0 {
+        initial_state.auto_save(autosave_minutes, autosave_keep);
+    }
+    if arg_matches.is_present("keyboard_cursor") {
+        let speed = arg_matches.value_of("cursor_speed")
+            .map(|speed| {
+                speed.parse::<u32>().unwrap_or_else(|_| {
+                    unrecoverable!("Invalid cursor speed: {}", speed);
+                })
+            })
+            .unwrap_or(DEFAULT_CURSOR_SPEED);
+        initial_state.use_keyboard_cursor(speed);
+    }
+    if arg_matches.is_present("dev") {
+        let script_files = script_file_names.iter().map(PathBuf::from).collect();
+        initial_state.enable_hot_reload(game.data().empires_location().to_path_buf(), script_files);
+    }
     game.push_state(initial_state as Box<GameState>);
 
     game.game_loop();
 }
+
+/// The starting conditions asked for on the command line; LAN games use the host's
+fn parse_game_settings(arg_matches: &clap::ArgMatches) -> GameSettings {
+    let mut settings = GameSettings::new();
+    settings.starting_resources = match arg_matches.value_of("starting_resources") {
+        Some("low") => StartingResources::Low,
+        Some("medium") => StartingResources::Medium,
+        Some("high") => StartingResources::High,
+        _ => StartingResources::Scenario,
+    };
+    settings.map_visibility = match arg_matches.value_of("map_visibility") {
+        Some("explored") => MapVisibility::Explored,
+        Some("revealed") => MapVisibility::Revealed,
+        _ => MapVisibility::Normal,
+    };
+    if arg_matches.is_present("random_positions") {
+        settings.starting_positions = StartingPositions::Random;
+    }
+    if arg_matches.is_present("conquest") {
+        settings.victory_condition = VictoryCondition::Conquest;
+    }
+    for name in &["difficulty", "handicap"] {
+        let values = match arg_matches.values_of(name) {
+            Some(values) => values,
+            None => continue,
+        };
+        for value in values {
+            let player_settings = GameSettings::parse(&format!("{}.{}", name, value)).unwrap_or_else(|_| {
+                unrecoverable!("Invalid {}: {}", name, value);
+            });
+            settings.difficulties.extend(player_settings.difficulties);
+            settings.handicaps.extend(player_settings.handicaps);
+        }
+    }
+    settings
+}
+
+/// The civilization, color, team, and allied vision asked for on the command line, as lobby requests
+fn parse_lobby_choices(arg_matches: &clap::ArgMatches) -> Vec<LobbyMessage> {
+    let parse = |name: &str| {
+        arg_matches.value_of(name).map(|value| {
+            value.parse::<u8>().unwrap_or_else(|_| {
+                unrecoverable!("Invalid {}: {}", name, value);
+            })
+        })
+    };
+
+    let mut choices = Vec::new();
+    if let Some(civ_id) = parse("civ") {
+        choices.push(LobbyMessage::SetCivilization((civ_id as usize).into()));
+    }
+    if let Some(color_id) = parse("color") {
+        choices.push(LobbyMessage::SetColor((color_id as usize).into()));
+    }
+    if let Some(team) = parse("team") {
+        choices.push(LobbyMessage::SetTeam(team));
+    }
+    if arg_matches.is_present("allied_vision") {
+        choices.push(LobbyMessage::SetAlliedVision(true));
+    }
+    choices
+}
@@ -0,0 +1,220 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::FORMAT_VERSIONS;
+use ecs::GameSetup;
+use std::collections::BTreeMap;
+use std::io;
+use std::net::SocketAddr;
+use super::connection::Connection;
+use super::host::PING_INTERVAL_NS;
+use super::lobby::{Lobby, LobbySettings};
+use super::message::{LobbyMessage, PROTOCOL_VERSION};
+use time;
+
+/// What happened to the lobby since the client last polled it
+#[derive(Clone, Debug, PartialEq)]
+pub enum LobbyEvent {
+    Nothing,
+    Changed,
+    /// The host started the game
+    Started(GameSetup),
+    /// The host dropped, and this player is next in line to host (see `LobbyHost::take_over`)
+    TakeOver,
+    /// The host dropped, and whoever took over is hosting at this address
+    HostMoved(SocketAddr),
+}
+
+/// A player's (or observer's) connection to someone else's lobby, along with a mirror of its state.
+/// Once the game starts, the connection is kept for the lockstep session to send commands over.
+pub struct LobbyClient {
+    connection: Connection,
+    observer: bool,
+    slot: Option<usize>,
+    lobby: Option<Lobby>,
+    /// The host counts as dropped after this long without hearing from it
+    drop_timeout_ns: u64,
+    last_heard_ns: u64,
+    last_ping_ns: u64,
+    waiting: BTreeMap<usize, u32>,
+    successor: Option<(usize, SocketAddr)>,
+    /// Game messages (commands and drops) that the session hasn't taken yet
+    received: Vec<LobbyMessage>,
+    /// The command log version the host said to send commands in when it started the game
+    command_format: Option<u8>,
+}
+
+impl LobbyClient {
+    /// The host counts as dropped once nothing has been heard from it for `drop_timeout` seconds
+    pub fn join(lobby_address: &SocketAddr,
+                name: &str,
+                observer: bool,
+                drop_timeout: u32)
+                -> io::Result<LobbyClient> {
+        let mut connection = try!(Connection::connect(lobby_address));
+        try!(connection.send(&LobbyMessage::Join {
+            version: PROTOCOL_VERSION,
+            observer: observer,
+            command_formats: FORMAT_VERSIONS.to_vec(),
+            name: name.to_string(),
+        }));
+        let now = time::precise_time_ns();
+        Ok(LobbyClient {
+            connection: connection,
+            observer: observer,
+            slot: None,
+            lobby: None,
+            drop_timeout_ns: drop_timeout as u64 * 1_000_000_000,
+            last_heard_ns: now,
+            last_ping_ns: now,
+            waiting: BTreeMap::new(),
+            successor: None,
+            received: Vec::new(),
+            command_format: None,
+        })
+    }
+
+    /// The lobby as last described by the host; None until the host has accepted the join
+    pub fn lobby(&self) -> Option<&Lobby> {
+        self.lobby.as_ref()
+    }
+
+    pub fn slot(&self) -> Option<usize> {
+        self.slot
+    }
+
+    /// The players everyone is waiting on, with how many seconds they have left before they're dropped
+    pub fn waiting(&self) -> &BTreeMap<usize, u32> {
+        &self.waiting
+    }
+
+    /// Asks the host to change this player's slot (civilization, color, team, ready)
+    pub fn request(&mut self, message: &LobbyMessage) -> io::Result<()> {
+        self.connection.send(message)
+    }
+
+    pub fn leave(mut self) -> io::Result<()> {
+        self.connection.send(&LobbyMessage::Leave)
+    }
+
+    /// The command log version the game's commands are sent in, once the host has started it
+    pub fn command_format(&self) -> Option<u8> {
+        self.command_format
+    }
+
+    /// Game messages (commands and drops) received since this was last called
+    pub fn take_received(&mut self) -> Vec<LobbyMessage> {
+        self.received.drain(..).collect()
+    }
+
+    pub fn poll(&mut self) -> io::Result<LobbyEvent> {
+        let now = time::precise_time_ns();
+        if now - self.last_ping_ns >= PING_INTERVAL_NS {
+            if let Err(err) = self.connection.send(&LobbyMessage::Ping) {
+                return self.host_lost(err);
+            }
+            self.last_ping_ns = now;
+        }
+        let messages = match self.connection.receive() {
+            Ok(messages) => messages,
+            Err(err) => return self.host_lost(err),
+        };
+        if !messages.is_empty() {
+            self.last_heard_ns = now;
+        } else if now - self.last_heard_ns >= self.drop_timeout_ns {
+            return self.host_lost(io::Error::new(io::ErrorKind::TimedOut, "the host stopped responding"));
+        }
+
+        let mut event = LobbyEvent::Nothing;
+        let mut started = None;
+        for message in messages {
+            match message {
+                LobbyMessage::Welcome { slot } => self.slot = slot,
+                LobbyMessage::Rejected(reason) => {
+                    return Err(io::Error::new(io::ErrorKind::ConnectionRefused, reason));
+                }
+                LobbyMessage::Settings(settings) => self.update_settings(settings),
+                LobbyMessage::Slot { slot, player } => {
+                    if player.is_none() {
+                        self.waiting.remove(&slot);
+                        if self.successor.map_or(false, |(successor, _)| successor == slot) {
+                            self.successor = None;
+                        }
+                    }
+                    if let Some(ref mut lobby) = self.lobby {
+                        lobby.set_slot(slot, player);
+                    }
+                }
+                LobbyMessage::Waiting { slot, seconds_left } => {
+                    self.waiting.insert(slot, seconds_left);
+                }
+                LobbyMessage::Resumed(slot) => {
+                    self.waiting.remove(&slot);
+                }
+                LobbyMessage::Successor { slot, address } => {
+                    self.successor = Some((slot, address));
+                    continue;
+                }
+                LobbyMessage::Start { seed, command_format } => {
+                    let setup = match (self.lobby.as_ref(), self.slot) {
+                        (Some(lobby), _) if self.observer => lobby.observer_setup(seed),
+                        (Some(lobby), Some(slot)) => lobby.game_setup(seed, slot),
+                        _ => {
+                            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                                      "lobby message: start before welcome"))
+                        }
+                    };
+                    // The host may have sent commands right after this, which mustn't be missed
+                    self.command_format = Some(command_format);
+                    started = Some(setup);
+                    continue;
+                }
+                LobbyMessage::Commands { .. } |
+                LobbyMessage::Dropped { .. } => {
+                    self.received.push(message);
+                    continue;
+                }
+                _ => continue,
+            }
+            event = LobbyEvent::Changed;
+        }
+        Ok(started.map_or(event, LobbyEvent::Started))
+    }
+
+    /// The host is gone: either this player takes over, everyone follows whoever does, or (if the
+    /// host never named a successor) the lobby is over
+    fn host_lost(&self, err: io::Error) -> io::Result<LobbyEvent> {
+        match self.successor {
+            Some((slot, _)) if !self.observer && self.slot == Some(slot) => Ok(LobbyEvent::TakeOver),
+            Some((_, address)) => Ok(LobbyEvent::HostMoved(address)),
+            None => Err(err),
+        }
+    }
+
+    fn update_settings(&mut self, settings: LobbySettings) {
+        if let Some(ref mut lobby) = self.lobby {
+            // The host already checked that nobody is left without a slot
+            let _ = lobby.configure(settings);
+            return;
+        }
+        self.lobby = Some(Lobby::new(settings));
+    }
+}
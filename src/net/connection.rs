@@ -0,0 +1,99 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use super::message::LobbyMessage;
+
+/// A non-blocking TCP connection that sends and receives one LobbyMessage per line
+pub struct Connection {
+    stream: TcpStream,
+    received: Vec<u8>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Connection> {
+        try!(stream.set_nonblocking(true));
+        try!(stream.set_nodelay(true));
+        Ok(Connection {
+            stream: stream,
+            received: Vec::new(),
+        })
+    }
+
+    pub fn connect(address: &SocketAddr) -> io::Result<Connection> {
+        Connection::new(try!(TcpStream::connect(address)))
+    }
+
+    /// Where the other end of the connection is
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    pub fn send(&mut self, message: &LobbyMessage) -> io::Result<()> {
+        let line = format!("{}\n", message.to_line());
+        let mut bytes = line.as_bytes();
+        while !bytes.is_empty() {
+            match self.stream.write(bytes) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "connection closed")),
+                Ok(written) => bytes = &bytes[written..],
+                // Lobby messages are tiny, so the send buffer rarely fills; just wait it out
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => thread::yield_now(),
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every complete message that has arrived since the last call without blocking.
+    /// An error means the connection is no longer usable (which includes the peer closing it);
+    /// whatever the peer sent before closing it is still returned first.
+    pub fn receive(&mut self) -> io::Result<Vec<LobbyMessage>> {
+        let mut buffer = [0u8; 1024];
+        let mut closed = false;
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    closed = true;
+                    break;
+                }
+                Ok(read) => self.received.extend_from_slice(&buffer[..read]),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.received.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.received.drain(..end + 1).collect();
+            let line = try!(String::from_utf8(line)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "lobby message: invalid UTF-8")));
+            messages.push(try!(LobbyMessage::parse(&line)));
+        }
+        if closed && messages.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+        }
+        Ok(messages)
+    }
+}
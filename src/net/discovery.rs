@@ -0,0 +1,131 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::str;
+use super::message::{GameAnnouncement, LobbyMessage, PROTOCOL_VERSION};
+use time;
+
+/// UDP port hosts broadcast their announcements to
+pub const DISCOVERY_PORT: u16 = 28961;
+
+const ANNOUNCE_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// Games that haven't been announced for this long are assumed to be gone
+const GAME_TIMEOUT_NS: u64 = 5 * ANNOUNCE_INTERVAL_NS;
+
+/// A game found on the LAN
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscoveredGame {
+    /// Where to connect to join the lobby
+    pub lobby_address: SocketAddr,
+    pub announcement: GameAnnouncement,
+}
+
+/// Periodically broadcasts a host's game to the LAN
+pub struct Announcer {
+    socket: UdpSocket,
+    last_announce_ns: Option<u64>,
+}
+
+impl Announcer {
+    pub fn new() -> io::Result<Announcer> {
+        let socket = try!(UdpSocket::bind("0.0.0.0:0"));
+        try!(socket.set_broadcast(true));
+        try!(socket.set_nonblocking(true));
+        Ok(Announcer {
+            socket: socket,
+            last_announce_ns: None,
+        })
+    }
+
+    /// Broadcasts the announcement if it hasn't been broadcast recently (call it every frame)
+    pub fn announce(&mut self, announcement: &GameAnnouncement) -> io::Result<()> {
+        let now = time::precise_time_ns();
+        if let Some(last_announce_ns) = self.last_announce_ns {
+            if now - last_announce_ns < ANNOUNCE_INTERVAL_NS {
+                return Ok(());
+            }
+        }
+        self.last_announce_ns = Some(now);
+
+        let message = LobbyMessage::Announce(announcement.clone()).to_line();
+        let broadcast = SocketAddrV4::new(Ipv4Addr::new(255, 255, 255, 255), DISCOVERY_PORT);
+        match self.socket.send_to(message.as_bytes(), broadcast) {
+            Ok(_) => Ok(()),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Listens for announcements and keeps a list of the games that are currently being hosted
+pub struct GameBrowser {
+    socket: UdpSocket,
+    games: Vec<(DiscoveredGame, u64)>,
+}
+
+impl GameBrowser {
+    pub fn new() -> io::Result<GameBrowser> {
+        let socket = try!(UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT)));
+        try!(socket.set_nonblocking(true));
+        Ok(GameBrowser {
+            socket: socket,
+            games: Vec::new(),
+        })
+    }
+
+    pub fn poll(&mut self) -> io::Result<()> {
+        let now = time::precise_time_ns();
+        let mut buffer = [0u8; 512];
+        loop {
+            let (size, sender) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            };
+            // Anyone can send anything to this port, so quietly skip whatever isn't an announcement
+            let announcement = match str::from_utf8(&buffer[..size]).ok().map(LobbyMessage::parse) {
+                Some(Ok(LobbyMessage::Announce(announcement))) => announcement,
+                _ => continue,
+            };
+            if announcement.version != PROTOCOL_VERSION {
+                continue;
+            }
+            let mut lobby_address = sender;
+            lobby_address.set_port(announcement.lobby_port);
+
+            self.games.retain(|&(ref game, _)| game.lobby_address != lobby_address);
+            self.games.push((DiscoveredGame {
+                                 lobby_address: lobby_address,
+                                 announcement: announcement,
+                             },
+                             now));
+        }
+        self.games.retain(|&(_, last_seen_ns)| now - last_seen_ns < GAME_TIMEOUT_NS);
+        Ok(())
+    }
+
+    pub fn games(&self) -> Vec<&DiscoveredGame> {
+        self.games.iter().map(|&(ref game, _)| game).collect()
+    }
+}
@@ -0,0 +1,529 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{FORMAT_VERSIONS, shared_formats};
+use ecs::GameSetup;
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use super::connection::Connection;
+use super::discovery::Announcer;
+use super::lobby::{Lobby, LobbySettings};
+use super::message::{GameAnnouncement, LobbyMessage, PROTOCOL_VERSION};
+use super::peer_watch::{PeerStatus, PeerWatch};
+use time;
+
+/// Default TCP port for lobby connections
+pub const LOBBY_PORT: u16 = 28960;
+
+/// How often the host lets everyone know it's still there
+pub const PING_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// Hosts a lobby: announces it on the LAN, accepts players and observers, and relays every change
+/// to everyone. The host plays in slot 0, unless it took over from a host that dropped.
+///
+/// Players who lose their connection keep their slot until the drop timeout runs out, so that
+/// they can rejoin under the same name; until then, everyone waits on them and can vote to drop
+/// them early. That goes on once the game starts, when the host keeps the connections for the
+/// lockstep session (see `LockstepSession`) to send commands over.
+pub struct LobbyHost {
+    game_name: String,
+    listener: TcpListener,
+    announcer: Announcer,
+    lobby: Lobby,
+    host_slot: usize,
+    /// Connections that haven't sent their join message yet
+    pending: Vec<Connection>,
+    players: Vec<(usize, Connection)>,
+    /// Observers only listen; anything they send other than leaving is ignored
+    observers: Vec<Connection>,
+    peers: PeerWatch,
+    /// The players everyone is waiting on, with the seconds left they were last announced with
+    waiting: BTreeMap<usize, u32>,
+    /// Who takes over if the host drops, as last announced
+    successor: Option<(usize, SocketAddr)>,
+    last_ping_ns: u64,
+    /// Once the game has started, only players who lost their connection can join (to get their
+    /// slot back)
+    started: bool,
+    /// Game messages from players (and the slot they came from) that the session hasn't taken yet
+    received: Vec<(usize, LobbyMessage)>,
+    /// Players dropped during the game, who the session hasn't told everyone about yet
+    dropped: Vec<usize>,
+    /// Players who got their slot back during the game, who need to be caught up
+    rejoined: Vec<usize>,
+    /// The command log versions that the host and everyone who joined can all read, oldest first
+    /// (see `command_format`)
+    command_formats: Vec<u8>,
+}
+
+impl LobbyHost {
+    /// Players who stay quiet for `drop_timeout` seconds are dropped
+    pub fn new(game_name: &str,
+               host_name: &str,
+               settings: LobbySettings,
+               drop_timeout: u32)
+               -> io::Result<LobbyHost> {
+        let mut lobby = Lobby::new(settings);
+        try!(lobby.join(host_name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err)));
+        LobbyHost::listen(game_name, lobby, 0, drop_timeout)
+    }
+
+    /// Takes over a lobby whose host dropped, playing in `host_slot`. Everyone else (the old host
+    /// included) keeps their slot until the drop timeout runs out, to give them time to rejoin.
+    pub fn take_over(game_name: &str,
+                     lobby: Lobby,
+                     host_slot: usize,
+                     drop_timeout: u32)
+                     -> io::Result<LobbyHost> {
+        let mut host = try!(LobbyHost::listen(game_name, lobby, host_slot, drop_timeout));
+        let now = time::precise_time_ns();
+        for slot in 0..host.lobby.slots().len() {
+            if slot != host_slot && host.lobby.slot(slot).is_some() {
+                host.peers.heard(slot, now);
+            }
+        }
+        Ok(host)
+    }
+
+    fn listen(game_name: &str, lobby: Lobby, host_slot: usize, drop_timeout: u32) -> io::Result<LobbyHost> {
+        let listener = try!(TcpListener::bind(("0.0.0.0", LOBBY_PORT)));
+        try!(listener.set_nonblocking(true));
+        Ok(LobbyHost {
+            game_name: game_name.to_string(),
+            listener: listener,
+            announcer: try!(Announcer::new()),
+            lobby: lobby,
+            host_slot: host_slot,
+            pending: Vec::new(),
+            players: Vec::new(),
+            observers: Vec::new(),
+            peers: PeerWatch::new(drop_timeout),
+            waiting: BTreeMap::new(),
+            successor: None,
+            last_ping_ns: 0,
+            started: false,
+            received: Vec::new(),
+            dropped: Vec::new(),
+            rejoined: Vec::new(),
+            command_formats: FORMAT_VERSIONS.to_vec(),
+        })
+    }
+
+    pub fn lobby(&self) -> &Lobby {
+        &self.lobby
+    }
+
+    pub fn host_slot(&self) -> usize {
+        self.host_slot
+    }
+
+    /// The players everyone is waiting on, with how many seconds they have left before they're dropped
+    pub fn waiting(&self) -> &BTreeMap<usize, u32> {
+        &self.waiting
+    }
+
+    /// Changes the host's own slot (civilization, color, team, ready)
+    pub fn apply(&mut self, message: &LobbyMessage) {
+        let host_slot = self.host_slot;
+        if self.lobby.apply(host_slot, message) {
+            self.broadcast_slot(host_slot);
+        }
+    }
+
+    pub fn configure(&mut self, settings: LobbySettings) -> Result<(), &'static str> {
+        try!(self.lobby.configure(settings));
+        let settings = LobbyMessage::Settings(self.lobby.settings().clone());
+        self.broadcast(&settings);
+        for slot in 0..self.lobby.slots().len() {
+            self.broadcast_slot(slot);
+        }
+        Ok(())
+    }
+
+    /// The host votes to drop a player everyone is waiting on. Returns whether they were dropped.
+    pub fn vote_drop(&mut self, slot: usize) -> bool {
+        let host_slot = self.host_slot;
+        self.count_drop_vote(host_slot, slot, time::precise_time_ns())
+    }
+
+    /// Whether everyone is ready, with nobody being waited on or still to rejoin
+    pub fn can_start(&self) -> bool {
+        self.lobby.can_start() && self.waiting.is_empty() &&
+        self.lobby.slots().iter().enumerate().all(|(slot, player)| {
+            player.is_none() || slot == self.host_slot || self.players.iter().any(|&(s, _)| s == slot)
+        })
+    }
+
+    /// Announces the game, accepts new players, and handles whatever they've sent.
+    /// Returns whether anything in the lobby changed.
+    pub fn poll(&mut self) -> io::Result<bool> {
+        let now = time::precise_time_ns();
+        if !self.started {
+            let announcement = GameAnnouncement {
+                version: PROTOCOL_VERSION,
+                game_name: self.game_name.clone(),
+                lobby_port: LOBBY_PORT,
+                player_count: self.lobby.player_count() as u8,
+                max_players: self.lobby.settings().max_players,
+            };
+            try!(self.announcer.announce(&announcement));
+        }
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => self.pending.push(try!(Connection::new(stream))),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut changed = self.poll_pending(now);
+        let mut leaving = Vec::new();
+        let mut drop_votes = Vec::new();
+        let mut index = 0;
+        while index < self.players.len() {
+            let slot = self.players[index].0;
+            let messages = match self.players[index].1.receive() {
+                Ok(messages) => messages,
+                Err(err) => {
+                    // Their slot is kept until the drop timeout, in case they make it back
+                    log_warn!("Lost connection to player {}: {}", slot + 1, err);
+                    self.players.remove(index);
+                    continue;
+                }
+            };
+            index += 1;
+            if !messages.is_empty() {
+                self.peers.heard(slot, now);
+            }
+            for message in &messages {
+                match *message {
+                    LobbyMessage::Leave => leaving.push(slot),
+                    LobbyMessage::VoteDrop(target) => drop_votes.push((slot, target)),
+                    LobbyMessage::Ping => {}
+                    LobbyMessage::Commands { .. } |
+                    LobbyMessage::Dropped { .. } => {
+                        if self.started {
+                            self.received.push((slot, message.clone()));
+                        }
+                    }
+                    _ if self.started => {}
+                    _ => {
+                        if self.lobby.apply(slot, message) {
+                            self.broadcast_slot(slot);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        for slot in leaving {
+            self.drop_player(slot);
+            changed = true;
+        }
+        for (voter, slot) in drop_votes {
+            changed |= self.count_drop_vote(voter, slot, now);
+        }
+        changed |= self.check_waiting(now);
+
+        let observers: Vec<Connection> = self.observers.drain(..).collect();
+        for mut observer in observers {
+            let leaving = match observer.receive() {
+                Ok(messages) => messages.contains(&LobbyMessage::Leave),
+                Err(_) => true,
+            };
+            if !leaving {
+                self.observers.push(observer);
+            }
+        }
+
+        if now - self.last_ping_ns >= PING_INTERVAL_NS {
+            self.broadcast(&LobbyMessage::Ping);
+            self.last_ping_ns = now;
+        }
+        self.update_successor();
+        Ok(changed)
+    }
+
+    /// The newest command log version everyone can read, which the game's commands are sent in
+    pub fn command_format(&self) -> u8 {
+        *self.command_formats.last().unwrap()
+    }
+
+    /// Tells everyone to start and returns the setup the host plays with. The host should be
+    /// handed to a `LockstepSession` to keep polling during the game.
+    /// Only call this once `can_start` says so.
+    pub fn start(&mut self, seed: u64) -> GameSetup {
+        let command_format = self.command_format();
+        self.broadcast(&LobbyMessage::Start {
+            seed: seed,
+            command_format: command_format,
+        });
+        self.started = true;
+        self.lobby.game_setup(seed, self.host_slot)
+    }
+
+    /// Carries on with a game that's already going, for a host that took over during it; its
+    /// commands are already being sent in `command_format`, so everyone who rejoins has to read that
+    pub fn resume_game(&mut self, command_format: u8) {
+        self.started = true;
+        self.command_formats = vec![command_format];
+    }
+
+    /// Game messages (commands and drops) received since this was last called, with the slot of
+    /// the player who sent each
+    pub fn take_received(&mut self) -> Vec<(usize, LobbyMessage)> {
+        self.received.drain(..).collect()
+    }
+
+    /// Players who were dropped during the game since this was last called
+    pub fn take_dropped(&mut self) -> Vec<usize> {
+        self.dropped.drain(..).collect()
+    }
+
+    /// Players who got their slot back during the game since this was last called
+    pub fn take_rejoined(&mut self) -> Vec<usize> {
+        self.rejoined.drain(..).collect()
+    }
+
+    /// Sends the message to the player in the slot, if they're connected
+    pub fn send_to(&mut self, slot: usize, message: &LobbyMessage) {
+        for &mut (s, ref mut connection) in &mut self.players {
+            if s == slot {
+                let _ = connection.send(message);
+            }
+        }
+    }
+
+    /// Sends the message to every player and observer, except for the player in `from` (who it
+    /// came from)
+    pub fn relay(&mut self, from: Option<usize>, message: &LobbyMessage) {
+        for &mut (slot, ref mut connection) in &mut self.players {
+            if Some(slot) != from {
+                let _ = connection.send(message);
+            }
+        }
+        for observer in &mut self.observers {
+            let _ = observer.send(message);
+        }
+    }
+
+    fn poll_pending(&mut self, now: u64) -> bool {
+        let mut changed = false;
+        let pending: Vec<Connection> = self.pending.drain(..).collect();
+        for mut connection in pending {
+            let messages = match connection.receive() {
+                Ok(messages) => messages,
+                Err(_) => continue,
+            };
+            let (version, observer, command_formats, name) = match messages.into_iter().next() {
+                Some(LobbyMessage::Join { version, observer, command_formats, name }) => {
+                    (version, observer, command_formats, name)
+                }
+                Some(_) => continue,
+                None => {
+                    self.pending.push(connection);
+                    continue;
+                }
+            };
+            if version != PROTOCOL_VERSION {
+                let _ = connection.send(&LobbyMessage::Rejected("Different game version".to_string()));
+                continue;
+            }
+            // Anyone who shares a format with everyone else can join, until the game starts and
+            // the format is settled
+            let shared = shared_formats(&self.command_formats, &command_formats);
+            let readable = if self.started {
+                command_formats.contains(&self.command_format())
+            } else {
+                !shared.is_empty()
+            };
+            if !readable {
+                let _ = connection.send(&LobbyMessage::Rejected("No command format in common".to_string()));
+                continue;
+            }
+            if observer {
+                if self.welcome(&mut connection, None).is_ok() {
+                    log_info!("{} is observing", name);
+                    self.observers.push(connection);
+                    if !self.started {
+                        self.command_formats = shared;
+                    }
+                }
+                continue;
+            }
+            let rejoined = self.rejoin_slot(&name);
+            let joined = match rejoined {
+                Some(slot) => Ok(slot),
+                None if self.started => Err("The game has already started"),
+                None => self.lobby.join(&name),
+            };
+            let slot = match joined {
+                Ok(slot) => slot,
+                Err(reason) => {
+                    let _ = connection.send(&LobbyMessage::Rejected(reason.to_string()));
+                    continue;
+                }
+            };
+            if self.welcome(&mut connection, Some(slot)).is_err() {
+                if rejoined.is_none() {
+                    self.lobby.leave(slot);
+                }
+                continue;
+            }
+            self.peers.heard(slot, now);
+            self.broadcast_slot(slot);
+            self.players.push((slot, connection));
+            if self.started {
+                self.rejoined.push(slot);
+            } else {
+                self.command_formats = shared;
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// The slot kept for a player with this name who lost their connection, if there is one
+    fn rejoin_slot(&self, name: &str) -> Option<usize> {
+        (0..self.lobby.slots().len()).find(|&slot| {
+            slot != self.host_slot && self.lobby.slot(slot).map_or(false, |player| player.name == name) &&
+            !self.players.iter().any(|&(s, _)| s == slot)
+        })
+    }
+
+    fn welcome(&self, connection: &mut Connection, slot: Option<usize>) -> io::Result<()> {
+        try!(connection.send(&LobbyMessage::Welcome { slot: slot }));
+        try!(connection.send(&LobbyMessage::Settings(self.lobby.settings().clone())));
+        for (index, player) in self.lobby.slots().iter().enumerate() {
+            try!(connection.send(&LobbyMessage::Slot {
+                slot: index,
+                player: player.clone(),
+            }));
+        }
+        for (&slot, &seconds_left) in &self.waiting {
+            try!(connection.send(&LobbyMessage::Waiting {
+                slot: slot,
+                seconds_left: seconds_left,
+            }));
+        }
+        if let Some((slot, address)) = self.successor {
+            try!(connection.send(&LobbyMessage::Successor {
+                slot: slot,
+                address: address,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the vote got the player dropped
+    fn count_drop_vote(&mut self, voter: usize, slot: usize, now: u64) -> bool {
+        // Everyone but the player in question gets a vote
+        let voters = self.lobby.player_count() - 1;
+        if slot == self.host_slot || !self.peers.vote_drop(voter, slot, voters, now) {
+            return false;
+        }
+        log_info!("Player {} was voted out", slot + 1);
+        self.drop_player(slot);
+        true
+    }
+
+    /// Lets everyone know who they're waiting on, and drops whoever they've waited on for too long.
+    /// Returns whether anything changed.
+    fn check_waiting(&mut self, now: u64) -> bool {
+        let mut changed = false;
+        for slot in self.peers.slots() {
+            match self.peers.status(slot, now) {
+                PeerStatus::Connected => {
+                    if self.waiting.remove(&slot).is_some() {
+                        self.broadcast(&LobbyMessage::Resumed(slot));
+                        changed = true;
+                    }
+                }
+                PeerStatus::Waiting { seconds_left } => {
+                    if self.waiting.insert(slot, seconds_left) != Some(seconds_left) {
+                        self.broadcast(&LobbyMessage::Waiting {
+                            slot: slot,
+                            seconds_left: seconds_left,
+                        });
+                        changed = true;
+                    }
+                }
+                PeerStatus::TimedOut => {
+                    log_info!("Player {} timed out", slot + 1);
+                    self.drop_player(slot);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Frees up the player's slot, telling them first if they're still connected
+    fn drop_player(&mut self, slot: usize) {
+        if let Some(index) = self.players.iter().position(|&(s, _)| s == slot) {
+            let (_, mut connection) = self.players.remove(index);
+            let _ = connection.send(&LobbyMessage::Rejected("Dropped from the game".to_string()));
+        }
+        self.peers.forget(slot);
+        self.waiting.remove(&slot);
+        self.lobby.leave(slot);
+        self.broadcast_slot(slot);
+        if self.started {
+            self.dropped.push(slot);
+        }
+    }
+
+    /// Whoever is connected in the lowest slot takes over if the host drops
+    fn update_successor(&mut self) {
+        let successor = self.players
+            .iter()
+            .filter(|&&(slot, _)| !self.waiting.contains_key(&slot))
+            .min_by_key(|&&(slot, _)| slot)
+            .and_then(|&(slot, ref connection)| {
+                connection.peer_addr().ok().map(|address| (slot, SocketAddr::new(address.ip(), LOBBY_PORT)))
+            });
+        if successor == self.successor {
+            return;
+        }
+        if let Some((slot, address)) = successor {
+            self.broadcast(&LobbyMessage::Successor {
+                slot: slot,
+                address: address,
+            });
+        }
+        self.successor = successor;
+    }
+
+    fn broadcast_slot(&mut self, slot: usize) {
+        let message = LobbyMessage::Slot {
+            slot: slot,
+            player: self.lobby.slot(slot).cloned(),
+        };
+        self.broadcast(&message);
+    }
+
+    /// Players whose connection fails here are noticed on the next poll, when reading fails too
+    fn broadcast(&mut self, message: &LobbyMessage) {
+        self.relay(None, message);
+    }
+}
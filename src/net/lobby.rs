@@ -0,0 +1,309 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::{GameSettings, GameSetup, PlayerSetup};
+use identifier::{CivilizationId, PlayerColorId, PlayerId};
+use super::message::LobbyMessage;
+
+/// Player colors available in the lobby (there are 8 in empires.dat)
+const PLAYER_COLOR_COUNT: u8 = 8;
+
+/// Civilization picked for new players until they choose one
+const DEFAULT_CIVILIZATION_ID: usize = 1;
+
+/// Settings that only the host can change
+#[derive(Clone, Debug, PartialEq)]
+pub struct LobbySettings {
+    /// File name of the scenario inside the game data's scenario directory
+    pub scenario: String,
+    pub max_players: u8,
+    pub game: GameSettings,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LobbySlot {
+    pub name: String,
+    pub civilization_id: CivilizationId,
+    pub player_color_id: PlayerColorId,
+    pub team: u8,
+    /// Whether the player shows their allies what their units see (allies have to agree too)
+    pub allied_vision: bool,
+    pub ready: bool,
+}
+
+/// The state of a lobby that both the host and its clients keep; the host's copy is authoritative.
+/// Slot 0 belongs to whoever created the lobby, and each slot plays as player `slot + 1` (player 0 is gaia).
+#[derive(Clone, Debug)]
+pub struct Lobby {
+    settings: LobbySettings,
+    slots: Vec<Option<LobbySlot>>,
+}
+
+impl Lobby {
+    pub fn new(settings: LobbySettings) -> Lobby {
+        let slots = vec![None; settings.max_players as usize];
+        Lobby {
+            settings: settings,
+            slots: slots,
+        }
+    }
+
+    pub fn settings(&self) -> &LobbySettings {
+        &self.settings
+    }
+
+    pub fn slots(&self) -> &[Option<LobbySlot>] {
+        &self.slots
+    }
+
+    pub fn slot(&self, slot: usize) -> Option<&LobbySlot> {
+        self.slots.get(slot).and_then(|s| s.as_ref())
+    }
+
+    pub fn player_id(slot: usize) -> PlayerId {
+        (slot + 1).into()
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.slots.iter().filter(|s| s.is_some()).count()
+    }
+
+    /// Puts a new player in the first free slot with the first unused color
+    pub fn join(&mut self, name: &str) -> Result<usize, &'static str> {
+        let slot = try!(self.slots.iter().position(|s| s.is_none()).ok_or("The lobby is full"));
+        let player_color_id = (0..PLAYER_COLOR_COUNT)
+            .map(|color| (color as usize).into())
+            .find(|&color| !self.color_taken(color, slot))
+            .unwrap_or((slot % PLAYER_COLOR_COUNT as usize).into());
+        self.slots[slot] = Some(LobbySlot {
+            name: name.to_string(),
+            civilization_id: DEFAULT_CIVILIZATION_ID.into(),
+            player_color_id: player_color_id,
+            team: (slot + 1) as u8,
+            allied_vision: false,
+            ready: false,
+        });
+        Ok(slot)
+    }
+
+    pub fn leave(&mut self, slot: usize) {
+        if let Some(s) = self.slots.get_mut(slot) {
+            *s = None;
+        }
+    }
+
+    /// Changes the settings; everyone has to ready up again afterwards.
+    /// The lobby can't shrink below the slots that are currently taken.
+    pub fn configure(&mut self, settings: LobbySettings) -> Result<(), &'static str> {
+        let max_players = settings.max_players as usize;
+        if self.slots.iter().skip(max_players).any(|s| s.is_some()) {
+            return Err("Not enough slots for the players in the lobby");
+        }
+        self.slots.resize(max_players, None);
+        self.settings = settings;
+        for slot in self.slots.iter_mut().filter_map(|s| s.as_mut()) {
+            slot.ready = false;
+        }
+        Ok(())
+    }
+
+    /// Applies a player's request to change their slot, returning whether anything changed.
+    /// Colors have to be unique, and choices can't change once a player is ready.
+    pub fn apply(&mut self, slot: usize, message: &LobbyMessage) -> bool {
+        let color_taken = match *message {
+            LobbyMessage::SetColor(color) => {
+                *color >= PLAYER_COLOR_COUNT || self.color_taken(color, slot)
+            }
+            _ => false,
+        };
+        let player = match self.slots.get_mut(slot) {
+            Some(&mut Some(ref mut player)) => player,
+            _ => return false,
+        };
+        match *message {
+            LobbyMessage::SetReady(ready) => {
+                let changed = player.ready != ready;
+                player.ready = ready;
+                return changed;
+            }
+            _ if player.ready => return false,
+            LobbyMessage::SetCivilization(civ_id) => player.civilization_id = civ_id,
+            LobbyMessage::SetColor(color) if !color_taken => player.player_color_id = color,
+            LobbyMessage::SetTeam(team) => player.team = team,
+            LobbyMessage::SetAlliedVision(allied_vision) => player.allied_vision = allied_vision,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Used by clients to mirror the host's lobby
+    pub fn set_slot(&mut self, slot: usize, player: Option<LobbySlot>) {
+        if slot >= self.slots.len() {
+            self.slots.resize(slot + 1, None);
+        }
+        self.slots[slot] = player;
+    }
+
+    /// A game needs at least two players, all of whom are ready
+    pub fn can_start(&self) -> bool {
+        self.player_count() >= 2 && self.slots.iter().filter_map(|s| s.as_ref()).all(|s| s.ready)
+    }
+
+    /// What the game is set up with once it starts, as seen by the player in `local_slot`
+    pub fn game_setup(&self, seed: u64, local_slot: usize) -> GameSetup {
+        let mut setup = GameSetup::new(seed);
+        setup.local_player_id = Lobby::player_id(local_slot);
+        setup.settings = self.settings.game.clone();
+        for (slot, player) in self.slots.iter().enumerate() {
+            if let Some(ref player) = *player {
+                setup.players.push(PlayerSetup {
+                    player_id: Lobby::player_id(slot),
+                    name: player.name.clone(),
+                    civilization_id: player.civilization_id,
+                    player_color_id: player.player_color_id,
+                    team: player.team,
+                    allied_vision: player.allied_vision,
+                });
+            }
+        }
+        setup.assign_positions();
+        setup
+    }
+
+    /// What the game is set up with for observers; they start out watching the host
+    pub fn observer_setup(&self, seed: u64) -> GameSetup {
+        let mut setup = self.game_setup(seed, 0);
+        setup.observer = true;
+        setup
+    }
+
+    fn color_taken(&self, color: PlayerColorId, except_slot: usize) -> bool {
+        self.slots.iter().enumerate().any(|(slot, s)| {
+            slot != except_slot && s.as_ref().map(|s| s.player_color_id == color).unwrap_or(false)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identifier::{CivilizationId, PlayerColorId, PlayerId};
+    use net::LobbyMessage;
+    use super::*;
+
+    fn new_lobby(max_players: u8) -> Lobby {
+        Lobby::new(LobbySettings {
+            scenario: "test.scn".to_string(),
+            max_players: max_players,
+            game: GameSettings::new(),
+        })
+    }
+
+    #[test]
+    fn test_join_until_full() {
+        let mut lobby = new_lobby(2);
+        assert_eq!(Ok(0), lobby.join("host"));
+        assert_eq!(Ok(1), lobby.join("guest"));
+        assert!(lobby.join("late").is_err());
+
+        lobby.leave(0);
+        assert_eq!(Ok(0), lobby.join("late"));
+        assert_eq!(2, lobby.player_count());
+    }
+
+    #[test]
+    fn test_unique_colors() {
+        let mut lobby = new_lobby(3);
+        lobby.join("host").unwrap();
+        lobby.join("guest").unwrap();
+        let host_color = lobby.slot(0).unwrap().player_color_id;
+        assert!(host_color != lobby.slot(1).unwrap().player_color_id);
+
+        assert!(!lobby.apply(1, &LobbyMessage::SetColor(host_color)));
+        assert!(!lobby.apply(1, &LobbyMessage::SetColor(8.into())));
+        let color: PlayerColorId = 5.into();
+        assert!(lobby.apply(1, &LobbyMessage::SetColor(color)));
+        assert_eq!(color, lobby.slot(1).unwrap().player_color_id);
+    }
+
+    #[test]
+    fn test_ready_locks_choices() {
+        let mut lobby = new_lobby(2);
+        lobby.join("host").unwrap();
+        assert!(lobby.apply(0, &LobbyMessage::SetReady(true)));
+        assert!(!lobby.apply(0, &LobbyMessage::SetTeam(4)));
+        assert!(lobby.apply(0, &LobbyMessage::SetReady(false)));
+        assert!(lobby.apply(0, &LobbyMessage::SetTeam(4)));
+        assert_eq!(4, lobby.slot(0).unwrap().team);
+        assert!(lobby.apply(0, &LobbyMessage::SetAlliedVision(true)));
+        assert!(lobby.slot(0).unwrap().allied_vision);
+        assert!(!lobby.apply(1, &LobbyMessage::SetTeam(4)));
+    }
+
+    #[test]
+    fn test_can_start() {
+        let mut lobby = new_lobby(4);
+        lobby.join("host").unwrap();
+        lobby.apply(0, &LobbyMessage::SetReady(true));
+        assert!(!lobby.can_start());
+
+        lobby.join("guest").unwrap();
+        assert!(!lobby.can_start());
+        lobby.apply(1, &LobbyMessage::SetReady(true));
+        assert!(lobby.can_start());
+
+        let settings = lobby.settings().clone();
+        lobby.configure(settings).unwrap();
+        assert!(!lobby.can_start());
+    }
+
+    #[test]
+    fn test_configure_cannot_drop_players() {
+        let mut lobby = new_lobby(4);
+        lobby.join("host").unwrap();
+        lobby.join("guest").unwrap();
+        let mut settings = lobby.settings().clone();
+        settings.max_players = 1;
+        assert!(lobby.configure(settings.clone()).is_err());
+        settings.max_players = 2;
+        assert!(lobby.configure(settings).is_ok());
+        assert_eq!(2, lobby.slots().len());
+    }
+
+    #[test]
+    fn test_game_setup() {
+        let mut lobby = new_lobby(4);
+        lobby.join("host").unwrap();
+        lobby.join("guest").unwrap();
+        lobby.apply(1, &LobbyMessage::SetCivilization(7.into()));
+
+        let setup = lobby.game_setup(1234, 1);
+        assert_eq!(1234, setup.seed);
+        let guest_id: PlayerId = 2.into();
+        assert_eq!(guest_id, setup.local_player_id);
+        assert_eq!(2, setup.players.len());
+        assert!(!setup.observer);
+        assert!(lobby.observer_setup(1234).observer);
+        let guest = setup.player(2.into()).unwrap();
+        let civ_id: CivilizationId = 7.into();
+        assert_eq!("guest", guest.name);
+        assert_eq!(civ_id, guest.civilization_id);
+    }
+}
@@ -0,0 +1,448 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ecs::GameSettings;
+use identifier::{CivilizationId, PlayerColorId};
+use std::io;
+use std::net::SocketAddr;
+use super::lobby::{LobbySettings, LobbySlot};
+
+/// Bumped whenever a message changes so that mismatched builds don't try to play together. The
+/// binary command logs that `Commands` carry aren't covered by it: joining players list the
+/// versions they can read, and the host picks one everyone shares (see `Start`).
+pub const PROTOCOL_VERSION: u32 = 8;
+
+/// What a host broadcasts on the LAN so that clients can find its lobby
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameAnnouncement {
+    pub version: u32,
+    pub game_name: String,
+    /// TCP port the lobby accepts connections on (the address is wherever the broadcast came from)
+    pub lobby_port: u16,
+    pub player_count: u8,
+    pub max_players: u8,
+}
+
+/// Everything sent between the host and clients, in the lobby and during the game.
+///
+/// Each message is a single line of text: a keyword followed by space separated fields.
+/// Free form text (names) always comes last so that it can contain spaces.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LobbyMessage {
+    /// Host to everyone on the LAN (over UDP)
+    Announce(GameAnnouncement),
+
+    /// Client to host; observers get to watch the game without taking a slot. `command_formats`
+    /// are the command log formats the client can read, to settle on one the game's commands are
+    /// sent in.
+    Join {
+        version: u32,
+        observer: bool,
+        command_formats: Vec<u8>,
+        name: String,
+    },
+    SetCivilization(CivilizationId),
+    SetColor(PlayerColorId),
+    SetTeam(u8),
+    SetAlliedVision(bool),
+    SetReady(bool),
+    Leave,
+    /// Client to host; votes to stop waiting for the player in the slot and drop them
+    VoteDrop(usize),
+
+    /// Both ways; sent every second so that a player (or host) who goes quiet is noticed even
+    /// without the connection closing
+    Ping,
+
+    /// Host to client; observers don't get a slot
+    Welcome { slot: Option<usize> },
+    Rejected(String),
+    Settings(LobbySettings),
+    Slot { slot: usize, player: Option<LobbySlot> },
+    /// Everyone has what they need; the game starts with this seed, and everyone sends their
+    /// commands in this command log version
+    Start { seed: u64, command_format: u8 },
+    /// Nothing has been heard from the player in the slot; they'll be dropped if they stay quiet
+    Waiting { slot: usize, seconds_left: u32 },
+    /// The player in the slot is back before they were dropped
+    Resumed(usize),
+    /// Who takes over hosting if the host drops, and the address everyone else reconnects to
+    Successor { slot: usize, address: SocketAddr },
+
+    /// Both ways during the game; what the player in the slot did for the tick (as a binary
+    /// command log), which the host passes on to everyone else
+    Commands { slot: usize, tick: u64, commands: Vec<u8> },
+    /// Host to client during the game; the player in the slot is out, and their units are
+    /// resigned on the tick
+    Dropped { slot: usize, tick: u64 },
+}
+
+impl LobbyMessage {
+    pub fn parse(line: &str) -> io::Result<LobbyMessage> {
+        parse_message(line.trim()).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("lobby message: {}", err))
+        })
+    }
+
+    pub fn to_line(&self) -> String {
+        match *self {
+            LobbyMessage::Announce(ref game) => {
+                format!("announce {} {} {} {} {}",
+                        game.version,
+                        game.lobby_port,
+                        game.player_count,
+                        game.max_players,
+                        single_line(&game.game_name))
+            }
+            LobbyMessage::Join { version, observer, ref command_formats, ref name } => {
+                let command_formats: Vec<String> = command_formats.iter()
+                    .map(|format| format.to_string())
+                    .collect();
+                format!("join {} {} {} {}",
+                        version,
+                        observer as u8,
+                        command_formats.join(","),
+                        single_line(name))
+            }
+            LobbyMessage::SetCivilization(civ_id) => format!("civ {}", *civ_id),
+            LobbyMessage::SetColor(color_id) => format!("color {}", *color_id),
+            LobbyMessage::SetTeam(team) => format!("team {}", team),
+            LobbyMessage::SetAlliedVision(allied_vision) => format!("vision {}", allied_vision as u8),
+            LobbyMessage::SetReady(ready) => format!("ready {}", ready as u8),
+            LobbyMessage::Leave => "leave".to_string(),
+            LobbyMessage::VoteDrop(slot) => format!("votedrop {}", slot),
+            LobbyMessage::Ping => "ping".to_string(),
+            LobbyMessage::Welcome { slot: None } => "welcome observer".to_string(),
+            LobbyMessage::Welcome { slot: Some(slot) } => format!("welcome {}", slot),
+            LobbyMessage::Rejected(ref reason) => format!("rejected {}", single_line(reason)),
+            LobbyMessage::Settings(ref settings) => {
+                format!("settings {} {} {}",
+                        settings.max_players,
+                        settings.game.to_field(),
+                        single_line(&settings.scenario))
+            }
+            LobbyMessage::Slot { slot, player: None } => format!("slot {} empty", slot),
+            LobbyMessage::Slot { slot, player: Some(ref player) } => {
+                format!("slot {} {} {} {} {} {} {}",
+                        slot,
+                        *player.civilization_id,
+                        *player.player_color_id,
+                        player.team,
+                        player.allied_vision as u8,
+                        player.ready as u8,
+                        single_line(&player.name))
+            }
+            LobbyMessage::Start { seed, command_format } => format!("start {} {}", seed, command_format),
+            LobbyMessage::Waiting { slot, seconds_left } => format!("waiting {} {}", slot, seconds_left),
+            LobbyMessage::Resumed(slot) => format!("resumed {}", slot),
+            LobbyMessage::Successor { slot, ref address } => format!("successor {} {}", slot, address),
+            LobbyMessage::Commands { slot, tick, ref commands } => {
+                let hex: Vec<String> = commands.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("commands {} {} {}", slot, tick, hex.concat())
+            }
+            LobbyMessage::Dropped { slot, tick } => format!("dropped {} {}", slot, tick),
+        }
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.replace(|c: char| c == '\n' || c == '\r', " ")
+}
+
+fn parse_message(line: &str) -> Result<LobbyMessage, String> {
+    let (keyword, rest) = split_field(line);
+    let message = match keyword {
+        "announce" => {
+            let (version, rest) = split_field(rest);
+            let (lobby_port, rest) = split_field(rest);
+            let (player_count, rest) = split_field(rest);
+            let (max_players, game_name) = split_field(rest);
+            LobbyMessage::Announce(GameAnnouncement {
+                version: try!(parse_field(version, "version")),
+                game_name: game_name.to_string(),
+                lobby_port: try!(parse_field(lobby_port, "lobby port")),
+                player_count: try!(parse_field(player_count, "player count")),
+                max_players: try!(parse_field(max_players, "max players")),
+            })
+        }
+        "join" => {
+            let (version, rest) = split_field(rest);
+            let (observer, rest) = split_field(rest);
+            let (command_formats, name) = split_field(rest);
+            let mut formats = Vec::new();
+            for format in command_formats.split(',') {
+                formats.push(try!(parse_field(format, "command format")));
+            }
+            LobbyMessage::Join {
+                version: try!(parse_field(version, "version")),
+                observer: try!(parse_flag(observer, "observer")),
+                command_formats: formats,
+                name: name.to_string(),
+            }
+        }
+        "civ" => LobbyMessage::SetCivilization((try!(parse_field::<u8>(rest, "civilization")) as usize).into()),
+        "color" => LobbyMessage::SetColor((try!(parse_field::<u8>(rest, "color")) as usize).into()),
+        "team" => LobbyMessage::SetTeam(try!(parse_field(rest, "team"))),
+        "vision" => LobbyMessage::SetAlliedVision(try!(parse_flag(rest, "allied vision"))),
+        "ready" => LobbyMessage::SetReady(try!(parse_flag(rest, "ready"))),
+        "leave" => LobbyMessage::Leave,
+        "votedrop" => LobbyMessage::VoteDrop(try!(parse_field(rest, "slot"))),
+        "ping" => LobbyMessage::Ping,
+        "welcome" if rest == "observer" => LobbyMessage::Welcome { slot: None },
+        "welcome" => LobbyMessage::Welcome { slot: Some(try!(parse_field(rest, "slot"))) },
+        "rejected" => LobbyMessage::Rejected(rest.to_string()),
+        "settings" => {
+            let (max_players, rest) = split_field(rest);
+            let (game, scenario) = split_field(rest);
+            if scenario.is_empty() {
+                return Err("missing scenario".to_string());
+            }
+            LobbyMessage::Settings(LobbySettings {
+                scenario: scenario.to_string(),
+                max_players: try!(parse_field(max_players, "max players")),
+                game: try!(GameSettings::parse(game)),
+            })
+        }
+        "slot" => {
+            let (slot, rest) = split_field(rest);
+            let slot = try!(parse_field(slot, "slot"));
+            if rest == "empty" {
+                LobbyMessage::Slot {
+                    slot: slot,
+                    player: None,
+                }
+            } else {
+                let (civ_id, rest) = split_field(rest);
+                let (color_id, rest) = split_field(rest);
+                let (team, rest) = split_field(rest);
+                let (allied_vision, rest) = split_field(rest);
+                let (ready, name) = split_field(rest);
+                LobbyMessage::Slot {
+                    slot: slot,
+                    player: Some(LobbySlot {
+                        name: name.to_string(),
+                        civilization_id: (try!(parse_field::<u8>(civ_id, "civilization")) as usize).into(),
+                        player_color_id: (try!(parse_field::<u8>(color_id, "color")) as usize).into(),
+                        team: try!(parse_field(team, "team")),
+                        allied_vision: try!(parse_flag(allied_vision, "allied vision")),
+                        ready: try!(parse_flag(ready, "ready")),
+                    }),
+                }
+            }
+        }
+        "start" => {
+            let (seed, command_format) = split_field(rest);
+            LobbyMessage::Start {
+                seed: try!(parse_field(seed, "seed")),
+                command_format: try!(parse_field(command_format, "command format")),
+            }
+        }
+        "waiting" => {
+            let (slot, seconds_left) = split_field(rest);
+            LobbyMessage::Waiting {
+                slot: try!(parse_field(slot, "slot")),
+                seconds_left: try!(parse_field(seconds_left, "seconds left")),
+            }
+        }
+        "resumed" => LobbyMessage::Resumed(try!(parse_field(rest, "slot"))),
+        "successor" => {
+            let (slot, address) = split_field(rest);
+            LobbyMessage::Successor {
+                slot: try!(parse_field(slot, "slot")),
+                address: try!(parse_field(address, "address")),
+            }
+        }
+        "commands" => {
+            let (slot, rest) = split_field(rest);
+            let (tick, commands) = split_field(rest);
+            LobbyMessage::Commands {
+                slot: try!(parse_field(slot, "slot")),
+                tick: try!(parse_field(tick, "tick")),
+                commands: try!(parse_hex(commands, "commands")),
+            }
+        }
+        "dropped" => {
+            let (slot, tick) = split_field(rest);
+            LobbyMessage::Dropped {
+                slot: try!(parse_field(slot, "slot")),
+                tick: try!(parse_field(tick, "tick")),
+            }
+        }
+        "" => return Err("empty message".to_string()),
+        unknown => return Err(format!("unknown message \"{}\"", unknown)),
+    };
+    Ok(message)
+}
+
+/// Splits off the first space separated field, returning it and the remainder of the line
+fn split_field(text: &str) -> (&str, &str) {
+    match text.find(' ') {
+        Some(index) => (&text[..index], &text[index + 1..]),
+        None => (text, ""),
+    }
+}
+
+fn parse_field<T: ::std::str::FromStr>(field: &str, name: &str) -> Result<T, String> {
+    if field.is_empty() {
+        return Err(format!("missing {}", name));
+    }
+    field.parse().map_err(|_| format!("invalid {} \"{}\"", name, field))
+}
+
+fn parse_flag(field: &str, name: &str) -> Result<bool, String> {
+    match field {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(format!("invalid {} \"{}\"", name, field)),
+    }
+}
+
+fn parse_hex(field: &str, name: &str) -> Result<Vec<u8>, String> {
+    if field.len() % 2 != 0 || !field.chars().all(|c| c.is_digit(16)) {
+        return Err(format!("invalid {} \"{}\"", name, field));
+    }
+    let mut bytes = Vec::new();
+    for index in 0..field.len() / 2 {
+        bytes.push(try!(u8::from_str_radix(&field[2 * index..2 * index + 2], 16)
+            .map_err(|_| format!("invalid {} \"{}\"", name, field))));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::GameSettings;
+    use super::*;
+    use net::lobby::{LobbySettings, LobbySlot};
+
+    fn round_trip(message: LobbyMessage) {
+        let line = message.to_line();
+        assert_eq!(message, LobbyMessage::parse(&line).unwrap(), "{}", line);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(LobbyMessage::Announce(GameAnnouncement {
+            version: PROTOCOL_VERSION,
+            game_name: "Kevin's game".to_string(),
+            lobby_port: 28960,
+            player_count: 2,
+            max_players: 4,
+        }));
+        round_trip(LobbyMessage::Join {
+            version: PROTOCOL_VERSION,
+            observer: false,
+            command_formats: vec![1],
+            name: "Player Two".to_string(),
+        });
+        round_trip(LobbyMessage::Join {
+            version: PROTOCOL_VERSION,
+            observer: true,
+            command_formats: vec![1, 2],
+            name: "Spectator".to_string(),
+        });
+        round_trip(LobbyMessage::SetCivilization(5.into()));
+        round_trip(LobbyMessage::SetColor(3.into()));
+        round_trip(LobbyMessage::SetTeam(2));
+        round_trip(LobbyMessage::SetAlliedVision(true));
+        round_trip(LobbyMessage::SetReady(true));
+        round_trip(LobbyMessage::Leave);
+        round_trip(LobbyMessage::Welcome { slot: Some(1) });
+        round_trip(LobbyMessage::Welcome { slot: None });
+        round_trip(LobbyMessage::Rejected("The lobby is full".to_string()));
+        round_trip(LobbyMessage::Settings(LobbySettings {
+            scenario: "The Great Hunt.scn".to_string(),
+            max_players: 2,
+            game: GameSettings::parse("resources=low,positions=random").unwrap(),
+        }));
+        round_trip(LobbyMessage::Slot {
+            slot: 0,
+            player: None,
+        });
+        round_trip(LobbyMessage::Slot {
+            slot: 1,
+            player: Some(LobbySlot {
+                name: "Player Two".to_string(),
+                civilization_id: 4.into(),
+                player_color_id: 1.into(),
+                team: 1,
+                allied_vision: true,
+                ready: false,
+            }),
+        });
+        round_trip(LobbyMessage::Start {
+            seed: 0xdeadbeefcafe,
+            command_format: 3,
+        });
+        round_trip(LobbyMessage::VoteDrop(2));
+        round_trip(LobbyMessage::Ping);
+        round_trip(LobbyMessage::Waiting {
+            slot: 2,
+            seconds_left: 25,
+        });
+        round_trip(LobbyMessage::Resumed(2));
+        round_trip(LobbyMessage::Successor {
+            slot: 1,
+            address: "192.168.1.20:28960".parse().unwrap(),
+        });
+        round_trip(LobbyMessage::Commands {
+            slot: 1,
+            tick: 412,
+            commands: vec![0x43, 0x48, 0x00, 0xff],
+        });
+        round_trip(LobbyMessage::Commands {
+            slot: 0,
+            tick: 7,
+            commands: Vec::new(),
+        });
+        round_trip(LobbyMessage::Dropped {
+            slot: 2,
+            tick: 1200,
+        });
+    }
+
+    #[test]
+    fn test_names_stay_on_one_line() {
+        let line = LobbyMessage::Join {
+            version: PROTOCOL_VERSION,
+            observer: false,
+            command_formats: vec![1],
+            name: "two\nlines".to_string(),
+        }
+            .to_line();
+        assert_eq!(format!("join {} 0 1 two lines", PROTOCOL_VERSION), line);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(LobbyMessage::parse("").is_err());
+        assert!(LobbyMessage::parse("dance").is_err());
+        assert!(LobbyMessage::parse("ready yes").is_err());
+        assert!(LobbyMessage::parse("civ 300").is_err());
+        assert!(LobbyMessage::parse("welcome").is_err());
+        assert!(LobbyMessage::parse("settings 4").is_err());
+        assert!(LobbyMessage::parse("settings 4 resources=lots game.scn").is_err());
+        assert!(LobbyMessage::parse("successor 1 nowhere").is_err());
+        assert!(LobbyMessage::parse("join 3 0 1,x Sam").is_err());
+        assert!(LobbyMessage::parse("commands 1 20 abc").is_err());
+        assert!(LobbyMessage::parse("commands 1 20 zz").is_err());
+    }
+}
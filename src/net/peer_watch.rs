@@ -0,0 +1,156 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+const NS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Players who haven't been heard from for this long are waited on (and can be voted out)
+const WAIT_NOTICE_NS: u64 = 3 * NS_PER_SECOND;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PeerStatus {
+    Connected,
+    /// Nothing has been heard from the player for a while; they're dropped once time runs out
+    Waiting { seconds_left: u32 },
+    TimedOut,
+}
+
+/// Keeps track of when each player was last heard from, to tell when everyone is waiting on
+/// someone and when to give up on them. Players can also vote to drop someone who's being waited
+/// on without waiting out the whole timeout.
+pub struct PeerWatch {
+    timeout_ns: u64,
+    last_heard_ns: BTreeMap<usize, u64>,
+    /// Who has voted to drop each slot
+    drop_votes: BTreeMap<usize, BTreeSet<usize>>,
+}
+
+impl PeerWatch {
+    pub fn new(timeout_seconds: u32) -> PeerWatch {
+        PeerWatch {
+            timeout_ns: timeout_seconds as u64 * NS_PER_SECOND,
+            last_heard_ns: BTreeMap::new(),
+            drop_votes: BTreeMap::new(),
+        }
+    }
+
+    /// Starts watching the slot, or notes that its player was just heard from. Hearing from
+    /// a player calls off any vote to drop them.
+    pub fn heard(&mut self, slot: usize, now_ns: u64) {
+        self.last_heard_ns.insert(slot, now_ns);
+        self.drop_votes.remove(&slot);
+    }
+
+    /// Stops watching the slot (the player left or was dropped); their votes go with them
+    pub fn forget(&mut self, slot: usize) {
+        self.last_heard_ns.remove(&slot);
+        self.drop_votes.remove(&slot);
+        for voters in self.drop_votes.values_mut() {
+            voters.remove(&slot);
+        }
+    }
+
+    /// Every watched slot, in order
+    pub fn slots(&self) -> Vec<usize> {
+        self.last_heard_ns.keys().cloned().collect()
+    }
+
+    pub fn status(&self, slot: usize, now_ns: u64) -> PeerStatus {
+        let silent_ns = match self.last_heard_ns.get(&slot) {
+            Some(&last_heard_ns) => now_ns.saturating_sub(last_heard_ns),
+            None => return PeerStatus::Connected,
+        };
+        if silent_ns >= self.timeout_ns {
+            PeerStatus::TimedOut
+        } else if silent_ns >= WAIT_NOTICE_NS {
+            let left_ns = self.timeout_ns - silent_ns;
+            PeerStatus::Waiting { seconds_left: ((left_ns + NS_PER_SECOND - 1) / NS_PER_SECOND) as u32 }
+        } else {
+            PeerStatus::Connected
+        }
+    }
+
+    /// The player in `voter` votes to drop the one in `slot`, which only counts while everyone's
+    /// waiting on them. Returns whether more than half of the `voters` (everyone in the game but the
+    /// player in `slot`) now want them dropped.
+    pub fn vote_drop(&mut self, voter: usize, slot: usize, voters: usize, now_ns: u64) -> bool {
+        match self.status(slot, now_ns) {
+            PeerStatus::Connected => return false,
+            PeerStatus::TimedOut => return true,
+            PeerStatus::Waiting { .. } => {}
+        }
+        if voter == slot {
+            return false;
+        }
+        let votes = self.drop_votes.entry(slot).or_insert_with(BTreeSet::new);
+        votes.insert(voter);
+        votes.len() * 2 > voters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NS_PER_SECOND, PeerStatus, PeerWatch};
+
+    const SECOND: u64 = NS_PER_SECOND;
+
+    #[test]
+    fn test_status() {
+        let mut peers = PeerWatch::new(10);
+        assert_eq!(PeerStatus::Connected, peers.status(1, 0));
+
+        peers.heard(1, 0);
+        assert_eq!(PeerStatus::Connected, peers.status(1, 2 * SECOND));
+        assert_eq!(PeerStatus::Waiting { seconds_left: 7 }, peers.status(1, 3 * SECOND));
+        assert_eq!(PeerStatus::Waiting { seconds_left: 1 }, peers.status(1, 9 * SECOND + 1));
+        assert_eq!(PeerStatus::TimedOut, peers.status(1, 10 * SECOND));
+
+        peers.heard(1, 10 * SECOND);
+        assert_eq!(PeerStatus::Connected, peers.status(1, 11 * SECOND));
+        peers.forget(1);
+        assert_eq!(PeerStatus::Connected, peers.status(1, 60 * SECOND));
+    }
+
+    #[test]
+    fn test_vote_drop() {
+        let mut peers = PeerWatch::new(30);
+        peers.heard(1, 0);
+        peers.heard(2, 0);
+        peers.heard(3, 0);
+
+        // Nobody can be voted out before everyone is waiting on them
+        assert!(!peers.vote_drop(0, 1, 3, 0));
+
+        // Three players are left besides slot 1, so it takes two votes
+        let now = 5 * SECOND;
+        peers.heard(2, now);
+        peers.heard(3, now);
+        assert!(!peers.vote_drop(0, 1, 3, now));
+        assert!(!peers.vote_drop(0, 1, 3, now));
+        assert!(!peers.vote_drop(1, 1, 3, now));
+        assert!(peers.vote_drop(2, 1, 3, now));
+
+        // Hearing from them again calls the vote off
+        peers.heard(1, now);
+        assert!(!peers.vote_drop(3, 1, 3, 9 * SECOND));
+    }
+}
@@ -0,0 +1,529 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use action::{CommandLog, CommandLogEntry};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::net::SocketAddr;
+use super::client::{LobbyClient, LobbyEvent};
+use super::host::LobbyHost;
+use super::lobby::Lobby;
+use super::message::LobbyMessage;
+use time;
+
+/// How many ticks after being issued commands are carried out, to give them time to reach everyone
+pub const COMMAND_DELAY_TICKS: u64 = 6;
+
+/// How many ticks' worth of game messages are kept to send again after the host drops, so that
+/// whoever takes over can pass on anything that didn't make it to everyone. Nobody gets further
+/// ahead of anyone else than the command delay.
+const RESEND_TICKS: u64 = 4 * COMMAND_DELAY_TICKS;
+
+/// The game only says who it's waiting on once it's been held up for this long
+const STALL_NOTICE_NS: u64 = 1_000_000_000;
+
+/// Everything to carry out on a tick
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    pub tick: u64,
+    /// Everyone's commands, in slot order
+    pub commands: Vec<CommandLogEntry>,
+    /// Players dropped on this tick, whose units resign
+    pub dropped: Vec<usize>,
+}
+
+/// Someone the game is held up on
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaitingFor {
+    pub slot: usize,
+    pub name: String,
+    /// How long until they're dropped, once the host has noticed they've gone quiet
+    pub seconds_left: Option<u32>,
+}
+
+/// The commands each player has sent for the ticks that haven't been run yet. A tick can only be
+/// run once everyone still in the game has sent theirs for it; the first `COMMAND_DELAY_TICKS`
+/// don't need any, since nothing could have been issued in time for them.
+pub struct TickQueue {
+    next_tick: u64,
+    /// The last tick each player has sent commands for
+    last_ticks: BTreeMap<usize, u64>,
+    /// The tick each dropped player was dropped on, from which they aren't waited on
+    dropped: BTreeMap<usize, u64>,
+    /// By tick, then by slot
+    commands: BTreeMap<u64, BTreeMap<usize, Vec<CommandLogEntry>>>,
+}
+
+impl TickQueue {
+    pub fn new(slots: &[usize]) -> TickQueue {
+        TickQueue {
+            next_tick: 1,
+            last_ticks: slots.iter().map(|&slot| (slot, COMMAND_DELAY_TICKS)).collect(),
+            dropped: BTreeMap::new(),
+            commands: BTreeMap::new(),
+        }
+    }
+
+    pub fn next_tick(&self) -> u64 {
+        self.next_tick
+    }
+
+    pub fn last_tick(&self, slot: usize) -> Option<u64> {
+        self.last_ticks.get(&slot).cloned()
+    }
+
+    fn is_needed(&self, slot: usize, tick: u64) -> bool {
+        self.dropped.get(&slot).map_or(true, |&dropped_tick| tick < dropped_tick)
+    }
+
+    /// Adds the player's commands for the tick, which have to come straight after the last ones
+    /// they sent. Returns whether they hadn't been added already.
+    pub fn add(&mut self, slot: usize, tick: u64, commands: Vec<CommandLogEntry>) -> bool {
+        let follows = self.last_tick(slot).map_or(false, |last_tick| tick == last_tick + 1);
+        if !follows || !self.is_needed(slot, tick) {
+            return false;
+        }
+        self.last_ticks.insert(slot, tick);
+        self.commands.entry(tick).or_insert_with(BTreeMap::new).insert(slot, commands);
+        true
+    }
+
+    /// Stops waiting on the player from the tick on. Returns whether they hadn't been dropped
+    /// already.
+    pub fn drop_player(&mut self, slot: usize, tick: u64) -> bool {
+        if !self.last_ticks.contains_key(&slot) || self.dropped.contains_key(&slot) {
+            return false;
+        }
+        self.dropped.insert(slot, tick);
+        true
+    }
+
+    /// The players whose commands for the next tick haven't arrived yet
+    pub fn missing(&self) -> Vec<usize> {
+        let tick = self.next_tick;
+        self.last_ticks
+            .iter()
+            .filter(|&(&slot, &last_tick)| last_tick < tick && self.is_needed(slot, tick))
+            .map(|(&slot, _)| slot)
+            .collect()
+    }
+
+    /// Everything to carry out on the next tick, once it's all there
+    pub fn take_turn(&mut self) -> Option<Turn> {
+        if !self.missing().is_empty() {
+            return None;
+        }
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        let commands = self.commands.remove(&tick).unwrap_or(BTreeMap::new());
+        Some(Turn {
+            tick: tick,
+            commands: commands.into_iter().flat_map(|(_, commands)| commands).collect(),
+            dropped: self.dropped
+                .iter()
+                .filter(|&(_, &dropped_tick)| dropped_tick == tick)
+                .map(|(&slot, _)| slot)
+                .collect(),
+        })
+    }
+}
+
+enum Link {
+    Host(LobbyHost),
+    Client(LobbyClient),
+    /// The host dropped and someone else took over; following them there until the deadline
+    Rejoining { address: SocketAddr, deadline_ns: u64 },
+}
+
+/// Keeps a LAN game in lockstep: every player sends their commands for each tick (through the
+/// host, which passes them on), and nobody runs a tick until they have everyone's commands for
+/// it. Players who go quiet hold everyone up until they're dropped (once the drop timeout runs
+/// out, or everyone else votes them out), after which their units are resigned. If the host
+/// drops, whoever is next in line takes over and everyone else rejoins them.
+pub struct LockstepSession {
+    game_name: String,
+    player_name: String,
+    /// Observers have no slot, and never send commands
+    slot: Option<usize>,
+    drop_timeout: u32,
+    link: Link,
+    /// The lobby as last known, for the names of who's being waited on
+    lobby: Lobby,
+    ticks: TickQueue,
+    /// The command log version that everyone in the game can read, which commands are sent in
+    command_format: u8,
+    /// Game messages taken in lately, with the tick each is for (see `RESEND_TICKS`)
+    recent: VecDeque<(u64, LobbyMessage)>,
+    stalled_since_ns: Option<u64>,
+}
+
+impl LockstepSession {
+    /// Plays the game the host just started (see `LobbyHost::start`)
+    pub fn host(game_name: &str, player_name: &str, host: LobbyHost, drop_timeout: u32) -> LockstepSession {
+        let slot = Some(host.host_slot());
+        let lobby = host.lobby().clone();
+        let command_format = host.command_format();
+        LockstepSession::new(game_name,
+                             player_name,
+                             slot,
+                             drop_timeout,
+                             lobby,
+                             command_format,
+                             Link::Host(host))
+    }
+
+    /// Plays the game the client's host just started (see `LobbyEvent::Started`)
+    pub fn client(game_name: &str,
+                  player_name: &str,
+                  client: LobbyClient,
+                  drop_timeout: u32)
+                  -> LockstepSession {
+        let slot = client.slot();
+        // The game can't have started before the host described the lobby and the command format
+        let lobby = client.lobby().unwrap().clone();
+        let command_format = client.command_format().unwrap();
+        LockstepSession::new(game_name,
+                             player_name,
+                             slot,
+                             drop_timeout,
+                             lobby,
+                             command_format,
+                             Link::Client(client))
+    }
+
+    fn new(game_name: &str,
+           player_name: &str,
+           slot: Option<usize>,
+           drop_timeout: u32,
+           lobby: Lobby,
+           command_format: u8,
+           link: Link)
+           -> LockstepSession {
+        let slots: Vec<usize> = (0..lobby.slots().len()).filter(|&slot| lobby.slot(slot).is_some()).collect();
+        LockstepSession {
+            game_name: game_name.to_string(),
+            player_name: player_name.to_string(),
+            slot: slot,
+            drop_timeout: drop_timeout,
+            link: link,
+            lobby: lobby,
+            ticks: TickQueue::new(&slots),
+            command_format: command_format,
+            recent: VecDeque::new(),
+            stalled_since_ns: None,
+        }
+    }
+
+    /// The local player's slot, or None when observing
+    pub fn slot(&self) -> Option<usize> {
+        self.slot
+    }
+
+    /// Whether the host dropped and this player is still on their way to whoever took over
+    pub fn is_rejoining(&self) -> bool {
+        match self.link {
+            Link::Rejoining { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Sends the local player's commands for the tick: everything the ActionBatcher held back
+    /// for it, which may be nothing. Observers don't send any.
+    pub fn send(&mut self, tick: u64, commands: &CommandLog) {
+        let slot = match self.slot {
+            Some(slot) => slot,
+            None => return,
+        };
+        let commands = match commands.to_bytes_in(self.command_format) {
+            Ok(commands) => commands,
+            Err(err) => {
+                // Everyone waits on the tick until this player is dropped
+                log_error!("Couldn't send the commands for tick {}: {}", tick, err);
+                return;
+            }
+        };
+        let message = LobbyMessage::Commands {
+            slot: slot,
+            tick: tick,
+            commands: commands,
+        };
+        self.take_in(Some(slot), message.clone());
+        if let Link::Client(ref mut client) = self.link {
+            // If this fails, the host is found to be gone on the next poll
+            let _ = client.request(&message);
+        }
+    }
+
+    /// Sends and receives whatever's due. Fails once the game can't go on over the network: this
+    /// player was dropped, or the host dropped with nobody to take over.
+    pub fn poll(&mut self) -> io::Result<()> {
+        let now = time::precise_time_ns();
+        let mut received = Vec::new();
+        let mut dropped = Vec::new();
+        let mut rejoined = Vec::new();
+        let mut next_link = None;
+        match self.link {
+            Link::Host(ref mut host) => {
+                try!(host.poll());
+                self.lobby = host.lobby().clone();
+                received = host.take_received()
+                    .into_iter()
+                    .map(|(slot, message)| (Some(slot), message))
+                    .collect();
+                dropped = host.take_dropped();
+                rejoined = host.take_rejoined();
+            }
+            Link::Client(ref mut client) => {
+                match try!(client.poll()) {
+                    LobbyEvent::TakeOver => {
+                        log_info!("Lost the host; taking over \"{}\"", self.game_name);
+                        let lobby = client.lobby().unwrap().clone();
+                        let mut host = try!(LobbyHost::take_over(&self.game_name,
+                                                                 lobby,
+                                                                 client.slot().unwrap(),
+                                                                 self.drop_timeout));
+                        host.resume_game(self.command_format);
+                        next_link = Some(Link::Host(host));
+                    }
+                    LobbyEvent::HostMoved(address) => {
+                        log_info!("Lost the host; rejoining \"{}\" at {}", self.game_name, address);
+                        next_link = Some(Link::Rejoining {
+                            address: address,
+                            deadline_ns: now + self.drop_timeout as u64 * 1_000_000_000,
+                        });
+                    }
+                    _ => {}
+                }
+                if let Some(lobby) = client.lobby() {
+                    self.lobby = lobby.clone();
+                }
+                received = client.take_received().into_iter().map(|message| (None, message)).collect();
+            }
+            Link::Rejoining { address, deadline_ns } => {
+                let observer = self.slot.is_none();
+                match LobbyClient::join(&address, &self.player_name, observer, self.drop_timeout) {
+                    Ok(client) => next_link = Some(Link::Client(client)),
+                    Err(ref err) if now < deadline_ns => {
+                        log_debug!("Couldn't rejoin \"{}\" yet: {}", self.game_name, err);
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        if let Some(link) = next_link {
+            self.link = link;
+            // Whatever didn't make it to everyone before the host dropped gets passed on by the
+            // new host; anything that already did is ignored
+            if let Link::Client(ref mut client) = self.link {
+                for &(_, ref message) in &self.recent {
+                    let _ = client.request(message);
+                }
+            }
+        }
+        for (from, message) in received {
+            self.take_in(from, message);
+        }
+        for slot in dropped {
+            // Nobody can have had their commands for any later tick, since they all came through here
+            let tick = self.ticks.last_tick(slot).map_or(self.ticks.next_tick(), |last_tick| last_tick + 1);
+            log_info!("Player {} was dropped from the game on tick {}", slot + 1, tick);
+            self.take_in(None,
+                         LobbyMessage::Dropped {
+                             slot: slot,
+                             tick: tick,
+                         });
+        }
+        if let Link::Host(ref mut host) = self.link {
+            for slot in rejoined {
+                for &(_, ref message) in &self.recent {
+                    host.send_to(slot, message);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Everything to carry out on the next tick, once everyone still in the game has sent their
+    /// commands for it
+    pub fn take_turn(&mut self) -> Option<Turn> {
+        let turn = self.ticks.take_turn();
+        if turn.is_some() {
+            self.stalled_since_ns = None;
+        } else if self.stalled_since_ns.is_none() {
+            self.stalled_since_ns = Some(time::precise_time_ns());
+        }
+        let next_tick = self.ticks.next_tick();
+        while self.recent.front().map_or(false, |&(tick, _)| tick + RESEND_TICKS < next_tick) {
+            self.recent.pop_front();
+        }
+        turn
+    }
+
+    /// Who the game is held up on: anyone the host says has gone quiet, and (once the game has
+    /// been held up for a moment) anyone whose commands for the next tick haven't arrived
+    pub fn waiting(&self) -> Vec<WaitingFor> {
+        let quiet = match self.link {
+            Link::Host(ref host) => host.waiting().clone(),
+            Link::Client(ref client) => client.waiting().clone(),
+            Link::Rejoining { .. } => BTreeMap::new(),
+        };
+        let mut waiting: BTreeMap<usize, Option<u32>> = quiet.into_iter()
+            .map(|(slot, seconds_left)| (slot, Some(seconds_left)))
+            .collect();
+        let now = time::precise_time_ns();
+        let stalled = self.stalled_since_ns.map_or(false, |since| now - since >= STALL_NOTICE_NS);
+        if stalled {
+            for slot in self.ticks.missing() {
+                waiting.entry(slot).or_insert(None);
+            }
+        }
+        waiting.into_iter()
+            .filter(|&(slot, _)| Some(slot) != self.slot)
+            .map(|(slot, seconds_left)| {
+                WaitingFor {
+                    slot: slot,
+                    name: self.lobby
+                        .slot(slot)
+                        .map_or(format!("Player {}", *Lobby::player_id(slot)), |player| player.name.clone()),
+                    seconds_left: seconds_left,
+                }
+            })
+            .collect()
+    }
+
+    /// Votes to drop everyone the host says has gone quiet
+    pub fn vote_drop(&mut self) {
+        match self.link {
+            Link::Host(ref mut host) => {
+                let slots: Vec<usize> = host.waiting().keys().cloned().collect();
+                for slot in slots {
+                    host.vote_drop(slot);
+                }
+            }
+            Link::Client(ref mut client) if self.slot.is_some() => {
+                let slots: Vec<usize> = client.waiting().keys().cloned().collect();
+                for slot in slots {
+                    let _ = client.request(&LobbyMessage::VoteDrop(slot));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Leaves the game, so that nobody has to wait out the drop timeout. A host just goes, and
+    /// whoever is next in line takes over.
+    pub fn leave(self) {
+        if let Link::Client(client) = self.link {
+            let _ = client.leave();
+        }
+    }
+
+    /// Adds commands or a drop to the queue, passing it on to everyone (other than who it came
+    /// from) when hosting. Returns whether it was new.
+    fn take_in(&mut self, from: Option<usize>, message: LobbyMessage) -> bool {
+        let (tick, new) = match message {
+            LobbyMessage::Commands { slot, tick, ref commands } => {
+                let entries = match CommandLog::from_bytes(commands) {
+                    Ok(log) => log.entries().iter().cloned().collect(),
+                    Err(err) => {
+                        log_warn!("Ignoring player {}'s commands for tick {}: {}", slot + 1, tick, err);
+                        return false;
+                    }
+                };
+                (tick, self.ticks.add(slot, tick, entries))
+            }
+            LobbyMessage::Dropped { slot, tick } => (tick, self.ticks.drop_player(slot, tick)),
+            _ => return false,
+        };
+        if !new {
+            return false;
+        }
+        if let Link::Host(ref mut host) = self.link {
+            host.relay(from, &message);
+        }
+        self.recent.push_back((tick, message));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use action::{Action, CommandLogEntry};
+    use super::{COMMAND_DELAY_TICKS, TickQueue};
+
+    fn kill(tick: u64, entity_id: u32) -> Vec<CommandLogEntry> {
+        vec![CommandLogEntry {
+                 tick: tick,
+                 entity_id: entity_id,
+                 action: Action::Kill,
+             }]
+    }
+
+    #[test]
+    fn test_waits_for_everyone() {
+        let mut ticks = TickQueue::new(&[0, 1]);
+        for tick in 1..COMMAND_DELAY_TICKS + 1 {
+            assert_eq!(tick, ticks.take_turn().unwrap().tick);
+        }
+
+        let tick = COMMAND_DELAY_TICKS + 1;
+        assert_eq!(None, ticks.take_turn());
+        assert_eq!(vec![0, 1], ticks.missing());
+        assert!(ticks.add(1, tick, kill(tick, 20)));
+        assert_eq!(vec![0], ticks.missing());
+
+        // Commands only count straight after the last ones
+        assert!(!ticks.add(1, tick, kill(tick, 20)));
+        assert!(!ticks.add(0, tick + 1, Vec::new()));
+        assert!(ticks.add(0, tick, kill(tick, 10)));
+
+        let turn = ticks.take_turn().unwrap();
+        assert_eq!(tick, turn.tick);
+        let entity_ids: Vec<u32> = turn.commands.iter().map(|entry| entry.entity_id).collect();
+        assert_eq!(vec![10, 20], entity_ids);
+        assert!(turn.dropped.is_empty());
+    }
+
+    #[test]
+    fn test_drop_player() {
+        let mut ticks = TickQueue::new(&[0, 1, 2]);
+        let tick = COMMAND_DELAY_TICKS + 1;
+        assert!(ticks.drop_player(2, tick + 1));
+        assert!(!ticks.drop_player(2, tick + 1));
+        assert!(!ticks.drop_player(3, tick));
+        for _ in 0..COMMAND_DELAY_TICKS {
+            ticks.take_turn().unwrap();
+        }
+
+        // They're still needed up until the tick they were dropped on
+        ticks.add(0, tick, Vec::new());
+        ticks.add(1, tick, Vec::new());
+        assert_eq!(vec![2], ticks.missing());
+        assert!(ticks.add(2, tick, Vec::new()));
+        assert!(ticks.take_turn().unwrap().dropped.is_empty());
+
+        assert!(!ticks.add(2, tick + 1, Vec::new()));
+        ticks.add(0, tick + 1, Vec::new());
+        ticks.add(1, tick + 1, Vec::new());
+        assert_eq!(vec![2], ticks.take_turn().unwrap().dropped);
+    }
+}
@@ -0,0 +1,173 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use dat::{EmpiresDb, Graphic, Research, SoundEffectGroup, Unit};
+use identifier::CivilizationId;
+use json::Json;
+use language::Language;
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+pub fn load(file_name: &Path) -> EmpiresDb {
+    let empires = EmpiresDb::read_from_file(file_name).unwrap_or_else(|err| {
+        eprintln!("Failed to load {:?}: {}", file_name, err);
+        process::exit(1);
+    });
+    if !empires.is_complete() {
+        eprintln!("Warning: {:?} is missing {:?}", file_name, empires.missing_sections());
+    }
+    empires
+}
+
+pub fn load_language(file_name: &Path) -> Language {
+    Language::read_from_file(file_name).unwrap_or_else(|err| {
+        eprintln!("Failed to load {:?}: {}", file_name, err);
+        process::exit(1);
+    })
+}
+
+/// One of the tables in empires.dat as JSON; units are grouped by civilization.
+/// The names players see are only included if there's a language.dll to look them up in.
+pub fn dump(empires: &EmpiresDb, table: &str, civ_id: Option<u8>, language: Option<&Language>) -> Json {
+    match table {
+        "units" => {
+            Json::Array(empires.civilizations()
+                .iter()
+                .filter(|civ| civ_id.map(|id| id == *civ.id()).unwrap_or(true))
+                .map(|civ| {
+                    Json::object(vec![("id", (*civ.id()).into()),
+                                      ("name", civ.name().into()),
+                                      ("units",
+                                       Json::Array(civ.units()
+                                           .into_iter()
+                                           .map(|u| unit(empires, civ.id(), u, language))
+                                           .collect()))])
+                })
+                .collect())
+        }
+        "techs" => {
+            Json::Array(empires.researches().iter().map(|r| research(empires, r, language)).collect())
+        }
+        "graphics" => Json::Array(empires.graphics().iter().map(graphic).collect()),
+        "sounds" => Json::Array(empires.sound_effect_groups().iter().map(sound_effect_group).collect()),
+        _ => unreachable!(),
+    }
+}
+
+pub fn write(json: &Json, output: Option<&Path>) {
+    let text = json.to_pretty_string();
+    match output {
+        Some(output) => {
+            let result = File::create(output).and_then(|mut file| file.write_all(text.as_bytes()));
+            if let Err(err) = result {
+                eprintln!("Failed to write {:?}: {}", output, err);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", text),
+    }
+}
+
+fn unit(empires: &EmpiresDb, civ_id: CivilizationId, unit: &Unit, language: Option<&Language>) -> Json {
+    let trains = empires.units_trained_at(civ_id, unit.id).into_iter().map(|trained| *trained.id);
+    let researches = empires.researched_at(unit.id).into_iter().map(|research| *research.id);
+    let affected_by = empires.researches_affecting(unit).into_iter().map(|research| *research.id);
+    Json::object(vec![("id", (*unit.id).into()),
+                      ("name", unit.name().into()),
+                      ("display_name", language.map(|language| unit.display_name(language)).into()),
+                      ("class_id", unit.class_id().into()),
+                      ("enabled", unit.enabled.into()),
+                      ("hit_points", unit.hit_points.into()),
+                      ("line_of_sight", unit.line_of_sight.into()),
+                      ("speed", unit.motion_params.as_ref().map(|params| params.speed).into()),
+                      ("collision_size",
+                       Json::array(vec![unit.collision_size_x,
+                                        unit.collision_size_y,
+                                        unit.collision_size_z])),
+                      ("interaction_mode", (&format!("{:?}", unit.interaction_mode)[..]).into()),
+                      ("terrain_restriction", (&format!("{:?}", unit.terrain_restriction)[..]).into()),
+                      ("standing_graphic", unit.standing_graphic.map(|id| *id).into()),
+                      ("dying_graphic", unit.dying_graphic.map(|id| *id).into()),
+                      ("dead_unit_id", unit.dead_unit_id.map(|id| *id).into()),
+                      ("train_location", unit.train_location_id().map(|id| *id).into()),
+                      ("trains", Json::array(trains)),
+                      ("researches", Json::array(researches)),
+                      ("affected_by", Json::array(affected_by))])
+}
+
+fn research(empires: &EmpiresDb, research: &Research, language: Option<&Language>) -> Json {
+    let required_by = empires.researches_requiring(research.id).into_iter().map(|other| *other.id);
+    Json::object(vec![("id", (*research.id).into()),
+                      ("name", (&research.name[..]).into()),
+                      ("display_name", language.map(|language| research.display_name(language)).into()),
+                      ("name_id", (*research.name_id).into()),
+                      ("required_techs", Json::array(research.required_techs.iter().cloned())),
+                      ("location", research.location.map(|id| *id).into()),
+                      ("time_seconds", research.time_seconds.into()),
+                      ("age_id", research.age_id.map(|id| *id).into()),
+                      ("type_id", research.type_id.into()),
+                      ("icon_id", research.icon_id.into()),
+                      ("button_id", research.button_id.into()),
+                      ("required_by", Json::array(required_by))])
+}
+
+fn graphic(graphic: &Graphic) -> Json {
+    Json::object(vec![("id", (*graphic.id).into()),
+                      ("name", (&graphic.name[..]).into()),
+                      ("short_name", (&graphic.short_name[..]).into()),
+                      ("slp_id", graphic.slp_id.map(|id| *id).into()),
+                      ("layer", graphic.layer.into()),
+                      ("player_color_id", graphic.player_color_id.map(|id| *id).into()),
+                      ("sound_group_id", graphic.sound_group_id.map(|id| *id).into()),
+                      ("frame_count", graphic.frame_count.into()),
+                      ("angle_count", graphic.angle_count.into()),
+                      ("frame_rate", graphic.frame_rate.into()),
+                      ("replay_delay", graphic.replay_delay.into()),
+                      ("sequence_type", graphic.sequence_type.into()),
+                      ("mirror_mode", graphic.mirror_mode.into()),
+                      ("deltas",
+                       Json::Array(graphic.deltas
+                           .iter()
+                           .map(|delta| {
+                               Json::object(vec![("graphic_id", (*delta.graphic_id).into()),
+                                                 ("offset_x", delta.offset_x.into()),
+                                                 ("offset_y", delta.offset_y.into())])
+                           })
+                           .collect()))])
+}
+
+fn sound_effect_group(group: &SoundEffectGroup) -> Json {
+    Json::object(vec![("id", (*group.id).into()),
+                      ("play_at_update_count", group.play_at_update_count.into()),
+                      ("cache_time", group.cache_time.into()),
+                      ("sound_effects",
+                       Json::Array(group.sound_effects
+                           .iter()
+                           .map(|effect| {
+                               Json::object(vec![("file_name", (&effect.file_name[..]).into()),
+                                                 ("wav_id", effect.resource_id.map(|id| *id).into()),
+                                                 ("probability", effect.probability.into())])
+                           })
+                           .collect()))])
+}
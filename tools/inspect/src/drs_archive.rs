@@ -0,0 +1,86 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use drs::{DrsFile, DrsFileType, DrsTable};
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+
+pub fn load(file_name: &Path) -> DrsFile {
+    DrsFile::read_from_file(file_name).unwrap_or_else(|err| {
+        eprintln!("Failed to load DRS {:?}: {}", file_name, err);
+        process::exit(1);
+    })
+}
+
+/// Prints every table in the archive, and every file in each table
+pub fn list(archive: &DrsFile) {
+    for table in &archive.tables {
+        println!("{} table: {} files", extension(table.header.file_type), table.entries.len());
+        for (entry, contents) in table.entries.iter().zip(table.contents.iter()) {
+            println!("  {:>6}.{}  {:>8} bytes  (offset {})",
+                     entry.file_id as u32,
+                     extension(table.header.file_type),
+                     contents.len(),
+                     entry.file_offset as u32);
+        }
+    }
+}
+
+/// Writes the file with the given ID (from whichever table has it) to `output`
+pub fn extract(archive: &DrsFile, file_id: u32, output: Option<PathBuf>) {
+    let (table, contents) = match find_file(archive, file_id) {
+        Some(found) => found,
+        None => {
+            eprintln!("There's no file with ID {} in the archive", file_id);
+            process::exit(1);
+        }
+    };
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(format!("{}.{}", file_id, extension(table.header.file_type)))
+    });
+    let result = File::create(&output).and_then(|mut file| file.write_all(contents));
+    if let Err(err) = result {
+        eprintln!("Failed to write {:?}: {}", output, err);
+        process::exit(1);
+    }
+    println!("Wrote {} bytes to {:?}", contents.len(), output);
+}
+
+fn find_file<'a>(archive: &'a DrsFile, file_id: u32) -> Option<(&'a DrsTable, &'a [u8])> {
+    for table in &archive.tables {
+        if let Some(contents) = table.find_file_contents(file_id) {
+            return Some((table, &contents[..]));
+        }
+    }
+    None
+}
+
+fn extension(file_type: DrsFileType) -> &'static str {
+    match file_type {
+        DrsFileType::Binary => "bin",
+        DrsFileType::Shp => "shp",
+        DrsFileType::Slp => "slp",
+        DrsFileType::Wav => "wav",
+    }
+}
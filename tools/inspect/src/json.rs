@@ -0,0 +1,178 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt::Write;
+
+/// Just enough JSON to dump game data tables with, without pulling in a serialization framework
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+    }
+
+    pub fn array<T: Into<Json>, I: IntoIterator<Item = T>>(values: I) -> Json {
+        Json::Array(values.into_iter().map(|value| value.into()).collect())
+    }
+
+    /// Pretty printed with two spaces of indentation
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        match *self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(value) => out.push_str(if value { "true" } else { "false" }),
+            Json::Number(value) => {
+                if !value.is_finite() {
+                    out.push_str("null");
+                } else if value == value.trunc() && value.abs() < 1e15 {
+                    write!(out, "{}", value as i64).unwrap();
+                } else {
+                    write!(out, "{}", value).unwrap();
+                }
+            }
+            Json::String(ref value) => write_string(out, value),
+            Json::Array(ref values) => {
+                if values.is_empty() {
+                    out.push_str("[]");
+                    return;
+                }
+                out.push('[');
+                for (index, value) in values.iter().enumerate() {
+                    out.push_str(if index == 0 { "\n" } else { ",\n" });
+                    push_indent(out, indent + 1);
+                    value.write(out, indent + 1);
+                }
+                out.push('\n');
+                push_indent(out, indent);
+                out.push(']');
+            }
+            Json::Object(ref fields) => {
+                if fields.is_empty() {
+                    out.push_str("{}");
+                    return;
+                }
+                out.push('{');
+                for (index, &(ref name, ref value)) in fields.iter().enumerate() {
+                    out.push_str(if index == 0 { "\n" } else { ",\n" });
+                    push_indent(out, indent + 1);
+                    write_string(out, name);
+                    out.push_str(": ");
+                    value.write(out, indent + 1);
+                }
+                out.push('\n');
+                push_indent(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("  ");
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+macro_rules! json_number_from {
+    ($($number_type:ty),*) => {
+        $(impl From<$number_type> for Json {
+            fn from(value: $number_type) -> Json {
+                Json::Number(value as f64)
+            }
+        })*
+    }
+}
+
+json_number_from!(u8, i8, u16, i16, u32, i32, f32);
+
+impl<'a> From<&'a str> for Json {
+    fn from(value: &'a str) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        match value {
+            Some(value) => value.into(),
+            None => Json::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    #[test]
+    fn test_write_nested() {
+        let json = Json::object(vec![("id", 5u32.into()),
+                                     ("name", "Clubman\t\"1\"".into()),
+                                     ("speed", 1.2f32.into()),
+                                     ("graphics", Json::array(vec![Some(12u32), None])),
+                                     ("deltas", Json::Array(Vec::new()))]);
+        assert_eq!("{\n  \"id\": 5,\n  \"name\": \"Clubman\\t\\\"1\\\"\",\n  \
+                    \"speed\": 1.2000000476837158,\n  \"graphics\": [\n    12,\n    null\n  ],\n  \
+                    \"deltas\": []\n}\n",
+                   json.to_pretty_string());
+    }
+
+    #[test]
+    fn test_non_finite_numbers_are_null() {
+        assert_eq!("null\n", Json::from(::std::f32::NAN).to_pretty_string());
+    }
+}
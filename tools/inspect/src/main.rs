@@ -0,0 +1,207 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+extern crate chariot_drs as drs;
+extern crate chariot_slp as slp;
+extern crate chariot_palette as palette;
+extern crate chariot_dat as dat;
+extern crate chariot_identifier as identifier;
+extern crate chariot_language as language;
+extern crate chariot_resource as resource;
+
+extern crate png;
+
+#[macro_use(value_t)]
+extern crate clap;
+
+mod dat_dump;
+mod drs_archive;
+mod json;
+mod slp_export;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use resource::GameDir;
+use std::path::{Path, PathBuf};
+use std::process;
+
+macro_rules! fail {
+    ($fmt:expr, $($arg:tt)*) => {{
+        eprintln!($fmt, $($arg)*);
+        process::exit(1);
+    }}
+}
+
+fn main() {
+    let matches = App::new("chariot_inspect")
+        .about("Inspects the game data of Age of Empires (1997): DRS archives, SLP shapes, and empires.dat")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(Arg::with_name("game_data_dir")
+            .short("d")
+            .long("game-data-dir")
+            .value_name("GAME_DATA_DIR")
+            .help("Looks up file names that don't exist relative to this directory (ignoring case), so \
+                   \"data/graphics.drs\" can be given instead of a full path")
+            .takes_value(true))
+        .subcommand(SubCommand::with_name("list")
+            .about("Lists the tables and files in a DRS archive")
+            .arg(Arg::with_name("DRS").help("DRS archive").required(true)))
+        .subcommand(SubCommand::with_name("extract")
+            .about("Extracts a file from a DRS archive")
+            .arg(Arg::with_name("DRS").help("DRS archive").required(true))
+            .arg(Arg::with_name("FILE_ID").help("ID of the file to extract").required(true))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("File to write to. Defaults to FILE_ID.EXTENSION in the working directory.")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("slp")
+            .about("Renders an SLP from a DRS archive to a PNG, with every frame side by side")
+            .arg(Arg::with_name("DRS").help("DRS archive that has the SLP").required(true))
+            .arg(Arg::with_name("SLP_ID").help("ID of the SLP").required(true))
+            .arg(Arg::with_name("OUTPUT").help("PNG file to write").required(true))
+            .arg(Arg::with_name("interfac")
+                .short("i")
+                .long("interfac")
+                .value_name("INTERFAC")
+                .help("Where interfac.drs is (to get the palette from). Defaults to \"data/interfac.drs\".")
+                .takes_value(true))
+            .arg(Arg::with_name("palette")
+                .long("palette")
+                .value_name("PALETTE_ID")
+                .help("ID of the palette in interfac.drs. Defaults to 50500, the one the game uses.")
+                .takes_value(true))
+            .arg(Arg::with_name("player")
+                .short("p")
+                .long("player")
+                .value_name("PLAYER")
+                .help("Player color to use (1 to 8). Defaults to 1.")
+                .takes_value(true))
+            .arg(Arg::with_name("frame")
+                .short("f")
+                .long("frame")
+                .value_name("FRAME")
+                .help("Only renders the given frame")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("dat")
+            .about("Dumps a table from empires.dat as JSON")
+            .arg(Arg::with_name("TABLE")
+                .help("Table to dump")
+                .required(true)
+                .possible_values(&["units", "techs", "graphics", "sounds"]))
+            .arg(Arg::with_name("empires")
+                .short("e")
+                .long("empires")
+                .value_name("EMPIRES_DAT")
+                .help("Where empires.dat is. Defaults to \"data/empires.dat\".")
+                .takes_value(true))
+            .arg(Arg::with_name("civ")
+                .long("civ")
+                .value_name("CIVILIZATION_ID")
+                .help("Only dumps the units of the given civilization")
+                .takes_value(true))
+            .arg(Arg::with_name("language")
+                .short("l")
+                .long("language")
+                .value_name("LANGUAGE_DLL")
+                .help("Where language.dll is, to add the names the player sees")
+                .takes_value(true))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("File to write the JSON to instead of standard output")
+                .takes_value(true)))
+        .get_matches();
+
+    let game_dir = matches.value_of("game_data_dir").map(|dir| {
+        GameDir::new(dir).unwrap_or_else(|err| fail!("{}", err))
+    });
+    let find_file = |file_name: &str| resolve_file_name(game_dir.as_ref(), file_name);
+
+    match matches.subcommand() {
+        ("list", Some(args)) => {
+            let archive = drs_archive::load(&find_file(args.value_of("DRS").unwrap()));
+            drs_archive::list(&archive);
+        }
+        ("extract", Some(args)) => {
+            let archive = drs_archive::load(&find_file(args.value_of("DRS").unwrap()));
+            let file_id = value_t!(args, "FILE_ID", u32).unwrap_or_else(|err| err.exit());
+            drs_archive::extract(&archive, file_id, args.value_of("output").map(PathBuf::from));
+        }
+        ("slp", Some(args)) => export_slp(args, &find_file),
+        ("dat", Some(args)) => {
+            let empires = dat_dump::load(&find_file(args.value_of("empires").unwrap_or("data/empires.dat")));
+            let civ_id = args.value_of("civ")
+                .map(|_| value_t!(args, "civ", u8).unwrap_or_else(|err| err.exit()));
+            let language = args.value_of("language")
+                .map(|file_name| dat_dump::load_language(&find_file(file_name)));
+            let json = dat_dump::dump(&empires, args.value_of("TABLE").unwrap(), civ_id, language.as_ref());
+            dat_dump::write(&json, args.value_of("output").map(Path::new));
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn export_slp(args: &ArgMatches, find_file: &Fn(&str) -> PathBuf) {
+    let slp_id = value_t!(args, "SLP_ID", u32).unwrap_or_else(|err| err.exit());
+    let palette_id = if args.is_present("palette") {
+        value_t!(args, "palette", u32).unwrap_or_else(|err| err.exit())
+    } else {
+        slp_export::DEFAULT_PALETTE_ID
+    };
+    let player = if args.is_present("player") {
+        value_t!(args, "player", u8).unwrap_or_else(|err| err.exit())
+    } else {
+        1
+    };
+    if player < 1 || player > 8 {
+        fail!("Player must be in the range of 1 to 8 inclusive, not {}", player);
+    }
+    let frame = if args.is_present("frame") {
+        Some(value_t!(args, "frame", usize).unwrap_or_else(|err| err.exit()))
+    } else {
+        None
+    };
+
+    let archive = drs_archive::load(&find_file(args.value_of("DRS").unwrap()));
+    let interfac = drs_archive::load(&find_file(args.value_of("interfac").unwrap_or("data/interfac.drs")));
+    let palette = slp_export::load_palette(&interfac, palette_id).unwrap_or_else(|err| fail!("{}", err));
+    let slp = slp_export::load_slp(&archive, slp_id, player).unwrap_or_else(|err| fail!("{}", err));
+
+    let output = Path::new(args.value_of("OUTPUT").unwrap());
+    if let Err(err) = slp_export::write_png(&slp, &palette, frame, output) {
+        fail!("Failed to write {:?}: {}", output, err);
+    }
+    println!("Wrote {:?}", output);
+}
+
+/// File names that exist are used as is; otherwise, they're looked for in the game data directory
+fn resolve_file_name(game_dir: Option<&GameDir>, file_name: &str) -> PathBuf {
+    let path = PathBuf::from(file_name);
+    if path.exists() {
+        return path;
+    }
+    match game_dir {
+        Some(game_dir) => game_dir.find_file(file_name).unwrap_or_else(|err| fail!("{}", err)),
+        None => fail!("{:?} doesn't exist (use --game-data-dir to look for it in the game data)", path),
+    }
+}
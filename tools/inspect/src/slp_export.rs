@@ -0,0 +1,111 @@
+// Chariot: An open source reimplementation of Age of Empires (1997)
+// Copyright (c) 2016 Kevin Fuller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use drs::{DrsFile, DrsFileType};
+use palette::{self, Palette};
+use png::{self, HasParameters};
+use slp::SlpFile;
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+/// The palette that the game uses for everything but the menus
+pub const DEFAULT_PALETTE_ID: u32 = 50500;
+
+/// Transparent pixels between frames when rendering all of them
+const FRAME_PADDING: usize = 2;
+
+pub fn load_palette(interfac: &DrsFile, palette_id: u32) -> Result<Palette, String> {
+    let contents = try!(interfac.find_table(DrsFileType::Binary)
+        .and_then(|table| table.find_file_contents(palette_id))
+        .ok_or(format!("There's no palette with ID {} in interfac.drs", palette_id)));
+    palette::read_from(&mut io::Cursor::new(&contents[..]))
+        .map_err(|err| format!("Failed to read palette: {}", err))
+}
+
+pub fn load_slp(archive: &DrsFile, slp_id: u32, player: u8) -> Result<SlpFile, String> {
+    let contents = try!(archive.find_table(DrsFileType::Slp)
+        .and_then(|table| table.find_file_contents(slp_id))
+        .ok_or(format!("There's no SLP with ID {} in the archive", slp_id)));
+    SlpFile::read_from(&mut io::Cursor::new(&contents[..]), player)
+        .map_err(|err| format!("Failed to read SLP {}: {}", slp_id, err))
+}
+
+/// Writes the frame (or all of them, left to right) to a PNG. Palette index 0 is transparent,
+/// like it is in the game.
+pub fn write_png(slp: &SlpFile,
+                 palette: &Palette,
+                 frame: Option<usize>,
+                 output: &Path)
+                 -> Result<(), String> {
+    let frame_indices: Vec<usize> = match frame {
+        Some(frame) if frame < slp.shapes.len() => vec![frame],
+        Some(frame) => {
+            return Err(format!("The SLP only has {} frames, so {} is out of range",
+                               slp.shapes.len(),
+                               frame))
+        }
+        None => (0..slp.shapes.len()).collect(),
+    };
+
+    let mut width = 0;
+    let mut height = 0;
+    for &index in &frame_indices {
+        let header = &slp.shapes[index].header;
+        if width > 0 {
+            width += FRAME_PADDING;
+        }
+        width += header.width as usize;
+        height = ::std::cmp::max(height, header.height as usize);
+    }
+    if width == 0 || height == 0 {
+        return Err("The SLP has no pixels to render".into());
+    }
+
+    let mut rgba = vec![0u8; width * height * 4];
+    let mut next_x = 0;
+    for &index in &frame_indices {
+        let shape = &slp.shapes[index];
+        let (shape_width, shape_height) = (shape.header.width as usize, shape.header.height as usize);
+        for y in 0..shape_height {
+            for x in 0..shape_width {
+                let palette_index = shape.pixels[y * shape_width + x] as usize;
+                if palette_index == 0 {
+                    continue;
+                }
+                let color = &palette[palette_index];
+                let dest = (y * width + next_x + x) * 4;
+                rgba[dest] = color.r;
+                rgba[dest + 1] = color.g;
+                rgba[dest + 2] = color.b;
+                rgba[dest + 3] = 255;
+            }
+        }
+        next_x += shape_width + FRAME_PADDING;
+    }
+
+    let file = try!(File::create(output).map_err(|err| err.to_string()));
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    let mut writer = try!(encoder.write_header().map_err(|err| err.to_string()));
+    writer.write_image_data(&rgba).map_err(|err| err.to_string())
+}